@@ -1,5 +1,6 @@
 //! Command within CLI
 
+use std::path::PathBuf;
 use std::str::FromStr;
 use std::sync::Arc;
 use std::time::Instant;
@@ -13,7 +14,7 @@ use datafusion::arrow::record_batch::RecordBatch;
 use crate::ctx::{ResultSet, SessionContext};
 use crate::exec::connect_database;
 use crate::functions::{display_all_functions, Function};
-use crate::print_format::PrintFormat;
+use crate::print_format::{FileFormat, PrintFormat};
 use crate::print_options::PrintOptions;
 use crate::Result;
 
@@ -30,6 +31,7 @@ pub enum Command {
     SearchFunctions(String),
     QuietMode(Option<bool>),
     OutputFormat(Option<String>),
+    OutputFile(Option<String>),
     WriteLineProtocol(String),
     ChangeTenant(String),
 }
@@ -92,6 +94,18 @@ impl Command {
             Self::OutputFormat(_) => Err(anyhow!(
                 "Unexpected change output format, this should be handled outside"
             )),
+            Self::OutputFile(path) => {
+                if let Some(path) = path {
+                    let path = PathBuf::from(path);
+                    FileFormat::try_from_path(&path)?;
+                    println!("Output file is {}.", path.display());
+                    print_options.output = Some(path);
+                } else {
+                    print_options.output = None;
+                    println!("Output is stdout.");
+                }
+                Ok(())
+            }
             Self::WriteLineProtocol(path) => {
                 ctx.write(path).await?;
                 let result_set = ResultSet::Bytes((vec![], 0));
@@ -116,13 +130,17 @@ impl Command {
             Self::SearchFunctions(_) => ("\\h function", "search function"),
             Self::QuietMode(_) => ("\\quiet (true|false)?", "print or set quiet mode"),
             Self::OutputFormat(_) => ("\\pset [NAME [VALUE]]", "set table output option\n(format)"),
+            Self::OutputFile(_) => (
+                "\\o [file]",
+                "write results to a parquet or arrow file,\nor to stdout if no file",
+            ),
             Self::WriteLineProtocol(_) => ("\\w path", "line protocol"),
             Self::ChangeTenant(_) => ("\\change_tenant <TenantName>", "change tenant."),
         }
     }
 }
 
-const ALL_COMMANDS: [Command; 11] = [
+const ALL_COMMANDS: [Command; 12] = [
     Command::ConnectDatabase(String::new()),
     Command::ListTables,
     Command::DescribeTable(String::new()),
@@ -133,6 +151,7 @@ const ALL_COMMANDS: [Command; 11] = [
     Command::SearchFunctions(String::new()),
     Command::QuietMode(None),
     Command::OutputFormat(None),
+    Command::OutputFile(None),
     Command::WriteLineProtocol(String::new()),
 ];
 
@@ -178,6 +197,8 @@ impl FromStr for Command {
             ("quiet", None) => Self::QuietMode(None),
             ("pset", Some(subcommand)) => Self::OutputFormat(Some(subcommand.to_string())),
             ("pset", None) => Self::OutputFormat(None),
+            ("o", Some(path)) => Self::OutputFile(Some(path.into())),
+            ("o", None) => Self::OutputFile(None),
             ("w", Some(path)) => Self::WriteLineProtocol(path.into()),
             ("db", Some(db)) => Self::DescribeDatabase(db.to_string()),
             _ => return Err(()),
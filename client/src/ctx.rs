@@ -1,11 +1,13 @@
-use std::io::BufRead;
+use std::io::{BufRead, Cursor};
 use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
 
 use anyhow::anyhow;
 use base64::prelude::{Engine, BASE64_STANDARD};
+use datafusion::arrow::ipc::reader::StreamReader;
 use datafusion::arrow::record_batch::RecordBatch;
 use http_protocol::encoding::Encoding;
-use http_protocol::header::{ACCEPT, PRIVATE_KEY};
+use http_protocol::header::{ACCEPT, APPLICATION_ARROW_STREAM, PRIVATE_KEY};
 use http_protocol::http_client::HttpClient;
 use http_protocol::parameter::{DumpParam, SqlParam, WriteParam};
 use http_protocol::status_code::OK;
@@ -38,6 +40,11 @@ pub struct SessionConfig {
     pub precision: String,
     pub target_partitions: Option<usize>,
     pub stream_trigger_interval: Option<String>,
+    pub stale_read_max_lag: Option<String>,
+    pub consistency: Option<String>,
+    pub cache: Option<String>,
+    /// Identifies the requests of this client, which share prepared statements
+    pub session_id: String,
     pub accept_encoding: Option<Encoding>,
     pub content_encoding: Option<Encoding>,
     pub fmt: PrintFormat,
@@ -49,6 +56,14 @@ pub struct SessionConfig {
     pub error_stop: bool,
 }
 
+fn new_session_id() -> String {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_nanos())
+        .unwrap_or_default();
+    format!("cli-{}-{}", std::process::id(), nanos)
+}
+
 impl SessionConfig {
     /// Create an execution config with config options read from the environment
     pub fn from_env() -> Self {
@@ -62,6 +77,10 @@ impl SessionConfig {
             precision: DEFAULT_PRECISION.to_string(),
             target_partitions: None,
             stream_trigger_interval: None,
+            stale_read_max_lag: None,
+            consistency: None,
+            cache: None,
+            session_id: new_session_id(),
             accept_encoding: None,
             content_encoding: None,
             config_options,
@@ -113,6 +132,21 @@ impl SessionConfig {
         self
     }
 
+    pub fn with_stale_read_max_lag(mut self, stale_read_max_lag: Option<String>) -> Self {
+        self.stale_read_max_lag = stale_read_max_lag;
+        self
+    }
+
+    pub fn with_consistency(mut self, consistency: Option<String>) -> Self {
+        self.consistency = consistency;
+        self
+    }
+
+    pub fn with_cache(mut self, cache: Option<String>) -> Self {
+        self.cache = cache;
+        self
+    }
+
     pub fn with_accept_encoding(mut self, accept_encoding: Option<Encoding>) -> Self {
         self.accept_encoding = accept_encoding;
         self
@@ -241,6 +275,10 @@ impl SessionContext {
         self.session_config.database = name.to_string();
     }
 
+    pub fn set_cache(&mut self, cache: String) {
+        self.session_config.cache = Some(cache);
+    }
+
     pub fn set_tenant(&mut self, tenant: String) {
         self.session_config.tenant = tenant
     }
@@ -258,6 +296,27 @@ impl SessionContext {
     }
 
     pub async fn sql(&self, sql: String) -> Result<ResultSet> {
+        let body = self
+            .request_sql(sql, self.session_config.fmt.get_http_content_type())
+            .await?;
+
+        Ok(ResultSet::Bytes((body, 0)))
+    }
+
+    /// Execute the sql and fetch the result as arrow record batches
+    pub async fn sql_record_batches(&self, sql: String) -> Result<ResultSet> {
+        let body = self.request_sql(sql, APPLICATION_ARROW_STREAM).await?;
+        if body.is_empty() {
+            return Ok(ResultSet::RecordBatches(vec![]));
+        }
+
+        let reader = StreamReader::try_new(Cursor::new(body), None)?;
+        let batches = reader.collect::<std::result::Result<Vec<_>, _>>()?;
+
+        Ok(ResultSet::RecordBatches(batches))
+    }
+
+    async fn request_sql(&self, sql: String, accept: &str) -> Result<Vec<u8>> {
         let mut sql = sql.into_bytes();
         let user_info = &self.session_config.user_info;
 
@@ -265,6 +324,9 @@ impl SessionContext {
         let db = self.session_config.database.clone();
         let target_partitions = self.session_config.target_partitions;
         let stream_trigger_interval = self.session_config.stream_trigger_interval.clone();
+        let stale_read_max_lag = self.session_config.stale_read_max_lag.clone();
+        let consistency = self.session_config.consistency.clone();
+        let cache = self.session_config.cache.clone();
         let chunked = self.session_config.chunked;
         let param = SqlParam {
             tenant: Some(tenant),
@@ -272,6 +334,12 @@ impl SessionContext {
             chunked: Some(chunked),
             target_partitions,
             stream_trigger_interval,
+            stale_read_max_lag,
+            consistency,
+            cache,
+            materialized_view_rewrite: None,
+            params: None,
+            session_id: Some(self.session_config.session_id.clone()),
         };
 
         // let param = &[("db", &self.session_config.database)];
@@ -279,7 +347,7 @@ impl SessionContext {
             .http_client
             .post(API_V1_SQL_PATH)
             .basic_auth::<&str, &str>(&user_info.user, user_info.password.as_deref())
-            .header(ACCEPT, self.session_config.fmt.get_http_content_type());
+            .header(ACCEPT, accept);
 
         if let Some(encoding) = self.session_config.accept_encoding {
             builder = builder.header(ACCEPT_ENCODING, encoding.to_header_value());
@@ -313,7 +381,7 @@ impl SessionContext {
                 }
                 .to_vec();
 
-                Ok(ResultSet::Bytes((body, 0)))
+                Ok(body)
             }
             _ => {
                 let status = resp.status().to_string();
@@ -379,6 +447,7 @@ impl SessionContext {
             precision: Some(precision),
             tenant: Some(tenant),
             db: Some(db),
+            consistency: self.session_config.consistency.clone(),
         };
 
         let mut builder = self
@@ -37,6 +37,11 @@ pub async fn exec_from_lines(
                     bail!("Can't process \"\\change_tenant {}\", please add arg --process_cli_command", tenant)
                 }
             }
+            Ok(line) if parse_set_cache(&line).is_some() => {
+                if let Some(cache) = parse_set_cache(&line) {
+                    set_cache(ctx, cache);
+                }
+            }
             Ok(line) if line.starts_with("\\c") => {
                 let database = line.trim_start_matches("\\c").trim();
                 if ctx.get_session_config().process_cli_command {
@@ -154,6 +159,13 @@ pub async fn exec_from_repl(ctx: &mut SessionContext, print_options: &PrintOptio
                 }
             }
 
+            Ok(line) if parse_set_cache(&line).is_some() => {
+                rl.add_history_entry(line.trim_end()).unwrap();
+                if let Some(cache) = parse_set_cache(&line) {
+                    set_cache(ctx, cache);
+                }
+            }
+
             Ok(line) => {
                 rl.add_history_entry(line.trim_end()).unwrap();
                 match exec_and_print(ctx, &print_options, line).await {
@@ -221,8 +233,13 @@ async fn exec_and_print(
         }
 
         let now = Instant::now();
-        let results = ctx.sql(tmp.to_string() + ";").await?;
-        print_options.print_batches(&results, now)?;
+        if print_options.output.is_some() {
+            let results = ctx.sql_record_batches(tmp.to_string() + ";").await?;
+            print_options.write_batches(&results, now)?;
+        } else {
+            let results = ctx.sql(tmp.to_string() + ";").await?;
+            print_options.print_batches(&results, now)?;
+        }
     }
 
     Ok(())
@@ -244,6 +261,28 @@ fn parse_use_database(sql: &str) -> Option<String> {
     }
 }
 
+/// Parse `SET cache = on|off`, which is a session option sent with each query.
+fn parse_set_cache(sql: &str) -> Option<String> {
+    let sql = sql.trim().trim_end_matches(';');
+    if !sql.to_ascii_lowercase().starts_with("set") {
+        return None;
+    }
+
+    let (name, value) = sql[3..].split_once('=')?;
+    if !name.trim().eq_ignore_ascii_case("cache") {
+        return None;
+    }
+
+    Some(value.trim().trim_matches('\'').to_ascii_lowercase())
+}
+
+fn set_cache(ctx: &mut SessionContext, cache: String) {
+    match cache.as_str() {
+        "on" | "off" | "true" | "false" => ctx.set_cache(cache),
+        _ => eprintln!("Invalid cache mode '{}', expected on or off.", cache),
+    }
+}
+
 pub fn is_system_table_db(db: &str) -> bool {
     let db = db.to_ascii_lowercase();
     db.eq("cluster_schema") || db.eq("information_schema") || db.eq("usage_schema")
@@ -63,6 +63,18 @@ struct CliArgs {
     #[arg(short, long)]
     stream_trigger_interval: Option<String>,
 
+    /// Optionally, allow queries to read from replicas lagging at most the duration. e.g. 500ms, 1s .
+    #[arg(long)]
+    stale_read_max_lag: Option<String>,
+
+    /// Optionally, specify the consistency level of writes. e.g. any, one, quorum, all .
+    #[arg(long)]
+    consistency: Option<String>,
+
+    /// Optionally, specify whether queries use the result cache of the server. e.g. on, off .
+    #[arg(long)]
+    cache: Option<String>,
+
     /// Path to your data, default to current directory
     #[arg(long, value_parser = try_parse_data_dir)]
     data_path: Option<String>,
@@ -198,6 +210,9 @@ pub async fn main() -> Result<(), anyhow::Error> {
         .with_database(args.database)
         .with_target_partitions(args.target_partitions)
         .with_stream_trigger_interval(args.stream_trigger_interval)
+        .with_stale_read_max_lag(args.stale_read_max_lag)
+        .with_consistency(args.consistency)
+        .with_cache(args.cache)
         .with_accept_encoding(args.receive_data_encoding)
         .with_content_encoding(args.send_data_encoding)
         .with_result_format(args.format)
@@ -218,6 +233,7 @@ pub async fn main() -> Result<(), anyhow::Error> {
     let print_options = PrintOptions {
         format: args.format,
         quiet: args.quiet,
+        output: None,
     };
 
     match args.subcommand {
@@ -1,11 +1,15 @@
 //! Print format variants
+use std::fs::File;
+use std::path::Path;
 use std::str::FromStr;
 
 use datafusion::arrow::csv::writer::WriterBuilder;
+use datafusion::arrow::ipc::writer::FileWriter;
 use datafusion::arrow::json::{ArrayWriter, LineDelimitedWriter};
 use datafusion::arrow::record_batch::RecordBatch;
 use datafusion::arrow::util::pretty;
 use datafusion::error::{DataFusionError, Result};
+use datafusion::parquet::arrow::ArrowWriter;
 use http_protocol::header::{
     APPLICATION_CSV, APPLICATION_JSON, APPLICATION_NDJSON, APPLICATION_TABLE, APPLICATION_TSV,
 };
@@ -86,6 +90,60 @@ impl PrintFormat {
     }
 }
 
+/// Format of the file which results of queries are written to by `\o file`
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum FileFormat {
+    /// Arrow IPC file
+    Arrow,
+    Parquet,
+}
+
+impl FileFormat {
+    /// Get the format from the extension of the file
+    pub fn try_from_path(path: &Path) -> Result<Self> {
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some(ext) if ext.eq_ignore_ascii_case("parquet") => Ok(Self::Parquet),
+            Some(ext)
+                if ["arrow", "ipc", "feather"]
+                    .iter()
+                    .any(|e| ext.eq_ignore_ascii_case(e)) =>
+            {
+                Ok(Self::Arrow)
+            }
+            _ => Err(DataFusionError::Plan(format!(
+                "can't write results to {}, expected a .parquet or .arrow file",
+                path.display()
+            ))),
+        }
+    }
+
+    /// Write the batches to the file, the file is overwritten if it exists
+    pub fn write_batches(&self, path: &Path, batches: &[RecordBatch]) -> Result<()> {
+        let schema = match batches.first() {
+            Some(batch) => batch.schema(),
+            None => return Err(DataFusionError::Execution("no result to write".to_string())),
+        };
+        let file = File::create(path)?;
+        match self {
+            Self::Arrow => {
+                let mut writer = FileWriter::try_new(file, &schema)?;
+                for batch in batches {
+                    writer.write(batch)?;
+                }
+                writer.finish()?;
+            }
+            Self::Parquet => {
+                let mut writer = ArrowWriter::try_new(file, schema, None)?;
+                for batch in batches {
+                    writer.write(batch)?;
+                }
+                writer.close()?;
+            }
+        }
+        Ok(())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use std::sync::Arc;
@@ -160,4 +218,18 @@ mod tests {
         );
         Ok(())
     }
+
+    #[test]
+    fn test_file_format_from_path() {
+        assert_eq!(
+            FileFormat::try_from_path(Path::new("/tmp/result.parquet")).unwrap(),
+            FileFormat::Parquet
+        );
+        assert_eq!(
+            FileFormat::try_from_path(Path::new("result.Arrow")).unwrap(),
+            FileFormat::Arrow
+        );
+        assert!(FileFormat::try_from_path(Path::new("result.csv")).is_err());
+        assert!(FileFormat::try_from_path(Path::new("result")).is_err());
+    }
 }
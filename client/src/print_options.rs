@@ -1,13 +1,16 @@
+use std::path::PathBuf;
 use std::time::Instant;
 
 use crate::ctx::ResultSet;
-use crate::print_format::PrintFormat;
+use crate::print_format::{FileFormat, PrintFormat};
 use crate::Result;
 
 #[derive(Debug, Clone)]
 pub struct PrintOptions {
     pub format: PrintFormat,
     pub quiet: bool,
+    /// The file which results of queries are written to instead of stdout
+    pub output: Option<PathBuf>,
 }
 
 fn print_timing_info(_row_count: usize, now: Instant) {
@@ -30,4 +33,27 @@ impl PrintOptions {
         }
         Ok(())
     }
+
+    /// write the batches to the output file, nothing is written if the statement
+    /// has no result, e.g. DDL
+    pub fn write_batches(&self, result_set: &ResultSet, now: Instant) -> Result<()> {
+        if let (Some(path), ResultSet::RecordBatches(batches)) = (&self.output, result_set) {
+            let has_schema = batches
+                .first()
+                .is_some_and(|batch| !batch.schema().fields().is_empty());
+            if has_schema {
+                FileFormat::try_from_path(path)?.write_batches(path, batches)?;
+                println!(
+                    "{} rows written to {}.",
+                    result_set.row_count(),
+                    path.display()
+                );
+            }
+        }
+
+        if !self.quiet {
+            print_timing_info(result_set.row_count(), now);
+        }
+        Ok(())
+    }
 }
@@ -4,6 +4,8 @@ pub use reqwest::header::{HeaderValue, ACCEPT, AUTHORIZATION, CONTENT_TYPE};
 // header
 // privateKey
 pub const PRIVATE_KEY: &str = "X-CnosDB-PrivateKey";
+// cursor of the next page of the result of an async query
+pub const NEXT_CURSOR: &str = "X-CnosDB-Next-Cursor";
 
 // value
 pub const APPLICATION_PREFIX: &str = "application/";
@@ -12,6 +14,8 @@ pub const APPLICATION_TSV: &str = "application/tsv";
 pub const APPLICATION_JSON: &str = "application/json";
 pub const APPLICATION_NDJSON: &str = "application/nd-json";
 pub const APPLICATION_TABLE: &str = "application/table";
+pub const APPLICATION_ARROW_STREAM: &str = "application/vnd.apache.arrow.stream";
+pub const APPLICATION_PARQUET: &str = "application/vnd.apache.parquet";
 pub const APPLICATION_STAR: &str = "application/*";
 pub const STAR_STAR: &str = "*/*";
 
@@ -24,6 +28,10 @@ pub const TENANT: &str = "tenant";
 pub const DB: &str = "db";
 pub const TARGET_PARTITIONS: &str = "target_partitions";
 pub const STREAM_TRIGGER_INTERVAL: &str = "stream_trigger_interval";
+pub const STALE_READ_MAX_LAG: &str = "stale_read_max_lag";
+pub const CONSISTENCY: &str = "consistency";
+pub const CACHE: &str = "cache";
+pub const MATERIALIZED_VIEW_REWRITE: &str = "materialized_view_rewrite";
 
 // encoding
 pub const GZIP: &str = "gzip";
@@ -9,6 +9,18 @@ pub struct SqlParam {
     // Number of partitions for query execution. Increasing partitions can increase concurrency.
     pub target_partitions: Option<usize>,
     pub stream_trigger_interval: Option<String>,
+    // Max staleness of reads served by replicas without confirming with the raft leader.
+    pub stale_read_max_lag: Option<String>,
+    // Consistency level of writes: any, one, quorum or all.
+    pub consistency: Option<String>,
+    // Whether to use the result cache: on or off.
+    pub cache: Option<String>,
+    // Whether aggregates may read the results of materialized views: on or off.
+    pub materialized_view_rewrite: Option<String>,
+    // JSON array of the values of the placeholders $1/? of a parameterized query.
+    pub params: Option<String>,
+    // Id of the client session, statements created by PREPARE are only visible in the session.
+    pub session_id: Option<String>,
 }
 
 #[derive(Debug, Deserialize, Serialize)]
@@ -17,6 +29,16 @@ pub struct WriteParam {
     pub precision: Option<String>,
     pub tenant: Option<String>,
     pub db: Option<String>,
+    // Consistency level of the write: any, one, quorum or all.
+    pub consistency: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub struct AsyncQueryParam {
+    pub tenant: Option<String>,
+    // Cursor of the page of the result to fetch, the first page if absent.
+    pub cursor: Option<String>,
 }
 
 #[derive(Debug, Deserialize, Serialize)]
@@ -82,6 +82,70 @@ impl MemoryPool for GreedyMemoryPool {
     }
 }
 
+/// A [`MemoryPool`] that allocates up to `quota` bytes from another pool
+#[derive(Debug)]
+pub struct QuotaMemoryPool {
+    inner: MemoryPoolRef,
+    quota: AtomicUsize,
+    used: AtomicUsize,
+}
+
+impl QuotaMemoryPool {
+    pub fn new(inner: MemoryPoolRef, quota: usize) -> Self {
+        Self {
+            inner,
+            quota: AtomicUsize::new(quota),
+            used: AtomicUsize::new(0),
+        }
+    }
+
+    /// Change the quota, memory already allocated is not released
+    pub fn set_quota(&self, quota: usize) {
+        self.quota.store(quota, Ordering::Relaxed);
+    }
+}
+
+impl MemoryPool for QuotaMemoryPool {
+    fn register(&self, consumer: &MemoryConsumer) {
+        self.inner.register(consumer)
+    }
+
+    fn unregister(&self, consumer: &MemoryConsumer) {
+        self.inner.unregister(consumer)
+    }
+
+    fn grow(&self, reservation: &MemoryReservation, additional: usize) {
+        self.used.fetch_add(additional, Ordering::Relaxed);
+        self.inner.grow(reservation, additional)
+    }
+
+    fn shrink(&self, reservation: &MemoryReservation, shrink: usize) {
+        self.used.fetch_sub(shrink, Ordering::Relaxed);
+        self.inner.shrink(reservation, shrink)
+    }
+
+    fn try_grow(&self, reservation: &MemoryReservation, additional: usize) -> Result<()> {
+        let quota = self.quota.load(Ordering::Relaxed);
+        self.used
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |used| {
+                let new_used = used + additional;
+                (new_used <= quota).then_some(new_used)
+            })
+            .map_err(|used| {
+                insufficient_capacity_err(reservation, additional, quota.saturating_sub(used))
+            })?;
+
+        self.inner.try_grow(reservation, additional).map_err(|e| {
+            self.used.fetch_sub(additional, Ordering::Relaxed);
+            e
+        })
+    }
+
+    fn reserved(&self) -> usize {
+        self.used.load(Ordering::Relaxed)
+    }
+}
+
 fn insufficient_capacity_err(
     reservation: &MemoryReservation,
     additional: usize,
@@ -126,4 +190,29 @@ mod tests {
         a2.try_grow(25).unwrap();
         assert_eq!(pool.reserved(), 25);
     }
+
+    #[test]
+    fn test_quota() {
+        let inner = Arc::new(GreedyMemoryPool::new(50)) as MemoryPoolRef;
+        let pool = Arc::new(QuotaMemoryPool::new(inner.clone(), 30)) as _;
+        let mut a1 = MemoryConsumer::new("a1").register(&pool);
+
+        a1.try_grow(40).unwrap_err();
+        assert_eq!(pool.reserved(), 0);
+        assert_eq!(inner.reserved(), 0);
+
+        a1.try_grow(20).unwrap();
+        assert_eq!(pool.reserved(), 20);
+        assert_eq!(inner.reserved(), 20);
+
+        // the inner pool is exhausted by others
+        let mut a2 = MemoryConsumer::new("a2").register(&inner);
+        a2.try_grow(25).unwrap();
+        a1.try_grow(10).unwrap_err();
+        assert_eq!(pool.reserved(), 20);
+
+        drop(a1);
+        assert_eq!(pool.reserved(), 0);
+        assert_eq!(inner.reserved(), 25);
+    }
 }
@@ -1,6 +1,10 @@
 pub mod stream;
+use std::fmt::{self, Display};
+use std::str::FromStr;
+
 pub use arrow_schema::{DataType, Field, Fields, Schema, SchemaRef, TimeUnit};
 pub use datafusion::datasource::file_format::file_type::{FileCompressionType, FileType};
+use datafusion::error::DataFusionError;
 pub use datafusion::sql::sqlparser::ast::DataType as SQLDataType;
 use datafusion::sql::sqlparser::ast::{ExactNumberInfo, TimezoneInfo};
 
@@ -47,3 +51,46 @@ pub fn arrow_data_type_to_sql_data_type(
     };
     Ok(res)
 }
+
+/// File types of external tables, which are the file types of Datafusion and ORC.
+/// ORC files can only be read.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ExternalFileType {
+    DataFusion(FileType),
+    Orc,
+}
+
+impl ExternalFileType {
+    pub fn get_ext_with_compression(
+        &self,
+        c: FileCompressionType,
+    ) -> Result<String, DataFusionError> {
+        match self {
+            Self::DataFusion(file_type) => file_type.get_ext_with_compression(c),
+            Self::Orc if !c.is_compressed() => Ok(".orc".to_string()),
+            Self::Orc => Err(DataFusionError::Internal(
+                "FileCompressionType can be specified for CSV/JSON FileType.".to_string(),
+            )),
+        }
+    }
+}
+
+impl FromStr for ExternalFileType {
+    type Err = DataFusionError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.eq_ignore_ascii_case("ORC") {
+            return Ok(Self::Orc);
+        }
+        FileType::from_str(s).map(Self::DataFusion)
+    }
+}
+
+impl Display for ExternalFileType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::DataFusion(file_type) => write!(f, "{:?}", file_type),
+            Self::Orc => write!(f, "ORC"),
+        }
+    }
+}
@@ -1,12 +1,73 @@
-#[allow(dead_code)]
-#[derive(Debug)]
+use std::fmt::Display;
+use std::str::FromStr;
+
+/// Consistency level of a write request.
+///
+/// A write is committed by the raft group of its replication set, which can not acknowledge
+/// a write before a quorum of the group accepts it, so `One` behaves the same as `Quorum`.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum ConsistencyLevel {
     /// allows for hinted handoff, potentially no write happened yet.
     Any,
     /// at least one data node acknowledged a write or read.
     One,
     /// a quorum of data nodes to acknowledge a write or read.
+    #[default]
     Quorum,
     /// requires all data nodes to acknowledge a write or read.
     All,
 }
+
+impl FromStr for ConsistencyLevel {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_uppercase().as_str() {
+            "ANY" => Ok(Self::Any),
+            "ONE" => Ok(Self::One),
+            "QUORUM" => Ok(Self::Quorum),
+            "ALL" => Ok(Self::All),
+            _ => Err(format!(
+                "invalid consistency level '{}', expected any, one, quorum or all",
+                s
+            )),
+        }
+    }
+}
+
+impl Display for ConsistencyLevel {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ConsistencyLevel::Any => f.write_str("any"),
+            ConsistencyLevel::One => f.write_str("one"),
+            ConsistencyLevel::Quorum => f.write_str("quorum"),
+            ConsistencyLevel::All => f.write_str("all"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::str::FromStr;
+
+    use super::ConsistencyLevel;
+
+    #[test]
+    fn test_parse_consistency_level() {
+        assert_eq!(
+            ConsistencyLevel::from_str("any").unwrap(),
+            ConsistencyLevel::Any
+        );
+        assert_eq!(
+            ConsistencyLevel::from_str("Quorum").unwrap(),
+            ConsistencyLevel::Quorum
+        );
+        assert_eq!(
+            ConsistencyLevel::from_str(" ALL ").unwrap(),
+            ConsistencyLevel::All
+        );
+        assert!(ConsistencyLevel::from_str("two").is_err());
+        assert_eq!(ConsistencyLevel::default(), ConsistencyLevel::Quorum);
+        assert_eq!(ConsistencyLevel::One.to_string(), "one");
+    }
+}
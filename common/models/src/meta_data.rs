@@ -1,4 +1,6 @@
 use std::collections::HashMap;
+use std::fmt::{self, Display};
+use std::str::FromStr;
 use std::sync::Arc;
 
 use serde::{Deserialize, Serialize};
@@ -44,6 +46,147 @@ pub struct UserInfo {
 pub struct NodeInfo {
     pub id: NodeId,
     pub grpc_addr: String,
+    /// No new buckets are placed on a decommissioning node.
+    #[serde(default)]
+    pub decommissioning: bool,
+    /// Failure domain labels of the node.
+    #[serde(default)]
+    pub zone: Option<String>,
+    #[serde(default)]
+    pub rack: Option<String>,
+    #[serde(default)]
+    pub tier: NodeTier,
+}
+
+/// Storage tier of a data node, new buckets are only placed on hot nodes,
+/// and moved to cold nodes after the `cold_after` of their database.
+#[derive(Serialize, Deserialize, Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum NodeTier {
+    #[default]
+    Hot,
+    Cold,
+}
+
+impl FromStr for NodeTier {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "hot" => Ok(NodeTier::Hot),
+            "cold" => Ok(NodeTier::Cold),
+            _ => Err(format!(
+                "invalid node tier '{}', expected 'hot' or 'cold'",
+                s
+            )),
+        }
+    }
+}
+
+impl Display for NodeTier {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NodeTier::Hot => write!(f, "hot"),
+            NodeTier::Cold => write!(f, "cold"),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FailureDomain {
+    Zone,
+    Rack,
+}
+
+impl Display for FailureDomain {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FailureDomain::Zone => write!(f, "zone"),
+            FailureDomain::Rack => write!(f, "rack"),
+        }
+    }
+}
+
+/// How the replicas of a replication set are placed on data nodes.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ReplicaPlacement {
+    /// Replicas are placed on data nodes of distinct failure domains,
+    /// nodes without the label of the failure domain are not used.
+    Spread(FailureDomain),
+}
+
+impl ReplicaPlacement {
+    /// The failure domain of the node, None if the node is not labeled.
+    pub fn domain<'a>(&self, node: &'a NodeInfo) -> Option<&'a str> {
+        match self {
+            ReplicaPlacement::Spread(FailureDomain::Zone) => node.zone.as_deref(),
+            ReplicaPlacement::Spread(FailureDomain::Rack) => node.rack.as_deref(),
+        }
+    }
+
+    /// Group the labeled nodes by failure domain, keeping the order of nodes.
+    pub fn group_nodes(&self, nodes: &[NodeInfo]) -> Vec<Vec<NodeInfo>> {
+        let mut groups: Vec<(&str, Vec<NodeInfo>)> = vec![];
+        for node in nodes {
+            let domain = match self.domain(node) {
+                Some(domain) => domain,
+                None => continue,
+            };
+            match groups.iter_mut().find(|(d, _)| *d == domain) {
+                Some((_, group)) => group.push(node.clone()),
+                None => groups.push((domain, vec![node.clone()])),
+            }
+        }
+
+        groups.into_iter().map(|(_, group)| group).collect()
+    }
+
+    /// Check if `replica` replicas can be placed on the nodes, returns the reason if not.
+    pub fn check(&self, nodes: &[NodeInfo], replica: u64) -> Result<(), String> {
+        let domains = self.group_nodes(nodes).len() as u64;
+        if domains < replica {
+            let ReplicaPlacement::Spread(domain) = self;
+            return Err(format!(
+                "replica placement '{}' needs {} replicas in distinct {}s, but valid nodes are only in {} {}s",
+                self, replica, domain, domains, domain
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Check if a replica can be placed on the node, other replicas are on `peers`.
+    pub fn allows(&self, node: &NodeInfo, peers: &[NodeInfo]) -> bool {
+        match self.domain(node) {
+            Some(domain) => peers
+                .iter()
+                .filter(|p| p.id != node.id)
+                .all(|p| self.domain(p) != Some(domain)),
+            None => false,
+        }
+    }
+}
+
+impl FromStr for ReplicaPlacement {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "spread:zone" => Ok(ReplicaPlacement::Spread(FailureDomain::Zone)),
+            "spread:rack" => Ok(ReplicaPlacement::Spread(FailureDomain::Rack)),
+            _ => Err(format!(
+                "invalid replica placement '{}', expected 'spread:zone' or 'spread:rack'",
+                s
+            )),
+        }
+    }
+}
+
+impl Display for ReplicaPlacement {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReplicaPlacement::Spread(domain) => write!(f, "spread:{}", domain),
+        }
+    }
 }
 
 #[derive(Serialize, Deserialize, Debug, Default, Clone)]
@@ -60,6 +203,42 @@ impl NodeMetrics {
     }
 }
 
+/// Series of a database in a vnode, reported by the node of the vnode.
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq)]
+pub struct VnodeSeriesCount {
+    pub vnode_id: VnodeId,
+    pub tenant: String,
+    pub db_name: String,
+    pub series: u64,
+    // table -> series of the table in the vnode
+    pub tables: HashMap<String, u64>,
+}
+
+/// Series of a tenant in the cluster, summed up from the reported [`VnodeSeriesCount`]s.
+/// A replication set is counted once, by its vnode with the most series.
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq)]
+pub struct TenantSeriesCount {
+    pub series: u64,
+    // db -> series of the database
+    pub databases: HashMap<String, u64>,
+    // db -> table -> series of the table
+    pub tables: HashMap<String, HashMap<String, u64>>,
+}
+
+impl TenantSeriesCount {
+    pub fn database_series(&self, db: &str) -> u64 {
+        self.databases.get(db).copied().unwrap_or_default()
+    }
+
+    pub fn table_series(&self, db: &str, table: &str) -> u64 {
+        self.tables
+            .get(db)
+            .and_then(|tables| tables.get(table))
+            .copied()
+            .unwrap_or_default()
+    }
+}
+
 #[derive(Serialize, Deserialize, Debug, Default, Clone)]
 pub struct BucketInfo {
     pub id: u32,
@@ -120,6 +299,14 @@ impl ReplicationSet {
     }
 }
 
+/// Version of the data of a vnode, changes on writes, flushes and compactions.
+#[derive(Serialize, Deserialize, Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct VnodeVersion {
+    pub vnode_id: VnodeId,
+    pub version_number: u64,
+    pub seq_no: u64,
+}
+
 #[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq, Eq, Hash)]
 pub struct VnodeInfo {
     pub id: VnodeId,
@@ -146,6 +333,16 @@ pub enum VnodeStatus {
     Broken,
 }
 
+/// Move a vnode of a replication set from one data node to another.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct VnodeMove {
+    pub tenant: String,
+    pub replica_id: ReplicationSetId,
+    pub vnode_id: VnodeId,
+    pub from_node_id: NodeId,
+    pub to_node_id: NodeId,
+}
+
 #[derive(Serialize, Deserialize, Debug, Default, Clone)]
 pub struct VnodeAllInfo {
     pub vnode_id: VnodeId,
@@ -314,22 +511,33 @@ pub fn get_time_range(ts: i64, duration: i64) -> (i64, i64) {
     }
 }
 
+/// Allocate replication sets on the nodes. If a placement is given, replicas of a
+/// replication set are placed in distinct failure domains, the caller should check
+/// the placement by `ReplicaPlacement::check` first.
 pub fn allocation_replication_set(
     nodes: Vec<NodeInfo>,
     shards: u32,
     replica: u32,
     begin_seq: u32,
+    placement: Option<&ReplicaPlacement>,
 ) -> (Vec<ReplicationSet>, u32) {
-    let node_count = nodes.len() as u32;
+    // Each group is a failure domain, or a single node if there is no placement.
+    let groups = match placement {
+        Some(placement) => placement.group_nodes(&nodes),
+        None => nodes.into_iter().map(|n| vec![n]).collect(),
+    };
+    let group_count = groups.len() as u32;
     let mut replica = replica;
     if replica == 0 {
         replica = 1
-    } else if replica > node_count {
-        replica = node_count
+    } else if replica > group_count {
+        replica = group_count
     }
 
     let mut incr_id = begin_seq;
     let mut index = 0;
+    // Next node to use in each group.
+    let mut group_index = vec![0_usize; groups.len()];
     let mut group = vec![];
 
     for _ in 0..shards {
@@ -342,10 +550,13 @@ pub fn allocation_replication_set(
         incr_id += 1;
 
         for _ in 0..replica {
+            let i = (index % group_count) as usize;
+            let nodes = &groups[i];
             repl_set.vnodes.push(VnodeInfo::new(
                 incr_id,
-                nodes.get((index % node_count) as usize).unwrap().id,
+                nodes[group_index[i] % nodes.len()].id,
             ));
+            group_index[i] += 1;
             incr_id += 1;
             index += 1;
         }
@@ -414,7 +625,9 @@ pub fn get_disk_info(path: &str) -> std::io::Result<u64> {
 
 #[cfg(test)]
 mod test {
-    use super::get_disk_info;
+    use std::collections::HashSet;
+
+    use super::{allocation_replication_set, get_disk_info, NodeInfo, ReplicaPlacement};
 
     #[test]
     fn test_get_disk_info() {
@@ -429,4 +642,51 @@ mod test {
         let pe = std::io::Error::last_os_error();
         println!("disk info error: {}", pe);
     }
+
+    fn labeled_node(id: u64, zone: &str) -> NodeInfo {
+        NodeInfo {
+            id,
+            zone: Some(zone.to_string()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_allocation_with_placement() {
+        let placement: ReplicaPlacement = "spread:zone".parse().unwrap();
+        assert_eq!(placement.to_string(), "spread:zone");
+        assert!("spread:host".parse::<ReplicaPlacement>().is_err());
+
+        let mut nodes = vec![
+            labeled_node(1, "z1"),
+            labeled_node(2, "z1"),
+            labeled_node(3, "z2"),
+            labeled_node(4, "z2"),
+        ];
+        assert!(placement.check(&nodes, 2).is_ok());
+        assert!(placement.check(&nodes, 3).is_err());
+
+        // Node 5 is not labeled, it's not used.
+        nodes.push(NodeInfo {
+            id: 5,
+            ..Default::default()
+        });
+        let (group, used) = allocation_replication_set(nodes.clone(), 4, 2, 1, Some(&placement));
+        assert_eq!(used, 12);
+        let mut used_nodes = HashSet::new();
+        for repl_set in group.iter() {
+            let zones: HashSet<_> = repl_set
+                .vnodes
+                .iter()
+                .map(|v| nodes[v.node_id as usize - 1].zone.clone())
+                .collect();
+            assert_eq!(zones.len(), 2);
+            used_nodes.extend(repl_set.vnodes.iter().map(|v| v.node_id));
+        }
+        assert_eq!(used_nodes, HashSet::from([1, 2, 3, 4]));
+
+        assert!(!placement.allows(&nodes[1], &nodes[..1]));
+        assert!(placement.allows(&nodes[2], &nodes[..2]));
+        assert!(!placement.allows(&nodes[4], &nodes[..1]));
+    }
 }
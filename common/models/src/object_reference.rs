@@ -47,6 +47,14 @@ pub struct ResolvedTable {
 }
 
 impl ResolvedTable {
+    pub fn new(tenant: String, database: String, table: String) -> Self {
+        Self {
+            tenant,
+            database,
+            table,
+        }
+    }
+
     pub fn tenant(&self) -> &str {
         &self.tenant
     }
@@ -4,6 +4,7 @@ use std::fmt::{Display, Formatter};
 use std::hash::Hash;
 use std::ops::{Bound as StdBound, RangeBounds};
 use std::sync::Arc;
+use std::time::Duration;
 
 use arrow_schema::Schema;
 use datafusion::arrow::datatypes::DataType;
@@ -1076,10 +1077,101 @@ impl Display for EqutableValueSet {
     }
 }
 
+/// A pattern that string values are matched with.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Pattern {
+    /// SQL LIKE pattern, '%' matches any sequence of characters and '_' matches any character.
+    Like(String),
+    /// Regular expression, matches if any part of the value matches.
+    Regex(String),
+}
+
+impl Pattern {
+    /// Returns the literal prefix that all matched values start with.
+    pub fn literal_prefix(&self) -> String {
+        match self {
+            Pattern::Like(pattern) => pattern
+                .chars()
+                .take_while(|c| !matches!(c, '%' | '_' | '\\'))
+                .collect(),
+            Pattern::Regex(pattern) => {
+                // Only anchored regular expression without alternation has a prefix
+                let rest = match pattern.strip_prefix('^') {
+                    Some(rest) if !pattern.contains('|') => rest,
+                    _ => return String::new(),
+                };
+                let mut prefix = String::new();
+                for c in rest.chars() {
+                    match c {
+                        // The last character may be repeated zero times
+                        '*' | '?' | '{' => {
+                            prefix.pop();
+                            break;
+                        }
+                        '.' | '^' | '$' | '+' | '(' | ')' | '[' | ']' | '}' | '\\' => break,
+                        _ => prefix.push(c),
+                    }
+                }
+                prefix
+            }
+        }
+    }
+}
+
+impl Display for Pattern {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Pattern::Like(p) => write!(f, "like '{p}'"),
+            Pattern::Regex(p) => write!(f, "~ '{p}'"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct PatternEntry {
+    pub pattern: Pattern,
+    pub negated: bool,
+}
+
+impl Display for PatternEntry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.negated {
+            write!(f, "not ")?;
+        }
+        write!(f, "{}", self.pattern)
+    }
+}
+
+/// A set of values that match all the patterns.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PatternValueSet {
+    entries: Vec<PatternEntry>,
+}
+
+impl PatternValueSet {
+    pub fn entries(&self) -> &[PatternEntry] {
+        &self.entries
+    }
+}
+
+impl Display for PatternValueSet {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "[")?;
+        for (i, entry) in self.entries.iter().enumerate() {
+            if i > 0 {
+                write!(f, " and ")?;
+            }
+            write!(f, "{entry}")?;
+        }
+        write!(f, "]")
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum Domain {
     Range(RangeValueSet),
     Equtable(EqutableValueSet),
+    Pattern(PatternValueSet),
     None,
     All,
 }
@@ -1147,6 +1239,13 @@ impl Domain {
             entries,
         })
     }
+
+    /// Construct a set of values that match (or not match if negated) the pattern.
+    pub fn of_pattern(pattern: Pattern, negated: bool) -> Domain {
+        Domain::Pattern(PatternValueSet {
+            entries: vec![PatternEntry { pattern, negated }],
+        })
+    }
     /// Calculates the intersection of two ranges, and returns None if the intersection does not exist
     ///
     /// This method returns the new value without changing the old value
@@ -1158,9 +1257,18 @@ impl Domain {
             (Self::Equtable(ref self_val_set), Self::Equtable(ref other_val_set)) => {
                 Domain::value_intersect(self_val_set, other_val_set)
             }
+            (Self::Pattern(ref self_val_set), Self::Pattern(ref other_val_set)) => {
+                let mut entries = self_val_set.entries.clone();
+                entries.extend(other_val_set.entries.iter().cloned());
+                Ok(Self::Pattern(PatternValueSet { entries }))
+            }
             (Self::None, _) | (_, Self::None) => Ok(Self::None),
             (Self::All, _) => Ok(other.clone()),
             (_, Self::All) => Ok(self.clone()),
+            // Patterns can not be merged into other sets, keep the other set only,
+            // the result is a superset of the intersection.
+            (Self::Pattern(_), _) => Ok(other.clone()),
+            (_, Self::Pattern(_)) => Ok(self.clone()),
             _ => Err(InternalSnafu {
                 err: "mismatched ValueSet type".to_string(),
             }
@@ -1185,6 +1293,8 @@ impl Domain {
             (Self::None, _) => Ok(other.clone()),
             (_, Self::None) => Ok(self.clone()),
             (Self::All, _) | (_, Self::All) => Ok(Self::All),
+            // The union with patterns can not be represented.
+            (Self::Pattern(_), _) | (_, Self::Pattern(_)) => Ok(Self::All),
             _ => Err(InternalSnafu {
                 err: "mismatched ValueSet type".to_string(),
             }
@@ -1383,6 +1493,7 @@ impl Display for Domain {
         match self {
             Domain::Range(s) => write!(f, "range({s})"),
             Domain::Equtable(s) => write!(f, "equtable({s})"),
+            Domain::Pattern(s) => write!(f, "pattern({s})"),
             Domain::None => write!(f, "none"),
             Domain::All => write!(f, "all"),
         }
@@ -1744,6 +1855,8 @@ pub struct QueryArgs {
 
     pub limit: Option<usize>,
     pub batch_size: usize,
+    /// Read index check of the replica is skipped if the last one is within the lag.
+    pub stale_read_max_lag: Option<Duration>,
 }
 
 impl QueryArgs {
@@ -2122,4 +2235,21 @@ mod tests {
 
         assert_eq!(wrap.0.expr_type, wrap1.0.expr_type);
     }
+
+    #[test]
+    fn test_pattern_literal_prefix() {
+        let like = |p: &str| Pattern::Like(p.to_string()).literal_prefix();
+        assert_eq!(like("host%"), "host");
+        assert_eq!(like("ho_t%"), "ho");
+        assert_eq!(like("%host"), "");
+        assert_eq!(like("host"), "host");
+
+        let regex = |p: &str| Pattern::Regex(p.to_string()).literal_prefix();
+        assert_eq!(regex("^host"), "host");
+        assert_eq!(regex("^host.*"), "host");
+        assert_eq!(regex("^hosts?"), "host");
+        assert_eq!(regex("^ho[s]t"), "ho");
+        assert_eq!(regex("host"), "");
+        assert_eq!(regex("^host|^server"), "");
+    }
 }
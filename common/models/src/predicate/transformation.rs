@@ -5,11 +5,11 @@ use datafusion::arrow::datatypes::DataType;
 use datafusion::common::tree_node::{TreeNode, TreeNodeVisitor, VisitRecursion};
 use datafusion::common::Result as DFResult;
 use datafusion::error::DataFusionError;
-use datafusion::logical_expr::{BinaryExpr, Operator};
+use datafusion::logical_expr::{BinaryExpr, Like, Operator};
 use datafusion::prelude::{Column, Expr};
 use datafusion::scalar::ScalarValue;
 
-use super::domain::{ColumnDomains, Domain, Range};
+use super::domain::{ColumnDomains, Domain, Pattern, Range};
 use crate::schema::TIME_FIELD_NAME;
 
 type Result<T> = result::Result<T, DataFusionError>;
@@ -141,6 +141,44 @@ fn get_range_fn(
     }
 }
 
+/// Convert `column [NOT] LIKE 'pattern'` and `column [!]~ 'regex'` to column domains.
+///
+/// Return None if the expression is not a supported pattern match.
+fn pattern_match_to_column_domains(expr: &Expr) -> Option<ColumnDomains<Column>> {
+    let (column, pattern, negated) = match expr {
+        Expr::Like(Like {
+            negated,
+            expr,
+            pattern,
+            escape_char: None,
+        }) => match (expr.as_ref(), pattern.as_ref()) {
+            (Expr::Column(c), Expr::Literal(ScalarValue::Utf8(Some(p)))) => {
+                (c, Pattern::Like(p.clone()), *negated)
+            }
+            _ => return None,
+        },
+        Expr::BinaryExpr(BinaryExpr { left, op, right }) => {
+            let negated = match op {
+                Operator::RegexMatch => false,
+                Operator::RegexNotMatch => true,
+                _ => return None,
+            };
+            match (left.as_ref(), right.as_ref()) {
+                (Expr::Column(c), Expr::Literal(ScalarValue::Utf8(Some(p)))) => {
+                    (c, Pattern::Regex(p.clone()), negated)
+                }
+                _ => return None,
+            }
+        }
+        _ => return None,
+    };
+
+    Some(ColumnDomains::of(
+        column.to_owned(),
+        &Domain::of_pattern(pattern, negated),
+    ))
+}
+
 impl TreeNodeVisitor for RowExpressionToDomainsVisitor<'_> {
     type N = Expr;
 
@@ -186,6 +224,13 @@ impl TreeNodeVisitor for RowExpressionToDomainsVisitor<'_> {
                         // support
                         Ok(VisitRecursion::Continue)
                     }
+                    Operator::RegexMatch | Operator::RegexNotMatch => {
+                        // The pattern match has no child domains, push it directly
+                        let domains = pattern_match_to_column_domains(expr)
+                            .unwrap_or_else(ColumnDomains::all);
+                        self.ctx.current_domain_stack.push_back(domains);
+                        Ok(VisitRecursion::Skip)
+                    }
                     _ => {
                         // not support
                         self.ctx
@@ -195,9 +240,14 @@ impl TreeNodeVisitor for RowExpressionToDomainsVisitor<'_> {
                     }
                 }
             }
+            Expr::Like(_) => {
+                let domains =
+                    pattern_match_to_column_domains(expr).unwrap_or_else(ColumnDomains::all);
+                self.ctx.current_domain_stack.push_back(domains);
+                Ok(VisitRecursion::Skip)
+            }
             // TODO Currently not supported, follow-up support needs to implement the corresponding expression in post_visit
-            Expr::Like(_)
-            | Expr::ILike(_)
+            Expr::ILike(_)
             | Expr::SimilarTo(_)
             | Expr::Not(_)
             | Expr::IsNotNull(_)
@@ -749,6 +799,7 @@ mod tests {
     /// eg.
     ///   s1 like '%上证180' and time >= '2022-10-10 00:00:00'
     ///   ===>
+    ///   s1: like '%上证180'
     ///   time: ['2022-10-10 00:00:00', _)
     #[test]
     fn test_simple_and_to_domain_0() {
@@ -763,6 +814,7 @@ mod tests {
         let and = and(filter1, filter2);
 
         // build except result
+        //   s1: like '%上证180'
         //   time: ['2022-10-10 00:00:00', _)
         let i1 = Range::gt(
             &DataType::Utf8,
@@ -770,8 +822,10 @@ mod tests {
         );
 
         let i1_domain = Domain::of_ranges(&[i1]).unwrap();
+        let s1_domain = Domain::of_pattern(Pattern::Like("%上证180".to_string()), false);
 
-        let except_column_domains = ColumnDomains::of(Column::from_name("time"), &i1_domain);
+        let mut except_column_domains = ColumnDomains::of(Column::from_name("time"), &i1_domain);
+        except_column_domains.insert_or_intersect(Column::from_name("s1"), &s1_domain);
 
         let result = get_domains(&and);
 
@@ -1644,6 +1698,92 @@ mod tests {
         );
     }
 
+    /// pattern match push down
+    /// eg.
+    ///   s1 ~ '^host' and \
+    ///   s1 not like '%test' and \
+    ///   s2 !~ 'a|b'
+    ///   ===>
+    ///   s1: [not like '%test' and ~ '^host']
+    ///   s2: [not ~ 'a|b']
+    #[test]
+    fn test_pattern_match_to_domain() {
+        let s1_regex = binary_expr(col("s1"), Operator::RegexMatch, lit("^host"));
+        let s1_like = Expr::Like(Like::new(
+            true,
+            Box::new(col("s1")),
+            Box::new(lit("%test")),
+            None,
+        ));
+        let s2_regex = binary_expr(col("s2"), Operator::RegexNotMatch, lit("a|b"));
+
+        let and = and(and(s1_regex, s1_like), s2_regex);
+
+        let result = get_domains(&and);
+
+        assert!(
+            result.is_ok(),
+            "convert expr {} to column domains err",
+            &and
+        );
+
+        let column_domain = result.as_ref().unwrap();
+
+        // build except result
+        let s1_like_domain = Domain::of_pattern(Pattern::Like("%test".to_string()), true);
+        let s1_regex_domain = Domain::of_pattern(Pattern::Regex("^host".to_string()), false);
+        let s2_domain = Domain::of_pattern(Pattern::Regex("a|b".to_string()), true);
+
+        let except_column_domains =
+            &mut ColumnDomains::of(Column::from_name("s1"), &s1_like_domain);
+        except_column_domains.insert_or_intersect(Column::from_name("s1"), &s1_regex_domain);
+        except_column_domains.insert_or_intersect(Column::from_name("s2"), &s2_domain);
+
+        assert!(
+            except_column_domains.eq(column_domain),
+            "convert expr {} to column domains err, excepted {:?}, found {:?}",
+            &and,
+            except_column_domains,
+            column_domain,
+        );
+    }
+
+    /// not support push down - 6
+    /// eg.
+    ///   s1 like s2 or \
+    ///   s1 ~ '^host'
+    ///   ===>
+    ///   All
+    #[test]
+    fn test_not_support_expr_to_domain_6() {
+        let like = Expr::Like(Like::new(
+            false,
+            Box::new(col("s1")),
+            Box::new(col("s2")),
+            None,
+        ));
+        let regex = binary_expr(col("s1"), Operator::RegexMatch, lit("^host"));
+
+        let or = or(like, regex);
+
+        let result = get_domains(&or);
+
+        assert!(
+            result.is_ok(),
+            "convert expr {} to column domains err: {}",
+            &or,
+            result.unwrap_err()
+        );
+
+        let column_domain = result.as_ref().unwrap();
+
+        assert!(
+            column_domain.is_all(),
+            "convert expr {} to column domains err, excepted ColumnDomains::All",
+            &or
+        );
+    }
+
     #[cfg(test)]
     mod test_normalized_simple_comparison {
         use super::*;
@@ -63,7 +63,7 @@ pub fn filter_to_time_ranges(time_domain: &ColumnDomains<String>) -> Vec<TimeRan
                         }
                     }
                 }
-                Domain::All => time_ranges.push(TimeRange::all()),
+                Domain::All | Domain::Pattern(_) => time_ranges.push(TimeRange::all()),
                 Domain::None => return vec![],
             }
         } else {
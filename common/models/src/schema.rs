@@ -12,23 +12,17 @@ use std::cmp::Ordering;
 use std::collections::HashMap;
 use std::fmt::{self, Display};
 use std::mem::size_of_val;
-use std::str::FromStr;
 use std::sync::Arc;
 use std::time::Duration as StdDuration;
 
-use config::common::{RequestLimiterConfig, TenantLimiterConfig, TenantObjectLimiterConfig};
+use config::common::{
+    RequestLimiterConfig, TenantLimiterConfig, TenantObjectLimiterConfig, TenantResourceGroupConfig,
+};
 use datafusion::arrow::datatypes::{
     DataType as ArrowDataType, Field as ArrowField, Schema, SchemaRef, TimeUnit,
 };
 use datafusion::common::{DFField, DFSchema, DFSchemaRef};
-use datafusion::datasource::file_format::avro::AvroFormat;
-use datafusion::datasource::file_format::csv::CsvFormat;
-use datafusion::datasource::file_format::file_type::{FileCompressionType, FileType};
-use datafusion::datasource::file_format::json::JsonFormat;
-use datafusion::datasource::file_format::parquet::ParquetFormat;
-use datafusion::datasource::file_format::FileFormat;
-use datafusion::datasource::listing::ListingOptions;
-use datafusion::error::{DataFusionError, Result as DataFusionResult};
+use datafusion::error::DataFusionError;
 use datafusion::prelude::Column;
 use derive_builder::Builder;
 use serde::{Deserialize, Serialize};
@@ -37,7 +31,9 @@ use snafu::ResultExt;
 use crate::codec::Encoding;
 use crate::errors::InvalidSerdeMessageSnafu;
 use crate::gis::data_type::Geometry;
-use crate::meta_data::{NodeId, ReplicationSet};
+use crate::meta_data::{
+    NodeId, ReplicaPlacement, ReplicationSet, ReplicationSetId, VnodeInfo, VnodeMove,
+};
 use crate::oid::{Identifier, Oid};
 use crate::utils::{
     now_timestamp_nanos, DAY_MICROS, DAY_MILLS, DAY_NANOS, HOUR_MICROS, HOUR_MILLS, HOUR_NANOS,
@@ -59,6 +55,14 @@ pub const USAGE_SCHEMA: &str = "usage_schema";
 pub const CLUSTER_SCHEMA: &str = "cluster_schema";
 pub const DEFAULT_CATALOG: &str = "cnosdb";
 pub const DEFAULT_PRECISION: &str = "NS";
+pub const AUDIT_LOG: &str = "audit_log";
+
+/// Tables only written by the system, e.g. the audit log, users can't modify them.
+pub fn is_read_only_table(tenant: &str, database: &str, table: &str) -> bool {
+    tenant.eq_ignore_ascii_case(DEFAULT_CATALOG)
+        && database.eq_ignore_ascii_case(CLUSTER_SCHEMA)
+        && table.eq_ignore_ascii_case(AUDIT_LOG)
+}
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub enum ResourceOperator {
@@ -88,6 +92,21 @@ pub enum ResourceOperator {
         Vec<Vec<u8>>,
         Vec<ReplicationSet>,
     ),
+
+    // dead node_id
+    RepairReplicas(NodeId),
+
+    // vnode moves
+    Rebalance(Vec<VnodeMove>),
+
+    // node_id
+    DecommissionNode(NodeId),
+
+    // vnode moves from hot nodes to cold nodes
+    MigrateToCold(Vec<VnodeMove>),
+
+    // tenant_name, db_name, replica_id, vnode removed from the replica set on an unreachable node
+    DropRaftNode(String, String, ReplicationSetId, VnodeInfo),
 }
 
 impl fmt::Display for ResourceOperator {
@@ -100,6 +119,11 @@ impl fmt::Display for ResourceOperator {
             ResourceOperator::AddColumn(..) => write!(f, "AddColumn"),
             ResourceOperator::AlterColumn(..) => write!(f, "AlterColumn"),
             ResourceOperator::UpdateTagValue(..) => write!(f, "UpdateTagValue"),
+            ResourceOperator::RepairReplicas(..) => write!(f, "RepairReplicas"),
+            ResourceOperator::Rebalance(..) => write!(f, "Rebalance"),
+            ResourceOperator::DecommissionNode(..) => write!(f, "DecommissionNode"),
+            ResourceOperator::MigrateToCold(..) => write!(f, "MigrateToCold"),
+            ResourceOperator::DropRaftNode(..) => write!(f, "DropRaftNode"),
         }
     }
 }
@@ -112,6 +136,9 @@ pub enum ResourceStatus {
     Failed,
     Cancel,
     Fatal,
+    Paused,
+    /// Asked to pause, it's paused after the vnode being moved is moved.
+    Pausing,
 }
 
 impl fmt::Display for ResourceStatus {
@@ -123,6 +150,8 @@ impl fmt::Display for ResourceStatus {
             ResourceStatus::Failed => write!(f, "Failed"),
             ResourceStatus::Cancel => write!(f, "Cancel"),
             ResourceStatus::Fatal => write!(f, "Fatal"),
+            ResourceStatus::Paused => write!(f, "Paused"),
+            ResourceStatus::Pausing => write!(f, "Pausing"),
         }
     }
 }
@@ -234,6 +263,7 @@ pub enum TableSchema {
     TsKvTableSchema(TskvTableSchemaRef),
     ExternalTableSchema(Arc<ExternalTableSchema>),
     StreamTableSchema(Arc<StreamTable>),
+    ViewSchema(Arc<ViewSchema>),
 }
 
 impl TableSchema {
@@ -242,6 +272,7 @@ impl TableSchema {
             TableSchema::TsKvTableSchema(schema) => schema.name.as_str(),
             TableSchema::ExternalTableSchema(schema) => schema.name.as_str(),
             TableSchema::StreamTableSchema(schema) => schema.name(),
+            TableSchema::ViewSchema(schema) => schema.name.as_str(),
         }
     }
 
@@ -250,6 +281,7 @@ impl TableSchema {
             TableSchema::TsKvTableSchema(schema) => schema.db.as_str(),
             TableSchema::ExternalTableSchema(schema) => schema.db.as_str(),
             TableSchema::StreamTableSchema(schema) => schema.db(),
+            TableSchema::ViewSchema(schema) => schema.db.as_str(),
         }
     }
 
@@ -258,6 +290,7 @@ impl TableSchema {
             TableSchema::TsKvTableSchema(_) => "TSKV",
             TableSchema::ExternalTableSchema(_) => "EXTERNAL",
             TableSchema::StreamTableSchema(_) => "STREAM",
+            TableSchema::ViewSchema(_) => "VIEW",
         }
     }
 
@@ -266,10 +299,40 @@ impl TableSchema {
             Self::ExternalTableSchema(e) => Arc::new(e.schema.clone()),
             Self::TsKvTableSchema(e) => e.to_arrow_schema(),
             Self::StreamTableSchema(e) => e.schema(),
+            Self::ViewSchema(e) => Arc::new(e.schema.clone()),
         }
     }
 }
 
+/// A view, the query `definition` is planned each time the view is queried, the table names
+/// in it without database are in the database of the view.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct ViewSchema {
+    pub tenant: String,
+    pub db: String,
+    pub name: String,
+    pub definition: String,
+    pub schema: Schema,
+    /// Set if the results of the view are stored
+    pub materialized: Option<MaterializedView>,
+}
+
+/// The results of a materialized view are stored in the tskv table `table`, which is kept
+/// up-to-date by the stream query `query` reading the stream table `stream_table` of `source`.
+/// All of them are in the database of the view.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct MaterializedView {
+    pub source: String,
+    pub table: String,
+    pub stream_table: String,
+    pub query: String,
+    /// Group by expressions of the definition, without qualifier
+    pub group_by: Vec<String>,
+    /// Aggregate expressions of the definition without qualifier,
+    /// and the columns of `table` storing them
+    pub aggregates: Vec<(String, String)>,
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
 pub struct ExternalTableSchema {
     pub tenant: String,
@@ -285,39 +348,6 @@ pub struct ExternalTableSchema {
     pub schema: Schema,
 }
 
-impl ExternalTableSchema {
-    pub fn table_options(&self) -> DataFusionResult<ListingOptions> {
-        let file_compression_type = FileCompressionType::from_str(&self.file_compression_type)?;
-        let file_type = FileType::from_str(&self.file_type)?;
-        let file_extension =
-            file_type.get_ext_with_compression(self.file_compression_type.to_owned().parse()?)?;
-        let file_format: Arc<dyn FileFormat> = match file_type {
-            FileType::CSV => Arc::new(
-                CsvFormat::default()
-                    .with_has_header(self.has_header)
-                    .with_delimiter(self.delimiter)
-                    .with_file_compression_type(file_compression_type),
-            ),
-            FileType::PARQUET => Arc::new(ParquetFormat::default()),
-            FileType::AVRO => Arc::new(AvroFormat),
-            FileType::JSON => {
-                Arc::new(JsonFormat::default().with_file_compression_type(file_compression_type))
-            }
-            FileType::ARROW => {
-                return Err(DataFusionError::NotImplemented(
-                    "Arrow external table.".to_string(),
-                ));
-            }
-        };
-
-        let options = ListingOptions::new(file_format)
-            .with_file_extension(file_extension)
-            .with_target_partitions(self.target_partitions);
-
-        Ok(options)
-    }
-}
-
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
 pub struct TskvTableSchema {
     pub tenant: String,
@@ -916,6 +946,19 @@ impl DatabaseSchema {
         };
         now - ttl
     }
+
+    /// Buckets end before the returned time should be on cold nodes,
+    /// None if the database has no `cold_after`.
+    pub fn time_to_cold(&self) -> Option<i64> {
+        let cold_after = self.config.cold_after().as_ref()?;
+        let precision = *self.config.precision_or_default();
+        let now = match precision {
+            Precision::MS => crate::utils::now_timestamp_millis(),
+            Precision::US => crate::utils::now_timestamp_micros(),
+            Precision::NS => crate::utils::now_timestamp_nanos(),
+        };
+        Some(now.saturating_sub(cold_after.to_precision(precision)))
+    }
 }
 
 pub fn make_owner(tenant_name: &str, database_name: &str) -> String {
@@ -948,6 +991,12 @@ pub struct DatabaseOptions {
     precision: Option<Precision>,
 
     db_is_hidden: bool,
+    // failure domain aware placement of replicas
+    #[serde(default)]
+    replica_placement: Option<ReplicaPlacement>,
+    // buckets older than it are moved to cold nodes
+    #[serde(default)]
+    cold_after: Option<Duration>,
 }
 
 impl DatabaseOptions {
@@ -977,6 +1026,8 @@ impl DatabaseOptions {
             replica,
             precision,
             db_is_hidden: false,
+            replica_placement: None,
+            cold_after: None,
         }
     }
 
@@ -1024,6 +1075,14 @@ impl DatabaseOptions {
             .unwrap_or(&DatabaseOptions::DEFAULT_PRECISION)
     }
 
+    pub fn replica_placement(&self) -> &Option<ReplicaPlacement> {
+        &self.replica_placement
+    }
+
+    pub fn cold_after(&self) -> &Option<Duration> {
+        &self.cold_after
+    }
+
     pub fn with_ttl(&mut self, ttl: Duration) {
         self.ttl = Some(ttl);
     }
@@ -1044,6 +1103,18 @@ impl DatabaseOptions {
         self.precision = Some(precision)
     }
 
+    pub fn with_replica_placement(&mut self, replica_placement: ReplicaPlacement) {
+        self.replica_placement = Some(replica_placement);
+    }
+
+    pub fn unset_replica_placement(&mut self) {
+        self.replica_placement = None;
+    }
+
+    pub fn with_cold_after(&mut self, cold_after: Duration) {
+        self.cold_after = Some(cold_after);
+    }
+
     pub fn get_db_is_hidden(&self) -> bool {
         self.db_is_hidden
     }
@@ -1284,6 +1355,8 @@ impl Tenant {
 pub struct TenantOptions {
     pub comment: Option<String>,
     pub limiter_config: Option<TenantLimiterConfig>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub resource_group_config: Option<TenantResourceGroupConfig>,
     drop_after: Option<Duration>,
     // None means now
     tenant_is_hidden: bool,
@@ -1298,6 +1371,9 @@ impl From<TenantOptions> for TenantOptionsBuilder {
         if let Some(config) = value.limiter_config.clone() {
             builder.limiter_config(config);
         }
+        if let Some(config) = value.resource_group_config.clone() {
+            builder.resource_group_config(config);
+        }
         if let Some(drop_after) = value.get_drop_after() {
             builder.drop_after(drop_after);
         }
@@ -1313,6 +1389,9 @@ impl TenantOptionsBuilder {
     pub fn unset_limiter_config(&mut self) {
         self.limiter_config = None
     }
+    pub fn unset_resource_group_config(&mut self) {
+        self.resource_group_config = None
+    }
     pub fn unset_drop_after(&mut self) {
         self.drop_after = None;
     }
@@ -1358,6 +1437,10 @@ impl Display for TenantOptions {
             write!(f, "limiter=None,")?;
         }
 
+        if let Some(ref e) = self.resource_group_config {
+            write!(f, "resource_group={e:?},")?;
+        }
+
         Ok(())
     }
 }
@@ -1450,3 +1533,28 @@ impl PhysicalCType {
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::{DatabaseSchema, Duration, Precision};
+    use crate::utils::{now_timestamp_millis, now_timestamp_nanos, DAY_NANOS};
+
+    #[test]
+    fn test_time_to_cold() {
+        let mut schema = DatabaseSchema::new("cnosdb", "db");
+        assert_eq!(schema.time_to_cold(), None);
+
+        schema.config.with_cold_after(Duration::new_with_day(1));
+        let before = now_timestamp_nanos() - DAY_NANOS;
+        let time_to_cold = schema.time_to_cold().unwrap();
+        assert!(before <= time_to_cold);
+        assert!(time_to_cold <= now_timestamp_nanos() - DAY_NANOS);
+
+        // in the precision of the database
+        schema.config.with_precision(Precision::MS);
+        let before = now_timestamp_millis() - DAY_NANOS / 1_000_000;
+        let time_to_cold = schema.time_to_cold().unwrap();
+        assert!(before <= time_to_cold);
+        assert!(time_to_cold <= now_timestamp_millis() - DAY_NANOS / 1_000_000);
+    }
+}
@@ -2,7 +2,7 @@ use std::str::FromStr;
 
 use datafusion::datasource::file_format::file_type::{FileCompressionType, FileType};
 
-use crate::arrow::arrow_data_type_to_sql_data_type;
+use crate::arrow::{arrow_data_type_to_sql_data_type, ExternalFileType};
 use crate::auth::role::CustomTenantRole;
 use crate::auth::user::UserDesc;
 use crate::codec::Encoding;
@@ -11,7 +11,7 @@ use crate::errors::DumpSnafu;
 use crate::oid::{Identifier, Oid};
 use crate::schema::{
     ColumnType, DatabaseSchema, DurationUnit, ExternalTableSchema, StreamTable, TableSchema,
-    Tenant, TskvTableSchema,
+    Tenant, TskvTableSchema, ViewSchema,
 };
 use crate::ModelError;
 
@@ -66,9 +66,29 @@ impl ToDDLSql for Tenant {
                     .map(|config| ("_limiter", SqlParserValue::SingleQuotedString(config)))
             })
             .transpose()?;
+        let resource_group = option
+            .resource_group_config
+            .as_ref()
+            .map(|a| {
+                serde_json::to_string(&a)
+                    .map_err(|e| {
+                        DumpSnafu {
+                            msg: format!("dump tenant resource group failed: {}", e),
+                        }
+                        .build()
+                    })
+                    .map(|config| {
+                        (
+                            "_resource_group",
+                            SqlParserValue::SingleQuotedString(config),
+                        )
+                    })
+            })
+            .transpose()?;
         sql_opts.push(comment);
         sql_opts.push(drop_after);
         sql_opts.push(limit);
+        sql_opts.push(resource_group);
         let str = sql_option_to_sql_str(sql_opts);
         if !str.is_empty() {
             res.push_str("with ");
@@ -169,6 +189,22 @@ impl ToDDLSql for DatabaseSchema {
             res.push_str(format!("replica {} ", rep).as_str())
         }
 
+        if let Some(placement) = self.config.replica_placement() {
+            res.push_str(format!("replica_placement '{}' ", placement).as_str())
+        }
+
+        if let Some(cold_after) = self.config.cold_after() {
+            let unit = match cold_after.unit {
+                DurationUnit::Minutes => Some("M"),
+                DurationUnit::Hour => Some("H"),
+                DurationUnit::Day => Some("D"),
+                DurationUnit::Inf => None,
+            };
+            if let Some(u) = unit {
+                res.push_str(format!("cold_after '{}{}' ", cold_after.time_num, u).as_str())
+            }
+        }
+
         if let Some(d) = self.config.vnode_duration() {
             let unit = match d.unit {
                 DurationUnit::Minutes => Some("M"),
@@ -252,11 +288,13 @@ pub fn create_table_sqls(tables: &[TableSchema], if_not_exists: bool) -> Result<
     let mut ts_table = vec![];
     let mut ex_table = vec![];
     let mut stream_table = vec![];
+    let mut views = vec![];
     for table in tables {
         match table {
             TableSchema::TsKvTableSchema(t) => ts_table.push(t),
             TableSchema::ExternalTableSchema(t) => ex_table.push(t),
             TableSchema::StreamTableSchema(s) => stream_table.push(s),
+            TableSchema::ViewSchema(v) => views.push(v),
         }
     }
 
@@ -272,6 +310,11 @@ pub fn create_table_sqls(tables: &[TableSchema], if_not_exists: bool) -> Result<
         res.push(stream.to_ddl_sql(if_not_exists)?)
     }
 
+    // views are created after the tables they query
+    for view in views.into_iter() {
+        res.push(view.to_ddl_sql(if_not_exists)?)
+    }
+
     Ok(res)
 }
 
@@ -282,7 +325,25 @@ impl ToDDLSql for TableSchema {
             TableSchema::TsKvTableSchema(t) => t.to_ddl_sql(if_not_exists),
             TableSchema::ExternalTableSchema(t) => t.to_ddl_sql(if_not_exists),
             TableSchema::StreamTableSchema(t) => t.to_ddl_sql(if_not_exists),
+            TableSchema::ViewSchema(t) => t.to_ddl_sql(if_not_exists),
+        }
+    }
+}
+
+// CREATE VIEW
+impl ToDDLSql for ViewSchema {
+    fn to_ddl_sql(&self, _if_not_exists: bool) -> Result<String> {
+        if self.materialized.is_some() {
+            // the stored results are in a tskv table, which is created before
+            return Ok(format!(
+                "create materialized view if not exists \"{}\".\"{}\" as {};",
+                self.db, self.name, self.definition
+            ));
         }
+        Ok(format!(
+            "create or replace view \"{}\".\"{}\" as {};",
+            self.db, self.name, self.definition
+        ))
     }
 }
 
@@ -332,9 +393,9 @@ impl ToDDLSql for ExternalTableSchema {
             res.push_str("if not exists ")
         }
         res.push_str(format!("\"{}\".\"{}\" ", self.db, self.name).as_str());
-        let file_type = FileType::from_str(&self.file_type)
+        let file_type = ExternalFileType::from_str(&self.file_type)
             .map_err(|e| DumpSnafu { msg: e.to_string() }.build())?;
-        if file_type.ne(&FileType::PARQUET) {
+        if file_type.ne(&ExternalFileType::DataFusion(FileType::PARQUET)) {
             let columns = self
                 .schema
                 .fields
@@ -352,7 +413,7 @@ impl ToDDLSql for ExternalTableSchema {
         }
 
         res.push_str(format!("stored as {} ", self.file_type).as_str());
-        if file_type.eq(&FileType::CSV) {
+        if file_type.eq(&ExternalFileType::DataFusion(FileType::CSV)) {
             if self.has_header {
                 res.push_str("with header row ");
             }
@@ -365,7 +426,10 @@ impl ToDDLSql for ExternalTableSchema {
             )
         }
 
-        if matches!(file_type, FileType::CSV | FileType::JSON) {
+        if matches!(
+            file_type,
+            ExternalFileType::DataFusion(FileType::CSV | FileType::JSON)
+        ) {
             let compression_type = FileCompressionType::from_str(&self.file_compression_type)
                 .map_err(|e| DumpSnafu { msg: e.to_string() }.build())?;
 
@@ -144,6 +144,66 @@ pub struct FetchChecksumRequest {
 }
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
+pub struct RebuildIndexRequest {
+    #[prost(uint32, tag = "1")]
+    pub vnode_id: u32,
+}
+/// A connection option of object store, value is a sql literal like 'value' or true.
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ConnectionOption {
+    #[prost(string, tag = "1")]
+    pub name: ::prost::alloc::string::String,
+    #[prost(string, tag = "2")]
+    pub value: ::prost::alloc::string::String,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct BackupVnodeRequest {
+    #[prost(uint32, tag = "1")]
+    pub vnode_id: u32,
+    #[prost(string, tag = "2")]
+    pub location: ::prost::alloc::string::String,
+    #[prost(message, repeated, tag = "3")]
+    pub connection_options: ::prost::alloc::vec::Vec<ConnectionOption>,
+    #[prost(bool, tag = "4")]
+    pub incremental: bool,
+    #[prost(string, tag = "5")]
+    pub backup_id: ::prost::alloc::string::String,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct RestoreVnodeRequest {
+    #[prost(string, tag = "1")]
+    pub db_name: ::prost::alloc::string::String,
+    #[prost(uint32, tag = "2")]
+    pub vnode_id: u32,
+    #[prost(string, tag = "3")]
+    pub location: ::prost::alloc::string::String,
+    #[prost(message, repeated, tag = "4")]
+    pub connection_options: ::prost::alloc::vec::Vec<ConnectionOption>,
+    #[prost(string, tag = "5")]
+    pub backup_id: ::prost::alloc::string::String,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct GetVnodeVersionsRequest {
+    #[prost(string, tag = "1")]
+    pub db_name: ::prost::alloc::string::String,
+    #[prost(uint32, repeated, tag = "2")]
+    pub vnode_ids: ::prost::alloc::vec::Vec<u32>,
+}
+/// Wait until the vnode applied all logs committed in the replication set.
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct SyncReplicaRequest {
+    #[prost(uint32, tag = "1")]
+    pub replica_id: u32,
+    #[prost(uint32, tag = "2")]
+    pub vnode_id: u32,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
 pub struct OpenRaftNodeRequest {
     #[prost(string, tag = "1")]
     pub tenant: ::prost::alloc::string::String,
@@ -185,6 +245,9 @@ pub struct RemoveRaftNodeRequest {
     pub replica_id: u32,
     #[prost(uint32, tag = "3")]
     pub vnode_id: u32,
+    /// The node of the vnode is unreachable, don't drop the vnode on it.
+    #[prost(bool, tag = "4")]
+    pub keep_unreachable: bool,
 }
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
@@ -217,7 +280,7 @@ pub struct LearnerToFollowerRequest {
 pub struct AdminCommand {
     #[prost(string, tag = "1")]
     pub tenant: ::prost::alloc::string::String,
-    #[prost(oneof = "admin_command::Command", tags = "2, 3, 4, 5, 6, 7, 8, 9, 10")]
+    #[prost(oneof = "admin_command::Command", tags = "2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15")]
     pub command: ::core::option::Option<admin_command::Command>,
 }
 /// Nested message and enum types in `AdminCommand`.
@@ -243,6 +306,16 @@ pub mod admin_command {
         PromoteLeader(super::PromoteLeaderRequest),
         #[prost(message, tag = "10")]
         LearnerToFollower(super::LearnerToFollowerRequest),
+        #[prost(message, tag = "11")]
+        RebuildIndex(super::RebuildIndexRequest),
+        #[prost(message, tag = "12")]
+        BackupVnode(super::BackupVnodeRequest),
+        #[prost(message, tag = "13")]
+        RestoreVnode(super::RestoreVnodeRequest),
+        #[prost(message, tag = "14")]
+        GetVnodeVersions(super::GetVnodeVersionsRequest),
+        #[prost(message, tag = "15")]
+        SyncReplica(super::SyncReplicaRequest),
     }
 }
 /// --------------------------------------------------------------------
@@ -251,6 +324,12 @@ pub mod admin_command {
 pub struct DownloadFileRequest {
     #[prost(string, tag = "1")]
     pub filename: ::prost::alloc::string::String,
+    /// download from the offset, to resume an interrupted download
+    #[prost(uint64, tag = "2")]
+    pub offset: u64,
+    /// size of each chunk in the response stream
+    #[prost(uint64, tag = "3")]
+    pub chunk_size: u64,
 }
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
@@ -261,6 +340,9 @@ pub struct QueryRecordBatchRequest {
     pub expr: ::prost::alloc::vec::Vec<u8>,
     #[prost(bytes = "vec", tag = "3")]
     pub aggs: ::prost::alloc::vec::Vec<u8>,
+    /// plan executed on the scanned record batches, empty if no plan is pushed down
+    #[prost(bytes = "vec", tag = "4")]
+    pub plan: ::prost::alloc::vec::Vec<u8>,
 }
 /// Generated client implementations.
 pub mod tskv_service_client {
@@ -31,6 +31,12 @@ pub struct RaftAppendEntriesReq {
     #[prost(bytes = "vec", tag = "3")]
     pub data: ::prost::alloc::vec::Vec<u8>,
 }
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct RaftReadIndexReq {
+    #[prost(uint32, tag = "2")]
+    pub group_id: u32,
+}
 /// Generated client implementations.
 pub mod raft_service_client {
     #![allow(unused_variables, dead_code, missing_docs, clippy::let_unit_value)]
@@ -185,6 +191,28 @@ pub mod raft_service_client {
                 );
             self.inner.unary(req, path, codec).await
         }
+        pub async fn raft_read_index(
+            &mut self,
+            request: impl tonic::IntoRequest<super::RaftReadIndexReq>,
+        ) -> std::result::Result<tonic::Response<super::RaftResponse>, tonic::Status> {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/raft_service.RaftService/RaftReadIndex",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("raft_service.RaftService", "RaftReadIndex"));
+            self.inner.unary(req, path, codec).await
+        }
     }
 }
 /// Generated server implementations.
@@ -206,6 +234,10 @@ pub mod raft_service_server {
             &self,
             request: tonic::Request<super::RaftAppendEntriesReq>,
         ) -> std::result::Result<tonic::Response<super::RaftResponse>, tonic::Status>;
+        async fn raft_read_index(
+            &self,
+            request: tonic::Request<super::RaftReadIndexReq>,
+        ) -> std::result::Result<tonic::Response<super::RaftResponse>, tonic::Status>;
     }
     /// --------------------------------------------------------------------
     #[derive(Debug)]
@@ -421,6 +453,52 @@ pub mod raft_service_server {
                     };
                     Box::pin(fut)
                 }
+                "/raft_service.RaftService/RaftReadIndex" => {
+                    #[allow(non_camel_case_types)]
+                    struct RaftReadIndexSvc<T: RaftService>(pub Arc<T>);
+                    impl<
+                        T: RaftService,
+                    > tonic::server::UnaryService<super::RaftReadIndexReq>
+                    for RaftReadIndexSvc<T> {
+                        type Response = super::RaftResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::RaftReadIndexReq>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                (*inner).raft_read_index(request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = RaftReadIndexSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
                 _ => {
                     Box::pin(async move {
                         Ok(
@@ -15,6 +15,40 @@ pub struct TenantObjectLimiterConfig {
     pub max_shard_number: Option<usize>,
     pub max_replicate_number: Option<usize>,
     pub max_retention_time: Option<usize>,
+    /// max series of a database in each vnode, checked when writing.
+    /// Vnodes are counted separately, so a database may have up to
+    /// (number of vnodes) * max_series_per_vnode series.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_series_per_vnode: Option<usize>,
+    /// max series of a table in each vnode, checked when writing
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_table_series_per_vnode: Option<usize>,
+    /// max series of the tenant in the cluster, checked when writing.
+    /// The series are counted from what vnodes report to meta every
+    /// `meta.report_time_interval`, so the series written since the last
+    /// report may go over the limit.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_series_per_tenant: Option<usize>,
+    /// max series of a database in the cluster, counted like max_series_per_tenant
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_series_per_database: Option<usize>,
+    /// max series of a table in the cluster, counted like max_series_per_tenant
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_series_per_table: Option<usize>,
+}
+
+impl TenantObjectLimiterConfig {
+    /// If any limit of the series in the cluster is set.
+    pub fn limits_cluster_series(&self) -> bool {
+        self.max_series_per_tenant.is_some()
+            || self.max_series_per_database.is_some()
+            || self.max_series_per_table.is_some()
+    }
+
+    /// If any limit of the series in a vnode is set.
+    pub fn limits_vnode_series(&self) -> bool {
+        self.max_series_per_vnode.is_some() || self.max_table_series_per_vnode.is_some()
+    }
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, Copy)]
@@ -61,6 +95,11 @@ max_databases = 3
 max_shard_number = 2
 max_replicate_number = 2
 max_retention_time = 30
+max_series_per_vnode = 1000000
+max_table_series_per_vnode = 100000
+max_series_per_tenant = 10000000
+max_series_per_database = 5000000
+max_series_per_table = 1000000
 
 
 [request_config.coord_data_in]
@@ -1,5 +1,7 @@
 mod limiter_config;
 mod log_config;
+mod resource_group_config;
 
 pub use limiter_config::*;
 pub use log_config::*;
+pub use resource_group_config::*;
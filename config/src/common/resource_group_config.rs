@@ -0,0 +1,79 @@
+use std::collections::HashMap;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use crate::codec::duration;
+
+/// Resource groups of a tenant, the queries of a user run in the group of the user if
+/// there is one, otherwise in the group of the tenant
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct TenantResourceGroupConfig {
+    pub tenant: Option<ResourceGroupConfig>,
+    /// groups of users, by user name
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub users: HashMap<String, ResourceGroupConfig>,
+}
+
+impl TenantResourceGroupConfig {
+    pub fn group_of_user(&self, user: &str) -> Option<&ResourceGroupConfig> {
+        self.users.get(user).or(self.tenant.as_ref())
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct ResourceGroupConfig {
+    /// max number of queries of the group running at the same time
+    pub max_concurrent_queries: usize,
+    /// max number of queries waiting for a running query to finish, more are rejected
+    #[serde(default)]
+    pub max_queued_queries: usize,
+    /// a query waiting longer than this in the queue is rejected
+    #[serde(
+        with = "duration",
+        default = "ResourceGroupConfig::default_queue_timeout"
+    )]
+    pub queue_timeout: Duration,
+    /// percentage of the cpu cores of a node that one query may use
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cpu_share: Option<usize>,
+    /// max bytes of memory used by the running queries of the group
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub memory_quota: Option<usize>,
+}
+
+impl ResourceGroupConfig {
+    fn default_queue_timeout() -> Duration {
+        Duration::from_secs(60)
+    }
+}
+
+#[test]
+fn test_config() {
+    let config_str = r#"
+[tenant]
+max_concurrent_queries = 8
+max_queued_queries = 100
+queue_timeout = "30s"
+memory_quota = 1073741824
+
+[users.etl]
+max_concurrent_queries = 2
+cpu_share = 25
+"#;
+    let config: TenantResourceGroupConfig = toml::from_str(config_str).unwrap();
+
+    let tenant = config.tenant.as_ref().unwrap();
+    assert_eq!(tenant.max_concurrent_queries, 8);
+    assert_eq!(tenant.max_queued_queries, 100);
+    assert_eq!(tenant.queue_timeout, Duration::from_secs(30));
+    assert_eq!(tenant.cpu_share, None);
+    assert_eq!(tenant.memory_quota, Some(1073741824));
+
+    let etl = config.group_of_user("etl").unwrap();
+    assert_eq!(etl.max_concurrent_queries, 2);
+    assert_eq!(etl.max_queued_queries, 0);
+    assert_eq!(etl.queue_timeout, Duration::from_secs(60));
+    assert_eq!(etl.cpu_share, Some(25));
+    assert_eq!(config.group_of_user("root"), config.tenant.as_ref());
+}
@@ -0,0 +1,90 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use macros::EnvKeys;
+use serde::{Deserialize, Serialize};
+
+use crate::check::{CheckConfig, CheckConfigItemResult, CheckConfigResult};
+use crate::codec::duration;
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, EnvKeys)]
+pub struct AuditConfig {
+    #[serde(default = "AuditConfig::default_enable")]
+    pub enable: bool,
+
+    #[serde(with = "duration", default = "AuditConfig::default_retention")]
+    pub retention: Duration,
+
+    #[serde(
+        with = "duration",
+        default = "AuditConfig::default_retention_check_interval"
+    )]
+    pub retention_check_interval: Duration,
+
+    #[serde(default = "AuditConfig::default_file_path")]
+    pub file_path: Option<String>,
+}
+
+impl AuditConfig {
+    fn default_enable() -> bool {
+        false
+    }
+
+    fn default_retention() -> Duration {
+        Duration::from_secs(90 * 24 * 3600)
+    }
+
+    fn default_retention_check_interval() -> Duration {
+        Duration::from_secs(3600)
+    }
+
+    fn default_file_path() -> Option<String> {
+        None
+    }
+}
+
+impl Default for AuditConfig {
+    fn default() -> Self {
+        Self {
+            enable: AuditConfig::default_enable(),
+            retention: AuditConfig::default_retention(),
+            retention_check_interval: AuditConfig::default_retention_check_interval(),
+            file_path: AuditConfig::default_file_path(),
+        }
+    }
+}
+
+impl CheckConfig for AuditConfig {
+    fn check(&self, _: &super::Config) -> Option<CheckConfigResult> {
+        let config_name = Arc::new("audit".to_string());
+        let mut ret = CheckConfigResult::default();
+
+        if self.retention.is_zero() {
+            ret.add_error(CheckConfigItemResult {
+                config: config_name.clone(),
+                item: "retention".to_string(),
+                message: "'retention' must be greater than 0".to_string(),
+            });
+        }
+        if self.retention_check_interval.is_zero() {
+            ret.add_error(CheckConfigItemResult {
+                config: config_name.clone(),
+                item: "retention_check_interval".to_string(),
+                message: "'retention_check_interval' must be greater than 0".to_string(),
+            });
+        }
+        if self.file_path.as_ref().is_some_and(|p| p.is_empty()) {
+            ret.add_warn(CheckConfigItemResult {
+                config: config_name,
+                item: "file_path".to_string(),
+                message: "'file_path' is empty, audit events are not written to file".to_string(),
+            });
+        }
+
+        if ret.is_empty() {
+            None
+        } else {
+            Some(ret)
+        }
+    }
+}
@@ -47,6 +47,54 @@ pub struct ClusterConfig {
         default = "ClusterConfig::default_install_snapshot_timeout"
     )]
     pub install_snapshot_timeout: Duration, //ms
+
+    #[serde(
+        with = "bytes_num",
+        default = "ClusterConfig::default_snapshot_chunk_size"
+    )]
+    pub snapshot_chunk_size: u64,
+
+    #[serde(
+        with = "bytes_num",
+        default = "ClusterConfig::default_snapshot_transfer_rate_limit"
+    )]
+    pub snapshot_transfer_rate_limit: u64,
+
+    #[serde(default = "ClusterConfig::default_enable_replica_repair")]
+    pub enable_replica_repair: bool,
+
+    #[serde(
+        with = "duration",
+        default = "ClusterConfig::default_replica_repair_grace_period"
+    )]
+    pub replica_repair_grace_period: Duration,
+
+    #[serde(
+        with = "duration",
+        default = "ClusterConfig::default_rebalance_move_interval"
+    )]
+    pub rebalance_move_interval: Duration,
+
+    #[serde(default = "ClusterConfig::default_hinted_handoff_path")]
+    pub hinted_handoff_path: String,
+
+    #[serde(
+        with = "bytes_num",
+        default = "ClusterConfig::default_hinted_handoff_max_size"
+    )]
+    pub hinted_handoff_max_size: u64,
+
+    #[serde(
+        with = "duration",
+        default = "ClusterConfig::default_hinted_handoff_replay_interval"
+    )]
+    pub hinted_handoff_replay_interval: Duration,
+
+    #[serde(
+        with = "duration",
+        default = "ClusterConfig::default_cold_migration_check_interval"
+    )]
+    pub cold_migration_check_interval: Duration,
 }
 
 impl ClusterConfig {
@@ -77,6 +125,43 @@ impl ClusterConfig {
     fn default_install_snapshot_timeout() -> Duration {
         Duration::from_millis(3_600_000)
     }
+
+    fn default_snapshot_chunk_size() -> u64 {
+        4 * 1024 * 1024
+    }
+
+    fn default_snapshot_transfer_rate_limit() -> u64 {
+        0
+    }
+
+    fn default_enable_replica_repair() -> bool {
+        true
+    }
+
+    fn default_replica_repair_grace_period() -> Duration {
+        Duration::from_secs(1800)
+    }
+
+    fn default_rebalance_move_interval() -> Duration {
+        Duration::from_secs(60)
+    }
+
+    fn default_hinted_handoff_path() -> String {
+        let path = std::path::Path::new("cnosdb_data").join("hh");
+        path.to_string_lossy().to_string()
+    }
+
+    fn default_hinted_handoff_max_size() -> u64 {
+        1024 * 1024 * 1024
+    }
+
+    fn default_hinted_handoff_replay_interval() -> Duration {
+        Duration::from_secs(10)
+    }
+
+    fn default_cold_migration_check_interval() -> Duration {
+        Duration::from_secs(600)
+    }
 }
 
 impl Default for ClusterConfig {
@@ -89,6 +174,15 @@ impl Default for ClusterConfig {
             trigger_snapshot_interval: ClusterConfig::default_trigger_snapshot_interval(),
             send_append_entries_timeout: ClusterConfig::default_send_append_entries_timeout(),
             install_snapshot_timeout: ClusterConfig::default_install_snapshot_timeout(),
+            snapshot_chunk_size: ClusterConfig::default_snapshot_chunk_size(),
+            snapshot_transfer_rate_limit: ClusterConfig::default_snapshot_transfer_rate_limit(),
+            enable_replica_repair: ClusterConfig::default_enable_replica_repair(),
+            replica_repair_grace_period: ClusterConfig::default_replica_repair_grace_period(),
+            rebalance_move_interval: ClusterConfig::default_rebalance_move_interval(),
+            hinted_handoff_path: ClusterConfig::default_hinted_handoff_path(),
+            hinted_handoff_max_size: ClusterConfig::default_hinted_handoff_max_size(),
+            hinted_handoff_replay_interval: ClusterConfig::default_hinted_handoff_replay_interval(),
+            cold_migration_check_interval: ClusterConfig::default_cold_migration_check_interval(),
         }
     }
 }
@@ -15,6 +15,14 @@ pub struct GlobalConfig {
     pub cluster_name: String,
     #[serde(default = "GlobalConfig::default_store_metrics")]
     pub store_metrics: bool,
+    /// Failure domain labels of the node, used by the replica placement policy.
+    #[serde(default)]
+    pub zone: Option<String>,
+    #[serde(default)]
+    pub rack: Option<String>,
+    /// 'hot' or 'cold', new buckets are only placed on hot nodes.
+    #[serde(default = "GlobalConfig::default_tier")]
+    pub tier: String,
 }
 
 impl GlobalConfig {
@@ -33,6 +41,10 @@ impl GlobalConfig {
     fn default_store_metrics() -> bool {
         true
     }
+
+    fn default_tier() -> String {
+        "hot".to_string()
+    }
 }
 
 impl Default for GlobalConfig {
@@ -42,6 +54,9 @@ impl Default for GlobalConfig {
             host: GlobalConfig::default_host(),
             cluster_name: GlobalConfig::default_cluster_name(),
             store_metrics: GlobalConfig::default_store_metrics(),
+            zone: None,
+            rack: None,
+            tier: GlobalConfig::default_tier(),
         }
     }
 }
@@ -59,6 +74,17 @@ impl CheckConfig for GlobalConfig {
             });
         }
 
+        if !matches!(self.tier.to_ascii_lowercase().as_str(), "hot" | "cold") {
+            ret.add_error(CheckConfigItemResult {
+                config: config_name,
+                item: "tier".to_string(),
+                message: format!(
+                    "'tier' should be 'hot' or 'cold', but found '{}'",
+                    self.tier
+                ),
+            });
+        }
+
         if ret.is_empty() {
             None
         } else {
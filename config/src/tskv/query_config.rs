@@ -27,6 +27,63 @@ pub struct QueryConfig {
     pub stream_executor_cpu: usize,
     #[serde(with = "duration", default = "QueryConfig::default_sql_record_timeout")]
     pub sql_record_timeout: Duration,
+    /// Max bytes of temp files a query may spill to disk when it runs out of memory.
+    ///
+    /// Only limits the spills of gapfill, sorts of DataFusion spill to the same directory
+    /// without a limit. A GROUP BY running out of memory is run again with its input sorted,
+    /// joins don't spill.
+    #[serde(
+        with = "bytes_num",
+        default = "QueryConfig::default_max_spill_size_per_query"
+    )]
+    pub max_spill_size_per_query: u64,
+    /// Cache results of queries, reused while the data of the scanned vnodes is unchanged
+    #[serde(default = "QueryConfig::default_result_cache_enabled")]
+    pub result_cache_enabled: bool,
+    #[serde(
+        with = "bytes_num",
+        default = "QueryConfig::default_result_cache_max_size"
+    )]
+    pub result_cache_max_size: u64,
+    /// Results larger than this are not cached
+    #[serde(
+        with = "bytes_num",
+        default = "QueryConfig::default_result_cache_max_entry_size"
+    )]
+    pub result_cache_max_entry_size: u64,
+    #[serde(with = "duration", default = "QueryConfig::default_result_cache_ttl")]
+    pub result_cache_ttl: Duration,
+    /// Data older than this when a result was cached is assumed to be unchanged,
+    /// so that only the newer time range is recomputed for time-range queries
+    #[serde(
+        with = "duration",
+        default = "QueryConfig::default_result_cache_mutable_window"
+    )]
+    pub result_cache_mutable_window: Duration,
+    /// Results of async queries are expired after they finished for this long
+    #[serde(
+        with = "duration",
+        default = "QueryConfig::default_async_query_result_ttl"
+    )]
+    pub async_query_result_ttl: Duration,
+    /// Max number of rows of a page of the results of async queries
+    #[serde(default = "QueryConfig::default_async_query_page_rows")]
+    pub async_query_page_rows: usize,
+    /// Max bytes of the results of an async query spooled to disk
+    #[serde(
+        with = "bytes_num",
+        default = "QueryConfig::default_async_query_max_result_size"
+    )]
+    pub async_query_max_result_size: u64,
+    /// Max bytes of the results of all async queries spooled to disk
+    #[serde(
+        with = "bytes_num",
+        default = "QueryConfig::default_async_query_max_total_size"
+    )]
+    pub async_query_max_total_size: u64,
+    /// Max number of running async queries of a user
+    #[serde(default = "QueryConfig::default_async_query_max_running_per_user")]
+    pub async_query_max_running_per_user: usize,
 }
 
 impl QueryConfig {
@@ -65,6 +122,50 @@ impl QueryConfig {
     fn default_sql_record_timeout() -> Duration {
         Duration::from_secs(10)
     }
+
+    fn default_max_spill_size_per_query() -> u64 {
+        10 * 1024 * 1024 * 1024
+    }
+
+    fn default_result_cache_enabled() -> bool {
+        false
+    }
+
+    fn default_result_cache_max_size() -> u64 {
+        256 * 1024 * 1024
+    }
+
+    fn default_result_cache_max_entry_size() -> u64 {
+        16 * 1024 * 1024
+    }
+
+    fn default_result_cache_ttl() -> Duration {
+        Duration::from_secs(5 * 60)
+    }
+
+    fn default_result_cache_mutable_window() -> Duration {
+        Duration::from_secs(60)
+    }
+
+    fn default_async_query_result_ttl() -> Duration {
+        Duration::from_secs(60 * 60)
+    }
+
+    fn default_async_query_page_rows() -> usize {
+        10_000
+    }
+
+    fn default_async_query_max_result_size() -> u64 {
+        10 * 1024 * 1024 * 1024
+    }
+
+    fn default_async_query_max_total_size() -> u64 {
+        50 * 1024 * 1024 * 1024
+    }
+
+    fn default_async_query_max_running_per_user() -> usize {
+        10
+    }
 }
 
 impl Default for QueryConfig {
@@ -79,6 +180,17 @@ impl Default for QueryConfig {
             stream_trigger_cpu: Self::default_stream_trigger_cpu(),
             stream_executor_cpu: Self::default_stream_executor_cpu(),
             sql_record_timeout: Self::default_sql_record_timeout(),
+            max_spill_size_per_query: Self::default_max_spill_size_per_query(),
+            result_cache_enabled: Self::default_result_cache_enabled(),
+            result_cache_max_size: Self::default_result_cache_max_size(),
+            result_cache_max_entry_size: Self::default_result_cache_max_entry_size(),
+            result_cache_ttl: Self::default_result_cache_ttl(),
+            result_cache_mutable_window: Self::default_result_cache_mutable_window(),
+            async_query_result_ttl: Self::default_async_query_result_ttl(),
+            async_query_page_rows: Self::default_async_query_page_rows(),
+            async_query_max_result_size: Self::default_async_query_max_result_size(),
+            async_query_max_total_size: Self::default_async_query_max_total_size(),
+            async_query_max_running_per_user: Self::default_async_query_max_running_per_user(),
         }
     }
 }
@@ -141,6 +253,25 @@ impl CheckConfig for QueryConfig {
             })
         }
 
+        if self.result_cache_enabled
+            && self.result_cache_max_entry_size > self.result_cache_max_size
+        {
+            ret.add_warn(CheckConfigItemResult {
+                config: config_name.clone(),
+                item: "result_cache_max_entry_size".to_string(),
+                message: "'result_cache_max_entry_size' is bigger than 'result_cache_max_size'"
+                    .to_string(),
+            })
+        }
+
+        if self.async_query_page_rows == 0 {
+            ret.add_warn(CheckConfigItemResult {
+                config: config_name.clone(),
+                item: "async_query_page_rows".to_string(),
+                message: "'async_query_page_rows' must be greater than 0".to_string(),
+            })
+        }
+
         if self.sql_record_timeout.as_secs() < 1 {
             ret.add_warn(CheckConfigItemResult {
                 config: config_name,
@@ -6,7 +6,7 @@ use datafusion::error::DataFusionError;
 use flatbuffers::InvalidFlatbuffer;
 use meta::error::MetaError;
 use models::error_code::{ErrorCode, ErrorCoder};
-use models::meta_data::{ReplicationSet, ReplicationSetId, VnodeId};
+use models::meta_data::{NodeId, ReplicationSet, ReplicationSetId, VnodeId};
 use models::schema::Precision;
 use models::Timestamp;
 use protos::PointsError;
@@ -291,6 +291,50 @@ pub enum CoordinatorError {
         location: Location,
         backtrace: Backtrace,
     },
+
+    #[snafu(display("Hinted handoff is full, the max size is {} bytes", max_size))]
+    #[error_code(code = 37)]
+    HintedHandoffFull {
+        max_size: u64,
+        location: Location,
+        backtrace: Backtrace,
+    },
+
+    #[snafu(display(
+        "Replication set {} lost its quorum, only {} of {} vnodes are on healthy nodes",
+        replica_id,
+        alive,
+        total
+    ))]
+    #[error_code(code = 38)]
+    ReplicaQuorumLost {
+        replica_id: ReplicationSetId,
+        alive: usize,
+        total: usize,
+        location: Location,
+        backtrace: Backtrace,
+    },
+
+    #[snafu(display("Table {} is read-only", table))]
+    #[error_code(code = 39)]
+    ReadOnlyTable {
+        table: String,
+    },
+
+    #[snafu(display("Node {} is unreachable: {}", node_id, error))]
+    #[error_code(code = 40)]
+    NodeUnreachable {
+        node_id: NodeId,
+        error: String,
+    },
+
+    #[snafu(display("Hints of replication set {} are not replayed yet", replica_id))]
+    #[error_code(code = 41)]
+    HintsNotReplayed {
+        replica_id: ReplicationSetId,
+        location: Location,
+        backtrace: Backtrace,
+    },
 }
 
 impl From<ArrowError> for CoordinatorError {
@@ -336,6 +380,19 @@ impl From<tonic::Status> for CoordinatorError {
 }
 
 impl CoordinatorError {
+    /// Convert the status of a request to the node, the node is unreachable
+    /// if the status is `Unavailable`.
+    pub fn from_node_status(node_id: NodeId, status: tonic::Status) -> Self {
+        if status.code() == tonic::Code::Unavailable {
+            Self::NodeUnreachable {
+                node_id,
+                error: status.to_string(),
+            }
+        } else {
+            status.into()
+        }
+    }
+
     pub fn error_code(&self) -> &dyn ErrorCode {
         match self {
             CoordinatorError::Meta { source } => source.error_code(),
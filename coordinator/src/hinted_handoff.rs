@@ -0,0 +1,726 @@
+//! Hinted handoff of writes with consistency level `any`.
+//!
+//! If a replication set can not commit a write, e.g. its raft group has no leader, the write
+//! is appended to a local hint queue of the replication set instead of failing, and the hints
+//! are replayed to the replication set in background once it's writable again.
+//!
+//! Hints of a replication set are stored in record files `$hinted_handoff_path/$replica_id/_$id.hh`,
+//! a file is removed after all hints in it are replayed. Hints in a file may be replayed more
+//! than once if replaying fails halfway, which is harmless since writing a point again
+//! overwrites the same point.
+//!
+//! Hints are replayed in the order they were appended. A hint must not overwrite a point
+//! written after it, so a write with consistency level `any` to a replication set with hints
+//! is appended to the hints too, and other writes wait for the background replay before
+//! they are written. Writes never replay hints themselves.
+//!
+//! Replaying stops at a hint failed by a retryable error, e.g. the replication set has no
+//! leader, and is retried later. A hint failed by a permanent error, e.g. the table is
+//! dropped, would block the replication set forever, so it's moved to the dead letters
+//! `$hinted_handoff_path/dead_letters/$replica_id/_$id.hh` and replaying goes on.
+
+use std::collections::hash_map::Entry;
+use std::collections::{BTreeMap, HashMap};
+use std::future::Future;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+
+use config::tskv::ClusterConfig;
+use metrics::count::U64Counter;
+use metrics::gauge::U64Gauge;
+use metrics::metric::Metric;
+use metrics::metric_register::MetricsRegister;
+use models::meta_data::{NodeId, ReplicationSetId};
+use protos::kv_service::RaftWriteCommand;
+use protos::models_helper::{parse_prost_bytes, to_prost_bytes};
+use snafu::ResultExt;
+use tokio::sync::{Mutex, Notify};
+use trace::{debug, error, info};
+use tskv::record_file::{self, RecordDataType, RecordDataVersion};
+use tskv::{file_utils, TskvError};
+
+use crate::errors::*;
+use crate::{get_replica_all_info, Coordinator};
+
+const HINT_FILE_BUFFER_SIZE: usize = 1024 * 1024;
+const DEAD_LETTERS_DIR: &str = "dead_letters";
+
+/// Why a hint failed to be replayed
+#[derive(Debug)]
+pub enum ReplayError {
+    /// The replication set is not writable for now, the hint is replayed later.
+    Retryable(CoordinatorError),
+    /// The hint can never be written, it's moved to the dead letters.
+    Permanent(CoordinatorError),
+}
+
+impl From<CoordinatorError> for ReplayError {
+    fn from(err: CoordinatorError) -> Self {
+        if HintedHandoff::can_handoff(&err) {
+            Self::Retryable(err)
+        } else {
+            Self::Permanent(err)
+        }
+    }
+}
+
+pub struct HintedHandoff {
+    dir: PathBuf,
+    max_size: u64,
+    queues: Mutex<HashMap<ReplicationSetId, HintQueue>>,
+    /// Hints failed by permanent errors, which are kept for inspection but never replayed.
+    dead_letters: Mutex<HashMap<ReplicationSetId, HintQueue>>,
+    /// Only one replay runs at a time, so a hint is not replayed twice concurrently.
+    replay_lock: Mutex<()>,
+    /// Wakes up the background replay before the next interval.
+    wakeup: Notify,
+    /// Notified after each round of the background replay.
+    replayed: Notify,
+    metrics: HintedHandoffMetrics,
+}
+
+impl HintedHandoff {
+    /// Open the hint queues left by the last run.
+    pub async fn open(
+        config: &ClusterConfig,
+        node_id: NodeId,
+        register: &MetricsRegister,
+    ) -> CoordinatorResult<Self> {
+        let dir = PathBuf::from(&config.hinted_handoff_path);
+        let mut queues = HashMap::new();
+        if dir.exists() {
+            for entry in std::fs::read_dir(&dir).context(IOErrorsSnafu)? {
+                let entry = entry.context(IOErrorsSnafu)?;
+                let replica_id = match entry.file_name().to_string_lossy().parse() {
+                    Ok(id) => id,
+                    Err(_) => continue,
+                };
+                let queue = HintQueue::load(entry.path())?;
+                if queue.files.is_empty() {
+                    let _ = std::fs::remove_dir_all(entry.path());
+                } else {
+                    queues.insert(replica_id, queue);
+                }
+            }
+        }
+
+        let metrics = HintedHandoffMetrics::new(register, node_id);
+        let size = queues.values().map(|q| q.size()).sum();
+        metrics.size.set(size);
+        if size > 0 {
+            info!(
+                "Hinted handoff: {} bytes of hints to replay for {} replication sets",
+                size,
+                queues.len()
+            );
+        }
+
+        Ok(Self {
+            dir,
+            max_size: config.hinted_handoff_max_size,
+            queues: Mutex::new(queues),
+            dead_letters: Mutex::new(HashMap::new()),
+            replay_lock: Mutex::new(()),
+            wakeup: Notify::new(),
+            replayed: Notify::new(),
+            metrics,
+        })
+    }
+
+    /// Whether a failed write can be handed off, which fails because the replication set
+    /// is not writable for now, rather than the request itself is invalid. Errors returned
+    /// by a remote node lose their kind, so they are not retryable.
+    pub fn can_handoff(err: &CoordinatorError) -> bool {
+        matches!(
+            err,
+            CoordinatorError::NoValidReplica { .. }
+                | CoordinatorError::NodeUnreachable { .. }
+                | CoordinatorError::RaftForwardToLeader { .. }
+                | CoordinatorError::RaftWriteError { .. }
+                | CoordinatorError::RaftGroupError { .. }
+                | CoordinatorError::RaftNodeNotFound { .. }
+                | CoordinatorError::MemoryExhausted { .. }
+        )
+    }
+
+    /// Append a write to the hint queue of its replication set.
+    pub async fn append(&self, request: &RaftWriteCommand) -> CoordinatorResult<()> {
+        let data = to_prost_bytes(request);
+        let mut queues = self.queues.lock().await;
+        let size: u64 = queues.values().map(|q| q.size()).sum();
+        if size + data.len() as u64 > self.max_size {
+            self.metrics.dropped_writes.inc_one();
+            return Err(HintedHandoffFullSnafu {
+                max_size: self.max_size,
+            }
+            .build());
+        }
+
+        let queue = queues
+            .entry(request.replica_id)
+            .or_insert_with(|| HintQueue::new(self.dir.join(request.replica_id.to_string())));
+        let old_size = queue.size();
+        queue.append(&data).await?;
+
+        self.metrics.writes.inc_one();
+        self.metrics.size.inc(queue.size() - old_size);
+        self.wakeup.notify_one();
+
+        Ok(())
+    }
+
+    /// Whether the replication set has hints not replayed yet.
+    pub async fn has_hints(&self, replica_id: ReplicationSetId) -> bool {
+        self.queues.lock().await.contains_key(&replica_id)
+    }
+
+    /// Wait until the hints of the replication set are replayed in background,
+    /// returns false if they are not replayed in `timeout`.
+    pub async fn wait_replayed(&self, replica_id: ReplicationSetId, timeout: Duration) -> bool {
+        let deadline = tokio::time::Instant::now() + timeout;
+        loop {
+            let replayed = self.replayed.notified();
+            tokio::pin!(replayed);
+            replayed.as_mut().enable();
+            if !self.has_hints(replica_id).await {
+                return true;
+            }
+
+            self.wakeup.notify_one();
+            if tokio::time::timeout_at(deadline, replayed).await.is_err() {
+                return !self.has_hints(replica_id).await;
+            }
+        }
+    }
+
+    /// Replay hints to their replication sets every `interval`, or once hints are appended.
+    pub async fn run(self: Arc<Self>, coord: Arc<dyn Coordinator>, interval: Duration) {
+        let mut ticker = tokio::time::interval(interval);
+        loop {
+            tokio::select! {
+                _ = ticker.tick() => {}
+                _ = self.wakeup.notified() => {}
+            }
+
+            let replica_ids: Vec<ReplicationSetId> =
+                self.queues.lock().await.keys().cloned().collect();
+            for replica_id in replica_ids {
+                if let Err(err) = self.replay(coord.as_ref(), replica_id).await {
+                    debug!(
+                        "Hinted handoff: replication set {} is not writable yet: {}",
+                        replica_id, err
+                    );
+                }
+            }
+            self.replayed.notify_waiters();
+        }
+    }
+
+    /// Replay the hint files of a replication set, stops at the first write failed by
+    /// a retryable error.
+    pub async fn replay(
+        &self,
+        coord: &dyn Coordinator,
+        replica_id: ReplicationSetId,
+    ) -> CoordinatorResult<()> {
+        self.replay_with(replica_id, |request| self.replay_hint(coord, request))
+            .await
+    }
+
+    async fn replay_with<F, Fut>(
+        &self,
+        replica_id: ReplicationSetId,
+        write: F,
+    ) -> CoordinatorResult<()>
+    where
+        F: Fn(RaftWriteCommand) -> Fut,
+        Fut: Future<Output = Result<(), ReplayError>>,
+    {
+        let _replaying = self.replay_lock.lock().await;
+
+        // New hints are written to a new file, while replaying the sealed ones.
+        let files = match self.queues.lock().await.get_mut(&replica_id) {
+            Some(queue) => queue.seal().await?,
+            None => return Ok(()),
+        };
+
+        for (file_id, path) in files {
+            let mut reader = record_file::Reader::open(&path).await.context(TskvSnafu)?;
+            loop {
+                let data = match reader.read_record().await {
+                    Ok(record) => record.data,
+                    Err(TskvError::Eof) => break,
+                    Err(TskvError::RecordFileHashCheckFailed { .. }) => continue,
+                    Err(err) => {
+                        error!(
+                            "Hinted handoff: failed to read '{}', the rest hints are dropped: {}",
+                            path.display(),
+                            err
+                        );
+                        break;
+                    }
+                };
+                match parse_prost_bytes::<RaftWriteCommand>(&data) {
+                    Ok(request) => match write(request).await {
+                        Ok(()) => {}
+                        Err(ReplayError::Retryable(err)) => return Err(err),
+                        Err(ReplayError::Permanent(err)) => {
+                            self.move_to_dead_letters(replica_id, &data, &err).await?
+                        }
+                    },
+                    Err(err) => {
+                        error!("Hinted handoff: drop an invalid hint: {}", err);
+                        self.metrics.dropped_writes.inc_one();
+                    }
+                }
+            }
+
+            let mut queues = self.queues.lock().await;
+            if let Some(queue) = queues.get_mut(&replica_id) {
+                let file_size = queue.remove(file_id, &path)?;
+                self.metrics.size.dec(file_size);
+                if queue.is_empty() {
+                    let _ = std::fs::remove_dir_all(&queue.dir);
+                    queues.remove(&replica_id);
+                }
+            }
+        }
+
+        info!(
+            "Hinted handoff: hints of replication set {} are replayed",
+            replica_id
+        );
+        Ok(())
+    }
+
+    async fn replay_hint(
+        &self,
+        coord: &dyn Coordinator,
+        request: RaftWriteCommand,
+    ) -> Result<(), ReplayError> {
+        let replica =
+            match get_replica_all_info(coord.meta_manager(), &request.tenant, request.replica_id)
+                .await
+            {
+                Ok(info) => info.replica_set,
+                Err(
+                    err @ (CoordinatorError::TenantNotFound { .. }
+                    | CoordinatorError::ReplicationSetNotFound { .. }),
+                ) => return Err(ReplayError::Permanent(err)),
+                // meta may be unavailable for now
+                Err(err) => return Err(ReplayError::Retryable(err)),
+            };
+
+        coord.write_replica_by_raft(replica, request, None).await?;
+        self.metrics.replayed_writes.inc_one();
+
+        Ok(())
+    }
+
+    /// Append a hint failed by a permanent error to the dead letters of its replication set.
+    async fn move_to_dead_letters(
+        &self,
+        replica_id: ReplicationSetId,
+        data: &[u8],
+        err: &CoordinatorError,
+    ) -> CoordinatorResult<()> {
+        error!(
+            "Hinted handoff: move a hint of replication set {} to the dead letters: {}",
+            replica_id, err
+        );
+        let mut dead_letters = self.dead_letters.lock().await;
+        let queue = match dead_letters.entry(replica_id) {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => {
+                let dir = self.dir.join(DEAD_LETTERS_DIR).join(replica_id.to_string());
+                let queue = if dir.exists() {
+                    HintQueue::load(dir)?
+                } else {
+                    HintQueue::new(dir)
+                };
+                entry.insert(queue)
+            }
+        };
+        queue.append(data).await?;
+        self.metrics.dead_letters.inc_one();
+
+        Ok(())
+    }
+}
+
+/// Hint files of a replication set.
+struct HintQueue {
+    dir: PathBuf,
+    /// file id -> file size
+    files: BTreeMap<u64, u64>,
+    /// The file being written, it's the last one in `files`.
+    writer: Option<(u64, record_file::Writer)>,
+}
+
+impl HintQueue {
+    fn new(dir: PathBuf) -> Self {
+        Self {
+            dir,
+            files: BTreeMap::new(),
+            writer: None,
+        }
+    }
+
+    fn load(dir: PathBuf) -> CoordinatorResult<Self> {
+        let mut files = BTreeMap::new();
+        for entry in std::fs::read_dir(&dir).context(IOErrorsSnafu)? {
+            let entry = entry.context(IOErrorsSnafu)?;
+            let file_name = entry.file_name().to_string_lossy().to_string();
+            if let Ok(file_id) = file_utils::get_hinted_handoff_file_id(&file_name) {
+                let file_size = entry.metadata().context(IOErrorsSnafu)?.len();
+                files.insert(file_id, file_size);
+            }
+        }
+
+        Ok(Self {
+            dir,
+            files,
+            writer: None,
+        })
+    }
+
+    fn size(&self) -> u64 {
+        self.files.values().sum()
+    }
+
+    fn is_empty(&self) -> bool {
+        self.files.is_empty() && self.writer.is_none()
+    }
+
+    async fn append(&mut self, data: &[u8]) -> CoordinatorResult<()> {
+        if self.writer.is_none() {
+            std::fs::create_dir_all(&self.dir).context(IOErrorsSnafu)?;
+            let file_id = self.files.keys().last().map(|id| id + 1).unwrap_or(1);
+            let path = file_utils::make_hinted_handoff_file(&self.dir, file_id);
+            let writer = record_file::Writer::open(
+                &path,
+                RecordDataType::HintedHandoff,
+                HINT_FILE_BUFFER_SIZE,
+            )
+            .await
+            .context(TskvSnafu)?;
+            self.writer = Some((file_id, writer));
+        }
+
+        if let Some((file_id, writer)) = self.writer.as_mut() {
+            writer
+                .write_record(
+                    RecordDataVersion::V1 as u8,
+                    RecordDataType::HintedHandoff as u8,
+                    [data].as_slice(),
+                )
+                .await
+                .context(TskvSnafu)?;
+            writer.sync().await.context(TskvSnafu)?;
+            self.files.insert(*file_id, writer.file_size());
+        }
+
+        Ok(())
+    }
+
+    /// Close the file being written, returns all files to replay.
+    async fn seal(&mut self) -> CoordinatorResult<Vec<(u64, PathBuf)>> {
+        if let Some((_, mut writer)) = self.writer.take() {
+            writer.close().await.context(TskvSnafu)?;
+        }
+
+        Ok(self
+            .files
+            .keys()
+            .map(|id| (*id, file_utils::make_hinted_handoff_file(&self.dir, *id)))
+            .collect())
+    }
+
+    /// Remove a replayed file, returns its size.
+    fn remove(&mut self, file_id: u64, path: &Path) -> CoordinatorResult<u64> {
+        std::fs::remove_file(path).context(IOErrorsSnafu)?;
+        Ok(self.files.remove(&file_id).unwrap_or(0))
+    }
+}
+
+struct HintedHandoffMetrics {
+    size: U64Gauge,
+    writes: U64Counter,
+    replayed_writes: U64Counter,
+    dropped_writes: U64Counter,
+    dead_letters: U64Counter,
+}
+
+impl HintedHandoffMetrics {
+    fn new(register: &MetricsRegister, node_id: NodeId) -> Self {
+        let node_id = node_id.to_string();
+        let labels = [("node_id", node_id.as_str())];
+
+        let metric: Metric<U64Gauge> =
+            register.metric("hinted_handoff_size", "total size of hints in bytes");
+        let size = metric.recorder(labels);
+
+        let metric: Metric<U64Counter> =
+            register.metric("hinted_handoff_writes", "writes handed off to hints");
+        let writes = metric.recorder(labels);
+
+        let metric: Metric<U64Counter> =
+            register.metric("hinted_handoff_replayed_writes", "hints replayed");
+        let replayed_writes = metric.recorder(labels);
+
+        let metric: Metric<U64Counter> = register.metric(
+            "hinted_handoff_dropped_writes",
+            "writes dropped as the hints are full or invalid",
+        );
+        let dropped_writes = metric.recorder(labels);
+
+        let metric: Metric<U64Counter> = register.metric(
+            "hinted_handoff_dead_letters",
+            "hints moved to the dead letters as they failed by permanent errors",
+        );
+        let dead_letters = metric.recorder(labels);
+
+        Self {
+            size,
+            writes,
+            replayed_writes,
+            dropped_writes,
+            dead_letters,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::Path;
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    use config::tskv::ClusterConfig;
+    use metrics::metric_register::MetricsRegister;
+    use protos::kv_service::{raft_write_command, DropTableRequest, RaftWriteCommand};
+    use protos::models_helper::to_prost_bytes;
+    use tokio::sync::Mutex;
+
+    use super::{HintedHandoff, ReplayError};
+    use crate::errors::{CommonSnafu, CoordinatorError, CoordinatorResult};
+
+    fn request(replica_id: u32, table: &str) -> RaftWriteCommand {
+        RaftWriteCommand {
+            tenant: "cnosdb".to_string(),
+            db_name: "public".to_string(),
+            replica_id,
+            command: Some(raft_write_command::Command::DropTable(DropTableRequest {
+                db: "public".to_string(),
+                table: table.to_string(),
+            })),
+        }
+    }
+
+    async fn open(dir: &Path, max_size: u64) -> HintedHandoff {
+        let config = ClusterConfig {
+            hinted_handoff_path: dir.to_string_lossy().to_string(),
+            hinted_handoff_max_size: max_size,
+            ..Default::default()
+        };
+        HintedHandoff::open(&config, 1, &MetricsRegister::default())
+            .await
+            .unwrap()
+    }
+
+    /// Replay hints of the replication set, returns the replayed hints, the write of
+    /// table `fail_table` fails by a retryable error, and the write of table `invalid_table`
+    /// fails by a permanent error.
+    async fn replay_or_drop(
+        hinted_handoff: &HintedHandoff,
+        replica_id: u32,
+        fail_table: &str,
+        invalid_table: &str,
+    ) -> (CoordinatorResult<()>, Vec<RaftWriteCommand>) {
+        let replayed = Arc::new(Mutex::new(vec![]));
+        let result = hinted_handoff
+            .replay_with(replica_id, |request| {
+                let replayed = replayed.clone();
+                async move {
+                    if let Some(raft_write_command::Command::DropTable(req)) = &request.command {
+                        if req.table == fail_table {
+                            return Err(ReplayError::Retryable(CoordinatorError::NoValidReplica {
+                                id: replica_id,
+                            }));
+                        }
+                        if req.table == invalid_table {
+                            return Err(ReplayError::Permanent(
+                                CommonSnafu {
+                                    msg: "table not found".to_string(),
+                                }
+                                .build(),
+                            ));
+                        }
+                    }
+                    replayed.lock().await.push(request);
+                    Ok(())
+                }
+            })
+            .await;
+        let replayed = replayed.lock().await.clone();
+        (result, replayed)
+    }
+
+    async fn replay(
+        hinted_handoff: &HintedHandoff,
+        replica_id: u32,
+        fail_table: &str,
+    ) -> (CoordinatorResult<()>, Vec<RaftWriteCommand>) {
+        replay_or_drop(hinted_handoff, replica_id, fail_table, "").await
+    }
+
+    #[test]
+    fn test_can_handoff() {
+        assert!(HintedHandoff::can_handoff(
+            &CoordinatorError::NodeUnreachable {
+                node_id: 1,
+                error: "connection refused".to_string(),
+            }
+        ));
+        assert!(HintedHandoff::can_handoff(
+            &CoordinatorError::NoValidReplica { id: 1 }
+        ));
+        assert!(!HintedHandoff::can_handoff(
+            &CoordinatorError::PreExecution {
+                error: "stale read".to_string(),
+            }
+        ));
+        assert!(!HintedHandoff::can_handoff(
+            &CoordinatorError::TenantNotFound {
+                name: "cnosdb".to_string(),
+            }
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_replay_to_dead_letters() {
+        let dir = Path::new("/tmp/test/coordinator/hinted_handoff/dead_letters");
+        let _ = std::fs::remove_dir_all(dir);
+        let hinted_handoff = open(dir, 1024 * 1024).await;
+
+        let requests: Vec<_> = ["t1", "t2", "t3"].iter().map(|t| request(1, t)).collect();
+        for request in requests.iter() {
+            hinted_handoff.append(request).await.unwrap();
+        }
+
+        // The hint failed by a permanent error doesn't block the hints after it.
+        let (result, replayed) = replay_or_drop(&hinted_handoff, 1, "", "t2").await;
+        assert!(result.is_ok());
+        assert_eq!(replayed, vec![requests[0].clone(), requests[2].clone()]);
+        assert!(!hinted_handoff.has_hints(1).await);
+        assert!(dir.join("dead_letters").join("1").exists());
+
+        // Dead letters are not replayed after reopening.
+        let hinted_handoff = open(dir, 1024 * 1024).await;
+        assert!(!hinted_handoff.has_hints(1).await);
+    }
+
+    #[tokio::test]
+    async fn test_wait_replayed() {
+        let dir = Path::new("/tmp/test/coordinator/hinted_handoff/wait_replayed");
+        let _ = std::fs::remove_dir_all(dir);
+        let hinted_handoff = Arc::new(open(dir, 1024 * 1024).await);
+        assert!(
+            hinted_handoff
+                .wait_replayed(1, Duration::from_millis(10))
+                .await
+        );
+
+        hinted_handoff.append(&request(1, "t1")).await.unwrap();
+        assert!(
+            !hinted_handoff
+                .wait_replayed(1, Duration::from_millis(10))
+                .await
+        );
+
+        let replayer = {
+            let hinted_handoff = hinted_handoff.clone();
+            tokio::spawn(async move {
+                hinted_handoff.wakeup.notified().await;
+                let (result, _) = replay(&hinted_handoff, 1, "").await;
+                assert!(result.is_ok());
+                hinted_handoff.replayed.notify_waiters();
+            })
+        };
+        assert!(
+            hinted_handoff
+                .wait_replayed(1, Duration::from_secs(10))
+                .await
+        );
+        replayer.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_append_and_replay() {
+        let dir = Path::new("/tmp/test/coordinator/hinted_handoff/replay");
+        let _ = std::fs::remove_dir_all(dir);
+        let hinted_handoff = open(dir, 1024 * 1024).await;
+
+        let requests: Vec<_> = ["t1", "t2", "t3"].iter().map(|t| request(1, t)).collect();
+        for request in requests.iter() {
+            hinted_handoff.append(request).await.unwrap();
+        }
+        hinted_handoff.append(&request(2, "t1")).await.unwrap();
+        assert!(hinted_handoff.has_hints(1).await);
+        assert!(hinted_handoff.has_hints(2).await);
+
+        // Replaying stops at the failed write, the replayed hints are kept.
+        let (result, replayed) = replay(&hinted_handoff, 1, "t2").await;
+        assert!(result.is_err());
+        assert_eq!(replayed, requests[..1]);
+        assert!(hinted_handoff.has_hints(1).await);
+
+        // Hints are replayed in the order they were appended.
+        let (result, replayed) = replay(&hinted_handoff, 1, "").await;
+        assert!(result.is_ok());
+        assert_eq!(replayed, requests);
+        assert!(!hinted_handoff.has_hints(1).await);
+        assert!(!dir.join("1").exists());
+        assert!(hinted_handoff.has_hints(2).await);
+    }
+
+    #[tokio::test]
+    async fn test_append_over_max_size() {
+        let dir = Path::new("/tmp/test/coordinator/hinted_handoff/max_size");
+        let _ = std::fs::remove_dir_all(dir);
+        let request = request(1, "t1");
+        let hinted_handoff = open(dir, to_prost_bytes(&request).len() as u64).await;
+
+        hinted_handoff.append(&request).await.unwrap();
+        let err = hinted_handoff.append(&request).await.unwrap_err();
+        assert!(matches!(err, CoordinatorError::HintedHandoffFull { .. }));
+
+        let (result, replayed) = replay(&hinted_handoff, 1, "").await;
+        assert!(result.is_ok());
+        assert_eq!(replayed, vec![request.clone()]);
+        hinted_handoff.append(&request).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_reopen() {
+        let dir = Path::new("/tmp/test/coordinator/hinted_handoff/reopen");
+        let _ = std::fs::remove_dir_all(dir);
+        let requests: Vec<_> = ["t1", "t2"].iter().map(|t| request(1, t)).collect();
+        {
+            let hinted_handoff = open(dir, 1024 * 1024).await;
+            for request in requests.iter() {
+                hinted_handoff.append(request).await.unwrap();
+            }
+        }
+
+        let hinted_handoff = open(dir, 1024 * 1024).await;
+        assert!(hinted_handoff.has_hints(1).await);
+        // Hints appended after reopening are replayed after the old ones.
+        hinted_handoff.append(&request(1, "t3")).await.unwrap();
+        let (result, replayed) = replay(&hinted_handoff, 1, "").await;
+        assert!(result.is_ok());
+        assert_eq!(replayed[..2], requests);
+        assert_eq!(replayed[2], request(1, "t3"));
+    }
+}
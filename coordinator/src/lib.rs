@@ -1,5 +1,6 @@
 #![recursion_limit = "256"]
 
+use std::collections::HashMap;
 use std::fmt::Debug;
 use std::pin::Pin;
 use std::sync::Arc;
@@ -8,15 +9,18 @@ use config::tskv::Config;
 use datafusion::arrow::record_batch::RecordBatch;
 use errors::CoordinatorError;
 use futures::Stream;
+use memory_pool::MemoryPoolRef;
 use meta::model::{MetaClientRef, MetaRef};
+use models::consistency_level::ConsistencyLevel;
 use models::meta_data::{
-    NodeId, ReplicaAllInfo, ReplicationSet, ReplicationSetId, VnodeAllInfo, VnodeId,
+    NodeId, NodeInfo, ReplicaAllInfo, ReplicaPlacement, ReplicationSet, ReplicationSetId,
+    VnodeAllInfo, VnodeId, VnodeVersion,
 };
 use models::object_reference::ResolvedTable;
 use models::predicate::domain::{ResolvedPredicate, ResolvedPredicateRef};
 use models::schema::{Precision, TskvTableSchemaRef};
 use protocol_parser::Line;
-use protos::kv_service::{RaftWriteCommand, UpdateSetValue};
+use protos::kv_service::{ConnectionOption, RaftWriteCommand, UpdateSetValue};
 use raft::manager::RaftNodesManager;
 use raft::writer::TskvRaftWriter;
 use snafu::ResultExt;
@@ -24,27 +28,35 @@ use trace::SpanContext;
 use tskv::reader::QueryOption;
 use tskv::EngineRef;
 
-use crate::errors::{CoordinatorResult, MetaSnafu};
+use crate::errors::{CommonSnafu, CoordinatorResult, MetaSnafu, TskvSnafu};
 use crate::service::CoordServiceMetrics;
 
 pub mod errors;
+pub mod hinted_handoff;
 pub mod metrics;
 pub mod raft;
 pub mod reader;
+pub mod rebalance;
 pub mod resource_manager;
 pub mod service;
 pub mod service_mock;
+#[cfg(test)]
+mod test_util;
+pub mod tiering;
 pub mod tskv_executor;
 
 pub type SendableCoordinatorRecordBatchStream =
     Pin<Box<dyn Stream<Item = CoordinatorResult<RecordBatch>> + Send>>;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum ReplicationCmdType {
     /// replica set id, dst nod id
     AddRaftFollower(u32, u64),
     /// vnode id
     RemoveRaftNode(u32),
+    /// vnode id, the vnode is on an unreachable node, it's removed from the raft group
+    /// but not dropped, which is left to a `DropRaftNode` resource task
+    RemoveUnreachableRaftNode(u32),
     /// replica set id
     DestoryRaftGroup(u32),
     /// replica set id, new leader vnode id
@@ -57,6 +69,8 @@ pub trait Coordinator: Send + Sync {
     fn meta_manager(&self) -> MetaRef;
     fn store_engine(&self) -> Option<EngineRef>;
     fn raft_manager(&self) -> Arc<RaftNodesManager>;
+    /// Memory pool of the node, shared by the storage engine and queries.
+    fn memory_pool(&self) -> MemoryPoolRef;
     async fn tenant_meta(&self, tenant: &str) -> Option<MetaClientRef>;
 
     fn tskv_raft_writer(&self, request: RaftWriteCommand) -> TskvRaftWriter;
@@ -80,6 +94,19 @@ pub trait Coordinator: Send + Sync {
         tenant: &str,
         db: &str,
         precision: Precision,
+        consistency: ConsistencyLevel,
+        lines: Vec<Line<'a>>,
+        span_ctx: Option<&SpanContext>,
+    ) -> CoordinatorResult<usize>;
+
+    /// Write lines without checking if the tables are read-only,
+    /// only for the tables written by the system, e.g. the audit log.
+    async fn write_system_lines<'a>(
+        &self,
+        tenant: &str,
+        db: &str,
+        precision: Precision,
+        consistency: ConsistencyLevel,
         lines: Vec<Line<'a>>,
         span_ctx: Option<&SpanContext>,
     ) -> CoordinatorResult<usize>;
@@ -89,6 +116,7 @@ pub trait Coordinator: Send + Sync {
         table_schema: TskvTableSchemaRef,
         record_batch: RecordBatch,
         db_precision: Precision,
+        consistency: ConsistencyLevel,
         span_ctx: Option<&SpanContext>,
     ) -> CoordinatorResult<usize>;
 
@@ -126,6 +154,47 @@ pub trait Coordinator: Send + Sync {
         replica_id: ReplicationSetId,
     ) -> CoordinatorResult<Vec<RecordBatch>>;
 
+    /// Rebuild the series index of a vnode, returns the differences with the old index.
+    async fn rebuild_vnode_index(
+        &self,
+        tenant: &str,
+        vnode_id: VnodeId,
+    ) -> CoordinatorResult<RecordBatch>;
+
+    /// Backup files of a vnode into the location of an object store as the backup
+    /// `backup_id`, returns statistics of the backup.
+    async fn backup_vnode(
+        &self,
+        tenant: &str,
+        vnode_id: VnodeId,
+        location: &str,
+        backup_id: &str,
+        connection_options: Vec<ConnectionOption>,
+        incremental: bool,
+    ) -> CoordinatorResult<RecordBatch>;
+
+    /// Replace files of a vnode with the ones of the backup `backup_id` in the location
+    /// of an object store.
+    async fn restore_vnode(
+        &self,
+        tenant: &str,
+        db_name: &str,
+        vnode_id: VnodeId,
+        location: &str,
+        backup_id: &str,
+        connection_options: Vec<ConnectionOption>,
+    ) -> CoordinatorResult<()>;
+
+    /// Versions of the vnodes of a database stored in a node, used to tell whether
+    /// the data of the vnodes changed.
+    async fn vnode_versions(
+        &self,
+        tenant: &str,
+        db: &str,
+        node_id: NodeId,
+        vnode_ids: Vec<VnodeId>,
+    ) -> CoordinatorResult<Vec<VnodeVersion>>;
+
     fn metrics(&self) -> &Arc<CoordServiceMetrics>;
 
     async fn update_tags_value(
@@ -143,6 +212,31 @@ pub trait TskvLeaderCaller: Send + Sync {
     async fn call(&self, replica: &ReplicationSet, node_id: NodeId) -> CoordinatorResult<Vec<u8>>;
 }
 
+/// Get versions of the vnodes stored in the local engine, vnodes not found are skipped.
+pub async fn get_vnode_versions(
+    engine: &EngineRef,
+    tenant: &str,
+    db: &str,
+    vnode_ids: &[VnodeId],
+) -> CoordinatorResult<Vec<VnodeVersion>> {
+    let mut versions = Vec::with_capacity(vnode_ids.len());
+    for vnode_id in vnode_ids {
+        if let Some(version) = engine
+            .get_db_version(tenant, db, *vnode_id)
+            .await
+            .context(TskvSnafu)?
+        {
+            versions.push(VnodeVersion {
+                vnode_id: *vnode_id,
+                version_number: version.version_number,
+                seq_no: version.max_seq_no(),
+            });
+        }
+    }
+
+    Ok(versions)
+}
+
 pub async fn get_vnode_all_info(
     meta: MetaRef,
     tenant: &str,
@@ -177,6 +271,72 @@ pub async fn get_replica_all_info(
     Ok(replica)
 }
 
+/// Check if a vnode of the replication set can be added on the node by the replica
+/// placement, the vnode `moving` is ignored as it's removed after the new one is added.
+pub fn replica_placement_allows(
+    placement: &ReplicaPlacement,
+    nodes: &HashMap<NodeId, NodeInfo>,
+    replica: &ReplicationSet,
+    moving: Option<VnodeId>,
+    node_id: NodeId,
+) -> bool {
+    let node = match nodes.get(&node_id) {
+        Some(node) => node,
+        None => return false,
+    };
+    let peers: Vec<NodeInfo> = replica
+        .vnodes
+        .iter()
+        .filter(|v| Some(v.id) != moving)
+        .filter_map(|v| nodes.get(&v.node_id).cloned())
+        .collect();
+
+    placement.allows(node, &peers)
+}
+
+/// Refuse to add a vnode of the replication set on the node,
+/// if it breaks the replica placement of the database.
+pub async fn check_replica_placement(
+    meta: MetaRef,
+    tenant: &str,
+    replica_id: ReplicationSetId,
+    node_id: NodeId,
+    moving: Option<VnodeId>,
+) -> CoordinatorResult<()> {
+    let replica = get_replica_all_info(meta.clone(), tenant, replica_id).await?;
+    let placement = meta
+        .tenant_meta(tenant)
+        .await
+        .ok_or_else(|| CoordinatorError::TenantNotFound {
+            name: tenant.to_owned(),
+        })?
+        .get_db_schema(&replica.db_name)
+        .context(MetaSnafu)?
+        .and_then(|schema| *schema.config.replica_placement());
+    let placement = match placement {
+        Some(placement) => placement,
+        None => return Ok(()),
+    };
+
+    let nodes: HashMap<NodeId, NodeInfo> = meta
+        .data_nodes()
+        .await
+        .into_iter()
+        .map(|n| (n.id, n))
+        .collect();
+    if !replica_placement_allows(&placement, &nodes, &replica.replica_set, moving, node_id) {
+        return Err(CommonSnafu {
+            msg: format!(
+                "Can not place a replica of replication set {} on node {}: replica placement '{}' of database {} needs replicas in distinct failure domains",
+                replica_id, node_id, placement, replica.db_name
+            ),
+        }
+        .build());
+    }
+
+    Ok(())
+}
+
 pub async fn update_replication_set(
     meta: MetaRef,
     tenant: &str,
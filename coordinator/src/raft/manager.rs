@@ -27,7 +27,7 @@ use crate::errors::{
     RaftGroupSnafu, RaftNodeNotFoundSnafu, ReplicatSnafu, TskvSnafu,
 };
 use crate::tskv_executor::{TskvAdminRequest, TskvLeaderExecutor};
-use crate::{get_replica_all_info, update_replication_set};
+use crate::{get_replica_all_info, get_vnode_all_info, update_replication_set};
 
 pub struct RaftNodesManager {
     meta: MetaRef,
@@ -83,6 +83,34 @@ impl RaftNodesManager {
         }
     }
 
+    /// Make sure a read on the replica of the group on this node is linearizable,
+    /// or lags at most `max_lag` if it is set.
+    pub async fn linearizable_read(
+        &self,
+        replica_id: ReplicationSetId,
+        vnode_id: VnodeId,
+        max_lag: Option<Duration>,
+    ) -> CoordinatorResult<()> {
+        let raft_node = self
+            .raft_nodes
+            .read()
+            .await
+            .get_node(replica_id)
+            .context(ReplicatSnafu)?
+            .ok_or_else(|| {
+                RaftNodeNotFoundSnafu {
+                    vnode_id,
+                    replica_id,
+                }
+                .build()
+            })?;
+
+        raft_node
+            .linearizable_read(max_lag)
+            .await
+            .context(ReplicatSnafu)
+    }
+
     async fn register_raft_metrics(
         &self,
         tenant: &str,
@@ -231,6 +259,45 @@ impl RaftNodesManager {
         Ok(())
     }
 
+    /// Replace the storage of a vnode, e.g. restore it from a backup. The raft node of
+    /// the vnode holds the storage, so it's closed before replacing and opened again
+    /// on the new storage after that. Writes to the vnode must be rejected until done.
+    pub async fn replace_vnode_storage<F>(
+        &self,
+        tenant: &str,
+        db_name: &str,
+        vnode_id: VnodeId,
+        replace: F,
+    ) -> CoordinatorResult<()>
+    where
+        F: std::future::Future<Output = CoordinatorResult<()>>,
+    {
+        let vnode = get_vnode_all_info(self.meta.clone(), tenant, vnode_id).await?;
+        let group_id = vnode.repl_set_id;
+        let closed = self
+            .raft_nodes
+            .write()
+            .await
+            .close(group_id)
+            .await
+            .context(ReplicatSnafu)?;
+        if closed.is_some() {
+            info!("closed raft node {group_id}.{vnode_id} to replace the storage");
+        }
+
+        let result = replace.await;
+
+        if closed.is_some() {
+            let node = self
+                .open_raft_node(tenant, db_name, vnode_id, group_id)
+                .await?;
+            self.raft_nodes.write().await.add_node(node);
+            info!("reopened raft node {group_id}.{vnode_id}");
+        }
+
+        result
+    }
+
     async fn build_replica_group(
         &self,
         tenant: &str,
@@ -534,12 +601,18 @@ impl RaftNodesManager {
         Ok(())
     }
 
+    /// Remove the vnode from its raft group and drop it on its node, the vnode is removed
+    /// from meta only after it's dropped, so a failed removal can be retried.
+    ///
+    /// If `keep_unreachable` is set, the vnode is on an unreachable node, it's removed from
+    /// meta without being dropped, dropping it is left to a `DropRaftNode` resource task.
     pub async fn remove_node_from_group(
         &self,
         tenant: &str,
         db_name: &str,
         vnode_id: VnodeId,
         replica_id: ReplicationSetId,
+        keep_unreachable: bool,
     ) -> CoordinatorResult<()> {
         let all_info = get_replica_all_info(self.meta.clone(), tenant, replica_id).await?;
         let replica = all_info.replica_set.clone();
@@ -561,6 +634,11 @@ impl RaftNodesManager {
             if vnode.node_id == self.node_id() {
                 self.exec_drop_raft_node(tenant, db_name, vnode.id, replica.id)
                     .await?;
+            } else if keep_unreachable {
+                info!(
+                    "Keep raft node {}.{} on unreachable node {}",
+                    replica.id, vnode.id, vnode.node_id
+                );
             } else {
                 self.drop_remote_raft_node(tenant, db_name, &vnode, replica.id)
                     .await?;
@@ -683,6 +761,7 @@ impl RaftNodesManager {
             vnode_store.clone(),
             storage,
             self.config.service.grpc_enable_gzip,
+            self.config.cluster.snapshot_chunk_size,
         );
 
         let engine = Arc::new(RwLock::new(engine));
@@ -708,7 +787,7 @@ impl RaftNodesManager {
         }
     }
 
-    async fn drop_remote_raft_node(
+    pub async fn drop_remote_raft_node(
         &self,
         tenant: &str,
         db_name: &str,
@@ -10,25 +10,26 @@ use protos::{tskv_service_time_out_client, DEFAULT_GRPC_SERVER_MESSAGE_LEN};
 use replication::errors::{
     IOErrSnafu, MsgInvalidSnafu, ReplicationError, ReplicationResult, SnapshotErrSnafu,
 };
+use replication::file_transfer::{FileChunk, FileChunkWriter};
 use replication::{ApplyContext, ApplyStorage, EngineMetrics};
 use snafu::ResultExt;
-use tokio::io::AsyncWriteExt;
 use tokio_stream::StreamExt;
 use tonic::transport::Channel;
 use tower::timeout::Timeout;
-use tracing::{error, info};
-use tskv::file_system::async_filesystem::LocalFileSystem;
-use tskv::file_system::FileSystem;
+use tracing::{error, info, warn};
 use tskv::kv_option::DATA_PATH;
 use tskv::vnode_store::VnodeStorage;
 use tskv::VnodeSnapshot;
 
-use crate::errors::{CommonSnafu, CoordinatorResult, IOErrorsSnafu, MetaSnafu};
+use crate::errors::{CommonSnafu, CoordinatorResult, MetaSnafu, ReplicatSnafu};
 
 pub mod manager;
 mod metrics;
 pub mod writer;
 
+/// Times to retry downloading a file of a snapshot without any progress.
+const DOWNLOAD_FILE_RETRIES: u64 = 3;
+
 pub struct TskvEngineStorage {
     tenant: String,
     db_name: String,
@@ -37,6 +38,7 @@ pub struct TskvEngineStorage {
     vnode: VnodeStorage,
     storage: tskv::EngineRef,
     grpc_enable_gzip: bool,
+    snapshot_chunk_size: u64,
 }
 
 impl TskvEngineStorage {
@@ -48,6 +50,7 @@ impl TskvEngineStorage {
         vnode: VnodeStorage,
         storage: tskv::EngineRef,
         grpc_enable_gzip: bool,
+        snapshot_chunk_size: u64,
     ) -> Self {
         Self {
             meta,
@@ -57,6 +60,7 @@ impl TskvEngineStorage {
             tenant: tenant.to_owned(),
             db_name: db_name.to_owned(),
             grpc_enable_gzip,
+            snapshot_chunk_size,
         }
     }
 
@@ -77,16 +81,11 @@ impl TskvEngineStorage {
             self.grpc_enable_gzip,
         );
 
+        // Files already downloaded are kept if downloading fails, so that the next
+        // attempt to install the snapshot resumes from them.
         info!("download snapshot to path: {:?}", dir);
-        if let Err(err) = self
-            .download_snapshot_files(dir, snapshot, &mut client)
-            .await
-        {
-            tokio::fs::remove_dir_all(&dir)
-                .await
-                .context(IOErrorsSnafu)?;
-            return Err(err);
-        }
+        self.download_snapshot_files(dir, snapshot, &mut client)
+            .await?;
 
         info!("success download snapshot all files");
 
@@ -115,51 +114,85 @@ impl TskvEngineStorage {
                 src_filename, filename, snapshot.node_id
             );
 
-            Self::download_file(&src_filename, &filename, client).await?;
-            let filename = filename.to_string_lossy().to_string();
-            let length = LocalFileSystem::get_file_length(filename);
-            if info.file_size != length {
-                return Err(CommonSnafu {
-                    msg: format!(
-                        "download file length not match {} -> {}",
-                        info.file_size, length
-                    ),
-                }
-                .build());
-            }
+            self.download_file(&src_filename, &filename, info.file_size, client)
+                .await?;
         }
 
         Ok(())
     }
 
+    /// Download a file as checksummed chunks, an interrupted download is retried
+    /// from the end of the received data.
     async fn download_file(
+        &self,
         download: &str,
         filename: &Path,
+        file_size: u64,
         client: &mut TskvServiceClient<Timeout<Channel>>,
     ) -> CoordinatorResult<()> {
-        if let Some(dir) = filename.parent() {
-            tokio::fs::create_dir_all(dir)
+        let mut writer = FileChunkWriter::open(filename, file_size)
+            .await
+            .context(ReplicatSnafu)?;
+
+        let mut retries = 0;
+        while !writer.is_finished() {
+            let offset = writer.offset();
+            match self
+                .download_file_chunks(download, &mut writer, client)
                 .await
-                .context(IOErrorsSnafu)?;
+            {
+                Ok(()) if writer.offset() < file_size => {
+                    return Err(CommonSnafu {
+                        msg: format!(
+                            "download file {} ends at {}, expected size {}",
+                            download,
+                            writer.offset(),
+                            file_size
+                        ),
+                    }
+                    .build());
+                }
+                Ok(()) => {}
+                Err(err) => {
+                    // Retries are reset once the download makes progress.
+                    if writer.offset() > offset {
+                        retries = 0;
+                    }
+                    retries += 1;
+                    if retries > DOWNLOAD_FILE_RETRIES {
+                        return Err(err);
+                    }
+                    warn!(
+                        "download file {} failed at {}, retry: {}",
+                        download,
+                        writer.offset(),
+                        err
+                    );
+                    tokio::time::sleep(Duration::from_secs(retries)).await;
+                }
+            }
         }
 
-        let mut file = tokio::fs::OpenOptions::new()
-            .create(true)
-            .truncate(true)
-            .read(true)
-            .write(true)
-            .open(filename)
-            .await
-            .context(IOErrorsSnafu)?;
+        writer.finish().await.context(ReplicatSnafu)
+    }
 
+    async fn download_file_chunks(
+        &self,
+        download: &str,
+        writer: &mut FileChunkWriter,
+        client: &mut TskvServiceClient<Timeout<Channel>>,
+    ) -> CoordinatorResult<()> {
         let request = tonic::Request::new(DownloadFileRequest {
             filename: download.to_string(),
+            offset: writer.offset(),
+            chunk_size: self.snapshot_chunk_size,
         });
         let mut resp_stream = client.download_file(request).await?.into_inner();
         while let Some(received) = resp_stream.next().await {
             let received = received?;
             let data = crate::errors::decode_grpc_response(received)?;
-            file.write_all(&data).await.context(IOErrorsSnafu)?;
+            let chunk = FileChunk::decode(&data).context(ReplicatSnafu)?;
+            writer.write_chunk(&chunk).await.context(ReplicatSnafu)?;
         }
 
         Ok(())
@@ -239,11 +272,13 @@ impl ApplyStorage for TskvEngineStorage {
         let snapshot = bincode::deserialize::<VnodeSnapshot>(data)
             .map_err(|e| MsgInvalidSnafu { msg: e.to_string() }.build())?;
         let opt = self.storage.get_storage_options();
-        let snapshot_name = format!(
-            "snap_{}_{}_{}_{}",
-            snapshot.node_id, snapshot.vnode_id, snapshot.last_seq_no, snapshot.create_time
-        );
+        // Files in a snapshot are immutable, files downloaded for an older snapshot
+        // from the same node are reused.
+        let snapshot_name = format!("snap_{}_{}", snapshot.node_id, snapshot.vnode_id);
         let download_dir = opt.path().join(snapshot_name);
+        remove_stale_snapshot_dirs(&opt.path(), &download_dir, snapshot.vnode_id)
+            .await
+            .context(IOErrSnafu)?;
 
         self.download_snapshot(&download_dir, &snapshot)
             .await
@@ -281,3 +316,25 @@ impl ApplyStorage for TskvEngineStorage {
         Ok(self.vnode.metrics().await)
     }
 }
+
+/// Remove directories of the vnode's snapshots that are downloaded from other nodes.
+async fn remove_stale_snapshot_dirs(
+    dir: &Path,
+    keep: &Path,
+    vnode_id: VnodeId,
+) -> std::io::Result<()> {
+    let vnode_id = vnode_id.to_string();
+    let mut entries = tokio::fs::read_dir(dir).await?;
+    while let Some(entry) = entries.next_entry().await? {
+        let path = entry.path();
+        let name = entry.file_name().to_string_lossy().to_string();
+        let mut parts = name.split('_');
+        let is_vnode_snapshot =
+            parts.next() == Some("snap") && parts.nth(1) == Some(vnode_id.as_str());
+        if is_vnode_snapshot && path != keep && path.is_dir() {
+            tokio::fs::remove_dir_all(&path).await?;
+        }
+    }
+
+    Ok(())
+}
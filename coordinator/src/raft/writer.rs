@@ -58,7 +58,8 @@ impl TskvRaftWriter {
 
     async fn write_to_remote(&self, leader_id: u64) -> CoordinatorResult<()> {
         let channel = self.meta.get_node_conn(leader_id).await.map_err(|error| {
-            CoordinatorError::PreExecution {
+            CoordinatorError::NodeUnreachable {
+                node_id: leader_id,
                 error: error.to_string(),
             }
         })?;
@@ -71,7 +72,11 @@ impl TskvRaftWriter {
 
         let cmd = tonic::Request::new(self.request.clone());
         let begin_time = models::utils::now_timestamp_millis();
-        let response = client.raft_write(cmd).await?.into_inner();
+        let response = client
+            .raft_write(cmd)
+            .await
+            .map_err(|status| CoordinatorError::from_node_status(leader_id, status))?
+            .into_inner();
 
         let use_time = models::utils::now_timestamp_millis() - begin_time;
         if use_time > 200 {
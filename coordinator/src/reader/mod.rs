@@ -12,10 +12,13 @@ use futures::future::BoxFuture;
 use futures::{ready, FutureExt, Stream, StreamExt, TryFutureExt};
 use meta::model::MetaRef;
 use metrics::count::U64Counter;
-use models::meta_data::{VnodeId, VnodeInfo, VnodeStatus};
+use models::meta_data::{NodeId, VnodeId, VnodeInfo, VnodeStatus};
 use snafu::ResultExt;
+use trace::span_ext::SpanExt;
+use trace::{Span, SpanContext};
 use tracing::warn;
 use tskv::reader::QueryOption;
+use tskv::TskvError;
 
 use crate::errors::{CommonSnafu, CoordinatorError, CoordinatorResult, MetaSnafu};
 use crate::service::CoordServiceMetrics;
@@ -26,6 +29,8 @@ pub type VnodeOpenFuture =
     BoxFuture<'static, CoordinatorResult<SendableCoordinatorRecordBatchStream>>;
 /// A fallible future that checks the vnode query operation is available
 pub type CheckFuture = BoxFuture<'static, CoordinatorResult<()>>;
+/// Schedules the repair of replicas on a node that can't be connected
+pub type ReplicaRepairer = Arc<dyn Fn(NodeId) + Send + Sync>;
 
 /// Generic API for connect a vnode and reading to a stream of [`RecordBatch`]
 pub trait VnodeOpener: Unpin {
@@ -33,15 +38,51 @@ pub trait VnodeOpener: Unpin {
     fn open(&self, vnode: &VnodeInfo, option: &QueryOption) -> CoordinatorResult<VnodeOpenFuture>;
 }
 
+/// Why the scan of a vnode failed because of its replica
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ReplicaFailure {
+    /// The node of the replica can't be connected
+    Unreachable,
+    /// Files of the replica are broken
+    Broken,
+}
+
+impl ReplicaFailure {
+    /// Failures to read the local replica, e.g. of `linearizable_read`, are not failures of
+    /// the replica, the node is not scheduled to be repaired for them.
+    fn of(err: &CoordinatorError) -> Option<Self> {
+        match err {
+            CoordinatorError::NodeUnreachable { .. }
+            | CoordinatorError::TskvError {
+                source: TskvError::Network { .. },
+            } => Some(Self::Unreachable),
+            _ if TskvError::vnode_broken_code(err.error_code().code()) => Some(Self::Broken),
+            _ => None,
+        }
+    }
+}
+
+/// Scan a vnode split, failing over to another replica of the replication set
+/// when the replica is unreachable or its files are broken. Broken replicas are
+/// marked broken, replicas on unreachable nodes are scheduled to be repaired.
+///
+/// Rows of a replica can't be matched with rows of another one, so the scan is only
+/// resumed on another replica if no batch of the split has been returned yet.
+/// Once a batch has been returned, the error is returned and fails the query.
 pub struct CheckedCoordinatorRecordBatchStream<O: VnodeOpener> {
     opener: O,
     meta: MetaRef,
     tenant: Arc<String>,
     vnode: VnodeInfo,
     option: QueryOption,
+    repairer: ReplicaRepairer,
     state: StreamState,
+    /// Whether any batch of the split has been returned
+    emitted: bool,
+    span_ctx: Option<SpanContext>,
 
     coord_data_out: U64Counter,
+    coord_scan_failovers: U64Counter,
 }
 
 impl<O: VnodeOpener> CheckedCoordinatorRecordBatchStream<O> {
@@ -50,27 +91,68 @@ impl<O: VnodeOpener> CheckedCoordinatorRecordBatchStream<O> {
         opener: O,
         meta: MetaRef,
         checker: CheckFuture,
+        repairer: ReplicaRepairer,
         metrics: &CoordServiceMetrics,
+        span_ctx: Option<&SpanContext>,
     ) -> Self {
         let tenant: Arc<String> = option.table_schema.tenant.clone().into();
         let db = option.table_schema.db.as_str();
 
         let coord_data_out = metrics.coord_data_out(tenant.as_str(), db);
+        let coord_scan_failovers = metrics.coord_scan_failovers(tenant.as_str(), db);
         metrics.coord_queries(tenant.as_str(), db).inc_one();
         Self {
             option,
             opener,
             meta,
             tenant,
+            repairer,
             vnode: VnodeInfo::default(),
             state: StreamState::Check(checker),
+            emitted: false,
+            span_ctx: span_ctx.cloned(),
             coord_data_out,
+            coord_scan_failovers,
+        }
+    }
+
+    /// Resume the scan on the next replica, returns the error if there is none
+    /// or batches of the failed replica have been returned.
+    fn failover(&mut self, err: CoordinatorError) -> Result<(), CoordinatorError> {
+        if self.emitted {
+            return Err(err);
         }
+        let vnode = match self.option.split.pop_front() {
+            Some(vnode) => vnode,
+            None => return Err(err),
+        };
+
+        warn!(
+            "failover reader try to read another vnode: {:?}, failed vnode: {}, error: {}",
+            vnode, self.vnode.id, err
+        );
+        self.coord_scan_failovers.inc_one();
+        let mut span = Span::from_context(
+            format!("failover vnode ({} -> {})", self.vnode.id, vnode.id),
+            self.span_ctx.as_ref(),
+        );
+        span.add_properties(|| {
+            [
+                ("failed_node_id", self.vnode.node_id.to_string()),
+                ("node_id", vnode.node_id.to_string()),
+                ("error", err.to_string()),
+            ]
+        });
+
+        self.vnode = vnode;
+        self.state = StreamState::Idle;
+        Ok(())
     }
 
     fn poll_inner(&mut self, cx: &mut Context<'_>) -> Poll<Option<CoordinatorResult<RecordBatch>>> {
         loop {
             match &mut self.state {
+                StreamState::Done => return Poll::Ready(None),
                 StreamState::Check(checker) => {
                     // TODO record time used
                     match ready!(checker.try_poll_unpin(cx)) {
@@ -101,19 +183,7 @@ impl<O: VnodeOpener> CheckedCoordinatorRecordBatchStream<O> {
                         Ok(stream) => {
                             self.state = StreamState::Scan(stream, ScanState::Scan);
                         }
-                        Err(err) => {
-                            if let CoordinatorError::PreExecution { ref error } = err {
-                                if let Some(vnode) = self.option.split.pop_front() {
-                                    warn!("failover reader try to read another vnode: {:?}, error: {}", vnode, error);
-                                    self.vnode = vnode;
-                                    self.state = StreamState::Idle;
-                                } else {
-                                    return Poll::Ready(Some(Err(err)));
-                                }
-                            } else {
-                                return Poll::Ready(Some(Err(err)));
-                            }
-                        }
+                        Err(err) => self.handle_replica_error(err)?,
                     };
                 }
                 StreamState::Scan(stream, state) => match state {
@@ -137,25 +207,7 @@ impl<O: VnodeOpener> CheckedCoordinatorRecordBatchStream<O> {
                                     ScanState::CheckLimiter(batch, Box::pin(future)),
                                 );
                             }
-                            Err(err) => {
-                                if tskv::TskvError::vnode_broken_code(err.error_code().code()) {
-                                    let id = self.vnode.id;
-                                    let meta = self.meta.clone();
-                                    let tenant = self.option.tenant_name();
-
-                                    trace::warn!("updated vnode {} status broken", id);
-                                    meta.try_change_local_vnode_status(
-                                        tenant,
-                                        id,
-                                        VnodeStatus::Broken,
-                                    );
-                                    let future = change_vnode_to_broken(tenant.into(), id, meta);
-                                    self.state =
-                                        StreamState::UpdateVNodeBroken(Box::pin(future), err);
-                                } else {
-                                    return Poll::Ready(Some(Err(err)));
-                                }
-                            }
+                            Err(err) => self.handle_replica_error(err)?,
                         },
                     },
                     ScanState::CheckLimiter(b, c) => {
@@ -163,6 +215,7 @@ impl<O: VnodeOpener> CheckedCoordinatorRecordBatchStream<O> {
                             Ok(_) => {
                                 let batch = b.clone();
                                 let _ = mem::replace(state, ScanState::Scan);
+                                self.emitted = true;
                                 Poll::Ready(Some(Ok(batch)))
                             }
                             Err(e) => Poll::Ready(Some(Err(e))),
@@ -171,17 +224,42 @@ impl<O: VnodeOpener> CheckedCoordinatorRecordBatchStream<O> {
                 },
                 StreamState::UpdateVNodeBroken(c, err) => {
                     let _ = ready!(c.try_poll_unpin(cx));
-                    return Poll::Ready(Some(Err(mem::replace(
+                    let err = mem::replace(
                         err,
                         CommonSnafu {
                             msg: "default err to mem::replace".to_string(),
                         }
                         .build(),
-                    ))));
+                    );
+                    self.failover(err)?;
                 }
             }
         }
     }
+
+    /// Fail over to another replica on errors of the replica, a broken replica
+    /// is marked broken first so that it's no longer read and gets repaired,
+    /// the repair of replicas on an unreachable node is scheduled.
+    fn handle_replica_error(&mut self, err: CoordinatorError) -> Result<(), CoordinatorError> {
+        match ReplicaFailure::of(&err) {
+            Some(ReplicaFailure::Broken) => {
+                let id = self.vnode.id;
+                let meta = self.meta.clone();
+                let tenant = self.option.tenant_name();
+
+                trace::warn!("updated vnode {} status broken", id);
+                meta.try_change_local_vnode_status(tenant, id, VnodeStatus::Broken);
+                let future = change_vnode_to_broken(tenant.into(), id, meta);
+                self.state = StreamState::UpdateVNodeBroken(Box::pin(future), err);
+                Ok(())
+            }
+            Some(ReplicaFailure::Unreachable) => {
+                (self.repairer)(self.vnode.node_id);
+                self.failover(err)
+            }
+            None => Err(err),
+        }
+    }
 }
 
 impl<O: VnodeOpener> Stream for CheckedCoordinatorRecordBatchStream<O> {
@@ -189,9 +267,13 @@ impl<O: VnodeOpener> Stream for CheckedCoordinatorRecordBatchStream<O> {
 
     fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
         let poll = self.poll_inner(cx);
-        if let Poll::Ready(Some(Ok(batch))) = &poll {
-            self.coord_data_out
-                .inc(batch.get_array_memory_size() as u64)
+        match &poll {
+            Poll::Ready(Some(Ok(batch))) => self
+                .coord_data_out
+                .inc(batch.get_array_memory_size() as u64),
+            // the failed futures and streams are not polled again
+            Poll::Ready(Some(Err(_))) => self.state = StreamState::Done,
+            _ => {}
         }
         poll
     }
@@ -233,6 +315,8 @@ pub async fn change_vnode_to_broken(
 
 enum StreamState {
     Check(CheckFuture),
+    /// The scan failed
+    Done,
     Idle,
     Open(VnodeOpenFuture),
     Scan(SendableCoordinatorRecordBatchStream, ScanState),
@@ -243,3 +327,210 @@ enum ScanState {
     Scan,
     CheckLimiter(RecordBatch, CheckFuture),
 }
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use datafusion::arrow::array::Int64Array;
+    use datafusion::arrow::datatypes::{DataType, Field, Schema, TimeUnit};
+    use meta::model::meta_admin::AdminMeta;
+    use metrics::metric_register::MetricsRegister;
+    use models::meta_data::ReplicationSet;
+    use models::predicate::domain::{ColumnDomains, ResolvedPredicate, TimeRanges};
+    use models::predicate::PlacedSplit;
+    use models::schema::{TableColumn, TskvTableSchema};
+    use tskv::error::{CommonSnafu as TskvCommonSnafu, ReadTsmSnafu};
+
+    use super::*;
+
+    /// Fails to connect to the vnode on node 1, files of the vnode on node 3 are broken,
+    /// those of the vnode on node 4 are found broken after a batch is read.
+    /// Returns one batch of the vnode id for the others.
+    struct MockOpener {
+        opened: Arc<Mutex<Vec<VnodeId>>>,
+    }
+
+    fn broken() -> CoordinatorError {
+        CoordinatorError::TskvError {
+            source: ReadTsmSnafu {
+                reason: "crc mismatch".to_string(),
+            }
+            .build(),
+        }
+    }
+
+    impl VnodeOpener for MockOpener {
+        fn open(
+            &self,
+            vnode: &VnodeInfo,
+            option: &QueryOption,
+        ) -> CoordinatorResult<VnodeOpenFuture> {
+            self.opened.lock().unwrap().push(vnode.id);
+            let vnode = vnode.clone();
+            let schema = option.df_schema.clone();
+            Ok(Box::pin(async move {
+                if vnode.node_id == 1 {
+                    return Err(CoordinatorError::NodeUnreachable {
+                        node_id: vnode.node_id,
+                        error: "connection refused".to_string(),
+                    });
+                }
+                let batch = RecordBatch::try_new(
+                    schema,
+                    vec![Arc::new(Int64Array::from(vec![vnode.id as i64]))],
+                )
+                .unwrap();
+                let results = match vnode.node_id {
+                    3 => vec![Err(broken())],
+                    4 => vec![Ok(batch), Err(broken())],
+                    _ => vec![Ok(batch)],
+                };
+                let stream: SendableCoordinatorRecordBatchStream =
+                    Box::pin(futures::stream::iter(results));
+                Ok(stream)
+            }))
+        }
+    }
+
+    struct ScanResult {
+        vnode_ids: Vec<CoordinatorResult<i64>>,
+        opened: Vec<VnodeId>,
+        repaired: Vec<NodeId>,
+    }
+
+    /// Scan the replicas `(vnode_id, node_id)` of a replication set
+    async fn scan(replicas: &[(VnodeId, NodeId)]) -> ScanResult {
+        let predicate =
+            ResolvedPredicate::new(Arc::new(TimeRanges::all()), ColumnDomains::all(), None)
+                .unwrap();
+        let vnodes = replicas
+            .iter()
+            .map(|(id, node_id)| VnodeInfo::new(*id, *node_id))
+            .collect();
+        let repl_set = ReplicationSet::new(1, 1, 1, vnodes);
+        let split = PlacedSplit::new(0, Arc::new(predicate), None, repl_set);
+        let table_schema = TskvTableSchema::new(
+            "cnosdb".to_string(),
+            "public".to_string(),
+            "air".to_string(),
+            vec![TableColumn::new_time_column(1, TimeUnit::Nanosecond)],
+        );
+        let df_schema = Arc::new(Schema::new(vec![Field::new(
+            "vnode_id",
+            DataType::Int64,
+            false,
+        )]));
+        let option = QueryOption::new(1024, split, None, df_schema, Arc::new(table_schema));
+
+        let opened = Arc::new(Mutex::new(vec![]));
+        let repaired = Arc::new(Mutex::new(vec![]));
+        let repairer: ReplicaRepairer = {
+            let repaired = repaired.clone();
+            Arc::new(move |node_id| repaired.lock().unwrap().push(node_id))
+        };
+        let metrics = CoordServiceMetrics::new(&MetricsRegister::default());
+        let stream = CheckedCoordinatorRecordBatchStream::new(
+            option,
+            MockOpener {
+                opened: opened.clone(),
+            },
+            Arc::new(AdminMeta::mock()),
+            Box::pin(async { Ok(()) }),
+            repairer,
+            &metrics,
+            None,
+        );
+
+        let vnode_ids = stream
+            .map(|batch| {
+                batch.map(|batch| {
+                    let vnode_ids = batch
+                        .column(0)
+                        .as_any()
+                        .downcast_ref::<Int64Array>()
+                        .unwrap();
+                    vnode_ids.value(0)
+                })
+            })
+            .collect::<Vec<_>>()
+            .await;
+        let opened = opened.lock().unwrap().clone();
+        let repaired = repaired.lock().unwrap().clone();
+        ScanResult {
+            vnode_ids,
+            opened,
+            repaired,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_failover_unreachable_replica() {
+        let result = scan(&[(1, 1), (2, 2)]).await;
+        assert_eq!(result.vnode_ids.len(), 1);
+        assert_eq!(result.vnode_ids[0].as_ref().unwrap(), &2);
+        assert_eq!(result.opened, vec![1, 2]);
+        assert_eq!(result.repaired, vec![1]);
+    }
+
+    #[tokio::test]
+    async fn test_failover_broken_replica() {
+        // the broken replica is marked broken instead of repairing its node
+        let result = scan(&[(3, 3), (2, 2)]).await;
+        assert_eq!(result.vnode_ids.len(), 1);
+        assert_eq!(result.vnode_ids[0].as_ref().unwrap(), &2);
+        assert_eq!(result.opened, vec![3, 2]);
+        assert!(result.repaired.is_empty());
+
+        // no replica left
+        let result = scan(&[(3, 3)]).await;
+        assert_eq!(result.vnode_ids.len(), 1);
+        assert!(result.vnode_ids[0].is_err());
+    }
+
+    #[tokio::test]
+    async fn test_no_failover_after_batch_returned() {
+        let result = scan(&[(4, 4), (2, 2)]).await;
+        assert_eq!(result.vnode_ids.len(), 2);
+        assert_eq!(result.vnode_ids[0].as_ref().unwrap(), &4);
+        let err = result.vnode_ids[1].as_ref().unwrap_err();
+        assert!(TskvError::vnode_broken_code(err.error_code().code()));
+        // the other replica is not read
+        assert_eq!(result.opened, vec![4]);
+    }
+
+    #[test]
+    fn test_replica_failure() {
+        let unreachable = CoordinatorError::NodeUnreachable {
+            node_id: 1,
+            error: "connection refused".to_string(),
+        };
+        assert_eq!(
+            ReplicaFailure::of(&unreachable),
+            Some(ReplicaFailure::Unreachable)
+        );
+        let network = CoordinatorError::TskvError {
+            source: tonic::Status::unavailable("connection reset").into(),
+        };
+        assert_eq!(
+            ReplicaFailure::of(&network),
+            Some(ReplicaFailure::Unreachable)
+        );
+
+        // the local replica failed to catch up with the leader
+        let not_ready = CoordinatorError::PreExecution {
+            error: "read index timeout".to_string(),
+        };
+        assert_eq!(ReplicaFailure::of(&not_ready), None);
+
+        assert_eq!(ReplicaFailure::of(&broken()), Some(ReplicaFailure::Broken));
+
+        let other = CoordinatorError::TskvError {
+            source: TskvCommonSnafu {
+                reason: "other".to_string(),
+            }
+            .build(),
+        };
+        assert_eq!(ReplicaFailure::of(&other), None);
+    }
+}
@@ -2,6 +2,7 @@ use std::sync::Arc;
 
 use config::tskv::QueryConfig;
 use futures::TryStreamExt;
+use memory_pool::MemoryPoolRef;
 use meta::model::MetaRef;
 use models::meta_data::VnodeInfo;
 use protos::{tskv_service_time_out_client, DEFAULT_GRPC_SERVER_MESSAGE_LEN};
@@ -10,11 +11,13 @@ use tokio::runtime::Runtime;
 use trace::http::http_ctx::grpc_append_trace_context;
 use trace::span_ext::SpanExt;
 use trace::{Span, SpanContext};
+use tskv::reader::pushdown::PLAN_NOT_PUSHED_HEADER;
 use tskv::reader::table_scan::LocalTskvTableScanStream;
-use tskv::reader::QueryOption;
+use tskv::reader::{QueryOption, SendableTskvRecordBatchStream};
 use tskv::EngineRef;
 
 use crate::errors::{CommonSnafu, CoordinatorError, CoordinatorResult, ModelsSnafu, TskvSnafu};
+use crate::raft::manager::RaftNodesManager;
 use crate::reader::deserialize::TonicRecordBatchDecoder;
 use crate::reader::{VnodeOpenFuture, VnodeOpener};
 use crate::SendableCoordinatorRecordBatchStream;
@@ -25,6 +28,8 @@ pub struct TemporaryTableScanOpener {
     kv_inst: Option<EngineRef>,
     runtime: Arc<Runtime>,
     meta: MetaRef,
+    raft_manager: Arc<RaftNodesManager>,
+    memory_pool: MemoryPoolRef,
     span_ctx: Option<SpanContext>,
     grpc_enable_gzip: bool,
 }
@@ -35,6 +40,8 @@ impl TemporaryTableScanOpener {
         kv_inst: Option<EngineRef>,
         runtime: Arc<Runtime>,
         meta: MetaRef,
+        raft_manager: Arc<RaftNodesManager>,
+        memory_pool: MemoryPoolRef,
         span_ctx: Option<&SpanContext>,
         grpc_enable_gzip: bool,
     ) -> Self {
@@ -43,6 +50,8 @@ impl TemporaryTableScanOpener {
             kv_inst,
             runtime,
             meta,
+            raft_manager,
+            memory_pool,
             span_ctx: span_ctx.cloned(),
             grpc_enable_gzip,
         }
@@ -58,6 +67,8 @@ impl VnodeOpener for TemporaryTableScanOpener {
         let runtime = self.runtime.clone();
         let option = option.clone();
         let meta = self.meta.clone();
+        let raft_manager = self.raft_manager.clone();
+        let memory_pool = self.memory_pool.clone();
         let config = self.config.clone();
         let span_ctx = self.span_ctx;
         let grpc_enable_gzip = self.grpc_enable_gzip;
@@ -67,21 +78,42 @@ impl VnodeOpener for TemporaryTableScanOpener {
             if node_id == curren_nodet_id {
                 // 路由到进程内的引擎
                 let kv_inst = kv_inst.ok_or(CoordinatorError::KvInstanceNotFound { node_id })?;
-                let stream = LocalTskvTableScanStream::new(
-                    vnode_id,
-                    option,
-                    kv_inst,
-                    runtime,
-                    Span::from_context(
-                        format!("LocalTskvTableScanStream ({vnode_id})"),
-                        span_ctx.as_ref(),
-                    ),
-                )
+                raft_manager
+                    .linearizable_read(
+                        option.split.replica_id(),
+                        vnode_id,
+                        option.stale_read_max_lag,
+                    )
+                    .await
+                    .map_err(|err| CoordinatorError::PreExecution {
+                        error: err.to_string(),
+                    })?;
+                let pushed_plan = option.pushed_plan.clone();
+                let (df_schema, batch_size) = (option.df_schema.clone(), option.batch_size);
+                let stream: SendableTskvRecordBatchStream =
+                    Box::pin(LocalTskvTableScanStream::new(
+                        vnode_id,
+                        option,
+                        kv_inst,
+                        runtime,
+                        Span::from_context(
+                            format!("LocalTskvTableScanStream ({vnode_id})"),
+                            span_ctx.as_ref(),
+                        ),
+                    ));
+                let stream = match pushed_plan {
+                    Some(plan) => plan
+                        .execute(stream, df_schema, batch_size, memory_pool)
+                        .context(TskvSnafu)?,
+                    None => stream,
+                }
                 .map_err(|e| TskvSnafu.into_error(e));
 
                 Ok(Box::pin(stream) as SendableCoordinatorRecordBatchStream)
             } else {
                 // 路由到远程的引擎
+                let pushed_plan = option.pushed_plan.clone();
+                let (df_schema, batch_size) = (option.df_schema.clone(), option.batch_size);
                 let mut request = {
                     let vnode_ids = vec![vnode_id];
                     let req = option
@@ -99,9 +131,10 @@ impl VnodeOpener for TemporaryTableScanOpener {
                     },
                 )?;
 
-                let resp_stream = {
+                let response = {
                     let channel = meta.get_node_conn(node_id).await.map_err(|error| {
-                        CoordinatorError::PreExecution {
+                        CoordinatorError::NodeUnreachable {
+                            node_id,
                             error: error.to_string(),
                         }
                     })?;
@@ -111,11 +144,25 @@ impl VnodeOpener for TemporaryTableScanOpener {
                         DEFAULT_GRPC_SERVER_MESSAGE_LEN,
                         grpc_enable_gzip,
                     );
-                    client.query_record_batch(request).await?.into_inner()
+                    client
+                        .query_record_batch(request)
+                        .await
+                        .map_err(|status| CoordinatorError::from_node_status(node_id, status))?
                 };
 
-                Ok(Box::pin(TonicRecordBatchDecoder::new(resp_stream))
-                    as SendableCoordinatorRecordBatchStream)
+                let plan_not_pushed = response.metadata().contains_key(PLAN_NOT_PUSHED_HEADER);
+                let stream = TonicRecordBatchDecoder::new(response.into_inner());
+                match pushed_plan {
+                    // The node scanned raw rows, execute the pushed plan here.
+                    Some(plan) if plan_not_pushed => {
+                        let stream = plan
+                            .execute(stream, df_schema, batch_size, memory_pool)
+                            .context(TskvSnafu)?
+                            .map_err(|e| TskvSnafu.into_error(e));
+                        Ok(Box::pin(stream) as SendableCoordinatorRecordBatchStream)
+                    }
+                    _ => Ok(Box::pin(stream) as SendableCoordinatorRecordBatchStream),
+                }
             }
         };
 
@@ -1,3 +1,5 @@
+use std::sync::Arc;
+
 use config::tskv::QueryConfig;
 use futures::TryStreamExt;
 use meta::model::MetaRef;
@@ -12,6 +14,7 @@ use tskv::reader::QueryOption;
 use tskv::EngineRef;
 
 use crate::errors::{CommonSnafu, CoordinatorError, CoordinatorResult, ModelsSnafu, TskvSnafu};
+use crate::raft::manager::RaftNodesManager;
 use crate::reader::deserialize::TonicRecordBatchDecoder;
 use crate::reader::{VnodeOpenFuture, VnodeOpener};
 use crate::SendableCoordinatorRecordBatchStream;
@@ -20,6 +23,7 @@ pub struct TemporaryTagScanOpener {
     config: QueryConfig,
     kv_inst: Option<EngineRef>,
     meta: MetaRef,
+    raft_manager: Arc<RaftNodesManager>,
     span_ctx: Option<SpanContext>,
     grpc_enable_gzip: bool,
 }
@@ -29,6 +33,7 @@ impl TemporaryTagScanOpener {
         config: QueryConfig,
         kv_inst: Option<EngineRef>,
         meta: MetaRef,
+        raft_manager: Arc<RaftNodesManager>,
         span_ctx: Option<&SpanContext>,
         grpc_enable_gzip: bool,
     ) -> Self {
@@ -36,6 +41,7 @@ impl TemporaryTagScanOpener {
             config,
             kv_inst,
             meta,
+            raft_manager,
             span_ctx: span_ctx.cloned(),
             grpc_enable_gzip,
         }
@@ -50,6 +56,7 @@ impl VnodeOpener for TemporaryTagScanOpener {
         let kv_inst = self.kv_inst.clone();
         let option = option.clone();
         let admin_meta = self.meta.clone();
+        let raft_manager = self.raft_manager.clone();
         let config = self.config.clone();
         let span_ctx = self.span_ctx;
         let grpc_enable_gzip = self.grpc_enable_gzip;
@@ -59,6 +66,16 @@ impl VnodeOpener for TemporaryTagScanOpener {
             if node_id == curren_nodet_id {
                 // 路由到进程内的引擎
                 let kv_inst = kv_inst.ok_or(CoordinatorError::KvInstanceNotFound { node_id })?;
+                raft_manager
+                    .linearizable_read(
+                        option.split.replica_id(),
+                        vnode_id,
+                        option.stale_read_max_lag,
+                    )
+                    .await
+                    .map_err(|err| CoordinatorError::PreExecution {
+                        error: err.to_string(),
+                    })?;
                 // TODO U64Counter
                 let stream = LocalTskvTagScanStream::new(
                     vnode_id,
@@ -92,7 +109,8 @@ impl VnodeOpener for TemporaryTagScanOpener {
 
                 let resp_stream = {
                     let channel = admin_meta.get_node_conn(node_id).await.map_err(|error| {
-                        CoordinatorError::PreExecution {
+                        CoordinatorError::NodeUnreachable {
+                            node_id,
                             error: error.to_string(),
                         }
                     })?;
@@ -102,7 +120,11 @@ impl VnodeOpener for TemporaryTagScanOpener {
                         DEFAULT_GRPC_SERVER_MESSAGE_LEN,
                         grpc_enable_gzip,
                     );
-                    client.tag_scan(request).await?.into_inner()
+                    client
+                        .tag_scan(request)
+                        .await
+                        .map_err(|status| CoordinatorError::from_node_status(node_id, status))?
+                        .into_inner()
                 };
 
                 Ok(Box::pin(TonicRecordBatchDecoder::new(resp_stream))
@@ -0,0 +1,565 @@
+//! Rebalance vnodes between data nodes.
+//!
+//! New data nodes only receive vnodes of newly created buckets, a rebalance plans
+//! vnode moves from the data nodes with the most vnodes to the ones with the least
+//! of the same tier, and executes them one by one as a resource task, by adding a
+//! raft learner on the new node and removing the old member.
+//!
+//! Vnodes which break the replica placement of their database are moved first, so a
+//! rebalance repairs the placement set by ALTER DATABASE on existing buckets.
+
+use std::cmp::Reverse;
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+
+use meta::error::MetaError;
+use meta::model::MetaRef;
+use models::meta_data::{
+    NodeId, NodeInfo, NodeMetrics, NodeTier, ReplicaPlacement, ReplicationSet, VnodeId, VnodeMove,
+    VnodeStatus,
+};
+use models::oid::Identifier;
+use models::schema::{ResourceInfo, ResourceOperator, ResourceStatus};
+use snafu::ResultExt;
+use tracing::{info, warn};
+
+use crate::errors::*;
+use crate::resource_manager::ResourceManager;
+use crate::tiering::MIGRATE_TO_COLD_TASK_NAME;
+use crate::{
+    get_replica_all_info, get_vnode_all_info, replica_placement_allows, Coordinator,
+    ReplicationCmdType,
+};
+
+pub const REBALANCE_TASK_NAME: &str = "rebalance";
+
+/// Plan the vnode moves, and start moving them in background if it's not a dry run.
+pub async fn start_rebalance(
+    coord: Arc<dyn Coordinator>,
+    dry_run: bool,
+) -> CoordinatorResult<Vec<VnodeMove>> {
+    let meta = coord.meta_manager();
+    let last = meta
+        .read_resourceinfo_by_name(REBALANCE_TASK_NAME)
+        .await
+        .context(MetaSnafu)?;
+    if let Some(resourceinfo) = &last {
+        if is_in_progress(resourceinfo.get_status()) {
+            return Err(CommonSnafu {
+                msg: format!(
+                    "A rebalance is already {}, check it by SHOW REBALANCE",
+                    resourceinfo.get_status()
+                ),
+            }
+            .build());
+        }
+    }
+    refuse_if_migrating_to_cold(meta.clone()).await?;
+
+    let moves = plan_rebalance(meta.clone()).await?;
+    if dry_run || moves.is_empty() {
+        return Ok(moves);
+    }
+
+    let resourceinfo = ResourceInfo::new(
+        (0, "".to_string()),
+        REBALANCE_TASK_NAME.to_string(),
+        ResourceOperator::Rebalance(moves.clone()),
+        &None,
+        coord.node_id(),
+    );
+    match last {
+        Some(last) => update_rebalance_task(meta, &last, resourceinfo.clone()).await?,
+        None => meta
+            .write_resourceinfo(resourceinfo.get_name(), resourceinfo.clone())
+            .await
+            .context(MetaSnafu)?,
+    }
+    ResourceManager::spawn_resource_task(coord, resourceinfo);
+
+    Ok(moves)
+}
+
+/// Stop the rebalance before moving the next vnode, it's paused by the running
+/// rebalance after the vnode being moved is moved.
+pub async fn pause_rebalance(coord: Arc<dyn Coordinator>) -> CoordinatorResult<()> {
+    let meta = coord.meta_manager();
+    let resourceinfo = read_rebalance_task(meta.clone()).await?;
+    if !matches!(
+        resourceinfo.get_status(),
+        ResourceStatus::Schedule | ResourceStatus::Executing
+    ) {
+        return Err(CommonSnafu {
+            msg: format!(
+                "Can not pause a rebalance which is {}",
+                resourceinfo.get_status()
+            ),
+        }
+        .build());
+    }
+
+    let mut pausing = resourceinfo.clone();
+    pausing.set_status(ResourceStatus::Pausing);
+    pausing.set_is_new_add(false);
+    update_rebalance_task(meta, &resourceinfo, pausing).await?;
+    info!("Rebalance is pausing");
+
+    Ok(())
+}
+
+/// Continue moving the vnodes which are not moved yet. Only a paused rebalance is
+/// resumed, so the rebalance is never moving vnodes in two places.
+pub async fn resume_rebalance(coord: Arc<dyn Coordinator>) -> CoordinatorResult<()> {
+    let meta = coord.meta_manager();
+    let resourceinfo = read_rebalance_task(meta.clone()).await?;
+    if *resourceinfo.get_status() != ResourceStatus::Paused {
+        return Err(CommonSnafu {
+            msg: format!(
+                "Can not resume a rebalance which is {}",
+                resourceinfo.get_status()
+            ),
+        }
+        .build());
+    }
+    refuse_if_migrating_to_cold(meta.clone()).await?;
+
+    let mut resumed = resourceinfo.clone();
+    resumed.set_status(ResourceStatus::Executing);
+    resumed.set_execute_node_id(coord.node_id());
+    resumed.set_is_new_add(false);
+    update_rebalance_task(meta, &resourceinfo, resumed.clone()).await?;
+    info!("Rebalance is resumed");
+    ResourceManager::spawn_resource_task(coord, resumed);
+
+    Ok(())
+}
+
+/// Returns the latest rebalance task and the number of moved vnodes.
+pub async fn rebalance_status(
+    coord: Arc<dyn Coordinator>,
+) -> CoordinatorResult<Option<(ResourceInfo, usize)>> {
+    let meta = coord.meta_manager();
+    let resourceinfo = match meta
+        .read_resourceinfo_by_name(REBALANCE_TASK_NAME)
+        .await
+        .context(MetaSnafu)?
+    {
+        Some(resourceinfo) => resourceinfo,
+        None => return Ok(None),
+    };
+
+    let mut moved = 0;
+    if let ResourceOperator::Rebalance(moves) = resourceinfo.get_operator() {
+        for vnode_move in moves {
+            if is_moved(meta.clone(), vnode_move).await {
+                moved += 1;
+            }
+        }
+    }
+
+    Ok(Some((resourceinfo, moved)))
+}
+
+/// A vnode is moved if it's removed from the replication set.
+pub async fn is_moved(meta: MetaRef, vnode_move: &VnodeMove) -> bool {
+    get_vnode_all_info(meta, &vnode_move.tenant, vnode_move.vnode_id)
+        .await
+        .is_err()
+}
+
+/// Add a vnode on the new node, if it's not added by a failed try before,
+/// then remove the old vnode.
+pub async fn move_vnode(
+    coord: Arc<dyn Coordinator>,
+    vnode_move: &VnodeMove,
+) -> CoordinatorResult<()> {
+    let replica = get_replica_all_info(
+        coord.meta_manager(),
+        &vnode_move.tenant,
+        vnode_move.replica_id,
+    )
+    .await?
+    .replica_set;
+    if replica.by_node_id(vnode_move.to_node_id).is_none() {
+        let cmd_type =
+            ReplicationCmdType::AddRaftFollower(vnode_move.replica_id, vnode_move.to_node_id);
+        coord
+            .replication_manager(&vnode_move.tenant, cmd_type)
+            .await?;
+    }
+
+    let cmd_type = ReplicationCmdType::RemoveRaftNode(vnode_move.vnode_id);
+    coord
+        .replication_manager(&vnode_move.tenant, cmd_type)
+        .await?;
+
+    Ok(())
+}
+
+async fn plan_rebalance(meta: MetaRef) -> CoordinatorResult<Vec<VnodeMove>> {
+    let nodes: HashMap<NodeId, NodeInfo> = meta
+        .data_nodes()
+        .await
+        .into_iter()
+        .map(|n| (n.id, n))
+        .collect();
+    // Decommissioning nodes are left to their own tasks.
+    let nodes_metrics: Vec<NodeMetrics> = meta
+        .data_nodes_metrics()
+        .await
+        .context(MetaSnafu)?
+        .into_iter()
+        .filter(|m| nodes.get(&m.id).is_some_and(|n| !n.decommissioning))
+        .collect();
+
+    let mut replicas = vec![];
+    for tenant in meta.tenants().await.context(MetaSnafu)? {
+        let tenant_meta = match meta.tenant_meta(tenant.name()).await {
+            Some(meta) => meta,
+            None => continue,
+        };
+        for (_, db_info) in tenant_meta.list_databases().context(MetaSnafu)? {
+            let placement = *db_info.schema.config.replica_placement();
+            for bucket in db_info.buckets {
+                for replica in bucket.shard_group {
+                    replicas.push((tenant.name().to_string(), replica, placement));
+                }
+            }
+        }
+    }
+
+    // Vnodes are not moved between hot nodes and cold nodes.
+    let mut moves = vec![];
+    for tier in [NodeTier::Hot, NodeTier::Cold] {
+        let tier_metrics: Vec<NodeMetrics> = nodes_metrics
+            .iter()
+            .filter(|m| nodes.get(&m.id).is_some_and(|n| n.tier == tier))
+            .cloned()
+            .collect();
+        moves.extend(plan_moves(&nodes, &tier_metrics, replicas.clone()));
+    }
+
+    Ok(moves)
+}
+
+/// Move vnodes from the healthy node with the most vnodes to the one with the least,
+/// until their vnode counts differ by at most one. Ties are broken by free disk space.
+/// A vnode is not moved if the move breaks the replica placement of its database,
+/// and vnodes which already break it are moved to repair it before balancing.
+fn plan_moves(
+    nodes: &HashMap<NodeId, NodeInfo>,
+    nodes_metrics: &[NodeMetrics],
+    mut replicas: Vec<(String, ReplicationSet, Option<ReplicaPlacement>)>,
+) -> Vec<VnodeMove> {
+    let disk_free: HashMap<NodeId, u64> = nodes_metrics
+        .iter()
+        .filter(|m| m.is_healthy())
+        .map(|m| (m.id, m.disk_free))
+        .collect();
+    let mut vnode_counts: HashMap<NodeId, usize> = disk_free.keys().map(|id| (*id, 0)).collect();
+    for (_, replica, _) in replicas.iter() {
+        for vnode in replica.vnodes.iter() {
+            if let Some(count) = vnode_counts.get_mut(&vnode.node_id) {
+                *count += 1;
+            }
+        }
+    }
+
+    let mut moves = vec![];
+    // A vnode is moved at most once, its id is changed after moving.
+    let mut moved_vnodes: HashSet<VnodeId> = HashSet::new();
+
+    // Vnodes breaking the replica placement, e.g. placed before the placement is set,
+    // are moved first to the node with the least vnodes which the placement allows.
+    for (tenant, replica, placement) in replicas.iter_mut() {
+        let placement = match placement {
+            Some(placement) => *placement,
+            None => continue,
+        };
+        for i in 0..replica.vnodes.len() {
+            let (vnode_id, src) = (replica.vnodes[i].id, replica.vnodes[i].node_id);
+            if replica.vnodes[i].status != VnodeStatus::Running
+                || !vnode_counts.contains_key(&src)
+                || replica_placement_allows(&placement, nodes, replica, Some(vnode_id), src)
+            {
+                continue;
+            }
+            let dst = vnode_counts
+                .iter()
+                .filter(|(id, _)| {
+                    replica.by_node_id(**id).is_none()
+                        && replica_placement_allows(
+                            &placement,
+                            nodes,
+                            replica,
+                            Some(vnode_id),
+                            **id,
+                        )
+                })
+                .min_by_key(|(id, count)| (**count, Reverse(disk_free[*id]), **id))
+                .map(|(id, _)| *id);
+            let dst = match dst {
+                Some(dst) => dst,
+                None => {
+                    warn!(
+                        "Vnode {} of replication set {} on node {} breaks replica placement '{}', no node can take it",
+                        vnode_id, replica.id, src, placement
+                    );
+                    continue;
+                }
+            };
+
+            replica.vnodes[i].node_id = dst;
+            moved_vnodes.insert(vnode_id);
+            *vnode_counts.entry(src).or_default() -= 1;
+            *vnode_counts.entry(dst).or_default() += 1;
+            moves.push(VnodeMove {
+                tenant: tenant.clone(),
+                replica_id: replica.id,
+                vnode_id,
+                from_node_id: src,
+                to_node_id: dst,
+            });
+        }
+    }
+
+    loop {
+        let src = vnode_counts
+            .iter()
+            .max_by_key(|(id, count)| (**count, Reverse(disk_free[*id]), Reverse(**id)));
+        let dst = vnode_counts
+            .iter()
+            .min_by_key(|(id, count)| (**count, Reverse(disk_free[*id]), **id));
+        let (src, dst) = match (src, dst) {
+            (Some((src, src_count)), Some((dst, dst_count))) if *src_count > *dst_count + 1 => {
+                (*src, *dst)
+            }
+            _ => break,
+        };
+
+        let vnode_move = replicas
+            .iter_mut()
+            .find_map(|(tenant, replica, placement)| {
+                if replica.by_node_id(dst).is_some() {
+                    return None;
+                }
+                let vnode_id = replica
+                    .vnodes
+                    .iter()
+                    .find(|v| {
+                        v.node_id == src
+                            && v.status == VnodeStatus::Running
+                            && !moved_vnodes.contains(&v.id)
+                    })?
+                    .id;
+                if let Some(placement) = placement {
+                    if !replica_placement_allows(placement, nodes, replica, Some(vnode_id), dst) {
+                        return None;
+                    }
+                }
+                let vnode = replica.vnodes.iter_mut().find(|v| v.id == vnode_id)?;
+                vnode.node_id = dst;
+                Some(VnodeMove {
+                    tenant: tenant.clone(),
+                    replica_id: replica.id,
+                    vnode_id: vnode.id,
+                    from_node_id: src,
+                    to_node_id: dst,
+                })
+            });
+        match vnode_move {
+            Some(vnode_move) => {
+                moved_vnodes.insert(vnode_move.vnode_id);
+                *vnode_counts.entry(src).or_default() -= 1;
+                *vnode_counts.entry(dst).or_default() += 1;
+                moves.push(vnode_move);
+            }
+            None => break,
+        }
+    }
+
+    moves
+}
+
+async fn read_rebalance_task(meta: MetaRef) -> CoordinatorResult<ResourceInfo> {
+    meta.read_resourceinfo_by_name(REBALANCE_TASK_NAME)
+        .await
+        .context(MetaSnafu)?
+        .ok_or_else(|| {
+            CommonSnafu {
+                msg: "No rebalance found".to_string(),
+            }
+            .build()
+        })
+}
+
+/// Write the rebalance task only if it's not changed since `current` is read.
+async fn update_rebalance_task(
+    meta: MetaRef,
+    current: &ResourceInfo,
+    resourceinfo: ResourceInfo,
+) -> CoordinatorResult<()> {
+    match meta.update_resourceinfo(current, resourceinfo).await {
+        Err(MetaError::ResourceInfoChanged { .. }) => Err(CommonSnafu {
+            msg: "The rebalance is changed meanwhile, check it by SHOW REBALANCE".to_string(),
+        }
+        .build()),
+        res => res.context(MetaSnafu),
+    }
+}
+
+/// Returns the task of the name if it's moving vnodes, a failed one is moving too,
+/// as it's retried in background.
+pub(crate) async fn moving_task(
+    meta: MetaRef,
+    name: &str,
+) -> CoordinatorResult<Option<ResourceInfo>> {
+    let resourceinfo = meta
+        .read_resourceinfo_by_name(name)
+        .await
+        .context(MetaSnafu)?;
+    Ok(resourceinfo.filter(|r| {
+        matches!(
+            r.get_status(),
+            ResourceStatus::Schedule
+                | ResourceStatus::Executing
+                | ResourceStatus::Pausing
+                | ResourceStatus::Failed
+        )
+    }))
+}
+
+/// Rebalance and cold migration both move vnodes, only one of them is started at a time.
+async fn refuse_if_migrating_to_cold(meta: MetaRef) -> CoordinatorResult<()> {
+    if moving_task(meta, MIGRATE_TO_COLD_TASK_NAME)
+        .await?
+        .is_some()
+    {
+        return Err(CommonSnafu {
+            msg: "Buckets are being migrated to cold nodes, rebalance after it's finished"
+                .to_string(),
+        }
+        .build());
+    }
+    Ok(())
+}
+
+/// A failed rebalance is not in progress, though it's retried in background,
+/// REBALANCE replaces it with a new one, which stops the retrying.
+fn is_in_progress(status: &ResourceStatus) -> bool {
+    matches!(
+        status,
+        ResourceStatus::Schedule
+            | ResourceStatus::Executing
+            | ResourceStatus::Pausing
+            | ResourceStatus::Paused
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashMap;
+
+    use models::meta_data::{NodeTier, ReplicaPlacement, ReplicationSet, VnodeInfo};
+    use models::node_info::NodeStatus;
+
+    use super::plan_moves;
+    use crate::test_util::{node, node_info};
+
+    fn replicas() -> Vec<(String, ReplicationSet)> {
+        (0..4)
+            .map(|i| {
+                let vnodes = vec![VnodeInfo::new(i * 2, 1), VnodeInfo::new(i * 2 + 1, 2)];
+                let replica = ReplicationSet::new(i, 1, i * 2, vnodes);
+                ("cnosdb".to_string(), replica)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_plan_moves() {
+        let replicas = replicas()
+            .into_iter()
+            .map(|(tenant, replica)| (tenant, replica, None))
+            .collect();
+
+        // Node 3 is new, node 4 is unreachable.
+        let mut unreachable = node(4, 1000);
+        unreachable.status = NodeStatus::Unreachable;
+        let nodes = vec![node(1, 100), node(2, 200), node(3, 300), unreachable];
+
+        let moves = plan_moves(&HashMap::new(), &nodes, replicas);
+        assert_eq!(moves.len(), 2);
+        // Node 1 has less free disk space, so it gives vnode first.
+        assert_eq!(moves[0].from_node_id, 1);
+        assert_eq!(moves[1].from_node_id, 2);
+        for vnode_move in moves.iter() {
+            assert_eq!(vnode_move.to_node_id, 3);
+        }
+        // Vnodes of a replication set are not moved to the same node.
+        assert_ne!(moves[0].replica_id, moves[1].replica_id);
+
+        let moves = plan_moves(&HashMap::new(), &nodes[..2], vec![]);
+        assert!(moves.is_empty());
+    }
+
+    #[test]
+    fn test_plan_moves_with_placement() {
+        let placement: ReplicaPlacement = "spread:zone".parse().unwrap();
+        let replicas = replicas()
+            .into_iter()
+            .map(|(tenant, replica)| (tenant, replica, Some(placement)))
+            .collect();
+
+        // Node 3 is new and in the same zone as node 1.
+        let infos = HashMap::from([
+            node_info(1, NodeTier::Hot, "z1"),
+            node_info(2, NodeTier::Hot, "z2"),
+            node_info(3, NodeTier::Hot, "z1"),
+        ]);
+        let nodes = vec![node(1, 100), node(2, 200), node(3, 300)];
+
+        // A vnode on node 2 can't be moved to node 3, the other vnode is in the zone.
+        let moves = plan_moves(&infos, &nodes, replicas);
+        assert_eq!(moves.len(), 1);
+        assert_eq!(moves[0].from_node_id, 1);
+        assert_eq!(moves[0].to_node_id, 3);
+    }
+
+    #[test]
+    fn test_plan_moves_repair_placement() {
+        let placement: ReplicaPlacement = "spread:zone".parse().unwrap();
+        let replicas: Vec<_> = replicas()
+            .into_iter()
+            .map(|(tenant, replica)| (tenant, replica, Some(placement)))
+            .collect();
+
+        // Nodes 1 and 2 are in the same zone, so every replication set breaks the placement.
+        let infos = HashMap::from([
+            node_info(1, NodeTier::Hot, "z1"),
+            node_info(2, NodeTier::Hot, "z1"),
+            node_info(3, NodeTier::Hot, "z2"),
+            node_info(4, NodeTier::Hot, "z3"),
+        ]);
+        let nodes = vec![node(1, 100), node(2, 200), node(3, 300), node(4, 400)];
+
+        // One vnode of each replication set is moved out of zone z1 first, to node 3
+        // or 4 by turns, then the emptied node 1 takes vnodes from node 2.
+        let moves = plan_moves(&infos, &nodes, replicas.clone());
+        assert_eq!(moves.len(), 6);
+        for (i, vnode_move) in moves[..4].iter().enumerate() {
+            assert_eq!(vnode_move.replica_id, i as u32);
+            assert_eq!(vnode_move.from_node_id, 1);
+        }
+        assert_eq!(moves[..4].iter().filter(|m| m.to_node_id == 3).count(), 2);
+        assert_eq!(moves[..4].iter().filter(|m| m.to_node_id == 4).count(), 2);
+        for vnode_move in moves[4..].iter() {
+            assert_eq!(vnode_move.from_node_id, 2);
+            assert_eq!(vnode_move.to_node_id, 1);
+        }
+
+        // Nowhere to repair it, the violations are left as they are.
+        let moves = plan_moves(&infos, &nodes[..2], replicas);
+        assert!(moves.is_empty());
+    }
+}
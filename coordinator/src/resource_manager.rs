@@ -1,8 +1,20 @@
+use std::cmp::Reverse;
+use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
 use std::time::Duration;
 
-use models::meta_data::ReplicationSet;
-use models::schema::{ResourceInfo, ResourceOperator, ResourceStatus, TableSchema};
+use meta::error::MetaError;
+use meta::model::MetaRef;
+use models::meta_data::{
+    NodeId, NodeInfo, NodeMetrics, NodeTier, ReplicationSet, ReplicationSetId, VnodeId, VnodeInfo,
+    VnodeMove,
+};
+use models::node_info::NodeStatus;
+use models::oid::Identifier;
+use models::schema::{
+    DatabaseOptions, Duration as ScheduleDuration, ResourceInfo, ResourceOperator, ResourceStatus,
+    TableSchema,
+};
 use protos::kv_service::{
     raft_write_command, DropColumnRequest, DropTableRequest, RaftWriteCommand, UpdateSetValue,
     UpdateTagsRequest,
@@ -12,7 +24,9 @@ use tokio::time::sleep;
 use tracing::{debug, error, info};
 
 use crate::errors::*;
-use crate::{Coordinator, ReplicationCmdType};
+use crate::rebalance::REBALANCE_TASK_NAME;
+use crate::tiering::MIGRATE_TO_COLD_TASK_NAME;
+use crate::{rebalance, replica_placement_allows, Coordinator, ReplicationCmdType};
 
 #[derive(Clone)]
 pub struct ResourceManager {}
@@ -55,26 +69,85 @@ impl ResourceManager {
                 )
                 .await
             }
+            ResourceOperator::RepairReplicas(node_id) => {
+                ResourceManager::repair_replicas(coord.clone(), &resourceinfo, *node_id).await
+            }
+            ResourceOperator::Rebalance(moves) | ResourceOperator::MigrateToCold(moves) => {
+                ResourceManager::rebalance(coord.clone(), &resourceinfo, moves).await
+            }
+            ResourceOperator::DecommissionNode(node_id) => {
+                ResourceManager::decommission_node(coord.clone(), &resourceinfo, *node_id).await
+            }
+            ResourceOperator::DropRaftNode(tenant_name, db_name, replica_id, vnode) => {
+                ResourceManager::drop_raft_node(
+                    coord.clone(),
+                    tenant_name,
+                    db_name,
+                    *replica_id,
+                    vnode,
+                )
+                .await
+            }
         };
+        if let Ok(false) = operator_result {
+            // the task is paused or replaced, its status is written already
+            return operator_result;
+        }
         resourceinfo.set_is_new_add(false);
         let mut status_comment = (ResourceStatus::Successed, String::default());
         if let Err(coord_err) = &operator_result {
-            status_comment.0 = ResourceStatus::Failed;
+            // Retrying can't help if a replication set lost its quorum.
+            status_comment.0 = match coord_err {
+                CoordinatorError::ReplicaQuorumLost { .. } => ResourceStatus::Fatal,
+                _ => ResourceStatus::Failed,
+            };
             status_comment.1 = coord_err.to_string();
             resourceinfo.set_is_new_add(true);
         }
         resourceinfo.increase_try_count();
         resourceinfo.set_status(status_comment.0);
         resourceinfo.set_comment(&status_comment.1);
-        coord
-            .meta_manager()
-            .write_resourceinfo(resourceinfo.get_name(), resourceinfo.clone())
-            .await
-            .map_err(|err| CoordinatorError::Meta { source: err })?;
+        ResourceManager::write_result(coord.meta_manager(), resourceinfo).await?;
 
         operator_result
     }
 
+    /// Write the result of the task, unless it's replaced by a new task with the same name.
+    /// A failed task which is asked to pause is paused instead of retried.
+    async fn write_result(meta: MetaRef, mut resourceinfo: ResourceInfo) -> CoordinatorResult<()> {
+        loop {
+            let current = match meta
+                .read_resourceinfo_by_name(resourceinfo.get_name())
+                .await
+                .context(MetaSnafu)?
+            {
+                Some(current) => current,
+                None => {
+                    return meta
+                        .write_resourceinfo(resourceinfo.get_name(), resourceinfo)
+                        .await
+                        .context(MetaSnafu);
+                }
+            };
+            if current.get_time() != resourceinfo.get_time() {
+                return Ok(());
+            }
+            if *current.get_status() == ResourceStatus::Pausing
+                && *resourceinfo.get_status() == ResourceStatus::Failed
+            {
+                resourceinfo.set_status(ResourceStatus::Paused);
+                resourceinfo.set_is_new_add(false);
+            }
+            match meta
+                .update_resourceinfo(&current, resourceinfo.clone())
+                .await
+            {
+                Err(MetaError::ResourceInfoChanged { .. }) => continue,
+                res => return res.context(MetaSnafu),
+            }
+        }
+    }
+
     async fn drop_tenant(
         coord: Arc<dyn Coordinator>,
         tenant_name: &str,
@@ -312,6 +385,518 @@ impl ResourceManager {
         Ok(true)
     }
 
+    /// Replace the vnodes on a data node, which has stopped reporting heartbeats
+    /// for `cluster.replica_repair_grace_period`, with new vnodes on healthy nodes.
+    async fn repair_replicas(
+        coord: Arc<dyn Coordinator>,
+        resourceinfo: &ResourceInfo,
+        dead_node_id: NodeId,
+    ) -> CoordinatorResult<bool> {
+        let nodes_metrics = coord
+            .meta_manager()
+            .data_nodes_metrics()
+            .await
+            .context(MetaSnafu)?;
+        if nodes_metrics
+            .iter()
+            .any(|m| m.id == dead_node_id && m.status != NodeStatus::Unreachable)
+        {
+            info!("Node {} is back, skip repairing its replicas", dead_node_id);
+            return Ok(true);
+        }
+
+        ResourceManager::migrate_replicas(coord, resourceinfo, dead_node_id).await?;
+
+        Ok(true)
+    }
+
+    /// Move all vnodes on a decommissioning node to other nodes, then remove it from meta.
+    async fn decommission_node(
+        coord: Arc<dyn Coordinator>,
+        resourceinfo: &ResourceInfo,
+        node_id: NodeId,
+    ) -> CoordinatorResult<bool> {
+        let left = ResourceManager::migrate_replicas(coord.clone(), resourceinfo, node_id).await?;
+        if left > 0 {
+            return Err(CommonSnafu {
+                msg: format!("{} vnodes are still on node {}", left, node_id),
+            }
+            .build());
+        }
+
+        info!("Node {} is decommissioned, remove it from meta", node_id);
+        coord
+            .meta_manager()
+            .remove_data_node(node_id)
+            .await
+            .context(MetaSnafu)?;
+
+        Ok(true)
+    }
+
+    /// Drop a vnode which is removed from its replication set while its node was unreachable,
+    /// the task fails and is retried until the node is back, or removed from the cluster.
+    async fn drop_raft_node(
+        coord: Arc<dyn Coordinator>,
+        tenant_name: &str,
+        db_name: &str,
+        replica_id: ReplicationSetId,
+        vnode: &VnodeInfo,
+    ) -> CoordinatorResult<bool> {
+        let nodes = coord.meta_manager().data_nodes().await;
+        if !nodes.iter().any(|n| n.id == vnode.node_id) {
+            info!(
+                "Node {} is removed, skip dropping raft node {}.{}",
+                vnode.node_id, replica_id, vnode.id
+            );
+            return Ok(true);
+        }
+
+        coord
+            .raft_manager()
+            .drop_remote_raft_node(tenant_name, db_name, vnode, replica_id)
+            .await?;
+
+        Ok(true)
+    }
+
+    /// For each replication set with a vnode on the node, add a new vnode on another healthy
+    /// node of the same tier, allowed by the replica placement of the database, which waits
+    /// for the new raft learner to catch up, then remove the old vnode.
+    /// Returns the number of vnodes left on the node, which were created during migrating.
+    async fn migrate_replicas(
+        coord: Arc<dyn Coordinator>,
+        resourceinfo: &ResourceInfo,
+        node_id: NodeId,
+    ) -> CoordinatorResult<usize> {
+        let candidates = ResourceManager::available_nodes(coord.clone(), node_id).await?;
+        let nodes: HashMap<NodeId, NodeInfo> = coord
+            .meta_manager()
+            .data_nodes()
+            .await
+            .into_iter()
+            .map(|n| (n.id, n))
+            .collect();
+        let tier = nodes.get(&node_id).map(|n| n.tier).unwrap_or_default();
+        let healthy: HashSet<NodeId> = coord
+            .meta_manager()
+            .data_nodes_metrics()
+            .await
+            .context(MetaSnafu)?
+            .into_iter()
+            .filter(|m| m.is_healthy())
+            .map(|m| m.id)
+            .collect();
+
+        // (tenant_name, db_name, replication set, vnode id on the node, options of db)
+        let migrations = ResourceManager::vnodes_on_node(coord.clone(), node_id).await?;
+
+        let mut resourceinfo = resourceinfo.clone();
+        resourceinfo.set_is_new_add(false);
+        let mut placed: HashMap<NodeId, usize> = HashMap::new();
+        let total = migrations.len();
+        for (i, (tenant_name, db_name, replica, vnode_id, options)) in
+            migrations.into_iter().enumerate()
+        {
+            resourceinfo.set_comment(&format!(
+                "migrating replication set {} ({}/{})",
+                replica.id,
+                i + 1,
+                total
+            ));
+            coord
+                .meta_manager()
+                .write_resourceinfo(resourceinfo.get_name(), resourceinfo.clone())
+                .await
+                .context(MetaSnafu)?;
+
+            // Raft membership changes need a quorum, they never finish without it.
+            check_replica_quorum(&replica, &healthy)?;
+
+            let commands = migration_commands(
+                &replica,
+                vnode_id,
+                options.replica_or_default() as usize,
+                &healthy,
+                || {
+                    pick_new_replica_node(
+                        &candidates,
+                        &nodes,
+                        tier,
+                        &replica,
+                        vnode_id,
+                        &options,
+                        &placed,
+                    )
+                },
+            )?;
+            for cmd_type in commands {
+                info!("Migrate replication set {}: {:?}", replica.id, cmd_type);
+                coord
+                    .replication_manager(&tenant_name, cmd_type.clone())
+                    .await?;
+                match cmd_type {
+                    ReplicationCmdType::AddRaftFollower(_, new_node_id) => {
+                        *placed.entry(new_node_id).or_default() += 1;
+                    }
+                    ReplicationCmdType::RemoveUnreachableRaftNode(_) => {
+                        if let Some(vnode) = replica.vnode(vnode_id) {
+                            ResourceManager::add_drop_raft_node_task(
+                                coord.clone(),
+                                &tenant_name,
+                                &db_name,
+                                replica.id,
+                                vnode,
+                            )
+                            .await?;
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        let left = ResourceManager::vnodes_on_node(coord, node_id).await?;
+        Ok(left.len())
+    }
+
+    /// Returns (tenant_name, db_name, replication set, vnode id, options of db)
+    /// of the vnodes on the node.
+    #[allow(clippy::type_complexity)]
+    async fn vnodes_on_node(
+        coord: Arc<dyn Coordinator>,
+        node_id: NodeId,
+    ) -> CoordinatorResult<Vec<(String, String, ReplicationSet, VnodeId, DatabaseOptions)>> {
+        let mut vnodes = vec![];
+        for tenant in coord.meta_manager().tenants().await.context(MetaSnafu)? {
+            let tenant_meta = match coord.tenant_meta(tenant.name()).await {
+                Some(meta) => meta,
+                None => continue,
+            };
+            for (db_name, db_info) in tenant_meta.list_databases().context(MetaSnafu)? {
+                for bucket in db_info.buckets {
+                    for replica in bucket.shard_group {
+                        if let Some(vnode) = replica.by_node_id(node_id) {
+                            vnodes.push((
+                                tenant.name().to_string(),
+                                db_name.clone(),
+                                replica,
+                                vnode.id,
+                                db_info.schema.config.clone(),
+                            ));
+                        }
+                    }
+                }
+            }
+        }
+
+        Ok(vnodes)
+    }
+
+    /// Returns the metrics of healthy and not decommissioning data nodes,
+    /// except the excluded one.
+    pub async fn available_nodes(
+        coord: Arc<dyn Coordinator>,
+        excluded_node_id: NodeId,
+    ) -> CoordinatorResult<Vec<NodeMetrics>> {
+        let meta = coord.meta_manager();
+        let nodes: HashMap<NodeId, NodeInfo> = meta
+            .data_nodes()
+            .await
+            .into_iter()
+            .map(|n| (n.id, n))
+            .collect();
+        let nodes_metrics = meta.data_nodes_metrics().await.context(MetaSnafu)?;
+
+        Ok(nodes_metrics
+            .into_iter()
+            .filter(|m| {
+                m.id != excluded_node_id
+                    && m.is_healthy()
+                    && nodes.get(&m.id).is_some_and(|n| !n.decommissioning)
+            })
+            .collect())
+    }
+
+    /// Move vnodes one by one, with `cluster.rebalance_move_interval` between two moves.
+    /// Returns false if the rebalance is paused or replaced by a new one.
+    async fn rebalance(
+        coord: Arc<dyn Coordinator>,
+        resourceinfo: &ResourceInfo,
+        moves: &[VnodeMove],
+    ) -> CoordinatorResult<bool> {
+        let interval = coord.get_config().cluster.rebalance_move_interval;
+        let meta = coord.meta_manager();
+        let mut throttle = false;
+        for (i, vnode_move) in moves.iter().enumerate() {
+            if rebalance::is_moved(meta.clone(), vnode_move).await {
+                continue;
+            }
+            if throttle {
+                sleep(interval).await;
+            }
+            throttle = true;
+
+            let comment = format!(
+                "moving vnode {} from node {} to node {} ({}/{})",
+                vnode_move.vnode_id,
+                vnode_move.from_node_id,
+                vnode_move.to_node_id,
+                i + 1,
+                moves.len()
+            );
+            if !ResourceManager::continue_moving(meta.clone(), resourceinfo, &comment).await? {
+                return Ok(false);
+            }
+            // The earlier one of a rebalance and a migration moves vnodes first,
+            // the later one fails and is retried after it's finished.
+            let other_name = if resourceinfo.get_name() == REBALANCE_TASK_NAME {
+                MIGRATE_TO_COLD_TASK_NAME
+            } else {
+                REBALANCE_TASK_NAME
+            };
+            if let Some(other) = rebalance::moving_task(meta.clone(), other_name).await? {
+                if (other.get_time(), other.get_name())
+                    < (resourceinfo.get_time(), resourceinfo.get_name())
+                {
+                    return Err(CommonSnafu {
+                        msg: format!("waiting for {} to finish", other_name),
+                    }
+                    .build());
+                }
+            }
+
+            info!(
+                "{}: move vnode {} from node {} to node {}",
+                resourceinfo.get_name(),
+                vnode_move.vnode_id,
+                vnode_move.from_node_id,
+                vnode_move.to_node_id
+            );
+            rebalance::move_vnode(coord.clone(), vnode_move).await?;
+        }
+
+        Ok(true)
+    }
+
+    /// Write the comment of a rebalance or migration before moving a vnode, returns false
+    /// if it's paused or replaced. A pausing one is paused here, as no vnode is being moved.
+    async fn continue_moving(
+        meta: MetaRef,
+        resourceinfo: &ResourceInfo,
+        comment: &str,
+    ) -> CoordinatorResult<bool> {
+        loop {
+            let current = match meta
+                .read_resourceinfo_by_name(resourceinfo.get_name())
+                .await
+                .context(MetaSnafu)?
+            {
+                Some(current) => current,
+                None => return Ok(false),
+            };
+            if *current.get_status() == ResourceStatus::Paused
+                || current.get_time() != resourceinfo.get_time()
+            {
+                return Ok(false);
+            }
+
+            let moving = *current.get_status() != ResourceStatus::Pausing;
+            let mut update = current.clone();
+            if moving {
+                update.set_comment(comment);
+            } else {
+                update.set_status(ResourceStatus::Paused);
+                update.set_comment("paused");
+            }
+            update.set_is_new_add(false);
+            match meta.update_resourceinfo(&current, update).await {
+                Ok(()) => return Ok(moving),
+                Err(MetaError::ResourceInfoChanged { .. }) => continue,
+                Err(err) => return Err(err).context(MetaSnafu),
+            }
+        }
+    }
+
+    /// Schedule a task to repair the replicas on an unreachable data node,
+    /// if there isn't one already.
+    pub async fn add_repair_replicas_task(
+        coord: Arc<dyn Coordinator>,
+        node_id: NodeId,
+    ) -> CoordinatorResult<()> {
+        let config = coord.get_config().cluster;
+        if !config.enable_replica_repair {
+            return Ok(());
+        }
+
+        let name = format!("repair_replicas_of_node_{}", node_id);
+        let old_resourceinfo = coord
+            .meta_manager()
+            .read_resourceinfo_by_name(&name)
+            .await
+            .context(MetaSnafu)?;
+        if old_resourceinfo.is_some_and(|info| {
+            *info.get_status() == ResourceStatus::Schedule
+                || *info.get_status() == ResourceStatus::Executing
+                || *info.get_status() == ResourceStatus::Failed
+        }) {
+            return Ok(());
+        }
+
+        let minutes = config.replica_repair_grace_period.as_secs().div_ceil(60);
+        let after = ScheduleDuration::new(&format!("{}m", minutes));
+        let resourceinfo = ResourceInfo::new(
+            (0, "".to_string()),
+            name,
+            ResourceOperator::RepairReplicas(node_id),
+            &after,
+            coord.node_id(),
+        );
+        info!(
+            "Node {} is unreachable, repair its replicas after {} minutes",
+            node_id, minutes
+        );
+        ResourceManager::add_resource_task(coord, resourceinfo).await?;
+
+        Ok(())
+    }
+
+    /// Drop the vnode removed from its replication set on an unreachable node in background.
+    async fn add_drop_raft_node_task(
+        coord: Arc<dyn Coordinator>,
+        tenant_name: &str,
+        db_name: &str,
+        replica_id: ReplicationSetId,
+        vnode: VnodeInfo,
+    ) -> CoordinatorResult<()> {
+        let resourceinfo = ResourceInfo::new(
+            (0, "".to_string()),
+            format!("drop_raft_node_{}", vnode.id),
+            ResourceOperator::DropRaftNode(
+                tenant_name.to_string(),
+                db_name.to_string(),
+                replica_id,
+                vnode,
+            ),
+            &None,
+            coord.node_id(),
+        );
+        coord
+            .meta_manager()
+            .write_resourceinfo(resourceinfo.get_name(), resourceinfo.clone())
+            .await
+            .context(MetaSnafu)?;
+        ResourceManager::spawn_resource_task(coord, resourceinfo);
+
+        Ok(())
+    }
+
+    /// Stop placing new buckets on the node and start moving its vnodes to other nodes
+    /// in background, refuse if the other nodes of the same tier are not enough for replicas
+    /// of any database.
+    pub async fn add_decommission_node_task(
+        coord: Arc<dyn Coordinator>,
+        node_id: NodeId,
+    ) -> CoordinatorResult<()> {
+        let meta = coord.meta_manager();
+        let node = meta.node_info_by_id(node_id).await.context(MetaSnafu)?;
+
+        let name = format!("decommission_node_{}", node_id);
+        if let Some(resourceinfo) = meta
+            .read_resourceinfo_by_name(&name)
+            .await
+            .context(MetaSnafu)?
+        {
+            if *resourceinfo.get_status() == ResourceStatus::Executing
+                || *resourceinfo.get_status() == ResourceStatus::Failed
+            {
+                return Err(CommonSnafu {
+                    msg: format!("Node {} is already decommissioning", node_id),
+                }
+                .build());
+            }
+        }
+
+        let available_ids: Vec<NodeId> = ResourceManager::available_nodes(coord.clone(), node_id)
+            .await?
+            .into_iter()
+            .map(|m| m.id)
+            .collect();
+        let available_nodes: Vec<NodeInfo> = meta
+            .data_nodes()
+            .await
+            .into_iter()
+            .filter(|n| available_ids.contains(&n.id) && n.tier == node.tier)
+            .collect();
+        let available = available_nodes.len();
+        for tenant in meta.tenants().await.context(MetaSnafu)? {
+            let tenant_meta = match coord.tenant_meta(tenant.name()).await {
+                Some(meta) => meta,
+                None => continue,
+            };
+            for (db_name, db_info) in tenant_meta.list_databases().context(MetaSnafu)? {
+                let replica_num = db_info.schema.config.replica_or_default() as usize;
+                if replica_num > available {
+                    return Err(CommonSnafu {
+                        msg: format!(
+                            "Can not decommission node {}: only {} {} nodes are left, but database {}.{} needs {} replicas",
+                            node_id,
+                            available,
+                            node.tier,
+                            tenant.name(),
+                            db_name,
+                            replica_num
+                        ),
+                    }
+                    .build());
+                }
+                if let Some(placement) = db_info.schema.config.replica_placement() {
+                    if let Err(reason) = placement.check(&available_nodes, replica_num as u64) {
+                        return Err(CommonSnafu {
+                            msg: format!(
+                                "Can not decommission node {}: database {}.{}: {}",
+                                node_id,
+                                tenant.name(),
+                                db_name,
+                                reason
+                            ),
+                        }
+                        .build());
+                    }
+                }
+            }
+        }
+
+        meta.decommission_data_node(node_id)
+            .await
+            .context(MetaSnafu)?;
+        let resourceinfo = ResourceInfo::new(
+            (0, "".to_string()),
+            name,
+            ResourceOperator::DecommissionNode(node_id),
+            &None,
+            coord.node_id(),
+        );
+        meta.write_resourceinfo(resourceinfo.get_name(), resourceinfo.clone())
+            .await
+            .context(MetaSnafu)?;
+        info!("Start decommissioning node {}", node_id);
+        ResourceManager::spawn_resource_task(coord, resourceinfo);
+
+        Ok(())
+    }
+
+    /// Execute a long-running task in background, if failed, it's retried
+    /// by the node holding the resource tasks lock.
+    pub fn spawn_resource_task(coord: Arc<dyn Coordinator>, resourceinfo: ResourceInfo) {
+        tokio::spawn(async move {
+            let name = resourceinfo.get_name().to_string();
+            if let Err(err) = ResourceManager::do_operator(coord, resourceinfo).await {
+                error!("Resource task {} failed, retry later: {}", name, err);
+            }
+        });
+    }
+
     pub async fn add_resource_task(
         coord: Arc<dyn Coordinator>,
         mut resourceinfo: ResourceInfo,
@@ -364,10 +949,15 @@ impl ResourceManager {
         .await;
         let mut resourceinfo = resourceinfo;
         resourceinfo.set_execute_node_id(coord.node_id());
-        while ResourceManager::do_operator(coord.clone(), resourceinfo.clone())
-            .await
-            .is_err()
-        {
+        loop {
+            match ResourceManager::do_operator(coord.clone(), resourceinfo.clone()).await {
+                Ok(_) => break,
+                Err(err @ CoordinatorError::ReplicaQuorumLost { .. }) => {
+                    error!("{} is stopped: {}", resourceinfo.get_name(), err);
+                    break;
+                }
+                Err(_) => {}
+            }
             sleep(Duration::from_secs(
                 base_sleep.pow(resourceinfo.get_try_count() as u32),
             ))
@@ -377,6 +967,10 @@ impl ResourceManager {
                 .read_resourceinfo_by_name(resourceinfo.get_name())
                 .await;
             match res {
+                Ok(Some(res)) if res.get_time() != resourceinfo.get_time() => {
+                    info!("{} is replaced by a new one", resourceinfo.get_name());
+                    break;
+                }
                 Ok(Some(res)) => {
                     resourceinfo = res;
                 }
@@ -391,3 +985,259 @@ impl ResourceManager {
         }
     }
 }
+
+/// Returns an error if less than a majority of vnodes of the replication set are on
+/// healthy nodes.
+fn check_replica_quorum(
+    replica: &ReplicationSet,
+    healthy: &HashSet<NodeId>,
+) -> CoordinatorResult<()> {
+    let total = replica.vnodes.len();
+    let alive = replica
+        .vnodes
+        .iter()
+        .filter(|v| healthy.contains(&v.node_id))
+        .count();
+    if alive * 2 <= total {
+        return Err(ReplicaQuorumLostSnafu {
+            replica_id: replica.id,
+            alive,
+            total,
+        }
+        .build());
+    }
+
+    Ok(())
+}
+
+/// Returns the commands to move the vnode of the replication set off its node: add a new
+/// vnode on the node picked by `pick_node`, unless a failed try before has added it, then
+/// remove the vnode. The vnode is kept on its node if the node is not healthy, as dropping
+/// it would fail.
+fn migration_commands(
+    replica: &ReplicationSet,
+    vnode_id: VnodeId,
+    replica_num: usize,
+    healthy: &HashSet<NodeId>,
+    pick_node: impl FnOnce() -> CoordinatorResult<NodeId>,
+) -> CoordinatorResult<Vec<ReplicationCmdType>> {
+    let mut commands = vec![];
+    if replica.vnodes.len() <= replica_num {
+        commands.push(ReplicationCmdType::AddRaftFollower(
+            replica.id,
+            pick_node()?,
+        ));
+    }
+
+    let reachable = replica
+        .vnode(vnode_id)
+        .is_some_and(|v| healthy.contains(&v.node_id));
+    if reachable {
+        commands.push(ReplicationCmdType::RemoveRaftNode(vnode_id));
+    } else {
+        commands.push(ReplicationCmdType::RemoveUnreachableRaftNode(vnode_id));
+    }
+
+    Ok(commands)
+}
+
+/// Pick a node for a new vnode of the replication set to replace the vnode, from the
+/// candidates of the same tier allowed by the replica placement, the node with the fewest
+/// vnodes placed by this migration and the most free disk space first.
+fn pick_new_replica_node(
+    candidates: &[NodeMetrics],
+    nodes: &HashMap<NodeId, NodeInfo>,
+    tier: NodeTier,
+    replica: &ReplicationSet,
+    vnode_id: VnodeId,
+    options: &DatabaseOptions,
+    placed: &HashMap<NodeId, usize>,
+) -> CoordinatorResult<NodeId> {
+    candidates
+        .iter()
+        .filter(|m| replica.by_node_id(m.id).is_none())
+        .filter(|m| nodes.get(&m.id).is_some_and(|n| n.tier == tier))
+        .filter(|m| {
+            options.replica_placement().map_or(true, |placement| {
+                replica_placement_allows(&placement, nodes, replica, Some(vnode_id), m.id)
+            })
+        })
+        .min_by_key(|m| {
+            (
+                placed.get(&m.id).copied().unwrap_or(0),
+                Reverse(m.disk_free),
+            )
+        })
+        .map(|m| m.id)
+        .ok_or_else(|| {
+            CommonSnafu {
+                msg: format!(
+                    "no healthy node allowed by the replica placement for a new replica of replication set {}",
+                    replica.id
+                ),
+            }
+            .build()
+        })
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::{HashMap, HashSet};
+
+    use models::meta_data::{NodeTier, ReplicationSet, VnodeInfo};
+    use models::schema::DatabaseOptions;
+
+    use super::{check_replica_quorum, migration_commands, pick_new_replica_node};
+    use crate::errors::{CommonSnafu, CoordinatorError};
+    use crate::test_util::{node, node_info};
+    use crate::ReplicationCmdType;
+
+    fn replica() -> ReplicationSet {
+        let vnodes = vec![
+            VnodeInfo::new(1, 1),
+            VnodeInfo::new(2, 2),
+            VnodeInfo::new(3, 3),
+        ];
+        ReplicationSet::new(1, 1, 1, vnodes)
+    }
+
+    #[test]
+    fn test_check_replica_quorum() {
+        let replica = replica();
+        assert!(check_replica_quorum(&replica, &HashSet::from([1, 2])).is_ok());
+        assert!(check_replica_quorum(&replica, &HashSet::from([1, 2, 3, 4])).is_ok());
+
+        // Node 3 is down too, membership of the replication set can't be changed.
+        let err = check_replica_quorum(&replica, &HashSet::from([1, 4])).unwrap_err();
+        assert!(matches!(
+            err,
+            CoordinatorError::ReplicaQuorumLost {
+                replica_id: 1,
+                alive: 1,
+                total: 3,
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn test_migration_commands() {
+        let mut replica = replica();
+        let healthy = HashSet::from([1, 2, 4, 5]);
+
+        // Node 3 is dead, a new vnode is added on node 5, and vnode 3 is removed
+        // without being dropped on node 3.
+        let commands = migration_commands(&replica, 3, 3, &healthy, || Ok(5)).unwrap();
+        assert_eq!(
+            commands,
+            vec![
+                ReplicationCmdType::AddRaftFollower(1, 5),
+                ReplicationCmdType::RemoveUnreachableRaftNode(3),
+            ]
+        );
+
+        // The new vnode is added by a failed try before, only vnode 3 is removed.
+        replica.vnodes.push(VnodeInfo::new(4, 5));
+        let commands = migration_commands(&replica, 3, 3, &healthy, || unreachable!()).unwrap();
+        assert_eq!(
+            commands,
+            vec![ReplicationCmdType::RemoveUnreachableRaftNode(3)]
+        );
+
+        // Node 2 is decommissioning but alive, vnode 2 is dropped when it's removed.
+        let replica = self::replica();
+        let commands = migration_commands(&replica, 2, 3, &healthy, || Ok(4)).unwrap();
+        assert_eq!(
+            commands,
+            vec![
+                ReplicationCmdType::AddRaftFollower(1, 4),
+                ReplicationCmdType::RemoveRaftNode(2),
+            ]
+        );
+
+        // Nothing is removed if there is no node for the new vnode.
+        let result = migration_commands(&replica, 3, 3, &healthy, || {
+            Err(CommonSnafu { msg: "no node" }.build())
+        });
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_pick_new_replica_node() {
+        let replica = replica();
+        let nodes: HashMap<_, _> = [
+            node_info(1, NodeTier::Hot, "a"),
+            node_info(2, NodeTier::Hot, "b"),
+            node_info(3, NodeTier::Hot, "c"),
+            node_info(4, NodeTier::Hot, "a"),
+            node_info(5, NodeTier::Hot, "c"),
+            node_info(6, NodeTier::Cold, "d"),
+        ]
+        .into_iter()
+        .collect();
+        // Node 3 is dead, vnode 3 on it is replaced.
+        let candidates = vec![
+            node(1, 100),
+            node(2, 100),
+            node(4, 200),
+            node(5, 300),
+            node(6, 1000),
+        ];
+        let mut options = DatabaseOptions::default();
+        let mut placed = HashMap::new();
+
+        // Nodes already with a vnode of the replication set and nodes of another tier are skipped,
+        // the node with most free disk space is picked.
+        let picked = pick_new_replica_node(
+            &candidates,
+            &nodes,
+            NodeTier::Hot,
+            &replica,
+            3,
+            &options,
+            &placed,
+        )
+        .unwrap();
+        assert_eq!(picked, 5);
+
+        // Vnodes are spread over nodes in a migration.
+        placed.insert(5, 1);
+        let picked = pick_new_replica_node(
+            &candidates,
+            &nodes,
+            NodeTier::Hot,
+            &replica,
+            3,
+            &options,
+            &placed,
+        )
+        .unwrap();
+        assert_eq!(picked, 4);
+
+        // Node 4 is in the same zone as node 1, so only node 5 is allowed.
+        options.with_replica_placement("spread:zone".parse().unwrap());
+        let picked = pick_new_replica_node(
+            &candidates,
+            &nodes,
+            NodeTier::Hot,
+            &replica,
+            3,
+            &options,
+            &placed,
+        )
+        .unwrap();
+        assert_eq!(picked, 5);
+
+        let candidates = vec![node(4, 200), node(6, 1000)];
+        assert!(pick_new_replica_node(
+            &candidates,
+            &nodes,
+            NodeTier::Hot,
+            &replica,
+            3,
+            &options,
+            &placed,
+        )
+        .is_err());
+    }
+}
@@ -23,16 +23,17 @@ use metrics::count::U64Counter;
 use metrics::label::Labels;
 use metrics::metric::Metric;
 use metrics::metric_register::MetricsRegister;
+use models::consistency_level::ConsistencyLevel;
 use models::meta_data::{
     ExpiredBucketInfo, MetaModifyType, NodeId, ReplicationSet, ReplicationSetId, VnodeId,
-    VnodeStatus,
+    VnodeStatus, VnodeVersion,
 };
 use models::object_reference::ResolvedTable;
 use models::oid::Identifier;
 use models::predicate::domain::{ResolvedPredicate, ResolvedPredicateRef, TimeRange, TimeRanges};
 use models::schema::{
-    timestamp_convert, ColumnType, Precision, ResourceInfo, ResourceOperator, ResourceStatus,
-    TskvTableSchemaRef, DEFAULT_CATALOG, TIME_FIELD, USAGE_SCHEMA,
+    is_read_only_table, timestamp_convert, ColumnType, Precision, ResourceInfo, ResourceOperator,
+    ResourceStatus, TskvTableSchemaRef, DEFAULT_CATALOG, TIME_FIELD, USAGE_SCHEMA,
 };
 use models::utils::now_timestamp_nanos;
 use models::{record_batch_decode, SeriesKey, Tag};
@@ -42,6 +43,7 @@ use protocol_parser::lines_convert::{
 use protocol_parser::Line;
 use protos::kv_service::admin_command::Command::*;
 use protos::kv_service::*;
+use rand::seq::SliceRandom;
 use replication::multi_raft::MultiRaft;
 use snafu::{IntoError, OptionExt, ResultExt};
 use tokio::runtime::Runtime;
@@ -51,25 +53,26 @@ use tokio::time::Instant;
 use tokio_retry::strategy::{jitter, ExponentialBackoff};
 use tokio_retry::Retry;
 use trace::span_ext::SpanExt;
-use trace::{debug, error, info, Span, SpanContext};
+use trace::{debug, error, info, warn, Span, SpanContext};
 use tskv::EngineRef;
 use utils::BkdrHasher;
 
 use crate::errors::{
     ArrowSnafu, BincodeSerdeSnafu, ColumnNotFoundSnafu, CommonSnafu, CoordinatorError,
-    CoordinatorResult, FieldsIsEmptySnafu, MetaSnafu,
+    CoordinatorResult, FieldsIsEmptySnafu, HintsNotReplayedSnafu, MetaSnafu,
 };
+use crate::hinted_handoff::HintedHandoff;
 use crate::metrics::LPReporter;
 use crate::raft::manager::RaftNodesManager;
 use crate::raft::writer::TskvRaftWriter;
 use crate::reader::table_scan::opener::TemporaryTableScanOpener;
 use crate::reader::tag_scan::opener::TemporaryTagScanOpener;
-use crate::reader::{CheckFuture, CheckedCoordinatorRecordBatchStream};
+use crate::reader::{CheckFuture, CheckedCoordinatorRecordBatchStream, ReplicaRepairer};
 use crate::resource_manager::ResourceManager;
 use crate::tskv_executor::{TskvAdminRequest, TskvLeaderExecutor};
 use crate::{
-    get_replica_all_info, get_vnode_all_info, Coordinator, QueryOption, ReplicationCmdType,
-    SendableCoordinatorRecordBatchStream,
+    get_replica_all_info, get_vnode_all_info, get_vnode_versions, tiering, Coordinator,
+    QueryOption, ReplicationCmdType, SendableCoordinatorRecordBatchStream,
 };
 
 pub type CoordinatorRef = Arc<dyn Coordinator>;
@@ -85,6 +88,7 @@ pub struct CoordService {
     memory_pool: MemoryPoolRef,
     metrics: Arc<CoordServiceMetrics>,
     raft_manager: Arc<RaftNodesManager>,
+    hinted_handoff: Arc<HintedHandoff>,
     async_task_joinhandle: Arc<Mutex<HashMap<String, JoinHandle<()>>>>,
     failed_task_joinhandle: Arc<Mutex<HashMap<String, JoinHandle<()>>>>,
 }
@@ -95,6 +99,7 @@ pub struct CoordServiceMetrics {
     coord_data_out: Metric<U64Counter>,
     coord_queries: Metric<U64Counter>,
     coord_writes: Metric<U64Counter>,
+    coord_scan_failovers: Metric<U64Counter>,
 
     sql_data_in: Metric<U64Counter>,
     sql_write_row: Metric<U64Counter>,
@@ -114,6 +119,7 @@ generate_coord_metrics_gets!(coord_data_in);
 generate_coord_metrics_gets!(coord_data_out);
 generate_coord_metrics_gets!(coord_queries);
 generate_coord_metrics_gets!(coord_writes);
+generate_coord_metrics_gets!(coord_scan_failovers);
 generate_coord_metrics_gets!(sql_data_in);
 generate_coord_metrics_gets!(sql_write_row);
 generate_coord_metrics_gets!(sql_points_data_in);
@@ -124,6 +130,10 @@ impl CoordServiceMetrics {
         let coord_data_out = register.metric("coord_data_out", "tenant data out");
         let coord_writes = register.metric("coord_writes", "");
         let coord_queries = register.metric("coord_queries", "");
+        let coord_scan_failovers = register.metric(
+            "coord_scan_failovers",
+            "scans of vnodes resumed on another replica",
+        );
 
         let sql_data_in = register.metric("sql_data_in", "Traffic written through sql");
         let sql_write_row = register.metric("sql_write_row", "sql write row");
@@ -134,6 +144,7 @@ impl CoordServiceMetrics {
             coord_data_out,
             coord_writes,
             coord_queries,
+            coord_scan_failovers,
 
             sql_data_in,
             sql_write_row,
@@ -175,11 +186,18 @@ impl CoordService {
             Duration::from_secs(10),
         ));
 
+        let hinted_handoff = Arc::new(
+            HintedHandoff::open(&config.cluster, config.global.node_id, &metrics_register)
+                .await
+                .unwrap(),
+        );
+
         let coord = Arc::new(Self {
             runtime,
             kv_inst,
             memory_pool,
             raft_manager,
+            hinted_handoff: hinted_handoff.clone(),
             meta: meta.clone(),
             config: config.clone(),
             async_task_joinhandle: Arc::new(Mutex::new(HashMap::new())),
@@ -197,6 +215,13 @@ impl CoordService {
             meta_task_receiver,
         ));
         tokio::spawn(CoordService::db_ttl_service(coord.clone()));
+        tokio::spawn(tiering::cold_migration_service(
+            coord.clone(),
+            config.cluster.cold_migration_check_interval,
+        ));
+        tokio::spawn(
+            hinted_handoff.run(coord.clone(), config.cluster.hinted_handoff_replay_interval),
+        );
 
         if config.global.store_metrics {
             tokio::spawn(CoordService::metrics_service(
@@ -334,6 +359,9 @@ impl CoordService {
                     .await
                     .map_err(|meta_err| CoordinatorError::Meta { source: meta_err })?;
                 if coord.node_id() == id && lock {
+                    ResourceManager::add_repair_replicas_task(coord.clone(), node_metrics.id)
+                        .await?;
+
                     let mut resourceinfos = coord
                         .meta_manager()
                         .read_resourceinfos()
@@ -345,6 +373,10 @@ impl CoordService {
                         let coord = coord.clone();
                         resourceinfo.set_execute_node_id(coord.node_id());
                         resourceinfo.set_is_new_add(false);
+                        // No vnode is being moved by a pausing task on the dead node.
+                        if *resourceinfo.get_status() == ResourceStatus::Pausing {
+                            resourceinfo.set_status(ResourceStatus::Paused);
+                        }
                         coord
                             .meta_manager()
                             .write_resourceinfo(resourceinfo.get_name(), resourceinfo.clone())
@@ -445,6 +477,7 @@ impl CoordService {
                         DEFAULT_CATALOG,
                         USAGE_SCHEMA,
                         Precision::NS,
+                        ConsistencyLevel::default(),
                         lines.iter().map(|l| l.to_line()).collect::<Vec<_>>(),
                         None,
                     )
@@ -517,6 +550,20 @@ impl CoordService {
         Box::pin(checker)
     }
 
+    fn replica_repairer(&self) -> ReplicaRepairer {
+        let coord: CoordinatorRef = Arc::new(self.clone());
+        let runtime = self.runtime.clone();
+
+        Arc::new(move |node_id| {
+            let coord = coord.clone();
+            runtime.spawn(async move {
+                if let Err(err) = ResourceManager::add_repair_replicas_task(coord, node_id).await {
+                    warn!("Failed to add repair task of replicas on node {node_id}: {err}");
+                }
+            });
+        })
+    }
+
     async fn vnode_checksum_on_node(
         &self,
         tenant: &str,
@@ -542,6 +589,7 @@ impl CoordService {
         tenant: &'a str,
         db: &'a str,
         precision: Precision,
+        consistency: ConsistencyLevel,
         info: ReplicationSet,
         points: Arc<Vec<u8>>,
         span_ctx: Option<&'a SpanContext>,
@@ -584,12 +632,110 @@ impl CoordService {
             command: Some(raft_write_command::Command::WriteData(request)),
         };
 
-        let request = self.write_replica_by_raft(info.clone(), request, span_ctx);
+        let request = self.write_replica_with_consistency(info, request, consistency, span_ctx);
         requests.push(Box::pin(request));
 
         Ok(requests)
     }
 
+    /// Write to a replication set by raft, which commits the write on a quorum of vnodes.
+    /// With `ConsistencyLevel::All` all vnodes must be running, and it waits for all vnodes
+    /// to apply the write. With `ConsistencyLevel::Any` the write is handed off to a hint
+    /// if the replication set is not writable.
+    async fn write_replica_with_consistency(
+        &self,
+        replica: ReplicationSet,
+        request: RaftWriteCommand,
+        consistency: ConsistencyLevel,
+        span_ctx: Option<&SpanContext>,
+    ) -> CoordinatorResult<()> {
+        if consistency == ConsistencyLevel::All {
+            if let Some(vnode) = replica
+                .vnodes
+                .iter()
+                .find(|v| v.status != VnodeStatus::Running)
+            {
+                return Err(CommonSnafu {
+                    msg: format!(
+                        "vnode {} of replication set {} is {:?}, can't write with consistency level {}",
+                        vnode.id, replica.id, vnode.status, consistency
+                    ),
+                }
+                .build());
+            }
+        }
+
+        // The hints must not overwrite this write later, so the write is appended after them,
+        // or is written after they are replayed in background.
+        if self.hinted_handoff.has_hints(replica.id).await {
+            if consistency == ConsistencyLevel::Any {
+                return self.hinted_handoff.append(&request).await;
+            }
+            if !self
+                .hinted_handoff
+                .wait_replayed(replica.id, self.config.query.write_timeout)
+                .await
+            {
+                return Err(HintsNotReplayedSnafu {
+                    replica_id: replica.id,
+                }
+                .build());
+            }
+        }
+
+        match self
+            .write_replica_by_raft(replica.clone(), request.clone(), span_ctx)
+            .await
+        {
+            Ok(()) if consistency == ConsistencyLevel::All => {
+                self.sync_replica(&request.tenant, &replica).await
+            }
+            Err(err)
+                if consistency == ConsistencyLevel::Any && HintedHandoff::can_handoff(&err) =>
+            {
+                warn!(
+                    "Write to replication set {} failed, hand it off: {}",
+                    request.replica_id, err
+                );
+                self.hinted_handoff.append(&request).await
+            }
+            res => res,
+        }
+    }
+
+    /// Wait until all vnodes of the replication set applied the logs committed before.
+    async fn sync_replica(&self, tenant: &str, replica: &ReplicationSet) -> CoordinatorResult<()> {
+        let mut requests = Vec::with_capacity(replica.vnodes.len());
+        for vnode in replica.vnodes.iter() {
+            let request = async move {
+                if vnode.node_id == self.node_id {
+                    return self
+                        .raft_manager
+                        .linearizable_read(replica.id, vnode.id, None)
+                        .await;
+                }
+
+                let request = AdminCommand {
+                    tenant: tenant.to_string(),
+                    command: Some(SyncReplica(SyncReplicaRequest {
+                        replica_id: replica.id,
+                        vnode_id: vnode.id,
+                    })),
+                };
+                self.admin_command_on_node(vnode.node_id, request)
+                    .await
+                    .map(|_| ())
+            };
+            requests.push(request);
+        }
+
+        for result in futures::future::join_all(requests).await {
+            result?;
+        }
+
+        Ok(())
+    }
+
     async fn admin_command_on_leader(
         &self,
         replica: ReplicationSet,
@@ -644,6 +790,10 @@ impl Coordinator for CoordService {
         self.raft_manager.clone()
     }
 
+    fn memory_pool(&self) -> MemoryPoolRef {
+        self.memory_pool.clone()
+    }
+
     async fn tenant_meta(&self, tenant: &str) -> Option<MetaClientRef> {
         self.meta.tenant_meta(tenant).await
     }
@@ -676,25 +826,23 @@ impl Coordinator for CoordService {
             .await?;
 
         // 2. 选择最优的副本
+        // Any healthy replica can serve the scan after a read index check, so replicas
+        // are shuffled to spread the read load instead of always reading from the leader.
+        // The other healthy replicas are kept for failover of the scan.
         for replica_set in replica_sets.iter_mut() {
+            replica_set.vnodes.shuffle(&mut rand::thread_rng());
             replica_set.vnodes.sort_by_key(|vnode| {
                 // The smaller the score, the easier it is to be selected
-                if vnode.id == replica_set.leader_vnode_id {
-                    0
-                } else {
-                    match vnode.status {
-                        VnodeStatus::Running => 1,
-                        VnodeStatus::Copying => 2,
-                        VnodeStatus::Broken => i32::MAX,
-                    }
+                match vnode.status {
+                    VnodeStatus::Running => 0,
+                    VnodeStatus::Copying => 1,
+                    VnodeStatus::Broken => i32::MAX,
                 }
             });
 
             replica_set
                 .vnodes
                 .retain(|e| e.status != VnodeStatus::Broken);
-
-            replica_set.vnodes.truncate(2);
         }
 
         Ok(replica_sets)
@@ -722,6 +870,29 @@ impl Coordinator for CoordService {
         tenant: &str,
         db: &str,
         precision: Precision,
+        consistency: ConsistencyLevel,
+        lines: Vec<Line<'a>>,
+        span_ctx: Option<&SpanContext>,
+    ) -> CoordinatorResult<usize> {
+        if let Some(line) = lines
+            .iter()
+            .find(|line| is_read_only_table(tenant, db, &line.table))
+        {
+            return Err(CoordinatorError::ReadOnlyTable {
+                table: format!("{}.{}.{}", tenant, db, line.table),
+            });
+        }
+
+        self.write_system_lines(tenant, db, precision, consistency, lines, span_ctx)
+            .await
+    }
+
+    async fn write_system_lines<'a>(
+        &self,
+        tenant: &str,
+        db: &str,
+        precision: Precision,
+        consistency: ConsistencyLevel,
         lines: Vec<Line<'a>>,
         span_ctx: Option<&SpanContext>,
     ) -> CoordinatorResult<usize> {
@@ -775,8 +946,16 @@ impl Coordinator for CoordService {
             let points = Arc::new(mutable_batches_to_point(db, batches));
             write_bytes += points.len();
             requests.extend(
-                self.push_points_to_requests(tenant, db, precision, lines.info, points, span_ctx)
-                    .await?,
+                self.push_points_to_requests(
+                    tenant,
+                    db,
+                    precision,
+                    consistency,
+                    lines.info,
+                    points,
+                    span_ctx,
+                )
+                .await?,
             );
         }
         let now = tokio::time::Instant::now();
@@ -797,12 +976,18 @@ impl Coordinator for CoordService {
         table_schema: TskvTableSchemaRef,
         record_batch: RecordBatch,
         db_precision: Precision,
+        consistency: ConsistencyLevel,
         span_ctx: Option<&SpanContext>,
     ) -> CoordinatorResult<usize> {
         let mut write_bytes: usize = 0;
         let mut precision = Precision::NS;
         let tenant = table_schema.tenant.as_str();
         let db = table_schema.db.as_str();
+        if is_read_only_table(tenant, db, &table_schema.name) {
+            return Err(CoordinatorError::ReadOnlyTable {
+                table: format!("{}.{}.{}", tenant, db, table_schema.name),
+            });
+        }
         let meta_client = self.meta.tenant_meta(tenant).await.ok_or_else(|| {
             CoordinatorError::TenantNotFound {
                 name: tenant.to_string(),
@@ -908,8 +1093,16 @@ impl Coordinator for CoordService {
             );
             write_bytes += points.len();
             requests.extend(
-                self.push_points_to_requests(tenant, db, precision, repl, points, span_ctx)
-                    .await?,
+                self.push_points_to_requests(
+                    tenant,
+                    db,
+                    precision,
+                    consistency,
+                    repl,
+                    points,
+                    span_ctx,
+                )
+                .await?,
             );
         }
         let now = tokio::time::Instant::now();
@@ -937,6 +1130,8 @@ impl Coordinator for CoordService {
             self.kv_inst.clone(),
             self.runtime.clone(),
             self.meta.clone(),
+            self.raft_manager.clone(),
+            self.memory_pool.clone(),
             span_ctx,
             self.config.service.grpc_enable_gzip,
         );
@@ -946,7 +1141,9 @@ impl Coordinator for CoordService {
             opener,
             self.meta.clone(),
             Box::pin(checker),
+            self.replica_repairer(),
             &self.metrics,
+            span_ctx,
         )))
     }
 
@@ -961,6 +1158,7 @@ impl Coordinator for CoordService {
             self.config.query.clone(),
             self.kv_inst.clone(),
             self.meta.clone(),
+            self.raft_manager.clone(),
             span_ctx,
             self.config.service.grpc_enable_gzip,
         );
@@ -970,7 +1168,9 @@ impl Coordinator for CoordService {
             opener,
             self.meta.clone(),
             Box::pin(checker),
+            self.replica_repairer(),
             &self.metrics,
+            span_ctx,
         )))
     }
 
@@ -1044,7 +1244,10 @@ impl Coordinator for CoordService {
                 )
             }
 
-            ReplicationCmdType::RemoveRaftNode(vnode_id) => {
+            ReplicationCmdType::RemoveRaftNode(vnode_id)
+            | ReplicationCmdType::RemoveUnreachableRaftNode(vnode_id) => {
+                let keep_unreachable =
+                    matches!(cmd_type, ReplicationCmdType::RemoveUnreachableRaftNode(_));
                 let all_info = get_vnode_all_info(self.meta.clone(), tenant, vnode_id).await?;
                 let replica_id = all_info.repl_set_id;
                 let replica = get_replica_all_info(self.meta.clone(), tenant, replica_id).await?;
@@ -1055,6 +1258,7 @@ impl Coordinator for CoordService {
                             vnode_id,
                             replica_id,
                             db_name: all_info.db_name,
+                            keep_unreachable,
                         })),
                     },
                     replica.replica_set,
@@ -1159,6 +1363,102 @@ impl Coordinator for CoordService {
         Ok(record_batches)
     }
 
+    async fn rebuild_vnode_index(
+        &self,
+        tenant: &str,
+        vnode_id: VnodeId,
+    ) -> CoordinatorResult<RecordBatch> {
+        let vnode = get_vnode_all_info(self.meta.clone(), tenant, vnode_id).await?;
+        let request = AdminCommand {
+            tenant: tenant.to_string(),
+            command: Some(RebuildIndex(RebuildIndexRequest { vnode_id })),
+        };
+
+        let data = self.admin_command_on_node(vnode.node_id, request).await?;
+        match record_batch_decode(&data) {
+            Ok(r) => Ok(r),
+            Err(e) => Err(ArrowSnafu.into_error(e)),
+        }
+    }
+
+    async fn backup_vnode(
+        &self,
+        tenant: &str,
+        vnode_id: VnodeId,
+        location: &str,
+        backup_id: &str,
+        connection_options: Vec<ConnectionOption>,
+        incremental: bool,
+    ) -> CoordinatorResult<RecordBatch> {
+        let vnode = get_vnode_all_info(self.meta.clone(), tenant, vnode_id).await?;
+        let request = AdminCommand {
+            tenant: tenant.to_string(),
+            command: Some(BackupVnode(BackupVnodeRequest {
+                vnode_id,
+                location: location.to_string(),
+                connection_options,
+                incremental,
+                backup_id: backup_id.to_string(),
+            })),
+        };
+
+        let data = self.admin_command_on_node(vnode.node_id, request).await?;
+        match record_batch_decode(&data) {
+            Ok(r) => Ok(r),
+            Err(e) => Err(ArrowSnafu.into_error(e)),
+        }
+    }
+
+    async fn vnode_versions(
+        &self,
+        tenant: &str,
+        db: &str,
+        node_id: NodeId,
+        vnode_ids: Vec<VnodeId>,
+    ) -> CoordinatorResult<Vec<VnodeVersion>> {
+        if node_id == self.node_id {
+            if let Some(engine) = &self.kv_inst {
+                return get_vnode_versions(engine, tenant, db, &vnode_ids).await;
+            }
+        }
+
+        let request = AdminCommand {
+            tenant: tenant.to_string(),
+            command: Some(GetVnodeVersions(GetVnodeVersionsRequest {
+                db_name: db.to_string(),
+                vnode_ids,
+            })),
+        };
+
+        let data = self.admin_command_on_node(node_id, request).await?;
+        bincode::deserialize(&data).context(BincodeSerdeSnafu)
+    }
+
+    async fn restore_vnode(
+        &self,
+        tenant: &str,
+        db_name: &str,
+        vnode_id: VnodeId,
+        location: &str,
+        backup_id: &str,
+        connection_options: Vec<ConnectionOption>,
+    ) -> CoordinatorResult<()> {
+        let vnode = get_vnode_all_info(self.meta.clone(), tenant, vnode_id).await?;
+        let request = AdminCommand {
+            tenant: tenant.to_string(),
+            command: Some(RestoreVnode(RestoreVnodeRequest {
+                db_name: db_name.to_string(),
+                vnode_id,
+                location: location.to_string(),
+                connection_options,
+                backup_id: backup_id.to_string(),
+            })),
+        };
+
+        self.admin_command_on_node(vnode.node_id, request).await?;
+        Ok(())
+    }
+
     fn metrics(&self) -> &Arc<CoordServiceMetrics> {
         &self.metrics
     }
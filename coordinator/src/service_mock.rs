@@ -6,15 +6,19 @@ use std::todo;
 
 use config::tskv::Config;
 use datafusion::arrow::record_batch::RecordBatch;
+use memory_pool::{GreedyMemoryPool, MemoryPoolRef};
 use meta::model::meta_admin::AdminMeta;
 use meta::model::meta_tenant::TenantMeta;
 use meta::model::{MetaClientRef, MetaRef};
-use models::meta_data::{ReplicationSet, ReplicationSetId, VnodeId, VnodeInfo, VnodeStatus};
+use models::consistency_level::ConsistencyLevel;
+use models::meta_data::{
+    NodeId, ReplicationSet, ReplicationSetId, VnodeId, VnodeInfo, VnodeStatus, VnodeVersion,
+};
 use models::object_reference::ResolvedTable;
 use models::predicate::domain::{ResolvedPredicate, ResolvedPredicateRef};
 use models::schema::{Precision, TskvTableSchemaRef};
 use protocol_parser::Line;
-use protos::kv_service::{RaftWriteCommand, UpdateSetValue};
+use protos::kv_service::{ConnectionOption, RaftWriteCommand, UpdateSetValue};
 use trace::SpanContext;
 use tskv::engine_mock::MockEngine;
 use tskv::reader::QueryOption;
@@ -49,6 +53,10 @@ impl Coordinator for MockCoordinator {
         todo!()
     }
 
+    fn memory_pool(&self) -> MemoryPoolRef {
+        Arc::new(GreedyMemoryPool::default())
+    }
+
     async fn tenant_meta(&self, tenant: &str) -> Option<MetaClientRef> {
         Some(Arc::new(TenantMeta::mock()))
     }
@@ -167,6 +175,19 @@ impl Coordinator for MockCoordinator {
         tenant: &str,
         db: &str,
         precision: Precision,
+        consistency: ConsistencyLevel,
+        line: Vec<Line<'a>>,
+        span_ctx: Option<&SpanContext>,
+    ) -> CoordinatorResult<usize> {
+        todo!()
+    }
+
+    async fn write_system_lines<'a>(
+        &self,
+        tenant: &str,
+        db: &str,
+        precision: Precision,
+        consistency: ConsistencyLevel,
         line: Vec<Line<'a>>,
         span_ctx: Option<&SpanContext>,
     ) -> CoordinatorResult<usize> {
@@ -178,6 +199,7 @@ impl Coordinator for MockCoordinator {
         table_schema: TskvTableSchemaRef,
         record_batch: RecordBatch,
         db_precision: Precision,
+        consistency: ConsistencyLevel,
         span_ctx: Option<&SpanContext>,
     ) -> CoordinatorResult<usize> {
         todo!()
@@ -224,6 +246,48 @@ impl Coordinator for MockCoordinator {
         Ok(vec![])
     }
 
+    async fn rebuild_vnode_index(
+        &self,
+        tenant: &str,
+        vnode_id: VnodeId,
+    ) -> CoordinatorResult<RecordBatch> {
+        todo!()
+    }
+
+    async fn backup_vnode(
+        &self,
+        tenant: &str,
+        vnode_id: VnodeId,
+        location: &str,
+        backup_id: &str,
+        connection_options: Vec<ConnectionOption>,
+        incremental: bool,
+    ) -> CoordinatorResult<RecordBatch> {
+        todo!()
+    }
+
+    async fn restore_vnode(
+        &self,
+        tenant: &str,
+        db_name: &str,
+        vnode_id: VnodeId,
+        location: &str,
+        backup_id: &str,
+        connection_options: Vec<ConnectionOption>,
+    ) -> CoordinatorResult<()> {
+        todo!()
+    }
+
+    async fn vnode_versions(
+        &self,
+        tenant: &str,
+        db: &str,
+        node_id: NodeId,
+        vnode_ids: Vec<VnodeId>,
+    ) -> CoordinatorResult<Vec<VnodeVersion>> {
+        todo!()
+    }
+
     fn metrics(&self) -> &Arc<CoordServiceMetrics> {
         todo!()
     }
@@ -0,0 +1,24 @@
+//! Fixtures of data nodes shared by the tests of vnode placement.
+
+use models::meta_data::{NodeId, NodeInfo, NodeMetrics, NodeTier};
+use models::node_info::NodeStatus;
+
+/// Metrics of a healthy data node.
+pub fn node(id: NodeId, disk_free: u64) -> NodeMetrics {
+    NodeMetrics {
+        id,
+        disk_free,
+        time: 0,
+        status: NodeStatus::Healthy,
+    }
+}
+
+pub fn node_info(id: NodeId, tier: NodeTier, zone: &str) -> (NodeId, NodeInfo) {
+    let info = NodeInfo {
+        id,
+        tier,
+        zone: Some(zone.to_string()),
+        ..Default::default()
+    };
+    (id, info)
+}
@@ -0,0 +1,266 @@
+//! Migrate buckets from hot data nodes to cold ones.
+//!
+//! New buckets are only placed on hot nodes. Once a bucket ends before the `cold_after`
+//! of its database, its vnodes on hot nodes are moved to cold nodes one by one, the same
+//! way as a rebalance: a raft learner is added on the cold node, and the old member is
+//! removed after the learner catches up, so the replication set keeps serving queries.
+
+use std::cmp::Reverse;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use meta::model::MetaRef;
+use models::meta_data::{
+    NodeId, NodeInfo, NodeMetrics, NodeTier, ReplicaPlacement, ReplicationSet, VnodeId, VnodeMove,
+    VnodeStatus,
+};
+use models::oid::Identifier;
+use models::schema::{ResourceInfo, ResourceOperator, ResourceStatus};
+use snafu::ResultExt;
+use tracing::{error, info, warn};
+
+use crate::errors::*;
+use crate::rebalance::{self, REBALANCE_TASK_NAME};
+use crate::resource_manager::ResourceManager;
+use crate::{replica_placement_allows, Coordinator};
+
+pub const MIGRATE_TO_COLD_TASK_NAME: &str = "migrate_to_cold";
+
+/// Check the buckets to migrate every `interval`.
+pub async fn cold_migration_service(coord: Arc<dyn Coordinator>, interval: Duration) {
+    loop {
+        tokio::time::sleep(interval).await;
+        if let Err(err) = start_cold_migration(coord.clone()).await {
+            error!("Failed to migrate buckets to cold nodes: {}", err);
+        }
+    }
+}
+
+/// Plan the vnode moves to cold nodes, and start moving them in background if the last
+/// migration is finished. Only the healthy data node with the smallest id does it.
+pub async fn start_cold_migration(
+    coord: Arc<dyn Coordinator>,
+) -> CoordinatorResult<Vec<VnodeMove>> {
+    let meta = coord.meta_manager();
+    let nodes_metrics = meta.data_nodes_metrics().await.context(MetaSnafu)?;
+    let runner = nodes_metrics
+        .iter()
+        .filter(|m| m.is_healthy())
+        .map(|m| m.id)
+        .min();
+    if runner != Some(coord.node_id()) {
+        return Ok(vec![]);
+    }
+
+    // A failed migration is planned again, the new task stops retrying the failed one.
+    if let Some(resourceinfo) = meta
+        .read_resourceinfo_by_name(MIGRATE_TO_COLD_TASK_NAME)
+        .await
+        .context(MetaSnafu)?
+    {
+        if matches!(
+            resourceinfo.get_status(),
+            ResourceStatus::Schedule | ResourceStatus::Executing
+        ) {
+            return Ok(vec![]);
+        }
+    }
+    // Vnodes are not moved by a rebalance and a migration at the same time.
+    if rebalance::moving_task(meta.clone(), REBALANCE_TASK_NAME)
+        .await?
+        .is_some()
+    {
+        return Ok(vec![]);
+    }
+
+    let moves = plan_cold_migration(meta.clone(), nodes_metrics).await?;
+    if moves.is_empty() {
+        return Ok(moves);
+    }
+
+    info!("Start migrating {} vnodes to cold nodes", moves.len());
+    let resourceinfo = ResourceInfo::new(
+        (0, "".to_string()),
+        MIGRATE_TO_COLD_TASK_NAME.to_string(),
+        ResourceOperator::MigrateToCold(moves.clone()),
+        &None,
+        coord.node_id(),
+    );
+    meta.write_resourceinfo(resourceinfo.get_name(), resourceinfo.clone())
+        .await
+        .context(MetaSnafu)?;
+    ResourceManager::spawn_resource_task(coord, resourceinfo);
+
+    Ok(moves)
+}
+
+async fn plan_cold_migration(
+    meta: MetaRef,
+    nodes_metrics: Vec<NodeMetrics>,
+) -> CoordinatorResult<Vec<VnodeMove>> {
+    let nodes: HashMap<NodeId, NodeInfo> = meta
+        .data_nodes()
+        .await
+        .into_iter()
+        .map(|n| (n.id, n))
+        .collect();
+    let cold_nodes: Vec<NodeMetrics> = nodes_metrics
+        .into_iter()
+        .filter(|m| {
+            m.is_healthy()
+                && nodes
+                    .get(&m.id)
+                    .is_some_and(|n| n.tier == NodeTier::Cold && !n.decommissioning)
+        })
+        .collect();
+    if cold_nodes.is_empty() {
+        return Ok(vec![]);
+    }
+
+    let mut replicas = vec![];
+    for tenant in meta.tenants().await.context(MetaSnafu)? {
+        let tenant_meta = match meta.tenant_meta(tenant.name()).await {
+            Some(meta) => meta,
+            None => continue,
+        };
+        for (_, db_info) in tenant_meta.list_databases().context(MetaSnafu)? {
+            let time_to_cold = match db_info.schema.time_to_cold() {
+                Some(time_to_cold) => time_to_cold,
+                None => continue,
+            };
+            let time_to_expired = db_info.schema.time_to_expired();
+            let placement = *db_info.schema.config.replica_placement();
+            for bucket in db_info.buckets {
+                // Expired buckets are going to be deleted.
+                if bucket.end_time > time_to_cold || bucket.end_time < time_to_expired {
+                    continue;
+                }
+                for replica in bucket.shard_group {
+                    replicas.push((tenant.name().to_string(), replica, placement));
+                }
+            }
+        }
+    }
+
+    Ok(plan_cold_moves(&nodes, &cold_nodes, replicas))
+}
+
+/// Move each running vnode on a hot node to the cold node with the least vnodes moved to,
+/// ties are broken by free disk space. A vnode is left on the hot node if no cold node
+/// is allowed by the replica placement of its database.
+fn plan_cold_moves(
+    nodes: &HashMap<NodeId, NodeInfo>,
+    cold_nodes: &[NodeMetrics],
+    replicas: Vec<(String, ReplicationSet, Option<ReplicaPlacement>)>,
+) -> Vec<VnodeMove> {
+    let mut moves = vec![];
+    let mut moved_to: HashMap<NodeId, usize> = HashMap::new();
+    for (tenant, mut replica, placement) in replicas {
+        let hot_vnodes: Vec<VnodeId> = replica
+            .vnodes
+            .iter()
+            .filter(|v| {
+                v.status == VnodeStatus::Running
+                    && nodes
+                        .get(&v.node_id)
+                        .is_some_and(|n| n.tier == NodeTier::Hot)
+            })
+            .map(|v| v.id)
+            .collect();
+
+        for vnode_id in hot_vnodes {
+            let to_node_id = cold_nodes
+                .iter()
+                .filter(|m| replica.by_node_id(m.id).is_none())
+                .filter(|m| {
+                    placement.map_or(true, |placement| {
+                        replica_placement_allows(&placement, nodes, &replica, Some(vnode_id), m.id)
+                    })
+                })
+                .min_by_key(|m| {
+                    (
+                        moved_to.get(&m.id).copied().unwrap_or(0),
+                        Reverse(m.disk_free),
+                        m.id,
+                    )
+                })
+                .map(|m| m.id);
+            let to_node_id = match to_node_id {
+                Some(id) => id,
+                None => {
+                    warn!(
+                        "No cold node for vnode {} of replication set {}",
+                        vnode_id, replica.id
+                    );
+                    continue;
+                }
+            };
+
+            let vnode = match replica.vnodes.iter_mut().find(|v| v.id == vnode_id) {
+                Some(vnode) => vnode,
+                None => continue,
+            };
+            let from_node_id = vnode.node_id;
+            vnode.node_id = to_node_id;
+            *moved_to.entry(to_node_id).or_default() += 1;
+            moves.push(VnodeMove {
+                tenant: tenant.clone(),
+                replica_id: replica.id,
+                vnode_id,
+                from_node_id,
+                to_node_id,
+            });
+        }
+    }
+
+    moves
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashMap;
+
+    use models::meta_data::{NodeTier, ReplicationSet, VnodeInfo};
+
+    use super::plan_cold_moves;
+    use crate::test_util::{node, node_info};
+
+    #[test]
+    fn test_plan_cold_moves() {
+        let nodes = HashMap::from([
+            node_info(1, NodeTier::Hot, "z1"),
+            node_info(2, NodeTier::Hot, "z1"),
+            node_info(3, NodeTier::Cold, "z1"),
+            node_info(4, NodeTier::Cold, "z1"),
+        ]);
+        let cold_nodes = vec![node(3, 100), node(4, 200)];
+
+        // The vnode of replication set 1 on node 3 is already cold.
+        let replicas = vec![
+            (
+                "cnosdb".to_string(),
+                ReplicationSet::new(0, 1, 1, vec![VnodeInfo::new(1, 1), VnodeInfo::new(2, 2)]),
+                None,
+            ),
+            (
+                "cnosdb".to_string(),
+                ReplicationSet::new(3, 1, 4, vec![VnodeInfo::new(4, 1), VnodeInfo::new(5, 3)]),
+                None,
+            ),
+        ];
+
+        let moves = plan_cold_moves(&nodes, &cold_nodes, replicas);
+        assert_eq!(moves.len(), 3);
+        // Node 4 has more free disk space, so it receives vnode first.
+        assert_eq!((moves[0].vnode_id, moves[0].to_node_id), (1, 4));
+        assert_eq!((moves[1].vnode_id, moves[1].to_node_id), (2, 3));
+        assert_eq!((moves[2].vnode_id, moves[2].to_node_id), (4, 4));
+        for vnode_move in moves.iter() {
+            assert!(vnode_move.from_node_id == 1 || vnode_move.from_node_id == 2);
+        }
+
+        let moves = plan_cold_moves(&nodes, &[], vec![]);
+        assert!(moves.is_empty());
+    }
+}
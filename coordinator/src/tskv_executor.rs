@@ -38,7 +38,8 @@ impl TskvAdminRequest {
 
     async fn warp_do_request(&self, node_id: u64) -> CoordinatorResult<Vec<u8>> {
         let channel = self.meta.get_node_conn(node_id).await.map_err(|error| {
-            CoordinatorError::PreExecution {
+            CoordinatorError::NodeUnreachable {
+                node_id,
                 error: error.to_string(),
             }
         })?;
@@ -51,7 +52,11 @@ impl TskvAdminRequest {
 
         info!("Call node {}, admin request: {:?}", node_id, self.request);
         let request = tonic::Request::new(self.request.clone());
-        let response = client.admin_request(request).await?.into_inner();
+        let response = client
+            .admin_request(request)
+            .await
+            .map_err(|status| CoordinatorError::from_node_status(node_id, status))?
+            .into_inner();
 
         decode_grpc_response(response)
     }
@@ -89,7 +94,10 @@ impl TskvLeaderExecutor {
             };
 
             result = caller.call(replica, vnode.node_id).await;
-            if let Err(CoordinatorError::PreExecution { .. }) = &result {
+            if let Err(
+                CoordinatorError::PreExecution { .. } | CoordinatorError::NodeUnreachable { .. },
+            ) = &result
+            {
                 continue;
             } else if let Err(CoordinatorError::RaftForwardToLeader {
                 replica_id: _,
@@ -39,7 +39,11 @@ impl CallHeaderAuthenticator for BasicCallHeaderAuthenticator {
 
         let user = self
             .instance
-            .authenticate(&user_info, tenant.as_deref().unwrap_or(DEFAULT_CATALOG))
+            .authenticate(
+                &user_info,
+                tenant.as_deref().unwrap_or(DEFAULT_CATALOG),
+                None,
+            )
             .await
             .map_err(|e| Status::unauthenticated(e.to_string()))?;
 
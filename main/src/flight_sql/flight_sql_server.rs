@@ -1,7 +1,11 @@
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
 use std::pin::Pin;
 use std::sync::Arc;
 use std::time::Duration;
 
+use arrow_flight::decode::FlightRecordBatchStream;
+use arrow_flight::error::FlightError;
 use arrow_flight::flight_service_server::FlightService;
 use arrow_flight::sql::server::FlightSqlService;
 use arrow_flight::sql::{
@@ -21,14 +25,21 @@ use arrow_flight::{
     HandshakeRequest, HandshakeResponse, IpcMessage, Ticket,
 };
 use datafusion::arrow::datatypes::{Schema, SchemaRef, ToByteSlice};
-use futures::Stream;
-use http_protocol::header::{DB, STREAM_TRIGGER_INTERVAL, TARGET_PARTITIONS, TENANT};
+use datafusion::scalar::ScalarValue;
+use futures::{Stream, TryStreamExt};
+use http_protocol::header::{
+    BEARER_PREFIX, CACHE, CONSISTENCY, DB, MATERIALIZED_VIEW_REWRITE, STALE_READ_MAX_LAG,
+    STREAM_TRIGGER_INTERVAL, TARGET_PARTITIONS, TENANT,
+};
 use models::auth::user::User;
+use models::consistency_level::ConsistencyLevel;
 use models::oid::UuidGenerator;
 use moka::sync::Cache;
 use prost::bytes::Bytes;
 use prost::Message;
-use spi::query::config::StreamTriggerInterval;
+use spi::query::config::{
+    CacheMode, MaterializedViewRewrite, StaleReadMaxLag, StreamTriggerInterval,
+};
 use spi::query::execution::{Output, QueryStateMachineRef};
 use spi::query::logical_planner::Plan;
 use spi::server::dbms::DBMSRef;
@@ -44,12 +55,27 @@ use crate::flight_sql::utils;
 use crate::status;
 
 const UNKNOWN_AFFECTED_ROWS_COUNT: i64 = -1;
+/// Prepared statements not used for this long are closed,
+/// the same as the bearer tokens of the connections
+const PREPARED_STATEMENT_IDLE_TIMEOUT: Duration = Duration::from_secs(10 * 60);
+
+/// A prepared statement of a client.
+///
+/// It's planned on each execution as a parameterized query with a new query id,
+/// the plan is cached by the dispatcher so it's not planned again.
+#[derive(Clone)]
+struct PreparedStatement {
+    sql: String,
+    // values of the placeholders bound by the client
+    params: Vec<ScalarValue>,
+}
 
 pub struct FlightSqlServiceImpl<T> {
     instance: DBMSRef,
     authenticator: T,
     id_generator: UuidGenerator,
     result_cache: Cache<Vec<u8>, (Option<Plan>, QueryStateMachineRef)>,
+    prepared_statements: Cache<Vec<u8>, PreparedStatement>,
 }
 
 impl<T> FlightSqlServiceImpl<T> {
@@ -59,12 +85,17 @@ impl<T> FlightSqlServiceImpl<T> {
             // The query results are only cached for 2 minutes and expire after 2 minutes
             .time_to_live(Duration::from_secs(2 * 60))
             .build();
+        // closed by the client, or once not used
+        let prepared_statements = Cache::builder()
+            .time_to_idle(PREPARED_STATEMENT_IDLE_TIMEOUT)
+            .build();
 
         Self {
             instance,
             authenticator,
             id_generator: Default::default(),
             result_cache,
+            prepared_statements,
         }
     }
 }
@@ -76,6 +107,7 @@ where
     async fn pre_precess_statement_query_req(
         &self,
         sql: impl Into<String>,
+        params: Option<Vec<ScalarValue>>,
         req_headers: &MetadataMap,
         span_ctx: Option<&SpanContext>,
     ) -> Result<(Option<Plan>, QueryStateMachineRef), Status> {
@@ -89,7 +121,7 @@ where
         // construct context by user_info and headers(parse tenant & default database)
         let ctx = {
             let _span = Span::from_context("construct context", span_ctx);
-            self.construct_context(user, req_headers)?
+            self.construct_context(user, params, req_headers)?
         };
 
         // build query state machine
@@ -108,11 +140,12 @@ where
     async fn pre_precess_statement_query_req_and_save(
         &self,
         sql: impl Into<String>,
+        params: Option<Vec<ScalarValue>>,
         req_headers: &MetadataMap,
         span_ctx: Option<&SpanContext>,
     ) -> Result<(Vec<u8>, SchemaRef), Status> {
         let (logical_plan, query_state_machine) = self
-            .pre_precess_statement_query_req(sql, req_headers, span_ctx)
+            .pre_precess_statement_query_req(sql, params, req_headers, span_ctx)
             .await?;
 
         let schema = logical_plan
@@ -137,7 +170,7 @@ where
         span_ctx: Option<&SpanContext>,
     ) -> Result<Response<FlightInfo>, Status> {
         let (result_ident, schema) = self
-            .pre_precess_statement_query_req_and_save(sql, request.metadata(), span_ctx)
+            .pre_precess_statement_query_req_and_save(sql, None, request.metadata(), span_ctx)
             .await?;
 
         let ticket = TicketStatementQuery {
@@ -178,7 +211,12 @@ where
         Ok(flight_info)
     }
 
-    fn construct_context(&self, user: User, metadata: &MetadataMap) -> Result<Context, Status> {
+    fn construct_context(
+        &self,
+        user: User,
+        params: Option<Vec<ScalarValue>>,
+        metadata: &MetadataMap,
+    ) -> Result<Context, Status> {
         // parse tenant & default database
         let tenant = utils::get_value_from_header(metadata, TENANT, "");
         let db = utils::get_value_from_header(metadata, DB, "");
@@ -201,11 +239,48 @@ where
                         STREAM_TRIGGER_INTERVAL, e
                     ))
                 })?;
+        let stale_read_max_lag = utils::get_value_from_header(metadata, STALE_READ_MAX_LAG, "")
+            .map(|e| e.parse::<StaleReadMaxLag>())
+            .transpose()
+            .map_err(|e| {
+                Status::invalid_argument(format!(
+                    "parse {} failed, error: {}",
+                    STALE_READ_MAX_LAG, e
+                ))
+            })?;
+        let consistency = utils::get_value_from_header(metadata, CONSISTENCY, "")
+            .map(|e| e.parse::<ConsistencyLevel>())
+            .transpose()
+            .map_err(|e| {
+                Status::invalid_argument(format!("parse {} failed, error: {}", CONSISTENCY, e))
+            })?;
+        let cache_mode = utils::get_value_from_header(metadata, CACHE, "")
+            .map(|e| e.parse::<CacheMode>())
+            .transpose()
+            .map_err(|e| {
+                Status::invalid_argument(format!("parse {} failed, error: {}", CACHE, e))
+            })?;
+        let materialized_view_rewrite =
+            utils::get_value_from_header(metadata, MATERIALIZED_VIEW_REWRITE, "")
+                .map(|e| e.parse::<MaterializedViewRewrite>())
+                .transpose()
+                .map_err(|e| {
+                    Status::invalid_argument(format!(
+                        "parse {} failed, error: {}",
+                        MATERIALIZED_VIEW_REWRITE, e
+                    ))
+                })?;
         let ctx = ContextBuilder::new(user)
             .with_tenant(tenant)
             .with_database(db)
             .with_target_partitions(target_partitions)
             .with_stream_trigger_interval(stream_trigger_interval)
+            .with_stale_read_max_lag(stale_read_max_lag)
+            .with_consistency_level(consistency)
+            .with_cache_mode(cache_mode)
+            .with_materialized_view_rewrite(materialized_view_rewrite)
+            .with_params(params)
+            .with_session_id(session_id(metadata))
             .build();
 
         Ok(ctx)
@@ -276,14 +351,31 @@ where
         Ok((logical_plan, query_state_machine))
     }
 
-    async fn execute_and_fetch_result_set(
+    /// Plan the prepared statement with the bound parameters, as a new query
+    async fn pre_precess_prepared_statement_req(
         &self,
-        statement_handle: &[u8],
+        prepared_statement_handle: &[u8],
+        req_headers: &MetadataMap,
         span_ctx: Option<&SpanContext>,
-    ) -> Result<<Self as FlightService>::DoGetStream, Status> {
-        let (logical_plan, query_state_machine) =
-            self.get_plan_and_qsm(statement_handle, span_ctx.cloned())?;
+    ) -> Result<(Option<Plan>, QueryStateMachineRef), Status> {
+        let PreparedStatement { sql, params } = self
+            .prepared_statements
+            .get(prepared_statement_handle)
+            .ok_or_else(|| {
+                Status::not_found(format!(
+                    "Prepared statement({:?}) does not exist or has expired",
+                    prepared_statement_handle
+                ))
+            })?;
+        self.pre_precess_statement_query_req(sql, Some(params), req_headers, span_ctx)
+            .await
+    }
 
+    async fn execute_and_fetch_result_set(
+        &self,
+        logical_plan: Option<Plan>,
+        query_state_machine: QueryStateMachineRef,
+    ) -> Result<<Self as FlightService>::DoGetStream, Status> {
         // execute plan
         let query_result = self
             .execute_logical_plan(logical_plan, query_state_machine)
@@ -342,7 +434,7 @@ where
 /// ```
 /// 1. do_handshake: basic auth -> baerar token
 /// 2. do_action_create_prepared_statement: sql(baerar token) -> sql
-/// 3. do_put_prepared_statement_update: not use
+/// 3. do_put_prepared_statement_query: bind the parameters of the prepared statement, if any
 /// 4. get_flight_info_prepared_statement: sql(baerar token) -> address of resut set
 /// 5. do_get_statement: address of resut set(baerar token) -> resut set stream
 /// ```
@@ -429,7 +521,13 @@ where
         );
 
         let statement_handle = query.prepared_statement_handle.to_byte_slice();
-        let (plan, _) = self.get_plan_and_qsm(statement_handle, span.context())?;
+        let (plan, _) = self
+            .pre_precess_prepared_statement_req(
+                statement_handle,
+                request.metadata(),
+                span.context().as_ref(),
+            )
+            .await?;
         let schema = plan
             .map(|e| e.schema())
             .unwrap_or(Arc::new(Schema::empty()));
@@ -694,8 +792,10 @@ where
 
         let TicketStatementQuery { statement_handle } = ticket;
 
+        let (logical_plan, query_state_machine) =
+            self.get_plan_and_qsm(&statement_handle, span.context())?;
         let output = self
-            .execute_and_fetch_result_set(&statement_handle, span.context().as_ref())
+            .execute_and_fetch_result_set(logical_plan, query_state_machine)
             .await?;
 
         // clear cache of this query
@@ -722,14 +822,18 @@ where
 
         let prepared_statement_handle = query.prepared_statement_handle.to_byte_slice();
 
+        // each execution is a new query
+        let (logical_plan, query_state_machine) = self
+            .pre_precess_prepared_statement_req(
+                prepared_statement_handle,
+                request.metadata(),
+                span.context().as_ref(),
+            )
+            .await?;
         let output = self
-            .execute_and_fetch_result_set(prepared_statement_handle, span.context().as_ref())
+            .execute_and_fetch_result_set(logical_plan, query_state_machine)
             .await?;
 
-        // clear cache of this query
-        self.result_cache
-            .invalidate(prepared_statement_handle.to_byte_slice());
-
         Ok(Response::new(output))
     }
 
@@ -871,7 +975,7 @@ where
         let req_headers = request.metadata();
 
         let (logical_plan, query_state_machine) = self
-            .pre_precess_statement_query_req(query, req_headers, span_ctx.as_ref())
+            .pre_precess_statement_query_req(query, None, req_headers, span_ctx.as_ref())
             .await?;
 
         // execute plan
@@ -884,7 +988,10 @@ where
         Ok(affected_rows)
     }
 
-    /// not support
+    /// Bind the values of the placeholders of the prepared statement,
+    /// taken from the row of the uploaded record batches.
+    ///
+    /// Binding several rows to execute the statement for each of them is not supported.
     async fn do_put_prepared_statement_query(
         &self,
         query: CommandPreparedStatementQuery,
@@ -895,16 +1002,52 @@ where
             query, request
         );
 
-        Err(Status::unimplemented(
-            "do_put_prepared_statement_query not implemented",
-        ))
+        let prepared_statement_handle = query.prepared_statement_handle.to_vec();
+        let statement = self
+            .prepared_statements
+            .get(&prepared_statement_handle)
+            .ok_or_else(|| {
+                Status::not_found(format!(
+                    "Prepared statement({:?}) does not exist or has expired",
+                    prepared_statement_handle
+                ))
+            })?;
+
+        let batches: Vec<_> = FlightRecordBatchStream::new_from_flight_data(
+            request.into_inner().map_err(FlightError::Tonic),
+        )
+        .try_collect()
+        .await?;
+        let num_rows = batches.iter().map(|b| b.num_rows()).sum::<usize>();
+        if num_rows > 1 {
+            return Err(Status::invalid_argument(format!(
+                "Only one row of parameters can be bound to a prepared statement, found {}",
+                num_rows
+            )));
+        }
+        let params = match batches.iter().find(|b| b.num_rows() > 0) {
+            Some(batch) => batch
+                .columns()
+                .iter()
+                .map(|column| ScalarValue::try_from_array(column, 0))
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(|e| status!("Decode parameters", e))?,
+            None => vec![],
+        };
+        self.prepared_statements.insert(
+            prepared_statement_handle,
+            PreparedStatement {
+                params,
+                ..statement
+            },
+        );
+
+        let stream: <Self as FlightService>::DoPutStream = Box::pin(futures::stream::empty());
+        Ok(Response::new(stream))
     }
 
     /// Execute the query and return the number of affected rows.
     /// The prepared statement can be reused afterwards.
-    ///
-    /// Prepared statement is not supported,
-    /// because ad-hoc statement of flight jdbc needs to call this interface, so it is simple to implement
     async fn do_put_prepared_statement_update(
         &self,
         query: CommandPreparedStatementUpdate,
@@ -919,17 +1062,23 @@ where
             request.extensions(),
             "flight sql do_put_prepared_statement_update",
         );
-        let (plan, query_machine) =
-            self.get_plan_and_qsm(prepared_statement_ident, span.context())?;
+        let (plan, query_machine) = self
+            .pre_precess_prepared_statement_req(
+                prepared_statement_ident,
+                request.metadata(),
+                span.context().as_ref(),
+            )
+            .await?;
         // execute plan
         let query_result = self.execute_logical_plan(plan, query_machine).await?;
         let output = query_result.result();
         Ok(output.affected_rows().await)
     }
 
-    /// Prepared statement is not supported,
-    /// because ad-hoc statement of flight jdbc needs to call this interface,
-    /// so directly return the sql as the result.
+    /// Plan the sql and return the handle of the prepared statement.
+    ///
+    /// The plan is cached by the dispatcher with the plans of parameterized queries,
+    /// so preparing the same sql again by the user is not planned again.
     async fn do_action_create_prepared_statement(
         &self,
         query: ActionCreatePreparedStatementRequest,
@@ -947,13 +1096,27 @@ where
         // ignore transaction_id
         let ActionCreatePreparedStatementRequest { query: sql, .. } = query;
 
-        let (result_ident, schema) = self
-            .pre_precess_statement_query_req_and_save(
-                sql,
+        let (logical_plan, _) = self
+            .pre_precess_statement_query_req(
+                sql.clone(),
+                Some(vec![]),
                 request.metadata(),
                 span.context().as_ref(),
             )
             .await?;
+        let schema = logical_plan
+            .as_ref()
+            .map(|e| e.schema())
+            .unwrap_or(Arc::new(Schema::empty()));
+
+        let result_ident = self.id_generator.next_id().to_le_bytes().to_vec();
+        self.prepared_statements.insert(
+            result_ident.clone(),
+            PreparedStatement {
+                sql,
+                params: vec![],
+            },
+        );
 
         let IpcMessage(dataset_schema) = utils::schema_to_ipc_message(schema.as_ref())
             .map_err(|e| status!("Schema to ipc message", e))?;
@@ -970,8 +1133,6 @@ where
     }
 
     /// Close a previously created prepared statement.
-    ///
-    /// Empty logic, because we not save created prepared statement.
     async fn do_action_close_prepared_statement(
         &self,
         query: ActionClosePreparedStatementRequest,
@@ -982,6 +1143,10 @@ where
             query, request
         );
 
+        let prepared_statement_handle = query.prepared_statement_handle.to_byte_slice();
+        self.prepared_statements
+            .invalidate(prepared_statement_handle);
+
         Ok(())
     }
 
@@ -1083,6 +1248,16 @@ where
     }
 }
 
+/// The bearer token of the connection identifies the session of the requests,
+/// only its hash is kept.
+fn session_id(metadata: &MetadataMap) -> Option<String> {
+    utils::get_value_from_auth_header(metadata, BEARER_PREFIX).map(|token| {
+        let mut hasher = DefaultHasher::new();
+        token.hash(&mut hasher);
+        format!("flight-{:016x}", hasher.finish())
+    })
+}
+
 fn get_span(extensions: &Extensions, child_span_name: &'static str) -> Span {
     let span_context = extensions.get::<SpanContext>();
     Span::from_context(child_span_name, span_context)
@@ -9,6 +9,7 @@ pub enum HttpApiType {
     ApiV1PromWrite,
 
     ApiV1Sql,
+    ApiV1Query,
     ApiV1PromRead,
     ApiV1ESLogWrite,
 
@@ -42,6 +43,9 @@ impl Display for HttpApiType {
             HttpApiType::ApiV1Sql => {
                 write!(f, "api/v1/sql")
             }
+            HttpApiType::ApiV1Query => {
+                write!(f, "api/v1/query")
+            }
             HttpApiType::ApiV1PromRead => {
                 write!(f, "api/v1/prom/read")
             }
@@ -92,6 +96,7 @@ pub fn metrics_record_db(api: &HttpApiType) -> bool {
         | HttpApiType::ApiV1ESLogWrite
         | HttpApiType::ApiV1PromRead => true,
         HttpApiType::ApiV1Sql
+        | HttpApiType::ApiV1Query
         | HttpApiType::ApiV1Ping
         | HttpApiType::DebugBacktrace
         | HttpApiType::Write
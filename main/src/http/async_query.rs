@@ -0,0 +1,799 @@
+//! Queries submitted by `POST /api/v1/query` run in the background.
+//!
+//! Results are spooled to local disk as pages of Arrow IPC files, fetched page by page
+//! by `GET /api/v1/query/{id}/result?cursor=`, and removed after they finished for a TTL.
+//!
+//! Async queries are kept in memory of the node they were submitted to, their status and
+//! results can only be fetched from that node, e.g. with sticky sessions of load balancers.
+
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Weak};
+use std::time::{Duration, Instant};
+
+use datafusion::arrow::datatypes::SchemaRef;
+use datafusion::arrow::ipc::reader::FileReader;
+use datafusion::arrow::ipc::writer::FileWriter;
+use datafusion::arrow::record_batch::RecordBatch;
+use futures::StreamExt;
+use parking_lot::{Mutex, RwLock};
+use serde::Serialize;
+use spi::query::execution::Output;
+use spi::service::protocol::{QueryHandle, QueryId};
+use trace::{debug, warn};
+
+use super::Error as HttpError;
+
+pub const ASYNC_QUERY_DIRECTORY_NAME: &str = "async_query";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AsyncQueryStatus {
+    Running,
+    Finished,
+    Failed,
+}
+
+/// Status and progress of an async query
+#[derive(Debug, Serialize)]
+pub struct AsyncQueryInfo {
+    /// As a string, the id may exceed the max safe integer of json parsers
+    pub query_id: String,
+    pub status: AsyncQueryStatus,
+    pub sql: String,
+    /// Number of rows spooled so far
+    pub rows: usize,
+    /// Number of pages spooled so far
+    pub pages: usize,
+    pub elapsed_ms: u64,
+    pub error: Option<String>,
+}
+
+/// A page of the result of an async query
+pub struct Page {
+    pub schema: SchemaRef,
+    pub batches: Vec<RecordBatch>,
+    /// None if this is the last page
+    pub next_cursor: Option<usize>,
+}
+
+struct AsyncQueryState {
+    status: AsyncQueryStatus,
+    rows: usize,
+    pages: usize,
+    bytes: u64,
+    error: Option<String>,
+    finished_at: Option<Instant>,
+    /// The spooling task is still running, it may be writing a page
+    spooling: bool,
+    /// The results are removed by the spooling task when it ends
+    removed: bool,
+}
+
+pub struct AsyncQuery {
+    id: QueryId,
+    tenant: String,
+    user: String,
+    sql: String,
+    schema: SchemaRef,
+    dir: PathBuf,
+    submitted_at: Instant,
+    state: Mutex<AsyncQueryState>,
+    /// Bytes of the results of all async queries
+    total_bytes: Arc<AtomicU64>,
+}
+
+impl AsyncQuery {
+    pub fn id(&self) -> QueryId {
+        self.id
+    }
+
+    pub fn info(&self) -> AsyncQueryInfo {
+        let state = self.state.lock();
+        let elapsed = state
+            .finished_at
+            .unwrap_or_else(Instant::now)
+            .duration_since(self.submitted_at);
+        AsyncQueryInfo {
+            query_id: self.id.to_string(),
+            status: state.status,
+            sql: self.sql.clone(),
+            rows: state.rows,
+            pages: state.pages,
+            elapsed_ms: elapsed.as_millis() as u64,
+            error: state.error.clone(),
+        }
+    }
+
+    /// The page at the cursor, empty with the same cursor if it's not spooled yet
+    pub async fn page(&self, cursor: usize) -> Result<Page, HttpError> {
+        let (status, pages) = {
+            let state = self.state.lock();
+            if let Some(reason) = &state.error {
+                return Err(HttpError::AsyncQueryFailed {
+                    query_id: self.id.to_string(),
+                    reason: reason.clone(),
+                });
+            }
+            (state.status, state.pages)
+        };
+
+        if cursor >= pages {
+            let next_cursor = (status == AsyncQueryStatus::Running).then_some(cursor);
+            return Ok(Page {
+                schema: self.schema.clone(),
+                batches: vec![],
+                next_cursor,
+            });
+        }
+
+        let path = self.page_path(cursor);
+        let batches =
+            blocking(move || read_page(&path))
+                .await
+                .map_err(|reason| HttpError::SpoolResult {
+                    reason: format!("read page {} of query {}: {}", cursor, self.id, reason),
+                })?;
+        let next_cursor =
+            (cursor + 1 < pages || status == AsyncQueryStatus::Running).then_some(cursor + 1);
+        Ok(Page {
+            schema: self.schema.clone(),
+            batches,
+            next_cursor,
+        })
+    }
+
+    fn page_path(&self, page: usize) -> PathBuf {
+        self.dir.join(format!("{}.arrow", page))
+    }
+
+    fn is_expired(&self, ttl: Duration) -> bool {
+        self.state
+            .lock()
+            .finished_at
+            .is_some_and(|finished_at| finished_at.elapsed() > ttl)
+    }
+
+    fn finish(&self, result: Result<(), String>) {
+        let mut state = self.state.lock();
+        if state.finished_at.is_some() {
+            return;
+        }
+        match result {
+            Ok(()) => state.status = AsyncQueryStatus::Finished,
+            Err(reason) => {
+                state.status = AsyncQueryStatus::Failed;
+                state.error = Some(reason);
+            }
+        }
+        state.finished_at = Some(Instant::now());
+    }
+
+    fn is_running(&self) -> bool {
+        self.state.lock().status == AsyncQueryStatus::Running
+    }
+
+    /// Remove the results, or let the spooling task remove them when it ends
+    /// if it may still be writing a page
+    fn remove_results(&self) {
+        let spooling = {
+            let mut state = self.state.lock();
+            state.removed = true;
+            state.spooling
+        };
+        if !spooling {
+            self.remove_spooled();
+        }
+    }
+
+    /// Called by the spooling task when it ends
+    fn spooled(&self) {
+        let removed = {
+            let mut state = self.state.lock();
+            state.spooling = false;
+            state.removed
+        };
+        if removed {
+            self.remove_spooled();
+        }
+    }
+
+    fn remove_spooled(&self) {
+        let bytes = self.state.lock().bytes;
+        self.total_bytes.fetch_sub(bytes, Ordering::Relaxed);
+        remove_results(self.dir.clone());
+    }
+
+    /// Write the result to pages of at most `page_rows` rows
+    async fn spool(
+        &self,
+        mut output: Output,
+        page_rows: usize,
+        max_result_size: u64,
+        max_total_size: u64,
+    ) -> Result<(), String> {
+        let schema = self.schema.clone();
+        let mut page: Option<PageWriter> = None;
+
+        while let Some(batch) = output.next().await {
+            if self.state.lock().finished_at.is_some() {
+                // cancelled
+                return Ok(());
+            }
+            let batch = batch.map_err(|e| e.to_string())?;
+            let mut offset = 0;
+            while offset < batch.num_rows() {
+                let mut writer = match page.take() {
+                    Some(writer) => writer,
+                    None => {
+                        let path = self.page_path(self.state.lock().pages);
+                        let schema = schema.clone();
+                        blocking(move || PageWriter::try_new(&path, &schema)).await?
+                    }
+                };
+                let len = (page_rows - writer.rows).min(batch.num_rows() - offset);
+                let slice = batch.slice(offset, len);
+                offset += len;
+                let writer = blocking(move || {
+                    writer.write(&slice)?;
+                    Ok(writer)
+                })
+                .await?;
+
+                // checked for each batch, a page may be much larger than the limit
+                let bytes = self.state.lock().bytes + writer.bytes();
+                if bytes > max_result_size {
+                    return Err(format!(
+                        "the result exceeds the max size {} of async queries",
+                        max_result_size
+                    ));
+                }
+                let total_bytes = self.total_bytes.load(Ordering::Relaxed) + writer.bytes();
+                if total_bytes > max_total_size {
+                    return Err(format!(
+                        "the results of async queries exceed the max total size {}",
+                        max_total_size
+                    ));
+                }
+
+                if writer.rows == page_rows {
+                    self.finish_page(writer).await?;
+                } else {
+                    page = Some(writer);
+                }
+            }
+        }
+        if let Some(writer) = page {
+            self.finish_page(writer).await?;
+        }
+
+        Ok(())
+    }
+
+    async fn finish_page(&self, writer: PageWriter) -> Result<(), String> {
+        let (rows, bytes) = blocking(move || writer.finish()).await?;
+
+        let mut state = self.state.lock();
+        state.bytes += bytes;
+        self.total_bytes.fetch_add(bytes, Ordering::Relaxed);
+        state.rows += rows;
+        state.pages += 1;
+        Ok(())
+    }
+}
+
+/// Run blocking file I/O without blocking the async runtime
+async fn blocking<T, F>(f: F) -> Result<T, String>
+where
+    T: Send + 'static,
+    F: FnOnce() -> Result<T, String> + Send + 'static,
+{
+    tokio::task::spawn_blocking(f)
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+/// A page being written
+struct PageWriter {
+    writer: FileWriter<CountingWriter>,
+    rows: usize,
+    bytes: Arc<AtomicU64>,
+}
+
+impl PageWriter {
+    fn try_new(path: &Path, schema: &SchemaRef) -> Result<Self, String> {
+        let file = File::create(path).map_err(|e| e.to_string())?;
+        let bytes = Arc::new(AtomicU64::new(0));
+        let counting = CountingWriter {
+            inner: BufWriter::new(file),
+            bytes: bytes.clone(),
+        };
+        let writer = FileWriter::try_new(counting, schema).map_err(|e| e.to_string())?;
+        Ok(Self {
+            writer,
+            rows: 0,
+            bytes,
+        })
+    }
+
+    fn write(&mut self, batch: &RecordBatch) -> Result<(), String> {
+        self.writer.write(batch).map_err(|e| e.to_string())?;
+        self.rows += batch.num_rows();
+        Ok(())
+    }
+
+    /// Bytes written so far
+    fn bytes(&self) -> u64 {
+        self.bytes.load(Ordering::Relaxed)
+    }
+
+    /// Returns the number of rows and bytes of the page
+    fn finish(mut self) -> Result<(usize, u64), String> {
+        self.writer.finish().map_err(|e| e.to_string())?;
+        let mut counting = self.writer.into_inner().map_err(|e| e.to_string())?;
+        counting.flush().map_err(|e| e.to_string())?;
+        Ok((self.rows, self.bytes()))
+    }
+}
+
+/// Counts the bytes written to a page
+struct CountingWriter {
+    inner: BufWriter<File>,
+    bytes: Arc<AtomicU64>,
+}
+
+impl Write for CountingWriter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let n = self.inner.write(buf)?;
+        self.bytes.fetch_add(n as u64, Ordering::Relaxed);
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+fn read_page(path: &Path) -> Result<Vec<RecordBatch>, String> {
+    let file = File::open(path).map_err(|e| e.to_string())?;
+    let reader = FileReader::try_new(file, None).map_err(|e| e.to_string())?;
+    reader
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())
+}
+
+pub struct AsyncQueryManager {
+    dir: PathBuf,
+    ttl: Duration,
+    page_rows: usize,
+    max_result_size: u64,
+    max_total_size: u64,
+    max_running_per_user: usize,
+    queries: RwLock<HashMap<QueryId, Arc<AsyncQuery>>>,
+    /// Bytes of the finished pages of all queries
+    total_bytes: Arc<AtomicU64>,
+}
+
+impl AsyncQueryManager {
+    /// Results left by the previous run under `dir` are removed
+    pub fn new(
+        dir: PathBuf,
+        ttl: Duration,
+        page_rows: usize,
+        max_result_size: u64,
+        max_total_size: u64,
+        max_running_per_user: usize,
+    ) -> Self {
+        if let Err(e) = std::fs::remove_dir_all(&dir) {
+            if e.kind() != std::io::ErrorKind::NotFound {
+                warn!("Failed to remove results of async queries {:?}: {}", dir, e);
+            }
+        }
+
+        Self {
+            dir,
+            ttl,
+            page_rows: page_rows.max(1),
+            max_result_size,
+            max_total_size,
+            max_running_per_user,
+            queries: Default::default(),
+            total_bytes: Default::default(),
+        }
+    }
+
+    /// Remove the expired results periodically, until the manager is dropped
+    pub fn start_expiration(self: &Arc<Self>) {
+        let manager = Arc::downgrade(self);
+        let period = self
+            .ttl
+            .clamp(Duration::from_secs(1), Duration::from_secs(60));
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(period);
+            loop {
+                interval.tick().await;
+                match Weak::upgrade(&manager) {
+                    Some(manager) => manager.expire(),
+                    None => return,
+                }
+            }
+        });
+    }
+
+    /// Fails if the user of the tenant has too many running async queries
+    pub fn check_running(&self, tenant: &str, user: &str) -> Result<(), HttpError> {
+        self.check_running_of(&self.queries.read(), tenant, user)
+    }
+
+    fn check_running_of(
+        &self,
+        queries: &HashMap<QueryId, Arc<AsyncQuery>>,
+        tenant: &str,
+        user: &str,
+    ) -> Result<(), HttpError> {
+        let running = queries
+            .values()
+            .filter(|query| query.tenant == tenant && query.user == user && query.is_running())
+            .count();
+        if running >= self.max_running_per_user {
+            return Err(HttpError::TooManyAsyncQueries {
+                user: user.to_string(),
+                limit: self.max_running_per_user,
+            });
+        }
+        Ok(())
+    }
+
+    /// Spool the result of the query in the background
+    pub async fn submit(
+        &self,
+        handle: QueryHandle,
+        tenant: &str,
+        user: &str,
+    ) -> Result<Arc<AsyncQuery>, HttpError> {
+        let id = handle.id();
+        let dir = self.dir.join(id.to_string());
+        let sql = handle.query().content().to_string();
+        let output = handle.result();
+        let query = Arc::new(AsyncQuery {
+            id,
+            tenant: tenant.to_string(),
+            user: user.to_string(),
+            sql,
+            schema: output.schema(),
+            dir,
+            submitted_at: Instant::now(),
+            state: Mutex::new(AsyncQueryState {
+                status: AsyncQueryStatus::Running,
+                rows: 0,
+                pages: 0,
+                bytes: 0,
+                error: None,
+                finished_at: None,
+                spooling: true,
+                removed: false,
+            }),
+            total_bytes: self.total_bytes.clone(),
+        });
+        {
+            let mut queries = self.queries.write();
+            self.check_running_of(&queries, tenant, user)?;
+            queries.insert(id, query.clone());
+        }
+
+        if let Err(e) = tokio::fs::create_dir_all(&query.dir).await {
+            self.queries.write().remove(&id);
+            return Err(HttpError::SpoolResult {
+                reason: format!("create directory {:?}: {}", query.dir, e),
+            });
+        }
+
+        let spooling = query.clone();
+        let page_rows = self.page_rows;
+        let max_result_size = self.max_result_size;
+        let max_total_size = self.max_total_size;
+        tokio::spawn(async move {
+            let result = spooling
+                .spool(output, page_rows, max_result_size, max_total_size)
+                .await;
+            debug!("Async query {} finished: {:?}", spooling.id, result);
+            spooling.finish(result);
+            spooling.spooled();
+        });
+
+        Ok(query)
+    }
+
+    /// The query submitted by the user of the tenant
+    pub fn get(&self, id: &str, tenant: &str, user: &str) -> Result<Arc<AsyncQuery>, HttpError> {
+        id.parse::<u64>()
+            .ok()
+            .and_then(|query_id| self.queries.read().get(&QueryId::from(query_id)).cloned())
+            .filter(|query| query.tenant == tenant && query.user == user)
+            .filter(|query| !query.is_expired(self.ttl))
+            .ok_or_else(|| HttpError::AsyncQueryNotFound {
+                query_id: id.to_string(),
+            })
+    }
+
+    /// Stop spooling the result of the query and remove it
+    pub fn remove(&self, query: &AsyncQuery) {
+        query.finish(Err("cancelled".to_string()));
+        self.queries.write().remove(&query.id);
+        query.remove_results();
+    }
+
+    /// Remove the results finished for longer than the TTL
+    fn expire(&self) {
+        let expired = {
+            let mut queries = self.queries.write();
+            let expired = queries
+                .values()
+                .filter(|query| query.is_expired(self.ttl))
+                .cloned()
+                .collect::<Vec<_>>();
+            for query in &expired {
+                queries.remove(&query.id);
+            }
+            expired
+        };
+        for query in expired {
+            query.remove_results();
+        }
+    }
+}
+
+fn remove_results(dir: PathBuf) {
+    tokio::task::spawn_blocking(move || {
+        if let Err(e) = std::fs::remove_dir_all(&dir) {
+            warn!("Failed to remove results of async query {:?}: {}", dir, e);
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use datafusion::arrow::array::Int32Array;
+    use datafusion::arrow::datatypes::{DataType, Field, Schema};
+    use datafusion::physical_plan::memory::MemoryStream;
+    use datafusion::physical_plan::stream::RecordBatchStreamAdapter;
+    use futures::stream;
+    use models::auth::user::{User, UserDesc, UserOptions};
+    use spi::service::protocol::{ContextBuilder, Query};
+
+    use super::*;
+
+    fn schema() -> SchemaRef {
+        Arc::new(Schema::new(vec![Field::new("a", DataType::Int32, false)]))
+    }
+
+    fn batches(rows: Vec<i32>) -> Vec<RecordBatch> {
+        rows.chunks(3)
+            .map(|chunk| {
+                RecordBatch::try_new(schema(), vec![Arc::new(Int32Array::from(chunk.to_vec()))])
+                    .unwrap()
+            })
+            .collect()
+    }
+
+    fn handle(rows: Vec<i32>) -> QueryHandle {
+        let stream = MemoryStream::try_new(batches(rows), schema(), None).unwrap();
+        query_handle(Output::StreamData(Box::pin(stream)))
+    }
+
+    /// A batch is produced every `delay`
+    fn slow_handle(rows: Vec<i32>, delay: Duration) -> QueryHandle {
+        let stream = stream::iter(batches(rows)).then(move |batch| async move {
+            tokio::time::sleep(delay).await;
+            Ok(batch)
+        });
+        let stream = RecordBatchStreamAdapter::new(schema(), stream);
+        query_handle(Output::StreamData(Box::pin(stream)))
+    }
+
+    fn query_handle(output: Output) -> QueryHandle {
+        let desc = UserDesc::new(0_u128, "root".to_string(), UserOptions::default(), true);
+        let user = User::new(desc, Default::default(), None);
+        let query = Query::new(ContextBuilder::new(user).build(), "select a".to_string());
+        QueryHandle::new(QueryId::next_id(), query, output)
+    }
+
+    async fn wait_finished(query: &AsyncQuery) {
+        while query.info().status == AsyncQueryStatus::Running {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+    }
+
+    #[tokio::test]
+    async fn test_spool_pages() {
+        let dir = std::env::temp_dir().join("cnosdb_test_async_query");
+        let manager =
+            AsyncQueryManager::new(dir, Duration::from_secs(60), 4, u64::MAX, u64::MAX, 8);
+        let query = manager
+            .submit(handle((0..10).collect()), "cnosdb", "root")
+            .await
+            .unwrap();
+        let id = query.id().to_string();
+        assert!(manager.get(&id, "cnosdb", "other").is_err());
+
+        wait_finished(&query).await;
+        let info = query.info();
+        assert_eq!(info.status, AsyncQueryStatus::Finished);
+        assert_eq!((info.rows, info.pages), (10, 3));
+
+        let query = manager.get(&id, "cnosdb", "root").unwrap();
+        let mut cursor = Some(0);
+        let mut pages = vec![];
+        while let Some(c) = cursor {
+            let page = query.page(c).await.unwrap();
+            assert_eq!(page.schema, schema());
+            pages.push(page.batches.iter().map(|b| b.num_rows()).sum::<usize>());
+            cursor = page.next_cursor;
+        }
+        assert_eq!(pages, vec![4, 4, 2]);
+
+        manager.remove(&query);
+        assert!(manager.get(&id, "cnosdb", "root").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_max_result_size() {
+        let dir = std::env::temp_dir().join("cnosdb_test_async_query_max_result_size");
+        let manager = AsyncQueryManager::new(dir, Duration::from_secs(60), 1000, 512, u64::MAX, 8);
+        let query = manager
+            .submit(handle((0..300).collect()), "cnosdb", "root")
+            .await
+            .unwrap();
+
+        // fails before the first page is finished
+        wait_finished(&query).await;
+        let info = query.info();
+        assert_eq!(info.status, AsyncQueryStatus::Failed);
+        assert_eq!(info.pages, 0);
+        assert!(info.error.unwrap().contains("exceeds the max size 512"));
+    }
+
+    #[tokio::test]
+    async fn test_max_total_size() {
+        // the size of the result of a query
+        let dir = std::env::temp_dir().join("cnosdb_test_async_query_result_size");
+        let manager =
+            AsyncQueryManager::new(dir, Duration::from_secs(60), 100, u64::MAX, u64::MAX, 8);
+        let query = manager
+            .submit(handle((0..100).collect()), "cnosdb", "root")
+            .await
+            .unwrap();
+        wait_finished(&query).await;
+        let bytes = manager.total_bytes.load(Ordering::Relaxed);
+        assert!(bytes > 0);
+
+        let max_total_size = bytes + bytes / 2;
+        let dir = std::env::temp_dir().join("cnosdb_test_async_query_max_total_size");
+        let manager = AsyncQueryManager::new(
+            dir,
+            Duration::from_secs(60),
+            100,
+            u64::MAX,
+            max_total_size,
+            8,
+        );
+        let query = manager
+            .submit(handle((0..100).collect()), "cnosdb", "root")
+            .await
+            .unwrap();
+        wait_finished(&query).await;
+        assert_eq!(query.info().status, AsyncQueryStatus::Finished);
+
+        // the results of all users are limited
+        let other = manager
+            .submit(handle((0..100).collect()), "cnosdb", "other")
+            .await
+            .unwrap();
+        wait_finished(&other).await;
+        let info = other.info();
+        assert_eq!(info.status, AsyncQueryStatus::Failed);
+        assert!(info
+            .error
+            .unwrap()
+            .contains(&format!("exceed the max total size {}", max_total_size)));
+
+        // removed results are not counted
+        manager.remove(&query);
+        assert_eq!(manager.total_bytes.load(Ordering::Relaxed), 0);
+        let query = manager
+            .submit(handle((0..100).collect()), "cnosdb", "other")
+            .await
+            .unwrap();
+        wait_finished(&query).await;
+        assert_eq!(query.info().status, AsyncQueryStatus::Finished);
+    }
+
+    #[tokio::test]
+    async fn test_expiration() {
+        let dir = std::env::temp_dir().join("cnosdb_test_async_query_expiration");
+        let manager = Arc::new(AsyncQueryManager::new(
+            dir,
+            Duration::from_millis(100),
+            4,
+            u64::MAX,
+            u64::MAX,
+            8,
+        ));
+        manager.start_expiration();
+        let query = manager
+            .submit(handle((0..10).collect()), "cnosdb", "root")
+            .await
+            .unwrap();
+        wait_finished(&query).await;
+
+        // removed without any request
+        while query.dir.exists() {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        assert!(manager.queries.read().is_empty());
+        assert!(manager
+            .get(&query.id().to_string(), "cnosdb", "root")
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_max_running_per_user() {
+        let dir = std::env::temp_dir().join("cnosdb_test_async_query_max_running");
+        let manager =
+            AsyncQueryManager::new(dir, Duration::from_secs(60), 4, u64::MAX, u64::MAX, 1);
+        let delay = Duration::from_millis(50);
+        let query = manager
+            .submit(slow_handle((0..6).collect(), delay), "cnosdb", "root")
+            .await
+            .unwrap();
+
+        assert!(matches!(
+            manager.check_running("cnosdb", "root"),
+            Err(HttpError::TooManyAsyncQueries { limit: 1, .. })
+        ));
+        assert!(matches!(
+            manager
+                .submit(slow_handle((0..6).collect(), delay), "cnosdb", "root")
+                .await,
+            Err(HttpError::TooManyAsyncQueries { limit: 1, .. })
+        ));
+        let other = manager
+            .submit(slow_handle((0..6).collect(), delay), "cnosdb", "other")
+            .await
+            .unwrap();
+
+        wait_finished(&query).await;
+        manager.check_running("cnosdb", "root").unwrap();
+        wait_finished(&other).await;
+    }
+
+    #[tokio::test]
+    async fn test_remove_while_spooling() {
+        let dir = std::env::temp_dir().join("cnosdb_test_async_query_remove");
+        let manager =
+            AsyncQueryManager::new(dir, Duration::from_secs(60), 3, u64::MAX, u64::MAX, 8);
+        let query = manager
+            .submit(
+                slow_handle((0..30).collect(), Duration::from_millis(20)),
+                "cnosdb",
+                "root",
+            )
+            .await
+            .unwrap();
+        while query.info().pages == 0 {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+
+        // the spooling task may be writing a page, it removes the results when it ends
+        manager.remove(&query);
+        assert_eq!(query.info().status, AsyncQueryStatus::Failed);
+        while query.dir.exists() {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        assert!(query.state.lock().removed);
+        assert!(!query.state.lock().spooling);
+    }
+}
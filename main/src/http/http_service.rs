@@ -6,15 +6,20 @@ use std::fmt;
 use std::fmt::Display;
 use std::mem::size_of_val;
 use std::net::SocketAddr;
+use std::path::Path;
 use std::sync::Arc;
 use std::time::Instant;
 
 use config::tskv::TLSConfig;
 use coordinator::service::CoordinatorRef;
+use datafusion::scalar::ScalarValue;
 use http_protocol::encoding::Encoding;
-use http_protocol::header::{ACCEPT, APPLICATION_JSON, AUTHORIZATION, PRIVATE_KEY};
-use http_protocol::parameter::{DebugParam, DumpParam, ESLogParam, SqlParam, WriteParam};
+use http_protocol::header::{ACCEPT, APPLICATION_JSON, AUTHORIZATION, NEXT_CURSOR, PRIVATE_KEY};
+use http_protocol::parameter::{
+    AsyncQueryParam, DebugParam, DumpParam, ESLogParam, SqlParam, WriteParam,
+};
 use http_protocol::response::ErrorResponse;
+use http_protocol::status_code::OK;
 use meta::error::{MetaError, MetaResult};
 use meta::limiter::RequestLimiter;
 use meta::model::MetaRef;
@@ -23,6 +28,7 @@ use metrics::gather_metrics;
 use metrics::metric_register::MetricsRegister;
 use metrics::prom_reporter::PromReporter;
 use models::auth::privilege::{DatabasePrivilege, Privilege, TenantObjectPrivilege};
+use models::consistency_level::ConsistencyLevel;
 use models::error_code::UnknownCodeWithMessage;
 use models::oid::{Identifier, Oid};
 use models::schema::{Precision, DEFAULT_CATALOG, DEFAULT_DATABASE};
@@ -36,7 +42,9 @@ use protocol_parser::{DataPoint, Line};
 use query::prom::remote_server::PromRemoteSqlServer;
 use reqwest::header::{ACCEPT_ENCODING, CONTENT_ENCODING};
 use snafu::{IntoError, ResultExt};
-use spi::query::config::StreamTriggerInterval;
+use spi::query::config::{
+    CacheMode, MaterializedViewRewrite, StaleReadMaxLag, StreamTriggerInterval,
+};
 use spi::server::dbms::DBMSRef;
 use spi::server::prom::PromRemoteServerRef;
 use spi::service::protocol::{Context, ContextBuilder, Query};
@@ -56,6 +64,7 @@ use warp::{header, reject, Filter, Rejection, Reply};
 use super::header::Header;
 use super::{ContextSnafu, CoordinatorSnafu, DecodeRequestSnafu, Error as HttpError, MetaSnafu};
 use crate::http::api_type::{metrics_record_db, HttpApiType};
+use crate::http::async_query::{AsyncQueryManager, ASYNC_QUERY_DIRECTORY_NAME};
 use crate::http::encoding::{get_accept_encoding_from_header, get_content_encoding_from_header};
 use crate::http::metrics::HttpMetrics;
 use crate::http::response::{HttpResponse, ResponseBuilder};
@@ -100,6 +109,7 @@ pub struct HttpService {
     metrics_register: Arc<MetricsRegister>,
     http_metrics: Arc<HttpMetrics>,
     auto_generate_span: bool,
+    async_queries: Arc<AsyncQueryManager>,
 }
 
 impl HttpService {
@@ -118,6 +128,16 @@ impl HttpService {
 
         let prs = Arc::new(PromRemoteSqlServer::new(dbms.clone(), coord.clone()));
 
+        let config = coord.get_config();
+        let async_queries = Arc::new(AsyncQueryManager::new(
+            Path::new(&config.storage.path).join(ASYNC_QUERY_DIRECTORY_NAME),
+            config.query.async_query_result_ttl,
+            config.query.async_query_page_rows,
+            config.query.async_query_max_result_size,
+            config.query.async_query_max_total_size,
+            config.query.async_query_max_running_per_user,
+        ));
+
         Self {
             tls_config,
             addr,
@@ -131,6 +151,7 @@ impl HttpService {
             metrics_register,
             http_metrics,
             auto_generate_span,
+            async_queries,
         }
     }
 
@@ -194,6 +215,12 @@ impl HttpService {
         warp::any().map(move || hostaddr.clone())
     }
 
+    fn with_remote_addr(
+        &self,
+    ) -> impl Filter<Extract = (Option<String>,), Error = Infallible> + Clone {
+        warp::addr::remote().map(|addr: Option<SocketAddr>| addr.map(|addr| addr.to_string()))
+    }
+
     fn with_coord(&self) -> impl Filter<Extract = (CoordinatorRef,), Error = Infallible> + Clone {
         let coord = self.coord.clone();
         warp::any().map(move || coord.clone())
@@ -224,11 +251,22 @@ impl HttpService {
         warp::any().map(move || meta.clone())
     }
 
+    fn with_async_queries(
+        &self,
+    ) -> impl Filter<Extract = (Arc<AsyncQueryManager>,), Error = Infallible> + Clone {
+        let async_queries = self.async_queries.clone();
+        warp::any().map(move || async_queries.clone())
+    }
+
     fn routes_query(
         &self,
     ) -> impl Filter<Extract = (impl warp::Reply,), Error = warp::Rejection> + Clone {
         self.ping()
             .or(self.query())
+            .or(self.submit_async_query())
+            .or(self.async_query_result())
+            .or(self.async_query_status())
+            .or(self.cancel_async_query())
             .or(self.mock_influxdb_write())
             .or(self.metrics())
             .or(self.print_meta())
@@ -325,6 +363,7 @@ impl HttpService {
             .and(self.with_coord())
             .and(self.with_http_metrics())
             .and(self.with_hostaddr())
+            .and(self.with_remote_addr())
             .and(self.handle_span_header())
             // construct_query
             .and_then(
@@ -336,6 +375,7 @@ impl HttpService {
                  coord: CoordinatorRef,
                  metrics: Arc<HttpMetrics>,
                  addr: String,
+                 client_addr: Option<String>,
                  parent_span_ctx: Option<SpanContext>| async move {
                     let start = Instant::now();
                     debug!(
@@ -356,12 +396,13 @@ impl HttpService {
                         let mut span = Span::enter_with_parent("authenticate", &span);
 
                         // Parse req、header and param to construct query request
-                        let query = construct_query(req, &header, param, dbms.clone(), coord)
-                            .await
-                            .map_err(|e| {
-                                error!("Failed to construct query, err: {:?}", e);
-                                reject::custom(e)
-                            })?;
+                        let query =
+                            construct_query(req, &header, param, dbms.clone(), coord, client_addr)
+                                .await
+                                .map_err(|e| {
+                                    error!("Failed to construct query, err: {:?}", e);
+                                    reject::custom(e)
+                                })?;
                         record_context_in_span(&mut span, query.context());
                         query
                     };
@@ -437,6 +478,241 @@ impl HttpService {
             )
     }
 
+    /// Submit a query running in the background, its result is fetched by
+    /// [`Self::async_query_result`] page by page.
+    fn submit_async_query(
+        &self,
+    ) -> impl Filter<Extract = (impl warp::Reply,), Error = warp::Rejection> + Clone {
+        warp::path!("api" / "v1" / "query")
+            .and(warp::post())
+            .and(warp::body::content_length_limit(self.query_body_limit))
+            .and(warp::body::bytes())
+            .and(self.handle_header())
+            .and(warp::query::<SqlParam>())
+            .and(self.with_dbms())
+            .and(self.with_meta())
+            .and(self.with_coord())
+            .and(self.with_http_metrics())
+            .and(self.with_hostaddr())
+            .and(self.with_remote_addr())
+            .and(self.with_async_queries())
+            .and(self.handle_span_header())
+            .and_then(
+                |mut req: Bytes,
+                 header: Header,
+                 param: SqlParam,
+                 dbms: DBMSRef,
+                 meta: MetaRef,
+                 coord: CoordinatorRef,
+                 metrics: Arc<HttpMetrics>,
+                 addr: String,
+                 client_addr: Option<String>,
+                 async_queries: Arc<AsyncQueryManager>,
+                 parent_span_ctx: Option<SpanContext>| async move {
+                    let start = Instant::now();
+                    debug!(
+                        "Receive http async query request, header: {:?}, param: {:?}",
+                        header, param
+                    );
+
+                    let span =
+                        Span::from_context("rest async query request", parent_span_ctx.as_ref());
+                    let req_len = req.len();
+                    if let Some(encoding) = get_content_encoding_from_header(&header)? {
+                        req = encoding.decode(req).map_err(|e| {
+                            error!("Failed to decode request, err: {:?}", e);
+                            reject::custom(HttpError::DecodeRequest { source: e })
+                        })?;
+                    }
+                    let query =
+                        construct_query(req, &header, param, dbms.clone(), coord, client_addr)
+                            .await
+                            .map_err(|e| {
+                                error!("Failed to construct query, err: {:?}", e);
+                                reject::custom(e)
+                            })?;
+                    http_limiter_check_query(&meta, query.context().tenant(), req_len)
+                        .await
+                        .map_err(|e| {
+                            error!("Failed to check query limiter, err: {:?}", e);
+                            reject::custom(e)
+                        })?;
+
+                    let result = async {
+                        async_queries.check_running(
+                            query.context().tenant(),
+                            query.context().user().desc().name(),
+                        )?;
+                        let span = Span::enter_with_parent("execute", &span);
+                        let handle = dbms
+                            .execute(&query, span.context().as_ref())
+                            .await
+                            .map_err(|err| {
+                                span.error(err.to_string());
+                                err
+                            })
+                            .context(QuerySnafu)?;
+                        let async_query = async_queries
+                            .submit(
+                                handle,
+                                query.context().tenant(),
+                                query.context().user().desc().name(),
+                            )
+                            .await?;
+                        Ok::<_, HttpError>(ResponseBuilder::new(OK).json(&async_query.info()))
+                    }
+                    .await;
+
+                    http_record_query_metrics(
+                        &metrics,
+                        query.context(),
+                        &addr,
+                        req_len,
+                        start,
+                        HttpApiType::ApiV1Query,
+                    );
+                    result.map_err(|e| {
+                        error!("Failed to submit http async query, err: {:?}", e);
+                        reject::custom(e)
+                    })
+                },
+            )
+    }
+
+    /// Status and progress of an async query
+    fn async_query_status(
+        &self,
+    ) -> impl Filter<Extract = (impl warp::Reply,), Error = warp::Rejection> + Clone {
+        warp::path!("api" / "v1" / "query" / String)
+            .and(warp::get())
+            .and(self.handle_header())
+            .and(warp::query::<AsyncQueryParam>())
+            .and(self.with_dbms())
+            .and(self.with_remote_addr())
+            .and(self.with_async_queries())
+            .and_then(
+                |query_id: String,
+                 header: Header,
+                 param: AsyncQueryParam,
+                 dbms: DBMSRef,
+                 client_addr: Option<String>,
+                 async_queries: Arc<AsyncQueryManager>| async move {
+                    let (tenant, user) =
+                        authenticate_async_query(&header, param.tenant, &dbms, client_addr)
+                            .await
+                            .map_err(reject::custom)?;
+                    let async_query = async_queries
+                        .get(&query_id, &tenant, &user)
+                        .map_err(reject::custom)?;
+                    Ok::<_, Rejection>(ResponseBuilder::new(OK).json(&async_query.info()))
+                },
+            )
+    }
+
+    /// A page of the result of an async query in the format of the `Accept` header,
+    /// with the cursor of the next page in the `X-CnosDB-Next-Cursor` header
+    /// unless it's the last page. The page is empty if it's not produced yet.
+    fn async_query_result(
+        &self,
+    ) -> impl Filter<Extract = (impl warp::Reply,), Error = warp::Rejection> + Clone {
+        warp::path!("api" / "v1" / "query" / String / "result")
+            .and(warp::get())
+            .and(self.handle_header())
+            .and(warp::query::<AsyncQueryParam>())
+            .and(self.with_dbms())
+            .and(self.with_http_metrics())
+            .and(self.with_hostaddr())
+            .and(self.with_remote_addr())
+            .and(self.with_async_queries())
+            .and_then(
+                |query_id: String,
+                 header: Header,
+                 param: AsyncQueryParam,
+                 dbms: DBMSRef,
+                 metrics: Arc<HttpMetrics>,
+                 addr: String,
+                 client_addr: Option<String>,
+                 async_queries: Arc<AsyncQueryManager>| async move {
+                    let result_fmt = get_result_format_from_header(&header)?;
+                    let result_encoding = get_accept_encoding_from_header(&header)?;
+                    let result = async {
+                        let (tenant, user) =
+                            authenticate_async_query(&header, param.tenant, &dbms, client_addr)
+                                .await?;
+                        let cursor = param
+                            .cursor
+                            .map(|c| c.parse::<usize>())
+                            .transpose()
+                            .map_err(|e| HttpError::InvalidHeader {
+                                reason: format!("invalid cursor: {}", e),
+                            })?
+                            .unwrap_or_default();
+                        let page = async_queries
+                            .get(&query_id, &tenant, &user)?
+                            .page(cursor)
+                            .await?;
+
+                        let http_data_out = metrics.http_data_out(
+                            &tenant,
+                            &user,
+                            None,
+                            &addr,
+                            HttpApiType::ApiV1Query,
+                        );
+                        let mut resp = result_fmt.wrap_batches_to_response(
+                            page.schema,
+                            &page.batches,
+                            true,
+                            http_data_out,
+                            result_encoding,
+                        )?;
+                        if let Some(next_cursor) = page.next_cursor {
+                            resp.headers_mut()
+                                .insert(NEXT_CURSOR, (next_cursor as u64).into());
+                        }
+                        Ok::<_, HttpError>(resp)
+                    }
+                    .await;
+                    result.map_err(|e| {
+                        error!("Failed to fetch result of http async query, err: {:?}", e);
+                        reject::custom(e)
+                    })
+                },
+            )
+    }
+
+    /// Cancel an async query and remove its result
+    fn cancel_async_query(
+        &self,
+    ) -> impl Filter<Extract = (impl warp::Reply,), Error = warp::Rejection> + Clone {
+        warp::path!("api" / "v1" / "query" / String)
+            .and(warp::delete())
+            .and(self.handle_header())
+            .and(warp::query::<AsyncQueryParam>())
+            .and(self.with_dbms())
+            .and(self.with_remote_addr())
+            .and(self.with_async_queries())
+            .and_then(
+                |query_id: String,
+                 header: Header,
+                 param: AsyncQueryParam,
+                 dbms: DBMSRef,
+                 client_addr: Option<String>,
+                 async_queries: Arc<AsyncQueryManager>| async move {
+                    let (tenant, user) =
+                        authenticate_async_query(&header, param.tenant, &dbms, client_addr)
+                            .await
+                            .map_err(reject::custom)?;
+                    let async_query = async_queries
+                        .get(&query_id, &tenant, &user)
+                        .map_err(reject::custom)?;
+                    dbms.cancel(&async_query.id());
+                    async_queries.remove(&async_query);
+                    Ok::<_, Rejection>(ResponseBuilder::ok())
+                },
+            )
+    }
+
     fn write_line_protocol(
         &self,
     ) -> impl Filter<Extract = (impl warp::Reply,), Error = warp::Rejection> + Clone {
@@ -510,6 +786,7 @@ impl HttpService {
                         ctx.database(),
                         precision,
                         write_points_lines,
+                        ctx.session_config().consistency_level(),
                         span_context.as_ref(),
                     )
                     .await;
@@ -574,6 +851,7 @@ impl HttpService {
                         db: Some(db),
                         precision: None,
                         tenant: None,
+                        consistency: None,
                     };
                     let precision = Precision::NS;
 
@@ -600,6 +878,7 @@ impl HttpService {
                         ctx.database(),
                         precision,
                         lines,
+                        ctx.session_config().consistency_level(),
                         None,
                     )
                     .await;
@@ -706,6 +985,7 @@ impl HttpService {
                         ctx.database(),
                         precision,
                         write_points_req,
+                        ctx.session_config().consistency_level(),
                         span_context.as_ref(),
                     )
                     .await;
@@ -829,6 +1109,7 @@ impl HttpService {
                         ctx.database(),
                         precision,
                         write_points_req,
+                        ctx.session_config().consistency_level(),
                         span_context.as_ref(),
                     )
                     .await;
@@ -1121,7 +1402,7 @@ impl HttpService {
                     let context = {
                         let mut span = Span::enter_with_parent("construct context", &span);
                         span.add_property(|| ("bytes", req.len().to_string()));
-                        let ctx = construct_read_context(&header, param, dbms, coord, false)
+                        let ctx = construct_read_context(&header, param, dbms, coord, false, None)
                             .await
                             .map_err(|e| {
                                 error!("Failed to construct read context, err: {:?}", e);
@@ -1274,6 +1555,7 @@ impl HttpService {
                         ctx.database(),
                         Precision::NS,
                         write_request,
+                        ctx.session_config().consistency_level(),
                         span_context.as_ref(),
                     )
                     .await;
@@ -1412,6 +1694,7 @@ impl HttpService {
                         precision: None,
                         tenant: param.tenant,
                         db: param.db,
+                        consistency: param.consistency,
                     };
 
                     if param.table.is_none() {
@@ -1463,6 +1746,7 @@ impl HttpService {
                         eslog,
                         param.time_column,
                         param.tag_columns,
+                        ctx.session_config().consistency_level(),
                         span_context.as_ref(),
                     )
                     .await;
@@ -1501,6 +1785,8 @@ impl HttpService {
 #[async_trait::async_trait]
 impl Service for HttpService {
     fn start(&mut self) -> Result<(), server::Error> {
+        self.async_queries.start_expiration();
+
         let (shutdown, rx) = oneshot::channel();
         let signal = async {
             rx.await.ok();
@@ -1589,8 +1875,9 @@ async fn construct_query(
     param: SqlParam,
     dbms: DBMSRef,
     coord: CoordinatorRef,
+    client_addr: Option<String>,
 ) -> Result<Query, HttpError> {
-    let context = construct_read_context(header, param, dbms, coord, true).await?;
+    let context = construct_read_context(header, param, dbms, coord, true, client_addr).await?;
 
     Ok(Query::new(
         context,
@@ -1604,12 +1891,17 @@ async fn construct_read_context(
     dbms: DBMSRef,
     coord: CoordinatorRef,
     is_sql: bool,
+    client_addr: Option<String>,
 ) -> Result<Context, HttpError> {
     let user_info = header.try_get_basic_auth()?;
 
     let tenant = param.tenant;
     let user = dbms
-        .authenticate(&user_info, tenant.as_deref().unwrap_or(DEFAULT_CATALOG))
+        .authenticate(
+            &user_info,
+            tenant.as_deref().unwrap_or(DEFAULT_CATALOG),
+            client_addr.as_deref(),
+        )
         .await
         .context(QuerySnafu)?;
 
@@ -1642,6 +1934,37 @@ async fn construct_read_context(
                 })
                 .transpose()?,
         )
+        .with_stale_read_max_lag(
+            param
+                .stale_read_max_lag
+                .map(|ref e| {
+                    e.parse::<StaleReadMaxLag>()
+                        .map_err(|reason| HttpError::InvalidHeader { reason })
+                })
+                .transpose()?,
+        )
+        .with_consistency_level(parse_consistency_level(param.consistency)?)
+        .with_cache_mode(
+            param
+                .cache
+                .map(|ref e| {
+                    e.parse::<CacheMode>()
+                        .map_err(|reason| HttpError::InvalidHeader { reason })
+                })
+                .transpose()?,
+        )
+        .with_materialized_view_rewrite(
+            param
+                .materialized_view_rewrite
+                .map(|ref e| {
+                    e.parse::<MaterializedViewRewrite>()
+                        .map_err(|reason| HttpError::InvalidHeader { reason })
+                })
+                .transpose()?,
+        )
+        .with_params(param.params.as_deref().map(parse_params).transpose()?)
+        .with_session_id(param.session_id)
+        .with_client_addr(client_addr)
         .build();
 
     Ok(context)
@@ -1658,7 +1981,11 @@ async fn construct_write_context(
     let precision = param.precision;
 
     let user = dbms
-        .authenticate(&user_info, tenant.as_deref().unwrap_or(DEFAULT_CATALOG))
+        .authenticate(
+            &user_info,
+            tenant.as_deref().unwrap_or(DEFAULT_CATALOG),
+            None,
+        )
         .await
         .context(QuerySnafu)?;
 
@@ -1666,11 +1993,66 @@ async fn construct_write_context(
         .with_tenant(tenant)
         .with_database(db)
         .with_precision(precision)
+        .with_consistency_level(parse_consistency_level(param.consistency)?)
         .build();
 
     Ok(context)
 }
 
+fn parse_consistency_level(
+    consistency: Option<String>,
+) -> Result<Option<ConsistencyLevel>, HttpError> {
+    consistency
+        .map(|ref e| {
+            e.parse::<ConsistencyLevel>()
+                .map_err(|reason| HttpError::InvalidHeader { reason })
+        })
+        .transpose()
+}
+
+/// Returns the tenant and the name of the user
+async fn authenticate_async_query(
+    header: &Header,
+    tenant: Option<String>,
+    dbms: &DBMSRef,
+    client_addr: Option<String>,
+) -> Result<(String, String), HttpError> {
+    let user_info = header.try_get_basic_auth()?;
+    let tenant = tenant.unwrap_or_else(|| DEFAULT_CATALOG.to_string());
+    let user = dbms
+        .authenticate(&user_info, &tenant, client_addr.as_deref())
+        .await
+        .context(QuerySnafu)?;
+
+    Ok((tenant, user.desc().name().to_string()))
+}
+
+fn parse_params(params: &str) -> Result<Vec<ScalarValue>, HttpError> {
+    let invalid = |reason: String| HttpError::InvalidHeader {
+        reason: format!("invalid params '{}': {}", params, reason),
+    };
+    let values: Vec<serde_json::Value> =
+        serde_json::from_str(params).map_err(|e| invalid(e.to_string()))?;
+    values
+        .into_iter()
+        .map(|value| match value {
+            serde_json::Value::Null => Ok(ScalarValue::Null),
+            serde_json::Value::Bool(b) => Ok(ScalarValue::Boolean(Some(b))),
+            serde_json::Value::Number(n) => {
+                if let Some(v) = n.as_i64() {
+                    Ok(ScalarValue::Int64(Some(v)))
+                } else if let Some(v) = n.as_u64() {
+                    Ok(ScalarValue::UInt64(Some(v)))
+                } else {
+                    Ok(ScalarValue::Float64(n.as_f64()))
+                }
+            }
+            serde_json::Value::String(s) => Ok(ScalarValue::Utf8(Some(s))),
+            other => Err(invalid(format!("unsupported parameter {}", other))),
+        })
+        .collect()
+}
+
 fn _construct_write_db_privilege(tenant_id: Oid, database: &str) -> Privilege<Oid> {
     Privilege::TenantObject(
         TenantObjectPrivilege::Database(DatabasePrivilege::Write, Some(database.to_string())),
@@ -1828,6 +2210,7 @@ async fn coord_write_eslog(
     es_log: Vec<ESLog>,
     time_column: Option<String>,
     tag_columns: Option<String>,
+    consistency: ConsistencyLevel,
     span_context: Option<&SpanContext>,
 ) -> Result<Response, HttpError> {
     let span = Span::from_context("write points", span_context);
@@ -1861,7 +2244,14 @@ async fn coord_write_eslog(
     }
 
     coord
-        .write_lines(tenant, db, Precision::NS, lines, span.context().as_ref())
+        .write_lines(
+            tenant,
+            db,
+            Precision::NS,
+            consistency,
+            lines,
+            span.context().as_ref(),
+        )
         .await
         .map_err(|e| {
             span.error(e.to_string());
@@ -1882,6 +2272,7 @@ async fn coord_write_points_with_span_recorder(
     db: &str,
     precision: Precision,
     write_points_lines: Vec<Line<'_>>,
+    consistency: ConsistencyLevel,
     span_context: Option<&SpanContext>,
 ) -> Result<usize, HttpError> {
     let span = Span::from_context("write points", span_context);
@@ -1890,6 +2281,7 @@ async fn coord_write_points_with_span_recorder(
             tenant,
             db,
             precision,
+            consistency,
             write_points_lines,
             span.context().as_ref(),
         )
@@ -1923,6 +2315,7 @@ async fn sql_handle(
 
     let out = handle.result();
 
+    let chunked = fmt.is_stream() || (query.context().chunked() && fmt.is_chunkable());
     let resp = HttpResponse::new(
         out,
         fmt.clone(),
@@ -1932,8 +2325,11 @@ async fn sql_handle(
     );
 
     let span = Span::from_context("build response", span_ctx);
-    if !query.context().chunked() {
+    if !chunked {
         let result = resp.wrap_batches_to_response().await;
+        // scans of vnodes fail over to other replicas in the coordinator until a batch
+        // is returned, a broken replica found after that has been marked broken, so the
+        // query is executed again to read another replica.
         if let Err(err) = &result {
             if tskv::TskvError::vnode_broken_code(err.error_code().code()) {
                 info!("tsm file broken {:?}, try read....", err);
@@ -1948,13 +2344,7 @@ async fn sql_handle(
                         .context(QuerySnafu)?
                 };
                 let out = handle.result();
-                let resp = HttpResponse::new(
-                    out,
-                    fmt,
-                    encoding,
-                    http_query_data_out.clone(),
-                    limiter.clone(),
-                );
+                let resp = HttpResponse::new(out, fmt, encoding, http_query_data_out, limiter);
                 return resp.wrap_batches_to_response().await;
             }
         }
@@ -1,6 +1,6 @@
 use coordinator::errors::CoordinatorError;
 use http_protocol::response::ErrorResponse;
-use http_protocol::status_code::UNPROCESSABLE_ENTITY;
+use http_protocol::status_code::{NOT_FOUND, TOO_MANY_REQUESTS, UNPROCESSABLE_ENTITY};
 use meta::error::MetaError;
 use models::error_code::{ErrorCode, ErrorCoder};
 use snafu::Snafu;
@@ -12,6 +12,7 @@ use warp::reply::Response;
 use self::response::ResponseBuilder;
 
 mod api_type;
+mod async_query;
 mod encoding;
 pub mod header;
 pub mod http_service;
@@ -128,6 +129,39 @@ pub enum Error {
     Context {
         source: ContextError,
     },
+
+    #[snafu(display(
+        "Async query {} not found or expired, the results can only be fetched from the node the query was submitted to",
+        query_id
+    ))]
+    #[error_code(code = 19)]
+    AsyncQueryNotFound {
+        query_id: String,
+    },
+
+    #[snafu(display("Async query {} failed: {}", query_id, reason))]
+    #[error_code(code = 20)]
+    AsyncQueryFailed {
+        query_id: String,
+        reason: String,
+    },
+
+    #[snafu(display("Spool result of async query: {}", reason))]
+    #[error_code(code = 21)]
+    SpoolResult {
+        reason: String,
+    },
+
+    #[snafu(display(
+        "User {} has reached the limit of {} running async queries",
+        user,
+        limit
+    ))]
+    #[error_code(code = 22)]
+    TooManyAsyncQueries {
+        user: String,
+        limit: usize,
+    },
 }
 
 impl reject::Reject for Error {}
@@ -168,6 +202,13 @@ impl From<&Error> for Response {
             | Error::DecodeRequest { .. }
             | Error::ParseOpentsdbProtocol { .. }
             | Error::ParseOpentsdbJsonProtocol { .. } => ResponseBuilder::bad_request(&error_resp),
+            Error::AsyncQueryNotFound { .. } => ResponseBuilder::new(NOT_FOUND).json(&error_resp),
+            Error::AsyncQueryFailed { .. } | Error::SpoolResult { .. } => {
+                ResponseBuilder::new(UNPROCESSABLE_ENTITY).json(&error_resp)
+            }
+            Error::TooManyAsyncQueries { .. } => {
+                ResponseBuilder::new(TOO_MANY_REQUESTS).json(&error_resp)
+            }
             _ => ResponseBuilder::internal_server_error(),
         }
     }
@@ -26,7 +26,7 @@ use warp::reply::Response;
 use warp::{hyper, Reply};
 
 use super::header::IntoHeaderPair;
-use super::result_format::ResultFormat;
+use super::result_format::{ArrowStreamChunks, ResultFormat};
 use super::{Error as HttpError, MetaSnafu, QuerySnafu};
 
 #[derive(Default)]
@@ -128,6 +128,8 @@ pub struct HttpResponse {
     format: ResultFormat,
     encoding: Option<Encoding>,
     schema: Option<SchemaRef>,
+    /// Writer of the Arrow IPC stream if the results are returned as it
+    arrow_stream: Option<ArrowStreamChunks>,
     http_query_data_out: U64Counter,
     limiter: Arc<dyn RequestLimiter>,
     stream_state: HttpResponseStreamState,
@@ -147,6 +149,7 @@ impl HttpResponse {
             format,
             encoding,
             schema: Some(schema),
+            arrow_stream: None,
             limiter,
             stream_state: HttpResponseStreamState::PollNext,
             http_query_data_out,
@@ -154,8 +157,10 @@ impl HttpResponse {
     }
 
     pub async fn wrap_batches_to_response(self) -> Result<Response, HttpError> {
+        let schema = self.result.schema();
         let actual = self.result.chunk_result().await.context(QuerySnafu)?;
         self.format.wrap_batches_to_response(
+            schema,
             &actual,
             true,
             self.http_query_data_out.clone(),
@@ -172,21 +177,73 @@ impl HttpResponse {
         Ok(resp)
     }
 
+    /// Get the writer of the Arrow IPC stream, which is created with the schema at first.
+    fn arrow_stream(&mut self) -> Result<Option<&mut ArrowStreamChunks>, HttpError> {
+        if let Some(schema) = self.schema.take() {
+            let arrow_stream =
+                ArrowStreamChunks::try_new(&schema).map_err(|e| HttpError::FetchResult {
+                    reason: format!("{}", e),
+                })?;
+            self.arrow_stream = Some(arrow_stream);
+        }
+        Ok(self.arrow_stream.as_mut())
+    }
+
+    /// Encode the chunk of the response and check the limiter before it's returned.
+    fn check_chunk(
+        &mut self,
+        mut buffer: Vec<u8>,
+        is_finish: bool,
+    ) -> Result<HttpResponseStreamState, HttpError> {
+        if let Some(encoding) = self.encoding.as_ref() {
+            buffer = encoding
+                .encode(buffer)
+                .map_err(|e| HttpError::EncodeResponse { source: e })?;
+        }
+        self.http_query_data_out.inc(buffer.len() as u64);
+
+        let limiter = self.limiter.clone();
+        let buffer_len = buffer.len();
+        let future = async move {
+            limiter
+                .check_http_data_out(buffer_len)
+                .await
+                .context(MetaSnafu)
+        };
+        Ok(HttpResponseStreamState::CheckLimiter(
+            Box::pin(future),
+            buffer,
+            is_finish,
+        ))
+    }
+
     pub fn handle_opt_result_record_batch(
         &mut self,
         opt_result_batch: Option<Result<RecordBatch, QueryError>>,
     ) -> Result<HttpResponseStreamState, HttpError> {
         match opt_result_batch {
+            None if self.format.is_stream() => {
+                let buffer = match self.arrow_stream()? {
+                    Some(arrow_stream) => {
+                        arrow_stream.finish().map_err(|e| HttpError::FetchResult {
+                            reason: format!("{}", e),
+                        })?
+                    }
+                    None => return Ok(HttpResponseStreamState::Finish),
+                };
+                self.arrow_stream = None;
+                self.check_chunk(buffer, true)
+            }
             None => {
                 if let Some(schema) = self.schema.take() {
                     let has_headers = !schema.fields().is_empty();
-                    let rb = RecordBatch::new_empty(schema);
-                    let mut buffer =
-                        self.format
-                            .format_batches(&[rb], has_headers)
-                            .map_err(|e| HttpError::FetchResult {
-                                reason: format!("{}", e),
-                            })?;
+                    let rb = RecordBatch::new_empty(schema.clone());
+                    let mut buffer = self
+                        .format
+                        .format_batches(schema, &[rb], has_headers)
+                        .map_err(|e| HttpError::FetchResult {
+                            reason: format!("{}", e),
+                        })?;
                     if let Some(encoding) = self.encoding {
                         buffer = encoding
                             .encode(buffer)
@@ -211,11 +268,27 @@ impl HttpResponse {
                     Ok(HttpResponseStreamState::Finish)
                 }
             }
+            Some(Ok(rb)) if self.format.is_stream() => {
+                if rb.num_rows() == 0 {
+                    return Ok(HttpResponseStreamState::PollNext);
+                }
+                let buffer = match self.arrow_stream()? {
+                    Some(arrow_stream) => {
+                        arrow_stream
+                            .write(&rb)
+                            .map_err(|e| HttpError::FetchResult {
+                                reason: format!("{}", e),
+                            })?
+                    }
+                    None => return Ok(HttpResponseStreamState::PollNext),
+                };
+                self.check_chunk(buffer, false)
+            }
             Some(Ok(rb)) => {
                 if rb.num_rows() > 0 {
                     let mut buffer = self
                         .format
-                        .format_batches(&[rb], self.schema.is_some())
+                        .format_batches(rb.schema(), &[rb], self.schema.is_some())
                         .map_err(|e| HttpError::FetchResult {
                             reason: format!("{}", e),
                         })?;
@@ -1,17 +1,25 @@
+use std::io::Write;
+use std::mem;
 use std::str::FromStr;
+use std::sync::Arc;
 
 use datafusion::arrow::csv::writer::WriterBuilder;
-use datafusion::arrow::error::Result as ArrowResult;
+use datafusion::arrow::datatypes::{Schema, SchemaRef};
+use datafusion::arrow::error::{ArrowError, Result as ArrowResult};
+use datafusion::arrow::ipc::writer::StreamWriter;
 use datafusion::arrow::json::{ArrayWriter, LineDelimitedWriter};
 use datafusion::arrow::record_batch::RecordBatch;
 use datafusion::arrow::util::pretty::pretty_format_batches;
+use datafusion::parquet::arrow::ArrowWriter;
 use http_protocol::encoding::Encoding;
 use http_protocol::header::{
-    APPLICATION_CSV, APPLICATION_JSON, APPLICATION_NDJSON, APPLICATION_PREFIX, APPLICATION_STAR,
-    APPLICATION_TABLE, APPLICATION_TSV, CONTENT_TYPE, STAR_STAR,
+    APPLICATION_ARROW_STREAM, APPLICATION_CSV, APPLICATION_JSON, APPLICATION_NDJSON,
+    APPLICATION_PARQUET, APPLICATION_PREFIX, APPLICATION_STAR, APPLICATION_TABLE, APPLICATION_TSV,
+    CONTENT_TYPE, STAR_STAR,
 };
 use http_protocol::status_code::OK;
 use metrics::count::U64Counter;
+use parking_lot::Mutex;
 use reqwest::header::CONTENT_ENCODING;
 use trace::error;
 use warp::reply::Response;
@@ -53,6 +61,80 @@ fn batches_with_sep(
     Ok(bytes)
 }
 
+fn batches_to_arrow_stream(schema: &Schema, batches: &[RecordBatch]) -> ArrowResult<Vec<u8>> {
+    let mut writer = StreamWriter::try_new(vec![], schema)?;
+    for batch in batches {
+        writer.write(batch)?;
+    }
+    writer.finish()?;
+    writer.into_inner()
+}
+
+/// Buffer of an Arrow IPC stream writer, which is shared with the [`ArrowStreamChunks`]
+/// to take out the written bytes.
+#[derive(Clone, Default)]
+struct SharedBuffer(Arc<Mutex<Vec<u8>>>);
+
+impl Write for SharedBuffer {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.lock().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Writes batches to an Arrow IPC stream chunk by chunk, so that the results
+/// don't have to be buffered before they are responded.
+pub struct ArrowStreamChunks {
+    writer: StreamWriter<SharedBuffer>,
+    buffer: SharedBuffer,
+}
+
+impl ArrowStreamChunks {
+    pub fn try_new(schema: &Schema) -> ArrowResult<Self> {
+        let buffer = SharedBuffer::default();
+        let writer = StreamWriter::try_new(buffer.clone(), schema)?;
+        Ok(Self { writer, buffer })
+    }
+
+    /// Write the batch, returns the chunk written since the previous one,
+    /// the first chunk starts with the schema.
+    pub fn write(&mut self, batch: &RecordBatch) -> ArrowResult<Vec<u8>> {
+        self.writer.write(batch)?;
+        Ok(self.take_chunk())
+    }
+
+    /// Finish the stream, returns the last chunk.
+    pub fn finish(&mut self) -> ArrowResult<Vec<u8>> {
+        self.writer.finish()?;
+        Ok(self.take_chunk())
+    }
+
+    fn take_chunk(&self) -> Vec<u8> {
+        mem::take(&mut *self.buffer.0.lock())
+    }
+}
+
+fn batches_to_parquet(schema: SchemaRef, batches: &[RecordBatch]) -> ArrowResult<Vec<u8>> {
+    let mut bytes = vec![];
+    {
+        let mut writer = ArrowWriter::try_new(&mut bytes, schema, None)
+            .map_err(|e| ArrowError::ExternalError(Box::new(e)))?;
+        for batch in batches {
+            writer
+                .write(batch)
+                .map_err(|e| ArrowError::ExternalError(Box::new(e)))?;
+        }
+        writer
+            .close()
+            .map_err(|e| ArrowError::ExternalError(Box::new(e)))?;
+    }
+    Ok(bytes)
+}
+
 /// Allow records to be printed in different formats
 #[derive(Debug, PartialEq, Eq, clap::ValueEnum, Clone)]
 pub enum ResultFormat {
@@ -61,6 +143,9 @@ pub enum ResultFormat {
     Json,
     NdJson,
     Table,
+    /// Arrow IPC stream
+    Arrow,
+    Parquet,
 }
 
 impl ResultFormat {
@@ -71,15 +156,37 @@ impl ResultFormat {
             Self::Json => APPLICATION_JSON,
             Self::NdJson => APPLICATION_NDJSON,
             Self::Table => APPLICATION_TABLE,
+            Self::Arrow => APPLICATION_ARROW_STREAM,
+            Self::Parquet => APPLICATION_PARQUET,
         }
     }
 
+    /// Binary results are self-describing, they carry the schema even if there are no rows.
+    pub fn is_binary(&self) -> bool {
+        matches!(self, Self::Arrow | Self::Parquet)
+    }
+
+    /// Whether the results can be returned chunk by chunk, a Parquet file is written
+    /// as a whole because its metadata is at the end.
+    pub fn is_chunkable(&self) -> bool {
+        !matches!(self, Self::Parquet)
+    }
+
+    /// Whether the results are always returned chunk by chunk, an Arrow IPC stream
+    /// needn't be buffered.
+    pub fn is_stream(&self) -> bool {
+        matches!(self, Self::Arrow)
+    }
+
+    /// Format the batches of the schema, binary results without batches
+    /// only carry the schema and text results without batches are empty.
     pub fn format_batches(
         &self,
+        schema: SchemaRef,
         batches: &[RecordBatch],
         has_headers: bool,
     ) -> ArrowResult<Vec<u8>> {
-        if batches.is_empty() {
+        if batches.is_empty() && !self.is_binary() {
             return Ok(Vec::new());
         }
         match self {
@@ -90,21 +197,24 @@ impl ResultFormat {
                 batches_to_json!(LineDelimitedWriter, batches)
             }
             Self::Table => Ok(pretty_format_batches(batches)?.to_string().into_bytes()),
+            Self::Arrow => batches_to_arrow_stream(&schema, batches),
+            Self::Parquet => batches_to_parquet(schema, batches),
         }
     }
 
     pub fn wrap_batches_to_response(
         &self,
+        schema: SchemaRef,
         batches: &[RecordBatch],
         has_headers: bool,
         http_query_data_out: U64Counter,
         result_encoding: Option<Encoding>,
     ) -> Result<Response, HttpError> {
-        let mut result =
-            self.format_batches(batches, has_headers)
-                .map_err(|e| HttpError::FetchResult {
-                    reason: format!("{}", e),
-                })?;
+        let mut result = self
+            .format_batches(schema, batches, has_headers)
+            .map_err(|e| HttpError::FetchResult {
+                reason: format!("{}", e),
+            })?;
 
         let mut builder =
             ResponseBuilder::new(OK).insert_header((CONTENT_TYPE, self.get_http_content_type()));
@@ -130,6 +240,12 @@ impl TryFrom<&str> for ResultFormat {
             return Ok(ResultFormat::Csv);
         }
 
+        match s {
+            APPLICATION_ARROW_STREAM => return Ok(ResultFormat::Arrow),
+            APPLICATION_PARQUET => return Ok(ResultFormat::Parquet),
+            _ => {}
+        }
+
         if let Some(fmt) = s.strip_prefix(APPLICATION_PREFIX) {
             return ResultFormat::from_str(fmt)
                 .map_err(|reason| HttpError::InvalidHeader { reason });
@@ -161,10 +277,15 @@ pub fn get_result_format_from_header(header: &Header) -> Result<ResultFormat, Re
 
 #[cfg(test)]
 mod tests {
+    use std::io::Cursor;
     use std::sync::Arc;
 
+    use bytes::Bytes;
     use datafusion::arrow::array::Int32Array;
+    use datafusion::arrow::compute::concat_batches;
     use datafusion::arrow::datatypes::{DataType, Field, Schema};
+    use datafusion::arrow::ipc::reader::StreamReader;
+    use datafusion::parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
 
     use super::*;
 
@@ -238,4 +359,105 @@ mod tests {
         );
         Ok(())
     }
+
+    #[test]
+    fn test_binary_result_format_from_accept() {
+        assert_eq!(
+            ResultFormat::try_from(APPLICATION_ARROW_STREAM).unwrap(),
+            ResultFormat::Arrow
+        );
+        assert_eq!(
+            ResultFormat::try_from(APPLICATION_PARQUET).unwrap(),
+            ResultFormat::Parquet
+        );
+        assert!(ResultFormat::Parquet.is_binary());
+        assert!(!ResultFormat::Csv.is_binary());
+        assert!(ResultFormat::Arrow.is_stream());
+        assert!(!ResultFormat::Parquet.is_chunkable());
+    }
+
+    #[test]
+    fn test_arrow_stream_chunks() -> ArrowResult<()> {
+        let schema = Arc::new(Schema::new(vec![Field::new("a", DataType::Int32, false)]));
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![Arc::new(Int32Array::from(vec![1, 2, 3]))],
+        )?;
+
+        let mut chunks = ArrowStreamChunks::try_new(&schema)?;
+        let mut body = chunks.write(&batch)?;
+        let chunk = chunks.write(&batch)?;
+        assert!(!chunk.is_empty());
+        body.extend(chunk);
+        body.extend(chunks.finish()?);
+
+        let reader = StreamReader::try_new(Cursor::new(body), None)?;
+        assert_eq!(
+            vec![batch.clone(), batch],
+            reader.collect::<ArrowResult<Vec<_>>>()?
+        );
+
+        // a stream without batches still carries the schema
+        let mut chunks = ArrowStreamChunks::try_new(&schema)?;
+        let reader = StreamReader::try_new(Cursor::new(chunks.finish()?), None)?;
+        assert_eq!(reader.schema(), schema);
+        assert_eq!(reader.count(), 0);
+        Ok(())
+    }
+
+    #[test]
+    fn test_format_batches_binary() -> ArrowResult<()> {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("a", DataType::Int32, false),
+            Field::new("b", DataType::Int32, false),
+        ]));
+        let batch = RecordBatch::try_new(
+            schema,
+            vec![
+                Arc::new(Int32Array::from(vec![1, 2, 3])),
+                Arc::new(Int32Array::from(vec![4, 5, 6])),
+            ],
+        )?;
+        let batches = vec![batch.clone(), batch];
+        let schema = batches[0].schema();
+
+        let r = ResultFormat::Arrow.format_batches(schema.clone(), &batches, true)?;
+        let reader = StreamReader::try_new(Cursor::new(r), None)?;
+        assert_eq!(batches, reader.collect::<ArrowResult<Vec<_>>>()?);
+
+        let r = ResultFormat::Parquet.format_batches(schema.clone(), &batches, true)?;
+        let reader = ParquetRecordBatchReaderBuilder::try_new(Bytes::from(r))
+            .unwrap()
+            .build()
+            .unwrap();
+        let read = reader.collect::<ArrowResult<Vec<_>>>()?;
+        assert_eq!(
+            concat_batches(&schema, &batches)?,
+            concat_batches(&schema, &read)?
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_format_empty_batches_binary() -> ArrowResult<()> {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("a", DataType::Int32, false),
+            Field::new("b", DataType::Utf8, true),
+        ]));
+
+        let r = ResultFormat::Arrow.format_batches(schema.clone(), &[], true)?;
+        let reader = StreamReader::try_new(Cursor::new(r), None)?;
+        assert_eq!(reader.schema(), schema);
+        assert_eq!(reader.count(), 0);
+
+        let r = ResultFormat::Parquet.format_batches(schema.clone(), &[], true)?;
+        let builder = ParquetRecordBatchReaderBuilder::try_new(Bytes::from(r)).unwrap();
+        assert_eq!(builder.schema(), &schema);
+        assert_eq!(builder.metadata().file_metadata().num_rows(), 0);
+        assert_eq!(builder.build().unwrap().count(), 0);
+
+        let r = ResultFormat::Csv.format_batches(schema, &[], true)?;
+        assert!(r.is_empty());
+        Ok(())
+    }
 }
@@ -7,6 +7,7 @@ use metrics::metric_register::MetricsRegister;
 use protos::kv_service::tskv_service_server::TskvServiceServer;
 use protos::raft_service::raft_service_server::RaftServiceServer;
 use protos::DEFAULT_GRPC_SERVER_MESSAGE_LEN;
+use replication::file_transfer::RateLimiter;
 use replication::network_grpc::RaftCBServer;
 use tokio::runtime::Runtime;
 use tokio::sync::oneshot;
@@ -87,6 +88,9 @@ impl Service for GrpcService {
             coord: self.coord.clone(),
             metrics_register: self.metrics_register.clone(),
             grpc_enable_gzip: self.enable_gzip,
+            snapshot_rate_limiter: Arc::new(RateLimiter::new(
+                self.coord.get_config().cluster.snapshot_transfer_rate_limit,
+            )),
         })
         .max_decoding_message_size(DEFAULT_GRPC_SERVER_MESSAGE_LEN);
 
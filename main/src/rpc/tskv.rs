@@ -2,8 +2,9 @@ use std::pin::Pin;
 use std::sync::Arc;
 
 use coordinator::errors::{
-    encode_grpc_response, ArrowSnafu, CommonSnafu, CoordinatorResult, TskvSnafu,
+    encode_grpc_response, ArrowSnafu, BincodeSerdeSnafu, CommonSnafu, CoordinatorResult, TskvSnafu,
 };
+use coordinator::get_vnode_versions;
 use coordinator::service::CoordinatorRef;
 use futures::{Stream, TryStreamExt};
 use meta::model::MetaRef;
@@ -12,18 +13,25 @@ use models::meta_data::VnodeInfo;
 use models::predicate::domain::{self, QueryArgs, QueryExpr};
 use models::record_batch_encode;
 use models::schema::TableColumn;
+use object_store::path::Path as ObjectPath;
+use object_store::ObjectStore;
 use protos::kv_service::tskv_service_server::TskvService;
 use protos::kv_service::*;
 use protos::models::{PingBody, PingBodyBuilder};
+use protos::DEFAULT_GRPC_SERVER_MESSAGE_LEN;
+use query::extension::expr::pushed_plan_context;
+use replication::file_transfer::{FileChunkReader, RateLimiter, DEFAULT_CHUNK_SIZE};
 use snafu::ResultExt;
-use tokio::io::AsyncReadExt;
+use spi::query::datasource;
 use tokio::runtime::Runtime;
 use tokio::sync::mpsc;
 use tokio_stream::wrappers::ReceiverStream;
+use tonic::metadata::MetadataValue;
 use tonic::{Extensions, Request, Response, Status};
 use trace::span_ext::SpanExt;
-use trace::{debug, error, info, Span, SpanContext};
+use trace::{debug, error, info, warn, Span, SpanContext};
 use tskv::error::TskvResult;
+use tskv::reader::pushdown::{PushedPlan, PLAN_NOT_PUSHED_HEADER};
 use tskv::reader::query_executor::QueryExecutor;
 use tskv::reader::serialize::TonicRecordBatchEncoder;
 use tskv::reader::{QueryOption, SendableTskvRecordBatchStream};
@@ -31,6 +39,19 @@ use tskv::EngineRef;
 
 type ResponseStream<T> = Pin<Box<dyn Stream<Item = Result<T, tonic::Status>> + Send>>;
 
+fn build_object_store(
+    location: &str,
+    connection_options: &[ConnectionOption],
+) -> CoordinatorResult<(Arc<dyn ObjectStore>, ObjectPath)> {
+    let options = connection_options
+        .iter()
+        .map(|o| (o.name.clone(), o.value.clone()))
+        .collect();
+    datasource::decode_connection_options(options)
+        .and_then(|options| datasource::build_object_store_with_path(location, options))
+        .map_err(|e| CommonSnafu { msg: e.to_string() }.build())
+}
+
 #[derive(Clone)]
 pub struct TskvServiceImpl {
     pub runtime: Arc<Runtime>,
@@ -38,6 +59,7 @@ pub struct TskvServiceImpl {
     pub coord: CoordinatorRef,
     pub metrics_register: Arc<MetricsRegister>,
     pub grpc_enable_gzip: bool,
+    pub snapshot_rate_limiter: Arc<RateLimiter>,
 }
 
 impl TskvServiceImpl {
@@ -85,6 +107,70 @@ impl TskvServiceImpl {
                 Ok(data)
             }
 
+            admin_command::Command::RebuildIndex(req) => {
+                let record = self
+                    .kv_inst
+                    .rebuild_index(req.vnode_id)
+                    .await
+                    .context(TskvSnafu)?;
+                let data = record_batch_encode(&record).context(ArrowSnafu)?;
+                Ok(data)
+            }
+
+            admin_command::Command::BackupVnode(req) => {
+                let (store, location) = build_object_store(&req.location, &req.connection_options)?;
+                let record = self
+                    .kv_inst
+                    .backup_vnode(
+                        req.vnode_id,
+                        store,
+                        location,
+                        &req.backup_id,
+                        req.incremental,
+                    )
+                    .await
+                    .context(TskvSnafu)?;
+                let data = record_batch_encode(&record).context(ArrowSnafu)?;
+                Ok(data)
+            }
+
+            admin_command::Command::RestoreVnode(req) => {
+                let (store, location) = build_object_store(&req.location, &req.connection_options)?;
+                let restore = async {
+                    self.kv_inst
+                        .restore_vnode(
+                            tenant,
+                            &req.db_name,
+                            req.vnode_id,
+                            store,
+                            location,
+                            &req.backup_id,
+                        )
+                        .await
+                        .context(TskvSnafu)
+                };
+                self.coord
+                    .raft_manager()
+                    .replace_vnode_storage(tenant, &req.db_name, req.vnode_id, restore)
+                    .await?;
+                Ok(vec![])
+            }
+
+            admin_command::Command::SyncReplica(req) => {
+                self.coord
+                    .raft_manager()
+                    .linearizable_read(req.replica_id, req.vnode_id, None)
+                    .await?;
+                Ok(vec![])
+            }
+
+            admin_command::Command::GetVnodeVersions(req) => {
+                let versions =
+                    get_vnode_versions(&self.kv_inst, tenant, &req.db_name, &req.vnode_ids).await?;
+                let data = bincode::serialize(&versions).context(BincodeSerdeSnafu)?;
+                Ok(data)
+            }
+
             admin_command::Command::AddRaftFollower(command) => {
                 self.coord
                     .raft_manager()
@@ -106,6 +192,7 @@ impl TskvServiceImpl {
                         &command.db_name,
                         command.vnode_id,
                         command.replica_id,
+                        command.keep_unreachable,
                     )
                     .await?;
                 Ok(vec![])
@@ -140,11 +227,26 @@ impl TskvServiceImpl {
         }
     }
 
+    /// Scans may be served by any replica, so wait until the replica on this node
+    /// is up to date. Returns `Unavailable` if it can't catch up with the leader,
+    /// or lags more than the allowed stale read lag.
+    async fn ensure_readable(&self, args: &QueryArgs, expr: &QueryExpr) -> Result<(), Status> {
+        let raft_manager = self.coord.raft_manager();
+        for vnode_id in args.vnode_ids.iter() {
+            raft_manager
+                .linearizable_read(expr.split.replica_id(), *vnode_id, args.stale_read_max_lag)
+                .await
+                .map_err(|err| Status::unavailable(err.to_string()))?;
+        }
+        Ok(())
+    }
+
     fn query_record_batch_exec(
         self,
         args: QueryArgs,
         expr: QueryExpr,
         aggs: Option<Vec<TableColumn>>,
+        pushed_plan: Option<PushedPlan>,
         span_ctx: Option<&SpanContext>,
     ) -> TskvResult<SendableTskvRecordBatchStream> {
         let option = QueryOption::new(
@@ -153,7 +255,8 @@ impl TskvServiceImpl {
             aggs,
             Arc::new(expr.df_schema),
             expr.table_schema,
-        );
+        )
+        .with_pushed_plan(pushed_plan);
 
         let meta = self.coord.meta_manager();
         let node_id = meta.node_id();
@@ -163,7 +266,7 @@ impl TskvServiceImpl {
         }
 
         let executor = QueryExecutor::new(option, self.runtime.clone(), meta, self.kv_inst.clone());
-        executor.local_node_executor(vnodes, span_ctx)
+        executor.local_node_executor(vnodes, self.coord.memory_pool(), span_ctx)
     }
 
     fn tag_scan_exec(
@@ -275,23 +378,40 @@ impl TskvService for TskvServiceImpl {
         let inner = request.into_inner();
         let opt = self.kv_inst.get_storage_options();
         let filename = opt.path().join(inner.filename);
-        info!("request download file name: {:?}", filename);
+        info!(
+            "request download file name: {:?}, offset: {}",
+            filename, inner.offset
+        );
 
-        let (send, recv) = mpsc::channel(1024);
+        let chunk_size = match inner.chunk_size as usize {
+            0 => DEFAULT_CHUNK_SIZE,
+            size => size.min(DEFAULT_GRPC_SERVER_MESSAGE_LEN / 2),
+        };
+        let mut reader = FileChunkReader::open(
+            &filename,
+            inner.offset,
+            chunk_size,
+            Some(self.snapshot_rate_limiter.clone()),
+        )
+        .await
+        .map_err(|err| self.internal_status(err.to_string()))?;
+
+        let (send, recv) = mpsc::channel(4);
         tokio::spawn(async move {
-            if let Ok(mut file) = tokio::fs::File::open(filename).await {
-                let mut buffer = vec![0; 8 * 1024];
-                while let Ok(len) = file.read(&mut buffer).await {
-                    if len == 0 {
-                        break;
-                    }
-
-                    let _ = send
-                        .send(Ok(BatchBytesResponse {
-                            code: coordinator::errors::SUCCESS_RESPONSE_CODE,
-                            data: (buffer[0..len]).to_vec(),
-                        }))
-                        .await;
+            loop {
+                let response = match reader.next_chunk().await {
+                    Ok(Some(chunk)) => chunk.encode().map(|data| BatchBytesResponse {
+                        code: coordinator::errors::SUCCESS_RESPONSE_CODE,
+                        data,
+                    }),
+                    Ok(None) => break,
+                    Err(err) => Err(err),
+                }
+                .map_err(|err| tonic::Status::new(tonic::Code::Internal, err.to_string()));
+
+                let failed = response.is_err();
+                if send.send(response).await.is_err() || failed {
+                    break;
                 }
             }
         });
@@ -319,12 +439,27 @@ impl TskvService for TskvServiceImpl {
             Ok(expr) => expr,
             Err(err) => return Err(self.internal_status(err.to_string())),
         };
+        self.ensure_readable(&args, &expr).await?;
 
         let aggs = match domain::decode_agg(&inner.aggs) {
             Ok(aggs) => aggs,
             Err(err) => return Err(self.internal_status(err.to_string())),
         };
 
+        // If the pushed plan can't be decoded, e.g. the query node is of a newer version,
+        // scan without it and let the query node execute it.
+        let (pushed_plan, plan_not_pushed) = if inner.plan.is_empty() {
+            (None, false)
+        } else {
+            match PushedPlan::decode(&inner.plan, pushed_plan_context()) {
+                Ok(plan) => (Some(plan), false),
+                Err(err) => {
+                    warn!("Scan without the pushed plan, failed to decode it: {}", err);
+                    (None, true)
+                }
+            }
+        };
+
         let service = self.clone();
 
         let encoded_stream = {
@@ -335,12 +470,20 @@ impl TskvService for TskvServiceImpl {
                 args,
                 expr,
                 aggs,
+                pushed_plan,
                 span.context().as_ref(),
             )?;
             TonicRecordBatchEncoder::new(stream, span).map_err(Into::into)
         };
 
-        Ok(tonic::Response::new(Box::pin(encoded_stream)))
+        let mut response =
+            tonic::Response::new(Box::pin(encoded_stream) as Self::QueryRecordBatchStream);
+        if plan_not_pushed {
+            response
+                .metadata_mut()
+                .insert(PLAN_NOT_PUSHED_HEADER, MetadataValue::from_static("true"));
+        }
+        Ok(response)
     }
 
     type TagScanStream = ResponseStream<BatchBytesResponse>;
@@ -360,6 +503,7 @@ impl TskvService for TskvServiceImpl {
             Ok(expr) => expr,
             Err(err) => return Err(self.internal_status(err.to_string())),
         };
+        self.ensure_readable(&args, &expr).await?;
 
         let stream = {
             let span = Span::enter_with_parent("RecordBatch encorder stream", &span);
@@ -6,6 +6,7 @@ use coordinator::service::CoordinatorRef;
 use dateparser;
 use models::auth::privilege::{DatabasePrivilege, Privilege, TenantObjectPrivilege};
 use models::auth::user::{UserInfo, ROOT, ROOT_PWD};
+use models::consistency_level::ConsistencyLevel;
 use models::oid::Identifier;
 use models::schema::{Precision, DEFAULT_CATALOG, DEFAULT_DATABASE};
 use models::utils::now_timestamp_nanos;
@@ -147,7 +148,7 @@ impl VectorService {
         };
         let user = self
             .dbms
-            .authenticate(&user_info, tenant)
+            .authenticate(&user_info, tenant, None)
             .await
             .map_err(|e| Status::permission_denied(e.to_string()))?;
         if !user.check_privilege(&privilege) {
@@ -186,7 +187,14 @@ impl Vector for VectorService {
             .parse(lines.as_str())
             .map_err(|e| Status::invalid_argument(e.to_string()))?;
         self.coord
-            .write_lines(&tenant, &db, Precision::NS, lines, None)
+            .write_lines(
+                &tenant,
+                &db,
+                Precision::NS,
+                ConsistencyLevel::default(),
+                lines,
+                None,
+            )
             .await
             .map_err(|e| Status::internal(format!("failed to write lines to database {}", e)))?;
         Ok(Response::new(response))
@@ -208,7 +208,7 @@ mod test {
     use std::collections::HashSet;
     use std::{thread, time};
 
-    use models::meta_data::NodeInfo;
+    use models::meta_data::{NodeInfo, NodeTier};
 
     use crate::client::MetaHttpClient;
     use crate::store::command;
@@ -222,6 +222,10 @@ mod test {
         let node = NodeInfo {
             id: 111,
             grpc_addr: "".to_string(),
+            decommissioning: false,
+            zone: None,
+            rack: None,
+            tier: NodeTier::Hot,
         };
 
         let client = reqwest::Client::new();
@@ -249,6 +253,10 @@ mod test {
         let node = NodeInfo {
             id: 111,
             grpc_addr: "".to_string(),
+            decommissioning: false,
+            zone: None,
+            rack: None,
+            tier: NodeTier::Hot,
         };
 
         let req = command::WriteCommand::AddDataNode(cluster.clone(), node);
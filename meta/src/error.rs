@@ -182,6 +182,18 @@ pub enum MetaError {
     #[snafu(display("cannot revoke the privilege {privilege} of role"))]
     #[error_code(code = 56)]
     PrivilegeCannotRevoke { privilege: TenantObjectPrivilege },
+
+    #[snafu(display("Replica placement is unsatisfied: {msg}"))]
+    #[error_code(code = 57)]
+    ReplicaPlacementUnsatisfied { msg: String },
+
+    #[snafu(display("Data node {} is removed, a new node id is required to join again", id))]
+    #[error_code(code = 58)]
+    DataNodeRemoved { id: u64 },
+
+    #[snafu(display("resourceinfo {name} is changed by others"))]
+    #[error_code(code = 59)]
+    ResourceInfoChanged { name: String },
 }
 
 impl MetaError {
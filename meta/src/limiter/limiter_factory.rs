@@ -73,3 +73,20 @@ impl LimiterFactory for LocalRequestLimiterFactory {
         }
     }
 }
+
+/// Creates limiters without limits, used by the mocked meta
+#[derive(Debug, Default)]
+pub struct NoneLimiterFactory;
+
+#[async_trait::async_trait]
+impl LimiterFactory for NoneLimiterFactory {
+    async fn create_default(&self, key: LimiterKey) -> MetaResult<Arc<dyn RequestLimiter>> {
+        let LimiterKey(_, tenant_name) = key;
+        Ok(Arc::new(LocalRequestLimiter::new(
+            "",
+            &tenant_name,
+            None,
+            MetaHttpClient::new(""),
+        )))
+    }
+}
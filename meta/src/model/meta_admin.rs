@@ -4,6 +4,7 @@ use std::collections::{HashMap, HashSet};
 use std::fmt::Debug;
 use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
+use std::time::Duration;
 
 use config::tskv::Config;
 use models::auth::user::{admin_user, User, UserDesc, UserOptions};
@@ -22,7 +23,9 @@ use super::meta_tenant::TenantMeta;
 use super::MetaClientRef;
 use crate::client::MetaHttpClient;
 use crate::error::{MetaError, MetaResult};
-use crate::limiter::limiter_factory::{LimiterFactory, LocalRequestLimiterFactory};
+use crate::limiter::limiter_factory::{
+    LimiterFactory, LocalRequestLimiterFactory, NoneLimiterFactory,
+};
 use crate::limiter::limiter_manager::{LimiterKey, LimiterManager};
 use crate::limiter::{LimiterConfig, LimiterType, RequestLimiter};
 use crate::store::command::{self, EntryLog};
@@ -70,7 +73,10 @@ impl AdminMeta {
         let client = MetaHttpClient::new("");
         let config = Config::default();
 
-        let limiters = LimiterManager::new(HashMap::new());
+        let limiters = LimiterManager::new(HashMap::from([(
+            LimiterType::Tenant,
+            Arc::new(NoneLimiterFactory) as Arc<dyn LimiterFactory>,
+        )]));
         let (tx, rx) = mpsc::channel::<MetaModifyType>(1024);
 
         Self {
@@ -474,9 +480,19 @@ impl AdminMeta {
             self.config.service.grpc_listen_port,
         );
 
+        let tier = self
+            .config
+            .global
+            .tier
+            .parse::<NodeTier>()
+            .map_err(|msg| MetaError::InvalidInitialConfig { msg })?;
         let node = NodeInfo {
             id: self.config.global.node_id,
             grpc_addr,
+            decommissioning: false,
+            zone: self.config.global.zone.clone(),
+            rack: self.config.global.rack.clone(),
+            tier,
         };
 
         let cluster_name = self.config.global.cluster_name.clone();
@@ -498,6 +514,26 @@ impl AdminMeta {
         nodes
     }
 
+    /// Stop placing new buckets on the node.
+    pub async fn decommission_data_node(&self, node_id: NodeId) -> MetaResult<()> {
+        let req = command::WriteCommand::DecommissionDataNode(self.cluster(), node_id);
+
+        self.client.write::<()>(&req).await
+    }
+
+    /// Forget the node and its metrics, it should have no vnodes.
+    pub async fn remove_data_node(&self, node_id: NodeId) -> MetaResult<()> {
+        let req = command::WriteCommand::RemoveDataNode(self.cluster(), node_id);
+
+        self.client.write::<()>(&req).await
+    }
+
+    pub async fn data_nodes_metrics(&self) -> MetaResult<Vec<NodeMetrics>> {
+        let req = command::ReadCommand::NodeMetrics(self.cluster());
+
+        self.client.read::<Vec<NodeMetrics>>(&req).await
+    }
+
     pub async fn report_node_metrics(&self) -> MetaResult<()> {
         let disk_free = match get_disk_info(&self.config.storage.path) {
             Ok(size) => size,
@@ -529,6 +565,17 @@ impl AdminMeta {
 
         self.client.write::<()>(&req).await
     }
+
+    /// Report the series of the vnodes on this node, they are summed up per tenant by meta.
+    pub async fn report_series_count(&self, counts: Vec<VnodeSeriesCount>) -> MetaResult<()> {
+        let req = command::WriteCommand::ReportSeriesCount(self.cluster(), counts);
+
+        self.client.write::<()>(&req).await
+    }
+
+    pub fn report_time_interval(&self) -> Duration {
+        self.config.meta.report_time_interval
+    }
     /******************** Data Node Operation End *********************/
 
     /******************** User Operation Begin *********************/
@@ -762,6 +809,25 @@ impl AdminMeta {
         Ok(())
     }
 
+    /// Write the resourceinfo only if the one in meta has the same time and status as
+    /// `current`, returns [`MetaError::ResourceInfoChanged`] otherwise.
+    pub async fn update_resourceinfo(
+        &self,
+        current: &ResourceInfo,
+        res_info: ResourceInfo,
+    ) -> MetaResult<()> {
+        let req = command::WriteCommand::UpdateResourceInfo(
+            self.cluster(),
+            current.get_name().to_string(),
+            (current.get_time(), current.get_status().clone()),
+            res_info,
+        );
+
+        self.client.write::<()>(&req).await?;
+
+        Ok(())
+    }
+
     pub async fn read_resourceinfo_by_name(&self, name: &str) -> MetaResult<Option<ResourceInfo>> {
         let req = command::ReadCommand::ResourceInfo(self.cluster(), name.to_string());
 
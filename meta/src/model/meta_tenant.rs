@@ -3,6 +3,7 @@
 use std::collections::{HashMap, HashSet};
 use std::fmt::Debug;
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 
 use client::MetaHttpClient;
 use config::common::TenantObjectLimiterConfig;
@@ -31,6 +32,9 @@ pub struct TenantMeta {
 
     data: RwLock<TenantMetaData>,
     pub client: MetaHttpClient,
+
+    // series of the tenant in the cluster and when it's read from meta
+    series_count: RwLock<Option<(Instant, Arc<TenantSeriesCount>)>>,
 }
 
 impl TenantMeta {
@@ -41,6 +45,7 @@ impl TenantMeta {
             meta_url: "".to_string(),
             data: RwLock::new(TenantMetaData::new()),
             client: MetaHttpClient::new(""),
+            series_count: RwLock::new(None),
         }
     }
 
@@ -51,6 +56,7 @@ impl TenantMeta {
             meta_url: meta_url.clone(),
             data: RwLock::new(TenantMetaData::new()),
             client: MetaHttpClient::new(&meta_url),
+            series_count: RwLock::new(None),
         });
 
         client.sync_all_tenant_metadata().await?;
@@ -434,6 +440,21 @@ impl TenantMeta {
         Ok(self.data.read().dbs.clone())
     }
 
+    /// Series of the tenant in the cluster, it's read from meta again if it's older than `max_age`.
+    pub async fn series_count(&self, max_age: Duration) -> MetaResult<Arc<TenantSeriesCount>> {
+        if let Some((time, count)) = self.series_count.read().as_ref() {
+            if time.elapsed() < max_age {
+                return Ok(count.clone());
+            }
+        }
+
+        let req = ReadCommand::TenantSeriesCount(self.cluster.clone(), self.tenant_name());
+        let count = Arc::new(self.client.read::<TenantSeriesCount>(&req).await?);
+        *self.series_count.write() = Some((Instant::now(), count.clone()));
+
+        Ok(count)
+    }
+
     pub async fn drop_db(&self, name: &str) -> MetaResult<bool> {
         let mut exist = false;
         if self.data.read().dbs.contains_key(name) {
@@ -7,7 +7,9 @@ use models::auth::role::{SystemTenantRole, TenantRoleIdentifier};
 use models::auth::user::{UserDesc, UserOptions};
 use models::meta_data::*;
 use models::oid::Oid;
-use models::schema::{DatabaseSchema, ResourceInfo, TableSchema, Tenant, TenantOptions};
+use models::schema::{
+    DatabaseSchema, ResourceInfo, ResourceStatus, TableSchema, Tenant, TenantOptions,
+};
 use parking_lot::RwLock;
 use serde::{Deserialize, Serialize};
 use tokio::sync::broadcast;
@@ -60,6 +62,15 @@ pub enum WriteCommand {
     //cluster, node metrics
     ReportNodeMetrics(String, NodeMetrics),
 
+    // cluster, series of the vnodes of a node
+    ReportSeriesCount(String, Vec<VnodeSeriesCount>),
+
+    // cluster, node id
+    DecommissionDataNode(String, NodeId),
+
+    // cluster, node id
+    RemoveDataNode(String, NodeId),
+
     // cluster, tenant, db schema
     CreateDB(String, String, DatabaseSchema),
 
@@ -138,6 +149,8 @@ pub enum WriteCommand {
     },
     // cluster, resource_name, ResourceInfo
     ResourceInfo(String, String, ResourceInfo),
+    // cluster, resource_name, (time, status) of the current ResourceInfo, ResourceInfo
+    UpdateResourceInfo(String, String, (i64, ResourceStatus), ResourceInfo),
     // cluster, node_id, is_lock
     ResourceInfosMark(String, NodeId, bool),
 }
@@ -177,6 +190,9 @@ pub enum ReadCommand {
 
     // cluster, tenant, db, replication set id
     ReplicationSet(String, String, String, u32),
+
+    // cluster, tenant
+    TenantSeriesCount(String, String),
 }
 
 pub const ENTRY_LOG_TYPE_SET: i32 = 1;
@@ -8,6 +8,7 @@ use models::oid::Oid;
 // **    /cluster_name/tenants/tenant/limiter ->
 // **    /cluster_name/auto_incr_id -> id
 // **    /cluster_name/data_nodes/node_id -> [NodeInfo] 集群、数据节点等信息
+// **    /cluster_name/series_counts/tenant/vnode_id -> [VnodeSeriesCount] series of a vnode
 
 // **    /cluster_name/tenant_name/dbs/db_name -> [DatabaseInfo] db相关信息、保留策略等
 // **    /cluster_name/tenant_name/dbs/db_name/buckets/id -> [BucketInfo] bucket相关信息
@@ -26,6 +27,7 @@ pub const AUTO_INCR_ID: &str = "auto_incr_id";
 pub const DATA_NODES_METRICS: &str = "data_nodes_metrics";
 pub const RESOURCE_INFOS: &str = "resourceinfos";
 pub const RESOURCE_INFOS_MARK: &str = "resourceinfosmark";
+pub const SERIES_COUNTS: &str = "series_counts";
 
 pub struct KeyPath {}
 
@@ -65,6 +67,10 @@ impl KeyPath {
         format!("/{}/data_nodes/{}", cluster, id)
     }
 
+    pub fn removed_data_node_id(cluster: &str, id: u64) -> String {
+        format!("/{}/removed_data_nodes/{}", cluster, id)
+    }
+
     pub fn data_nodes_metrics(cluster: &str) -> String {
         format!("/{}/data_nodes_metrics", cluster)
     }
@@ -73,6 +79,14 @@ impl KeyPath {
         format!("/{}/data_nodes_metrics/{}", cluster, id)
     }
 
+    pub fn series_counts(cluster: &str, tenant: &str) -> String {
+        format!("/{}/series_counts/{}", cluster, tenant)
+    }
+
+    pub fn series_count(cluster: &str, tenant: &str, vnode_id: u32) -> String {
+        format!("/{}/series_counts/{}/{}", cluster, tenant, vnode_id)
+    }
+
     pub fn tenant_dbs(cluster: &str, tenant: &str) -> String {
         format!("/{}/tenants/{}/dbs", cluster, tenant)
     }
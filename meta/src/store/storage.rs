@@ -9,7 +9,10 @@ use models::auth::role::{CustomTenantRole, SystemTenantRole, TenantRoleIdentifie
 use models::auth::user::{UserDesc, UserOptions};
 use models::meta_data::*;
 use models::oid::{Identifier, Oid, UuidGenerator};
-use models::schema::{DatabaseSchema, ResourceInfo, TableSchema, Tenant, TenantOptions};
+use models::schema::{
+    DatabaseOptions, DatabaseSchema, ResourceInfo, ResourceStatus, TableSchema, Tenant,
+    TenantOptions,
+};
 use replication::errors::{HeedSnafu, MsgInvalidSnafu, ReplicationResult, SnapshotErrSnafu};
 use replication::{ApplyContext, ApplyStorage, EngineMetrics, Request, Response};
 use serde::{Deserialize, Serialize};
@@ -455,9 +458,58 @@ impl StateMachine {
             ReadCommand::ReplicationSet(cluster, tenant, db_name, repl_id) => response_encode(
                 self.process_read_replication_set(cluster, tenant, db_name, *repl_id),
             ),
+            ReadCommand::TenantSeriesCount(cluster, tenant) => {
+                response_encode(self.process_read_tenant_series_count(cluster, tenant))
+            }
         }
     }
 
+    /// Sum up the reported series of the vnodes in the buckets of the tenant,
+    /// reports of vnodes which are not in any bucket any more are ignored.
+    pub fn process_read_tenant_series_count(
+        &self,
+        cluster: &str,
+        tenant: &str,
+    ) -> MetaResult<TenantSeriesCount> {
+        let counts =
+            self.children_data::<VnodeSeriesCount>(&KeyPath::series_counts(cluster, tenant))?;
+
+        let mut result = TenantSeriesCount::default();
+        let db_names = self
+            .children_data::<DatabaseSchema>(&KeyPath::tenant_dbs(cluster, tenant))?
+            .into_keys();
+        for db_name in db_names {
+            let buckets = self.children_data::<BucketInfo>(&KeyPath::tenant_db_buckets(
+                cluster, tenant, &db_name,
+            ))?;
+            let mut db_series = 0;
+            let mut tables = HashMap::new();
+            for repl_set in buckets
+                .values()
+                .flat_map(|bucket| bucket.shard_group.iter())
+            {
+                let count = repl_set
+                    .vnodes
+                    .iter()
+                    .filter_map(|vnode| counts.get(&vnode.id.to_string()))
+                    .filter(|count| count.db_name == db_name)
+                    .max_by_key(|count| count.series);
+                if let Some(count) = count {
+                    db_series += count.series;
+                    for (table, series) in count.tables.iter() {
+                        *tables.entry(table.clone()).or_insert(0) += *series;
+                    }
+                }
+            }
+
+            result.series += db_series;
+            result.databases.insert(db_name.clone(), db_series);
+            result.tables.insert(db_name, tables);
+        }
+
+        Ok(result)
+    }
+
     pub fn process_read_replication_set(
         &self,
         cluster: &str,
@@ -610,6 +662,15 @@ impl StateMachine {
             WriteCommand::ReportNodeMetrics(cluster, node_metrics) => {
                 response_encode(self.process_add_node_metrics(cluster, node_metrics))
             }
+            WriteCommand::ReportSeriesCount(cluster, counts) => {
+                response_encode(self.process_report_series_count(cluster, counts))
+            }
+            WriteCommand::DecommissionDataNode(cluster, node_id) => {
+                response_encode(self.process_decommission_data_node(cluster, *node_id))
+            }
+            WriteCommand::RemoveDataNode(cluster, node_id) => {
+                response_encode(self.process_remove_data_node(cluster, *node_id))
+            }
             WriteCommand::CreateDB(cluster, tenant, schema) => {
                 response_encode(self.process_create_db(cluster, tenant, schema))
             }
@@ -730,6 +791,9 @@ impl StateMachine {
             WriteCommand::ResourceInfo(cluster, name, res_info) => {
                 response_encode(self.process_write_resourceinfo(cluster, name, res_info))
             }
+            WriteCommand::UpdateResourceInfo(cluster, name, current, res_info) => {
+                response_encode(self.process_update_resourceinfo(cluster, name, current, res_info))
+            }
             WriteCommand::ResourceInfosMark(cluster, node_id, is_lock) => {
                 response_encode(self.process_write_resourceinfos_mark(cluster, *node_id, *is_lock))
             }
@@ -867,13 +931,18 @@ impl StateMachine {
     }
 
     fn process_add_date_node(&self, cluster: &str, node: &NodeInfo) -> MetaResult<()> {
+        self.check_node_not_removed(cluster, node.id)?;
         if !self.check_node_ip_address(cluster, node)? {
             return Err(MetaError::DataNodeExist {
                 addr: node.grpc_addr.clone(),
             });
         }
         let key = KeyPath::data_node_id(cluster, node.id);
-        let value = value_encode(node)?;
+        let mut node = node.clone();
+        if let Some(old_node) = self.get_struct::<NodeInfo>(&key)? {
+            node.decommissioning = old_node.decommissioning;
+        }
+        let value = value_encode(&node)?;
         let res = self.insert(&key, &value);
         if res.is_ok() {
             let _ = self.process_write_resourceinfos_mark(cluster, node.id, true);
@@ -881,16 +950,76 @@ impl StateMachine {
         res
     }
 
+    fn process_decommission_data_node(&self, cluster: &str, node_id: NodeId) -> MetaResult<()> {
+        let key = KeyPath::data_node_id(cluster, node_id);
+        let mut node = self
+            .get_struct::<NodeInfo>(&key)?
+            .ok_or(MetaError::NotFoundNode { id: node_id })?;
+        node.decommissioning = true;
+        self.insert(&key, &value_encode(&node)?)
+    }
+
+    /// Returns an error if the data node was removed, a removed node may still be running
+    /// with its old data, it must not join the cluster again with the same id.
+    fn check_node_not_removed(&self, cluster: &str, node_id: NodeId) -> MetaResult<()> {
+        if self.contains_key(&KeyPath::removed_data_node_id(cluster, node_id))? {
+            return Err(MetaError::DataNodeRemoved { id: node_id });
+        }
+
+        Ok(())
+    }
+
+    fn process_remove_data_node(&self, cluster: &str, node_id: NodeId) -> MetaResult<()> {
+        self.remove(&KeyPath::data_node_id(cluster, node_id))?;
+        self.remove(&KeyPath::data_node_metrics(cluster, node_id))?;
+        self.insert(
+            &KeyPath::removed_data_node_id(cluster, node_id),
+            &value_encode(&node_id)?,
+        )?;
+
+        // hand the lock of resource tasks over to another node
+        let (mark_node_id, is_lock) = self.process_read_resourceinfos_mark(cluster)?;
+        if mark_node_id == node_id && is_lock {
+            let next_node_id = self
+                .children_data::<NodeInfo>(&KeyPath::data_nodes(cluster))?
+                .into_values()
+                .map(|n| n.id)
+                .min();
+            let mark = match next_node_id {
+                Some(id) => (id, true),
+                None => (0, false),
+            };
+            self.insert(&KeyPath::resourceinfosmark(cluster), &value_encode(&mark)?)?;
+        }
+
+        Ok(())
+    }
+
     fn process_add_node_metrics(
         &self,
         cluster: &str,
         node_metrics: &NodeMetrics,
     ) -> MetaResult<()> {
+        self.check_node_not_removed(cluster, node_metrics.id)?;
         let key = KeyPath::data_node_metrics(cluster, node_metrics.id);
         let value = value_encode(node_metrics)?;
         self.insert(&key, &value)
     }
 
+    fn process_report_series_count(
+        &self,
+        cluster: &str,
+        counts: &[VnodeSeriesCount],
+    ) -> MetaResult<()> {
+        for count in counts {
+            let key = KeyPath::series_count(cluster, &count.tenant, count.vnode_id);
+            let value = value_encode(count)?;
+            self.insert(&key, &value)?;
+        }
+
+        Ok(())
+    }
+
     fn process_drop_db(&self, cluster: &str, tenant: &str, db_name: &str) -> MetaResult<()> {
         let key = KeyPath::tenant_db_name(cluster, tenant, db_name);
         let _ = self.remove(&key);
@@ -984,7 +1113,7 @@ impl StateMachine {
 
     fn check_db_schema_valid(&self, cluster: &str, db_schema: &DatabaseSchema) -> MetaResult<()> {
         let node_list = self.get_valid_node_list(cluster)?;
-        check_node_enough(db_schema.config.replica_or_default(), &node_list)?;
+        check_node_enough(&db_schema.config, &node_list)?;
 
         if db_schema.config.shard_num_or_default() == 0 {
             return Err(MetaError::DatabaseSchemaInvalid {
@@ -1035,6 +1164,8 @@ impl StateMachine {
                         });
                     }
                 }
+                // CREATE OR REPLACE VIEW
+                (TableSchema::ViewSchema(_), TableSchema::ViewSchema(_)) => {}
                 _ => {
                     return Err(MetaError::NotSupport {
                         msg: "update external table".to_string(),
@@ -1047,6 +1178,8 @@ impl StateMachine {
         Ok(())
     }
 
+    /// Nodes for new buckets: the healthy hot nodes which are not decommissioning,
+    /// the ones with more free disk space first.
     fn get_valid_node_list(&self, cluster: &str) -> MetaResult<Vec<NodeInfo>> {
         let node_info_list: Vec<NodeInfo> = self
             .children_data::<NodeInfo>(&KeyPath::data_nodes(cluster))?
@@ -1061,7 +1194,7 @@ impl StateMachine {
         let mut node_info_list = node_info_list
             .into_iter()
             .filter_map(|n| node_metrics_list.get(&n.id).map(|m| (n, m)))
-            .filter(|(_, m)| m.is_healthy())
+            .filter(|(n, m)| m.is_healthy() && !n.decommissioning && n.tier == NodeTier::Hot)
             .collect::<Vec<_>>();
 
         node_info_list.sort_by_key(|(_, m)| Reverse(m.disk_free));
@@ -1089,7 +1222,7 @@ impl StateMachine {
             })?;
 
         let node_list = self.get_valid_node_list(cluster)?;
-        check_node_enough(db_schema.config.replica_or_default(), &node_list)?;
+        check_node_enough(&db_schema.config, &node_list)?;
 
         if db_schema.config.shard_num_or_default() == 0 {
             return Err(MetaError::DatabaseSchemaInvalid {
@@ -1121,6 +1254,7 @@ impl StateMachine {
             db_schema.config.shard_num_or_default() as u32,
             db_schema.config.replica_or_default() as u32,
             bucket.id + 1,
+            db_schema.config.replica_placement().as_ref(),
         );
         bucket.shard_group = group;
         self.fetch_and_add_incr_id(cluster, used)?;
@@ -1332,6 +1466,11 @@ impl StateMachine {
         self.remove(&key)?;
         self.remove(&limiter_key)?;
 
+        let series_counts_path = KeyPath::series_counts(cluster, name);
+        for it in self.children_fullpath(&series_counts_path)?.iter() {
+            let _ = self.remove(it);
+        }
+
         Ok(())
     }
 
@@ -1547,6 +1686,24 @@ impl StateMachine {
         self.insert(&key, &value_encode(&res_info)?)
     }
 
+    /// Write the resourceinfo only if the current one has the same time and status.
+    fn process_update_resourceinfo(
+        &self,
+        cluster: &str,
+        name: &str,
+        current: &(i64, ResourceStatus),
+        res_info: &ResourceInfo,
+    ) -> MetaResult<()> {
+        match self.process_read_resourceinfo_by_name(cluster, name)? {
+            Some(old) if (old.get_time(), old.get_status()) == (current.0, &current.1) => {
+                self.process_write_resourceinfo(cluster, name, res_info)
+            }
+            _ => Err(MetaError::ResourceInfoChanged {
+                name: name.to_string(),
+            }),
+        }
+    }
+
     fn process_write_resourceinfos_mark(
         &self,
         cluster: &str,
@@ -1565,13 +1722,19 @@ impl StateMachine {
     }
 }
 
-fn check_node_enough(need: u64, node_list: &[NodeInfo]) -> MetaResult<()> {
+fn check_node_enough(options: &DatabaseOptions, node_list: &[NodeInfo]) -> MetaResult<()> {
+    let need = options.replica_or_default();
     if need > node_list.len() as u64 {
         return Err(MetaError::ValidNodeNotEnough {
             need,
             valid_node_num: node_list.len() as u32,
         });
     }
+    if let Some(placement) = options.replica_placement() {
+        placement
+            .check(node_list, need)
+            .map_err(|msg| MetaError::ReplicaPlacementUnsatisfied { msg })?;
+    }
     Ok(())
 }
 
@@ -1580,8 +1743,194 @@ mod test {
     use std::collections::BTreeMap;
     use std::println;
 
+    use models::meta_data::{
+        BucketInfo, NodeInfo, NodeMetrics, NodeTier, ReplicationSet, VnodeInfo, VnodeSeriesCount,
+    };
+    use models::node_info::NodeStatus;
+    use models::schema::{DatabaseSchema, ResourceInfo, ResourceOperator, ResourceStatus};
     use serde::{Deserialize, Serialize};
 
+    use super::{value_encode, StateMachine};
+    use crate::error::MetaError;
+    use crate::store::key_path::KeyPath;
+
+    #[test]
+    fn test_removed_data_node_can_not_join() {
+        let path = "/tmp/test/meta/removed_data_node";
+        let _ = std::fs::remove_dir_all(path);
+        let storage = StateMachine::open(path, 1024 * 1024 * 1024).unwrap();
+        let cluster = "cluster_xxx";
+        let node = NodeInfo {
+            id: 2,
+            grpc_addr: "127.0.0.1:8903".to_string(),
+            ..Default::default()
+        };
+        let metrics = NodeMetrics {
+            id: 2,
+            ..Default::default()
+        };
+        storage.process_add_date_node(cluster, &node).unwrap();
+        storage.process_add_node_metrics(cluster, &metrics).unwrap();
+
+        storage.process_remove_data_node(cluster, 2).unwrap();
+        assert!(matches!(
+            storage.process_add_date_node(cluster, &node),
+            Err(MetaError::DataNodeRemoved { id: 2 })
+        ));
+        assert!(matches!(
+            storage.process_add_node_metrics(cluster, &metrics),
+            Err(MetaError::DataNodeRemoved { id: 2 })
+        ));
+
+        // A new node id is accepted.
+        let node = NodeInfo { id: 3, ..node };
+        storage.process_add_date_node(cluster, &node).unwrap();
+    }
+
+    #[test]
+    fn test_update_resourceinfo() {
+        let path = "/tmp/test/meta/update_resourceinfo";
+        let _ = std::fs::remove_dir_all(path);
+        let storage = StateMachine::open(path, 1024 * 1024 * 1024).unwrap();
+        let cluster = "cluster_xxx";
+        let name = "rebalance";
+        let resourceinfo = ResourceInfo::new(
+            (0, "".to_string()),
+            name.to_string(),
+            ResourceOperator::Rebalance(vec![]),
+            &None,
+            1,
+        );
+        storage
+            .process_write_resourceinfo(cluster, name, &resourceinfo)
+            .unwrap();
+        let current = (resourceinfo.get_time(), ResourceStatus::Executing);
+
+        let mut paused = resourceinfo.clone();
+        paused.set_status(ResourceStatus::Pausing);
+        storage
+            .process_update_resourceinfo(cluster, name, &current, &paused)
+            .unwrap();
+
+        // The status is changed, it's not overwritten.
+        let mut moving = resourceinfo;
+        moving.set_comment("moving");
+        assert!(matches!(
+            storage.process_update_resourceinfo(cluster, name, &current, &moving),
+            Err(MetaError::ResourceInfoChanged { .. })
+        ));
+        let stored = storage
+            .process_read_resourceinfo_by_name(cluster, name)
+            .unwrap()
+            .unwrap();
+        assert_eq!(*stored.get_status(), ResourceStatus::Pausing);
+    }
+
+    #[test]
+    fn test_tenant_series_count() {
+        let path = "/tmp/test/meta/tenant_series_count";
+        let _ = std::fs::remove_dir_all(path);
+        let storage = StateMachine::open(path, 1024 * 1024 * 1024).unwrap();
+        let cluster = "cluster_xxx";
+        let tenant = "cnosdb";
+
+        let schema = DatabaseSchema::new(tenant, "db0");
+        let key = KeyPath::tenant_db_name(cluster, tenant, "db0");
+        storage
+            .insert(&key, &value_encode(&schema).unwrap())
+            .unwrap();
+        // Two replication sets, each of two vnodes.
+        let bucket = BucketInfo {
+            id: 1,
+            shard_group: vec![
+                ReplicationSet::new(1, 1, 1, vec![VnodeInfo::new(1, 1), VnodeInfo::new(2, 2)]),
+                ReplicationSet::new(2, 1, 3, vec![VnodeInfo::new(3, 1), VnodeInfo::new(4, 2)]),
+            ],
+            ..Default::default()
+        };
+        let key = KeyPath::tenant_bucket_id(cluster, tenant, "db0", 1);
+        storage
+            .insert(&key, &value_encode(&bucket).unwrap())
+            .unwrap();
+
+        let count = |vnode_id, series, tables: &[(&str, u64)]| VnodeSeriesCount {
+            vnode_id,
+            tenant: tenant.to_string(),
+            db_name: "db0".to_string(),
+            series,
+            tables: tables.iter().map(|(t, c)| (t.to_string(), *c)).collect(),
+        };
+        storage
+            .process_report_series_count(
+                cluster,
+                &[
+                    count(1, 10, &[("t0", 6), ("t1", 4)]),
+                    // A replica which is behind.
+                    count(2, 8, &[("t0", 4), ("t1", 4)]),
+                    count(3, 5, &[("t0", 5)]),
+                    // A vnode not in any bucket.
+                    count(5, 100, &[("t0", 100)]),
+                ],
+            )
+            .unwrap();
+
+        let result = storage
+            .process_read_tenant_series_count(cluster, tenant)
+            .unwrap();
+        assert_eq!(result.series, 15);
+        assert_eq!(result.database_series("db0"), 15);
+        assert_eq!(result.table_series("db0", "t0"), 11);
+        assert_eq!(result.table_series("db0", "t1"), 4);
+        assert_eq!(result.table_series("db0", "t2"), 0);
+        assert_eq!(result.database_series("db1"), 0);
+    }
+
+    #[test]
+    fn test_get_valid_node_list() {
+        let path = "/tmp/test/meta/valid_node_list";
+        let _ = std::fs::remove_dir_all(path);
+        let storage = StateMachine::open(path, 1024 * 1024 * 1024).unwrap();
+        let cluster = "cluster_xxx";
+        let nodes = [
+            (1, NodeTier::Hot, false, NodeStatus::Healthy, 100),
+            (2, NodeTier::Hot, false, NodeStatus::Healthy, 300),
+            (3, NodeTier::Cold, false, NodeStatus::Healthy, 500),
+            (4, NodeTier::Hot, true, NodeStatus::Healthy, 500),
+            (5, NodeTier::Hot, false, NodeStatus::Unreachable, 500),
+            (6, NodeTier::Hot, false, NodeStatus::Healthy, 200),
+        ];
+        for (id, tier, decommissioning, status, disk_free) in nodes {
+            let node = NodeInfo {
+                id,
+                tier,
+                decommissioning,
+                ..Default::default()
+            };
+            let metrics = NodeMetrics {
+                id,
+                disk_free,
+                status,
+                ..Default::default()
+            };
+            storage.process_add_date_node(cluster, &node).unwrap();
+            storage.process_add_node_metrics(cluster, &metrics).unwrap();
+        }
+        // A node without metrics.
+        let node = NodeInfo {
+            id: 7,
+            ..Default::default()
+        };
+        storage.process_add_date_node(cluster, &node).unwrap();
+
+        let ids = storage
+            .get_valid_node_list(cluster)
+            .unwrap()
+            .into_iter()
+            .map(|n| n.id)
+            .collect::<Vec<_>>();
+        assert_eq!(ids, vec![2, 6, 1]);
+    }
+
     #[test]
     fn test_btree_map() {
         let mut map = BTreeMap::new();
@@ -5,7 +5,7 @@ use std::{env, thread, time};
 
 use meta::client;
 use meta::store::command;
-use models::meta_data::NodeInfo;
+use models::meta_data::{NodeInfo, NodeTier};
 use models::schema::Tenant;
 use sysinfo::System;
 #[cfg(feature = "meta_e2e_test")]
@@ -42,6 +42,10 @@ async fn write_data_to_meta() {
     let node = NodeInfo {
         id: 111,
         grpc_addr: "".to_string(),
+        decommissioning: false,
+        zone: None,
+        rack: None,
+        tier: NodeTier::Hot,
     };
     let req = command::WriteCommand::AddDataNode("cluster_xxx".to_string(), node);
     let cli = client::MetaHttpClient::new("127.0.0.1:8901");
@@ -0,0 +1,127 @@
+use std::any::Any;
+use std::str::FromStr;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use datafusion::arrow::datatypes::SchemaRef;
+use datafusion::common::Result as DFResult;
+use datafusion::datasource::file_format::arrow::ArrowFormat;
+use datafusion::datasource::file_format::avro::AvroFormat;
+use datafusion::datasource::file_format::csv::CsvFormat;
+use datafusion::datasource::file_format::file_type::{FileCompressionType, FileType};
+use datafusion::datasource::file_format::json::JsonFormat;
+use datafusion::datasource::file_format::parquet::ParquetFormat;
+use datafusion::datasource::file_format::FileFormat;
+use datafusion::datasource::listing::ListingOptions;
+use datafusion::datasource::physical_plan::FileScanConfig;
+use datafusion::execution::context::SessionState;
+use datafusion::physical_expr::PhysicalExpr;
+use datafusion::physical_plan::{ExecutionPlan, Statistics};
+use models::arrow::ExternalFileType;
+use models::schema::ExternalTableSchema;
+use object_store::{ObjectMeta, ObjectStore};
+use spi::query::logical_planner::FileBlockCompression;
+
+use super::orc::OrcFormat;
+
+/// Build the format of files of external tables and `COPY`, and the extension of the files.
+pub fn build_file_format(
+    file_type: &ExternalFileType,
+    file_compression_type: FileCompressionType,
+    has_header: bool,
+    delimiter: u8,
+) -> DFResult<(String, Arc<dyn FileFormat>)> {
+    let file_extension = file_type.get_ext_with_compression(file_compression_type.to_owned())?;
+    let file_format: Arc<dyn FileFormat> = match file_type {
+        ExternalFileType::DataFusion(FileType::CSV) => Arc::new(
+            CsvFormat::default()
+                .with_has_header(has_header)
+                .with_delimiter(delimiter)
+                .with_file_compression_type(file_compression_type),
+        ),
+        ExternalFileType::DataFusion(FileType::PARQUET) => Arc::new(ParquetFormat::default()),
+        ExternalFileType::DataFusion(FileType::AVRO) => Arc::new(AvroFormat),
+        ExternalFileType::DataFusion(FileType::JSON) => {
+            Arc::new(JsonFormat::default().with_file_compression_type(file_compression_type))
+        }
+        ExternalFileType::DataFusion(FileType::ARROW) => Arc::new(ArrowFormat),
+        ExternalFileType::Orc => Arc::new(OrcFormat),
+    };
+
+    Ok((file_extension, file_format))
+}
+
+/// The listing options of the files of an external table.
+pub fn external_table_options(schema: &ExternalTableSchema) -> DFResult<ListingOptions> {
+    let file_type = ExternalFileType::from_str(&schema.file_type)?;
+    let file_compression_type = FileCompressionType::from_str(&schema.file_compression_type)?;
+    let (file_extension, file_format) = build_file_format(
+        &file_type,
+        file_compression_type,
+        schema.has_header,
+        schema.delimiter,
+    )?;
+
+    Ok(ListingOptions::new(file_format)
+        .with_file_extension(file_extension)
+        .with_target_partitions(schema.target_partitions))
+}
+
+/// A [`FileFormat`] whose files are read by the inner format,
+/// and written with the blocks compressed by `compression`.
+#[derive(Debug)]
+pub struct CompressedFileFormat {
+    inner: Arc<dyn FileFormat>,
+    compression: FileBlockCompression,
+}
+
+impl CompressedFileFormat {
+    pub fn new(inner: Arc<dyn FileFormat>, compression: FileBlockCompression) -> Self {
+        Self { inner, compression }
+    }
+
+    pub fn inner(&self) -> &Arc<dyn FileFormat> {
+        &self.inner
+    }
+
+    pub fn compression(&self) -> FileBlockCompression {
+        self.compression
+    }
+}
+
+#[async_trait]
+impl FileFormat for CompressedFileFormat {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    async fn infer_schema(
+        &self,
+        state: &SessionState,
+        store: &Arc<dyn ObjectStore>,
+        objects: &[ObjectMeta],
+    ) -> DFResult<SchemaRef> {
+        self.inner.infer_schema(state, store, objects).await
+    }
+
+    async fn infer_stats(
+        &self,
+        state: &SessionState,
+        store: &Arc<dyn ObjectStore>,
+        table_schema: SchemaRef,
+        object: &ObjectMeta,
+    ) -> DFResult<Statistics> {
+        self.inner
+            .infer_stats(state, store, table_schema, object)
+            .await
+    }
+
+    async fn create_physical_plan(
+        &self,
+        state: &SessionState,
+        conf: FileScanConfig,
+        filters: Option<&Arc<dyn PhysicalExpr>>,
+    ) -> DFResult<Arc<dyn ExecutionPlan>> {
+        self.inner.create_physical_plan(state, conf, filters).await
+    }
+}
@@ -18,6 +18,8 @@ use crate::extension::physical::plan_node::update_tag::UpdateTagExec;
 use crate::extension::DropEmptyRecordBatchStream;
 
 pub mod batch;
+pub mod file_format;
+pub mod orc;
 pub mod sink;
 pub mod split;
 pub mod stream;
@@ -0,0 +1,149 @@
+use std::io::Read;
+
+use datafusion::common::Result as DFResult;
+use datafusion::error::DataFusionError;
+use flate2::read::DeflateDecoder;
+
+use super::invalid_file;
+use super::proto::compression_kind;
+
+/// Compression of the streams, footer and metadata of an ORC file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Compression {
+    None,
+    Zlib,
+    Snappy,
+    Lz4,
+    Zstd,
+}
+
+impl Compression {
+    pub fn try_from_kind(kind: i32) -> DFResult<Self> {
+        match kind {
+            compression_kind::NONE => Ok(Self::None),
+            compression_kind::ZLIB => Ok(Self::Zlib),
+            compression_kind::SNAPPY => Ok(Self::Snappy),
+            compression_kind::LZ4 => Ok(Self::Lz4),
+            compression_kind::ZSTD => Ok(Self::Zstd),
+            compression_kind::LZO => Err(DataFusionError::NotImplemented(
+                "LZO compressed ORC files are not supported".to_string(),
+            )),
+            _ => Err(invalid_file(format!("unknown compression kind {}", kind))),
+        }
+    }
+
+    /// Decompress a stream, which is a sequence of chunks of at most `block_size`
+    /// bytes after decompression if it's compressed. Each chunk starts with a 3 bytes
+    /// little-endian header, which is `length << 1 | is_original`.
+    pub fn decompress(&self, data: &[u8], block_size: usize) -> DFResult<Vec<u8>> {
+        if *self == Self::None {
+            return Ok(data.to_vec());
+        }
+
+        let mut output = Vec::with_capacity(data.len());
+        let mut pos = 0;
+        while pos < data.len() {
+            let header = data
+                .get(pos..pos + 3)
+                .ok_or_else(|| invalid_file("truncated compression chunk header"))?;
+            let header =
+                header[0] as usize | (header[1] as usize) << 8 | (header[2] as usize) << 16;
+            let (len, is_original) = (header >> 1, header & 1 == 1);
+            pos += 3;
+            let chunk = data
+                .get(pos..pos + len)
+                .ok_or_else(|| invalid_file("truncated compression chunk"))?;
+            pos += len;
+
+            if is_original {
+                output.extend_from_slice(chunk);
+            } else {
+                self.decompress_chunk(chunk, block_size, &mut output)?;
+            }
+        }
+
+        Ok(output)
+    }
+
+    fn decompress_chunk(
+        &self,
+        chunk: &[u8],
+        block_size: usize,
+        output: &mut Vec<u8>,
+    ) -> DFResult<()> {
+        let error = |e: String| invalid_file(format!("decompress {:?} chunk: {}", self, e));
+        match self {
+            Self::None => output.extend_from_slice(chunk),
+            Self::Zlib => {
+                // raw deflate without zlib header
+                DeflateDecoder::new(chunk)
+                    .read_to_end(output)
+                    .map_err(|e| error(e.to_string()))?;
+            }
+            Self::Snappy => {
+                let decompressed = snap::raw::Decoder::new()
+                    .decompress_vec(chunk)
+                    .map_err(|e| error(e.to_string()))?;
+                output.extend(decompressed);
+            }
+            Self::Lz4 => {
+                let decompressed = lz4_flex::block::decompress(chunk, block_size)
+                    .map_err(|e| error(e.to_string()))?;
+                output.extend(decompressed);
+            }
+            Self::Zstd => {
+                let decompressed = zstd::decode_all(chunk).map_err(|e| error(e.to_string()))?;
+                output.extend(decompressed);
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use flate2::write::DeflateEncoder;
+
+    use super::Compression;
+
+    /// Write the data as chunks of at most 4 bytes, compressed by zlib or as originals.
+    fn zlib_chunks(data: &[u8], original: bool) -> Vec<u8> {
+        let mut stream = vec![];
+        for chunk in data.chunks(4) {
+            let chunk = if original {
+                chunk.to_vec()
+            } else {
+                let mut encoder = DeflateEncoder::new(vec![], flate2::Compression::default());
+                encoder.write_all(chunk).unwrap();
+                encoder.finish().unwrap()
+            };
+            let header = chunk.len() << 1 | original as usize;
+            stream.extend([header as u8, (header >> 8) as u8, (header >> 16) as u8]);
+            stream.extend(chunk);
+        }
+        stream
+    }
+
+    #[test]
+    fn test_decompress_chunks() {
+        let data = b"time series database".to_vec();
+        for original in [false, true] {
+            let stream = zlib_chunks(&data, original);
+            assert_eq!(Compression::Zlib.decompress(&stream, 4).unwrap(), data);
+        }
+        assert_eq!(Compression::None.decompress(&data, 4).unwrap(), data);
+
+        let snappy = snap::raw::Encoder::new().compress_vec(&data).unwrap();
+        let header = snappy.len() << 1;
+        let mut stream = vec![header as u8, (header >> 8) as u8, (header >> 16) as u8];
+        stream.extend(snappy);
+        assert_eq!(Compression::Snappy.decompress(&stream, 1024).unwrap(), data);
+
+        let truncated = zlib_chunks(&data, false);
+        assert!(Compression::Zlib
+            .decompress(&truncated[..truncated.len() - 1], 4)
+            .is_err());
+    }
+}
@@ -0,0 +1,236 @@
+use std::any::Any;
+use std::collections::VecDeque;
+use std::fmt;
+use std::sync::Arc;
+
+use datafusion::arrow::datatypes::SchemaRef;
+use datafusion::arrow::record_batch::RecordBatch;
+use datafusion::common::Result as DFResult;
+use datafusion::datasource::listing::PartitionedFile;
+use datafusion::datasource::physical_plan::FileScanConfig;
+use datafusion::error::DataFusionError;
+use datafusion::execution::context::TaskContext;
+use datafusion::physical_expr::PhysicalSortExpr;
+use datafusion::physical_plan::metrics::{BaselineMetrics, ExecutionPlanMetricsSet, MetricsSet};
+use datafusion::physical_plan::stream::RecordBatchStreamAdapter;
+use datafusion::physical_plan::{
+    DisplayFormatType, ExecutionPlan, Partitioning, SendableRecordBatchStream, Statistics,
+};
+use futures::stream;
+use object_store::{ObjectMeta, ObjectStore};
+
+use super::proto::StripeInformation;
+use super::reader::{fetch_stripe, OrcTail};
+
+/// Execution plan for scanning ORC files, each file group is a partition.
+#[derive(Debug)]
+pub struct OrcExec {
+    conf: FileScanConfig,
+    /// The schema once the projection has been applied to the file schema
+    schema: SchemaRef,
+    /// Execution metrics
+    metrics: ExecutionPlanMetricsSet,
+}
+
+impl OrcExec {
+    pub fn try_new(conf: FileScanConfig) -> DFResult<Self> {
+        if !conf.table_partition_cols.is_empty() {
+            return Err(DataFusionError::NotImplemented(
+                "partition columns of ORC files are not supported".to_string(),
+            ));
+        }
+
+        let schema = match &conf.projection {
+            Some(projection) => Arc::new(conf.file_schema.project(projection)?),
+            None => conf.file_schema.clone(),
+        };
+
+        Ok(Self {
+            conf,
+            schema,
+            metrics: ExecutionPlanMetricsSet::new(),
+        })
+    }
+}
+
+impl ExecutionPlan for OrcExec {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn schema(&self) -> SchemaRef {
+        self.schema.clone()
+    }
+
+    fn output_partitioning(&self) -> Partitioning {
+        Partitioning::UnknownPartitioning(self.conf.file_groups.len())
+    }
+
+    fn output_ordering(&self) -> Option<&[PhysicalSortExpr]> {
+        None
+    }
+
+    fn children(&self) -> Vec<Arc<dyn ExecutionPlan>> {
+        vec![]
+    }
+
+    fn with_new_children(
+        self: Arc<Self>,
+        _children: Vec<Arc<dyn ExecutionPlan>>,
+    ) -> DFResult<Arc<dyn ExecutionPlan>> {
+        Ok(self)
+    }
+
+    fn execute(
+        &self,
+        partition: usize,
+        context: Arc<TaskContext>,
+    ) -> DFResult<SendableRecordBatchStream> {
+        let files = self.conf.file_groups.get(partition).ok_or_else(|| {
+            DataFusionError::Internal(format!("OrcExec invalid partition {}", partition))
+        })?;
+        let store = context
+            .runtime_env()
+            .object_store(&self.conf.object_store_url)?;
+
+        let state = OrcStreamState {
+            store,
+            files: files.iter().cloned().collect(),
+            file: None,
+            batches: VecDeque::new(),
+            schema: self.schema.clone(),
+            batch_size: context.session_config().batch_size(),
+            remaining: self.conf.limit,
+            baseline_metrics: BaselineMetrics::new(&self.metrics, partition),
+        };
+        let stream = stream::try_unfold(state, |mut state| async move {
+            let batch = state.next_batch().await?;
+            Ok(batch.map(|batch| (batch, state)))
+        });
+
+        Ok(Box::pin(RecordBatchStreamAdapter::new(
+            self.schema.clone(),
+            stream,
+        )))
+    }
+
+    fn fmt_as(&self, t: DisplayFormatType, f: &mut fmt::Formatter) -> fmt::Result {
+        match t {
+            DisplayFormatType::Default | DisplayFormatType::Verbose => {
+                let files = self
+                    .conf
+                    .file_groups
+                    .iter()
+                    .flatten()
+                    .map(|file| file.object_meta.location.as_ref())
+                    .collect::<Vec<_>>();
+                write!(f, "OrcExec: files=[{}]", files.join(", "))?;
+                if let Some(limit) = self.conf.limit {
+                    write!(f, ", limit={}", limit)?;
+                }
+                Ok(())
+            }
+        }
+    }
+
+    fn metrics(&self) -> Option<MetricsSet> {
+        Some(self.metrics.clone_inner())
+    }
+
+    fn statistics(&self) -> Statistics {
+        self.conf.statistics.clone()
+    }
+}
+
+/// An opened file, whose stripes are read one by one.
+struct OrcFile {
+    meta: ObjectMeta,
+    tail: OrcTail,
+    column_ids: Vec<Option<usize>>,
+    stripes: VecDeque<StripeInformation>,
+}
+
+struct OrcStreamState {
+    store: Arc<dyn ObjectStore>,
+    files: VecDeque<PartitionedFile>,
+    file: Option<OrcFile>,
+    /// The batches of the last read stripe
+    batches: VecDeque<RecordBatch>,
+    schema: SchemaRef,
+    batch_size: usize,
+    /// Rows to read at most
+    remaining: Option<usize>,
+    baseline_metrics: BaselineMetrics,
+}
+
+impl OrcStreamState {
+    async fn next_batch(&mut self) -> DFResult<Option<RecordBatch>> {
+        loop {
+            if self.remaining == Some(0) {
+                self.baseline_metrics.done();
+                return Ok(None);
+            }
+
+            if let Some(mut batch) = self.batches.pop_front() {
+                if let Some(remaining) = self.remaining.as_mut() {
+                    batch = batch.slice(0, batch.num_rows().min(*remaining));
+                    *remaining -= batch.num_rows();
+                }
+                self.baseline_metrics.record_output(batch.num_rows());
+                return Ok(Some(batch));
+            }
+
+            if let Some(file) = self.file.as_mut() {
+                if let Some(stripe) = file.stripes.pop_front() {
+                    let data = fetch_stripe(self.store.as_ref(), &file.meta, &stripe).await?;
+                    let _timer = self.baseline_metrics.elapsed_compute().timer();
+                    self.batches = file
+                        .tail
+                        .read_stripe(
+                            &stripe,
+                            &data,
+                            self.schema.clone(),
+                            &file.column_ids,
+                            self.batch_size,
+                        )?
+                        .into();
+                    continue;
+                }
+                self.file = None;
+            }
+
+            match self.files.pop_front() {
+                Some(file) => self.file = Some(self.open(file).await?),
+                None => {
+                    self.baseline_metrics.done();
+                    return Ok(None);
+                }
+            }
+        }
+    }
+
+    async fn open(&self, file: PartitionedFile) -> DFResult<OrcFile> {
+        let tail = OrcTail::read(self.store.as_ref(), &file.object_meta).await?;
+        let column_ids = tail.column_ids(&self.schema)?;
+        // a stripe belongs to the range of the file containing its start
+        let stripes = tail
+            .stripes()
+            .iter()
+            .filter(|stripe| match &file.range {
+                Some(range) => {
+                    let offset = stripe.offset.unwrap_or_default() as i64;
+                    range.start <= offset && offset < range.end
+                }
+                None => true,
+            })
+            .cloned()
+            .collect();
+
+        Ok(OrcFile {
+            meta: file.object_meta,
+            tail,
+            column_ids,
+            stripes,
+        })
+    }
+}
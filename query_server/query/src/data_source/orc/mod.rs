@@ -0,0 +1,83 @@
+//! Reader of ORC files for external tables and `COPY INTO <table> FROM`.
+//!
+//! Only the top-level columns of primitive types are read, stripes are read
+//! as a whole and the row indexes and statistics in the file are not used.
+
+use std::any::Any;
+use std::fmt::Display;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use datafusion::arrow::datatypes::{Schema, SchemaRef};
+use datafusion::common::Result as DFResult;
+use datafusion::datasource::file_format::FileFormat;
+use datafusion::datasource::physical_plan::FileScanConfig;
+use datafusion::error::DataFusionError;
+use datafusion::execution::context::SessionState;
+use datafusion::physical_expr::PhysicalExpr;
+use datafusion::physical_plan::{ExecutionPlan, Statistics};
+use object_store::{ObjectMeta, ObjectStore};
+
+pub use self::exec::OrcExec;
+use self::reader::OrcTail;
+
+mod compression;
+mod exec;
+mod proto;
+mod reader;
+mod rle;
+
+pub const ORC_FILE_EXTENSION: &str = ".orc";
+
+fn invalid_file(msg: impl Display) -> DataFusionError {
+    DataFusionError::Execution(format!("Invalid ORC file: {}", msg))
+}
+
+/// The [`FileFormat`] of ORC files, which can only be read.
+#[derive(Debug, Default)]
+pub struct OrcFormat;
+
+#[async_trait]
+impl FileFormat for OrcFormat {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    async fn infer_schema(
+        &self,
+        _state: &SessionState,
+        store: &Arc<dyn ObjectStore>,
+        objects: &[ObjectMeta],
+    ) -> DFResult<SchemaRef> {
+        let mut schemas = Vec::with_capacity(objects.len());
+        for object in objects {
+            let tail = OrcTail::read(store.as_ref(), object).await?;
+            schemas.push(tail.schema()?);
+        }
+        Ok(Arc::new(Schema::try_merge(schemas)?))
+    }
+
+    async fn infer_stats(
+        &self,
+        _state: &SessionState,
+        store: &Arc<dyn ObjectStore>,
+        _table_schema: SchemaRef,
+        object: &ObjectMeta,
+    ) -> DFResult<Statistics> {
+        let tail = OrcTail::read(store.as_ref(), object).await?;
+        Ok(Statistics {
+            num_rows: Some(tail.num_rows() as usize),
+            is_exact: true,
+            ..Default::default()
+        })
+    }
+
+    async fn create_physical_plan(
+        &self,
+        _state: &SessionState,
+        conf: FileScanConfig,
+        _filters: Option<&Arc<dyn PhysicalExpr>>,
+    ) -> DFResult<Arc<dyn ExecutionPlan>> {
+        Ok(Arc::new(OrcExec::try_new(conf)?))
+    }
+}
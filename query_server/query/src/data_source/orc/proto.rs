@@ -0,0 +1,152 @@
+//! Messages of the ORC file tail and stripe footers, a subset of `orc_proto.proto`
+//! of Apache ORC. Fields that are not read are skipped by the decoder.
+
+use datafusion::common::Result as DFResult;
+use prost::Message;
+
+use super::invalid_file;
+
+#[derive(Clone, PartialEq, Message)]
+pub struct PostScript {
+    #[prost(uint64, optional, tag = "1")]
+    pub footer_length: Option<u64>,
+    /// See [`compression_kind`]
+    #[prost(int32, optional, tag = "2")]
+    pub compression: Option<i32>,
+    #[prost(uint64, optional, tag = "3")]
+    pub compression_block_size: Option<u64>,
+    #[prost(uint32, repeated, tag = "4")]
+    pub version: Vec<u32>,
+    #[prost(uint64, optional, tag = "5")]
+    pub metadata_length: Option<u64>,
+    #[prost(string, optional, tag = "8000")]
+    pub magic: Option<String>,
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct Footer {
+    #[prost(uint64, optional, tag = "1")]
+    pub header_length: Option<u64>,
+    #[prost(uint64, optional, tag = "2")]
+    pub content_length: Option<u64>,
+    #[prost(message, repeated, tag = "3")]
+    pub stripes: Vec<StripeInformation>,
+    #[prost(message, repeated, tag = "4")]
+    pub types: Vec<Type>,
+    #[prost(uint64, optional, tag = "6")]
+    pub number_of_rows: Option<u64>,
+    #[prost(uint32, optional, tag = "8")]
+    pub row_index_stride: Option<u32>,
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct StripeInformation {
+    #[prost(uint64, optional, tag = "1")]
+    pub offset: Option<u64>,
+    #[prost(uint64, optional, tag = "2")]
+    pub index_length: Option<u64>,
+    #[prost(uint64, optional, tag = "3")]
+    pub data_length: Option<u64>,
+    #[prost(uint64, optional, tag = "4")]
+    pub footer_length: Option<u64>,
+    #[prost(uint64, optional, tag = "5")]
+    pub number_of_rows: Option<u64>,
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct Type {
+    /// See [`type_kind`]
+    #[prost(int32, optional, tag = "1")]
+    pub kind: Option<i32>,
+    #[prost(uint32, repeated, tag = "2")]
+    pub subtypes: Vec<u32>,
+    #[prost(string, repeated, tag = "3")]
+    pub field_names: Vec<String>,
+    #[prost(uint32, optional, tag = "4")]
+    pub maximum_length: Option<u32>,
+    #[prost(uint32, optional, tag = "5")]
+    pub precision: Option<u32>,
+    #[prost(uint32, optional, tag = "6")]
+    pub scale: Option<u32>,
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct StripeFooter {
+    #[prost(message, repeated, tag = "1")]
+    pub streams: Vec<Stream>,
+    #[prost(message, repeated, tag = "2")]
+    pub columns: Vec<ColumnEncoding>,
+    #[prost(string, optional, tag = "3")]
+    pub writer_timezone: Option<String>,
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct Stream {
+    /// See [`stream_kind`]
+    #[prost(int32, optional, tag = "1")]
+    pub kind: Option<i32>,
+    #[prost(uint32, optional, tag = "2")]
+    pub column: Option<u32>,
+    #[prost(uint64, optional, tag = "3")]
+    pub length: Option<u64>,
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct ColumnEncoding {
+    /// See [`encoding_kind`]
+    #[prost(int32, optional, tag = "1")]
+    pub kind: Option<i32>,
+    #[prost(uint32, optional, tag = "2")]
+    pub dictionary_size: Option<u32>,
+}
+
+pub mod compression_kind {
+    pub const NONE: i32 = 0;
+    pub const ZLIB: i32 = 1;
+    pub const SNAPPY: i32 = 2;
+    pub const LZO: i32 = 3;
+    pub const LZ4: i32 = 4;
+    pub const ZSTD: i32 = 5;
+}
+
+pub mod type_kind {
+    pub const BOOLEAN: i32 = 0;
+    pub const BYTE: i32 = 1;
+    pub const SHORT: i32 = 2;
+    pub const INT: i32 = 3;
+    pub const LONG: i32 = 4;
+    pub const FLOAT: i32 = 5;
+    pub const DOUBLE: i32 = 6;
+    pub const STRING: i32 = 7;
+    pub const BINARY: i32 = 8;
+    pub const TIMESTAMP: i32 = 9;
+    pub const LIST: i32 = 10;
+    pub const MAP: i32 = 11;
+    pub const STRUCT: i32 = 12;
+    pub const UNION: i32 = 13;
+    pub const DECIMAL: i32 = 14;
+    pub const DATE: i32 = 15;
+    pub const VARCHAR: i32 = 16;
+    pub const CHAR: i32 = 17;
+    pub const TIMESTAMP_INSTANT: i32 = 18;
+}
+
+pub mod stream_kind {
+    pub const PRESENT: i32 = 0;
+    pub const DATA: i32 = 1;
+    pub const LENGTH: i32 = 2;
+    pub const DICTIONARY_DATA: i32 = 3;
+    pub const SECONDARY: i32 = 5;
+}
+
+pub mod encoding_kind {
+    pub const DIRECT: i32 = 0;
+    pub const DICTIONARY: i32 = 1;
+    pub const DIRECT_V2: i32 = 2;
+    pub const DICTIONARY_V2: i32 = 3;
+}
+
+/// Decode a message, the error is reported with the name of the message.
+pub fn decode<M: Message + Default>(name: &str, bytes: &[u8]) -> DFResult<M> {
+    M::decode(bytes).map_err(|e| invalid_file(format!("decode {}: {}", name, e)))
+}
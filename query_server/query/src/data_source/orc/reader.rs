@@ -0,0 +1,678 @@
+use std::collections::HashMap;
+use std::ops::Range;
+use std::sync::Arc;
+
+use bytes::Bytes;
+use datafusion::arrow::array::{
+    new_null_array, ArrayRef, BinaryArray, BooleanArray, Date32Array, Decimal128Array,
+    Float32Array, Float64Array, Int16Array, Int32Array, Int64Array, Int8Array, StringArray,
+    TimestampNanosecondArray,
+};
+use datafusion::arrow::compute::cast;
+use datafusion::arrow::datatypes::{DataType, Field, Schema, SchemaRef, TimeUnit};
+use datafusion::arrow::record_batch::{RecordBatch, RecordBatchOptions};
+use datafusion::common::Result as DFResult;
+use datafusion::error::DataFusionError;
+use object_store::{ObjectMeta, ObjectStore};
+
+use super::compression::Compression;
+use super::invalid_file;
+use super::proto::{
+    self, encoding_kind, stream_kind, type_kind, ColumnEncoding, Footer, PostScript, StripeFooter,
+    StripeInformation, Type,
+};
+use super::rle::{
+    decode_bool_rle, decode_byte_rle, decode_int_rle, zigzag128, ByteReader, RleVersion,
+};
+
+/// Bytes read from the end of a file at first, which usually contain the whole tail.
+const DEFAULT_TAIL_SIZE: usize = 16 * 1024;
+const DEFAULT_COMPRESSION_BLOCK_SIZE: u64 = 256 * 1024;
+/// Timestamps are seconds since 2015-01-01 00:00:00.
+const TIMESTAMP_BASE_SECONDS: i64 = 1_420_070_400;
+const MAX_DECIMAL_PRECISION: u32 = 38;
+
+/// The postscript and footer at the end of an ORC file.
+#[derive(Debug)]
+pub struct OrcTail {
+    compression: Compression,
+    block_size: usize,
+    footer: Footer,
+}
+
+impl OrcTail {
+    pub async fn read(store: &dyn ObjectStore, meta: &ObjectMeta) -> DFResult<Self> {
+        let file_len = meta.size;
+        let tail_len = file_len.min(DEFAULT_TAIL_SIZE);
+        let tail = store
+            .get_range(&meta.location, file_len - tail_len..file_len)
+            .await?;
+
+        // the last byte is the length of the postscript, which is not compressed
+        let postscript_len = *tail.last().ok_or_else(|| invalid_file("empty file"))? as usize;
+        let postscript_start = tail_len
+            .checked_sub(postscript_len + 1)
+            .ok_or_else(|| invalid_file("truncated postscript"))?;
+        let postscript: PostScript =
+            proto::decode("postscript", &tail[postscript_start..tail_len - 1])?;
+        if postscript.magic.as_deref() != Some("ORC") {
+            return Err(invalid_file(format!(
+                "{} is not an ORC file",
+                meta.location
+            )));
+        }
+
+        let footer_len = postscript.footer_length.unwrap_or_default() as usize;
+        let footer = match postscript_start.checked_sub(footer_len) {
+            Some(footer_start) => tail.slice(footer_start..postscript_start),
+            None => {
+                let footer_end = file_len - tail_len + postscript_start;
+                let footer_start = footer_end
+                    .checked_sub(footer_len)
+                    .ok_or_else(|| invalid_file("truncated footer"))?;
+                store
+                    .get_range(&meta.location, footer_start..footer_end)
+                    .await?
+            }
+        };
+
+        let compression = Compression::try_from_kind(postscript.compression.unwrap_or_default())?;
+        let block_size = postscript
+            .compression_block_size
+            .unwrap_or(DEFAULT_COMPRESSION_BLOCK_SIZE) as usize;
+        let footer = proto::decode("footer", &compression.decompress(&footer, block_size)?)?;
+
+        Ok(Self {
+            compression,
+            block_size,
+            footer,
+        })
+    }
+
+    pub fn num_rows(&self) -> u64 {
+        self.footer.number_of_rows.unwrap_or_default()
+    }
+
+    pub fn stripes(&self) -> &[StripeInformation] {
+        &self.footer.stripes
+    }
+
+    /// The schema of the top-level columns, nested columns are not supported.
+    pub fn schema(&self) -> DFResult<Schema> {
+        let fields = self
+            .columns()?
+            .map(|(name, column)| {
+                Ok(Field::new(
+                    name,
+                    arrow_type(self.column_type(column)?)?,
+                    true,
+                ))
+            })
+            .collect::<DFResult<Vec<_>>>()?;
+        Ok(Schema::new(fields))
+    }
+
+    /// The names and ids of the top-level columns, which are the fields of the root struct.
+    fn columns(&self) -> DFResult<impl Iterator<Item = (&str, usize)>> {
+        let root = self.column_type(0)?;
+        if root.kind != Some(type_kind::STRUCT) {
+            return Err(invalid_file("the root type is not a struct"));
+        }
+        Ok(root
+            .field_names
+            .iter()
+            .zip(&root.subtypes)
+            .map(|(name, column)| (name.as_str(), *column as usize)))
+    }
+
+    fn column_type(&self, column: usize) -> DFResult<&Type> {
+        self.footer
+            .types
+            .get(column)
+            .ok_or_else(|| invalid_file(format!("type of column {} is missing", column)))
+    }
+
+    /// The ids of the columns of the fields, None if a field is not in the file.
+    pub fn column_ids(&self, schema: &Schema) -> DFResult<Vec<Option<usize>>> {
+        let columns = self.columns()?.collect::<Vec<_>>();
+        Ok(schema
+            .fields()
+            .iter()
+            .map(|field| {
+                columns
+                    .iter()
+                    .find(|(name, _)| *name == field.name())
+                    .or_else(|| {
+                        columns
+                            .iter()
+                            .find(|(name, _)| name.eq_ignore_ascii_case(field.name()))
+                    })
+                    .map(|(_, column)| *column)
+            })
+            .collect())
+    }
+
+    /// Read the columns of the stripe as record batches of the schema, of at most
+    /// `batch_size` rows. `data` is the whole stripe including its footer.
+    pub fn read_stripe(
+        &self,
+        stripe: &StripeInformation,
+        data: &[u8],
+        schema: SchemaRef,
+        column_ids: &[Option<usize>],
+        batch_size: usize,
+    ) -> DFResult<Vec<RecordBatch>> {
+        let rows = stripe.number_of_rows.unwrap_or_default() as usize;
+        let streams = StripeStreams::try_new(self, stripe, data)?;
+
+        let columns = schema
+            .fields()
+            .iter()
+            .zip(column_ids)
+            .map(|(field, column)| match column {
+                Some(column) => {
+                    let array = streams.read_column(*column, self.column_type(*column)?, rows)?;
+                    if array.data_type() == field.data_type() {
+                        Ok(array)
+                    } else {
+                        Ok(cast(&array, field.data_type())?)
+                    }
+                }
+                None => Ok(new_null_array(field.data_type(), rows)),
+            })
+            .collect::<DFResult<Vec<_>>>()?;
+        let batch = RecordBatch::try_new_with_options(
+            schema,
+            columns,
+            &RecordBatchOptions::new().with_row_count(Some(rows)),
+        )?;
+
+        let batch_size = batch_size.max(1);
+        Ok((0..rows)
+            .step_by(batch_size)
+            .map(|offset| batch.slice(offset, batch_size.min(rows - offset)))
+            .collect())
+    }
+
+    /// The range of the stripe in the file.
+    pub fn stripe_range(stripe: &StripeInformation) -> Range<usize> {
+        let start = stripe.offset.unwrap_or_default() as usize;
+        let len = stripe.index_length.unwrap_or_default()
+            + stripe.data_length.unwrap_or_default()
+            + stripe.footer_length.unwrap_or_default();
+        start..start + len as usize
+    }
+}
+
+/// Converts the type of a top-level column.
+fn arrow_type(column_type: &Type) -> DFResult<DataType> {
+    let data_type = match column_type.kind.unwrap_or_default() {
+        type_kind::BOOLEAN => DataType::Boolean,
+        type_kind::BYTE => DataType::Int8,
+        type_kind::SHORT => DataType::Int16,
+        type_kind::INT => DataType::Int32,
+        type_kind::LONG => DataType::Int64,
+        type_kind::FLOAT => DataType::Float32,
+        type_kind::DOUBLE => DataType::Float64,
+        type_kind::STRING | type_kind::VARCHAR | type_kind::CHAR => DataType::Utf8,
+        type_kind::BINARY => DataType::Binary,
+        type_kind::TIMESTAMP => DataType::Timestamp(TimeUnit::Nanosecond, None),
+        type_kind::TIMESTAMP_INSTANT => {
+            DataType::Timestamp(TimeUnit::Nanosecond, Some("UTC".into()))
+        }
+        type_kind::DATE => DataType::Date32,
+        type_kind::DECIMAL => {
+            // the precision is not set by old writers
+            let precision = match column_type.precision {
+                Some(precision) if precision > 0 => precision.min(MAX_DECIMAL_PRECISION),
+                _ => MAX_DECIMAL_PRECISION,
+            };
+            let scale = column_type.scale.unwrap_or_default().min(precision);
+            DataType::Decimal128(precision as u8, scale as i8)
+        }
+        kind @ (type_kind::LIST | type_kind::MAP | type_kind::STRUCT | type_kind::UNION) => {
+            return Err(DataFusionError::NotImplemented(format!(
+                "nested ORC column of type {} is not supported",
+                match kind {
+                    type_kind::LIST => "LIST",
+                    type_kind::MAP => "MAP",
+                    type_kind::STRUCT => "STRUCT",
+                    _ => "UNION",
+                }
+            )));
+        }
+        kind => return Err(invalid_file(format!("unknown type kind {}", kind))),
+    };
+    Ok(data_type)
+}
+
+/// The streams and column encodings of a stripe.
+struct StripeStreams<'a> {
+    compression: Compression,
+    block_size: usize,
+    data: &'a [u8],
+    /// The ranges of the streams in `data` by column and kind
+    streams: HashMap<(usize, i32), Range<usize>>,
+    encodings: Vec<ColumnEncoding>,
+}
+
+impl<'a> StripeStreams<'a> {
+    fn try_new(tail: &OrcTail, stripe: &StripeInformation, data: &'a [u8]) -> DFResult<Self> {
+        let footer_start = (stripe.index_length.unwrap_or_default()
+            + stripe.data_length.unwrap_or_default()) as usize;
+        let footer_end = footer_start + stripe.footer_length.unwrap_or_default() as usize;
+        let footer = data
+            .get(footer_start..footer_end)
+            .ok_or_else(|| invalid_file("truncated stripe"))?;
+        let footer: StripeFooter = proto::decode(
+            "stripe footer",
+            &tail.compression.decompress(footer, tail.block_size)?,
+        )?;
+
+        // the streams are stored in order, index streams first
+        let mut streams = HashMap::with_capacity(footer.streams.len());
+        let mut offset = 0;
+        for stream in footer.streams {
+            let len = stream.length.unwrap_or_default() as usize;
+            if offset + len > footer_start {
+                return Err(invalid_file("stream is out of the stripe"));
+            }
+            let key = (
+                stream.column.unwrap_or_default() as usize,
+                stream.kind.unwrap_or_default(),
+            );
+            streams.insert(key, offset..offset + len);
+            offset += len;
+        }
+
+        Ok(Self {
+            compression: tail.compression,
+            block_size: tail.block_size,
+            data,
+            streams,
+            encodings: footer.columns,
+        })
+    }
+
+    /// The decompressed stream, None if it doesn't exist.
+    fn stream(&self, column: usize, kind: i32) -> DFResult<Option<Vec<u8>>> {
+        self.streams
+            .get(&(column, kind))
+            .map(|range| {
+                self.compression
+                    .decompress(&self.data[range.clone()], self.block_size)
+            })
+            .transpose()
+    }
+
+    fn required_stream(&self, column: usize, kind: i32) -> DFResult<Vec<u8>> {
+        self.stream(column, kind)?
+            .ok_or_else(|| invalid_file(format!("stream {} of column {} is missing", kind, column)))
+    }
+
+    fn read_column(&self, column: usize, column_type: &Type, rows: usize) -> DFResult<ArrayRef> {
+        let present = self
+            .stream(column, stream_kind::PRESENT)?
+            .map(|present| decode_bool_rle(&present, rows))
+            .transpose()?;
+        let present = present.as_deref();
+        // only present values are stored
+        let n = present.map_or(rows, |present| present.iter().filter(|p| **p).count());
+
+        let encoding = self
+            .encodings
+            .get(column)
+            .and_then(|encoding| encoding.kind)
+            .unwrap_or_default();
+        let version = match encoding {
+            encoding_kind::DIRECT | encoding_kind::DICTIONARY => RleVersion::V1,
+            encoding_kind::DIRECT_V2 | encoding_kind::DICTIONARY_V2 => RleVersion::V2,
+            kind => return Err(invalid_file(format!("unknown column encoding {}", kind))),
+        };
+        let read_ints = |kind: i32, n: usize, signed: bool| {
+            decode_int_rle(&self.required_stream(column, kind)?, n, signed, version)
+        };
+
+        let array: ArrayRef = match column_type.kind.unwrap_or_default() {
+            type_kind::BOOLEAN => {
+                let values = decode_bool_rle(&self.required_stream(column, stream_kind::DATA)?, n)?;
+                Arc::new(BooleanArray::from(with_nulls(present, values)))
+            }
+            type_kind::BYTE => {
+                let values = decode_byte_rle(&self.required_stream(column, stream_kind::DATA)?, n)?;
+                let values = values.into_iter().map(|v| v as i8).collect();
+                Arc::new(Int8Array::from(with_nulls(present, values)))
+            }
+            type_kind::SHORT => {
+                let values = read_ints(stream_kind::DATA, n, true)?;
+                let values = values.into_iter().map(|v| v as i16).collect();
+                Arc::new(Int16Array::from(with_nulls(present, values)))
+            }
+            type_kind::INT => {
+                let values = read_ints(stream_kind::DATA, n, true)?;
+                let values = values.into_iter().map(|v| v as i32).collect();
+                Arc::new(Int32Array::from(with_nulls(present, values)))
+            }
+            type_kind::LONG => {
+                let values = read_ints(stream_kind::DATA, n, true)?;
+                Arc::new(Int64Array::from(with_nulls(present, values)))
+            }
+            type_kind::DATE => {
+                let values = read_ints(stream_kind::DATA, n, true)?;
+                let values = values.into_iter().map(|v| v as i32).collect();
+                Arc::new(Date32Array::from(with_nulls(present, values)))
+            }
+            type_kind::FLOAT => {
+                let data = self.required_stream(column, stream_kind::DATA)?;
+                let values =
+                    read_fixed(&data, n, |b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]))?;
+                Arc::new(Float32Array::from(with_nulls(present, values)))
+            }
+            type_kind::DOUBLE => {
+                let data = self.required_stream(column, stream_kind::DATA)?;
+                let values = read_fixed(&data, n, |b| {
+                    f64::from_le_bytes([b[0], b[1], b[2], b[3], b[4], b[5], b[6], b[7]])
+                })?;
+                Arc::new(Float64Array::from(with_nulls(present, values)))
+            }
+            kind @ (type_kind::STRING
+            | type_kind::VARCHAR
+            | type_kind::CHAR
+            | type_kind::BINARY) => {
+                let data;
+                let values = if matches!(
+                    encoding,
+                    encoding_kind::DICTIONARY | encoding_kind::DICTIONARY_V2
+                ) {
+                    let size = self
+                        .encodings
+                        .get(column)
+                        .and_then(|encoding| encoding.dictionary_size)
+                        .unwrap_or_default() as usize;
+                    let lengths = read_ints(stream_kind::LENGTH, size, false)?;
+                    data = self
+                        .stream(column, stream_kind::DICTIONARY_DATA)?
+                        .unwrap_or_default();
+                    let dictionary = split_bytes(&data, &lengths)?;
+                    read_ints(stream_kind::DATA, n, false)?
+                        .into_iter()
+                        .map(|index| {
+                            dictionary.get(index as usize).copied().ok_or_else(|| {
+                                invalid_file(format!("dictionary index {} is out of range", index))
+                            })
+                        })
+                        .collect::<DFResult<Vec<_>>>()?
+                } else {
+                    let lengths = read_ints(stream_kind::LENGTH, n, false)?;
+                    data = self.required_stream(column, stream_kind::DATA)?;
+                    split_bytes(&data, &lengths)?
+                };
+
+                let values = with_nulls(present, values);
+                if kind == type_kind::BINARY {
+                    Arc::new(BinaryArray::from(values))
+                } else {
+                    let values = values
+                        .into_iter()
+                        .map(|v| v.map(std::str::from_utf8).transpose())
+                        .collect::<Result<Vec<_>, _>>()
+                        .map_err(|e| invalid_file(format!("invalid string: {}", e)))?;
+                    Arc::new(StringArray::from(values))
+                }
+            }
+            kind @ (type_kind::TIMESTAMP | type_kind::TIMESTAMP_INSTANT) => {
+                let seconds = read_ints(stream_kind::DATA, n, true)?;
+                let nanos = read_ints(stream_kind::SECONDARY, n, false)?;
+                let values = seconds
+                    .into_iter()
+                    .zip(nanos)
+                    .map(|(seconds, nanos)| timestamp_nanos(seconds, nanos as u64))
+                    .collect::<DFResult<Vec<_>>>()?;
+                let array = TimestampNanosecondArray::from(with_nulls(present, values));
+                if kind == type_kind::TIMESTAMP_INSTANT {
+                    Arc::new(array.with_timezone("UTC"))
+                } else {
+                    Arc::new(array)
+                }
+            }
+            type_kind::DECIMAL => {
+                let (precision, scale) = match arrow_type(column_type)? {
+                    DataType::Decimal128(precision, scale) => (precision, scale),
+                    data_type => unreachable!("the type of a decimal column is {}", data_type),
+                };
+                let data = self.required_stream(column, stream_kind::DATA)?;
+                let mut reader = ByteReader::new(&data);
+                let scales = read_ints(stream_kind::SECONDARY, n, true)?;
+                let values = scales
+                    .into_iter()
+                    .map(|value_scale| {
+                        let value = zigzag128(reader.read_uvarint128()?);
+                        rescale_decimal(value, value_scale, scale as i64)
+                    })
+                    .collect::<DFResult<Vec<_>>>()?;
+                Arc::new(
+                    Decimal128Array::from(with_nulls(present, values))
+                        .with_precision_and_scale(precision, scale)?,
+                )
+            }
+            _ => arrow_type(column_type).map(|data_type| new_null_array(&data_type, rows))?,
+        };
+
+        Ok(array)
+    }
+}
+
+/// Spread the present values to the rows.
+fn with_nulls<T: Copy>(present: Option<&[bool]>, values: Vec<T>) -> Vec<Option<T>> {
+    match present {
+        Some(present) => {
+            let mut values = values.into_iter();
+            present
+                .iter()
+                .map(|p| if *p { values.next() } else { None })
+                .collect()
+        }
+        None => values.into_iter().map(Some).collect(),
+    }
+}
+
+fn read_fixed<T, const N: usize>(
+    data: &[u8],
+    n: usize,
+    f: impl Fn(&[u8]) -> T,
+) -> DFResult<Vec<T>> {
+    if data.len() < n * N {
+        return Err(invalid_file("unexpected end of stream"));
+    }
+    Ok(data.chunks_exact(N).take(n).map(f).collect())
+}
+
+/// Split the data to values of the lengths.
+fn split_bytes<'a>(data: &'a [u8], lengths: &[i64]) -> DFResult<Vec<&'a [u8]>> {
+    let mut reader = ByteReader::new(data);
+    lengths
+        .iter()
+        .map(|len| reader.read_bytes(*len as usize))
+        .collect()
+}
+
+/// Converts seconds since 2015-01-01 and the encoded nanoseconds to nanoseconds since
+/// the Unix epoch. The nanoseconds are encoded with the number of trailing decimal zeros
+/// in the lowest 3 bits if it's more than 1.
+fn timestamp_nanos(seconds: i64, encoded_nanos: u64) -> DFResult<i64> {
+    let zeros = encoded_nanos & 0x07;
+    let mut nanos = (encoded_nanos >> 3) as i64;
+    if zeros != 0 {
+        nanos *= 10_i64.pow(zeros as u32 + 1);
+    }
+    let mut seconds = seconds + TIMESTAMP_BASE_SECONDS;
+    // seconds of timestamps before the epoch were truncated towards zero by writers
+    if seconds < 0 && nanos > 999_999 {
+        seconds -= 1;
+    }
+    seconds
+        .checked_mul(1_000_000_000)
+        .and_then(|s| s.checked_add(nanos))
+        .ok_or_else(|| invalid_file("timestamp overflows nanoseconds"))
+}
+
+fn rescale_decimal(value: i128, value_scale: i64, scale: i64) -> DFResult<i128> {
+    let overflow = || invalid_file("decimal overflows 128 bits");
+    let factor = |diff: i64| {
+        u32::try_from(diff)
+            .ok()
+            .and_then(|diff| 10_i128.checked_pow(diff))
+            .ok_or_else(overflow)
+    };
+    match value_scale.cmp(&scale) {
+        std::cmp::Ordering::Equal => Ok(value),
+        std::cmp::Ordering::Less => value
+            .checked_mul(factor(scale - value_scale)?)
+            .ok_or_else(overflow),
+        std::cmp::Ordering::Greater => Ok(value / factor(value_scale - scale)?),
+    }
+}
+
+/// Read the whole stripe of the file.
+pub async fn fetch_stripe(
+    store: &dyn ObjectStore,
+    meta: &ObjectMeta,
+    stripe: &StripeInformation,
+) -> DFResult<Bytes> {
+    let range = OrcTail::stripe_range(stripe);
+    if range.end > meta.size {
+        return Err(invalid_file("stripe is out of the file"));
+    }
+    Ok(store.get_range(&meta.location, range).await?)
+}
+
+#[cfg(test)]
+mod tests {
+    use datafusion::assert_batches_eq;
+    use object_store::memory::InMemory;
+    use object_store::path::Path;
+
+    use super::*;
+
+    /// The file of `resource/csv/part-0.csv`, which is compressed by zlib, and has
+    /// dictionary encoded strings and all null columns.
+    const PART_0: &[u8] = include_bytes!("../../../../sqllogicaltests/resource/orc/part-0.orc");
+
+    async fn put(store: &InMemory, data: &[u8]) -> ObjectMeta {
+        let location = Path::from("part-0.orc");
+        store
+            .put(&location, Bytes::copy_from_slice(data))
+            .await
+            .unwrap();
+        store.head(&location).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_read_stripe() {
+        let store = InMemory::new();
+        let meta = put(&store, PART_0).await;
+        let tail = OrcTail::read(&store, &meta).await.unwrap();
+        assert_eq!(tail.num_rows(), 10);
+
+        let schema = tail.schema().unwrap();
+        assert_eq!(schema.fields().len(), 16);
+        assert_eq!(
+            schema.field(0),
+            &Field::new(
+                "time",
+                DataType::Timestamp(TimeUnit::Nanosecond, None),
+                true
+            )
+        );
+        assert_eq!(schema.field(1), &Field::new("name", DataType::Utf8, true));
+        assert_eq!(
+            schema.field(6),
+            &Field::new("latitude", DataType::Float64, true)
+        );
+
+        // columns are matched by name, and missing columns are null
+        let projected = Arc::new(Schema::new(vec![
+            Field::new("name", DataType::Utf8, true),
+            Field::new("LATITUDE", DataType::Float64, true),
+            Field::new("grade", DataType::Float64, true),
+            Field::new("unknown", DataType::Int64, true),
+            Field::new(
+                "time",
+                DataType::Timestamp(TimeUnit::Nanosecond, None),
+                true,
+            ),
+        ]));
+        let column_ids = tail.column_ids(&projected).unwrap();
+        assert_eq!(column_ids, vec![Some(2), Some(7), Some(12), None, Some(1)]);
+
+        let stripe = &tail.stripes()[0];
+        let data = fetch_stripe(&store, &meta, stripe).await.unwrap();
+        let batches = tail
+            .read_stripe(stripe, &data, projected.clone(), &column_ids, 4)
+            .unwrap();
+        assert_eq!(
+            batches.iter().map(|b| b.num_rows()).collect::<Vec<_>>(),
+            vec![4, 4, 2]
+        );
+        let expected = [
+            "+------------+----------+-------+---------+---------------------+",
+            "| name       | LATITUDE | grade | unknown | time                |",
+            "+------------+----------+-------+---------+---------------------+",
+            "| truck_2991 | 34.5     |       |         | 2022-01-01T02:00:10 |",
+            "| truck_2991 | 31.9     |       |         | 2022-01-01T02:00:20 |",
+            "| truck_2991 | 29.1     |       |         | 2022-01-01T02:00:30 |",
+            "| truck_2991 | 26.3     |       |         | 2022-01-01T02:00:40 |",
+            "| truck_2991 | 22.2     |       |         | 2022-01-01T02:01:00 |",
+            "| truck_2991 | 27.0     |       |         | 2022-01-01T02:01:10 |",
+            "| truck_2991 | 22.3     |       |         | 2022-01-01T02:01:20 |",
+            "| truck_2991 | 20.8     |       |         | 2022-01-01T02:01:30 |",
+            "| truck_2991 | 22.9     |       |         | 2022-01-01T02:01:40 |",
+            "| truck_2991 | 24.2     |       |         | 2022-01-01T02:01:50 |",
+            "+------------+----------+-------+---------+---------------------+",
+        ];
+        assert_batches_eq!(expected, &batches);
+
+        // the stripe footer is at the end of the stripe
+        assert!(tail
+            .read_stripe(stripe, &data[..data.len() - 1], projected, &column_ids, 4)
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_read_invalid_file() {
+        let store = InMemory::new();
+        let meta = put(&store, b"PAR1 is not an ORC file").await;
+        assert!(OrcTail::read(&store, &meta).await.is_err());
+
+        let meta = put(&store, &PART_0[..PART_0.len() - 1]).await;
+        assert!(OrcTail::read(&store, &meta).await.is_err());
+    }
+
+    #[test]
+    fn test_timestamp_nanos() {
+        // 2015-01-01T00:00:00.000001
+        assert_eq!(
+            timestamp_nanos(0, 1 << 3).unwrap(),
+            TIMESTAMP_BASE_SECONDS * 1_000_000_000 + 1
+        );
+        // 1000 nanoseconds with 3 trailing zeros
+        assert_eq!(
+            timestamp_nanos(0, 1 << 3 | 2).unwrap(),
+            TIMESTAMP_BASE_SECONDS * 1_000_000_000 + 1_000
+        );
+        // -1.5 seconds is written as -1 seconds truncated towards zero and 500000000 nanoseconds
+        assert_eq!(
+            timestamp_nanos(-TIMESTAMP_BASE_SECONDS - 1, 5 << 3 | 7).unwrap(),
+            -1_500_000_000
+        );
+        assert!(timestamp_nanos(i64::MAX - TIMESTAMP_BASE_SECONDS, 0).is_err());
+    }
+
+    #[test]
+    fn test_rescale_decimal() {
+        assert_eq!(rescale_decimal(12345, 2, 2).unwrap(), 12345);
+        assert_eq!(rescale_decimal(12345, 2, 4).unwrap(), 1234500);
+        assert_eq!(rescale_decimal(12345, 4, 2).unwrap(), 123);
+        assert!(rescale_decimal(i128::MAX, 0, 1).is_err());
+    }
+}
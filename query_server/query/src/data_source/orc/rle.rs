@@ -0,0 +1,395 @@
+//! Run length encodings of ORC streams, see <https://orc.apache.org/specification/ORCv1/>.
+
+use datafusion::common::Result as DFResult;
+
+use super::invalid_file;
+
+/// Reads a decompressed stream.
+pub struct ByteReader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> ByteReader<'a> {
+    pub fn new(data: &'a [u8]) -> Self {
+        Self { data, pos: 0 }
+    }
+
+    pub fn read_u8(&mut self) -> DFResult<u8> {
+        let byte = *self
+            .data
+            .get(self.pos)
+            .ok_or_else(|| invalid_file("unexpected end of stream"))?;
+        self.pos += 1;
+        Ok(byte)
+    }
+
+    pub fn read_bytes(&mut self, len: usize) -> DFResult<&'a [u8]> {
+        let bytes = self
+            .data
+            .get(self.pos..self.pos + len)
+            .ok_or_else(|| invalid_file("unexpected end of stream"))?;
+        self.pos += len;
+        Ok(bytes)
+    }
+
+    /// Base 128 varint, of at most 128 bits for decimals.
+    pub fn read_uvarint128(&mut self) -> DFResult<u128> {
+        let mut value = 0_u128;
+        let mut shift = 0;
+        loop {
+            let byte = self.read_u8()?;
+            if shift >= 128 {
+                return Err(invalid_file("varint is too long"));
+            }
+            value |= ((byte & 0x7f) as u128) << shift;
+            shift += 7;
+            if byte < 0x80 {
+                return Ok(value);
+            }
+        }
+    }
+
+    pub fn read_uvarint(&mut self) -> DFResult<u64> {
+        let value = self.read_uvarint128()?;
+        u64::try_from(value).map_err(|_| invalid_file("varint overflows 64 bits"))
+    }
+
+    pub fn read_varint(&mut self, signed: bool) -> DFResult<i64> {
+        let value = self.read_uvarint()?;
+        Ok(if signed { zigzag(value) } else { value as i64 })
+    }
+
+    /// Big-endian unsigned integer of `len` bytes.
+    fn read_be(&mut self, len: usize) -> DFResult<u64> {
+        let mut value = 0_u64;
+        for _ in 0..len {
+            value = value << 8 | self.read_u8()? as u64;
+        }
+        Ok(value)
+    }
+
+    /// `count` big-endian bit-packed integers of `width` bits, which start at a byte
+    /// boundary and are padded to a byte boundary.
+    fn read_bits(&mut self, width: usize, count: usize, output: &mut Vec<u64>) -> DFResult<()> {
+        let mut current = 0_u64;
+        let mut bits_left = 0;
+        for _ in 0..count {
+            let mut value = 0_u64;
+            let mut needed = width;
+            while needed > 0 {
+                if bits_left == 0 {
+                    current = self.read_u8()? as u64;
+                    bits_left = 8;
+                }
+                let take = needed.min(bits_left);
+                let bits = (current >> (bits_left - take)) & ((1 << take) - 1);
+                value = value << take | bits;
+                bits_left -= take;
+                needed -= take;
+            }
+            output.push(value);
+        }
+        Ok(())
+    }
+}
+
+pub fn zigzag(value: u64) -> i64 {
+    (value >> 1) as i64 ^ -((value & 1) as i64)
+}
+
+pub fn zigzag128(value: u128) -> i128 {
+    (value >> 1) as i128 ^ -((value & 1) as i128)
+}
+
+/// Byte run length encoding of `n` values.
+pub fn decode_byte_rle(data: &[u8], n: usize) -> DFResult<Vec<u8>> {
+    let mut reader = ByteReader::new(data);
+    let mut output = Vec::with_capacity(n);
+    while output.len() < n {
+        let header = reader.read_u8()?;
+        if header < 0x80 {
+            let value = reader.read_u8()?;
+            output.extend(std::iter::repeat(value).take(header as usize + 3));
+        } else {
+            output.extend_from_slice(reader.read_bytes(0x100 - header as usize)?);
+        }
+    }
+    output.truncate(n);
+    Ok(output)
+}
+
+/// Byte run length encoding of bits of `n` values, the most significant bit first.
+pub fn decode_bool_rle(data: &[u8], n: usize) -> DFResult<Vec<bool>> {
+    let bytes = decode_byte_rle(data, (n + 7) / 8)?;
+    let mut output = Vec::with_capacity(n);
+    for byte in bytes {
+        output.extend((0..8).map(|i| byte >> (7 - i) & 1 == 1));
+    }
+    output.truncate(n);
+    Ok(output)
+}
+
+/// Version of integer run length encoding, which is determined by the column encoding.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RleVersion {
+    V1,
+    V2,
+}
+
+/// Integer run length encoding of `n` values, unsigned values are returned as they
+/// are casted from `u64`.
+pub fn decode_int_rle(
+    data: &[u8],
+    n: usize,
+    signed: bool,
+    version: RleVersion,
+) -> DFResult<Vec<i64>> {
+    let mut reader = ByteReader::new(data);
+    let mut output = Vec::with_capacity(n);
+    while output.len() < n {
+        match version {
+            RleVersion::V1 => decode_int_rle_v1(&mut reader, signed, &mut output)?,
+            RleVersion::V2 => decode_int_rle_v2(&mut reader, signed, &mut output)?,
+        }
+    }
+    output.truncate(n);
+    Ok(output)
+}
+
+fn decode_int_rle_v1(reader: &mut ByteReader, signed: bool, output: &mut Vec<i64>) -> DFResult<()> {
+    let header = reader.read_u8()?;
+    if header < 0x80 {
+        // run of `header + 3` values with a fixed delta
+        let delta = reader.read_u8()? as i8 as i64;
+        let base = reader.read_varint(signed)?;
+        output.extend((0..header as i64 + 3).map(|i| base.wrapping_add(i.wrapping_mul(delta))));
+    } else {
+        for _ in 0..0x100 - header as usize {
+            output.push(reader.read_varint(signed)?);
+        }
+    }
+    Ok(())
+}
+
+fn decode_int_rle_v2(reader: &mut ByteReader, signed: bool, output: &mut Vec<i64>) -> DFResult<()> {
+    let header = reader.read_u8()?;
+    let decode = |value: u64| if signed { zigzag(value) } else { value as i64 };
+    match header >> 6 {
+        // short repeat
+        0 => {
+            let width = (header >> 3 & 0x07) as usize + 1;
+            let count = (header & 0x07) as usize + 3;
+            let value = decode(reader.read_be(width)?);
+            output.extend(std::iter::repeat(value).take(count));
+        }
+        // direct
+        1 => {
+            let width = decode_bit_width(header >> 1 & 0x1f);
+            let len = ((header as usize & 0x01) << 8 | reader.read_u8()? as usize) + 1;
+            let mut values = Vec::with_capacity(len);
+            reader.read_bits(width, len, &mut values)?;
+            output.extend(values.into_iter().map(decode));
+        }
+        // patched base
+        2 => {
+            let width = decode_bit_width(header >> 1 & 0x1f);
+            let len = ((header as usize & 0x01) << 8 | reader.read_u8()? as usize) + 1;
+            let third = reader.read_u8()?;
+            let base_width = (third >> 5 & 0x07) as usize + 1;
+            let patch_width = decode_bit_width(third & 0x1f);
+            let fourth = reader.read_u8()?;
+            let gap_width = (fourth >> 5 & 0x07) as usize + 1;
+            let patch_len = (fourth & 0x1f) as usize;
+
+            // the most significant bit of the base is the sign
+            let base = reader.read_be(base_width)?;
+            let sign_mask = 1_u64 << (base_width * 8 - 1);
+            let base = if base & sign_mask != 0 {
+                -((base & !sign_mask) as i64)
+            } else {
+                base as i64
+            };
+
+            let mut values = Vec::with_capacity(len);
+            reader.read_bits(width, len, &mut values)?;
+            let mut patches = Vec::with_capacity(patch_len);
+            reader.read_bits(
+                closest_fixed_bits(patch_width + gap_width),
+                patch_len,
+                &mut patches,
+            )?;
+
+            let patch_mask = if patch_width >= 64 {
+                u64::MAX
+            } else {
+                (1 << patch_width) - 1
+            };
+            let mut pos = 0;
+            for patch in patches {
+                let gap = (patch >> patch_width) as usize;
+                let patch = patch & patch_mask;
+                pos += gap;
+                // a gap longer than 255 is split into gaps of 255 without patches
+                if gap == 255 && patch == 0 {
+                    continue;
+                }
+                let value = values
+                    .get_mut(pos)
+                    .ok_or_else(|| invalid_file("patch is out of the run"))?;
+                *value |= patch << width;
+            }
+            output.extend(values.into_iter().map(|v| base.wrapping_add(v as i64)));
+        }
+        // delta
+        _ => {
+            let encoded_width = header >> 1 & 0x1f;
+            let width = if encoded_width == 0 {
+                0
+            } else {
+                decode_bit_width(encoded_width)
+            };
+            let len = ((header as usize & 0x01) << 8 | reader.read_u8()? as usize) + 1;
+            let base = reader.read_varint(signed)?;
+            let delta_base = reader.read_varint(true)?;
+
+            output.push(base);
+            if len >= 2 {
+                let mut prev = base.wrapping_add(delta_base);
+                output.push(prev);
+                if width == 0 {
+                    for _ in 2..len {
+                        prev = prev.wrapping_add(delta_base);
+                        output.push(prev);
+                    }
+                } else {
+                    // deltas are unsigned, the sign is the one of the delta base
+                    let mut deltas = Vec::with_capacity(len - 2);
+                    reader.read_bits(width, len - 2, &mut deltas)?;
+                    for delta in deltas {
+                        prev = if delta_base < 0 {
+                            prev.wrapping_sub(delta as i64)
+                        } else {
+                            prev.wrapping_add(delta as i64)
+                        };
+                        output.push(prev);
+                    }
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+fn decode_bit_width(encoded: u8) -> usize {
+    match encoded {
+        0..=23 => encoded as usize + 1,
+        24 => 26,
+        25 => 28,
+        26 => 30,
+        27 => 32,
+        28 => 40,
+        29 => 48,
+        30 => 56,
+        _ => 64,
+    }
+}
+
+fn closest_fixed_bits(width: usize) -> usize {
+    match width {
+        0 => 1,
+        1..=24 => width,
+        25..=26 => 26,
+        27..=28 => 28,
+        29..=30 => 30,
+        31..=32 => 32,
+        33..=40 => 40,
+        41..=48 => 48,
+        49..=56 => 56,
+        _ => 64,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // examples of the ORC specification
+
+    #[test]
+    fn test_decode_byte_rle() {
+        assert_eq!(decode_byte_rle(&[0x61, 0x00], 100).unwrap(), vec![0; 100]);
+        assert_eq!(
+            decode_byte_rle(&[0xfe, 0x44, 0x45], 2).unwrap(),
+            vec![0x44, 0x45]
+        );
+        assert!(decode_byte_rle(&[0xfe, 0x44], 2).is_err());
+    }
+
+    #[test]
+    fn test_decode_bool_rle() {
+        let mut expected = vec![false; 8];
+        expected[0] = true;
+        assert_eq!(decode_bool_rle(&[0xff, 0x80], 8).unwrap(), expected);
+        assert_eq!(decode_bool_rle(&[0xff, 0x80], 3).unwrap(), &expected[..3]);
+    }
+
+    #[test]
+    fn test_decode_int_rle_v1() {
+        let decode = |data: &[u8], n| decode_int_rle(data, n, false, RleVersion::V1).unwrap();
+        assert_eq!(decode(&[0x61, 0x00, 0x07], 100), vec![7; 100]);
+        assert_eq!(
+            decode(&[0x61, 0x01, 0x00], 100),
+            (0..100).collect::<Vec<_>>()
+        );
+        assert_eq!(
+            decode(&[0xfb, 0x02, 0x03, 0x06, 0x07, 0x0b], 5),
+            vec![2, 3, 6, 7, 11]
+        );
+        // zigzag encoded -1, 1
+        assert_eq!(
+            decode_int_rle(&[0xfe, 0x01, 0x02], 2, true, RleVersion::V1).unwrap(),
+            vec![-1, 1]
+        );
+    }
+
+    #[test]
+    fn test_decode_int_rle_v2() {
+        let decode =
+            |data: &[u8], n, signed| decode_int_rle(data, n, signed, RleVersion::V2).unwrap();
+        // short repeat
+        assert_eq!(decode(&[0x0a, 0x27, 0x10], 5, false), vec![10000; 5]);
+        // direct
+        assert_eq!(
+            decode(
+                &[0x5e, 0x03, 0x5c, 0xa1, 0xab, 0x1e, 0xde, 0xad, 0xbe, 0xef],
+                4,
+                false
+            ),
+            vec![23713, 43806, 57005, 48879]
+        );
+        // patched base
+        let mut expected = (0..20).map(|i| 2000 + i * 10).collect::<Vec<i64>>();
+        expected[0] = 2030;
+        expected[1] = 2000;
+        expected[2] = 2020;
+        expected[3] = 1000000;
+        assert_eq!(
+            decode(
+                &[
+                    0x8e, 0x13, 0x2b, 0x21, 0x07, 0xd0, 0x1e, 0x00, 0x14, 0x70, 0x28, 0x32, 0x3c,
+                    0x46, 0x50, 0x5a, 0x64, 0x6e, 0x78, 0x82, 0x8c, 0x96, 0xa0, 0xaa, 0xb4, 0xbe,
+                    0xfc, 0xe8
+                ],
+                20,
+                true
+            ),
+            expected
+        );
+        // delta
+        assert_eq!(
+            decode(&[0xc6, 0x09, 0x02, 0x02, 0x22, 0x42, 0x42, 0x46], 10, false),
+            vec![2, 3, 5, 7, 11, 13, 17, 19, 23, 29]
+        );
+    }
+}
@@ -7,7 +7,8 @@ use async_trait::async_trait;
 use datafusion::arrow::datatypes::SchemaRef;
 use datafusion::arrow::record_batch::RecordBatch;
 use datafusion::execution::context::TaskContext;
-use datafusion::physical_plan::metrics::ExecutionPlanMetricsSet;
+use datafusion::execution::memory_pool::{MemoryConsumer, MemoryPool, MemoryReservation};
+use datafusion::physical_plan::metrics::{Count, ExecutionPlanMetricsSet, MetricBuilder};
 use datafusion::physical_plan::SendableRecordBatchStream;
 use futures::{pin_mut, TryStreamExt};
 use object_store::path::Path;
@@ -24,6 +25,9 @@ pub struct ObjectStoreSink {
     ctx: WriteContext,
     s: Arc<DynRecordBatchSerializer>,
     object_store: Arc<DynObjectStore>,
+    memory_pool: Arc<dyn MemoryPool>,
+    /// Number of files flushed early because there was not enough memory to buffer more records
+    memory_flushes: Count,
 }
 
 #[async_trait]
@@ -39,8 +43,16 @@ impl RecordBatchSink for ObjectStoreSink {
 
         pin_mut!(stream);
 
-        let mut writer =
-            BufferedWriter::new(&self.ctx, &self.s, &self.object_store, stream.schema());
+        let reservation = MemoryConsumer::new(format!("ObjectStoreSink[{}]", self.ctx.partition()))
+            .register(&self.memory_pool);
+        let mut writer = BufferedWriter::new(
+            &self.ctx,
+            &self.s,
+            &self.object_store,
+            stream.schema(),
+            reservation,
+            &self.memory_flushes,
+        );
 
         while let Some(batch) = stream.try_next().await? {
             writer.write(batch).await?;
@@ -84,7 +96,7 @@ impl RecordBatchSinkProvider for ObjectStoreSinkProvider {
     fn create_batch_sink(
         &self,
         context: Arc<TaskContext>,
-        _metrics: &ExecutionPlanMetricsSet,
+        metrics: &ExecutionPlanMetricsSet,
         partition: usize,
     ) -> Box<dyn RecordBatchSink> {
         let ctx = WriteContext::new(
@@ -106,6 +118,8 @@ impl RecordBatchSinkProvider for ObjectStoreSinkProvider {
             ctx,
             s: self.serializer.clone(),
             object_store: self.object_store.clone(),
+            memory_pool: context.memory_pool().clone(),
+            memory_flushes: MetricBuilder::new(metrics).counter("memory_flushes", partition),
         })
     }
 }
@@ -121,6 +135,9 @@ struct BufferedWriter<'a> {
     buffer: Vec<RecordBatch>,
     /// The total number of bytes of memory of currently buffered
     buffered_size: usize,
+    /// For tracking memory of the buffer.
+    reservation: MemoryReservation,
+    memory_flushes: &'a Count,
 
     // max buffer size
     max_file_size: usize,
@@ -139,12 +156,16 @@ impl<'a> BufferedWriter<'a> {
         s: &'a Arc<DynRecordBatchSerializer>,
         object_store: &'a Arc<DynObjectStore>,
         schema: SchemaRef,
+        reservation: MemoryReservation,
+        memory_flushes: &'a Count,
     ) -> Self {
         Self {
             ctx,
             s,
             object_store,
             schema,
+            reservation,
+            memory_flushes,
             max_file_size: ctx.sql_exec_info().copyinto_trigger_flush_size as usize,
             buffer: Default::default(),
             buffered_size: Default::default(),
@@ -155,7 +176,23 @@ impl<'a> BufferedWriter<'a> {
     }
 
     pub async fn write(&mut self, batch: RecordBatch) -> QueryResult<()> {
-        self.buffered_size += batch.get_array_memory_size();
+        let size = batch.get_array_memory_size();
+        if self.reservation.try_grow(size).is_err() {
+            // not enough memory to buffer more records, write the buffered ones to a smaller file
+            debug!(
+                "Export partition {} data of task[{}], out of memory, buffered_size: {}",
+                self.ctx.partition(),
+                self.ctx.task_id(),
+                self.buffered_size,
+            );
+            if self.buffered_size > 0 {
+                self.flush().await?;
+                self.memory_flushes.add(1);
+            }
+            self.reservation.try_grow(size)?;
+        }
+
+        self.buffered_size += size;
         self.buffer.push(batch);
         self.try_flush().await?;
         Ok(())
@@ -188,6 +225,7 @@ impl<'a> BufferedWriter<'a> {
             .await?;
         self.buffer.clear();
         self.buffered_size = 0;
+        self.reservation.free();
         let bytes_writed = data.len();
 
         if bytes_writed > 0 {
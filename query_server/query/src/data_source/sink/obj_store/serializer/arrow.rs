@@ -0,0 +1,72 @@
+use async_trait::async_trait;
+use bytes::Bytes;
+use datafusion::arrow::datatypes::SchemaRef;
+use datafusion::arrow::ipc::writer::{FileWriter, IpcWriteOptions};
+use datafusion::arrow::ipc::CompressionType;
+use datafusion::arrow::record_batch::RecordBatch;
+use datafusion::physical_plan::SendableRecordBatchStream;
+use futures::{pin_mut, TryStreamExt};
+use snafu::ResultExt;
+use spi::query::datasource::WriteContext;
+use spi::{QueryResult, SerializeArrowSnafu};
+
+use crate::data_source::sink::RecordBatchSerializer;
+
+/// Serialize record batches into an Arrow IPC file.
+pub struct ArrowRecordBatchSerializer {
+    compression: Option<CompressionType>,
+}
+
+impl ArrowRecordBatchSerializer {
+    pub fn new(compression: Option<CompressionType>) -> Self {
+        Self { compression }
+    }
+
+    fn writer(&self, schema: &SchemaRef) -> QueryResult<FileWriter<Vec<u8>>> {
+        let options = IpcWriteOptions::default()
+            .try_with_compression(self.compression)
+            .context(SerializeArrowSnafu)?;
+        FileWriter::try_new_with_options(vec![], schema, options).context(SerializeArrowSnafu)
+    }
+}
+
+#[async_trait]
+impl RecordBatchSerializer for ArrowRecordBatchSerializer {
+    async fn stream_to_bytes(
+        &self,
+        _ctx: &WriteContext,
+        stream: SendableRecordBatchStream,
+    ) -> QueryResult<(usize, Bytes)> {
+        let mut writer = self.writer(&stream.schema())?;
+        pin_mut!(stream);
+
+        let mut num_rows = 0;
+        while let Some(batch) = stream.try_next().await? {
+            num_rows += batch.num_rows();
+            writer.write(&batch).context(SerializeArrowSnafu)?;
+        }
+        writer.finish().context(SerializeArrowSnafu)?;
+        let bytes = writer.into_inner().context(SerializeArrowSnafu)?;
+
+        Ok((num_rows, Bytes::from(bytes)))
+    }
+
+    async fn batches_to_bytes(
+        &self,
+        _ctx: &WriteContext,
+        schema: SchemaRef,
+        batches: &[RecordBatch],
+    ) -> QueryResult<(usize, Bytes)> {
+        let mut writer = self.writer(&schema)?;
+
+        let mut num_rows = 0;
+        for batch in batches {
+            num_rows += batch.num_rows();
+            writer.write(batch).context(SerializeArrowSnafu)?;
+        }
+        writer.finish().context(SerializeArrowSnafu)?;
+        let bytes = writer.into_inner().context(SerializeArrowSnafu)?;
+
+        Ok((num_rows, Bytes::from(bytes)))
+    }
+}
@@ -0,0 +1,354 @@
+use apache_avro::types::Value;
+use apache_avro::{Codec, Schema as AvroSchema, Writer};
+use async_trait::async_trait;
+use bytes::Bytes;
+use datafusion::arrow::array::{Array, AsArray};
+use datafusion::arrow::datatypes::{
+    ArrowPrimitiveType, DataType, Float32Type, Float64Type, Int16Type, Int32Type, Int64Type,
+    Int8Type, Schema, SchemaRef, TimeUnit, TimestampMicrosecondType, TimestampMillisecondType,
+    TimestampNanosecondType, TimestampSecondType, UInt16Type, UInt32Type, UInt64Type, UInt8Type,
+};
+use datafusion::arrow::record_batch::RecordBatch;
+use datafusion::physical_plan::SendableRecordBatchStream;
+use futures::{pin_mut, TryStreamExt};
+use serde_json::json;
+use spi::query::datasource::WriteContext;
+use spi::{QueryResult, SerializeAvroSnafu};
+
+use crate::data_source::sink::RecordBatchSerializer;
+
+/// Serialize record batches into an Avro object container file.
+pub struct AvroRecordBatchSerializer {
+    codec: Codec,
+}
+
+impl AvroRecordBatchSerializer {
+    pub fn new(codec: Codec) -> Self {
+        Self { codec }
+    }
+}
+
+#[async_trait]
+impl RecordBatchSerializer for AvroRecordBatchSerializer {
+    async fn stream_to_bytes(
+        &self,
+        _ctx: &WriteContext,
+        stream: SendableRecordBatchStream,
+    ) -> QueryResult<(usize, Bytes)> {
+        let schema = to_avro_schema(&stream.schema())?;
+        pin_mut!(stream);
+
+        let mut num_rows = 0;
+        let mut writer = Writer::with_codec(&schema, vec![], self.codec);
+        while let Some(batch) = stream.try_next().await? {
+            num_rows += batch.num_rows();
+            append_batch(&mut writer, &batch)?;
+        }
+        let bytes = writer.into_inner().map_err(avro_error)?;
+
+        Ok((num_rows, Bytes::from(bytes)))
+    }
+
+    async fn batches_to_bytes(
+        &self,
+        _ctx: &WriteContext,
+        schema: SchemaRef,
+        batches: &[RecordBatch],
+    ) -> QueryResult<(usize, Bytes)> {
+        let schema = to_avro_schema(&schema)?;
+
+        let mut num_rows = 0;
+        let mut writer = Writer::with_codec(&schema, vec![], self.codec);
+        for batch in batches {
+            num_rows += batch.num_rows();
+            append_batch(&mut writer, batch)?;
+        }
+        let bytes = writer.into_inner().map_err(avro_error)?;
+
+        Ok((num_rows, Bytes::from(bytes)))
+    }
+}
+
+fn avro_error(err: impl ToString) -> spi::QueryError {
+    SerializeAvroSnafu {
+        reason: err.to_string(),
+    }
+    .build()
+}
+
+/// Convert the arrow schema to the schema of an avro record,
+/// nullable columns are unions of null and the type of the column.
+fn to_avro_schema(schema: &Schema) -> QueryResult<AvroSchema> {
+    let fields = schema
+        .fields()
+        .iter()
+        .map(|field| {
+            let avro_type = to_avro_type(field.data_type())?;
+            let avro_type = if field.is_nullable() {
+                json!(["null", avro_type])
+            } else {
+                avro_type
+            };
+            Ok(json!({ "name": field.name(), "type": avro_type }))
+        })
+        .collect::<QueryResult<Vec<_>>>()?;
+
+    AvroSchema::parse(&json!({
+        "type": "record",
+        "name": "record",
+        "fields": fields,
+    }))
+    .map_err(avro_error)
+}
+
+fn to_avro_type(data_type: &DataType) -> QueryResult<serde_json::Value> {
+    let avro_type = match data_type {
+        DataType::Boolean => json!("boolean"),
+        DataType::Int8 | DataType::Int16 | DataType::Int32 | DataType::UInt8 | DataType::UInt16 => {
+            json!("int")
+        }
+        DataType::Int64 | DataType::UInt32 | DataType::UInt64 => json!("long"),
+        DataType::Float32 => json!("float"),
+        DataType::Float64 => json!("double"),
+        DataType::Utf8 | DataType::LargeUtf8 => json!("string"),
+        DataType::Binary | DataType::LargeBinary => json!("bytes"),
+        DataType::Timestamp(TimeUnit::Second | TimeUnit::Millisecond, _) => {
+            json!({ "type": "long", "logicalType": "timestamp-millis" })
+        }
+        // timestamp-nanos is not in the avro spec, nanoseconds must be whole microseconds
+        DataType::Timestamp(TimeUnit::Microsecond | TimeUnit::Nanosecond, _) => {
+            json!({ "type": "long", "logicalType": "timestamp-micros" })
+        }
+        other => {
+            return Err(avro_error(format!(
+                "data type {} is not supported by avro",
+                other
+            )))
+        }
+    };
+
+    Ok(avro_type)
+}
+
+fn append_batch(writer: &mut Writer<Vec<u8>>, batch: &RecordBatch) -> QueryResult<()> {
+    let schema = batch.schema();
+    let mut columns = schema
+        .fields()
+        .iter()
+        .zip(batch.columns())
+        .map(|(field, array)| Ok(to_avro_values(array, field.is_nullable())?.into_iter()))
+        .collect::<QueryResult<Vec<_>>>()?;
+
+    for _ in 0..batch.num_rows() {
+        let record = schema
+            .fields()
+            .iter()
+            .zip(columns.iter_mut())
+            .map(|(field, values)| (field.name().clone(), values.next().unwrap_or(Value::Null)))
+            .collect();
+        writer.append(Value::Record(record)).map_err(avro_error)?;
+    }
+
+    Ok(())
+}
+
+fn to_avro_values(array: &dyn Array, nullable: bool) -> QueryResult<Vec<Value>> {
+    match array.data_type() {
+        DataType::Boolean => {
+            let array = array.as_boolean();
+            to_values(array, nullable, |i| Ok(Value::Boolean(array.value(i))))
+        }
+        DataType::Int8 => {
+            primitive_values::<Int8Type>(array, nullable, |v| Ok(Value::Int(v.into())))
+        }
+        DataType::Int16 => {
+            primitive_values::<Int16Type>(array, nullable, |v| Ok(Value::Int(v.into())))
+        }
+        DataType::Int32 => primitive_values::<Int32Type>(array, nullable, |v| Ok(Value::Int(v))),
+        DataType::UInt8 => {
+            primitive_values::<UInt8Type>(array, nullable, |v| Ok(Value::Int(v.into())))
+        }
+        DataType::UInt16 => {
+            primitive_values::<UInt16Type>(array, nullable, |v| Ok(Value::Int(v.into())))
+        }
+        DataType::Int64 => primitive_values::<Int64Type>(array, nullable, |v| Ok(Value::Long(v))),
+        DataType::UInt32 => {
+            primitive_values::<UInt32Type>(array, nullable, |v| Ok(Value::Long(v.into())))
+        }
+        DataType::UInt64 => primitive_values::<UInt64Type>(array, nullable, |v| {
+            i64::try_from(v)
+                .map(Value::Long)
+                .map_err(|_| avro_error(format!("{} is out of range of avro long", v)))
+        }),
+        DataType::Float32 => {
+            primitive_values::<Float32Type>(array, nullable, |v| Ok(Value::Float(v)))
+        }
+        DataType::Float64 => {
+            primitive_values::<Float64Type>(array, nullable, |v| Ok(Value::Double(v)))
+        }
+        DataType::Utf8 => {
+            let array = array.as_string::<i32>();
+            to_values(array, nullable, |i| {
+                Ok(Value::String(array.value(i).into()))
+            })
+        }
+        DataType::LargeUtf8 => {
+            let array = array.as_string::<i64>();
+            to_values(array, nullable, |i| {
+                Ok(Value::String(array.value(i).into()))
+            })
+        }
+        DataType::Binary => {
+            let array = array.as_binary::<i32>();
+            to_values(array, nullable, |i| Ok(Value::Bytes(array.value(i).into())))
+        }
+        DataType::LargeBinary => {
+            let array = array.as_binary::<i64>();
+            to_values(array, nullable, |i| Ok(Value::Bytes(array.value(i).into())))
+        }
+        DataType::Timestamp(TimeUnit::Second, _) => {
+            primitive_values::<TimestampSecondType>(array, nullable, |v| {
+                v.checked_mul(1000)
+                    .map(Value::TimestampMillis)
+                    .ok_or_else(|| avro_error(format!("timestamp {} is out of range", v)))
+            })
+        }
+        DataType::Timestamp(TimeUnit::Millisecond, _) => {
+            primitive_values::<TimestampMillisecondType>(array, nullable, |v| {
+                Ok(Value::TimestampMillis(v))
+            })
+        }
+        DataType::Timestamp(TimeUnit::Microsecond, _) => {
+            primitive_values::<TimestampMicrosecondType>(array, nullable, |v| {
+                Ok(Value::TimestampMicros(v))
+            })
+        }
+        DataType::Timestamp(TimeUnit::Nanosecond, _) => {
+            primitive_values::<TimestampNanosecondType>(array, nullable, |v| {
+                if v % 1000 != 0 {
+                    return Err(avro_error(format!(
+                        "timestamp {}ns can't be written as avro timestamp-micros without losing precision, \
+                        truncate it by arrow_cast(<column>, 'Timestamp(Microsecond, None)') first",
+                        v
+                    )));
+                }
+                Ok(Value::TimestampMicros(v / 1000))
+            })
+        }
+        other => Err(avro_error(format!(
+            "data type {} is not supported by avro",
+            other
+        ))),
+    }
+}
+
+fn primitive_values<T: ArrowPrimitiveType>(
+    array: &dyn Array,
+    nullable: bool,
+    value: impl Fn(T::Native) -> QueryResult<Value>,
+) -> QueryResult<Vec<Value>> {
+    let array = array.as_primitive::<T>();
+    to_values(array, nullable, |i| value(array.value(i)))
+}
+
+/// Values of a nullable column are the branches of the union of null and the type.
+fn to_values(
+    array: &dyn Array,
+    nullable: bool,
+    value: impl Fn(usize) -> QueryResult<Value>,
+) -> QueryResult<Vec<Value>> {
+    (0..array.len())
+        .map(|i| {
+            if !nullable {
+                value(i)
+            } else if array.is_null(i) {
+                Ok(Value::Union(0, Box::new(Value::Null)))
+            } else {
+                Ok(Value::Union(1, Box::new(value(i)?)))
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use apache_avro::Reader;
+    use datafusion::arrow::array::{Int64Array, StringArray, TimestampNanosecondArray};
+    use datafusion::arrow::datatypes::Field;
+
+    use super::*;
+
+    #[test]
+    fn test_append_batch() {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new(
+                "time",
+                DataType::Timestamp(TimeUnit::Nanosecond, None),
+                false,
+            ),
+            Field::new("host", DataType::Utf8, true),
+            Field::new("value", DataType::Int64, true),
+        ]));
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![
+                Arc::new(TimestampNanosecondArray::from(vec![1_000, 2_000])),
+                Arc::new(StringArray::from(vec![Some("a"), None])),
+                Arc::new(Int64Array::from(vec![None, Some(3)])),
+            ],
+        )
+        .unwrap();
+
+        let avro_schema = to_avro_schema(&schema).unwrap();
+        let mut writer = Writer::with_codec(&avro_schema, vec![], Codec::Deflate);
+        append_batch(&mut writer, &batch).unwrap();
+        let bytes = writer.into_inner().unwrap();
+
+        let records = Reader::new(bytes.as_slice())
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(
+            records,
+            vec![
+                Value::Record(vec![
+                    ("time".to_string(), Value::TimestampMicros(1)),
+                    (
+                        "host".to_string(),
+                        Value::Union(1, Box::new(Value::String("a".to_string())))
+                    ),
+                    ("value".to_string(), Value::Union(0, Box::new(Value::Null))),
+                ]),
+                Value::Record(vec![
+                    ("time".to_string(), Value::TimestampMicros(2)),
+                    ("host".to_string(), Value::Union(0, Box::new(Value::Null))),
+                    (
+                        "value".to_string(),
+                        Value::Union(1, Box::new(Value::Long(3)))
+                    ),
+                ]),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_append_sub_microsecond_timestamp() {
+        let schema = Arc::new(Schema::new(vec![Field::new(
+            "time",
+            DataType::Timestamp(TimeUnit::Nanosecond, None),
+            false,
+        )]));
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![Arc::new(TimestampNanosecondArray::from(vec![1_000, 2_999]))],
+        )
+        .unwrap();
+
+        let avro_schema = to_avro_schema(&schema).unwrap();
+        let mut writer = Writer::with_codec(&avro_schema, vec![], Codec::Null);
+        let err = append_batch(&mut writer, &batch).unwrap_err();
+        assert!(err.to_string().contains("timestamp 2999ns"), "{}", err);
+    }
+}
@@ -1,3 +1,5 @@
+pub mod arrow;
+pub mod avro;
 pub mod csv;
 pub mod json;
 pub mod parquet;
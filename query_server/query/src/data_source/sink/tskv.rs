@@ -6,6 +6,7 @@ use datafusion::arrow::datatypes::SchemaRef;
 use datafusion::arrow::record_batch::RecordBatch;
 use datafusion::execution::context::TaskContext;
 use datafusion::physical_plan::metrics::{self, Count, ExecutionPlanMetricsSet, MetricBuilder};
+use models::consistency_level::ConsistencyLevel;
 use models::schema::TskvTableSchemaRef;
 use snafu::ResultExt;
 use spi::{CoordinatorSnafu, MetaSnafu, QueryResult};
@@ -18,6 +19,7 @@ pub struct TskvRecordBatchSink {
     coord: CoordinatorRef,
     partition: usize,
     schema: TskvTableSchemaRef,
+    consistency: ConsistencyLevel,
 
     metrics: TskvSinkMetrics,
     span: Span,
@@ -75,6 +77,7 @@ impl RecordBatchSink for TskvRecordBatchSink {
                 self.schema.clone(),
                 record_batch,
                 *db_precision,
+                self.consistency,
                 span.context().as_ref(),
             )
             .await
@@ -128,11 +131,17 @@ impl RecordBatchSinkProvider for TskvRecordBatchSinkProvider {
             format!("TskvRecordBatchSink ({partition})"),
             parent_span_ctx.as_deref(),
         );
+        let consistency = context
+            .session_config()
+            .get_extension::<ConsistencyLevel>()
+            .map(|c| *c)
+            .unwrap_or_default();
 
         Box::new(TskvRecordBatchSink {
             coord: self.coord.clone(),
             partition,
             schema: self.schema.clone(),
+            consistency,
             metrics: TskvSinkMetrics::new(metrics, partition),
             span,
         })
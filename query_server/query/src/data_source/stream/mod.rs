@@ -8,7 +8,7 @@ use crate::utils::duration::parse_duration;
 pub mod tskv;
 
 // Table option keys
-const EVENT_TIME_COLUMN_OPTION: &str = "event_time_column";
+pub const EVENT_TIME_COLUMN_OPTION: &str = "event_time_column";
 const WATERMARK_DELAY_OPTION: &str = "watermark_delay";
 
 pub fn get_event_time_column<'a>(
@@ -1,6 +1,10 @@
 use std::sync::Arc;
 
+use apache_avro::Codec;
 use async_trait::async_trait;
+use datafusion::arrow::ipc::CompressionType;
+use datafusion::datasource::file_format::arrow::ArrowFormat;
+use datafusion::datasource::file_format::avro::AvroFormat;
 use datafusion::datasource::file_format::csv::CsvFormat;
 use datafusion::datasource::file_format::json::JsonFormat;
 use datafusion::datasource::file_format::parquet::ParquetFormat;
@@ -10,9 +14,13 @@ use datafusion::error::DataFusionError;
 use datafusion::execution::context::SessionState;
 use datafusion::physical_plan::ExecutionPlan;
 use object_store::path::Path;
+use spi::query::logical_planner::FileBlockCompression;
 use trace::debug;
 use url::Url;
 
+use crate::data_source::file_format::CompressedFileFormat;
+use crate::data_source::sink::obj_store::serializer::arrow::ArrowRecordBatchSerializer;
+use crate::data_source::sink::obj_store::serializer::avro::AvroRecordBatchSerializer;
 use crate::data_source::sink::obj_store::serializer::csv::CsvRecordBatchSerializer;
 use crate::data_source::sink::obj_store::serializer::json::NdJsonRecordBatchSerializer;
 use crate::data_source::sink::obj_store::serializer::parquet::ParquetRecordBatchSerializer;
@@ -71,6 +79,15 @@ impl WriteExecExt for ListingTable {
 fn get_record_batch_serializer(
     format: Arc<dyn FileFormat>,
 ) -> Result<Arc<DynRecordBatchSerializer>, DataFusionError> {
+    let compressed = format
+        .as_any()
+        .downcast_ref::<CompressedFileFormat>()
+        .map(|format| (format.inner().clone(), format.compression()));
+    let (format, compression) = match compressed {
+        Some((inner, compression)) => (inner, Some(compression)),
+        None => (format, None),
+    };
+
     let any = format.as_any();
     let result = if any.is::<ParquetFormat>() {
         Arc::new(ParquetRecordBatchSerializer {}) as _
@@ -81,15 +98,39 @@ fn get_record_batch_serializer(
         )) as _
     } else if any.is::<JsonFormat>() {
         Arc::new(NdJsonRecordBatchSerializer {}) as _
+    } else if any.is::<AvroFormat>() {
+        let codec = match compression {
+            None => Codec::Null,
+            Some(FileBlockCompression::Deflate) => Codec::Deflate,
+            Some(FileBlockCompression::Snappy) => Codec::Snappy,
+            Some(other) => return Err(unsupported_compression(other, "AvroFormat")),
+        };
+        Arc::new(AvroRecordBatchSerializer::new(codec)) as _
+    } else if any.is::<ArrowFormat>() {
+        let compression = match compression {
+            None => None,
+            Some(FileBlockCompression::Lz4) => Some(CompressionType::LZ4_FRAME),
+            Some(FileBlockCompression::Zstd) => Some(CompressionType::ZSTD),
+            Some(other) => return Err(unsupported_compression(other, "ArrowFormat")),
+        };
+        Arc::new(ArrowRecordBatchSerializer::new(compression)) as _
     } else {
         return Err(DataFusionError::NotImplemented(
-            "Only support ParquetFormat | CsvFormat | JsonFormat.".to_string(),
+            "Only support ParquetFormat | CsvFormat | JsonFormat | AvroFormat | ArrowFormat."
+                .to_string(),
         ));
     };
 
     Ok(result)
 }
 
+fn unsupported_compression(compression: FileBlockCompression, format: &str) -> DataFusionError {
+    DataFusionError::NotImplemented(format!(
+        "{} does not support compression {:?}.",
+        format, compression
+    ))
+}
+
 #[cfg(test)]
 mod tests {
     use std::io::Write;
@@ -0,0 +1,568 @@
+//! Audit log of DDL, DCL and authentication events.
+//!
+//! Events are appended to a local file as JSON lines if `audit.file_path` is set, and
+//! spooled in memory to be written to the `audit_log` table of `cluster_schema` of the
+//! system tenant in batches, failed batches are retried. Events older than
+//! `audit.retention` are deleted from the table periodically by the node holding the
+//! resource tasks lock. The table can only be written by the audit logger, and passwords
+//! in statements are not recorded.
+
+use std::borrow::Cow;
+use std::collections::VecDeque;
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+
+use config::tskv::AuditConfig;
+use coordinator::service::CoordinatorRef;
+use models::consistency_level::ConsistencyLevel;
+use models::object_reference::ResolvedTable;
+use models::predicate::domain::{ColumnDomains, ResolvedPredicate, TimeRange, TimeRanges};
+use models::schema::{Precision, AUDIT_LOG, CLUSTER_SCHEMA, DEFAULT_CATALOG};
+use models::utils::now_timestamp_nanos;
+use parking_lot::Mutex;
+use protocol_parser::Line;
+use protos::FieldValue;
+use serde::Serialize;
+use spi::query::ast::{AlterTenantOperation, ExtStatement};
+use spi::query::logical_planner::{
+    AlterTenantAction, DDLPlan, GlobalObjectType, Plan, TenantObjectType,
+};
+use spi::service::protocol::Context;
+use spi::{QueryError, QueryResult};
+use tokio::sync::Notify;
+use trace::{error, warn};
+
+pub type AuditLoggerRef = Arc<AuditLogger>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AuditEventType {
+    Ddl,
+    Dcl,
+    Auth,
+}
+
+impl AuditEventType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Ddl => "ddl",
+            Self::Dcl => "dcl",
+            Self::Auth => "auth",
+        }
+    }
+
+    /// Returns the event type of a statement, or None if it's not audited.
+    pub fn of_statement(stmt: &ExtStatement) -> Option<Self> {
+        match stmt {
+            ExtStatement::CreateUser(_)
+            | ExtStatement::CreateRole(_)
+            | ExtStatement::AlterUser(_)
+            | ExtStatement::GrantRevoke(_) => Some(Self::Dcl),
+            ExtStatement::DropGlobalObject(stmt) => match stmt.obj_type {
+                GlobalObjectType::User => Some(Self::Dcl),
+                GlobalObjectType::Tenant => Some(Self::Ddl),
+            },
+            ExtStatement::DropTenantObject(stmt) => match stmt.obj_type {
+                TenantObjectType::Role => Some(Self::Dcl),
+                TenantObjectType::Database => Some(Self::Ddl),
+            },
+            ExtStatement::AlterTenant(stmt) => match stmt.operation {
+                AlterTenantOperation::AddUser(..)
+                | AlterTenantOperation::SetUser(..)
+                | AlterTenantOperation::RemoveUser(_) => Some(Self::Dcl),
+                AlterTenantOperation::Set(_) | AlterTenantOperation::UnSet(_) => Some(Self::Ddl),
+            },
+            ExtStatement::CreateExternalTable(_)
+            | ExtStatement::CreateTable(_)
+            | ExtStatement::CreateStreamTable(_)
+            | ExtStatement::CreateView(_)
+            | ExtStatement::CreateMaterializedView(_)
+            | ExtStatement::CreateDatabase(_)
+            | ExtStatement::CreateTenant(_)
+            | ExtStatement::CreateStream(_)
+            | ExtStatement::DropStream(_)
+            | ExtStatement::DropDatabaseObject(_)
+            | ExtStatement::AlterDatabase(_)
+            | ExtStatement::AlterTable(_)
+            | ExtStatement::DropVnode(_)
+            | ExtStatement::CopyVnode(_)
+            | ExtStatement::MoveVnode(_)
+            | ExtStatement::CompactVnode(_)
+            | ExtStatement::RecoverTenant(_)
+            | ExtStatement::RecoverDatabase(_)
+            | ExtStatement::RestoreDatabase(_)
+            | ExtStatement::ReplicaDestory(_)
+            | ExtStatement::ReplicaAdd(_)
+            | ExtStatement::ReplicaRemove(_)
+            | ExtStatement::ReplicaPromote(_)
+            | ExtStatement::Rebalance(_)
+            | ExtStatement::PauseRebalance
+            | ExtStatement::ResumeRebalance
+            | ExtStatement::DecommissionNode(_) => Some(Self::Ddl),
+            _ => None,
+        }
+    }
+
+    /// Returns the event type of a plan, or None if it's not audited.
+    pub fn of_plan(plan: &Plan) -> Option<Self> {
+        let plan = match plan {
+            Plan::DDL(plan) => plan,
+            _ => return None,
+        };
+        match plan {
+            DDLPlan::CreateUser(_)
+            | DDLPlan::CreateRole(_)
+            | DDLPlan::AlterUser(_)
+            | DDLPlan::GrantRevoke(_) => Some(Self::Dcl),
+            DDLPlan::DropGlobalObject(plan) => match plan.obj_type {
+                GlobalObjectType::User => Some(Self::Dcl),
+                GlobalObjectType::Tenant => Some(Self::Ddl),
+            },
+            DDLPlan::DropTenantObject(plan) => match plan.obj_type {
+                TenantObjectType::Role => Some(Self::Dcl),
+                TenantObjectType::Database => Some(Self::Ddl),
+            },
+            DDLPlan::AlterTenant(plan) => match plan.alter_tenant_action {
+                AlterTenantAction::AddUser(_)
+                | AlterTenantAction::SetUser(_)
+                | AlterTenantAction::RemoveUser(_) => Some(Self::Dcl),
+                AlterTenantAction::SetOption(_) => Some(Self::Ddl),
+            },
+            DDLPlan::ChecksumGroup(_)
+            | DDLPlan::RebuildIndex(_)
+            | DDLPlan::BackupDatabase(_)
+            | DDLPlan::ShowReplicas
+            | DDLPlan::ShowRebalance => None,
+            _ => Some(Self::Ddl),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct AuditEvent {
+    /// Unix timestamp in nanoseconds.
+    pub time: i64,
+    pub event_type: AuditEventType,
+    pub user: String,
+    pub tenant: String,
+    pub client_addr: Option<String>,
+    pub statement: String,
+    pub success: bool,
+    pub error: Option<String>,
+}
+
+impl AuditEvent {
+    /// An event of a DDL or DCL statement executed in the context.
+    pub fn statement(
+        event_type: AuditEventType,
+        context: &Context,
+        statement: &str,
+        error: Option<String>,
+    ) -> Self {
+        Self {
+            time: now_timestamp_nanos(),
+            event_type,
+            user: context.user().desc().name().to_string(),
+            tenant: context.tenant().to_string(),
+            client_addr: context.client_addr().map(|addr| addr.to_string()),
+            statement: redact_password(statement),
+            success: error.is_none(),
+            error,
+        }
+    }
+
+    /// An event of an authentication, `error` is None if it succeeded.
+    pub fn auth(
+        user: &str,
+        tenant: &str,
+        client_addr: Option<&str>,
+        error: Option<String>,
+    ) -> Self {
+        Self {
+            time: now_timestamp_nanos(),
+            event_type: AuditEventType::Auth,
+            user: user.to_string(),
+            tenant: tenant.to_string(),
+            client_addr: client_addr.map(|addr| addr.to_string()),
+            statement: String::new(),
+            success: error.is_none(),
+            error,
+        }
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).unwrap_or_default()
+    }
+
+    fn to_line(&self) -> Line<'static> {
+        let mut tags = vec![(
+            Cow::Borrowed("event_type"),
+            Cow::Borrowed(self.event_type.as_str()),
+        )];
+        for (key, value) in [
+            ("user_name", Some(&self.user)),
+            ("tenant_name", Some(&self.tenant)),
+            ("client_addr", self.client_addr.as_ref()),
+        ] {
+            // Tags with empty value are not allowed.
+            if let Some(value) = value.filter(|v| !v.is_empty()) {
+                tags.push((Cow::Borrowed(key), Cow::Owned(value.clone())));
+            }
+        }
+
+        let mut fields = vec![
+            (
+                Cow::Borrowed("statement"),
+                FieldValue::Str(self.statement.as_bytes().to_owned()),
+            ),
+            (Cow::Borrowed("success"), FieldValue::Bool(self.success)),
+        ];
+        if let Some(error) = &self.error {
+            fields.push((
+                Cow::Borrowed("error"),
+                FieldValue::Str(error.as_bytes().to_owned()),
+            ));
+        }
+
+        Line {
+            hash_id: 0,
+            table: Cow::Borrowed(AUDIT_LOG),
+            tags,
+            fields,
+            timestamp: self.time,
+        }
+    }
+}
+
+/// Max number of events waiting to be written to the audit table,
+/// the oldest events are dropped if it's exceeded.
+const MAX_PENDING_EVENTS: usize = 100_000;
+/// Max number of events written to the audit table in a batch.
+const WRITE_BATCH_SIZE: usize = 1_000;
+const WRITE_RETRY_INTERVAL: Duration = Duration::from_secs(1);
+
+pub struct AuditLogger {
+    coord: CoordinatorRef,
+    file: Option<Mutex<File>>,
+    pending: Mutex<VecDeque<AuditEvent>>,
+    max_pending: usize,
+    notify: Notify,
+}
+
+impl AuditLogger {
+    pub fn try_new(coord: CoordinatorRef, config: &AuditConfig) -> std::io::Result<Self> {
+        let file = match config.file_path.as_deref().filter(|p| !p.is_empty()) {
+            Some(path) => {
+                if let Some(dir) = Path::new(path).parent() {
+                    std::fs::create_dir_all(dir)?;
+                }
+                let file = OpenOptions::new().create(true).append(true).open(path)?;
+                Some(Mutex::new(file))
+            }
+            None => None,
+        };
+
+        Ok(Self {
+            coord,
+            file,
+            pending: Mutex::new(VecDeque::new()),
+            max_pending: MAX_PENDING_EVENTS,
+            notify: Notify::new(),
+        })
+    }
+
+    /// Record the event, it's written to the file immediately and to the audit
+    /// table later by [`AuditLogger::run`]. Returns an error if it's not written
+    /// to the file.
+    pub fn log(&self, event: AuditEvent) -> QueryResult<()> {
+        let written = match &self.file {
+            Some(file) => {
+                let mut json = event.to_json();
+                json.push('\n');
+                file.lock()
+                    .write_all(json.as_bytes())
+                    .map_err(|err| QueryError::AuditLog {
+                        reason: format!("write to file: {}", err),
+                    })
+            }
+            None => Ok(()),
+        };
+
+        self.push_pending(vec![event], false);
+        self.notify.notify_one();
+
+        written
+    }
+
+    /// Write the spooled events to a quorum of replicas of the audit table,
+    /// events failed to write are retried later.
+    pub async fn run(self: Arc<Self>) {
+        loop {
+            let events = {
+                let mut pending = self.pending.lock();
+                let len = pending.len().min(WRITE_BATCH_SIZE);
+                pending.drain(..len).collect::<Vec<_>>()
+            };
+            if events.is_empty() {
+                self.notify.notified().await;
+                continue;
+            }
+
+            let lines = events.iter().map(AuditEvent::to_line).collect();
+            if let Err(err) = self
+                .coord
+                .write_system_lines(
+                    DEFAULT_CATALOG,
+                    CLUSTER_SCHEMA,
+                    Precision::NS,
+                    ConsistencyLevel::Quorum,
+                    lines,
+                    None,
+                )
+                .await
+            {
+                warn!(
+                    "Failed to write {} audit events, retry later: {}",
+                    events.len(),
+                    err
+                );
+                self.push_pending(events, true);
+                tokio::time::sleep(WRITE_RETRY_INTERVAL).await;
+            }
+        }
+    }
+
+    /// Append the events to the back of the pending events, or to the front if
+    /// they are failed to write and retried.
+    fn push_pending(&self, events: Vec<AuditEvent>, retried: bool) {
+        let mut pending = self.pending.lock();
+        if retried {
+            for event in events.into_iter().rev() {
+                pending.push_front(event);
+            }
+        } else {
+            pending.extend(events);
+        }
+
+        let exceeded = pending.len().saturating_sub(self.max_pending);
+        if exceeded > 0 {
+            pending.drain(..exceeded);
+            error!(
+                "Dropped {} audit events not written to the audit table in time",
+                exceeded
+            );
+        }
+    }
+}
+
+/// Replace passwords in the statement with `***`, e.g. of
+/// `CREATE USER u1 WITH PASSWORD='123'` and `ALTER USER u1 SET PASSWORD='123'`.
+pub fn redact_password(statement: &str) -> String {
+    const KEYWORD: &str = "password";
+    // ASCII lowercase keeps the byte offsets of the statement
+    let lowercase = statement.to_ascii_lowercase();
+    let bytes = statement.as_bytes();
+    let skip_whitespace = |mut i: usize| {
+        while bytes.get(i).is_some_and(|b| b.is_ascii_whitespace()) {
+            i += 1;
+        }
+        i
+    };
+
+    let mut redacted = String::with_capacity(statement.len());
+    let mut pos = 0;
+    while let Some(found) = lowercase[pos..].find(KEYWORD) {
+        let mut i = skip_whitespace(pos + found + KEYWORD.len());
+        if bytes.get(i) == Some(&b'=') {
+            i = skip_whitespace(i + 1);
+            if let Some(&quote) = bytes.get(i).filter(|b| **b == b'\'' || **b == b'"') {
+                // a doubled quote is an escaped quote in the value
+                let mut end = i + 1;
+                loop {
+                    match bytes.get(end) {
+                        Some(b) if *b == quote && bytes.get(end + 1) == Some(&quote) => end += 2,
+                        Some(b) if *b == quote => break,
+                        Some(_) => end += 1,
+                        None => break,
+                    }
+                }
+                redacted.push_str(&statement[pos..=i]);
+                redacted.push_str("***");
+                pos = end;
+                continue;
+            }
+        }
+        redacted.push_str(&statement[pos..i]);
+        pos = i;
+    }
+    redacted.push_str(&statement[pos..]);
+
+    redacted
+}
+
+/// Delete audit events older than `retention` every `interval`, only by the node
+/// holding the resource tasks lock so that it's done once in the cluster.
+pub async fn audit_retention_service(
+    coord: CoordinatorRef,
+    retention: Duration,
+    interval: Duration,
+) {
+    let table = ResolvedTable::new(
+        DEFAULT_CATALOG.to_string(),
+        CLUSTER_SCHEMA.to_string(),
+        AUDIT_LOG.to_string(),
+    );
+    loop {
+        tokio::time::sleep(interval).await;
+
+        match coord.meta_manager().read_resourceinfos_mark().await {
+            Ok((node_id, true)) if node_id == coord.node_id() => {}
+            Ok(_) => continue,
+            Err(err) => {
+                warn!("Failed to read the resource tasks lock: {}", err);
+                continue;
+            }
+        }
+
+        let expired = now_timestamp_nanos().saturating_sub(retention.as_nanos() as i64);
+        let time_ranges = TimeRanges::new(vec![TimeRange::new(i64::MIN, expired)]);
+        let predicate =
+            match ResolvedPredicate::new(Arc::new(time_ranges), ColumnDomains::all(), None) {
+                Ok(predicate) => predicate,
+                Err(err) => {
+                    warn!("Failed to build predicate of expired audit events: {}", err);
+                    continue;
+                }
+            };
+        if let Err(err) = coord.delete_from_table(&table, &predicate).await {
+            warn!("Failed to delete expired audit events: {}", err);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+
+    use config::tskv::AuditConfig;
+    use coordinator::service_mock::MockCoordinator;
+    use models::auth::user::{User, UserDesc, UserOptions};
+    use models::schema::is_read_only_table;
+    use spi::service::protocol::ContextBuilder;
+
+    use super::{redact_password, AuditEvent, AuditEventType, AuditLogger};
+    use crate::sql::parser::ExtParser;
+
+    fn event_type(sql: &str) -> Option<AuditEventType> {
+        let stmt = ExtParser::parse_sql(sql).unwrap().pop_front().unwrap();
+        AuditEventType::of_statement(&stmt)
+    }
+
+    #[test]
+    fn test_audit_event_type_of_statement() {
+        assert_eq!(event_type("CREATE DATABASE db1"), Some(AuditEventType::Ddl));
+        assert_eq!(event_type("DROP TABLE t1"), Some(AuditEventType::Ddl));
+        assert_eq!(event_type("DROP TENANT t1"), Some(AuditEventType::Ddl));
+        assert_eq!(
+            event_type("CREATE USER u1 WITH PASSWORD='123'"),
+            Some(AuditEventType::Dcl)
+        );
+        assert_eq!(event_type("DROP USER u1"), Some(AuditEventType::Dcl));
+        assert_eq!(
+            event_type("ALTER TENANT cnosdb ADD USER u1 AS member"),
+            Some(AuditEventType::Dcl)
+        );
+        assert_eq!(event_type("SELECT 1"), None);
+        assert_eq!(event_type("SHOW DATABASES"), None);
+        assert_eq!(event_type("SHOW REPLICAS"), None);
+    }
+
+    #[test]
+    fn test_audit_event_to_json() {
+        let event = AuditEvent::auth(
+            "u1",
+            "cnosdb",
+            Some("127.0.0.1:5000"),
+            Some("denied".into()),
+        );
+        let json: serde_json::Value = serde_json::from_str(&event.to_json()).unwrap();
+        assert_eq!(json["event_type"], "auth");
+        assert_eq!(json["user"], "u1");
+        assert_eq!(json["tenant"], "cnosdb");
+        assert_eq!(json["client_addr"], "127.0.0.1:5000");
+        assert_eq!(json["success"], false);
+        assert_eq!(json["error"], "denied");
+    }
+
+    #[test]
+    fn test_audit_logger_drops_oldest_pending_events() {
+        let mut logger = AuditLogger::try_new(
+            Arc::new(MockCoordinator::default()),
+            &AuditConfig::default(),
+        )
+        .unwrap();
+        logger.max_pending = 2;
+        for user in ["u1", "u2", "u3"] {
+            logger
+                .log(AuditEvent::auth(user, "cnosdb", None, None))
+                .unwrap();
+        }
+        let users = |logger: &AuditLogger| {
+            logger
+                .pending
+                .lock()
+                .iter()
+                .map(|e| e.user.clone())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(users(&logger), vec!["u2", "u3"]);
+
+        // failed events are retried before the new ones
+        let failed = logger.pending.lock().drain(..1).collect::<Vec<_>>();
+        logger
+            .log(AuditEvent::auth("u4", "cnosdb", None, None))
+            .unwrap();
+        logger.max_pending = 3;
+        logger.push_pending(failed, true);
+        assert_eq!(users(&logger), vec!["u2", "u3", "u4"]);
+    }
+
+    #[test]
+    fn test_redact_password() {
+        assert_eq!(
+            redact_password("CREATE USER u1 WITH PASSWORD='123', comment='a user'"),
+            "CREATE USER u1 WITH PASSWORD='***', comment='a user'"
+        );
+        assert_eq!(
+            redact_password("alter user u1 set password = 'it''s secret'"),
+            "alter user u1 set password = '***'"
+        );
+        assert_eq!(
+            redact_password("CREATE USER u1 WITH password=\"123\", must_change_password=true"),
+            "CREATE USER u1 WITH password=\"***\", must_change_password=true"
+        );
+        assert_eq!(redact_password("DROP USER password"), "DROP USER password");
+
+        let desc = UserDesc::new(0_u128, "root".to_string(), UserOptions::default(), true);
+        let context = ContextBuilder::new(User::new(desc, Default::default(), None)).build();
+        let sql = "ALTER USER u1 SET PASSWORD='123'";
+        let event = AuditEvent::statement(event_type(sql).unwrap(), &context, sql, None);
+        assert_eq!(event.statement, "ALTER USER u1 SET PASSWORD='***'");
+        assert!(!event.to_json().contains("123"));
+    }
+
+    #[test]
+    fn test_audit_log_is_read_only() {
+        assert!(is_read_only_table("cnosdb", "cluster_schema", "audit_log"));
+        assert!(is_read_only_table("cnosdb", "CLUSTER_SCHEMA", "AUDIT_LOG"));
+        assert!(!is_read_only_table("cnosdb", "cluster_schema", "queries"));
+        assert!(!is_read_only_table(
+            "tenant1",
+            "cluster_schema",
+            "audit_log"
+        ));
+    }
+}
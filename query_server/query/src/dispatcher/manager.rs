@@ -2,31 +2,39 @@ use std::sync::Arc;
 
 use async_trait::async_trait;
 use coordinator::service::CoordinatorRef;
+use datafusion::logical_expr::{LogicalPlan, Prepare};
+use datafusion::sql::sqlparser::ast::Statement;
 use memory_pool::MemoryPoolRef;
 use meta::error::MetaError;
 use meta::model::MetaClientRef;
 use models::oid::Oid;
+use models::schema::{MaterializedView, TableSchema};
 use snafu::ResultExt;
 use spi::query::ast::ExtStatement;
 use spi::query::datasource::stream::StreamProviderManagerRef;
 use spi::query::dispatcher::{QueryDispatcher, QueryInfo, QueryStatus};
 use spi::query::execution::{Output, QueryStateMachine};
 use spi::query::function::FuncMetaManagerRef;
-use spi::query::logical_planner::{LogicalPlanner, Plan};
+use spi::query::logical_planner::{DDLPlan, Plan, PlanWithPrivileges, QueryPlan};
 use spi::query::parser::Parser;
 use spi::query::session::{SessionCtx, SessionCtxFactory};
-use spi::service::protocol::{ContextBuilder, Query, QueryId};
+use spi::service::protocol::{Context, ContextBuilder, Query, QueryId};
 use spi::{MetaSnafu, QueryError, QueryResult};
 use trace::span_ext::SpanExt;
-use trace::{info, Span, SpanContext};
+use trace::{error, info, Span, SpanContext};
 
+use super::audit::{AuditEvent, AuditEventType, AuditLoggerRef};
+use super::prepared_statement::{PreparedPlan, PreparedStatements, SessionKey, SqlKey};
 use super::query_tracker::QueryTracker;
+use super::resource_group::{ResourceGroupManager, ResourceGroupRef};
 use crate::data_source::split::SplitManagerRef;
 use crate::execution::factory::QueryExecutionFactoryRef;
+use crate::execution::result_cache::ResultCacheRef;
 use crate::metadata::{
     BaseTableProvider, ContextProviderExtension, MetadataProvider, TableHandleProviderRef,
 };
 use crate::sql::logical::planner::DefaultLogicalPlanner;
+use crate::sql::planner::check_privilege;
 
 #[derive(Clone)]
 pub struct SimpleQueryDispatcher {
@@ -39,6 +47,10 @@ pub struct SimpleQueryDispatcher {
     memory_pool: MemoryPoolRef,
     // query tracker
     query_tracker: Arc<QueryTracker>,
+    // resource groups of tenants and users
+    resource_groups: Arc<ResourceGroupManager>,
+    // plans of prepared statements and parameterized queries
+    prepared_statements: Arc<PreparedStatements>,
     // parser
     parser: Arc<dyn Parser + Send + Sync>,
     // get query execution factory
@@ -46,6 +58,10 @@ pub struct SimpleQueryDispatcher {
     func_manager: FuncMetaManagerRef,
     stream_provider_manager: StreamProviderManagerRef,
     span_ctx: Option<SpanContext>,
+    // audit logger, None if audit is disabled
+    audit_logger: Option<AuditLoggerRef>,
+    // cached query results, None if the result cache is disabled
+    result_cache: Option<ResultCacheRef>,
 }
 
 #[async_trait]
@@ -112,9 +128,35 @@ impl QueryDispatcher for SimpleQueryDispatcher {
             Some(plan) => plan,
             None => return Ok(Output::Nil(())),
         };
+
+        // the stream query of a materialized view is started after the view is created,
+        // it stops by itself once the stream table is dropped with the view
+        let created_view = match &logical_plan {
+            Plan::DDL(DDLPlan::CreateMaterializedView(stmt))
+                if self
+                    .materialized_view(&query_state_machine.session, &stmt.view.db, &stmt.view.name)
+                    .await?
+                    .is_none() =>
+            {
+                Some((stmt.view.db.clone(), stmt.view.name.clone()))
+            }
+            _ => None,
+        };
+
         let result = self
-            .execute_logical_plan(logical_plan, query_state_machine)
+            .execute_logical_plan(logical_plan, query_state_machine.clone())
             .await?;
+
+        if let Some((database, name)) = created_view {
+            if let Some(view) = self
+                .materialized_view(&query_state_machine.session, &database, &name)
+                .await?
+            {
+                self.start_materialized_view(tenant_id, query.context().clone(), &view, span_ctx)
+                    .await?;
+            }
+        }
+
         Ok(result)
     }
 
@@ -124,6 +166,33 @@ impl QueryDispatcher for SimpleQueryDispatcher {
     ) -> QueryResult<Option<Plan>> {
         let session = &query_state_machine.session;
         let query = &query_state_machine.query;
+        // named statements are only visible in the session created them
+        let session_key = || {
+            query
+                .context()
+                .session_id()
+                .map(|session_id| SessionKey {
+                    tenant: session.tenant().to_string(),
+                    user: session.user().desc().name().to_string(),
+                    session_id: session_id.to_string(),
+                })
+                .ok_or(QueryError::SessionRequired)
+        };
+        let sql_key = || SqlKey {
+            tenant: session.tenant().to_string(),
+            user: session.user().desc().name().to_string(),
+            database: session.default_database().to_string(),
+            sql: query.content().to_string(),
+        };
+
+        // the plan of a parameterized query is reused, the values are bound on execution
+        let parameterized = query.context().params().is_some();
+        if parameterized {
+            if let Some(prepared) = self.prepared_statements.get_sql(&sql_key()) {
+                check_privilege(session.user(), prepared.privileges.clone())?;
+                return Ok(Some(Plan::Query(prepared.plan.clone())));
+            }
+        }
 
         let scheme_provider = self.build_scheme_provider(session).await?;
 
@@ -147,10 +216,57 @@ impl QueryDispatcher for SimpleQueryDispatcher {
 
         drop(span_recorder);
 
-        let logical_plan = self
-            .statement_to_logical_plan(stmt, &logical_planner, query_state_machine)
+        if let ExtStatement::SqlStatement(stmt) = &stmt {
+            match stmt.as_ref() {
+                Statement::Execute { name, parameters } => {
+                    let plan = self.prepared_statements.execute(
+                        &session_key()?,
+                        session.user(),
+                        &name.value,
+                        parameters,
+                    )?;
+                    return Ok(Some(Plan::Query(plan)));
+                }
+                Statement::Deallocate { name, .. } => {
+                    self.prepared_statements
+                        .deallocate(&session_key()?, &name.value)?;
+                    return Ok(None);
+                }
+                _ => {}
+            }
+        }
+
+        let PlanWithPrivileges { plan, privileges } = self
+            .statement_to_logical_plan(stmt, &logical_planner, query_state_machine.clone())
             .await?;
-        Ok(Some(logical_plan))
+
+        match plan {
+            Plan::Query(QueryPlan {
+                df_plan: df_plan @ LogicalPlan::Prepare(_),
+            }) => {
+                let name = match &df_plan {
+                    LogicalPlan::Prepare(Prepare { name, .. }) => name.clone(),
+                    _ => unreachable!(),
+                };
+                let prepared = PreparedPlan {
+                    plan: QueryPlan { df_plan },
+                    privileges,
+                };
+                self.prepared_statements
+                    .insert(session_key()?, name, prepared)?;
+                Ok(None)
+            }
+            Plan::Query(plan) if parameterized => {
+                let prepared = PreparedPlan {
+                    plan: plan.clone(),
+                    privileges,
+                };
+                self.prepared_statements
+                    .insert_sql(sql_key(), Arc::new(prepared));
+                Ok(Some(Plan::Query(plan)))
+            }
+            plan => Ok(Some(plan)),
+        }
     }
 
     async fn execute_logical_plan(
@@ -169,11 +285,26 @@ impl QueryDispatcher for SimpleQueryDispatcher {
         query: Query,
         span_ctx: Option<&SpanContext>,
     ) -> QueryResult<Arc<QueryStateMachine>> {
+        // the running queries of a resource group share the memory quota and cpu share of it
+        let (query, memory_pool) = match self.resource_group(query.context()).await {
+            Some(group) => {
+                let query = match group.max_target_partitions() {
+                    Some(n) => Query::new(
+                        query.context().clone().with_max_target_partitions(n),
+                        query.content().to_string(),
+                    ),
+                    None => query,
+                };
+                (query, group.memory_pool())
+            }
+            None => (query, self.memory_pool.clone()),
+        };
+
         let session = self.session_factory.create_session_ctx(
             query_id.to_string(),
             query.context(),
             tenant_id,
-            self.memory_pool.clone(),
+            memory_pool,
             span_ctx.cloned(),
             self.coord.clone(),
         )?;
@@ -214,16 +345,31 @@ impl SimpleQueryDispatcher {
         stmt: ExtStatement,
         logical_planner: &DefaultLogicalPlanner<'_, S>,
         query_state_machine: Arc<QueryStateMachine>,
-    ) -> QueryResult<Plan> {
+    ) -> QueryResult<PlanWithPrivileges> {
+        let audit_event_type = self
+            .audit_logger
+            .as_ref()
+            .and_then(|_| AuditEventType::of_statement(&stmt));
+
         // begin analyze
         query_state_machine.begin_analyze();
-        let logical_plan = logical_planner
-            .create_logical_plan(
+        let logical_plan = match logical_planner
+            .create_logical_plan_with_privileges(
                 stmt,
                 &query_state_machine.session,
                 self.coord.get_config().query.auth_enabled,
             )
-            .await?;
+            .await
+        {
+            Ok(logical_plan) => logical_plan,
+            Err(err) => {
+                // e.g. the user has no privilege to execute the statement
+                if let Some(event_type) = audit_event_type {
+                    self.audit(event_type, &query_state_machine, Some(err.to_string()));
+                }
+                return Err(err);
+            }
+        };
         query_state_machine.end_analyze();
 
         Ok(logical_plan)
@@ -234,16 +380,103 @@ impl SimpleQueryDispatcher {
         logical_plan: Plan,
         query_state_machine: Arc<QueryStateMachine>,
     ) -> QueryResult<Output> {
-        let execution = self
-            .query_execution_factory
-            .create_query_execution(logical_plan, query_state_machine.clone())?;
+        // bind the values of a parameterized query
+        let logical_plan = match (logical_plan, query_state_machine.query.context().params()) {
+            (Plan::Query(plan), Some(params)) => Plan::Query(plan.with_param_values(params)?),
+            (logical_plan, _) => logical_plan,
+        };
 
-        // TrackedQuery.drop() is called implicitly when the value goes out of scope,
-        self.query_tracker
-            .try_track_query(query_state_machine.query_id, execution)
-            .await?
-            .start()
-            .await
+        let audit_event_type = self
+            .audit_logger
+            .as_ref()
+            .and_then(|_| AuditEventType::of_plan(&logical_plan));
+        // cached results may be stale after schemas changed or data deleted
+        let invalidates_result_cache = matches!(logical_plan, Plan::DDL(_) | Plan::DML(_));
+        // cached plans may be stale after schemas changed
+        let invalidates_plans = matches!(logical_plan, Plan::DDL(_));
+
+        let result: QueryResult<Output> = async {
+            let execution = self
+                .query_execution_factory
+                .create_query_execution(logical_plan, query_state_machine.clone())?;
+
+            let resource_group = self
+                .resource_group(query_state_machine.query.context())
+                .await;
+
+            // TrackedQuery.drop() is called implicitly when the value goes out of scope,
+            self.query_tracker
+                .try_track_query(query_state_machine.query_id, execution, resource_group)
+                .await?
+                .start()
+                .await
+        }
+        .await;
+
+        if let (Some(result_cache), true, Ok(_)) =
+            (&self.result_cache, invalidates_result_cache, &result)
+        {
+            result_cache.invalidate_tenant(query_state_machine.session.tenant());
+        }
+        if invalidates_plans && result.is_ok() {
+            self.prepared_statements
+                .invalidate_tenant(query_state_machine.session.tenant());
+        }
+
+        // the statement is already applied, so its result is kept even if it's not audited
+        if let Some(event_type) = audit_event_type {
+            let error = result.as_ref().err().map(|err| err.to_string());
+            self.audit(event_type, &query_state_machine, error);
+        }
+
+        result
+    }
+
+    fn audit(
+        &self,
+        event_type: AuditEventType,
+        query_state_machine: &QueryStateMachine,
+        error: Option<String>,
+    ) {
+        if let Some(audit_logger) = &self.audit_logger {
+            let query = &query_state_machine.query;
+            let event = AuditEvent::statement(event_type, query.context(), query.content(), error);
+            if let Err(err) = audit_logger.log(event) {
+                error!("Failed to record audit event: {}", err);
+            }
+        }
+    }
+
+    async fn materialized_view(
+        &self,
+        session: &SessionCtx,
+        database: &str,
+        name: &str,
+    ) -> QueryResult<Option<MaterializedView>> {
+        let meta_client = self.build_current_session_meta_client(session).await?;
+        match meta_client
+            .get_table_schema(database, name)
+            .context(MetaSnafu)?
+        {
+            Some(TableSchema::ViewSchema(view)) => Ok(view.materialized.clone()),
+            _ => Ok(None),
+        }
+    }
+
+    /// Start the stream query maintaining the results of a materialized view,
+    /// it is persisted and re-executed on restart like other stream queries
+    async fn start_materialized_view(
+        &self,
+        tenant_id: Oid,
+        context: Context,
+        view: &MaterializedView,
+        span_ctx: Option<&SpanContext>,
+    ) -> QueryResult<()> {
+        let query = Query::new(context, view.query.clone());
+        self.execute_query(tenant_id, self.create_query_id(), &query, span_ctx)
+            .await?;
+        info!("Start materialized view query: {}", view.query);
+        Ok(())
     }
 
     async fn build_scheme_provider(&self, session: &SessionCtx) -> QueryResult<MetadataProvider> {
@@ -263,6 +496,17 @@ impl SimpleQueryDispatcher {
         Ok(metadata_provider)
     }
 
+    /// The resource group the query runs in, [`None`] if the tenant has no resource groups
+    async fn resource_group(&self, context: &Context) -> Option<ResourceGroupRef> {
+        let meta_client = self.coord.tenant_meta(context.tenant()).await?;
+        let tenant = meta_client.tenant();
+        self.resource_groups.group(
+            context.tenant(),
+            context.user().desc().name(),
+            tenant.options().resource_group_config.as_ref(),
+        )
+    }
+
     async fn build_current_session_meta_client(
         &self,
         session: &SessionCtx,
@@ -310,6 +554,8 @@ pub struct SimpleQueryDispatcherBuilder {
     func_manager: Option<FuncMetaManagerRef>,
     stream_provider_manager: Option<StreamProviderManagerRef>,
     span_ctx: Option<SpanContext>,
+    audit_logger: Option<AuditLoggerRef>,
+    result_cache: Option<ResultCacheRef>,
 }
 
 impl SimpleQueryDispatcherBuilder {
@@ -377,6 +623,16 @@ impl SimpleQueryDispatcherBuilder {
         self
     }
 
+    pub fn with_audit_logger(mut self, audit_logger: Option<AuditLoggerRef>) -> Self {
+        self.audit_logger = audit_logger;
+        self
+    }
+
+    pub fn with_result_cache(mut self, result_cache: Option<ResultCacheRef>) -> Self {
+        self.result_cache = result_cache;
+        self
+    }
+
     pub fn build(self) -> QueryResult<SimpleQueryDispatcher> {
         let coord = self.coord.ok_or_else(|| QueryError::BuildQueryDispatcher {
             err: "lost of coord".to_string(),
@@ -436,7 +692,12 @@ impl SimpleQueryDispatcherBuilder {
                     err: "lost of default_table_provider".to_string(),
                 })?;
 
+        let resource_groups = Arc::new(ResourceGroupManager::new(memory_pool.clone()));
+        let prepared_statements = Arc::new(PreparedStatements::default());
+
         let span_ctx = self.span_ctx;
+        let audit_logger = self.audit_logger;
+        let result_cache = self.result_cache;
 
         Ok(SimpleQueryDispatcher {
             coord,
@@ -447,9 +708,13 @@ impl SimpleQueryDispatcherBuilder {
             parser,
             query_execution_factory,
             query_tracker,
+            resource_groups,
+            prepared_statements,
             func_manager,
             stream_provider_manager,
             span_ctx,
+            audit_logger,
+            result_cache,
         })
     }
 }
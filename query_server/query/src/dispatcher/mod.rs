@@ -3,9 +3,12 @@ use spi::query::dispatcher::QueryInfo;
 use spi::service::protocol::QueryId;
 use spi::QueryResult;
 
+pub mod audit;
 pub mod manager;
 pub mod persister;
+pub mod prepared_statement;
 pub mod query_tracker;
+pub mod resource_group;
 
 #[async_trait]
 pub trait QueryPersister {
@@ -0,0 +1,416 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use datafusion::scalar::ScalarValue;
+use datafusion::sql::sqlparser::ast::{Expr as SQLExpr, UnaryOperator, Value};
+use models::auth::privilege::Privilege;
+use models::auth::user::User;
+use models::oid::Oid;
+use moka::notification::RemovalCause;
+use moka::sync::Cache;
+use parking_lot::Mutex;
+use spi::query::logical_planner::QueryPlan;
+use spi::{QueryError, QueryResult};
+use trace::info;
+
+use crate::sql::planner::check_privilege;
+
+/// Max number of the cached plans of parameterized queries of all users
+const MAX_PARAMETERIZED_PLANS: u64 = 10_000;
+/// Max number of the sessions having prepared statements
+const MAX_SESSIONS: u64 = 10_000;
+/// Max number of the prepared statements of a session
+pub const MAX_PREPARED_STATEMENTS_PER_SESSION: usize = 1_000;
+/// Sessions and cached plans not used for this long are removed
+const IDLE_TIMEOUT: Duration = Duration::from_secs(60 * 60);
+
+/// A client session, identified by the session or connection id of the requests.
+///
+/// The tenant and the user are part of the key, so that a session id
+/// can't be used to reach the statements of another user.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct SessionKey {
+    pub tenant: String,
+    pub user: String,
+    pub session_id: String,
+}
+
+/// A parameterized query of a user, identified by the sql and the default database
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct SqlKey {
+    pub tenant: String,
+    pub user: String,
+    pub database: String,
+    pub sql: String,
+}
+
+/// The plan of a prepared statement and the privileges required to execute it,
+/// which are checked on each execution since they may have been revoked.
+pub struct PreparedPlan {
+    pub plan: QueryPlan,
+    pub privileges: Vec<Privilege<Oid>>,
+}
+
+type Statements = Arc<Mutex<HashMap<String, Arc<PreparedPlan>>>>;
+
+/// The statements created by `PREPARE name AS ...` in each session,
+/// and the logical plans of parameterized queries,
+/// so that they are not parsed and planned again on each execution.
+///
+/// A session holds at most [`MAX_PREPARED_STATEMENTS_PER_SESSION`] statements,
+/// the statements of a session are dropped with it once it's idle for an hour
+/// or evicted by newer sessions, which is logged.
+pub struct PreparedStatements {
+    sessions: Cache<SessionKey, Statements>,
+    plans: Cache<SqlKey, Arc<PreparedPlan>>,
+}
+
+impl Default for PreparedStatements {
+    fn default() -> Self {
+        let sessions = Cache::builder()
+            .max_capacity(MAX_SESSIONS)
+            .time_to_idle(IDLE_TIMEOUT)
+            .eviction_listener(
+                |session: Arc<SessionKey>, statements: Statements, cause: RemovalCause| {
+                    if cause.was_evicted() {
+                        info!(
+                            "Drop {} prepared statements of session {} of user {}, cause: {:?}",
+                            statements.lock().len(),
+                            session.session_id,
+                            session.user,
+                            cause
+                        );
+                    }
+                },
+            )
+            .build();
+        let plans = Cache::builder()
+            .max_capacity(MAX_PARAMETERIZED_PLANS)
+            .time_to_idle(IDLE_TIMEOUT)
+            .support_invalidation_closures()
+            .build();
+        Self { sessions, plans }
+    }
+}
+
+impl PreparedStatements {
+    pub fn get(&self, session: &SessionKey, name: &str) -> Option<Arc<PreparedPlan>> {
+        self.sessions
+            .get(session)
+            .and_then(|statements| statements.lock().get(name).cloned())
+    }
+
+    /// Replace the statement of the same name,
+    /// fails if the session already holds too many statements.
+    pub fn insert(&self, session: SessionKey, name: String, plan: PreparedPlan) -> QueryResult<()> {
+        let statements = self.sessions.get_with(session, Default::default);
+        let mut statements = statements.lock();
+        if !statements.contains_key(&name)
+            && statements.len() >= MAX_PREPARED_STATEMENTS_PER_SESSION
+        {
+            return Err(QueryError::TooManyPreparedStatements {
+                max: MAX_PREPARED_STATEMENTS_PER_SESSION,
+            });
+        }
+        statements.insert(name, Arc::new(plan));
+        Ok(())
+    }
+
+    /// The plan of `EXECUTE name(parameters)` with the parameters bound,
+    /// fails if the user no longer has the privileges to execute it
+    pub fn execute(
+        &self,
+        session: &SessionKey,
+        user: &User,
+        name: &str,
+        parameters: &[SQLExpr],
+    ) -> QueryResult<QueryPlan> {
+        let prepared =
+            self.get(session, name)
+                .ok_or_else(|| QueryError::PreparedStatementNotFound {
+                    name: name.to_string(),
+                })?;
+        check_privilege(user, prepared.privileges.clone())?;
+        let values = parameters
+            .iter()
+            .map(param_value)
+            .collect::<QueryResult<Vec<_>>>()?;
+        prepared.plan.with_param_values(&values)
+    }
+
+    /// `DEALLOCATE name`
+    pub fn deallocate(&self, session: &SessionKey, name: &str) -> QueryResult<()> {
+        if !self.remove(session, name) {
+            return Err(QueryError::PreparedStatementNotFound {
+                name: name.to_string(),
+            });
+        }
+        Ok(())
+    }
+
+    /// Returns false if the statement doesn't exist
+    pub fn remove(&self, session: &SessionKey, name: &str) -> bool {
+        self.sessions
+            .get(session)
+            .map(|statements| statements.lock().remove(name).is_some())
+            .unwrap_or_default()
+    }
+
+    pub fn get_sql(&self, key: &SqlKey) -> Option<Arc<PreparedPlan>> {
+        self.plans.get(key)
+    }
+
+    pub fn insert_sql(&self, key: SqlKey, plan: Arc<PreparedPlan>) {
+        self.plans.insert(key, plan);
+    }
+
+    /// Remove the cached plans of parameterized queries of a tenant, after its schemas changed.
+    ///
+    /// Statements created by `PREPARE` are kept until deallocated.
+    pub fn invalidate_tenant(&self, tenant: &str) {
+        let tenant = tenant.to_string();
+        // only fails if invalidation closures are not supported
+        let _ = self
+            .plans
+            .invalidate_entries_if(move |key, _| key.tenant == tenant);
+    }
+}
+
+/// The value of a parameter of `EXECUTE`, only literals are allowed
+fn param_value(expr: &SQLExpr) -> QueryResult<ScalarValue> {
+    let invalid = || QueryError::InvalidParam {
+        reason: format!("parameter must be a literal, found {}", expr),
+    };
+    let number = |n: &str| {
+        n.parse::<i64>()
+            .map(|v| ScalarValue::Int64(Some(v)))
+            .or_else(|_| n.parse::<f64>().map(|v| ScalarValue::Float64(Some(v))))
+            .map_err(|_| invalid())
+    };
+    match expr {
+        SQLExpr::Value(Value::Number(n, _)) => number(n),
+        SQLExpr::UnaryOp {
+            op: UnaryOperator::Minus,
+            expr,
+        } => match expr.as_ref() {
+            SQLExpr::Value(Value::Number(n, _)) => number(&format!("-{}", n)),
+            _ => Err(invalid()),
+        },
+        SQLExpr::Value(Value::SingleQuotedString(s)) => Ok(ScalarValue::Utf8(Some(s.clone()))),
+        SQLExpr::Value(Value::Boolean(b)) => Ok(ScalarValue::Boolean(Some(*b))),
+        SQLExpr::Value(Value::Null) => Ok(ScalarValue::Null),
+        _ => Err(invalid()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use datafusion::arrow::datatypes::DataType;
+    use datafusion::common::DFSchema;
+    use datafusion::logical_expr::expr::Placeholder;
+    use datafusion::logical_expr::{
+        col, lit, EmptyRelation, Expr, LogicalPlan, LogicalPlanBuilder, Prepare,
+    };
+    use datafusion::sql::sqlparser::ast::Ident;
+    use models::auth::privilege::{DatabasePrivilege, TenantObjectPrivilege};
+    use models::auth::user::{UserDesc, UserOptions};
+
+    use super::*;
+
+    fn plan() -> PreparedPlan {
+        PreparedPlan {
+            plan: QueryPlan {
+                df_plan: LogicalPlan::EmptyRelation(EmptyRelation {
+                    produce_one_row: false,
+                    schema: Arc::new(DFSchema::empty()),
+                }),
+            },
+            privileges: vec![],
+        }
+    }
+
+    fn session(user: &str, session_id: &str) -> SessionKey {
+        SessionKey {
+            tenant: "cnosdb".to_string(),
+            user: user.to_string(),
+            session_id: session_id.to_string(),
+        }
+    }
+
+    fn sql_key(sql: &str) -> SqlKey {
+        SqlKey {
+            tenant: "cnosdb".to_string(),
+            user: "root".to_string(),
+            database: "public".to_string(),
+            sql: sql.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_prepared_statements() {
+        let statements = PreparedStatements::default();
+        let session = session("root", "s1");
+
+        statements
+            .insert(session.clone(), "q".to_string(), plan())
+            .unwrap();
+        assert!(statements.get(&session, "q").is_some());
+        // only visible in the session of the same user
+        assert!(statements.get(&self::session("root", "s2"), "q").is_none());
+        assert!(statements.get(&self::session("other", "s1"), "q").is_none());
+        // named statements are apart from the plans of parameterized queries
+        assert!(statements.get_sql(&sql_key("q")).is_none());
+
+        statements.insert_sql(sql_key("select 1"), Arc::new(plan()));
+        assert!(statements.get_sql(&sql_key("select 1")).is_some());
+        statements.invalidate_tenant("cnosdb");
+        assert!(statements.get_sql(&sql_key("select 1")).is_none());
+        assert!(statements.get(&session, "q").is_some());
+
+        assert!(statements.remove(&session, "q"));
+        assert!(!statements.remove(&session, "q"));
+    }
+
+    #[test]
+    fn test_max_prepared_statements() {
+        let statements = PreparedStatements::default();
+        let session = session("root", "s1");
+        for i in 0..MAX_PREPARED_STATEMENTS_PER_SESSION {
+            statements
+                .insert(session.clone(), format!("q{i}"), plan())
+                .unwrap();
+        }
+
+        assert!(matches!(
+            statements.insert(session.clone(), "q".to_string(), plan()),
+            Err(QueryError::TooManyPreparedStatements { .. })
+        ));
+        // no statement is evicted, and existing ones can still be replaced
+        assert!(statements.get(&session, "q0").is_some());
+        statements
+            .insert(session.clone(), "q0".to_string(), plan())
+            .unwrap();
+        // other sessions are not limited by this one
+        statements
+            .insert(self::session("root", "s2"), "q".to_string(), plan())
+            .unwrap();
+
+        statements.deallocate(&session, "q0").unwrap();
+        statements
+            .insert(session.clone(), "q".to_string(), plan())
+            .unwrap();
+    }
+
+    fn read_public() -> Privilege<Oid> {
+        Privilege::TenantObject(
+            TenantObjectPrivilege::Database(DatabasePrivilege::Read, Some("public".to_string())),
+            Some(0),
+        )
+    }
+
+    fn user(privileges: Vec<Privilege<Oid>>) -> User {
+        let desc = UserDesc::new(1_u128, "u1".to_string(), UserOptions::default(), false);
+        User::new(desc, privileges.into_iter().collect::<HashSet<_>>(), None)
+    }
+
+    fn placeholder(id: &str) -> Expr {
+        Expr::Placeholder(Placeholder {
+            id: id.to_string(),
+            data_type: None,
+        })
+    }
+
+    /// `PREPARE q(INT, STRING) AS SELECT * FROM (VALUES (1, 'a')) WHERE column1 = $1 AND column2 = $2`
+    fn prepared() -> PreparedPlan {
+        let input = LogicalPlanBuilder::values(vec![vec![lit(1_i32), lit("a")]])
+            .unwrap()
+            .filter(
+                col("column1")
+                    .eq(placeholder("$1"))
+                    .and(col("column2").eq(placeholder("$2"))),
+            )
+            .unwrap()
+            .build()
+            .unwrap();
+        PreparedPlan {
+            plan: QueryPlan {
+                df_plan: LogicalPlan::Prepare(Prepare {
+                    name: "q".to_string(),
+                    data_types: vec![DataType::Int32, DataType::Utf8],
+                    input: Arc::new(input),
+                }),
+            },
+            privileges: vec![read_public()],
+        }
+    }
+
+    fn number(n: &str) -> SQLExpr {
+        SQLExpr::Value(Value::Number(n.to_string(), false))
+    }
+
+    fn string(s: &str) -> SQLExpr {
+        SQLExpr::Value(Value::SingleQuotedString(s.to_string()))
+    }
+
+    #[test]
+    fn test_execute() {
+        let statements = PreparedStatements::default();
+        let session = session("u1", "s1");
+        let reader = user(vec![read_public()]);
+        statements
+            .insert(session.clone(), "q".to_string(), prepared())
+            .unwrap();
+
+        // the parameters are bound in order, cast to the declared types
+        let plan = statements
+            .execute(&session, &reader, "q", &[number("1"), string("a")])
+            .unwrap();
+        let display = plan.df_plan.display_indent().to_string();
+        assert!(
+            display.contains("column1 = CAST(Int64(1) AS Int32) AND column2 = Utf8(\"a\")"),
+            "{display}"
+        );
+
+        // the privileges are checked on each execution
+        assert!(matches!(
+            statements.execute(&session, &user(vec![]), "q", &[number("1"), string("a")]),
+            Err(QueryError::InsufficientPrivileges { .. })
+        ));
+
+        assert!(matches!(
+            statements.execute(&session, &reader, "q", &[number("1")]),
+            Err(QueryError::InvalidParam { .. })
+        ));
+        let column = SQLExpr::Identifier(Ident::new("column1"));
+        assert!(matches!(
+            statements.execute(&session, &reader, "q", &[number("1"), column]),
+            Err(QueryError::InvalidParam { .. })
+        ));
+        assert!(matches!(
+            statements.execute(&session, &reader, "other", &[]),
+            Err(QueryError::PreparedStatementNotFound { .. })
+        ));
+    }
+
+    #[test]
+    fn test_deallocate() {
+        let statements = PreparedStatements::default();
+        let session = session("u1", "s1");
+        statements
+            .insert(session.clone(), "q".to_string(), prepared())
+            .unwrap();
+
+        statements.deallocate(&session, "q").unwrap();
+        assert!(matches!(
+            statements.deallocate(&session, "q"),
+            Err(QueryError::PreparedStatementNotFound { .. })
+        ));
+        assert!(matches!(
+            statements.execute(&session, &user(vec![read_public()]), "q", &[]),
+            Err(QueryError::PreparedStatementNotFound { .. })
+        ));
+    }
+}
@@ -12,23 +12,29 @@ use datafusion::arrow::record_batch::RecordBatch;
 use datafusion::common::Result as DFResult;
 use datafusion::physical_plan::{RecordBatchStream, SendableRecordBatchStream};
 use futures::{Stream, StreamExt};
+use models::consistency_level::ConsistencyLevel;
 use models::schema::{Precision, CLUSTER_SCHEMA, DEFAULT_CATALOG};
 use models::utils::now_timestamp_nanos;
-use parking_lot::RwLock;
+use parking_lot::{Mutex, RwLock};
 use protocol_parser::Line;
 use protos::FieldValue;
 use spi::query::dispatcher::{QueryInfo, QueryStatus};
-use spi::query::execution::{Output, QueryExecution, QueryExecutionRef, QueryType};
+use spi::query::execution::{Output, QueryExecution, QueryExecutionRef, QueryState, QueryType};
 use spi::service::protocol::QueryId;
 use spi::{QueryError, QueryResult};
+use tokio::sync::Notify;
 use trace::{debug, warn};
 
 use super::persister::QueryPersisterRef;
+use super::resource_group::{ResourceGroupPermit, ResourceGroupRef};
 
 const SQL_HISTORY: &str = "sql_history";
 
 pub struct QueryTracker {
     queries: RwLock<HashMap<QueryId, Arc<dyn QueryExecution>>>,
+    /// queries waiting in the queue of their resource group, not counted in `query_limit`
+    queued: RwLock<HashMap<QueryId, Arc<QueuedQuery>>>,
+    permits: Mutex<HashMap<QueryId, ResourceGroupPermit>>,
     query_limit: usize,
     query_persister: QueryPersisterRef,
     coord: CoordinatorRef,
@@ -42,6 +48,8 @@ impl QueryTracker {
     ) -> Self {
         Self {
             queries: RwLock::new(HashMap::new()),
+            queued: RwLock::new(HashMap::new()),
+            permits: Mutex::new(HashMap::new()),
             query_limit,
             query_persister,
             coord,
@@ -54,12 +62,19 @@ impl QueryTracker {
     ///
     /// Returns [`TrackedQuery`], which holds a [`QueryExecutionTrackedProxy`] internally.
     ///
+    /// A batch query in a `resource_group` waits in the queue of the group
+    /// until the group has a free running slot.
+    ///
     /// Errors:
     ///     [`QueryError::RequestLimit`]
+    ///     [`QueryError::ResourceGroupQueueFull`]
+    ///     [`QueryError::ResourceGroupQueueTimeout`]
+    ///     [`QueryError::Cancel`]
     pub async fn try_track_query(
         self: &Arc<Self>,
         query_id: QueryId,
         query: Arc<dyn QueryExecution>,
+        resource_group: Option<ResourceGroupRef>,
     ) -> QueryResult<TrackedQuery> {
         debug!(
             "total query count: {}, status {:?}",
@@ -74,8 +89,21 @@ impl QueryTracker {
         //     query.status(),
         // );
 
+        // 流任务是常驻任务，不占用资源组的并发数
+        let permit = match (resource_group, query.query_type()) {
+            (Some(group), QueryType::Batch) => Some(
+                self.wait_resource_group(query_id, query.clone(), group)
+                    .await?,
+            ),
+            _ => None,
+        };
+
         self.save_query(query_id, query.clone()).await?;
 
+        if let Some(permit) = permit {
+            let _ = self.permits.lock().insert(query_id, permit);
+        }
+
         let query = match query.query_type() {
             // 封装一层代理，用于在释放query result stream时，从tracker中移除
             QueryType::Batch => Arc::new(QueryExecutionTrackedProxy {
@@ -93,7 +121,12 @@ impl QueryTracker {
     }
 
     pub fn query(&self, id: &QueryId) -> Option<Arc<dyn QueryExecution>> {
-        self.queries.read().get(id).cloned()
+        self.queries.read().get(id).cloned().or_else(|| {
+            self.queued
+                .read()
+                .get(id)
+                .map(|q| q.clone() as Arc<dyn QueryExecution>)
+        })
     }
 
     pub fn _running_query_count(&self) -> usize {
@@ -105,6 +138,15 @@ impl QueryTracker {
         self.queries.read().values().cloned().collect()
     }
 
+    /// all queries waiting in the queue of their resource group
+    pub fn queued_queries(&self) -> Vec<Arc<dyn QueryExecution>> {
+        self.queued
+            .read()
+            .values()
+            .map(|q| q.clone() as Arc<dyn QueryExecution>)
+            .collect()
+    }
+
     /// all persistent queries
     pub async fn persistent_queries(&self) -> QueryResult<Vec<QueryInfo>> {
         self.query_persister.queries().await
@@ -118,6 +160,12 @@ impl QueryTracker {
     }
 
     pub fn expire_query(&self, id: &QueryId) -> Option<Arc<dyn QueryExecution>> {
+        if let Some(query) = self.queued.write().remove(id) {
+            return Some(query);
+        }
+        // release the running slot of the resource group
+        let _ = self.permits.lock().remove(id);
+
         self.queries.write().remove(id).map(|query| {
             if query.need_persist() {
                 let _ = self.query_persister.remove(id).map_err(|err| {
@@ -198,6 +246,7 @@ impl QueryTracker {
                             DEFAULT_CATALOG,
                             CLUSTER_SCHEMA,
                             Precision::NS,
+                            ConsistencyLevel::default(),
                             vec![line],
                             None,
                         )
@@ -211,6 +260,37 @@ impl QueryTracker {
         })
     }
 
+    async fn wait_resource_group(
+        &self,
+        query_id: QueryId,
+        query: Arc<dyn QueryExecution>,
+        group: ResourceGroupRef,
+    ) -> QueryResult<ResourceGroupPermit> {
+        if let Some(permit) = group.try_acquire()? {
+            return Ok(permit);
+        }
+
+        debug!(
+            "Query {:?} queued in resource group {}",
+            query_id,
+            group.name()
+        );
+        let queued = Arc::new(QueuedQuery {
+            inner: query,
+            cancelled: Notify::new(),
+        });
+        let _ = self.queued.write().insert(query_id, queued.clone());
+        let _guard = QueuedQueryGuard {
+            queued: &self.queued,
+            query_id,
+        };
+
+        tokio::select! {
+            permit = group.acquire_queued() => permit,
+            _ = queued.cancelled.notified() => Err(QueryError::Cancel),
+        }
+    }
+
     async fn save_query(
         &self,
         query_id: QueryId,
@@ -240,6 +320,55 @@ impl QueryTracker {
     }
 }
 
+/// Removes a query from the queued queries once it leaves the queue
+struct QueuedQueryGuard<'a> {
+    queued: &'a RwLock<HashMap<QueryId, Arc<QueuedQuery>>>,
+    query_id: QueryId,
+}
+
+impl Drop for QueuedQueryGuard<'_> {
+    fn drop(&mut self) {
+        let _ = self.queued.write().remove(&self.query_id);
+    }
+}
+
+/// A query waiting in the queue of its resource group
+pub struct QueuedQuery {
+    inner: QueryExecutionRef,
+    cancelled: Notify,
+}
+
+#[async_trait]
+impl QueryExecution for QueuedQuery {
+    fn query_type(&self) -> QueryType {
+        self.inner.query_type()
+    }
+
+    async fn start(&self) -> QueryResult<Output> {
+        Err(QueryError::Internal {
+            reason: "a queued query can not be started".to_string(),
+        })
+    }
+
+    fn cancel(&self) -> QueryResult<()> {
+        // the query may not be waiting yet, notify_one keeps the wakeup
+        self.cancelled.notify_one();
+        Ok(())
+    }
+
+    fn info(&self) -> QueryInfo {
+        self.inner.info()
+    }
+
+    fn status(&self) -> QueryStatus {
+        QueryStatus::new(QueryState::QUEUED, *self.inner.status().duration())
+    }
+
+    fn need_persist(&self) -> bool {
+        false
+    }
+}
+
 pub struct TrackedQuery {
     query: QueryExecutionRef,
 }
@@ -345,9 +474,11 @@ mod tests {
     use std::time::Duration;
 
     use async_trait::async_trait;
+    use config::common::{ResourceGroupConfig, TenantResourceGroupConfig};
     use coordinator::service_mock::MockCoordinator;
     use datafusion::arrow::datatypes::Schema;
     use datafusion::physical_plan::EmptyRecordBatchStream;
+    use memory_pool::GreedyMemoryPool;
     use models::auth::user::{User, UserDesc, UserOptions};
     use spi::query::dispatcher::{QueryInfo, QueryStatus};
     use spi::query::execution::{Output, QueryExecution, QueryState, RUNNING};
@@ -356,6 +487,7 @@ mod tests {
 
     use super::QueryTracker;
     use crate::dispatcher::persister::LocalQueryPersister;
+    use crate::dispatcher::resource_group::ResourceGroupManager;
 
     struct QueryExecutionMock {}
 
@@ -405,14 +537,14 @@ mod tests {
         let tracker = Arc::new(new_query_tracker(10));
 
         let _tq = tracker
-            .try_track_query(query_id, query.clone())
+            .try_track_query(query_id, query.clone(), None)
             .await
             .unwrap();
         assert_eq!(tracker._running_query_count(), 1);
 
         let query_id = QueryId::next_id();
         let _tq = tracker
-            .try_track_query(query_id, query.clone())
+            .try_track_query(query_id, query.clone(), None)
             .await
             .unwrap();
         assert_eq!(tracker._running_query_count(), 2);
@@ -421,7 +553,7 @@ mod tests {
             // 作用域结束时，不会结束对当前query的追踪
             let query_id = QueryId::next_id();
             let _tq = tracker
-                .try_track_query(query_id, query.clone())
+                .try_track_query(query_id, query.clone(), None)
                 .await
                 .unwrap();
             assert_eq!(tracker._running_query_count(), 3);
@@ -432,7 +564,10 @@ mod tests {
         assert_eq!(tracker._running_query_count(), 2);
 
         let query_id = QueryId::next_id();
-        let _tq = tracker.try_track_query(query_id, query).await.unwrap();
+        let _tq = tracker
+            .try_track_query(query_id, query, None)
+            .await
+            .unwrap();
         assert_eq!(tracker._running_query_count(), 3);
     }
 
@@ -444,7 +579,7 @@ mod tests {
         let output = {
             let query_id = QueryId::next_id();
             let tq = tracker
-                .try_track_query(query_id, query.clone())
+                .try_track_query(query_id, query.clone(), None)
                 .await
                 .unwrap();
             tq.start().await.unwrap()
@@ -462,20 +597,23 @@ mod tests {
         let tracker = Arc::new(new_query_tracker(2));
 
         let _tq = tracker
-            .try_track_query(query_id, query.clone())
+            .try_track_query(query_id, query.clone(), None)
             .await
             .unwrap();
         assert_eq!(tracker._running_query_count(), 1);
 
         let query_id = QueryId::next_id();
         let _tq = tracker
-            .try_track_query(query_id, query.clone())
+            .try_track_query(query_id, query.clone(), None)
             .await
             .unwrap();
         assert_eq!(tracker._running_query_count(), 2);
 
         let query_id = QueryId::next_id();
-        assert!(tracker.try_track_query(query_id, query).await.is_err())
+        assert!(tracker
+            .try_track_query(query_id, query, None)
+            .await
+            .is_err())
     }
 
     #[tokio::test]
@@ -485,7 +623,7 @@ mod tests {
         let tracker = Arc::new(new_query_tracker(2));
 
         let _tq = tracker
-            .try_track_query(query_id, query.clone())
+            .try_track_query(query_id, query.clone(), None)
             .await
             .unwrap();
 
@@ -496,4 +634,69 @@ mod tests {
 
         assert_eq!(info_actual, info_found);
     }
+
+    #[tokio::test]
+    async fn test_queue_in_resource_group() {
+        let query = Arc::new(QueryExecutionMock {});
+        let tracker = Arc::new(new_query_tracker(10));
+        let manager = ResourceGroupManager::new(Arc::new(GreedyMemoryPool::default()));
+        let config = TenantResourceGroupConfig {
+            tenant: Some(ResourceGroupConfig {
+                max_concurrent_queries: 1,
+                max_queued_queries: 1,
+                queue_timeout: Duration::from_secs(10),
+                cpu_share: None,
+                memory_quota: None,
+            }),
+            users: Default::default(),
+        };
+        let group = manager.group("tenant", "user", Some(&config));
+
+        let running_id = QueryId::next_id();
+        let _tq = tracker
+            .try_track_query(running_id, query.clone(), group.clone())
+            .await
+            .unwrap();
+
+        let queued_id = QueryId::next_id();
+        let queued = {
+            let tracker = tracker.clone();
+            let query = query.clone();
+            let group = group.clone();
+            tokio::spawn(async move { tracker.try_track_query(queued_id, query, group).await })
+        };
+        while tracker.queued_queries().is_empty() {
+            tokio::task::yield_now().await;
+        }
+        assert!(matches!(
+            tracker.query(&queued_id).unwrap().status().query_state(),
+            QueryState::QUEUED
+        ));
+        assert_eq!(tracker._running_query_count(), 1);
+
+        // the queue is full
+        assert!(matches!(
+            tracker
+                .try_track_query(QueryId::next_id(), query.clone(), group.clone())
+                .await,
+            Err(QueryError::ResourceGroupQueueFull { .. })
+        ));
+
+        let _ = tracker.expire_query(&running_id);
+        let _tq = queued.await.unwrap().unwrap();
+        assert!(tracker.queued_queries().is_empty());
+        assert_eq!(tracker._running_query_count(), 1);
+
+        // cancel a queued query
+        let cancelled_id = QueryId::next_id();
+        let cancelled = {
+            let tracker = tracker.clone();
+            tokio::spawn(async move { tracker.try_track_query(cancelled_id, query, group).await })
+        };
+        while tracker.queued_queries().is_empty() {
+            tokio::task::yield_now().await;
+        }
+        let _ = tracker.expire_query(&cancelled_id).unwrap().cancel();
+        assert!(matches!(cancelled.await.unwrap(), Err(QueryError::Cancel)));
+    }
 }
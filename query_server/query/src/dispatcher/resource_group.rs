@@ -0,0 +1,267 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use config::common::{ResourceGroupConfig, TenantResourceGroupConfig};
+use memory_pool::{MemoryPoolRef, QuotaMemoryPool};
+use parking_lot::{Mutex, RwLock};
+use spi::{QueryError, QueryResult};
+use tokio::sync::Notify;
+use tokio::time::Instant;
+use trace::debug;
+
+pub type ResourceGroupRef = Arc<ResourceGroup>;
+
+/// Resource groups of all tenants and users, created on first use
+pub struct ResourceGroupManager {
+    groups: Mutex<HashMap<String, ResourceGroupRef>>,
+    memory_pool: MemoryPoolRef,
+}
+
+impl ResourceGroupManager {
+    pub fn new(memory_pool: MemoryPoolRef) -> Self {
+        Self {
+            groups: Mutex::new(HashMap::new()),
+            memory_pool,
+        }
+    }
+
+    /// The group the queries of `user` in `tenant` run in, [`None`] if not limited
+    pub fn group(
+        &self,
+        tenant: &str,
+        user: &str,
+        config: Option<&TenantResourceGroupConfig>,
+    ) -> Option<ResourceGroupRef> {
+        let config = config?;
+        let (name, group_config) = match config.users.get(user) {
+            Some(group_config) => (format!("{tenant}.{user}"), group_config),
+            None => (tenant.to_string(), config.tenant.as_ref()?),
+        };
+
+        let mut groups = self.groups.lock();
+        let group = groups.entry(name).or_insert_with_key(|name| {
+            Arc::new(ResourceGroup::new(
+                name.clone(),
+                group_config.clone(),
+                self.memory_pool.clone(),
+            ))
+        });
+        // the options of the tenant may have been altered
+        group.update_config(group_config);
+
+        Some(group.clone())
+    }
+}
+
+#[derive(Default)]
+struct Slots {
+    running: usize,
+    queued: usize,
+}
+
+pub struct ResourceGroup {
+    name: String,
+    config: RwLock<ResourceGroupConfig>,
+    slots: Mutex<Slots>,
+    notify: Notify,
+    memory_pool: Arc<QuotaMemoryPool>,
+}
+
+impl ResourceGroup {
+    fn new(name: String, config: ResourceGroupConfig, memory_pool: MemoryPoolRef) -> Self {
+        let quota = config.memory_quota.unwrap_or(usize::MAX);
+        Self {
+            name,
+            config: RwLock::new(config),
+            slots: Mutex::new(Slots::default()),
+            notify: Notify::new(),
+            memory_pool: Arc::new(QuotaMemoryPool::new(memory_pool, quota)),
+        }
+    }
+
+    fn update_config(&self, config: &ResourceGroupConfig) {
+        if *self.config.read() == *config {
+            return;
+        }
+        debug!(
+            "Update config of resource group {}: {:?}",
+            self.name, config
+        );
+        self.memory_pool
+            .set_quota(config.memory_quota.unwrap_or(usize::MAX));
+        *self.config.write() = config.clone();
+        // more queries may be allowed to run
+        self.notify.notify_waiters();
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Memory pool shared by the running queries of the group
+    pub fn memory_pool(&self) -> MemoryPoolRef {
+        self.memory_pool.clone()
+    }
+
+    /// Max target partitions of a query, derived from the cpu share of the group
+    pub fn max_target_partitions(&self) -> Option<usize> {
+        self.config
+            .read()
+            .cpu_share
+            .map(|share| (num_cpus::get() * share / 100).max(1))
+    }
+
+    /// Try to take a running slot of the group.
+    ///
+    /// Returns [`None`] if the query has to wait in the queue, a place in the queue is
+    /// reserved and the query must then call [`ResourceGroup::acquire_queued`].
+    ///
+    /// Errors:
+    ///     [`QueryError::ResourceGroupQueueFull`]
+    pub fn try_acquire(self: &Arc<Self>) -> QueryResult<Option<ResourceGroupPermit>> {
+        let config = self.config.read();
+        let mut slots = self.slots.lock();
+
+        // queued queries go first
+        if slots.queued == 0 && slots.running < config.max_concurrent_queries {
+            slots.running += 1;
+            return Ok(Some(ResourceGroupPermit {
+                group: self.clone(),
+            }));
+        }
+
+        if slots.queued >= config.max_queued_queries {
+            return Err(QueryError::ResourceGroupQueueFull {
+                group: self.name.clone(),
+            });
+        }
+        slots.queued += 1;
+
+        Ok(None)
+    }
+
+    /// Wait in the queue until a running slot is released.
+    ///
+    /// Errors:
+    ///     [`QueryError::ResourceGroupQueueTimeout`]
+    pub async fn acquire_queued(self: &Arc<Self>) -> QueryResult<ResourceGroupPermit> {
+        let _guard = QueuedGuard { group: self };
+        let timeout = self.config.read().queue_timeout;
+        let deadline = Instant::now() + timeout;
+
+        loop {
+            {
+                let config = self.config.read();
+                let mut slots = self.slots.lock();
+                if slots.running < config.max_concurrent_queries {
+                    slots.running += 1;
+                    return Ok(ResourceGroupPermit {
+                        group: self.clone(),
+                    });
+                }
+            }
+
+            if tokio::time::timeout_at(deadline, self.notify.notified())
+                .await
+                .is_err()
+            {
+                return Err(QueryError::ResourceGroupQueueTimeout {
+                    group: self.name.clone(),
+                    timeout,
+                });
+            }
+        }
+    }
+}
+
+/// Releases the place in the queue, whether the query got a running slot or not
+struct QueuedGuard<'a> {
+    group: &'a ResourceGroup,
+}
+
+impl Drop for QueuedGuard<'_> {
+    fn drop(&mut self) {
+        self.group.slots.lock().queued -= 1;
+        // pass a wakeup this query may have consumed on to the next one
+        self.group.notify.notify_one();
+    }
+}
+
+/// A running slot of a [`ResourceGroup`], released on drop
+pub struct ResourceGroupPermit {
+    group: ResourceGroupRef,
+}
+
+impl Drop for ResourceGroupPermit {
+    fn drop(&mut self) {
+        self.group.slots.lock().running -= 1;
+        self.group.notify.notify_one();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    use config::common::{ResourceGroupConfig, TenantResourceGroupConfig};
+    use memory_pool::GreedyMemoryPool;
+    use spi::QueryError;
+
+    use super::ResourceGroupManager;
+
+    fn config(max_concurrent_queries: usize, max_queued_queries: usize) -> ResourceGroupConfig {
+        ResourceGroupConfig {
+            max_concurrent_queries,
+            max_queued_queries,
+            queue_timeout: Duration::from_millis(100),
+            cpu_share: None,
+            memory_quota: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_acquire() {
+        let manager = ResourceGroupManager::new(Arc::new(GreedyMemoryPool::default()));
+        let tenant_config = TenantResourceGroupConfig {
+            tenant: Some(config(1, 1)),
+            users: [("etl".to_string(), config(1, 0))].into_iter().collect(),
+        };
+
+        assert!(manager.group("cnosdb", "root", None).is_none());
+        let group = manager
+            .group("cnosdb", "root", Some(&tenant_config))
+            .unwrap();
+        assert_eq!(group.name(), "cnosdb");
+        let etl = manager
+            .group("cnosdb", "etl", Some(&tenant_config))
+            .unwrap();
+        assert_eq!(etl.name(), "cnosdb.etl");
+
+        let permit = group.try_acquire().unwrap().unwrap();
+        // queued
+        assert!(group.try_acquire().unwrap().is_none());
+        // queue is full
+        assert!(matches!(
+            group.try_acquire(),
+            Err(QueryError::ResourceGroupQueueFull { .. })
+        ));
+        // other groups are not affected
+        let _etl_permit = etl.try_acquire().unwrap().unwrap();
+
+        let queued = {
+            let group = group.clone();
+            tokio::spawn(async move { group.acquire_queued().await })
+        };
+        drop(permit);
+        let permit = queued.await.unwrap().unwrap();
+
+        assert!(group.try_acquire().unwrap().is_none());
+        assert!(matches!(
+            group.acquire_queued().await,
+            Err(QueryError::ResourceGroupQueueTimeout { .. })
+        ));
+        drop(permit);
+        assert!(group.try_acquire().unwrap().is_some());
+    }
+}
@@ -46,6 +46,9 @@ impl DDLDefinitionTask for AlterDatabaseTask {
             });
         }
         build_database_schema(&self.stmt.database_options, &mut schema.config);
+        if self.stmt.unset_replica_placement {
+            schema.config.unset_replica_placement();
+        }
 
         client.alter_db_schema(schema).await.context(MetaSnafu)?;
         return Ok(Output::Nil(()));
@@ -68,4 +71,10 @@ fn build_database_schema(database_options: &DatabaseOptions, config: &mut Databa
     if let Some(precision) = database_options.precision() {
         config.with_precision(*precision);
     }
+    if let Some(replica_placement) = database_options.replica_placement() {
+        config.with_replica_placement(*replica_placement);
+    }
+    if let Some(cold_after) = database_options.cold_after() {
+        config.with_cold_after(cold_after.clone());
+    }
 }
@@ -0,0 +1,242 @@
+//! Backups of a database are stored in a location of an object store:
+//! - `backups/<backup_id>/meta.json`: the manifest of a backup, see [`BackupManifest`].
+//! - `vnodes/<vnode_id>`: files of the backed up vnodes, see `tskv::backup`. Column files
+//!   of a vnode are shared by its backups, other files are in the directory of each backup.
+//!
+//! Vnodes are backed up one by one, each vnode is consistent by itself, but a backup is not
+//! a snapshot of the database at one point in time, writes during the backup may be in the
+//! backup of some vnodes and not in others.
+
+use std::collections::HashMap;
+
+use async_trait::async_trait;
+use datafusion::arrow::datatypes::SchemaRef;
+use datafusion::sql::sqlparser::ast::SqlOption;
+use meta::error::MetaError;
+use models::meta_data::{BucketInfo, ReplicationSet, ReplicationSetId, VnodeId};
+use models::schema::{DatabaseSchema, TableSchema};
+use models::utils::now_timestamp_millis;
+use object_store::path::Path as ObjectPath;
+use object_store::ObjectStore;
+use protos::kv_service::ConnectionOption;
+use serde::{Deserialize, Serialize};
+use snafu::ResultExt;
+use spi::query::datasource;
+use spi::query::execution::{Output, QueryStateMachineRef};
+use spi::query::logical_planner::BackupDatabase;
+use spi::query::recordbatch::RecordBatchStreamWrapper;
+use spi::{CoordinatorSnafu, MetaSnafu, ObjectStoreSnafu, QueryError, QueryResult, SerdeJsonSnafu};
+use trace::info;
+
+use super::DDLDefinitionTask;
+
+/// Directory of the manifests of backups, each backup has a directory named by its id.
+pub(super) const BACKUPS_DIR: &str = "backups";
+/// Name of the file that describes a database backup, it's written after
+/// all vnodes are backed up, so a backup without it is incomplete.
+pub(super) const BACKUP_MANIFEST_FILE: &str = "meta.json";
+
+#[derive(Debug, Serialize, Deserialize)]
+pub(super) struct BackupManifest {
+    /// Ids of backups are increasing, the latest backup has the greatest id.
+    pub backup_id: String,
+    /// The backup that an incremental backup is based on, column files that are
+    /// already backed up by it are not uploaded again.
+    pub base_backup_id: Option<String>,
+    pub database: DatabaseSchema,
+    pub tables: Vec<TableSchema>,
+    pub buckets: Vec<BucketInfo>,
+    /// The backed up vnode of each replication set.
+    pub vnodes: HashMap<ReplicationSetId, BackupVnode>,
+    /// When the first vnode is started and the last vnode is finished backing up, in milliseconds.
+    pub start_time: i64,
+    pub finish_time: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub(super) struct BackupVnode {
+    pub vnode_id: VnodeId,
+    /// Location of the files of the vnode, relative to the location of the backups.
+    pub path: String,
+}
+
+impl BackupVnode {
+    pub fn location(&self, location: &str) -> String {
+        format!("{}/{}", location.trim_end_matches('/'), self.path)
+    }
+}
+
+fn vnode_path(vnode_id: VnodeId) -> String {
+    format!("vnodes/{}", vnode_id)
+}
+
+pub(super) fn manifest_path(path: &ObjectPath, backup_id: &str) -> ObjectPath {
+    path.child(BACKUPS_DIR)
+        .child(backup_id)
+        .child(BACKUP_MANIFEST_FILE)
+}
+
+/// Read the manifest of the latest complete backup in the location, if any.
+pub(super) async fn latest_manifest(
+    store: &dyn ObjectStore,
+    path: &ObjectPath,
+) -> QueryResult<Option<BackupManifest>> {
+    let backups = store
+        .list_with_delimiter(Some(&path.child(BACKUPS_DIR)))
+        .await
+        .map_err(|e| ObjectStoreSnafu { msg: e.to_string() }.build())?;
+    let mut backup_ids = backups
+        .common_prefixes
+        .iter()
+        .filter_map(|prefix| prefix.filename().map(|name| name.to_string()))
+        .collect::<Vec<_>>();
+    backup_ids.sort();
+
+    for backup_id in backup_ids.iter().rev() {
+        let data = match store.get(&manifest_path(path, backup_id)).await {
+            Ok(data) => data,
+            Err(object_store::Error::NotFound { .. }) => continue,
+            Err(e) => return Err(ObjectStoreSnafu { msg: e.to_string() }.build()),
+        };
+        let data = data
+            .bytes()
+            .await
+            .map_err(|e| ObjectStoreSnafu { msg: e.to_string() }.build())?;
+        let manifest = serde_json::from_slice(&data).context(SerdeJsonSnafu)?;
+        return Ok(Some(manifest));
+    }
+
+    Ok(None)
+}
+
+pub(super) fn connection_options_to_proto(options: &[SqlOption]) -> Vec<ConnectionOption> {
+    datasource::encode_connection_options(options)
+        .into_iter()
+        .map(|(name, value)| ConnectionOption { name, value })
+        .collect()
+}
+
+pub struct BackupDatabaseTask {
+    schema: SchemaRef,
+    stmt: BackupDatabase,
+}
+
+impl BackupDatabaseTask {
+    #[inline(always)]
+    pub fn new(stmt: BackupDatabase, schema: SchemaRef) -> Self {
+        Self { schema, stmt }
+    }
+}
+
+#[async_trait]
+impl DDLDefinitionTask for BackupDatabaseTask {
+    async fn execute(&self, query_state_machine: QueryStateMachineRef) -> QueryResult<Output> {
+        let BackupDatabase {
+            ref tenant_name,
+            ref db_name,
+            ref location,
+            ref connection_options,
+            incremental,
+        } = self.stmt;
+
+        let meta = query_state_machine
+            .meta
+            .tenant_meta(tenant_name)
+            .await
+            .ok_or_else(|| MetaError::TenantNotFound {
+                tenant: tenant_name.to_string(),
+            })
+            .context(MetaSnafu)?;
+        let db_info = meta
+            .get_db_info(db_name)
+            .context(MetaSnafu)?
+            .ok_or_else(|| MetaError::DatabaseNotFound {
+                database: db_name.to_string(),
+            })
+            .context(MetaSnafu)?;
+
+        // Build the object store first to check the location and options.
+        let (store, path) =
+            datasource::build_object_store_with_path(location, connection_options.clone())?;
+        let options = connection_options_to_proto(connection_options);
+
+        let base_backup_id = if incremental {
+            latest_manifest(store.as_ref(), &path)
+                .await?
+                .map(|manifest| manifest.backup_id)
+        } else {
+            None
+        };
+        // Zero padded, so ids are sorted by time as strings.
+        let start_time = now_timestamp_millis();
+        let backup_id = format!("{:016}", start_time);
+
+        let coord = query_state_machine.coord.clone();
+        let mut reports = vec![];
+        let mut vnodes = HashMap::new();
+        for bucket in db_info.buckets.iter() {
+            for replica in bucket.shard_group.iter() {
+                let vnode_id = backup_vnode_id(replica)?;
+                let vnode = BackupVnode {
+                    vnode_id,
+                    path: vnode_path(vnode_id),
+                };
+                let report = coord
+                    .backup_vnode(
+                        tenant_name,
+                        vnode.vnode_id,
+                        &vnode.location(location),
+                        &backup_id,
+                        options.clone(),
+                        incremental,
+                    )
+                    .await
+                    .context(CoordinatorSnafu)?;
+                reports.push(report);
+                vnodes.insert(replica.id, vnode);
+            }
+        }
+
+        let manifest = BackupManifest {
+            backup_id,
+            base_backup_id,
+            database: db_info.schema.clone(),
+            tables: db_info.tables.values().cloned().collect(),
+            buckets: db_info.buckets.clone(),
+            vnodes,
+            start_time,
+            finish_time: now_timestamp_millis(),
+        };
+        let data = serde_json::to_vec_pretty(&manifest).context(SerdeJsonSnafu)?;
+        store
+            .put(&manifest_path(&path, &manifest.backup_id), data.into())
+            .await
+            .map_err(|e| ObjectStoreSnafu { msg: e.to_string() }.build())?;
+        info!(
+            "Backed up database {} to '{}' as {}",
+            db_name, location, manifest.backup_id
+        );
+
+        let stream = RecordBatchStreamWrapper::new(self.schema.clone(), reports);
+        Ok(Output::StreamData(Box::pin(stream)))
+    }
+}
+
+/// Backup the leader vnode of the replication set, followers may fall behind.
+fn backup_vnode_id(replica: &ReplicationSet) -> QueryResult<VnodeId> {
+    if replica
+        .vnodes
+        .iter()
+        .any(|v| v.id == replica.leader_vnode_id)
+    {
+        return Ok(replica.leader_vnode_id);
+    }
+
+    replica
+        .vnodes
+        .first()
+        .map(|v| v.id)
+        .ok_or_else(|| QueryError::Semantic {
+            err: format!("Replication set [{}] has no vnode", replica.id),
+        })
+}
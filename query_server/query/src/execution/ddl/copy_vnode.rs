@@ -30,6 +30,16 @@ impl DDLDefinitionTask for CopyVnodeTask {
             .context(CoordinatorSnafu)?;
 
         let replica_id = vnode_all_info.repl_set_id;
+        coordinator::check_replica_placement(
+            query_state_machine.meta.clone(),
+            tenant,
+            replica_id,
+            node_id,
+            None,
+        )
+        .await
+        .context(CoordinatorSnafu)?;
+
         let cmd_type = coordinator::ReplicationCmdType::AddRaftFollower(replica_id, node_id);
         coord
             .replication_manager(tenant, cmd_type)
@@ -3,23 +3,20 @@ use std::sync::Arc;
 
 use async_trait::async_trait;
 use datafusion::arrow::datatypes::SchemaRef;
-use datafusion::datasource::file_format::avro::AvroFormat;
-use datafusion::datasource::file_format::csv::CsvFormat;
-use datafusion::datasource::file_format::file_type::{FileCompressionType, FileType};
-use datafusion::datasource::file_format::json::JsonFormat;
-use datafusion::datasource::file_format::parquet::ParquetFormat;
-use datafusion::datasource::file_format::FileFormat;
+use datafusion::datasource::file_format::file_type::FileCompressionType;
 use datafusion::datasource::listing::{ListingOptions, ListingTableUrl};
 use datafusion::execution::context::SessionState;
 use datafusion::logical_expr::CreateExternalTable;
 use lazy_static::__Deref;
 use meta::error::MetaError;
+use models::arrow::ExternalFileType;
 use models::schema::{ExternalTableSchema, TableSchema};
 use snafu::ResultExt;
 use spi::query::execution::{Output, QueryStateMachineRef};
 use spi::{DatafusionSnafu, MetaSnafu, QueryError, QueryResult};
 
 use super::DDLDefinitionTask;
+use crate::data_source::file_format::build_file_format;
 
 pub struct CreateExternalTableTask {
     stmt: CreateExternalTable,
@@ -163,26 +160,13 @@ fn build_external_table_config(
     target_partitions: usize,
 ) -> QueryResult<ListingOptions> {
     let file_compression_type = FileCompressionType::from(stmt.file_compression_type);
-    let file_type = FileType::from_str(stmt.file_type.as_str())?;
-    let file_extension = file_type.get_ext_with_compression(file_compression_type.to_owned())?;
-    let file_format: Arc<dyn FileFormat> = match file_type {
-        FileType::CSV => Arc::new(
-            CsvFormat::default()
-                .with_has_header(stmt.has_header)
-                .with_delimiter(stmt.delimiter as u8)
-                .with_file_compression_type(file_compression_type),
-        ),
-        FileType::PARQUET => Arc::new(ParquetFormat::default()),
-        FileType::AVRO => Arc::new(AvroFormat),
-        FileType::JSON => {
-            Arc::new(JsonFormat::default().with_file_compression_type(file_compression_type))
-        }
-        FileType::ARROW => {
-            return Err(QueryError::NotImplemented {
-                err: "Build arrow external table config".to_string(),
-            })
-        }
-    };
+    let file_type = ExternalFileType::from_str(stmt.file_type.as_str())?;
+    let (file_extension, file_format) = build_file_format(
+        &file_type,
+        file_compression_type,
+        stmt.has_header,
+        stmt.delimiter as u8,
+    )?;
 
     let options = ListingOptions::new(file_format)
         .with_file_extension(file_extension)
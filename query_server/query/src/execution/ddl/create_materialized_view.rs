@@ -0,0 +1,75 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use meta::error::MetaError;
+use models::schema::TableSchema;
+use snafu::ResultExt;
+use spi::query::datasource::stream::checker::StreamTableCheckerRef;
+use spi::query::execution::{Output, QueryStateMachineRef};
+use spi::query::logical_planner::CreateMaterializedView;
+use spi::{MetaSnafu, QueryResult};
+
+use super::create_stream_table::CreateStreamTableTask;
+use super::create_table::CreateTableTask;
+use crate::execution::ddl::DDLDefinitionTask;
+
+/// Create the tables of a materialized view and the view itself,
+/// the stream query maintaining the results is started by the dispatcher.
+pub struct CreateMaterializedViewTask {
+    checker: Option<StreamTableCheckerRef>,
+    stmt: CreateMaterializedView,
+}
+
+impl CreateMaterializedViewTask {
+    pub fn new(checker: Option<StreamTableCheckerRef>, stmt: CreateMaterializedView) -> Self {
+        Self { checker, stmt }
+    }
+}
+
+#[async_trait]
+impl DDLDefinitionTask for CreateMaterializedViewTask {
+    async fn execute(&self, query_state_machine: QueryStateMachineRef) -> QueryResult<Output> {
+        let CreateMaterializedView {
+            ref view,
+            ref if_not_exists,
+            ref table,
+            ref stream_table,
+        } = self.stmt;
+
+        let client = query_state_machine
+            .meta
+            .tenant_meta(&view.tenant)
+            .await
+            .ok_or_else(|| MetaError::TenantNotFound {
+                tenant: view.tenant.clone(),
+            })
+            .context(MetaSnafu)?;
+        let existing = client
+            .get_table_schema(&view.db, &view.name)
+            .context(MetaSnafu)?;
+
+        match (if_not_exists, existing) {
+            // do not create if exists
+            (true, Some(_)) => Ok(Output::Nil(())),
+            // Report an error if it exists
+            (false, Some(_)) => Err(MetaError::TableAlreadyExists {
+                table_name: format!("{}.{}", view.db, view.name),
+            })
+            .context(MetaSnafu)?,
+            // does not exist, create the table storing the results, the stream table and the view
+            (_, None) => {
+                CreateTableTask::new(table.clone())
+                    .execute(query_state_machine.clone())
+                    .await?;
+                CreateStreamTableTask::new(self.checker.clone(), stream_table.clone())
+                    .execute(query_state_machine)
+                    .await?;
+                client
+                    .create_table(&TableSchema::ViewSchema(Arc::new(view.clone())))
+                    .await
+                    .context(MetaSnafu)?;
+                Ok(Output::Nil(()))
+            }
+        }
+    }
+}
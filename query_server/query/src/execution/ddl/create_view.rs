@@ -0,0 +1,93 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use meta::error::MetaError;
+use models::schema::{TableSchema, ViewSchema};
+use snafu::ResultExt;
+use spi::query::execution::{Output, QueryStateMachineRef};
+use spi::query::logical_planner::CreateView;
+use spi::{MetaSnafu, QueryError, QueryResult};
+
+use crate::execution::ddl::DDLDefinitionTask;
+
+pub struct CreateViewTask {
+    stmt: CreateView,
+}
+
+impl CreateViewTask {
+    pub fn new(stmt: CreateView) -> Self {
+        Self { stmt }
+    }
+}
+
+#[async_trait]
+impl DDLDefinitionTask for CreateViewTask {
+    async fn execute(&self, query_state_machine: QueryStateMachineRef) -> QueryResult<Output> {
+        let CreateView {
+            ref name,
+            ref or_replace,
+            ..
+        } = self.stmt;
+
+        let tenant = name.tenant();
+        let client = query_state_machine
+            .meta
+            .tenant_meta(tenant)
+            .await
+            .ok_or_else(|| MetaError::TenantNotFound {
+                tenant: tenant.to_string(),
+            })
+            .context(MetaSnafu)?;
+        let table = client
+            .get_table_schema(name.database(), name.table())
+            .context(MetaSnafu)?;
+
+        let view = TableSchema::ViewSchema(Arc::new(build_view(&self.stmt)));
+        match (or_replace, table) {
+            // replace the definition of the view
+            (true, Some(TableSchema::ViewSchema(v))) if v.materialized.is_none() => {
+                client.update_table(&view).await.context(MetaSnafu)?;
+            }
+            (true, Some(TableSchema::ViewSchema(_))) => {
+                return Err(QueryError::Semantic {
+                    err: format!("{} is a materialized view", name),
+                })
+            }
+            // only a view can be replaced by a view
+            (true, Some(_)) => {
+                return Err(QueryError::Semantic {
+                    err: format!("{} is not a view", name),
+                })
+            }
+            // Report an error if it exists
+            (false, Some(_)) => Err(MetaError::TableAlreadyExists {
+                table_name: name.to_string(),
+            })
+            .context(MetaSnafu)?,
+            // does not exist, create
+            (_, None) => {
+                client.create_table(&view).await.context(MetaSnafu)?;
+            }
+        }
+
+        Ok(Output::Nil(()))
+    }
+}
+
+fn build_view(stmt: &CreateView) -> ViewSchema {
+    let CreateView {
+        name,
+        definition,
+        schema,
+        ..
+    } = stmt;
+
+    ViewSchema {
+        tenant: name.tenant().to_string(),
+        db: name.database().to_string(),
+        name: name.table().to_string(),
+        definition: definition.clone(),
+        schema: schema.clone(),
+        materialized: None,
+    }
+}
@@ -0,0 +1,31 @@
+use async_trait::async_trait;
+use coordinator::resource_manager::ResourceManager;
+use snafu::ResultExt;
+use spi::query::execution::{Output, QueryStateMachineRef};
+use spi::query::logical_planner::DecommissionNode;
+use spi::{CoordinatorSnafu, QueryResult};
+
+use super::DDLDefinitionTask;
+
+pub struct DecommissionNodeTask {
+    stmt: DecommissionNode,
+}
+
+impl DecommissionNodeTask {
+    #[inline(always)]
+    pub fn new(stmt: DecommissionNode) -> Self {
+        Self { stmt }
+    }
+}
+
+#[async_trait]
+impl DDLDefinitionTask for DecommissionNodeTask {
+    async fn execute(&self, query_state_machine: QueryStateMachineRef) -> QueryResult<Output> {
+        let coord = query_state_machine.coord.clone();
+        ResourceManager::add_decommission_node_task(coord, self.stmt.node_id)
+            .await
+            .context(CoordinatorSnafu)?;
+
+        Ok(Output::Nil(()))
+    }
+}
@@ -1,8 +1,8 @@
 use async_trait::async_trait;
 use coordinator::resource_manager::ResourceManager;
 use meta::error::MetaError;
-use models::oid::Identifier;
-use models::schema::{ResourceInfo, ResourceOperator};
+use models::oid::{Identifier, Oid};
+use models::schema::{ResourceInfo, ResourceOperator, TableSchema};
 use snafu::ResultExt;
 use spi::query::execution::{Output, QueryStateMachineRef};
 use spi::query::logical_planner::{DatabaseObjectType, DropDatabaseObject};
@@ -45,10 +45,15 @@ impl DDLDefinitionTask for DropDatabaseObjectTask {
                     })
                     .context(MetaSnafu)?;
 
-                if client
+                let table = client
                     .get_table_schema(object_name.database(), object_name.table())
-                    .is_ok_and(|opt| opt.is_none())
-                {
+                    .context(MetaSnafu)?;
+                if let Some(TableSchema::ViewSchema(_)) = table {
+                    return Err(QueryError::Semantic {
+                        err: format!("{} is a view, use DROP VIEW to drop it", object_name),
+                    });
+                }
+                if table.is_none() {
                     if *if_exist {
                         return Ok(Output::Nil(()));
                     } else {
@@ -60,27 +65,89 @@ impl DDLDefinitionTask for DropDatabaseObjectTask {
                     }
                 }
 
-                let resourceinfo = ResourceInfo::new(
-                    (*client.tenant().id(), object_name.database().to_string()),
-                    object_name.tenant().to_string()
-                        + "-"
-                        + object_name.database()
-                        + "-"
-                        + object_name.table(),
-                    ResourceOperator::DropTable(
-                        object_name.tenant().to_string(),
-                        object_name.database().to_string(),
-                        object_name.table().to_string(),
-                    ),
-                    &None,
-                    query_state_machine.coord.node_id(),
-                );
-                ResourceManager::add_resource_task(query_state_machine.coord.clone(), resourceinfo)
+                drop_tskv_table(
+                    &query_state_machine,
+                    *client.tenant().id(),
+                    object_name.tenant(),
+                    object_name.database(),
+                    object_name.table(),
+                )
+                .await?;
+            }
+            DatabaseObjectType::View => {
+                info!("Drop view {}", object_name);
+                let tenant = object_name.tenant();
+                let client = query_state_machine
+                    .meta
+                    .tenant_meta(tenant)
                     .await
-                    .context(CoordinatorSnafu)?;
+                    .ok_or_else(|| MetaError::TenantNotFound {
+                        tenant: tenant.to_string(),
+                    })
+                    .context(MetaSnafu)?;
+
+                match client
+                    .get_table_schema(object_name.database(), object_name.table())
+                    .context(MetaSnafu)?
+                {
+                    Some(TableSchema::ViewSchema(view)) => {
+                        client
+                            .drop_table(object_name.database(), object_name.table())
+                            .await
+                            .context(MetaSnafu)?;
+                        // the stream query reading the stream table stops once it is dropped
+                        if let Some(materialized) = &view.materialized {
+                            client
+                                .drop_table(object_name.database(), &materialized.stream_table)
+                                .await
+                                .context(MetaSnafu)?;
+                            drop_tskv_table(
+                                &query_state_machine,
+                                *client.tenant().id(),
+                                object_name.tenant(),
+                                object_name.database(),
+                                &materialized.table,
+                            )
+                            .await?;
+                        }
+                    }
+                    Some(_) => {
+                        return Err(QueryError::Semantic {
+                            err: format!("{} is not a view", object_name),
+                        });
+                    }
+                    None if *if_exist => {}
+                    None => {
+                        return Err(QueryError::Meta {
+                            source: MetaError::TableNotFound {
+                                table: object_name.table().to_string(),
+                            },
+                        });
+                    }
+                }
             }
         };
 
         Ok(Output::Nil(()))
     }
 }
+
+async fn drop_tskv_table(
+    query_state_machine: &QueryStateMachineRef,
+    tenant_id: Oid,
+    tenant: &str,
+    database: &str,
+    table: &str,
+) -> QueryResult<()> {
+    let resourceinfo = ResourceInfo::new(
+        (tenant_id, database.to_string()),
+        tenant.to_string() + "-" + database + "-" + table,
+        ResourceOperator::DropTable(tenant.to_string(), database.to_string(), table.to_string()),
+        &None,
+        query_state_machine.coord.node_id(),
+    );
+    ResourceManager::add_resource_task(query_state_machine.coord.clone(), resourceinfo)
+        .await
+        .context(CoordinatorSnafu)?;
+    Ok(())
+}
@@ -8,11 +8,13 @@ use spi::QueryResult;
 use self::alter_tenant::AlterTenantTask;
 use self::alter_user::AlterUserTask;
 use self::create_external_table::CreateExternalTableTask;
+use self::create_materialized_view::CreateMaterializedViewTask;
 use self::create_role::CreateRoleTask;
 use self::create_stream_table::CreateStreamTableTask;
 use self::create_table::CreateTableTask;
 use self::create_tenant::CreateTenantTask;
 use self::create_user::CreateUserTask;
+use self::create_view::CreateViewTask;
 use self::drop_database_object::DropDatabaseObjectTask;
 use self::drop_global_object::DropGlobalObjectTask;
 use self::drop_tenant_object::DropTenantObjectTask;
@@ -26,39 +28,53 @@ use self::replica_remove::ReplicaRemoveTask;
 use self::show_replica::ShowReplicasTask;
 use crate::execution::ddl::alter_database::AlterDatabaseTask;
 use crate::execution::ddl::alter_table::AlterTableTask;
+use crate::execution::ddl::backup_database::BackupDatabaseTask;
 use crate::execution::ddl::checksum_group::ChecksumGroupTask;
 use crate::execution::ddl::compact_vnode::CompactVnodeTask;
 use crate::execution::ddl::copy_vnode::CopyVnodeTask;
 use crate::execution::ddl::create_database::CreateDatabaseTask;
+use crate::execution::ddl::decommission_node::DecommissionNodeTask;
 use crate::execution::ddl::drop_vnode::DropVnodeTask;
 use crate::execution::ddl::move_node::MoveVnodeTask;
+use crate::execution::ddl::rebalance::{
+    PauseRebalanceTask, RebalanceTask, ResumeRebalanceTask, ShowRebalanceTask,
+};
+use crate::execution::ddl::rebuild_index::RebuildIndexTask;
+use crate::execution::ddl::restore_database::RestoreDatabaseTask;
 
 mod alter_database;
 mod alter_table;
 mod alter_tenant;
 mod alter_user;
+mod backup_database;
 mod checksum_group;
 mod compact_vnode;
 mod copy_vnode;
 mod create_database;
 mod create_external_table;
+mod create_materialized_view;
 mod create_role;
 mod create_stream_table;
 mod create_table;
 mod create_tenant;
 mod create_user;
+mod create_view;
+mod decommission_node;
 mod drop_database_object;
 mod drop_global_object;
 mod drop_tenant_object;
 mod drop_vnode;
 mod grant_revoke;
 mod move_node;
+mod rebalance;
+mod rebuild_index;
 mod recover_database;
 mod recover_tenant;
 mod replica_add;
 mod replica_destory;
 mod replica_promote;
 mod replica_remove;
+mod restore_database;
 mod show_replica;
 
 /// Traits that DDL tasks should implement
@@ -179,11 +195,30 @@ impl DDLDefinitionTaskFactory {
             DDLPlan::ChecksumGroup(sub_plan) => {
                 Box::new(ChecksumGroupTask::new(sub_plan.clone(), self.plan.schema()))
             }
+            DDLPlan::RebuildIndex(sub_plan) => {
+                Box::new(RebuildIndexTask::new(sub_plan.clone(), self.plan.schema()))
+            }
+            DDLPlan::BackupDatabase(sub_plan) => Box::new(BackupDatabaseTask::new(
+                sub_plan.clone(),
+                self.plan.schema(),
+            )),
+            DDLPlan::RestoreDatabase(sub_plan) => Box::new(RestoreDatabaseTask::new(
+                sub_plan.clone(),
+                self.plan.schema(),
+            )),
             DDLPlan::CreateStreamTable(sub_plan) => {
                 let checker = self.stream_checker_manager.checker(&sub_plan.stream_type);
 
                 Box::new(CreateStreamTableTask::new(checker, sub_plan.clone()))
             }
+            DDLPlan::CreateView(sub_plan) => Box::new(CreateViewTask::new(sub_plan.clone())),
+            DDLPlan::CreateMaterializedView(sub_plan) => {
+                let checker = self
+                    .stream_checker_manager
+                    .checker(&sub_plan.stream_table.stream_type);
+
+                Box::new(CreateMaterializedViewTask::new(checker, sub_plan.clone()))
+            }
             DDLPlan::RecoverDatabase(sub_plan) => {
                 Box::new(RecoverDatabaseTask::new(sub_plan.clone()))
             }
@@ -197,6 +232,15 @@ impl DDLDefinitionTaskFactory {
             DDLPlan::ReplicaPromote(sub_plan) => {
                 Box::new(ReplicaPromoteTask::new(sub_plan.clone()))
             }
+            DDLPlan::Rebalance(sub_plan) => {
+                Box::new(RebalanceTask::new(sub_plan.clone(), self.plan.schema()))
+            }
+            DDLPlan::ShowRebalance => Box::new(ShowRebalanceTask::new(self.plan.schema())),
+            DDLPlan::PauseRebalance => Box::new(PauseRebalanceTask::new()),
+            DDLPlan::ResumeRebalance => Box::new(ResumeRebalanceTask::new()),
+            DDLPlan::DecommissionNode(sub_plan) => {
+                Box::new(DecommissionNodeTask::new(sub_plan.clone()))
+            }
         }
     }
 }
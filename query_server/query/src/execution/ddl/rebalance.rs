@@ -0,0 +1,162 @@
+use std::sync::Arc;
+use std::time::UNIX_EPOCH;
+
+use async_trait::async_trait;
+use coordinator::rebalance;
+use datafusion::arrow::array::{StringArray, UInt32Array, UInt64Array};
+use datafusion::arrow::datatypes::SchemaRef;
+use datafusion::arrow::record_batch::RecordBatch;
+use models::schema::ResourceOperator;
+use snafu::ResultExt;
+use spi::query::execution::{Output, QueryStateMachineRef};
+use spi::query::logical_planner::Rebalance;
+use spi::query::recordbatch::RecordBatchStreamWrapper;
+use spi::{ArrowSnafu, CoordinatorSnafu, QueryResult};
+
+use super::DDLDefinitionTask;
+
+pub struct RebalanceTask {
+    schema: SchemaRef,
+    stmt: Rebalance,
+}
+
+impl RebalanceTask {
+    #[inline(always)]
+    pub fn new(stmt: Rebalance, schema: SchemaRef) -> Self {
+        Self { schema, stmt }
+    }
+}
+
+#[async_trait]
+impl DDLDefinitionTask for RebalanceTask {
+    async fn execute(&self, query_state_machine: QueryStateMachineRef) -> QueryResult<Output> {
+        let coord = query_state_machine.coord.clone();
+        let moves = rebalance::start_rebalance(coord, self.stmt.dry_run)
+            .await
+            .context(CoordinatorSnafu)?;
+
+        let batch = RecordBatch::try_new(
+            self.schema.clone(),
+            vec![
+                Arc::new(StringArray::from_iter_values(
+                    moves.iter().map(|m| m.tenant.as_str()),
+                )),
+                Arc::new(UInt32Array::from_iter_values(
+                    moves.iter().map(|m| m.replica_id),
+                )),
+                Arc::new(UInt32Array::from_iter_values(
+                    moves.iter().map(|m| m.vnode_id),
+                )),
+                Arc::new(UInt64Array::from_iter_values(
+                    moves.iter().map(|m| m.from_node_id),
+                )),
+                Arc::new(UInt64Array::from_iter_values(
+                    moves.iter().map(|m| m.to_node_id),
+                )),
+            ],
+        )
+        .context(ArrowSnafu)?;
+        let stream = RecordBatchStreamWrapper::new(self.schema.clone(), vec![batch]);
+        Ok(Output::StreamData(Box::pin(stream)))
+    }
+}
+
+pub struct ShowRebalanceTask {
+    schema: SchemaRef,
+}
+
+impl ShowRebalanceTask {
+    #[inline(always)]
+    pub fn new(schema: SchemaRef) -> Self {
+        Self { schema }
+    }
+}
+
+#[async_trait]
+impl DDLDefinitionTask for ShowRebalanceTask {
+    async fn execute(&self, query_state_machine: QueryStateMachineRef) -> QueryResult<Output> {
+        let coord = query_state_machine.coord.clone();
+        let status = rebalance::rebalance_status(coord)
+            .await
+            .context(CoordinatorSnafu)?;
+
+        let mut time = vec![];
+        let mut status_list = vec![];
+        let mut moved_vnodes = vec![];
+        let mut total_vnodes = vec![];
+        let mut comment = vec![];
+        if let Some((resourceinfo, moved)) = status {
+            let datetime =
+                UNIX_EPOCH + std::time::Duration::from_nanos(resourceinfo.get_time() as u64);
+            time.push(
+                chrono::DateTime::<chrono::Utc>::from(datetime)
+                    .format("%Y-%m-%d %H:%M:%S")
+                    .to_string(),
+            );
+            status_list.push(resourceinfo.get_status().to_string());
+            moved_vnodes.push(moved as u64);
+            let total = match resourceinfo.get_operator() {
+                ResourceOperator::Rebalance(moves) => moves.len(),
+                _ => 0,
+            };
+            total_vnodes.push(total as u64);
+            comment.push(resourceinfo.get_comment().clone());
+        }
+
+        let batch = RecordBatch::try_new(
+            self.schema.clone(),
+            vec![
+                Arc::new(StringArray::from(time)),
+                Arc::new(StringArray::from(status_list)),
+                Arc::new(UInt64Array::from(moved_vnodes)),
+                Arc::new(UInt64Array::from(total_vnodes)),
+                Arc::new(StringArray::from(comment)),
+            ],
+        )
+        .context(ArrowSnafu)?;
+        let stream = RecordBatchStreamWrapper::new(self.schema.clone(), vec![batch]);
+        Ok(Output::StreamData(Box::pin(stream)))
+    }
+}
+
+pub struct PauseRebalanceTask {}
+
+impl PauseRebalanceTask {
+    #[inline(always)]
+    pub fn new() -> Self {
+        Self {}
+    }
+}
+
+#[async_trait]
+impl DDLDefinitionTask for PauseRebalanceTask {
+    async fn execute(&self, query_state_machine: QueryStateMachineRef) -> QueryResult<Output> {
+        let coord = query_state_machine.coord.clone();
+        rebalance::pause_rebalance(coord)
+            .await
+            .context(CoordinatorSnafu)?;
+
+        Ok(Output::Nil(()))
+    }
+}
+
+pub struct ResumeRebalanceTask {}
+
+impl ResumeRebalanceTask {
+    #[inline(always)]
+    pub fn new() -> Self {
+        Self {}
+    }
+}
+
+#[async_trait]
+impl DDLDefinitionTask for ResumeRebalanceTask {
+    async fn execute(&self, query_state_machine: QueryStateMachineRef) -> QueryResult<Output> {
+        let coord = query_state_machine.coord.clone();
+        rebalance::resume_rebalance(coord)
+            .await
+            .context(CoordinatorSnafu)?;
+
+        Ok(Output::Nil(()))
+    }
+}
@@ -0,0 +1,37 @@
+use async_trait::async_trait;
+use datafusion::arrow::datatypes::SchemaRef;
+use snafu::ResultExt;
+use spi::query::execution::{Output, QueryStateMachineRef};
+use spi::query::logical_planner::RebuildIndex;
+use spi::query::recordbatch::RecordBatchStreamWrapper;
+use spi::{CoordinatorSnafu, QueryResult};
+
+use super::DDLDefinitionTask;
+
+pub struct RebuildIndexTask {
+    schema: SchemaRef,
+    stmt: RebuildIndex,
+}
+
+impl RebuildIndexTask {
+    #[inline(always)]
+    pub fn new(stmt: RebuildIndex, schema: SchemaRef) -> Self {
+        Self { schema, stmt }
+    }
+}
+
+#[async_trait]
+impl DDLDefinitionTask for RebuildIndexTask {
+    async fn execute(&self, query_state_machine: QueryStateMachineRef) -> QueryResult<Output> {
+        let vnode_id = self.stmt.vnode_id;
+        let tenant = query_state_machine.session.tenant();
+
+        let coord = query_state_machine.coord.clone();
+        let report = coord
+            .rebuild_vnode_index(tenant, vnode_id)
+            .await
+            .context(CoordinatorSnafu)?;
+        let stream = RecordBatchStreamWrapper::new(self.schema.clone(), vec![report]);
+        Ok(Output::StreamData(Box::pin(stream)))
+    }
+}
@@ -25,6 +25,16 @@ impl DDLDefinitionTask for ReplicaAddTask {
 
         let coord = query_state_machine.coord.clone();
 
+        coordinator::check_replica_placement(
+            query_state_machine.meta.clone(),
+            tenant,
+            replica_id,
+            node_id,
+            None,
+        )
+        .await
+        .context(CoordinatorSnafu)?;
+
         let cmd_type = coordinator::ReplicationCmdType::AddRaftFollower(replica_id, node_id);
         coord
             .replication_manager(tenant, cmd_type)
@@ -0,0 +1,252 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use coordinator::resource_manager::ResourceManager;
+use datafusion::arrow::array::{UInt32Array, UInt64Array};
+use datafusion::arrow::datatypes::SchemaRef;
+use datafusion::arrow::record_batch::RecordBatch;
+use meta::error::MetaError;
+use meta::model::MetaClientRef;
+use models::meta_data::ReplicationSetId;
+use models::schema::{DatabaseSchema, ResourceInfo, ResourceOperator, StreamTable, TableSchema};
+use snafu::ResultExt;
+use spi::query::datasource;
+use spi::query::execution::{Output, QueryStateMachineRef};
+use spi::query::logical_planner::RestoreDatabase;
+use spi::query::recordbatch::RecordBatchStreamWrapper;
+use spi::{ArrowSnafu, CoordinatorSnafu, MetaSnafu, ObjectStoreSnafu, QueryError, QueryResult};
+use trace::{error, warn};
+use tskv::backup::{backup_location, BACKUP_SUMMARY_FILE};
+
+use super::backup_database::{
+    connection_options_to_proto, latest_manifest, BackupManifest, BackupVnode,
+};
+use super::DDLDefinitionTask;
+
+pub struct RestoreDatabaseTask {
+    schema: SchemaRef,
+    stmt: RestoreDatabase,
+}
+
+impl RestoreDatabaseTask {
+    #[inline(always)]
+    pub fn new(stmt: RestoreDatabase, schema: SchemaRef) -> Self {
+        Self { schema, stmt }
+    }
+
+    /// Create tables and buckets of the backup in the new database, and restore vnodes.
+    async fn restore_data(
+        &self,
+        query_state_machine: &QueryStateMachineRef,
+        meta: &MetaClientRef,
+        manifest: &BackupManifest,
+        backup_vnodes: &HashMap<ReplicationSetId, &BackupVnode>,
+    ) -> QueryResult<RecordBatch> {
+        let RestoreDatabase {
+            ref tenant_name,
+            ref new_db_name,
+            ref location,
+            ref connection_options,
+            ..
+        } = self.stmt;
+
+        for table in manifest.tables.iter() {
+            let table = rename_table(table, tenant_name, new_db_name);
+            meta.create_table(&table).await.context(MetaSnafu)?;
+        }
+
+        let options = connection_options_to_proto(connection_options);
+        let coord = query_state_machine.coord.clone();
+        let mut backup_vnode_ids = vec![];
+        let mut vnode_ids = vec![];
+        let mut node_ids = vec![];
+        for bucket in manifest.buckets.iter() {
+            let new_bucket = meta
+                .create_bucket(new_db_name, bucket.start_time)
+                .await
+                .context(MetaSnafu)?;
+            if new_bucket.start_time != bucket.start_time
+                || new_bucket.end_time != bucket.end_time
+                || new_bucket.shard_group.len() != bucket.shard_group.len()
+            {
+                return Err(QueryError::Semantic {
+                    err: format!(
+                        "Bucket [{}, {}) of the backup can't be restored, shard or duration of database {} is changed",
+                        bucket.start_time, bucket.end_time, new_db_name
+                    ),
+                });
+            }
+
+            for (replica, new_replica) in bucket.shard_group.iter().zip(new_bucket.shard_group) {
+                let backup_vnode = backup_vnodes[&replica.id];
+                let backup_location = backup_vnode.location(location);
+                for vnode in new_replica.vnodes.iter() {
+                    coord
+                        .restore_vnode(
+                            tenant_name,
+                            new_db_name,
+                            vnode.id,
+                            &backup_location,
+                            &manifest.backup_id,
+                            options.clone(),
+                        )
+                        .await
+                        .context(CoordinatorSnafu)?;
+                    backup_vnode_ids.push(backup_vnode.vnode_id);
+                    vnode_ids.push(vnode.id);
+                    node_ids.push(vnode.node_id);
+                }
+            }
+        }
+
+        RecordBatch::try_new(
+            self.schema.clone(),
+            vec![
+                Arc::new(UInt32Array::from(backup_vnode_ids)),
+                Arc::new(UInt32Array::from(vnode_ids)),
+                Arc::new(UInt64Array::from(node_ids)),
+            ],
+        )
+        .context(ArrowSnafu)
+    }
+}
+
+#[async_trait]
+impl DDLDefinitionTask for RestoreDatabaseTask {
+    async fn execute(&self, query_state_machine: QueryStateMachineRef) -> QueryResult<Output> {
+        let RestoreDatabase {
+            ref tenant_name,
+            ref db_name,
+            ref new_db_name,
+            ref location,
+            ref connection_options,
+        } = self.stmt;
+
+        let meta = query_state_machine
+            .meta
+            .tenant_meta(tenant_name)
+            .await
+            .ok_or_else(|| MetaError::TenantNotFound {
+                tenant: tenant_name.to_string(),
+            })
+            .context(MetaSnafu)?;
+
+        let (store, path) =
+            datasource::build_object_store_with_path(location, connection_options.clone())?;
+        // The latest backup is restored.
+        let manifest = latest_manifest(store.as_ref(), &path)
+            .await?
+            .ok_or_else(|| QueryError::Semantic {
+                err: format!("No complete backup in '{}'", location),
+            })?;
+        if manifest.database.database_name() != db_name {
+            return Err(QueryError::Semantic {
+                err: format!(
+                    "Backup in '{}' is of database {}, not {}",
+                    location,
+                    manifest.database.database_name(),
+                    db_name
+                ),
+            });
+        }
+
+        // Check that all vnodes are in the backup before creating anything.
+        let mut backup_vnodes = HashMap::new();
+        for replica in manifest.buckets.iter().flat_map(|b| b.shard_group.iter()) {
+            let backup_vnode =
+                manifest
+                    .vnodes
+                    .get(&replica.id)
+                    .ok_or_else(|| QueryError::Semantic {
+                        err: format!("Replication set [{}] is not in the backup", replica.id),
+                    })?;
+            let vnode_path = backup_vnode
+                .path
+                .split('/')
+                .fold(path.clone(), |path, part| path.child(part));
+            let summary =
+                backup_location(&vnode_path, &manifest.backup_id).child(BACKUP_SUMMARY_FILE);
+            store
+                .head(&summary)
+                .await
+                .map_err(|e| ObjectStoreSnafu { msg: e.to_string() }.build())?;
+            backup_vnodes.insert(replica.id, backup_vnode);
+        }
+
+        // Schemas are restored with the same column ids, so column files can be read directly.
+        // The database is hidden until all vnodes are restored, and dropped if anything failed.
+        let mut db_schema = DatabaseSchema::new(tenant_name, new_db_name);
+        db_schema.config = manifest.database.config.clone();
+        db_schema.config.set_db_is_hidden(true);
+        meta.create_db(db_schema).await.context(MetaSnafu)?;
+
+        let batch = match self
+            .restore_data(&query_state_machine, &meta, &manifest, &backup_vnodes)
+            .await
+        {
+            Ok(batch) => batch,
+            Err(e) => {
+                warn!("Failed to restore database {new_db_name} from '{location}', drop it: {e}");
+                let resourceinfo = ResourceInfo::new(
+                    (*meta.tenant().id(), new_db_name.to_string()),
+                    tenant_name.clone() + "-" + new_db_name,
+                    ResourceOperator::DropDatabase(tenant_name.clone(), new_db_name.clone()),
+                    &None,
+                    query_state_machine.coord.node_id(),
+                );
+                if let Err(drop_err) = ResourceManager::add_resource_task(
+                    query_state_machine.coord.clone(),
+                    resourceinfo,
+                )
+                .await
+                {
+                    error!(
+                        "Failed to drop database {new_db_name} after restoring failed: {drop_err}"
+                    );
+                }
+                return Err(e);
+            }
+        };
+        meta.set_db_is_hidden(tenant_name, new_db_name, false)
+            .await
+            .context(MetaSnafu)?;
+
+        let stream = RecordBatchStreamWrapper::new(self.schema.clone(), vec![batch]);
+        Ok(Output::StreamData(Box::pin(stream)))
+    }
+}
+
+fn rename_table(table: &TableSchema, tenant: &str, db: &str) -> TableSchema {
+    match table {
+        TableSchema::TsKvTableSchema(schema) => {
+            let mut schema = schema.as_ref().clone();
+            schema.tenant = tenant.to_string();
+            schema.db = db.to_string();
+            TableSchema::TsKvTableSchema(Arc::new(schema))
+        }
+        TableSchema::ExternalTableSchema(schema) => {
+            let mut schema = schema.as_ref().clone();
+            schema.tenant = tenant.to_string();
+            schema.db = db.to_string();
+            TableSchema::ExternalTableSchema(Arc::new(schema))
+        }
+        TableSchema::StreamTableSchema(schema) => {
+            TableSchema::StreamTableSchema(Arc::new(StreamTable::new(
+                tenant,
+                db,
+                schema.name(),
+                schema.schema(),
+                schema.stream_type(),
+                schema.watermark().clone(),
+                schema.extra_options().clone(),
+            )))
+        }
+        TableSchema::ViewSchema(schema) => {
+            let mut schema = schema.as_ref().clone();
+            schema.tenant = tenant.to_string();
+            schema.db = db.to_string();
+            TableSchema::ViewSchema(Arc::new(schema))
+        }
+    }
+}
@@ -12,6 +12,7 @@ use tskv::kv_option::QueryOptions;
 
 use super::dml::DMLExecution;
 use super::query::SqlQueryExecution;
+use super::result_cache::ResultCacheRef;
 use super::stream::trigger::executor::{TriggerExecutorFactory, TriggerExecutorFactoryRef};
 use super::stream::{MicroBatchStreamExecutionBuilder, MicroBatchStreamExecutionDesc};
 use super::sys::SystemExecution;
@@ -28,6 +29,7 @@ pub struct SqlQueryExecutionFactory {
     trigger_executor_factory: TriggerExecutorFactoryRef,
     runtime: Arc<DedicatedExecutor>,
     stream_checker_manager: StreamCheckerManagerRef,
+    result_cache: Option<ResultCacheRef>,
 }
 
 impl SqlQueryExecutionFactory {
@@ -38,6 +40,7 @@ impl SqlQueryExecutionFactory {
         query_tracker: Arc<QueryTracker>,
         stream_checker_manager: StreamCheckerManagerRef,
         config: Arc<QueryOptions>,
+        result_cache: Option<ResultCacheRef>,
     ) -> Self {
         // Only do periodic scheduling, no need for many threads
         let trigger_executor_runtime =
@@ -59,6 +62,7 @@ impl SqlQueryExecutionFactory {
             trigger_executor_factory,
             runtime,
             stream_checker_manager,
+            result_cache,
         }
     }
 }
@@ -87,6 +91,7 @@ impl QueryExecutionFactory for SqlQueryExecutionFactory {
                         query_plan,
                         self.optimizer.clone(),
                         self.scheduler.clone(),
+                        self.result_cache.clone(),
                     ))),
                     (true, false, true) => {
                         // 流操作
@@ -103,6 +108,7 @@ impl QueryExecutionFactory for SqlQueryExecutionFactory {
                                 self.scheduler.clone(),
                                 self.trigger_executor_factory.clone(),
                                 self.runtime.clone(),
+                                self.query_tracker.clone(),
                             )?;
 
                         Ok(Arc::new(exec))
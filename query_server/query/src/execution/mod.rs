@@ -2,6 +2,7 @@ mod ddl;
 mod dml;
 pub mod factory;
 mod query;
+pub mod result_cache;
 pub mod scheduler;
 mod stream;
 mod sys;
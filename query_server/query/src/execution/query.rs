@@ -1,21 +1,49 @@
+use std::collections::{BTreeMap, HashMap};
 use std::sync::Arc;
 
 use async_trait::async_trait;
+use datafusion::arrow::record_batch::RecordBatch;
+use datafusion::error::DataFusionError;
+use datafusion::physical_plan::aggregates::AggregateExec;
+use datafusion::physical_plan::coalesce_partitions::CoalescePartitionsExec;
+use datafusion::physical_plan::empty::EmptyExec;
+use datafusion::physical_plan::memory::{MemoryExec, MemoryStream};
+use datafusion::physical_plan::sorts::sort::SortExec;
+use datafusion::physical_plan::stream::RecordBatchStreamAdapter;
+use datafusion::physical_plan::union::UnionExec;
+use datafusion::physical_plan::{ExecutionPlan, SendableRecordBatchStream};
 use futures::stream::AbortHandle;
+use futures::StreamExt;
+use meta::error::MetaError;
+use models::meta_data::{NodeId, VnodeId};
+use models::predicate::domain::TimeRange;
+use models::utils::now_timestamp_nanos;
 use parking_lot::Mutex;
+use snafu::ResultExt;
+use spi::query::config::CacheMode;
 use spi::query::dispatcher::{QueryInfo, QueryStatus};
 use spi::query::execution::{Output, QueryExecution, QueryStateMachineRef};
 use spi::query::logical_planner::QueryPlan;
 use spi::query::optimizer::Optimizer;
 use spi::query::scheduler::SchedulerRef;
-use spi::{QueryError, QueryResult};
-use trace::debug;
+use spi::query::session::{SessionCtx, SqlExecInfo};
+use spi::{CoordinatorSnafu, MetaSnafu, QueryError, QueryResult};
+use trace::{debug, info};
+
+use super::result_cache::{
+    filter_time_range, CacheKey, CacheablePlan, ResultCacheRef, Reuse, ScannedVnode,
+};
+use crate::extension::physical::plan_node::aggregate_filter_scan::AggregateFilterTskvExec;
+use crate::extension::physical::plan_node::traced_proxy::TracedProxyExec;
+use crate::extension::physical::plan_node::tskv_exec::TskvExec;
 
 pub struct SqlQueryExecution {
     query_state_machine: QueryStateMachineRef,
     plan: QueryPlan,
     optimizer: Arc<dyn Optimizer + Send + Sync>,
     scheduler: SchedulerRef,
+    // None if the result cache is disabled
+    result_cache: Option<ResultCacheRef>,
 
     abort_handle: Mutex<Option<AbortHandle>>,
 }
@@ -26,40 +54,351 @@ impl SqlQueryExecution {
         plan: QueryPlan,
         optimizer: Arc<dyn Optimizer + Send + Sync>,
         scheduler: SchedulerRef,
+        result_cache: Option<ResultCacheRef>,
     ) -> Self {
         Self {
             query_state_machine,
             plan,
             optimizer,
             scheduler,
+            result_cache,
             abort_handle: Mutex::new(None),
         }
     }
 
     async fn start(&self) -> QueryResult<Output> {
+        let cache_mode = self
+            .query_state_machine
+            .session
+            .inner()
+            .config()
+            .get_extension::<CacheMode>()
+            .map(|m| *m)
+            .unwrap_or_default();
+        match &self.result_cache {
+            Some(result_cache) if cache_mode == CacheMode::On && !self.plan.is_explain() => {
+                self.start_with_cache(result_cache).await
+            }
+            _ => {
+                // begin optimize
+                self.query_state_machine.begin_optimize();
+                let physical_plan = self
+                    .optimizer
+                    .optimize(&self.plan.df_plan, &self.query_state_machine.session)
+                    .await?;
+                self.query_state_machine.end_optimize();
+
+                Ok(Output::StreamData(
+                    self.schedule_spillable(physical_plan).await?,
+                ))
+            }
+        }
+    }
+
+    async fn start_with_cache(&self, result_cache: &ResultCacheRef) -> QueryResult<Output> {
+        let session = &self.query_state_machine.session;
+        let computed_at = now_timestamp_nanos();
+
         // begin optimize
         self.query_state_machine.begin_optimize();
+        let logical_plan = self
+            .optimizer
+            .optimize_logical_plan(&self.plan.df_plan, session)?;
         let physical_plan = self
             .optimizer
-            .optimize(&self.plan.df_plan, &self.query_state_machine.session)
+            .create_physical_plan(&logical_plan, session)
             .await?;
         self.query_state_machine.end_optimize();
 
+        let mut scanned_vnodes = BTreeMap::new();
+        let cacheable_plan = match CacheablePlan::try_new(&logical_plan) {
+            Some(plan) if collect_scanned_vnodes(&physical_plan, &mut scanned_vnodes) => plan,
+            _ => {
+                return Ok(Output::StreamData(
+                    self.schedule_spillable(physical_plan).await?,
+                ))
+            }
+        };
+
+        let mut databases = scanned_vnodes
+            .keys()
+            .map(|(db, _)| db.clone())
+            .collect::<Vec<_>>();
+        databases.dedup();
+
+        let vnodes = match self
+            .scanned_vnodes(scanned_vnodes, &cacheable_plan.time_range)
+            .await
+        {
+            Ok(vnodes) => vnodes,
+            Err(err) => {
+                debug!(
+                    "Skip the result cache, failed to get scanned vnodes: {}",
+                    err
+                );
+                return Ok(Output::StreamData(
+                    self.schedule_spillable(physical_plan).await?,
+                ));
+            }
+        };
+
+        let key = CacheKey {
+            tenant: session.tenant().to_string(),
+            databases,
+            template: cacheable_plan.template.clone(),
+        };
+        let cached = result_cache.get(&key);
+        let reuse = cached.as_ref().and_then(|cached| {
+            cached.reuse(&cacheable_plan, &vnodes, result_cache.mutable_window())
+        });
+
+        let stream = match (cached, reuse, cacheable_plan.partitioning) {
+            (Some(cached), Some(Reuse::All), _) => {
+                debug!(
+                    "Reuse the cached result of query {}",
+                    self.query_state_machine.query_id
+                );
+                let stream =
+                    MemoryStream::try_new(cached.batches.clone(), cached.schema.clone(), None)?;
+                return Ok(Output::StreamData(Box::pin(stream)));
+            }
+            (
+                Some(cached),
+                Some(Reuse::Partial {
+                    keep_start,
+                    keep_end,
+                }),
+                Some(partitioning),
+            ) => {
+                debug!(
+                    "Reuse the cached result of query {} in [{}, {})",
+                    self.query_state_machine.query_id, keep_start, keep_end
+                );
+                let kept = cached.rows_in(partitioning.column, keep_start, keep_end)?;
+                let head_end =
+                    (keep_start > cacheable_plan.time_range.min_ts).then_some(keep_start);
+                let newest_plan = filter_time_range(&self.plan.df_plan, head_end, keep_end)?;
+                let newest_plan = self.optimizer.optimize(&newest_plan, session).await?;
+                self.schedule(merge_cached_rows(kept, newest_plan)?, session)
+                    .await?
+            }
+            _ => self.schedule_spillable(physical_plan).await?,
+        };
+
+        Ok(Output::StreamData(result_cache.cache_stream(
+            stream,
+            key,
+            cacheable_plan.time_range,
+            vnodes,
+            computed_at,
+        )))
+    }
+
+    /// Versions and bucket time ranges of the scanned vnodes, sorted by vnode id
+    async fn scanned_vnodes(
+        &self,
+        scanned_vnodes: BTreeMap<(String, NodeId), Vec<VnodeId>>,
+        time_range: &TimeRange,
+    ) -> QueryResult<Vec<ScannedVnode>> {
+        let tenant = self.query_state_machine.session.tenant();
+        let meta = self
+            .query_state_machine
+            .meta
+            .tenant_meta(tenant)
+            .await
+            .ok_or_else(|| MetaError::TenantNotFound {
+                tenant: tenant.to_string(),
+            })
+            .context(MetaSnafu)?;
+
+        let mut vnodes = vec![];
+        let mut buckets = HashMap::new();
+        for ((db, node_id), vnode_ids) in scanned_vnodes {
+            if !buckets.contains_key(&db) {
+                let db_buckets = meta
+                    .mapping_bucket(&db, time_range.min_ts, time_range.max_ts)
+                    .context(MetaSnafu)?;
+                buckets.insert(db.clone(), db_buckets);
+            }
+            let versions = self
+                .query_state_machine
+                .coord
+                .vnode_versions(tenant, &db, node_id, vnode_ids)
+                .await
+                .context(CoordinatorSnafu)?;
+
+            for version in versions {
+                let bucket = buckets[&db]
+                    .iter()
+                    .find(|b| {
+                        b.shard_group
+                            .iter()
+                            .any(|rs| rs.vnodes.iter().any(|v| v.id == version.vnode_id))
+                    })
+                    .ok_or_else(|| QueryError::Internal {
+                        reason: format!("Bucket of vnode {} not found", version.vnode_id),
+                    })?;
+                vnodes.push(ScannedVnode {
+                    version,
+                    time_range: TimeRange::new(bucket.start_time, bucket.end_time),
+                });
+            }
+        }
+        vnodes.sort_by_key(|v| v.version.vnode_id);
+        vnodes.dedup();
+
+        Ok(vnodes)
+    }
+
+    /// Schedules the physical plan of the query.
+    ///
+    /// If a GROUP BY runs out of memory before any row is returned, the query is planned
+    /// again with the input of GROUP BY sorted, whose sorts spill to disk.
+    async fn schedule_spillable(
+        &self,
+        physical_plan: Arc<dyn ExecutionPlan>,
+    ) -> QueryResult<SendableRecordBatchStream> {
+        let session = &self.query_state_machine.session;
+        let sorted = session
+            .inner()
+            .config()
+            .options()
+            .extensions
+            .get::<SqlExecInfo>()
+            .map(|info| info.sort_aggregate_input)
+            .unwrap_or_default();
+        if sorted || !has_group_by(&physical_plan) {
+            return self.schedule(physical_plan, session).await;
+        }
+
+        let mut stream = self.schedule(physical_plan, session).await?;
+        match stream.next().await {
+            Some(Err(err)) if is_resources_exhausted(&err) => {
+                drop(stream);
+                info!(
+                    "GROUP BY of query {} ran out of memory, plan it again with sorted input: {}",
+                    self.query_state_machine.query_id, err
+                );
+                let session = session.with_sort_aggregate_input()?;
+                let physical_plan = self
+                    .optimizer
+                    .optimize(&self.plan.df_plan, &session)
+                    .await?;
+                self.schedule(physical_plan, &session).await
+            }
+            first => {
+                let schema = stream.schema();
+                let stream = futures::stream::iter(first).chain(stream);
+                Ok(Box::pin(RecordBatchStreamAdapter::new(schema, stream)))
+            }
+        }
+    }
+
+    async fn schedule(
+        &self,
+        physical_plan: Arc<dyn ExecutionPlan>,
+        session: &SessionCtx,
+    ) -> QueryResult<SendableRecordBatchStream> {
         // begin schedule
         self.query_state_machine.begin_schedule();
         let stream = self
             .scheduler
-            .schedule(
-                physical_plan.clone(),
-                self.query_state_machine.session.inner().task_ctx(),
-            )
+            .schedule(physical_plan.clone(), session.inner().task_ctx())
             .await?
             .stream();
 
         debug!("Success build result stream.");
         self.query_state_machine.end_schedule();
 
-        Ok(Output::StreamData(stream))
+        Ok(stream)
+    }
+}
+
+/// Collect the vnodes scanned by the plan, grouped by database and node.
+///
+/// Returns false if the plan reads anything other than the vnodes.
+fn collect_scanned_vnodes(
+    plan: &Arc<dyn ExecutionPlan>,
+    vnodes: &mut BTreeMap<(String, NodeId), Vec<VnodeId>>,
+) -> bool {
+    let plan = match plan.as_any().downcast_ref::<TracedProxyExec>() {
+        Some(proxy) => proxy.inner(),
+        None => plan,
+    };
+    let any = plan.as_any();
+    let (table_schema, splits) = if let Some(exec) = any.downcast_ref::<TskvExec>() {
+        (exec.table_schema(), exec.splits())
+    } else if let Some(exec) = any.downcast_ref::<AggregateFilterTskvExec>() {
+        (exec.table_schema(), exec.splits())
+    } else {
+        let children = plan.children();
+        if children.is_empty() {
+            return any.is::<EmptyExec>() || any.is::<MemoryExec>();
+        }
+        return children
+            .iter()
+            .all(|child| collect_scanned_vnodes(child, vnodes));
+    };
+
+    for split in splits {
+        let replication_set = split.replication_set();
+        vnodes
+            .entry((table_schema.db.clone(), replication_set.leader_node_id))
+            .or_default()
+            .push(replication_set.leader_vnode_id);
+    }
+    true
+}
+
+/// Whether the plan has an aggregation with group keys.
+fn has_group_by(plan: &Arc<dyn ExecutionPlan>) -> bool {
+    let plan = match plan.as_any().downcast_ref::<TracedProxyExec>() {
+        Some(proxy) => proxy.inner(),
+        None => plan,
+    };
+    let group_by = plan
+        .as_any()
+        .downcast_ref::<AggregateExec>()
+        .map(|agg_exec| !agg_exec.group_expr().expr().is_empty())
+        .unwrap_or_default();
+    group_by || plan.children().iter().any(has_group_by)
+}
+
+/// Whether the error is caused by running out of memory, which may be passed through
+/// the channels of repartitions.
+fn is_resources_exhausted(err: &DataFusionError) -> bool {
+    match err {
+        DataFusionError::ResourcesExhausted(_) => true,
+        DataFusionError::Context(_, err) => is_resources_exhausted(err),
+        DataFusionError::External(err) => {
+            if let Some(err) = err.downcast_ref::<DataFusionError>() {
+                is_resources_exhausted(err)
+            } else if let Some(err) = err.downcast_ref::<Arc<DataFusionError>>() {
+                is_resources_exhausted(err)
+            } else {
+                false
+            }
+        }
+        _ => false,
+    }
+}
+
+/// Union the reused rows of a cached result with the result of the recomputed time range
+fn merge_cached_rows(
+    cached: Vec<RecordBatch>,
+    newest_plan: Arc<dyn ExecutionPlan>,
+) -> QueryResult<Arc<dyn ExecutionPlan>> {
+    let ordering = newest_plan.output_ordering().map(|o| o.to_vec());
+    let cached = Arc::new(MemoryExec::try_new(&[cached], newest_plan.schema(), None)?);
+    let merged: Arc<dyn ExecutionPlan> =
+        Arc::new(CoalescePartitionsExec::new(Arc::new(UnionExec::new(vec![
+            cached,
+            newest_plan,
+        ]))));
+
+    match ordering {
+        Some(ordering) => Ok(Arc::new(SortExec::new(ordering, merged))),
+        None => Ok(merged),
     }
 }
 
@@ -0,0 +1,403 @@
+//! Cache of the results of queries on tskv tables.
+//!
+//! A result is reused as a whole while the versions of the scanned vnodes are unchanged.
+//! For queries partitioned by the time column, e.g. grouped by `date_bin(time)`, rows of
+//! a cached result older than the mutable window and older than the buckets of the changed
+//! vnodes are reused for the query of a newer time range, only the newest time range is
+//! recomputed.
+
+mod plan;
+
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+
+use config::tskv::QueryConfig;
+use datafusion::arrow::array::{Array, BooleanArray, Int64Array};
+use datafusion::arrow::compute::{cast, filter_record_batch};
+use datafusion::arrow::datatypes::{DataType, SchemaRef, TimeUnit};
+use datafusion::arrow::record_batch::RecordBatch;
+use datafusion::error::Result as DFResult;
+use datafusion::physical_plan::{RecordBatchStream, SendableRecordBatchStream};
+use futures::{Stream, StreamExt};
+use models::meta_data::VnodeVersion;
+use models::predicate::domain::TimeRange;
+use moka::sync::Cache;
+pub use plan::{filter_time_range, CacheablePlan, TimePartitioning};
+
+pub type ResultCacheRef = Arc<ResultCache>;
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct CacheKey {
+    pub tenant: String,
+    /// Databases scanned by the plan, sorted
+    pub databases: Vec<String>,
+    /// Display of the optimized plan with the time range masked, see [`CacheablePlan`]
+    pub template: String,
+}
+
+/// A vnode scanned by a query
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ScannedVnode {
+    pub version: VnodeVersion,
+    /// Time range of the bucket of the vnode
+    pub time_range: TimeRange,
+}
+
+#[derive(Debug)]
+pub struct CachedResult {
+    pub time_range: TimeRange,
+    /// The scanned vnodes, sorted by vnode id
+    pub vnodes: Vec<ScannedVnode>,
+    /// Timestamp in nanoseconds when the query started
+    pub computed_at: i64,
+    pub schema: SchemaRef,
+    pub batches: Vec<RecordBatch>,
+    size: usize,
+}
+
+/// How a cached result is reused for a query
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Reuse {
+    /// The whole result is reused
+    All,
+    /// Rows with the partition column in `[keep_start, keep_end)` are reused,
+    /// rows of the time range before `keep_start` and from `keep_end` are recomputed
+    Partial { keep_start: i64, keep_end: i64 },
+}
+
+impl CachedResult {
+    pub fn reuse(
+        &self,
+        plan: &CacheablePlan,
+        vnodes: &[ScannedVnode],
+        mutable_window: i64,
+    ) -> Option<Reuse> {
+        if self.time_range == plan.time_range && self.vnodes == vnodes {
+            return Some(Reuse::All);
+        }
+
+        let partitioning = plan.partitioning?;
+        // data after the mutable window when the result was cached may have changed since,
+        // so may data in the buckets of the changed vnodes
+        let cached_end = self
+            .time_range
+            .max_ts
+            .saturating_add(1)
+            .min(self.computed_at.saturating_sub(mutable_window))
+            .min(self.changed_from(plan, vnodes));
+        let keep_end =
+            partitioning.align_down(cached_end.min(plan.time_range.max_ts.saturating_add(1)));
+        // the first bucket may be incomplete in either time range
+        let keep_start = partitioning
+            .align_up(plan.time_range.min_ts)
+            .max(partitioning.align_up(self.time_range.min_ts));
+
+        (keep_start < keep_end).then_some(Reuse::Partial {
+            keep_start,
+            keep_end,
+        })
+    }
+
+    /// The start of the earliest bucket in the time range of the query whose data may differ
+    /// from the cached result: buckets of vnodes with a different version, vnodes not scanned
+    /// when the result was cached and vnodes no longer scanned, e.g. dropped by ttl.
+    fn changed_from(&self, plan: &CacheablePlan, vnodes: &[ScannedVnode]) -> i64 {
+        let changed = vnodes.iter().filter(|v| !self.vnodes.contains(v));
+        let removed = self.vnodes.iter().filter(|v| {
+            v.time_range.max_ts >= plan.time_range.min_ts
+                && !vnodes
+                    .iter()
+                    .any(|n| n.version.vnode_id == v.version.vnode_id)
+        });
+        changed
+            .chain(removed)
+            .map(|v| v.time_range.min_ts)
+            .min()
+            .unwrap_or(i64::MAX)
+    }
+
+    /// Rows with the partition column in `[start, end)`
+    pub fn rows_in(&self, column: usize, start: i64, end: i64) -> DFResult<Vec<RecordBatch>> {
+        self.batches
+            .iter()
+            .map(|batch| {
+                let times = cast(
+                    batch.column(column),
+                    &DataType::Timestamp(TimeUnit::Nanosecond, None),
+                )?;
+                let times = cast(&times, &DataType::Int64)?;
+                let times = times
+                    .as_any()
+                    .downcast_ref::<Int64Array>()
+                    .expect("cast to Int64Array");
+                let predicate = times
+                    .iter()
+                    .map(|t| t.map(|t| t >= start && t < end))
+                    .collect::<BooleanArray>();
+                Ok(filter_record_batch(batch, &predicate)?)
+            })
+            .collect()
+    }
+}
+
+pub struct ResultCache {
+    cache: Cache<CacheKey, Arc<CachedResult>>,
+    max_entry_size: usize,
+    /// In nanoseconds
+    mutable_window: i64,
+}
+
+impl ResultCache {
+    pub fn new(config: &QueryConfig) -> Self {
+        let cache = Cache::builder()
+            .max_capacity(config.result_cache_max_size)
+            .weigher(|_, v: &Arc<CachedResult>| v.size.try_into().unwrap_or(u32::MAX))
+            .time_to_live(config.result_cache_ttl)
+            .support_invalidation_closures()
+            .build();
+
+        Self {
+            cache,
+            max_entry_size: config.result_cache_max_entry_size as usize,
+            mutable_window: config.result_cache_mutable_window.as_nanos() as i64,
+        }
+    }
+
+    pub fn get(&self, key: &CacheKey) -> Option<Arc<CachedResult>> {
+        self.cache.get(key)
+    }
+
+    pub fn mutable_window(&self) -> i64 {
+        self.mutable_window
+    }
+
+    /// Remove the cached results of a tenant, after its data or schemas changed
+    pub fn invalidate_tenant(&self, tenant: &str) {
+        let tenant = tenant.to_string();
+        // only fails if invalidation closures are not supported
+        let _ = self
+            .cache
+            .invalidate_entries_if(move |key, _| key.tenant == tenant);
+    }
+
+    /// Cache the batches of a stream when it's completed,
+    /// unless they are larger than the max entry size.
+    pub fn cache_stream(
+        self: &Arc<Self>,
+        stream: SendableRecordBatchStream,
+        key: CacheKey,
+        time_range: TimeRange,
+        vnodes: Vec<ScannedVnode>,
+        computed_at: i64,
+    ) -> SendableRecordBatchStream {
+        Box::pin(CachingStream {
+            schema: stream.schema(),
+            inner: stream,
+            cache: self.clone(),
+            pending: Some(PendingResult {
+                key,
+                time_range,
+                vnodes,
+                computed_at,
+                batches: vec![],
+                size: 0,
+            }),
+        })
+    }
+}
+
+struct PendingResult {
+    key: CacheKey,
+    time_range: TimeRange,
+    vnodes: Vec<ScannedVnode>,
+    computed_at: i64,
+    batches: Vec<RecordBatch>,
+    size: usize,
+}
+
+struct CachingStream {
+    schema: SchemaRef,
+    inner: SendableRecordBatchStream,
+    cache: Arc<ResultCache>,
+    /// None if the result is not going to be cached
+    pending: Option<PendingResult>,
+}
+
+impl Stream for CachingStream {
+    type Item = DFResult<RecordBatch>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let poll = self.inner.poll_next_unpin(cx);
+        match &poll {
+            Poll::Ready(Some(Ok(batch))) => {
+                let max_entry_size = self.cache.max_entry_size;
+                let too_large = match self.pending.as_mut() {
+                    Some(pending) => {
+                        pending.size += batch.get_array_memory_size();
+                        pending.batches.push(batch.clone());
+                        pending.size > max_entry_size
+                    }
+                    None => false,
+                };
+                if too_large {
+                    self.pending = None;
+                }
+            }
+            Poll::Ready(Some(Err(_))) => self.pending = None,
+            Poll::Ready(None) => {
+                if let Some(pending) = self.pending.take() {
+                    self.cache.cache.insert(
+                        pending.key,
+                        Arc::new(CachedResult {
+                            time_range: pending.time_range,
+                            vnodes: pending.vnodes,
+                            computed_at: pending.computed_at,
+                            schema: self.schema.clone(),
+                            batches: pending.batches,
+                            size: pending.size,
+                        }),
+                    );
+                }
+            }
+            Poll::Pending => {}
+        }
+        poll
+    }
+}
+
+impl RecordBatchStream for CachingStream {
+    fn schema(&self) -> SchemaRef {
+        self.schema.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vnode(vnode_id: u32, seq_no: u64, min_ts: i64, max_ts: i64) -> ScannedVnode {
+        ScannedVnode {
+            version: VnodeVersion {
+                vnode_id,
+                version_number: 1,
+                seq_no,
+            },
+            time_range: TimeRange::new(min_ts, max_ts),
+        }
+    }
+
+    fn cached(min_ts: i64, max_ts: i64, computed_at: i64) -> CachedResult {
+        CachedResult {
+            time_range: TimeRange::new(min_ts, max_ts),
+            vnodes: vec![vnode(1, 1, 0, 99)],
+            computed_at,
+            schema: Arc::new(datafusion::arrow::datatypes::Schema::empty()),
+            batches: vec![],
+            size: 0,
+        }
+    }
+
+    fn plan(min_ts: i64, max_ts: i64, bucket: Option<(i64, i64)>) -> CacheablePlan {
+        CacheablePlan {
+            template: String::new(),
+            time_range: TimeRange::new(min_ts, max_ts),
+            partitioning: Some(TimePartitioning { column: 0, bucket }),
+        }
+    }
+
+    #[test]
+    fn test_reuse_all() {
+        let result = cached(0, 99, 200);
+        let vnodes = result.vnodes.clone();
+        assert_eq!(
+            result.reuse(&plan(0, 99, None), &vnodes, 10),
+            Some(Reuse::All)
+        );
+        let whole = CacheablePlan {
+            partitioning: None,
+            ..plan(0, 99, None)
+        };
+        assert_eq!(result.reuse(&whole, &vnodes, 10), None);
+    }
+
+    #[test]
+    fn test_reuse_partial() {
+        // data after 80 may have changed since the result was cached
+        let result = cached(0, 99, 90);
+        let vnodes = [vnode(1, 1, 0, 99), vnode(2, 1, 100, 199)];
+        assert_eq!(
+            result.reuse(&plan(10, 149, None), &vnodes, 10),
+            Some(Reuse::Partial {
+                keep_start: 10,
+                keep_end: 80,
+            })
+        );
+        // buckets of 20 from 5, the first bucket of the query is incomplete
+        assert_eq!(
+            result.reuse(&plan(10, 149, Some((20, 5))), &vnodes, 10),
+            Some(Reuse::Partial {
+                keep_start: 25,
+                keep_end: 65,
+            })
+        );
+        // nothing cached before the query range
+        assert_eq!(result.reuse(&plan(90, 149, None), &vnodes, 10), None);
+    }
+
+    #[test]
+    fn test_reuse_changed_vnodes() {
+        let mut result = cached(0, 199, 1000);
+        result.vnodes = vec![vnode(1, 1, 0, 99), vnode(2, 1, 100, 199)];
+
+        // rows in the bucket of the written vnode are recomputed
+        let written = [vnode(1, 1, 0, 99), vnode(2, 2, 100, 199)];
+        assert_eq!(
+            result.reuse(&plan(10, 249, None), &written, 10),
+            Some(Reuse::Partial {
+                keep_start: 10,
+                keep_end: 100,
+            })
+        );
+        let written = [vnode(1, 2, 0, 99), vnode(2, 1, 100, 199)];
+        assert_eq!(result.reuse(&plan(10, 249, None), &written, 10), None);
+
+        // a vnode not scanned when the result was cached, e.g. created by late data
+        let created = [
+            vnode(1, 1, 0, 99),
+            vnode(2, 1, 100, 199),
+            vnode(3, 1, 50, 59),
+        ];
+        assert_eq!(
+            result.reuse(&plan(10, 249, None), &created, 10),
+            Some(Reuse::Partial {
+                keep_start: 10,
+                keep_end: 50,
+            })
+        );
+
+        // a vnode in the time range of the query is dropped
+        let dropped = [vnode(2, 1, 100, 199)];
+        assert_eq!(result.reuse(&plan(10, 249, None), &dropped, 10), None);
+        // the bucket of the vnode is before the time range of the query
+        assert_eq!(
+            result.reuse(&plan(120, 249, None), &dropped, 10),
+            Some(Reuse::Partial {
+                keep_start: 120,
+                keep_end: 200,
+            })
+        );
+    }
+
+    #[test]
+    fn test_align() {
+        let p = TimePartitioning {
+            column: 0,
+            bucket: Some((10, 3)),
+        };
+        assert_eq!(p.align_up(3), 3);
+        assert_eq!(p.align_up(4), 13);
+        assert_eq!(p.align_down(12), 3);
+        assert_eq!(p.align_down(-1), -7);
+    }
+}
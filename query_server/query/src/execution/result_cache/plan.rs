@@ -0,0 +1,398 @@
+//! Analysis of the optimized logical plan of a query for the result cache.
+
+use std::sync::Arc;
+
+use datafusion::arrow::datatypes::{IntervalDayTimeType, IntervalMonthDayNanoType};
+use datafusion::common::tree_node::{Transformed, TreeNode, VisitRecursion};
+use datafusion::common::Column;
+use datafusion::error::Result as DFResult;
+use datafusion::logical_expr::expr::{AggregateUDF, ScalarFunction, ScalarUDF};
+use datafusion::logical_expr::{
+    lit, or, Aggregate, BinaryExpr, BuiltinScalarFunction, Expr, Filter, LogicalPlan, Operator,
+    Projection, Sort, TableScan, Volatility,
+};
+use datafusion::optimizer::utils::{conjunction, split_conjunction};
+use datafusion::scalar::ScalarValue;
+use models::predicate::domain::TimeRange;
+use models::schema::TIME_FIELD;
+
+use crate::data_source::source_downcast_adapter;
+use crate::data_source::table_source::TableHandle;
+
+/// An optimized logical plan whose result can be cached.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CacheablePlan {
+    /// Display of the plan, with the bounds of the scanned time range masked
+    pub template: String,
+    /// The scanned time range in nanoseconds, bounds of the time column
+    /// in the filters of the plan
+    pub time_range: TimeRange,
+    /// How result rows map to the time ranges of the scanned data,
+    /// [`None`] if the result can only be reused as a whole
+    pub partitioning: Option<TimePartitioning>,
+}
+
+/// The result rows of a plan are computed from the scanned data within a time range
+/// which is known by a column of the row.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TimePartitioning {
+    /// Index of the output column holding the time, or the start of the time bucket of the row
+    pub column: usize,
+    /// Stride and origin of the time buckets in nanoseconds, if rows are aggregated by `date_bin`
+    pub bucket: Option<(i64, i64)>,
+}
+
+impl TimePartitioning {
+    /// Start of the first bucket which is fully after `ts`
+    pub fn align_up(&self, ts: i64) -> i64 {
+        match self.bucket {
+            Some((stride, origin)) => {
+                let offset = (ts as i128 - origin as i128).rem_euclid(stride as i128);
+                let aligned = match offset {
+                    0 => ts as i128,
+                    _ => ts as i128 - offset + stride as i128,
+                };
+                aligned.min(i64::MAX as i128) as i64
+            }
+            None => ts,
+        }
+    }
+
+    /// Start of the bucket containing `ts`
+    pub fn align_down(&self, ts: i64) -> i64 {
+        match self.bucket {
+            Some((stride, origin)) => {
+                let offset = (ts as i128 - origin as i128).rem_euclid(stride as i128);
+                (ts as i128 - offset).max(i64::MIN as i128) as i64
+            }
+            None => ts,
+        }
+    }
+}
+
+impl CacheablePlan {
+    /// Returns [`None`] if the plan reads anything other than tskv tables,
+    /// or the result may change without data changes, e.g. calls volatile functions.
+    pub fn try_new(plan: &LogicalPlan) -> Option<Self> {
+        let mut scans = 0;
+        let mut cacheable = true;
+        let _ = plan.apply(&mut |plan| {
+            cacheable = is_cacheable_node(plan, &mut scans);
+            if cacheable {
+                Ok(VisitRecursion::Continue)
+            } else {
+                Ok(VisitRecursion::Stop)
+            }
+        });
+        if !cacheable || scans == 0 {
+            return None;
+        }
+
+        // time ranges of multiple scans can't be told apart
+        if scans > 1 {
+            return Some(Self {
+                template: plan.display_indent_schema().to_string(),
+                time_range: TimeRange::all(),
+                partitioning: None,
+            });
+        }
+
+        let mut time_range = TimeRange::all();
+        let masked_plan = plan.clone().transform_up(&mask_time_range).ok()?;
+        let _ = plan.apply(&mut |plan| {
+            for predicate in time_predicates(plan) {
+                for expr in split_conjunction(predicate) {
+                    if let Some((op, ts)) = time_bound(expr) {
+                        time_range = intersect(time_range, op, ts);
+                    }
+                }
+            }
+            Ok(VisitRecursion::Continue)
+        });
+
+        Some(Self {
+            template: masked_plan.display_indent_schema().to_string(),
+            time_range,
+            partitioning: time_partitioning(plan),
+        })
+    }
+}
+
+fn is_cacheable_node(plan: &LogicalPlan, scans: &mut usize) -> bool {
+    match plan {
+        LogicalPlan::TableScan(TableScan {
+            source, filters, ..
+        }) => {
+            *scans += 1;
+            matches!(
+                source_downcast_adapter(source).map(|s| s.table_handle()),
+                Ok(TableHandle::Tskv(_))
+            ) && filters.iter().all(|e| !is_volatile(e))
+        }
+        LogicalPlan::Projection(_)
+        | LogicalPlan::Filter(_)
+        | LogicalPlan::Window(_)
+        | LogicalPlan::Aggregate(_)
+        | LogicalPlan::Sort(_)
+        | LogicalPlan::Join(_)
+        | LogicalPlan::CrossJoin(_)
+        | LogicalPlan::Repartition(_)
+        | LogicalPlan::Union(_)
+        | LogicalPlan::EmptyRelation(_)
+        | LogicalPlan::Subquery(_)
+        | LogicalPlan::SubqueryAlias(_)
+        | LogicalPlan::Limit(_)
+        | LogicalPlan::Values(_)
+        | LogicalPlan::Distinct(_)
+        | LogicalPlan::Unnest(_) => plan.expressions().iter().all(|e| !is_volatile(e)),
+        _ => false,
+    }
+}
+
+fn is_volatile(expr: &Expr) -> bool {
+    let mut volatile = false;
+    let _ = expr.apply(&mut |expr| {
+        volatile = match expr {
+            Expr::ScalarFunction(ScalarFunction { fun, .. }) => {
+                fun.volatility() == Volatility::Volatile
+            }
+            Expr::ScalarUDF(ScalarUDF { fun, .. }) => {
+                fun.signature.volatility == Volatility::Volatile
+            }
+            Expr::AggregateUDF(AggregateUDF { fun, .. }) => {
+                fun.signature.volatility == Volatility::Volatile
+            }
+            _ => false,
+        };
+        if volatile {
+            Ok(VisitRecursion::Stop)
+        } else {
+            Ok(VisitRecursion::Continue)
+        }
+    });
+    volatile
+}
+
+/// Predicates of the plan node which may bound the time range of the scan
+fn time_predicates(plan: &LogicalPlan) -> Vec<&Expr> {
+    match plan {
+        LogicalPlan::Filter(filter) => vec![&filter.predicate],
+        LogicalPlan::TableScan(scan) => scan.filters.iter().collect(),
+        _ => vec![],
+    }
+}
+
+/// Replace literals of the time bounds with nulls
+fn mask_time_range(plan: LogicalPlan) -> DFResult<Transformed<LogicalPlan>> {
+    match plan {
+        LogicalPlan::Filter(filter) => Ok(Transformed::Yes(LogicalPlan::Filter(Filter::try_new(
+            mask_time_bounds(&filter.predicate),
+            filter.input,
+        )?))),
+        LogicalPlan::TableScan(mut scan) => {
+            scan.filters = scan.filters.iter().map(mask_time_bounds).collect();
+            Ok(Transformed::Yes(LogicalPlan::TableScan(scan)))
+        }
+        _ => Ok(Transformed::No(plan)),
+    }
+}
+
+fn mask_time_bounds(predicate: &Expr) -> Expr {
+    let mask = |e: &Expr| match e {
+        Expr::Literal(v) => ScalarValue::try_from(v.get_datatype())
+            .map(Expr::Literal)
+            .unwrap_or_else(|_| e.clone()),
+        _ => e.clone(),
+    };
+    let exprs = split_conjunction(predicate)
+        .into_iter()
+        .map(|expr| match expr {
+            Expr::BinaryExpr(BinaryExpr { left, op, right }) if time_bound(expr).is_some() => {
+                Expr::BinaryExpr(BinaryExpr::new(
+                    Box::new(mask(left)),
+                    *op,
+                    Box::new(mask(right)),
+                ))
+            }
+            _ => expr.clone(),
+        })
+        .collect::<Vec<_>>();
+    conjunction(exprs).unwrap_or_else(|| predicate.clone())
+}
+
+/// Returns the operator and the timestamp in nanoseconds of a comparison
+/// between the time column and a timestamp literal, as `time op ts`
+fn time_bound(expr: &Expr) -> Option<(Operator, i64)> {
+    let (left, op, right) = match expr {
+        Expr::BinaryExpr(BinaryExpr { left, op, right }) => (left, *op, right),
+        _ => return None,
+    };
+    match (is_time_column(left), is_time_column(right)) {
+        (true, false) => Some((op, timestamp_nanos(right)?)),
+        (false, true) => Some((op.swap()?, timestamp_nanos(left)?)),
+        _ => None,
+    }
+    .filter(|(op, _)| {
+        matches!(
+            op,
+            Operator::Gt | Operator::GtEq | Operator::Lt | Operator::LtEq
+        )
+    })
+}
+
+fn is_time_column(expr: &Expr) -> bool {
+    match expr {
+        Expr::Column(c) => c.name == TIME_FIELD,
+        Expr::Cast(c) => is_time_column(&c.expr),
+        Expr::TryCast(c) => is_time_column(&c.expr),
+        _ => false,
+    }
+}
+
+fn timestamp_nanos(expr: &Expr) -> Option<i64> {
+    match expr {
+        Expr::Literal(ScalarValue::TimestampSecond(Some(v), _)) => v.checked_mul(1_000_000_000),
+        Expr::Literal(ScalarValue::TimestampMillisecond(Some(v), _)) => v.checked_mul(1_000_000),
+        Expr::Literal(ScalarValue::TimestampMicrosecond(Some(v), _)) => v.checked_mul(1_000),
+        Expr::Literal(ScalarValue::TimestampNanosecond(Some(v), _)) => Some(*v),
+        _ => None,
+    }
+}
+
+fn intersect(range: TimeRange, op: Operator, ts: i64) -> TimeRange {
+    match op {
+        Operator::Gt => TimeRange::new(range.min_ts.max(ts.saturating_add(1)), range.max_ts),
+        Operator::GtEq => TimeRange::new(range.min_ts.max(ts), range.max_ts),
+        Operator::Lt => TimeRange::new(range.min_ts, range.max_ts.min(ts.saturating_sub(1))),
+        Operator::LtEq => TimeRange::new(range.min_ts, range.max_ts.min(ts)),
+        _ => range,
+    }
+}
+
+fn time_partitioning(plan: &LogicalPlan) -> Option<TimePartitioning> {
+    match plan {
+        LogicalPlan::Sort(Sort {
+            input, fetch: None, ..
+        }) => time_partitioning(input),
+        LogicalPlan::Filter(Filter { input, .. }) => time_partitioning(input),
+        LogicalPlan::SubqueryAlias(alias) => time_partitioning(&alias.input),
+        LogicalPlan::Projection(Projection { expr, input, .. }) => {
+            let child = time_partitioning(input)?;
+            expr.iter()
+                .position(|e| {
+                    column_index(input, e.clone().unalias()).is_some_and(|i| i == child.column)
+                })
+                .map(|column| TimePartitioning { column, ..child })
+        }
+        LogicalPlan::Aggregate(Aggregate {
+            input, group_expr, ..
+        }) => {
+            let child = time_partitioning(input)?;
+            if child.bucket.is_some() {
+                return None;
+            }
+            group_expr.iter().enumerate().find_map(|(column, e)| {
+                let (stride, origin, source) = date_bin(e)?;
+                (column_index(input, source)? == child.column).then_some(TimePartitioning {
+                    column,
+                    bucket: Some((stride, origin)),
+                })
+            })
+        }
+        LogicalPlan::TableScan(TableScan {
+            projected_schema,
+            fetch: None,
+            ..
+        }) => projected_schema
+            .fields()
+            .iter()
+            .position(|f| f.name() == TIME_FIELD)
+            .map(|column| TimePartitioning {
+                column,
+                bucket: None,
+            }),
+        _ => None,
+    }
+}
+
+fn column_index(plan: &LogicalPlan, expr: Expr) -> Option<usize> {
+    match expr {
+        Expr::Column(c) => plan.schema().index_of_column(&c).ok(),
+        _ => None,
+    }
+}
+
+/// Returns the stride and origin in nanoseconds and the source of `date_bin(stride, source, origin)`
+fn date_bin(expr: &Expr) -> Option<(i64, i64, Expr)> {
+    let args = match expr.clone().unalias() {
+        Expr::ScalarFunction(ScalarFunction {
+            fun: BuiltinScalarFunction::DateBin,
+            args,
+        }) => args,
+        _ => return None,
+    };
+
+    let stride = match args.first()? {
+        Expr::Literal(ScalarValue::IntervalDayTime(Some(v))) => {
+            let (days, ms) = IntervalDayTimeType::to_parts(*v);
+            (days as i64)
+                .checked_mul(86_400_000_000_000)?
+                .checked_add((ms as i64).checked_mul(1_000_000)?)?
+        }
+        Expr::Literal(ScalarValue::IntervalMonthDayNano(Some(v))) => {
+            let (months, days, nanos) = IntervalMonthDayNanoType::to_parts(*v);
+            if months != 0 {
+                return None;
+            }
+            (days as i64)
+                .checked_mul(86_400_000_000_000)?
+                .checked_add(nanos)?
+        }
+        _ => return None,
+    };
+    if stride <= 0 {
+        return None;
+    }
+    let origin = match args.get(2) {
+        Some(e) => timestamp_nanos(e)?,
+        None => 0,
+    };
+    let source = match args.get(1)? {
+        Expr::Column(c) if c.name == TIME_FIELD => Expr::Column(c.clone()),
+        _ => return None,
+    };
+
+    Some((stride, origin, source))
+}
+
+/// Add a filter of the time column above the only tskv table scan of an unoptimized plan,
+/// such that only data of the time range not reused from the cache is scanned.
+///
+/// Data in `[head_end, tail_start)` is filtered out.
+pub fn filter_time_range(
+    plan: &LogicalPlan,
+    head_end: Option<i64>,
+    tail_start: i64,
+) -> DFResult<LogicalPlan> {
+    plan.clone().transform_up(&|plan| match plan {
+        LogicalPlan::TableScan(ref scan)
+            if matches!(
+                source_downcast_adapter(&scan.source).map(|s| s.table_handle()),
+                Ok(TableHandle::Tskv(_))
+            ) =>
+        {
+            let time = Expr::Column(Column::new(Some(scan.table_name.clone()), TIME_FIELD));
+            let ts = |v: i64| lit(ScalarValue::TimestampNanosecond(Some(v), None));
+            let tail = time.clone().gt_eq(ts(tail_start));
+            let predicate = match head_end {
+                Some(head_end) => or(time.lt(ts(head_end)), tail),
+                None => tail,
+            };
+            Ok(Transformed::Yes(LogicalPlan::Filter(Filter::try_new(
+                predicate,
+                Arc::new(plan),
+            )?)))
+        }
+        _ => Ok(Transformed::No(plan)),
+    })
+}
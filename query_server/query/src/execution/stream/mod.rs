@@ -11,8 +11,10 @@ use datafusion::common::Result as DFResult;
 use datafusion::physical_plan::displayable;
 use datafusion::prelude::SessionConfig;
 use futures::TryStreamExt;
+use meta::error::MetaError;
 use models::runtime::executor::{DedicatedExecutor, Job};
 use parking_lot::Mutex;
+use snafu::ResultExt;
 use spi::query::config::StreamTriggerInterval;
 use spi::query::datasource::stream::StreamProviderRef;
 use spi::query::dispatcher::{QueryInfo, QueryStatus, QueryStatusBuilder};
@@ -21,13 +23,14 @@ use spi::query::logical_planner::QueryPlan;
 use spi::query::physical_planner::PhysicalPlanner;
 use spi::query::recordbatch::RecordBatchStreamWrapper;
 use spi::query::scheduler::SchedulerRef;
-use spi::QueryResult;
-use trace::error;
+use spi::{MetaSnafu, QueryResult};
+use trace::{error, info};
 
 use self::trigger::executor::{TriggerExecutorFactoryRef, TriggerExecutorRef};
+use crate::dispatcher::query_tracker::QueryTracker;
 use crate::extension::analyse::stream_checker::UnsupportedOperationChecker;
 use crate::extension::analyse::AnalyzerRule;
-use crate::extension::logical::utils::extract_stream_providers;
+use crate::extension::logical::utils::{extract_stream_providers, extract_stream_tables};
 use crate::extension::physical::optimizer_rule::add_state_store::AddStateStore;
 use crate::extension::physical::transform_rule::stream_scan::StreamScanPlanner;
 use crate::extension::physical::transform_rule::watermark::WatermarkPlanner;
@@ -94,6 +97,7 @@ impl MicroBatchStreamExecutionBuilder {
         scheduler: SchedulerRef,
         trigger_executor_factory: TriggerExecutorFactoryRef,
         runtime: Arc<DedicatedExecutor>,
+        query_tracker: Arc<QueryTracker>,
     ) -> QueryResult<MicroBatchStreamExecution> {
         let MicroBatchStreamExecutionDesc {
             plan,
@@ -103,6 +107,7 @@ impl MicroBatchStreamExecutionBuilder {
         let stream_providers = self
             .stream_providers
             .unwrap_or_else(|| extract_stream_providers(plan.as_ref()));
+        let stream_tables = Arc::new(extract_stream_tables(plan.as_ref()));
 
         let trigger_executor = trigger_executor_factory.create(&trigger_interval);
         let watermark_tracker = Arc::new(WatermarkTracker::try_new(
@@ -114,12 +119,14 @@ impl MicroBatchStreamExecutionBuilder {
             query_state_machine,
             plan,
             stream_providers,
+            stream_tables,
             scheduler,
             trigger_executor,
             watermark_tracker,
             offset_tracker: Arc::new(OffsetTracker::new()),
             state_store_factory: Arc::new(MemoryStateStoreFactory::default()),
             runtime,
+            query_tracker,
             abort_handle: Mutex::new(None),
         })
     }
@@ -129,12 +136,15 @@ pub struct MicroBatchStreamExecution {
     query_state_machine: QueryStateMachineRef,
     plan: Arc<QueryPlan>,
     stream_providers: Vec<StreamProviderRef>,
+    /// Databases and names of the stream tables, the query stops once one of them is dropped
+    stream_tables: Arc<Vec<(String, String)>>,
     scheduler: SchedulerRef,
     trigger_executor: TriggerExecutorRef,
     state_store_factory: Arc<MemoryStateStoreFactory>,
     watermark_tracker: WatermarkTrackerRef,
     offset_tracker: OffsetTrackerRef,
     runtime: Arc<DedicatedExecutor>,
+    query_tracker: Arc<QueryTracker>,
     abort_handle: Mutex<Option<Job<()>>>,
 }
 
@@ -149,6 +159,8 @@ impl MicroBatchStreamExecution {
         let plan = self.plan.clone();
         let scheduler = self.scheduler.clone();
         let stream_providers = self.stream_providers.clone();
+        let stream_tables = self.stream_tables.clone();
+        let query_tracker = self.query_tracker.clone();
         let watermark_tracker = self.watermark_tracker.clone();
         let state_store_factory = self.state_store_factory.clone();
         let runtime = self.runtime.clone();
@@ -162,6 +174,8 @@ impl MicroBatchStreamExecution {
                     scheduler: scheduler.clone(),
                     current_batch_id,
                     stream_providers: stream_providers.clone(),
+                    stream_tables: stream_tables.clone(),
+                    query_tracker: query_tracker.clone(),
                     watermark_tracker: watermark_tracker.clone(),
                     state_store_factory: state_store_factory.clone(),
                     offset_tracker: offset_tracker.clone(),
@@ -275,6 +289,8 @@ struct IncrementalExecution<T> {
     scheduler: SchedulerRef,
     current_batch_id: i64,
     stream_providers: Vec<StreamProviderRef>,
+    stream_tables: Arc<Vec<(String, String)>>,
+    query_tracker: Arc<QueryTracker>,
     watermark_tracker: WatermarkTrackerRef,
     state_store_factory: Arc<T>,
     offset_tracker: OffsetTrackerRef,
@@ -286,6 +302,17 @@ where
     T::SS: Send + Sync + Debug,
 {
     async fn execute(&self) -> QueryResult<()> {
+        // 0. Stop the query if one of its stream tables was dropped on any node,
+        // e.g. the stream table of a dropped materialized view
+        if let Some((database, table)) = self.dropped_stream_table().await? {
+            let id = self.query_state_machine.query_id;
+            info!("Stop stream query {id}, its stream table {database}.{table} was dropped");
+            if let Some(query) = self.query_tracker.expire_query(&id) {
+                let _ = query.cancel();
+            }
+            return Ok(());
+        }
+
         // 1. Traverse the data source list of the execution plan, check whether there is new data, and update offset_tracker
         update_available_offsets(self.offset_tracker.clone(), &self.stream_providers).await?;
         trace::trace!("Traverse the data source list of the execution plan, check whether there is new data, and update offset_tracker");
@@ -299,6 +326,30 @@ where
         self.execute_once().await
     }
 
+    async fn dropped_stream_table(&self) -> QueryResult<Option<(String, String)>> {
+        let tenant = self.query_state_machine.session.tenant();
+        let meta = self
+            .query_state_machine
+            .meta
+            .tenant_meta(tenant)
+            .await
+            .ok_or_else(|| MetaError::TenantNotFound {
+                tenant: tenant.to_string(),
+            })
+            .context(MetaSnafu)?;
+        for (database, table) in self.stream_tables.iter() {
+            if meta
+                .get_table_schema(database, table)
+                .context(MetaSnafu)?
+                .is_none()
+            {
+                return Ok(Some((database.clone(), table.clone())));
+            }
+        }
+
+        Ok(None)
+    }
+
     async fn execute_once(&self) -> QueryResult<()> {
         let session = &self.query_state_machine.session;
         let current_watermark_ns = self.watermark_tracker.current_watermark_ns();
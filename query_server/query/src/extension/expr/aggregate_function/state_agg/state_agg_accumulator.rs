@@ -95,7 +95,10 @@ impl Accumulator for StateAggAccumulator {
     }
 
     fn size(&self) -> usize {
-        std::mem::size_of_val(self)
+        // the buffered records are accounted in the memory pool by the aggregate operator
+        std::mem::size_of_val(self) - std::mem::size_of_val(&self.state)
+            + self.state.size()
+            + self.input_type.capacity() * std::mem::size_of::<DataType>()
     }
 
     fn state(&self) -> DFResult<Vec<ScalarValue>> {
@@ -156,6 +159,11 @@ impl IntermediateState {
         self.state_value_records.extend(state.state_value_records);
     }
 
+    fn size(&self) -> usize {
+        ScalarValue::size_of_vec(&self.time_records)
+            + ScalarValue::size_of_vec(&self.state_value_records)
+    }
+
     fn sort_indices(&self) -> Vec<usize> {
         let mut sort = self.time_records.iter().enumerate().collect::<Vec<_>>();
         sort.sort_unstable_by(|(_, a), (_, b)| unsafe { a.partial_cmp(b).unwrap_unchecked() });
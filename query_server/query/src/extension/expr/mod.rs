@@ -11,6 +11,9 @@ mod ts_gen_func;
 mod window;
 
 use datafusion::arrow::datatypes::{DataType, IntervalUnit};
+use datafusion::prelude::SessionContext;
+use func_manager::DFSessionContextFuncAdapter;
+use lazy_static::lazy_static;
 pub use scalar_function::{INTERPOLATE, LOCF, TIME_WINDOW_GAPFILL};
 pub use selector_function::{BOTTOM, TOPK};
 pub use session_function::register_session_udfs;
@@ -53,3 +56,21 @@ pub fn load_all_functions(func_manager: &mut dyn FunctionMetadataManager) -> Que
     window::register_window_udfs(func_manager)?;
     Ok(())
 }
+
+lazy_static! {
+    static ref PUSHED_PLAN_CONTEXT: SessionContext = {
+        let mut ctx = SessionContext::new();
+        load_all_functions(&mut DFSessionContextFuncAdapter::new(&mut ctx))
+            .expect("load all functions");
+        ctx
+    };
+}
+
+/// Context to decode plans pushed down to the nodes where vnodes are located, with
+/// all built-in functions of datafusion and cnosdb.
+///
+/// Session functions, e.g. `current_user()`, are bound to a session of the query node,
+/// so they are not registered, and plans using them are not pushed down.
+pub fn pushed_plan_context() -> &'static SessionContext {
+    &PUSHED_PLAN_CONTEXT
+}
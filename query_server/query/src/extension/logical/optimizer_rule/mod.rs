@@ -1,2 +1,3 @@
 pub mod reject_cross_join;
+pub mod rewrite_materialized_view;
 pub mod rewrite_tag_scan;
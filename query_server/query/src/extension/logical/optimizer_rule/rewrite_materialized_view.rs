@@ -0,0 +1,209 @@
+use std::collections::HashSet;
+use std::sync::Arc;
+
+use datafusion::arrow::datatypes::{DataType, IntervalUnit, TimeUnit};
+use datafusion::common::{Column, DFSchemaRef};
+use datafusion::error::Result;
+use datafusion::logical_expr::expr::ScalarUDF;
+use datafusion::logical_expr::expr_rewriter::unnormalize_col;
+use datafusion::logical_expr::{
+    cast, max, Aggregate, BinaryExpr, Expr, LogicalPlan, LogicalPlanBuilder, Operator, TableScan,
+    TableSource,
+};
+use datafusion::optimizer::optimizer::ApplyOrder;
+use datafusion::optimizer::utils::split_conjunction;
+use datafusion::optimizer::{OptimizerConfig, OptimizerRule};
+use datafusion::scalar::ScalarValue;
+use models::schema::{MaterializedView, TIME_FIELD};
+
+use crate::data_source::table_source::TableSourceAdapter;
+use crate::extension::analyse::transform_time_window::{parse_duration_arg, simplify_expr};
+use crate::extension::expr::TIME_WINDOW;
+
+/// A materialized view, which may replace aggregates over its source table
+pub struct MaterializedViewCandidate {
+    pub database: String,
+    pub view: MaterializedView,
+    /// The tskv table storing the results of the view
+    pub table: Arc<dyn TableSource>,
+}
+
+/// Read the results of an aggregate from a materialized view
+///
+/// Triggering conditions:
+/// 1. The aggregate is over a table, optionally filtered by tags of the view, or by
+///    `time >= t` and `time < t` where `t` is the start of a window of the view
+/// 2. The aggregate has the same group by expressions as the view
+/// 3. All aggregate expressions are stored by the view
+///
+/// There is one row for each group in the materialized view, so the aggregate
+/// is kept and each aggregate expression is replaced by `max` of its stored column.
+pub struct RewriteMaterializedView {
+    candidates: Vec<MaterializedViewCandidate>,
+}
+
+impl RewriteMaterializedView {
+    pub fn new(candidates: Vec<MaterializedViewCandidate>) -> Self {
+        Self { candidates }
+    }
+}
+
+impl OptimizerRule for RewriteMaterializedView {
+    fn try_optimize(
+        &self,
+        plan: &LogicalPlan,
+        _optimizer_config: &dyn OptimizerConfig,
+    ) -> Result<Option<LogicalPlan>> {
+        if let LogicalPlan::Aggregate(aggregate) = plan {
+            let (scan, predicate) = match aggregate.input.as_ref() {
+                LogicalPlan::TableScan(scan) => (scan, None),
+                LogicalPlan::Filter(filter) => match filter.input.as_ref() {
+                    LogicalPlan::TableScan(scan) => (scan, Some(&filter.predicate)),
+                    _ => return Ok(None),
+                },
+                _ => return Ok(None),
+            };
+
+            if let Some(adapter) = scan.source.as_any().downcast_ref::<TableSourceAdapter>() {
+                for candidate in self.candidates.iter().filter(|c| {
+                    c.database == adapter.database_name() && c.view.source == adapter.table_name()
+                }) {
+                    if let Some(plan) = try_rewrite(aggregate, scan, predicate, candidate)? {
+                        return Ok(Some(plan));
+                    }
+                }
+            }
+        }
+
+        Ok(None)
+    }
+
+    fn name(&self) -> &str {
+        "rewrite_materialized_view"
+    }
+
+    fn apply_order(&self) -> Option<ApplyOrder> {
+        Some(ApplyOrder::BottomUp)
+    }
+}
+
+fn try_rewrite(
+    aggregate: &Aggregate,
+    scan: &TableScan,
+    predicate: Option<&Expr>,
+    candidate: &MaterializedViewCandidate,
+) -> Result<Option<LogicalPlan>> {
+    let view = &candidate.view;
+
+    let group_by = aggregate
+        .group_expr
+        .iter()
+        .map(unqualified_name)
+        .collect::<Result<HashSet<_>>>()?;
+    if aggregate.group_expr.len() != view.group_by.len()
+        || view.group_by.iter().any(|e| !group_by.contains(e))
+    {
+        return Ok(None);
+    }
+
+    // Tags are stored as is, the time column stores the start of windows,
+    // so filters on the time can only keep or drop whole windows
+    if let Some(predicate) = predicate {
+        let mut window = None;
+        for expr in split_conjunction(predicate) {
+            let columns = expr.to_columns()?;
+            if columns
+                .iter()
+                .all(|c| aggregate.group_expr.contains(&Expr::Column(c.clone())))
+            {
+                continue;
+            }
+            if window.is_none() {
+                window = window_nanos(aggregate);
+            }
+            match window {
+                Some(window) if filters_whole_windows(expr, window, aggregate.input.schema()) => {}
+                _ => return Ok(None),
+            }
+        }
+    }
+
+    let mut aggr_expr = Vec::with_capacity(aggregate.aggr_expr.len());
+    for expr in &aggregate.aggr_expr {
+        let name = unqualified_name(expr)?;
+        let column = match view.aggregates.iter().find(|(e, _)| e == &name) {
+            Some((_, column)) => column,
+            None => return Ok(None),
+        };
+        let column = Expr::Column(Column::new(Some(scan.table_name.clone()), column));
+        aggr_expr.push(max(column).alias(expr.display_name()?));
+    }
+
+    // Keep the qualifier of the source table, the expressions reference it
+    let mut builder =
+        LogicalPlanBuilder::scan(scan.table_name.clone(), candidate.table.clone(), None)?;
+    if let Some(predicate) = predicate {
+        builder = builder.filter(predicate.clone())?;
+    }
+    let plan = builder
+        .aggregate(aggregate.group_expr.clone(), aggr_expr)?
+        .build()?;
+
+    Ok(Some(plan))
+}
+
+/// The duration in nanoseconds of the tumbling time window the aggregate is grouped by
+fn window_nanos(aggregate: &Aggregate) -> Option<i64> {
+    aggregate.group_expr.iter().find_map(|expr| match expr {
+        Expr::ScalarUDF(ScalarUDF { fun, args }) if fun.name == TIME_WINDOW && args.len() == 2 => {
+            // a string like '1m' is cast to an interval by type coercion later
+            let duration = match parse_duration_arg(&args[1]) {
+                Ok(duration) => duration,
+                Err(_) => {
+                    let interval = cast(
+                        args[1].clone(),
+                        DataType::Interval(IntervalUnit::MonthDayNano),
+                    );
+                    let interval =
+                        simplify_expr(interval, aggregate.input.schema().clone()).ok()?;
+                    parse_duration_arg(&interval).ok()?
+                }
+            };
+            i64::try_from(duration.as_nanos()).ok()
+        }
+        _ => None,
+    })
+}
+
+/// Whether the expression is `time >= t` or `time < t` with `t` the start of a window,
+/// windows start at multiples of their duration since the unix epoch
+fn filters_whole_windows(expr: &Expr, window: i64, schema: &DFSchemaRef) -> bool {
+    let (op, value) = match expr {
+        Expr::BinaryExpr(BinaryExpr { left, op, right }) => match (left.as_ref(), right.as_ref()) {
+            (Expr::Column(c), value) if c.name == TIME_FIELD => (*op, value),
+            (value, Expr::Column(c)) if c.name == TIME_FIELD => match op.swap() {
+                Some(op) => (op, value),
+                None => return false,
+            },
+            _ => return false,
+        },
+        _ => return false,
+    };
+    if !matches!(op, Operator::GtEq | Operator::Lt) {
+        return false;
+    }
+
+    let value = cast(
+        value.clone(),
+        DataType::Timestamp(TimeUnit::Nanosecond, None),
+    );
+    match simplify_expr(value, schema.clone()) {
+        Ok(Expr::Literal(ScalarValue::TimestampNanosecond(Some(nanos), _))) => nanos % window == 0,
+        _ => false,
+    }
+}
+
+/// The name of the expression without qualifiers of columns
+pub fn unqualified_name(expr: &Expr) -> Result<String> {
+    unnormalize_col(expr.clone()).display_name()
+}
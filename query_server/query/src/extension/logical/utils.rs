@@ -38,3 +38,25 @@ impl<'a> TreeNodeVisitor for ExtractStreamProvider<'a> {
         Ok(VisitRecursion::Continue)
     }
 }
+
+/// The databases and names of the stream tables read by the plan
+pub fn extract_stream_tables(plan: &QueryPlan) -> Vec<(String, String)> {
+    let mut stream_tables = vec![];
+
+    let _ = plan.df_plan.apply(&mut |plan| {
+        if let LogicalPlan::TableScan(TableScan { source, .. }) = plan {
+            let adapter = source_downcast_adapter(source)
+                .map_err(|err| DataFusionError::External(Box::new(err)))?;
+            if let TableHandle::StreamProvider(_) = adapter.table_handle() {
+                stream_tables.push((
+                    adapter.database_name().to_string(),
+                    adapter.table_name().to_string(),
+                ));
+            }
+        }
+
+        Ok(VisitRecursion::Continue)
+    });
+
+    stream_tables
+}
@@ -1,4 +1,5 @@
 pub mod optimizer_rule;
 pub mod plan_node;
+pub mod spill;
 pub mod transform_rule;
 pub mod utils;
@@ -0,0 +1,127 @@
+use std::sync::Arc;
+
+use datafusion::common::tree_node::{Transformed, TreeNode};
+use datafusion::common::Result as DFResult;
+use datafusion::config::ConfigOptions;
+use datafusion::physical_optimizer::PhysicalOptimizerRule;
+use datafusion::physical_plan::aggregates::{AggregateExec, AggregateMode};
+use datafusion::physical_plan::coalesce_batches::CoalesceBatchesExec;
+use datafusion::physical_plan::filter::FilterExec;
+use datafusion::physical_plan::limit::LocalLimitExec;
+use datafusion::physical_plan::projection::ProjectionExec;
+use datafusion::physical_plan::repartition::RepartitionExec;
+use datafusion::physical_plan::{ExecutionPlan, Partitioning};
+use trace::debug;
+use tskv::reader::pushdown::{PushedPlan, ScanInputExec};
+
+use crate::extension::expr::pushed_plan_context;
+use crate::extension::physical::plan_node::tskv_exec::TskvExec;
+use crate::extension::utils::downcast_execution_plan;
+
+/// Push the partial aggregation on a [`TskvExec`], with projections and filters under it,
+/// down to the nodes where vnodes are located, so only intermediate aggregate states are
+/// sent to the query node.
+///
+/// The local limit on a [`TskvExec`] is pushed down the same way, it's also kept on the
+/// query node, as the rows of all vnodes in a split are limited together there.
+#[non_exhaustive]
+pub struct PushDownPartialAggregate {}
+
+impl PushDownPartialAggregate {
+    pub fn new() -> Self {
+        Self {}
+    }
+}
+
+impl Default for PushDownPartialAggregate {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PhysicalOptimizerRule for PushDownPartialAggregate {
+    fn optimize(
+        &self,
+        plan: Arc<dyn ExecutionPlan>,
+        _config: &ConfigOptions,
+    ) -> DFResult<Arc<dyn ExecutionPlan>> {
+        plan.transform_down(&|plan| {
+            if let Some(exec) = downcast_execution_plan::<AggregateExec>(plan.as_ref()) {
+                if *exec.mode() == AggregateMode::Partial {
+                    if let Some(new_plan) = push_down_to_table_scan(plan.clone())? {
+                        return Ok(Transformed::Yes(new_plan));
+                    }
+                }
+            }
+
+            if plan.as_any().is::<LocalLimitExec>() {
+                if let Some(new_plan) = push_down_to_table_scan(plan.clone())? {
+                    return Ok(Transformed::Yes(plan.with_new_children(vec![new_plan])?));
+                }
+            }
+
+            Ok(Transformed::No(plan))
+        })
+    }
+
+    fn name(&self) -> &str {
+        "push_down_partial_aggregate"
+    }
+
+    fn schema_check(&self) -> bool {
+        true
+    }
+}
+
+/// Returns a [`TskvExec`] with the pushed plan, if the input of the aggregation or limit
+/// is a chain of projections and filters on it.
+///
+/// Repartitions and batch coalescing in the chain are dropped, the pushed plan
+/// is executed for each vnode of the table scan.
+///
+/// Plans using functions not in [`pushed_plan_context`], e.g. session functions,
+/// are not pushed, as the nodes where vnodes are located can't decode them.
+fn push_down_to_table_scan(
+    plan: Arc<dyn ExecutionPlan>,
+) -> DFResult<Option<Arc<dyn ExecutionPlan>>> {
+    let mut pushed_plans = vec![plan.clone()];
+    let mut input = plan.children()[0].clone();
+    loop {
+        if let Some(exec) = downcast_execution_plan::<TskvExec>(input.as_ref()) {
+            // The limit is already pushed to the table scan if there is no filter on it.
+            if exec.pushed_plan().is_some() || exec.filter().limit().is_some() {
+                return Ok(None);
+            }
+
+            let mut plan: Arc<dyn ExecutionPlan> = Arc::new(ScanInputExec::new(exec.scan_schema()));
+            for pushed_plan in pushed_plans.into_iter().rev() {
+                plan = pushed_plan.with_new_children(vec![plan])?;
+            }
+
+            let pushed_plan = PushedPlan::try_new(plan).and_then(|pushed_plan| {
+                PushedPlan::decode(pushed_plan.bytes(), pushed_plan_context())?;
+                Ok(pushed_plan)
+            });
+            return match pushed_plan {
+                Ok(pushed_plan) => Ok(Some(Arc::new(exec.with_pushed_plan(pushed_plan)))),
+                Err(err) => {
+                    debug!("Can not push down plan to table scan: {}", err);
+                    Ok(None)
+                }
+            };
+        }
+
+        let any = input.as_any();
+        if any.is::<ProjectionExec>() || any.is::<FilterExec>() {
+            pushed_plans.push(input.clone());
+        } else if let Some(exec) = any.downcast_ref::<RepartitionExec>() {
+            if !matches!(exec.partitioning(), Partitioning::RoundRobinBatch(_)) {
+                return Ok(None);
+            }
+        } else if !any.is::<CoalesceBatchesExec>() {
+            return Ok(None);
+        }
+
+        input = input.children()[0].clone();
+    }
+}
@@ -0,0 +1,190 @@
+//! Sorts the input of GROUP BY by the group keys when `sql_exec_info.sort_aggregate_input` is set.
+//!
+//! The hash table of a hash aggregation can't be spilled, it fails when it runs out of memory.
+//! With the input sorted, the aggregation only keeps the groups of the current key in memory
+//! and the sorts spill to disk through the memory pool instead. Queries are re-planned with
+//! this rule enabled when their aggregation runs out of memory.
+
+use std::sync::Arc;
+
+use datafusion::arrow::compute::SortOptions;
+use datafusion::common::tree_node::{Transformed, TreeNode};
+use datafusion::common::Result as DFResult;
+use datafusion::config::ConfigOptions;
+use datafusion::physical_expr::PhysicalSortExpr;
+use datafusion::physical_optimizer::PhysicalOptimizerRule;
+use datafusion::physical_plan::aggregates::AggregateExec;
+use datafusion::physical_plan::sorts::sort::SortExec;
+use datafusion::physical_plan::ExecutionPlan;
+use spi::query::session::SqlExecInfo;
+
+use crate::extension::utils::downcast_execution_plan;
+
+#[non_exhaustive]
+pub struct SortAggregateInput {}
+
+impl SortAggregateInput {
+    pub fn new() -> Self {
+        Self {}
+    }
+}
+
+impl Default for SortAggregateInput {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PhysicalOptimizerRule for SortAggregateInput {
+    fn optimize(
+        &self,
+        plan: Arc<dyn ExecutionPlan>,
+        config: &ConfigOptions,
+    ) -> DFResult<Arc<dyn ExecutionPlan>> {
+        let enabled = config
+            .extensions
+            .get::<SqlExecInfo>()
+            .map(|info| info.sort_aggregate_input)
+            .unwrap_or_default();
+        if !enabled {
+            return Ok(plan);
+        }
+
+        plan.transform_up(&|plan| {
+            let agg_exec = match downcast_execution_plan::<AggregateExec>(plan.as_ref()) {
+                Some(agg_exec) => agg_exec,
+                None => return Ok(Transformed::No(plan)),
+            };
+            let group_by = agg_exec.group_expr();
+            // grouping sets aggregate every group of the input at once
+            if !group_by.is_single() || group_by.expr().is_empty() {
+                return Ok(Transformed::No(plan));
+            }
+
+            let sort_exprs = group_by
+                .expr()
+                .iter()
+                .map(|(expr, _)| PhysicalSortExpr {
+                    expr: expr.clone(),
+                    options: SortOptions::default(),
+                })
+                .collect::<Vec<_>>();
+            let input = agg_exec.input().clone();
+            let sorted = input
+                .output_ordering()
+                .map(|ordering| ordering.starts_with(&sort_exprs))
+                .unwrap_or_default();
+            if sorted {
+                return Ok(Transformed::No(plan));
+            }
+
+            let sort_exec =
+                Arc::new(SortExec::new(sort_exprs, input).with_preserve_partitioning(true));
+            Ok(Transformed::Yes(plan.with_new_children(vec![sort_exec])?))
+        })
+    }
+
+    fn name(&self) -> &str {
+        "sort_aggregate_input"
+    }
+
+    fn schema_check(&self) -> bool {
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use datafusion::arrow::array::{Int64Array, StringArray};
+    use datafusion::arrow::datatypes::{DataType, Field, Schema};
+    use datafusion::arrow::record_batch::RecordBatch;
+    use datafusion::config::ConfigOptions;
+    use datafusion::physical_optimizer::PhysicalOptimizerRule;
+    use datafusion::physical_plan::aggregates::{AggregateExec, AggregateMode, PhysicalGroupBy};
+    use datafusion::physical_plan::expressions::{col, Count};
+    use datafusion::physical_plan::memory::MemoryExec;
+    use datafusion::physical_plan::{displayable, ExecutionPlan};
+    use spi::query::session::SqlExecInfo;
+
+    use super::SortAggregateInput;
+
+    fn aggregate(group_by: PhysicalGroupBy) -> Arc<dyn ExecutionPlan> {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("tag", DataType::Utf8, true),
+            Field::new("value", DataType::Int64, true),
+        ]));
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![
+                Arc::new(StringArray::from(vec!["b", "a", "b"])),
+                Arc::new(Int64Array::from(vec![1, 2, 3])),
+            ],
+        )
+        .unwrap();
+        let input = Arc::new(MemoryExec::try_new(&[vec![batch]], schema.clone(), None).unwrap());
+        let count = Arc::new(Count::new(
+            col("value", &schema).unwrap(),
+            "count",
+            DataType::Int64,
+        ));
+
+        Arc::new(
+            AggregateExec::try_new(
+                AggregateMode::Single,
+                group_by,
+                vec![count],
+                vec![None],
+                vec![None],
+                input,
+                schema,
+            )
+            .unwrap(),
+        )
+    }
+
+    fn optimize(plan: Arc<dyn ExecutionPlan>, enabled: bool) -> Arc<dyn ExecutionPlan> {
+        let mut config = ConfigOptions::new();
+        config.extensions.insert(SqlExecInfo::default());
+        config
+            .set("sql_exec_info.sort_aggregate_input", &enabled.to_string())
+            .unwrap();
+        SortAggregateInput::new().optimize(plan, &config).unwrap()
+    }
+
+    fn display(plan: &Arc<dyn ExecutionPlan>) -> String {
+        displayable(plan.as_ref()).indent(false).to_string()
+    }
+
+    #[test]
+    fn test_sort_aggregate_input() {
+        let schema = aggregate(PhysicalGroupBy::default()).children()[0].schema();
+        let group_by =
+            PhysicalGroupBy::new_single(vec![(col("tag", &schema).unwrap(), "tag".to_string())]);
+
+        let plan = optimize(aggregate(group_by.clone()), true);
+        let lines = display(&plan);
+        let lines = lines.lines().map(|l| l.trim()).collect::<Vec<_>>();
+        assert!(
+            lines[0].starts_with("AggregateExec: mode=Single"),
+            "{lines:?}"
+        );
+        assert!(
+            lines[1].starts_with("SortExec: expr=[tag@0 ASC]"),
+            "{lines:?}"
+        );
+        assert!(lines[2].starts_with("MemoryExec"), "{lines:?}");
+
+        // the input is sorted already
+        let plan = optimize(plan, true);
+        assert_eq!(display(&plan).matches("SortExec").count(), 1);
+
+        // disabled
+        let plan = optimize(aggregate(group_by), false);
+        assert!(!display(&plan).contains("SortExec"));
+        // without group keys
+        let plan = optimize(aggregate(PhysicalGroupBy::default()), true);
+        assert!(!display(&plan).contains("SortExec"));
+    }
+}
@@ -53,6 +53,14 @@ impl AggregateFilterTskvExec {
             metrics: ExecutionPlanMetricsSet::new(),
         }
     }
+
+    pub fn table_schema(&self) -> &TskvTableSchemaRef {
+        &self.table_schema
+    }
+
+    pub fn splits(&self) -> &[PlacedSplit] {
+        &self.splits
+    }
 }
 
 impl ExecutionPlan for AggregateFilterTskvExec {
@@ -1,7 +1,6 @@
 //! Logic for buffering record batches for gap filling.
 
 use std::collections::HashSet;
-use std::sync::Arc;
 
 use datafusion::arrow::array::ArrayRef;
 use datafusion::arrow::record_batch::RecordBatch;
@@ -10,6 +9,7 @@ use datafusion::error::{DataFusionError, Result};
 
 use super::params::GapFillParams;
 use super::FillStrategy;
+use crate::extension::physical::spill::{SpillFile, Spiller};
 
 /// Encapsulate the logic around how to buffer input records.
 ///
@@ -21,6 +21,12 @@ use super::FillStrategy;
 /// to ensure that we read ahead far enough to a non-null value, or a change
 /// of group columns, in the columns being interpolated.
 ///
+/// Reading ahead to a non-null value may buffer a lot of rows. When out of memory,
+/// the rows after the last row that may appear in the output, except the last
+/// buffered row, are spilled to disk. Since the interpolated columns of the spilled
+/// rows are all null and the group columns do not change, they are not needed to
+/// produce the next output batch, and are left out of the records returned by [`Self::take`].
+///
 /// [`FillStrategy::LinearInterpolate`]: super::FillStrategy::LinearInterpolate
 /// [`GapFillStream`]: super::stream::GapFillStream
 pub(super) struct BufferedInput {
@@ -30,6 +36,13 @@ pub(super) struct BufferedInput {
     interpolate_cols: Vec<usize>,
     /// Buffered records from the input stream.
     batches: Vec<RecordBatch>,
+    /// Records spilled to disk, they come after `batches`.
+    spilled: Vec<SpilledBatches>,
+    /// Records buffered after the spilled records, empty if nothing is spilled.
+    tail: Vec<RecordBatch>,
+    /// The spilled records left out of the records returned by [`Self::take`],
+    /// and the number of rows after them.
+    taken_without_spilled: Option<(Vec<SpilledBatches>, usize)>,
     /// When gap filling with interpolated values, this row converter
     /// is used to compare rows to see if group columns have changed.
     row_converter: Option<RowConverter>,
@@ -52,25 +65,188 @@ impl BufferedInput {
             group_cols,
             interpolate_cols,
             batches: vec![],
+            spilled: vec![],
+            tail: vec![],
+            taken_without_spilled: None,
             row_converter: None,
             last_output_row: None,
         }
     }
     /// Add a new batch of buffered records from the input stream.
     pub(super) fn push(&mut self, batch: RecordBatch) {
-        self.batches.push(batch);
+        if self.spilled.is_empty() {
+            self.batches.push(batch);
+        } else {
+            self.tail.push(batch);
+        }
+    }
+
+    /// Add the input remaining after processing the records returned by [`Self::take`],
+    /// putting back the spilled records left out of them.
+    pub(super) fn push_remaining(&mut self, batch: RecordBatch) -> Result<()> {
+        match self.taken_without_spilled.take() {
+            Some((spilled, tail_rows)) => {
+                let head_rows = batch.num_rows().checked_sub(tail_rows).ok_or_else(|| {
+                    DataFusionError::Internal(
+                        "remaining input of gap filling is shorter than the records after the spilled records"
+                            .to_string(),
+                    )
+                })?;
+                if head_rows > 0 {
+                    self.batches.push(batch.slice(0, head_rows));
+                }
+                self.spilled = spilled;
+                self.tail.push(batch.slice(head_rows, tail_rows));
+            }
+            None => self.push(batch),
+        }
+        Ok(())
+    }
+
+    /// Spill the buffered records that are not needed for the next output batch.
+    ///
+    /// Returns the memory size of the spilled records.
+    pub(super) fn spill(
+        &mut self,
+        last_output_row_offset: usize,
+        spiller: &Spiller,
+    ) -> Result<usize> {
+        let min_needed = last_output_row_offset + 2;
+        // keep the records up to the last row that may appear in the output and the
+        // last buffered row in memory.
+        let (first, to_spill) = if self.spilled.is_empty() {
+            let mut record_count = 0;
+            let first = self
+                .batches
+                .iter()
+                .position(|batch| {
+                    record_count += batch.num_rows();
+                    record_count >= min_needed
+                })
+                .map_or(self.batches.len(), |i| i + 1);
+            (first, &self.batches[first..])
+        } else {
+            (0, &self.tail[..])
+        };
+        if to_spill.len() < 2 {
+            return Ok(0);
+        }
+        let to_spill = &to_spill[..to_spill.len() - 1];
+
+        let non_null_cols = self
+            .interpolate_cols
+            .iter()
+            .filter(|col_offset| {
+                to_spill.iter().any(|batch| {
+                    let array = batch.column(**col_offset);
+                    array.null_count() < array.len()
+                })
+            })
+            .cloned()
+            .collect();
+        let file = spiller.spill(to_spill)?;
+        let size = to_spill.iter().map(|rb| rb.get_array_memory_size()).sum();
+
+        if self.spilled.is_empty() {
+            self.tail = self.batches.split_off(first);
+        }
+        self.tail.drain(..self.tail.len() - 1);
+        self.spilled.push(SpilledBatches {
+            file,
+            non_null_cols,
+        });
+
+        Ok(size)
+    }
+
+    /// Read back the spilled records that may appear in the next output batch.
+    ///
+    /// Returns the memory size of the records read back.
+    pub(super) fn unspill(&mut self, last_output_row_offset: usize) -> Result<usize> {
+        let min_needed = last_output_row_offset + 2;
+        let mut size = 0;
+        while !self.spilled.is_empty() && self.memory_record_count() < min_needed {
+            size += self.unspill_first()?;
+        }
+        Ok(size)
+    }
+
+    /// Read back the spilled records if they are needed to process the buffered records,
+    /// i.e. they have non-null values to interpolate or a change of group columns.
+    ///
+    /// Must be called before [`Self::take`], returns the memory size of the records read back.
+    pub(super) fn prepare_take(&mut self) -> Result<usize> {
+        if self.spilled.is_empty() {
+            return Ok(0);
+        }
+        if self.spilled.iter().all(|s| s.non_null_cols.is_empty())
+            && !self.group_columns_changed_over_spilled()?
+        {
+            return Ok(0);
+        }
+
+        let mut size = 0;
+        while !self.spilled.is_empty() {
+            size += self.unspill_first()?;
+        }
+        Ok(size)
     }
 
     /// Transfer ownership of the buffered record batches to the caller for
     /// processing.
+    ///
+    /// Spilled records are left out, they are put back by [`Self::push_remaining`].
     pub(super) fn take(&mut self) -> Vec<RecordBatch> {
         self.last_output_row = None;
-        std::mem::take(&mut self.batches)
+        let mut batches = std::mem::take(&mut self.batches);
+        if !self.spilled.is_empty() {
+            let tail_rows = self.tail.iter().map(|rb| rb.num_rows()).sum();
+            self.taken_without_spilled = Some((std::mem::take(&mut self.spilled), tail_rows));
+            batches.append(&mut self.tail);
+        }
+        batches
+    }
+
+    fn memory_record_count(&self) -> usize {
+        self.batches.iter().map(|rb| rb.num_rows()).sum()
+    }
+
+    /// Read back the first spilled records, returns their memory size.
+    fn unspill_first(&mut self) -> Result<usize> {
+        let spilled = self.spilled.remove(0);
+        let batches = spilled.file.read()?;
+        let size = batches.iter().map(|rb| rb.get_array_memory_size()).sum();
+        self.batches.extend(batches);
+        if self.spilled.is_empty() {
+            self.batches.append(&mut self.tail);
+        }
+        Ok(size)
+    }
+
+    /// Check to see if the group column values change between the records
+    /// before and after the spilled records.
+    fn group_columns_changed_over_spilled(&mut self) -> Result<bool> {
+        if self.group_cols.is_empty() {
+            return Ok(false);
+        }
+        let (before, after) = match (self.batches.last(), self.tail.first()) {
+            (Some(before), Some(after)) => (before.clone(), after.clone()),
+            _ => return Ok(true),
+        };
+        let before_rows = self.convert_batch_row(&before, before.num_rows() - 1)?;
+        let after_rows = self.convert_batch_row(&after, 0)?;
+        Ok(before_rows.row(0) != after_rows.row(0))
     }
 
     /// Determine if we need more input before we start processing.
     pub(super) fn need_more(&mut self, last_output_row_offset: usize) -> Result<bool> {
-        let record_count: usize = self.batches.iter().map(|rb| rb.num_rows()).sum();
+        let record_count: usize = self.memory_record_count()
+            + self
+                .spilled
+                .iter()
+                .map(|s| s.file.num_rows())
+                .sum::<usize>()
+            + self.tail.iter().map(|rb| rb.num_rows()).sum::<usize>();
         // min number of rows needed is the number of rows up to and including
         // the last row that may appear in the output, plus one more row.
         let min_needed = last_output_row_offset + 2;
@@ -97,7 +273,12 @@ impl BufferedInput {
         // having to slice unless necessary.
         let mut cols_that_need_more =
             HashSet::<usize>::from_iter(self.interpolate_cols.iter().cloned());
-        let mut to_remove = vec![];
+        for batch in self.tail.iter().rev() {
+            remove_non_null_cols(&mut cols_that_need_more, batch, 0);
+        }
+        for spilled in self.spilled.iter().rev() {
+            cols_that_need_more.retain(|c| !spilled.non_null_cols.contains(c));
+        }
         for (i, batch) in self
             .batches
             .iter()
@@ -105,30 +286,17 @@ impl BufferedInput {
             .skip(last_output_batch_offset)
             .rev()
         {
-            for col_offset in cols_that_need_more.clone() {
-                // If this is the batch containing the last possible output row, slice the
-                // array so we are just looking at that value and the ones after.
-                let array = batch.column(col_offset);
-                let array = if i == last_output_batch_offset {
-                    let length = array.len() - last_output_row_offset;
-                    batch
-                        .column(col_offset)
-                        .slice(last_output_row_offset, length)
-                } else {
-                    Arc::clone(array)
-                };
-
-                if array.null_count() < array.len() {
-                    to_remove.push(col_offset);
-                }
-            }
-
-            to_remove.drain(..).for_each(|c| {
-                cols_that_need_more.remove(&c);
-            });
             if cols_that_need_more.is_empty() {
                 break;
             }
+            // If this is the batch containing the last possible output row, only
+            // look at that value and the ones after.
+            let offset = if i == last_output_batch_offset {
+                last_output_row_offset
+            } else {
+                0
+            };
+            remove_non_null_cols(&mut cols_that_need_more, batch, offset);
         }
 
         Ok(!cols_that_need_more.is_empty())
@@ -146,14 +314,21 @@ impl BufferedInput {
             return Ok(false);
         }
 
-        let last_buffered_row_idx = self.last_buffered_row_idx();
-        if last_output_row_idx == last_buffered_row_idx {
-            // the output row is also the last buffered row,
-            // so there is nothing to compare.
-            return Ok(false);
-        }
-
-        let last_input_rows = self.convert_row(self.last_buffered_row_idx())?;
+        let last_input_rows = match self.tail.last() {
+            Some(batch) => {
+                let batch = batch.clone();
+                self.convert_batch_row(&batch, batch.num_rows() - 1)?
+            }
+            None => {
+                let last_buffered_row_idx = self.last_buffered_row_idx();
+                if last_output_row_idx == last_buffered_row_idx {
+                    // the output row is also the last buffered row,
+                    // so there is nothing to compare.
+                    return Ok(false);
+                }
+                self.convert_row(last_buffered_row_idx)?
+            }
+        };
         let last_row_in_output = self.last_output_row(last_output_row_idx)?;
 
         Ok(last_row_in_output.row(0) != last_input_rows.row(0))
@@ -178,11 +353,16 @@ impl BufferedInput {
 
     /// Convert a row to row-oriented format for easy comparison.
     fn convert_row(&mut self, row_idxs: (usize, usize)) -> Result<Rows> {
-        let batch = &self.batches[row_idxs.0];
+        let batch = self.batches[row_idxs.0].clone();
+        self.convert_batch_row(&batch, row_idxs.1)
+    }
+
+    /// Convert the `row` of `batch` to row-oriented format.
+    fn convert_batch_row(&mut self, batch: &RecordBatch, row: usize) -> Result<Rows> {
         let columns: Vec<ArrayRef> = self
             .group_cols
             .iter()
-            .map(|col_idx| batch.column(*col_idx).slice(row_idxs.1, 1))
+            .map(|col_idx| batch.column(*col_idx).slice(row, 1))
             .collect();
         self.get_row_converter()?
             .convert_columns(&columns)
@@ -220,3 +400,19 @@ impl BufferedInput {
         idx
     }
 }
+
+/// Records spilled to disk.
+struct SpilledBatches {
+    file: SpillFile,
+    /// Indexes of the interpolated columns with non-null values in the records.
+    non_null_cols: HashSet<usize>,
+}
+
+/// Remove the columns with non-null values in `batch` from `offset` on from `cols`.
+fn remove_non_null_cols(cols: &mut HashSet<usize>, batch: &RecordBatch, offset: usize) {
+    cols.retain(|col_offset| {
+        let array = batch.column(*col_offset);
+        let array = array.slice(offset, array.len() - offset);
+        array.null_count() == array.len()
+    });
+}
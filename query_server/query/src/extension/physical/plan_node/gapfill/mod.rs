@@ -25,6 +25,7 @@ use datafusion::physical_plan::{
 
 use self::stream::GapFillStream;
 use crate::extension::logical::plan_node::gapfill::FillStrategy;
+use crate::extension::physical::spill::Spiller;
 use crate::extension::utils::{try_map_bound, try_map_range};
 
 /// A physical node for the gap-fill operation.
@@ -207,12 +208,20 @@ impl ExecutionPlan for GapFillExec {
         let output_batch_size = context.session_config().batch_size();
         let reservation = MemoryConsumer::new(format!("GapFillExec[{partition}]"))
             .register(context.memory_pool());
+        let spiller = Spiller::new(
+            format!("GapFillExec[{partition}]"),
+            self.schema(),
+            &context,
+            &self.metrics,
+            partition,
+        );
         let input_stream = self.input.execute(partition, context)?;
         Ok(Box::pin(GapFillStream::try_new(
             self,
             output_batch_size,
             input_stream,
             reservation,
+            spiller,
             baseline_metrics,
         )?))
     }
@@ -20,12 +20,14 @@ use super::algo::GapFiller;
 use super::buffered_input::BufferedInput;
 use super::params::GapFillParams;
 use super::GapFillExec;
+use crate::extension::physical::spill::Spiller;
 
 /// An implementation of a gap-filling operator that uses the [Stream] trait.
 ///
 /// This type takes responsibility for:
 /// - Reading input record batches
 /// - Accounting for memory
+/// - Spilling buffered input to disk when out of memory
 /// - Extracting arrays for processing by [`GapFiller`]
 /// - Recording metrics
 /// - Sending record batches to next operator (by implementing [`Self::poll_next'])
@@ -52,6 +54,8 @@ pub(super) struct GapFillStream {
     more_input: bool,
     /// For tracking memory.
     reservation: MemoryReservation,
+    /// For spilling buffered input.
+    spiller: Spiller,
     /// Baseline metrics.
     baseline_metrics: BaselineMetrics,
 }
@@ -63,6 +67,7 @@ impl GapFillStream {
         batch_size: usize,
         input: SendableRecordBatchStream,
         reservation: MemoryReservation,
+        spiller: Spiller,
         metrics: BaselineMetrics,
     ) -> Result<Self> {
         let schema = exec.schema();
@@ -101,6 +106,7 @@ impl GapFillStream {
             gap_filler,
             more_input: true,
             reservation,
+            spiller,
             baseline_metrics: metrics,
         })
     }
@@ -123,11 +129,12 @@ impl Stream for GapFillStream {
         cx: &mut Context<'_>,
     ) -> Poll<Option<Result<RecordBatch>>> {
         let last_output_row_offset = self.gap_filler.last_output_row_offset();
+        let unspilled_size = self.buffered_input.unspill(last_output_row_offset)?;
+        self.reservation.try_grow(unspilled_size)?;
         while self.more_input && self.buffered_input.need_more(last_output_row_offset)? {
             match ready!(self.input.poll_next_unpin(cx)) {
                 Some(Ok(batch)) => {
-                    self.reservation.try_grow(batch.get_array_memory_size())?;
-                    self.buffered_input.push(batch);
+                    self.buffer_input(batch, last_output_row_offset)?;
                 }
                 Some(Err(e)) => {
                     return Poll::Ready(Some(Err(e)));
@@ -156,7 +163,9 @@ impl Stream for GapFillStream {
 
         match self.process(input_batch) {
             Ok((output_batch, remaining_input_batch)) => {
-                self.buffered_input.push(remaining_input_batch);
+                if let Err(e) = self.buffered_input.push_remaining(remaining_input_batch) {
+                    return Poll::Ready(Some(Err(e)));
+                }
 
                 self.reservation
                     .shrink(output_batch.get_array_memory_size());
@@ -168,9 +177,29 @@ impl Stream for GapFillStream {
 }
 
 impl GapFillStream {
+    /// Buffer a record batch from the input, spilling buffered input to disk
+    /// if there is not enough memory.
+    fn buffer_input(&mut self, batch: RecordBatch, last_output_row_offset: usize) -> Result<()> {
+        let size = batch.get_array_memory_size();
+        if self.reservation.try_grow(size).is_err() {
+            let spilled_size = self
+                .buffered_input
+                .spill(last_output_row_offset, &self.spiller)?;
+            self.reservation
+                .shrink(spilled_size.min(self.reservation.size()));
+            self.reservation.try_grow(size)?;
+        }
+        self.buffered_input.push(batch);
+        Ok(())
+    }
+
     /// If any buffered input batches are present, concatenates it all together
     /// and returns an owned batch to the caller, leaving `self.buffered_input_batches` empty.
+    ///
+    /// Spilled input not needed for the next output batch is left out.
     fn take_buffered_input(&mut self) -> Result<Option<RecordBatch>> {
+        let unspilled_size = self.buffered_input.prepare_take()?;
+        self.reservation.try_grow(unspilled_size)?;
         let batches = self.buffered_input.take();
         if batches.is_empty() {
             return Ok(None);
@@ -189,7 +218,8 @@ impl GapFillStream {
         //     batch = optimize_dictionaries(&batch).map_err(DataFusionError::ArrowError)?;
         // }
 
-        self.reservation.shrink(old_size);
+        self.reservation
+            .shrink(old_size.min(self.reservation.size()));
         Ok(Some(batch))
     }
 
@@ -3,6 +3,7 @@ use std::fmt::{self, Display, Formatter};
 use std::pin::Pin;
 use std::sync::Arc;
 use std::task::{Context, Poll};
+use std::time::Duration;
 
 use coordinator::service::CoordinatorRef;
 use coordinator::SendableCoordinatorRecordBatchStream;
@@ -22,6 +23,7 @@ use models::predicate::domain::PredicateRef;
 use models::predicate::PlacedSplit;
 use models::schema::{TskvTableSchema, TskvTableSchemaRef};
 use snafu::ResultExt;
+use spi::query::config::StaleReadMaxLag;
 use spi::{CommonSnafu, CoordinatorSnafu, QueryError};
 use trace::span_ext::SpanExt;
 use trace::{debug, Span, SpanContext};
@@ -125,6 +127,10 @@ impl ExecutionPlan for TagScanExec {
 
         let metrics = TableScanMetrics::new(&self.metrics, partition);
         let span_ctx = context.session_config().get_extension::<SpanContext>();
+        let stale_read_max_lag = context
+            .session_config()
+            .get_extension::<StaleReadMaxLag>()
+            .map(|lag| lag.0);
 
         let tag_scan_stream = TagScanStream::new(
             self.table_schema.clone(),
@@ -132,6 +138,7 @@ impl ExecutionPlan for TagScanExec {
             self.coord.clone(),
             split,
             batch_size,
+            stale_read_max_lag,
             metrics,
             Span::from_context(format!("TagScanStream ({partition})"), span_ctx.as_deref()),
         )
@@ -228,12 +235,14 @@ pub struct TagScanStream {
 }
 
 impl TagScanStream {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         table_schema: TskvTableSchemaRef,
         proj_schema: SchemaRef,
         coord: CoordinatorRef,
         split: PlacedSplit,
         batch_size: usize,
+        stale_read_max_lag: Option<Duration>,
         metrics: TableScanMetrics,
         span: Span,
     ) -> Result<Self, QueryError> {
@@ -264,7 +273,8 @@ impl TagScanStream {
             None,
             proj_schema.clone(),
             proj_table_schema.into(),
-        );
+        )
+        .with_stale_read_max_lag(stale_read_max_lag);
 
         let span_ctx = span.context();
         let stream = coord
@@ -42,6 +42,10 @@ impl TracedProxyExec {
     pub fn name(&self) -> &str {
         &self.name
     }
+
+    pub fn inner(&self) -> &Arc<dyn ExecutionPlan> {
+        &self.inner
+    }
 }
 
 impl ExecutionPlan for TracedProxyExec {
@@ -2,6 +2,7 @@ use std::any::Any;
 use std::fmt::{self, Display, Formatter};
 use std::sync::Arc;
 use std::task::Poll;
+use std::time::Duration;
 
 use coordinator::service::CoordinatorRef;
 use coordinator::SendableCoordinatorRecordBatchStream;
@@ -22,9 +23,11 @@ use models::predicate::domain::PredicateRef;
 use models::predicate::PlacedSplit;
 use models::schema::{ColumnType, TableColumn, TskvTableSchema, TskvTableSchemaRef, TIME_FIELD};
 use snafu::ResultExt;
+use spi::query::config::StaleReadMaxLag;
 use spi::{CommonSnafu, CoordinatorSnafu, QueryResult};
 use trace::span_ext::SpanExt;
 use trace::{debug, Span, SpanContext};
+use tskv::reader::pushdown::PushedPlan;
 use tskv::reader::QueryOption;
 
 use crate::extension::physical::plan_node::TableScanMetrics;
@@ -38,6 +41,9 @@ pub struct TskvExec {
     filter: PredicateRef,
     coord: CoordinatorRef,
     splits: Vec<PlacedSplit>,
+    /// Plan executed on the scanned record batches of each split,
+    /// where the vnode is located.
+    pushed_plan: Option<PushedPlan>,
 
     /// Execution metrics
     metrics: ExecutionPlanMetricsSet,
@@ -59,12 +65,39 @@ impl TskvExec {
             filter,
             coord,
             splits,
+            pushed_plan: None,
             metrics,
         }
     }
+
     pub fn filter(&self) -> PredicateRef {
         self.filter.clone()
     }
+
+    /// Schema of the scanned record batches, before executing the pushed plan.
+    pub fn scan_schema(&self) -> SchemaRef {
+        self.proj_schema.clone()
+    }
+
+    pub fn table_schema(&self) -> &TskvTableSchemaRef {
+        &self.table_schema
+    }
+
+    pub fn splits(&self) -> &[PlacedSplit] {
+        &self.splits
+    }
+
+    pub fn pushed_plan(&self) -> Option<&PushedPlan> {
+        self.pushed_plan.as_ref()
+    }
+
+    pub fn with_pushed_plan(&self, pushed_plan: PushedPlan) -> Self {
+        Self {
+            pushed_plan: Some(pushed_plan),
+            metrics: ExecutionPlanMetricsSet::new(),
+            ..self.clone()
+        }
+    }
 }
 
 impl ExecutionPlan for TskvExec {
@@ -73,7 +106,10 @@ impl ExecutionPlan for TskvExec {
     }
 
     fn schema(&self) -> SchemaRef {
-        self.proj_schema.clone()
+        match self.pushed_plan {
+            Some(ref plan) => plan.schema(),
+            None => self.proj_schema.clone(),
+        }
     }
 
     fn output_partitioning(&self) -> Partitioning {
@@ -98,6 +134,7 @@ impl ExecutionPlan for TskvExec {
             filter: self.filter.clone(),
             coord: self.coord.clone(),
             splits: self.splits.clone(),
+            pushed_plan: self.pushed_plan.clone(),
             metrics: self.metrics.clone(),
         }))
     }
@@ -126,13 +163,19 @@ impl ExecutionPlan for TskvExec {
         let metrics = TableScanMetrics::new(&self.metrics, partition);
 
         let span_ctx = context.session_config().get_extension::<SpanContext>();
+        let stale_read_max_lag = context
+            .session_config()
+            .get_extension::<StaleReadMaxLag>()
+            .map(|lag| lag.0);
 
         let table_stream = TableScanStream::new(
             self.table_schema.clone(),
-            self.schema(),
+            self.proj_schema.clone(),
+            self.pushed_plan.clone(),
             self.coord.clone(),
             split,
             batch_size,
+            stale_read_max_lag,
             metrics,
             Span::from_context(
                 format!("TableScanStream ({partition})"),
@@ -160,7 +203,11 @@ impl ExecutionPlan for TskvExec {
                     PredicateDisplay(&filter),
                     self.splits.len(),
                     fields.join(","),
-                )
+                )?;
+                if let Some(ref plan) = self.pushed_plan {
+                    write!(f, ", pushed_plan=[{}]", plan)?;
+                }
+                Ok(())
             }
         }
     }
@@ -182,6 +229,7 @@ impl std::fmt::Debug for TskvExec {
             .field("proj_schema", &self.proj_schema)
             .field("filter", &self.filter)
             .field("splits", &self.splits)
+            .field("pushed_plan", &self.pushed_plan)
             .finish()
     }
 }
@@ -204,6 +252,7 @@ impl<'a> Display for PredicateDisplay<'a> {
 
 #[allow(dead_code)]
 pub struct TableScanStream {
+    /// Schema of the stream, it's the output schema of the pushed plan if exists.
     proj_schema: SchemaRef,
     batch_size: usize,
     coord: CoordinatorRef,
@@ -217,12 +266,15 @@ pub struct TableScanStream {
 }
 
 impl TableScanStream {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         table_schema: TskvTableSchemaRef,
         proj_schema: SchemaRef,
+        pushed_plan: Option<PushedPlan>,
         coord: CoordinatorRef,
         split: PlacedSplit,
         batch_size: usize,
+        stale_read_max_lag: Option<Duration>,
         metrics: TableScanMetrics,
         span: Span,
     ) -> QueryResult<Self> {
@@ -264,14 +316,20 @@ impl TableScanStream {
         );
 
         let remain = split.limit();
+        let schema = match pushed_plan {
+            Some(ref plan) => plan.schema(),
+            None => proj_schema.clone(),
+        };
 
         let option = QueryOption::new(
             batch_size,
             split,
             None,
-            proj_schema.clone(),
+            proj_schema,
             proj_table_schema.into(),
-        );
+        )
+        .with_pushed_plan(pushed_plan)
+        .with_stale_read_max_lag(stale_read_max_lag);
 
         let span_ctx = span.context();
         let iterator = coord
@@ -279,7 +337,7 @@ impl TableScanStream {
             .context(CoordinatorSnafu)?;
 
         Ok(Self {
-            proj_schema,
+            proj_schema: schema,
             batch_size,
             coord,
             remain,
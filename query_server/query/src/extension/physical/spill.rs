@@ -0,0 +1,198 @@
+//! Spilling record batches of an operator to temp files when it runs out of memory.
+//!
+//! Used by gapfill. Sorts spill by DataFusion itself, which doesn't account the temp space
+//! of a query, hash joins of DataFusion don't spill at all. Hash aggregations don't spill
+//! either, they are planned again over sorted input when they run out of memory, see
+//! [`SortAggregateInput`](crate::extension::physical::optimizer_rule::sort_aggregate_input::SortAggregateInput).
+
+use std::fs::File;
+use std::io::{BufReader, BufWriter};
+use std::sync::Arc;
+
+use datafusion::arrow::datatypes::SchemaRef;
+use datafusion::arrow::ipc::reader::FileReader;
+use datafusion::arrow::ipc::writer::FileWriter;
+use datafusion::arrow::record_batch::RecordBatch;
+use datafusion::error::{DataFusionError, Result};
+use datafusion::execution::context::TaskContext;
+use datafusion::execution::disk_manager::DiskManager;
+use datafusion::physical_plan::metrics::{Count, ExecutionPlanMetricsSet, MetricBuilder};
+use spi::query::session::QueryTempSpace;
+use tempfile::NamedTempFile;
+use trace::debug;
+
+/// Writes record batches of an operator partition to temp files of the [`DiskManager`].
+///
+/// The size of the temp files is accounted in the [`QueryTempSpace`] of the query.
+pub struct Spiller {
+    name: String,
+    schema: SchemaRef,
+    disk_manager: Arc<DiskManager>,
+    temp_space: Option<Arc<QueryTempSpace>>,
+    metrics: SpillMetrics,
+}
+
+impl Spiller {
+    pub fn new(
+        name: impl Into<String>,
+        schema: SchemaRef,
+        context: &TaskContext,
+        metrics: &ExecutionPlanMetricsSet,
+        partition: usize,
+    ) -> Self {
+        Self {
+            name: name.into(),
+            schema,
+            disk_manager: context.runtime_env().disk_manager.clone(),
+            temp_space: context.session_config().get_extension::<QueryTempSpace>(),
+            metrics: SpillMetrics::new(metrics, partition),
+        }
+    }
+
+    /// Write `batches` to a new temp file.
+    ///
+    /// Errors with [`DataFusionError::ResourcesExhausted`] if the query would exceed its temp space.
+    pub fn spill(&self, batches: &[RecordBatch]) -> Result<SpillFile> {
+        let file = self.disk_manager.create_tmp_file(&self.name)?;
+
+        let mut num_rows = 0;
+        let mut writer = FileWriter::try_new(BufWriter::new(file.reopen()?), &self.schema)?;
+        for batch in batches {
+            num_rows += batch.num_rows();
+            writer.write(batch)?;
+        }
+        writer.finish()?;
+        drop(writer);
+
+        let size = file.as_file().metadata()?.len();
+        if let Some(temp_space) = &self.temp_space {
+            if !temp_space.try_grow(size) {
+                return Err(DataFusionError::ResourcesExhausted(format!(
+                    "Failed to spill {size} bytes for {}, the query has used {} of {} bytes temp space",
+                    self.name,
+                    temp_space.used(),
+                    temp_space.limit(),
+                )));
+            }
+        }
+
+        debug!(
+            "{} spilled {} rows, {} bytes to {:?}",
+            self.name,
+            num_rows,
+            size,
+            file.path()
+        );
+        self.metrics.record_spill(num_rows, size as usize);
+
+        Ok(SpillFile {
+            file,
+            size,
+            num_rows,
+            temp_space: self.temp_space.clone(),
+        })
+    }
+}
+
+/// A temp file of spilled record batches, deleted on drop
+pub struct SpillFile {
+    file: NamedTempFile,
+    size: u64,
+    num_rows: usize,
+    temp_space: Option<Arc<QueryTempSpace>>,
+}
+
+impl SpillFile {
+    pub fn num_rows(&self) -> usize {
+        self.num_rows
+    }
+
+    /// Read all spilled record batches back into memory
+    pub fn read(&self) -> Result<Vec<RecordBatch>> {
+        let reader = FileReader::try_new(BufReader::new(File::open(self.file.path())?), None)?;
+        reader
+            .map(|batch| batch.map_err(DataFusionError::ArrowError))
+            .collect()
+    }
+}
+
+impl Drop for SpillFile {
+    fn drop(&mut self) {
+        if let Some(temp_space) = &self.temp_space {
+            temp_space.shrink(self.size);
+        }
+    }
+}
+
+/// Stores metrics about spilling of an operator.
+#[derive(Debug, Clone)]
+pub struct SpillMetrics {
+    /// Number of times the operator spilled
+    spill_count: Count,
+    /// Total bytes of the temp files
+    spilled_bytes: Count,
+    /// Total number of rows spilled
+    spilled_rows: Count,
+}
+
+impl SpillMetrics {
+    pub fn new(metrics: &ExecutionPlanMetricsSet, partition: usize) -> Self {
+        Self {
+            spill_count: MetricBuilder::new(metrics).spill_count(partition),
+            spilled_bytes: MetricBuilder::new(metrics).spilled_bytes(partition),
+            spilled_rows: MetricBuilder::new(metrics).counter("spilled_rows", partition),
+        }
+    }
+
+    fn record_spill(&self, num_rows: usize, bytes: usize) {
+        self.spill_count.add(1);
+        self.spilled_bytes.add(bytes);
+        self.spilled_rows.add(num_rows);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use datafusion::arrow::array::Int64Array;
+    use datafusion::arrow::datatypes::{DataType, Field, Schema};
+    use datafusion::arrow::record_batch::RecordBatch;
+    use datafusion::error::DataFusionError;
+    use datafusion::execution::context::SessionContext;
+    use datafusion::physical_plan::metrics::ExecutionPlanMetricsSet;
+    use datafusion::prelude::SessionConfig;
+    use spi::query::session::QueryTempSpace;
+
+    use super::Spiller;
+
+    #[test]
+    fn test_spill() {
+        let schema = Arc::new(Schema::new(vec![Field::new("a", DataType::Int64, false)]));
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![Arc::new(Int64Array::from_iter_values(0..1024))],
+        )
+        .unwrap();
+
+        let temp_space = Arc::new(QueryTempSpace::new(64 * 1024));
+        let config = SessionConfig::new().with_extension(temp_space.clone());
+        let context = SessionContext::with_config(config).task_ctx();
+        let metrics = ExecutionPlanMetricsSet::new();
+        let spiller = Spiller::new("test", schema, &context, &metrics, 0);
+
+        let file = spiller.spill(&[batch.clone(), batch.clone()]).unwrap();
+        assert_eq!(file.num_rows(), 2048);
+        assert!(temp_space.used() > 0);
+        assert_eq!(file.read().unwrap(), vec![batch.clone(), batch.clone()]);
+
+        let batches = vec![batch; 8];
+        assert!(matches!(
+            spiller.spill(&batches),
+            Err(DataFusionError::ResourcesExhausted(_))
+        ));
+
+        drop(file);
+        assert_eq!(temp_space.used(), 0);
+    }
+}
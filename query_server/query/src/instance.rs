@@ -1,7 +1,9 @@
+use std::path::Path;
 use std::sync::Arc;
 
 use async_trait::async_trait;
 use coordinator::service::CoordinatorRef;
+use datafusion::execution::disk_manager::{DiskManager, DiskManagerConfig};
 use derive_builder::Builder;
 use memory_pool::MemoryPoolRef;
 use meta::error::MetaError;
@@ -19,17 +21,19 @@ use spi::query::logical_planner::Plan;
 use spi::query::session::SessionCtxFactory;
 use spi::server::dbms::DatabaseManagerSystem;
 use spi::service::protocol::{Query, QueryHandle, QueryId};
-use spi::{AuthSnafu, MetaSnafu, QueryResult};
-use trace::{debug, SpanContext};
+use spi::{AuthSnafu, MetaSnafu, QueryResult, StdIoSnafu};
+use trace::{debug, error, SpanContext};
 use tskv::kv_option::Options;
 
 use crate::auth::auth_control::{AccessControlImpl, AccessControlNoCheck};
 use crate::data_source::split::SplitManager;
 use crate::data_source::stream::tskv::factory::{TskvStreamProviderFactory, TSKV_STREAM_PROVIDER};
+use crate::dispatcher::audit::{audit_retention_service, AuditEvent, AuditLogger, AuditLoggerRef};
 use crate::dispatcher::manager::SimpleQueryDispatcherBuilder;
 use crate::dispatcher::persister::LocalQueryPersister;
 use crate::dispatcher::query_tracker::QueryTracker;
 use crate::execution::factory::SqlQueryExecutionFactory;
+use crate::execution::result_cache::{ResultCache, ResultCacheRef};
 use crate::execution::scheduler::local::LocalScheduler;
 use crate::extension::expr::{load_all_functions, register_session_udfs};
 use crate::extension::variable::load_all_system_vars;
@@ -41,6 +45,7 @@ use crate::variable::simple_sys_var_manager::SimpleSystemVarManager;
 
 pub const DEFAULT_CNOSDB_PATH: &str = ".cnosdb";
 pub const DEFAULT_CNOSDB_QUERY_DIRECTORY_NAME: &str = "query";
+pub const SPILL_DIRECTORY_NAME: &str = "spill";
 
 #[derive(Builder)]
 pub struct Cnosdbms<D: QueryDispatcher> {
@@ -48,6 +53,8 @@ pub struct Cnosdbms<D: QueryDispatcher> {
     access_control: AccessControlRef,
     // query dispatcher & query execution
     query_dispatcher: D,
+    // record authentications, None if audit is disabled
+    audit_logger: Option<AuditLoggerRef>,
 }
 
 #[async_trait]
@@ -59,11 +66,25 @@ where
         self.query_dispatcher.start().await
     }
 
-    async fn authenticate(&self, user_info: &UserInfo, tenant_name: &str) -> QueryResult<User> {
-        self.access_control
+    async fn authenticate(
+        &self,
+        user_info: &UserInfo,
+        tenant_name: &str,
+        client_addr: Option<&str>,
+    ) -> QueryResult<User> {
+        let result = self
+            .access_control
             .access_check(user_info, tenant_name)
-            .await
-            .context(AuthSnafu)
+            .await;
+        if let Some(audit_logger) = &self.audit_logger {
+            let error = result.as_ref().err().map(|err| err.to_string());
+            let event = AuditEvent::auth(&user_info.user, tenant_name, client_addr, error);
+            if let Err(err) = audit_logger.log(event) {
+                error!("Failed to record audit event: {}", err);
+            }
+        }
+
+        result.context(AuthSnafu)
     }
 
     async fn execute(
@@ -181,12 +202,19 @@ pub async fn make_cnosdbms(
     load_all_system_vars(&mut var_manager, coord.clone())?;
 
     let split_manager = Arc::new(SplitManager::new(coord.clone()));
+    // operators spill to temp files under the data directory when out of memory
+    let spill_dir = Path::new(&coord.get_config().storage.path).join(SPILL_DIRECTORY_NAME);
+    std::fs::create_dir_all(&spill_dir).context(StdIoSnafu)?;
+    let disk_manager = DiskManager::try_new(DiskManagerConfig::NewSpecified(vec![spill_dir]))?;
     // TODO session config need load global system config
-    let session_factory = Arc::new(SessionCtxFactory::new(
-        Some(Arc::new(var_manager)),
-        query_dedicated_hidden_dir.clone(),
-        Some(register_session_udfs),
-    ));
+    let session_factory = Arc::new(
+        SessionCtxFactory::new(
+            Some(Arc::new(var_manager)),
+            query_dedicated_hidden_dir.clone(),
+            Some(register_session_udfs),
+        )
+        .with_disk_manager(disk_manager),
+    );
     let parser = Arc::new(DefaultParser::default());
     let optimizer = Arc::new(CascadeOptimizerBuilder::default().build());
     // TODO wrap, and num_threads configurable
@@ -219,12 +247,20 @@ pub async fn make_cnosdbms(
         coord.clone(),
     ));
 
+    let query_config = coord.get_config().query;
+    let result_cache: Option<ResultCacheRef> = if query_config.result_cache_enabled {
+        Some(Arc::new(ResultCache::new(&query_config)))
+    } else {
+        None
+    };
+
     let query_execution_factory = Arc::new(SqlQueryExecutionFactory::new(
         optimizer,
         scheduler,
         query_tracker.clone(),
         Arc::new(stream_checker_manager),
         options.query.clone(),
+        result_cache.clone(),
     ));
 
     let meta_manager = coord.meta_manager();
@@ -246,6 +282,21 @@ pub async fn make_cnosdbms(
         stream_provider_manager.clone(),
     ));
 
+    let audit_config = coord.get_config().audit;
+    let audit_logger: Option<AuditLoggerRef> = if audit_config.enable {
+        let audit_logger =
+            Arc::new(AuditLogger::try_new(coord.clone(), &audit_config).context(StdIoSnafu)?);
+        tokio::spawn(audit_logger.clone().run());
+        tokio::spawn(audit_retention_service(
+            coord.clone(),
+            audit_config.retention,
+            audit_config.retention_check_interval,
+        ));
+        Some(audit_logger)
+    } else {
+        None
+    };
+
     let query_dispatcher = SimpleQueryDispatcherBuilder::default()
         .with_coord(coord)
         .with_default_table_provider(default_table_provider)
@@ -257,6 +308,8 @@ pub async fn make_cnosdbms(
         .with_query_tracker(query_tracker)
         .with_func_manager(Arc::new(func_manager))
         .with_stream_provider_manager(stream_provider_manager)
+        .with_audit_logger(audit_logger.clone())
+        .with_result_cache(result_cache)
         .build()?;
 
     let mut builder = CnosdbmsBuilder::default();
@@ -272,6 +325,7 @@ pub async fn make_cnosdbms(
 
     let db_server = builder
         .query_dispatcher(query_dispatcher)
+        .audit_logger(audit_logger)
         .build()
         .expect("build db server");
 
@@ -322,7 +376,7 @@ mod tests {
         };
 
         let user = db
-            .authenticate(&user, DEFAULT_CATALOG)
+            .authenticate(&user, DEFAULT_CATALOG, None)
             .await
             .expect("authenticate");
 
@@ -10,6 +10,7 @@ use spi::query::datasource::stream::StreamProviderManagerRef;
 
 use super::TableHandleProvider;
 use crate::data_source::batch::tskv::ClusterTable;
+use crate::data_source::file_format::external_table_options;
 use crate::data_source::split::SplitManagerRef;
 use crate::data_source::table_source::TableHandle;
 
@@ -75,7 +76,7 @@ impl TableHandleProvider for BaseTableProvider {
                 .into(),
                 TableSchema::ExternalTableSchema(schema) => {
                     let table_path = ListingTableUrl::parse(&schema.location)?;
-                    let options = schema.table_options()?;
+                    let options = external_table_options(&schema)?;
                     let config = ListingTableConfig::new(table_path)
                         .with_listing_options(options)
                         .with_schema(Arc::new(schema.schema.clone()));
@@ -86,6 +87,12 @@ impl TableHandleProvider for BaseTableProvider {
                     .create_provider(self.meta_client.clone(), table.as_ref())
                     .map_err(|e| DataFusionError::External(Box::new(e)))?
                     .into(),
+                TableSchema::ViewSchema(view) => {
+                    return Err(DataFusionError::Plan(format!(
+                        "{}.{} is a view, it can not be used as a table",
+                        view.db, view.name
+                    )));
+                }
             },
             None => {
                 return Err(DataFusionError::Plan(format!(
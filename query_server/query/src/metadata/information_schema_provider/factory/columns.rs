@@ -15,7 +15,7 @@ use meta::model::MetaClientRef;
 use models::auth::user::User;
 use models::oid::Identifier;
 use models::schema::{
-    ColumnType, ExternalTableSchema, StreamTable, TableSchema, TskvTableSchemaRef,
+    ColumnType, ExternalTableSchema, StreamTable, TableSchema, TskvTableSchemaRef, ViewSchema,
 };
 use models::ValueType;
 
@@ -117,6 +117,9 @@ impl TableProvider for InformationColumnsTable {
                         TableSchema::StreamTableSchema(t) => {
                             append_stream_table(tenant_name, &db, t.clone(), &mut builder);
                         }
+                        TableSchema::ViewSchema(t) => {
+                            append_view(tenant_name, &db, t.clone(), &mut builder);
+                        }
                     }
                 }
             }
@@ -198,3 +201,26 @@ fn append_stream_table(
         );
     }
 }
+
+fn append_view(
+    tenant_name: &str,
+    database_name: &str,
+    view: Arc<ViewSchema>,
+    builder: &mut InformationSchemaColumnsBuilder,
+) {
+    for (idx, col) in view.schema.all_fields().iter().enumerate() {
+        builder.append_row(
+            tenant_name,
+            database_name,
+            &view.name,
+            col.name(),
+            // The columns of a view are all type FIELD
+            ColumnType::Field(ValueType::Unknown).as_column_type_str(),
+            idx as u64,
+            "NULL",
+            col.is_nullable(),
+            col.data_type().to_string(),
+            None::<String>,
+        );
+    }
+}
@@ -91,7 +91,8 @@ impl TableProvider for InformationQueriesTable {
 
         let user_id = *self.user.desc().id();
         let tenant_id = *self.metadata.tenant().id();
-        let all_queries = self.query_tracker.running_queries();
+        let mut all_queries = self.query_tracker.running_queries();
+        all_queries.extend(self.query_tracker.queued_queries());
 
         let running_queries = filter_running_queries(user_id, tenant_id, &self.user, all_queries);
 
@@ -7,11 +7,13 @@ use coordinator::service::CoordinatorRef;
 use datafusion::arrow::datatypes::DataType;
 use datafusion::common::Result as DFResult;
 use datafusion::config::ConfigOptions;
+use datafusion::datasource::view::ViewTable;
 use datafusion::datasource::TableProvider;
 use datafusion::error::DataFusionError;
-use datafusion::logical_expr::{AggregateUDF, ScalarUDF, TableSource, WindowUDF};
+use datafusion::logical_expr::{AggregateUDF, LogicalPlan, ScalarUDF, TableSource, WindowUDF};
 use datafusion::physical_expr::var_provider::is_system_variables;
-use datafusion::sql::planner::ContextProvider;
+use datafusion::sql::planner::{ContextProvider, SqlToRel};
+use datafusion::sql::sqlparser::ast::Statement;
 use datafusion::sql::TableReference;
 use datafusion::variable::{VarProvider, VarType};
 pub use information_schema_provider::{
@@ -27,8 +29,11 @@ use meta::error::MetaError;
 use meta::model::MetaClientRef;
 use models::auth::user::UserDesc;
 use models::object_reference::{Resolve, ResolvedTable};
-use models::schema::{Precision, Tenant, DEFAULT_CATALOG, DEFAULT_DATABASE};
+use models::schema::{
+    MaterializedView, Precision, TableSchema, Tenant, DEFAULT_CATALOG, DEFAULT_DATABASE,
+};
 use parking_lot::RwLock;
+use spi::query::ast::ExtStatement;
 use spi::query::function::FuncMetaManagerRef;
 use spi::query::session::SessionCtx;
 
@@ -38,6 +43,7 @@ use self::information_schema_provider::InformationSchemaProvider;
 use crate::data_source::table_source::{TableHandle, TableSourceAdapter};
 use crate::dispatcher::query_tracker::QueryTracker;
 use crate::metadata::usage_schema_provider::UsageSchemaProvider;
+use crate::sql::parser::ExtParser;
 
 mod base_table;
 mod cluster_schema_provider;
@@ -69,6 +75,16 @@ pub trait ContextProviderExtension: ContextProvider {
     ) -> Result<(), MetaError> {
         Ok(())
     }
+    /// Called before planning the definition of a view,
+    /// unqualified table names in it are resolved against the view's database
+    fn enter_view(&self, _view: &ResolvedTable) -> DFResult<()> {
+        Ok(())
+    }
+    fn exit_view(&self) {}
+    /// The materialized views of `database` whose source is `table`
+    fn materialized_views(&self, _database: &str, _table: &str) -> DFResult<Vec<MaterializedView>> {
+        Ok(vec![])
+    }
 }
 
 pub type TableHandleProviderRef = Arc<dyn TableHandleProvider + Send + Sync>;
@@ -88,6 +104,7 @@ pub struct MetadataProvider {
     cluster_schema_provider: ClusterSchemaProvider,
     usage_schema_provider: UsageSchemaProvider,
     access_databases: RwLock<DatabaseSet>,
+    expanding_views: ExpandingViews,
     // tskv/external
     current_session_table_provider: TableHandleProviderRef,
 }
@@ -115,6 +132,7 @@ impl MetadataProvider {
             cluster_schema_provider: ClusterSchemaProvider::new(),
             usage_schema_provider: UsageSchemaProvider::new(default_table_provider),
             access_databases: Default::default(),
+            expanding_views: Default::default(),
         }
     }
 
@@ -168,6 +186,14 @@ impl MetadataProvider {
         Ok(None)
     }
 
+    /// Plan the definition of the view, the tables it reads are recorded in `access_databases`
+    fn build_view_handle(&self, name: &ResolvedTable, definition: &str) -> DFResult<TableHandle> {
+        let plan = plan_view_definition(self, name, definition)?;
+        let view: Arc<dyn TableProvider> =
+            Arc::new(ViewTable::try_new(plan, Some(definition.to_string()))?);
+        Ok(view.into())
+    }
+
     fn build_table_handle(&self, name: &ResolvedTable) -> datafusion::common::Result<TableHandle> {
         let tenant_name = name.tenant();
         let database_name = name.database();
@@ -179,6 +205,20 @@ impl MetadataProvider {
             return Ok(source.into());
         }
 
+        if let Some(TableSchema::ViewSchema(view)) = self
+            .meta_client
+            .get_table_schema(database_name, table_name)
+            .map_err(|e| DataFusionError::External(Box::new(e)))?
+        {
+            // the results of a materialized view are read from the table storing them
+            return match &view.materialized {
+                Some(materialized) => self
+                    .current_session_table_provider
+                    .build_table_handle(database_name, &materialized.table),
+                None => self.build_view_handle(name, &view.definition),
+            };
+        }
+
         self.current_session_table_provider
             .build_table_handle(database_name, table_name)
     }
@@ -234,9 +274,12 @@ impl ContextProviderExtension for MetadataProvider {
         &self,
         table_ref: TableReference,
     ) -> datafusion::common::Result<Arc<TableSourceAdapter>> {
+        let default_database = self
+            .expanding_views
+            .default_database(self.session.default_database());
         let name = table_ref
             .clone()
-            .resolve_object(self.session.tenant(), self.session.default_database())?;
+            .resolve_object(self.session.tenant(), &default_database)?;
 
         let table_name = name.table();
         let database_name = name.database();
@@ -294,6 +337,35 @@ impl ContextProviderExtension for MetadataProvider {
 
         Ok(())
     }
+
+    fn enter_view(&self, view: &ResolvedTable) -> DFResult<()> {
+        self.expanding_views.enter(view)
+    }
+
+    fn exit_view(&self) {
+        self.expanding_views.exit()
+    }
+
+    fn materialized_views(&self, database: &str, table: &str) -> DFResult<Vec<MaterializedView>> {
+        let db_info = match self
+            .meta_client
+            .get_db_info(database)
+            .map_err(|e| DataFusionError::External(Box::new(e)))?
+        {
+            Some(db_info) => db_info,
+            None => return Ok(vec![]),
+        };
+
+        Ok(db_info
+            .tables
+            .values()
+            .filter_map(|schema| match schema {
+                TableSchema::ViewSchema(view) => view.materialized.clone(),
+                _ => None,
+            })
+            .filter(|materialized| materialized.source == table)
+            .collect())
+    }
 }
 
 impl ContextProvider for MetadataProvider {
@@ -368,6 +440,66 @@ impl DatabaseSet {
     }
 }
 
+/// Views being expanded while planning a statement, innermost last
+#[derive(Default)]
+pub struct ExpandingViews {
+    views: RwLock<Vec<ResolvedTable>>,
+}
+
+impl ExpandingViews {
+    /// Unqualified table names are resolved against the database of the innermost view
+    pub fn default_database(&self, session_database: &str) -> String {
+        match self.views.read().last() {
+            Some(view) => view.database().to_string(),
+            None => session_database.to_string(),
+        }
+    }
+
+    pub fn enter(&self, view: &ResolvedTable) -> DFResult<()> {
+        let mut views = self.views.write();
+        if views.contains(view) {
+            return Err(DataFusionError::Plan(format!(
+                "View {} is defined recursively",
+                view
+            )));
+        }
+        views.push(view.clone());
+        Ok(())
+    }
+
+    pub fn exit(&self) {
+        self.views.write().pop();
+    }
+}
+
+/// Plan the definition of a view with the tables provided by `provider`
+pub fn plan_view_definition<S: ContextProviderExtension>(
+    provider: &S,
+    name: &ResolvedTable,
+    definition: &str,
+) -> DFResult<LogicalPlan> {
+    let statement = ExtParser::parse_sql(definition)
+        .map_err(|e| DataFusionError::External(Box::new(e)))?
+        .pop_front();
+    let query = match statement {
+        Some(ExtStatement::SqlStatement(stmt)) if matches!(stmt.as_ref(), Statement::Query(_)) => {
+            *stmt
+        }
+        _ => {
+            return Err(DataFusionError::Plan(format!(
+                "Invalid definition of view {}: {}",
+                name, definition
+            )))
+        }
+    };
+
+    provider.enter_view(name)?;
+    let plan = SqlToRel::new(provider).sql_statement_to_plan(query);
+    provider.exit_view();
+
+    plan
+}
+
 #[derive(Default, Clone)]
 pub struct TableSet {
     tables: HashSet<String>,
@@ -25,11 +25,11 @@ pub struct CascadeOptimizer {
 
 #[async_trait]
 impl Optimizer for CascadeOptimizer {
-    async fn optimize(
+    fn optimize_logical_plan(
         &self,
         plan: &LogicalPlan,
         session: &SessionCtx,
-    ) -> QueryResult<Arc<dyn ExecutionPlan>> {
+    ) -> QueryResult<LogicalPlan> {
         debug!("Original logical plan:\n{}\n", plan.display_indent_schema(),);
 
         let optimized_logical_plan = self.logical_optimizer.optimize(plan, session)?;
@@ -39,11 +39,19 @@ impl Optimizer for CascadeOptimizer {
             optimized_logical_plan.display_indent_schema(),
         );
 
+        Ok(optimized_logical_plan)
+    }
+
+    async fn create_physical_plan(
+        &self,
+        optimized_logical_plan: &LogicalPlan,
+        session: &SessionCtx,
+    ) -> QueryResult<Arc<dyn ExecutionPlan>> {
         let physical_plan = {
             let mut span = session.get_child_span("logical plan to physical plan");
 
             self.physical_planner
-                .create_physical_plan(&optimized_logical_plan, session)
+                .create_physical_plan(optimized_logical_plan, session)
                 .await
                 .map(|p| {
                     span.ok("complete physical plan creation");
@@ -158,6 +166,7 @@ mod test {
 
     use coordinator::service_mock::{MockCoordinator, WITH_NONEMPTY_DATABASE_FOR_TEST};
     use datafusion::arrow::datatypes::TimeUnit;
+    use datafusion::config::ConfigOptions;
     use datafusion::datasource::provider_as_source;
     use datafusion::error::Result;
     use datafusion::execution::context::SessionState;
@@ -165,15 +174,17 @@ mod test {
     use datafusion::logical_expr::{LogicalPlan, LogicalPlanBuilder, UNNAMED_TABLE};
     use datafusion::optimizer::optimizer::Optimizer;
     use datafusion::optimizer::{OptimizerContext, OptimizerRule};
+    use datafusion::physical_optimizer::PhysicalOptimizerRule;
     use datafusion::physical_plan::displayable;
     use datafusion::physical_planner::{DefaultPhysicalPlanner, PhysicalPlanner};
-    use datafusion::prelude::{col, count, max, min, sum, Expr, SessionConfig};
+    use datafusion::prelude::{col, count, lit, max, min, sum, Expr, SessionConfig};
     use meta::model::meta_tenant::TenantMeta;
     use models::schema::{ColumnType, TableColumn, TskvTableSchema};
     use models::ValueType;
 
     use crate::data_source::batch::tskv::ClusterTable;
     use crate::data_source::split;
+    use crate::extension::physical::optimizer_rule::push_down_aggregate::PushDownPartialAggregate;
 
     fn observe(_plan: &LogicalPlan, _rule: &dyn OptimizerRule) {}
 
@@ -590,4 +601,76 @@ mod test {
             \n",
         ).await
     }
+
+    #[tokio::test]
+    async fn test_push_down_partial_aggregate() -> Result<()> {
+        let plan = LogicalPlanBuilder::from(test_table_scan(true)?)
+            .aggregate(Vec::<Expr>::new(), vec![count(col("value"))])?
+            .build()?;
+        let plan = optimize_plan(&plan)?;
+
+        let physical_plan = DefaultPhysicalPlanner::default()
+            .create_physical_plan(
+                &plan,
+                &SessionState::with_config_rt(
+                    SessionConfig::default().with_target_partitions(8),
+                    Arc::new(RuntimeEnv::default()),
+                ),
+            )
+            .await?;
+        let physical_plan =
+            PushDownPartialAggregate::new().optimize(physical_plan, &ConfigOptions::default())?;
+
+        assert_eq!(
+            "\
+            AggregateExec: mode=Final, gby=[], aggr=[COUNT(?table?.value)]\
+            \n  CoalescePartitionsExec\
+            \n    TskvExec: limit=None, predicate=ColumnDomains { column_to_domain: Some({}) }, filter=None, split_num=8, projection=[time,value], \
+            pushed_plan=[AggregateExec: mode=Partial, gby=[], aggr=[COUNT(?table?.value)] -> ProjectionExec: expr=[value@1 as value] -> ScanInputExec]\
+            \n",
+            displayable(physical_plan.as_ref()).indent(false).to_string()
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_push_down_limit() -> Result<()> {
+        let plan = LogicalPlanBuilder::from(test_table_scan(true)?)
+            .filter(col("value").gt(lit(1_i64)))?
+            .limit(0, Some(10))?
+            .build()?;
+        let plan = optimize_plan(&plan)?;
+
+        let physical_plan = DefaultPhysicalPlanner::default()
+            .create_physical_plan(
+                &plan,
+                &SessionState::with_config_rt(
+                    SessionConfig::default().with_target_partitions(8),
+                    Arc::new(RuntimeEnv::default()),
+                ),
+            )
+            .await?;
+        let physical_plan =
+            PushDownPartialAggregate::new().optimize(physical_plan, &ConfigOptions::default())?;
+
+        // The local limit is kept on the query node, with the filter pushed down.
+        let plan_str = displayable(physical_plan.as_ref())
+            .indent(false)
+            .to_string();
+        let lines = plan_str.lines().map(str::trim).collect::<Vec<_>>();
+        let limit = lines
+            .iter()
+            .position(|l| *l == "LocalLimitExec: fetch=10")
+            .expect(&plan_str);
+        assert!(lines[limit + 1].starts_with("TskvExec"), "{plan_str}");
+        assert!(
+            lines[limit + 1]
+                .contains("pushed_plan=[LocalLimitExec: fetch=10 -> FilterExec: value@"),
+            "{plan_str}"
+        );
+        assert_eq!(lines.len(), limit + 2, "{plan_str}");
+
+        Ok(())
+    }
 }
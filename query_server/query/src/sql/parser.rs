@@ -17,12 +17,13 @@ use models::meta_data::{NodeId, ReplicationSetId, VnodeId};
 use snafu::ResultExt;
 use spi::query::ast::{
     self, parse_string_value, Action, AlterDatabase, AlterTable, AlterTableAction, AlterTenant,
-    AlterTenantOperation, AlterUser, AlterUserOperation, ChecksumGroup, ColumnOption, CompactVnode,
-    CopyIntoLocation, CopyIntoTable, CopyTarget, CopyVnode, CreateDatabase, CreateRole,
-    CreateStream, CreateTable, CreateTenant, CreateUser, DatabaseOptions, DescribeDatabase,
-    DescribeTable, DropDatabaseObject, DropGlobalObject, DropTenantObject, DropVnode, Explain,
-    ExtStatement, GrantRevoke, MoveVnode, OutputMode, Privilege, RecoverDatabase, RecoverTenant,
-    ShowSeries, ShowTagBody, ShowTagValues, Trigger, UriLocation, With,
+    AlterTenantOperation, AlterUser, AlterUserOperation, BackupDatabase, ChecksumGroup,
+    ColumnOption, CompactVnode, CopyIntoLocation, CopyIntoTable, CopyTarget, CopyVnode,
+    CreateDatabase, CreateMaterializedView, CreateRole, CreateStream, CreateTable, CreateTenant,
+    CreateUser, CreateView, DatabaseOptions, DescribeDatabase, DescribeTable, DropDatabaseObject,
+    DropGlobalObject, DropTenantObject, DropVnode, Explain, ExtStatement, GrantRevoke, MoveVnode,
+    OutputMode, Privilege, RebuildIndex, RecoverDatabase, RecoverTenant, RestoreDatabase,
+    ShowCardinality, ShowSeries, ShowTagBody, ShowTagValues, Trigger, UriLocation, With,
 };
 use spi::query::logical_planner::{DatabaseObjectType, GlobalObjectType, TenantObjectType};
 use spi::query::parser::Parser as CnosdbParser;
@@ -58,6 +59,10 @@ enum CnosKeyWord {
     REPLICA,
     #[allow(non_camel_case_types, clippy::upper_case_acronyms)]
     PRECISION,
+    #[allow(non_camel_case_types, clippy::upper_case_acronyms)]
+    REPLICA_PLACEMENT,
+    #[allow(non_camel_case_types, clippy::upper_case_acronyms)]
+    COLD_AFTER,
 
     #[allow(non_camel_case_types, clippy::upper_case_acronyms)]
     QUERIES,
@@ -124,6 +129,30 @@ enum CnosKeyWord {
     DESTORY,
     #[allow(non_camel_case_types, clippy::upper_case_acronyms)]
     REPLICAS,
+    #[allow(non_camel_case_types, clippy::upper_case_acronyms)]
+    CARDINALITY,
+    #[allow(non_camel_case_types, clippy::upper_case_acronyms)]
+    REBUILD,
+    #[allow(non_camel_case_types, clippy::upper_case_acronyms)]
+    BACKUP,
+    #[allow(non_camel_case_types, clippy::upper_case_acronyms)]
+    RESTORE,
+    #[allow(non_camel_case_types, clippy::upper_case_acronyms)]
+    INCREMENTAL,
+    #[allow(non_camel_case_types, clippy::upper_case_acronyms)]
+    REBALANCE,
+    #[allow(non_camel_case_types, clippy::upper_case_acronyms)]
+    PAUSE,
+    #[allow(non_camel_case_types, clippy::upper_case_acronyms)]
+    RESUME,
+    #[allow(non_camel_case_types, clippy::upper_case_acronyms)]
+    DRY,
+    #[allow(non_camel_case_types, clippy::upper_case_acronyms)]
+    RUN,
+    #[allow(non_camel_case_types, clippy::upper_case_acronyms)]
+    DECOMMISSION,
+    #[allow(non_camel_case_types, clippy::upper_case_acronyms)]
+    VIEWS,
 }
 
 impl FromStr for CnosKeyWord {
@@ -139,6 +168,8 @@ impl FromStr for CnosKeyWord {
             "VNODE_DURATION" => Ok(CnosKeyWord::VNODE_DURATION),
             "REPLICA" => Ok(CnosKeyWord::REPLICA),
             "PRECISION" => Ok(CnosKeyWord::PRECISION),
+            "REPLICA_PLACEMENT" => Ok(CnosKeyWord::REPLICA_PLACEMENT),
+            "COLD_AFTER" => Ok(CnosKeyWord::COLD_AFTER),
             "DATABASES" => Ok(CnosKeyWord::DATABASES),
             "QUERIES" => Ok(CnosKeyWord::QUERIES),
             "TENANT" => Ok(CnosKeyWord::TENANT),
@@ -172,6 +203,18 @@ impl FromStr for CnosKeyWord {
             "PROMOTE" => Ok(CnosKeyWord::PROMOTE),
             "DESTORY" => Ok(CnosKeyWord::DESTORY),
             "REPLICAS" => Ok(CnosKeyWord::REPLICAS),
+            "CARDINALITY" => Ok(CnosKeyWord::CARDINALITY),
+            "REBUILD" => Ok(CnosKeyWord::REBUILD),
+            "BACKUP" => Ok(CnosKeyWord::BACKUP),
+            "RESTORE" => Ok(CnosKeyWord::RESTORE),
+            "INCREMENTAL" => Ok(CnosKeyWord::INCREMENTAL),
+            "REBALANCE" => Ok(CnosKeyWord::REBALANCE),
+            "PAUSE" => Ok(CnosKeyWord::PAUSE),
+            "RESUME" => Ok(CnosKeyWord::RESUME),
+            "DRY" => Ok(CnosKeyWord::DRY),
+            "RUN" => Ok(CnosKeyWord::RUN),
+            "DECOMMISSION" => Ok(CnosKeyWord::DECOMMISSION),
+            "VIEWS" => Ok(CnosKeyWord::VIEWS),
             _ => Err(ParserError::ParserError(format!(
                 "fail parse {} to CnosKeyWord",
                 s
@@ -198,6 +241,32 @@ impl CnosdbParser for DefaultParser {
     }
 }
 
+/// Number the anonymous placeholders `?` as `$1`, `$2`, ... in order of appearance.
+///
+/// Fails if both `?` and numbered placeholders are used, since they would refer to the
+/// same parameters.
+fn number_placeholders(tokens: Vec<Token>) -> Result<Vec<Token>> {
+    let mut anonymous = 0;
+    let mut numbered = false;
+    let tokens = tokens
+        .into_iter()
+        .map(|token| match token {
+            Token::Placeholder(p) if p == "?" => {
+                anonymous += 1;
+                Token::Placeholder(format!("${}", anonymous))
+            }
+            token => {
+                numbered |= matches!(token, Token::Placeholder(_));
+                token
+            }
+        })
+        .collect();
+    if anonymous > 0 && numbered {
+        return parser_err!("Placeholders ? and $n can't be used in the same statement");
+    }
+    Ok(tokens)
+}
+
 /// SQL Parser
 pub struct ExtParser<'a> {
     parser: Parser<'a>,
@@ -212,7 +281,7 @@ impl<'a> ExtParser<'a> {
     /// Parse the specified tokens with dialect
     fn new_with_dialect(sql: &str, dialect: &'a dyn Dialect) -> Result<Self> {
         let mut tokenizer = Tokenizer::new(dialect, sql);
-        let tokens = tokenizer.tokenize()?;
+        let tokens = number_placeholders(tokenizer.tokenize()?)?;
         Ok(ExtParser {
             parser: Parser::new(dialect).with_tokens(tokens),
         })
@@ -315,6 +384,18 @@ impl<'a> ExtParser<'a> {
                                 self.parser.next_token();
                                 self.parse_checksum()
                             }
+                            CnosKeyWord::REBUILD => {
+                                self.parser.next_token();
+                                self.parse_rebuild()
+                            }
+                            CnosKeyWord::BACKUP => {
+                                self.parser.next_token();
+                                self.parse_backup()
+                            }
+                            CnosKeyWord::RESTORE => {
+                                self.parser.next_token();
+                                self.parse_restore()
+                            }
                             CnosKeyWord::RECOVER => {
                                 self.parser.next_token();
                                 self.parse_recover()
@@ -323,6 +404,20 @@ impl<'a> ExtParser<'a> {
                                 self.parser.next_token();
                                 self.parse_replica()
                             }
+                            CnosKeyWord::REBALANCE => {
+                                self.parser.next_token();
+                                self.parse_rebalance()
+                            }
+                            CnosKeyWord::PAUSE => {
+                                self.parser.next_token();
+                                self.expect_cnos_keyword(CnosKeyWord::REBALANCE)?;
+                                Ok(ExtStatement::PauseRebalance)
+                            }
+                            CnosKeyWord::RESUME => {
+                                self.parser.next_token();
+                                self.expect_cnos_keyword(CnosKeyWord::REBALANCE)?;
+                                Ok(ExtStatement::ResumeRebalance)
+                            }
                             _ => Ok(ExtStatement::SqlStatement(Box::new(
                                 self.parser.parse_statement()?,
                             ))),
@@ -351,6 +446,8 @@ impl<'a> ExtParser<'a> {
     fn parse_show(&mut self) -> Result<ExtStatement> {
         if self.parser.parse_keyword(Keyword::TABLES) {
             self.parse_show_tables()
+        } else if self.parse_cnos_keyword(CnosKeyWord::VIEWS) {
+            self.parse_show_views()
         } else if self.parse_cnos_keyword(CnosKeyWord::DATABASES) {
             self.parse_show_databases()
         } else if self.parse_cnos_keyword(CnosKeyWord::SERIES) {
@@ -372,6 +469,10 @@ impl<'a> ExtParser<'a> {
             Ok(ExtStatement::ShowStreams(ast::ShowStreams { verbose }))
         } else if self.parse_cnos_keyword(CnosKeyWord::REPLICAS) {
             self.parse_show_replicas()
+        } else if self.parse_cnos_keyword(CnosKeyWord::CARDINALITY) {
+            self.parse_show_cardinality()
+        } else if self.parse_cnos_keyword(CnosKeyWord::REBALANCE) {
+            Ok(ExtStatement::ShowRebalance)
         } else {
             parser_err!(format!("nonsupport: {}", self.parser.peek_token()))
         }
@@ -491,6 +592,17 @@ impl<'a> ExtParser<'a> {
         })))
     }
 
+    fn parse_show_cardinality(&mut self) -> Result<ExtStatement> {
+        let database_name = self.parse_on_database()?;
+        self.parser.expect_keyword(Keyword::FROM)?;
+        let table = self.parser.parse_identifier()?;
+
+        Ok(ExtStatement::ShowCardinality(ShowCardinality {
+            database_name,
+            table,
+        }))
+    }
+
     fn parse_explain(&mut self) -> Result<ExtStatement> {
         let analyze = self.parser.parse_keyword(Keyword::ANALYZE);
         let verbose = self.parser.parse_keyword(Keyword::VERBOSE);
@@ -526,6 +638,10 @@ impl<'a> ExtParser<'a> {
         Ok(ExtStatement::ShowTables(self.parse_on_database()?))
     }
 
+    fn parse_show_views(&mut self) -> Result<ExtStatement> {
+        Ok(ExtStatement::ShowViews(self.parse_on_database()?))
+    }
+
     fn parse_show_replicas(&mut self) -> Result<ExtStatement> {
         Ok(ExtStatement::ShowReplicas)
     }
@@ -569,11 +685,23 @@ impl<'a> ExtParser<'a> {
             self.parse_alter_tenant()
         } else if self.parser.parse_keyword(Keyword::USER) {
             self.parse_alter_user()
+        } else if self.parse_cnos_keyword(CnosKeyWord::NODE) {
+            self.parse_alter_node()
         } else {
-            self.expected("TABLE/DATABASE/TENANT/USER", self.parser.peek_token())
+            self.expected("TABLE/DATABASE/TENANT/USER/NODE", self.parser.peek_token())
         }
     }
 
+    /// Parses `ALTER NODE <node_id> DECOMMISSION`
+    fn parse_alter_node(&mut self) -> Result<ExtStatement> {
+        let node_id = self.parse_number::<NodeId>()?;
+        self.expect_cnos_keyword(CnosKeyWord::DECOMMISSION)?;
+
+        Ok(ExtStatement::DecommissionNode(ast::DecommissionNode {
+            node_id,
+        }))
+    }
+
     fn parse_alter_table(&mut self) -> Result<ExtStatement> {
         let table_name = self.parser.parse_object_name()?;
 
@@ -750,13 +878,15 @@ impl<'a> ExtParser<'a> {
         }
     }
 
-    /// Parses the set of valid formats
-    /// This is a copy of the equivalent implementation in Datafusion.
+    /// Parses the file format of STORED AS, which is validated when the table is created.
     fn parse_file_format(&mut self) -> Result<String, ParserError> {
         let TokenWithLocation { token, location: _ } = self.parser.next_token();
         match token {
             Token::Word(w) => parse_file_type(&w.value),
-            unexpected => self.expected("one of PARQUET, NDJSON, or CSV", unexpected),
+            unexpected => self.expected(
+                "one of PARQUET, NDJSON, CSV, AVRO, ARROW, or ORC",
+                unexpected,
+            ),
         }
     }
 
@@ -900,6 +1030,40 @@ impl<'a> ExtParser<'a> {
         Ok(ExtStatement::CreateTable(create))
     }
 
+    /// e.g.
+    /// CREATE [OR REPLACE] VIEW v AS SELECT * FROM t
+    fn parse_create_view(&mut self, or_replace: bool) -> Result<ExtStatement> {
+        let name = self.parser.parse_object_name()?;
+        self.parser.expect_keyword(Keyword::AS)?;
+        let query = Box::new(self.parser.parse_query()?);
+
+        Ok(ExtStatement::CreateView(CreateView {
+            name,
+            or_replace,
+            query,
+        }))
+    }
+
+    /// e.g.
+    /// CREATE MATERIALIZED VIEW [IF NOT EXISTS] mv AS
+    /// SELECT time_window(time, '1m'), tag, avg(v) FROM t GROUP BY time_window(time, '1m'), tag
+    fn parse_create_materialized_view(&mut self) -> Result<ExtStatement> {
+        let if_not_exists =
+            self.parser
+                .parse_keywords(&[Keyword::IF, Keyword::NOT, Keyword::EXISTS]);
+        let name = self.parser.parse_object_name()?;
+        self.parser.expect_keyword(Keyword::AS)?;
+        let query = Box::new(self.parser.parse_query()?);
+
+        Ok(ExtStatement::CreateMaterializedView(
+            CreateMaterializedView {
+                name,
+                if_not_exists,
+                query,
+            },
+        ))
+    }
+
     fn parse_database_options(&mut self) -> Result<DatabaseOptions> {
         if self.parser.parse_keyword(Keyword::WITH) {
             let mut options = DatabaseOptions::default();
@@ -936,6 +1100,12 @@ impl<'a> ExtParser<'a> {
         } else if self.parse_cnos_keyword(CnosKeyWord::PRECISION) {
             let _ = self.parser.expect_token(&Token::Eq);
             options.precision = Some(self.parse_string_value()?);
+        } else if self.parse_cnos_keyword(CnosKeyWord::REPLICA_PLACEMENT) {
+            let _ = self.parser.expect_token(&Token::Eq);
+            options.replica_placement = Some(self.parse_string_value()?);
+        } else if self.parse_cnos_keyword(CnosKeyWord::COLD_AFTER) {
+            let _ = self.parser.expect_token(&Token::Eq);
+            options.cold_after = Some(self.parse_string_value()?);
         } else {
             return Ok(false);
         }
@@ -1230,6 +1400,11 @@ impl<'a> ExtParser<'a> {
 
     /// Parse a SQL CREATE statement
     fn parse_create(&mut self) -> Result<ExtStatement> {
+        if self.parser.parse_keywords(&[Keyword::OR, Keyword::REPLACE]) {
+            self.parser.expect_keyword(Keyword::VIEW)?;
+            return self.parse_create_view(true);
+        }
+
         // Currently only supports the creation of external tables
         if self.parser.parse_keyword(Keyword::EXTERNAL) {
             self.parse_create_external_table(false)
@@ -1248,6 +1423,11 @@ impl<'a> ExtParser<'a> {
             self.parse_create_role()
         } else if self.parse_cnos_keyword(CnosKeyWord::STREAM) {
             self.parse_create_stream()
+        } else if self.parser.parse_keyword(Keyword::VIEW) {
+            self.parse_create_view(false)
+        } else if self.parser.parse_keyword(Keyword::MATERIALIZED) {
+            self.parser.expect_keyword(Keyword::VIEW)?;
+            self.parse_create_materialized_view()
         } else {
             self.expected("an object type after CREATE", self.parser.peek_token())
         }
@@ -1377,9 +1557,10 @@ impl<'a> ExtParser<'a> {
             if self.parse_cnos_keyword(CnosKeyWord::FILE_FORMAT) {
                 // parse file format & format options
                 // FILE_FORMAT = ({
-                //     TYPE = { CSV | JSON | AVRO | ORC | PARQUET | XML }
+                //     TYPE = { CSV | JSON | AVRO | ORC | ARROW | PARQUET }
                 //     | DELIMITER = '<character>'
                 //     | WITH_HEADER = { true | false }
+                //     | COMPRESSION = { DEFLATE | SNAPPY | LZ4 | ZSTD }
                 //     | ...
                 // } [, ...])
                 file_format_options = self.parse_options()?;
@@ -1460,6 +1641,14 @@ impl<'a> ExtParser<'a> {
                 if_exist,
                 obj_type: DatabaseObjectType::Table,
             })
+        } else if self.parser.parse_keyword(Keyword::VIEW) {
+            let if_exist = self.parser.parse_keywords(&[Keyword::IF, Keyword::EXISTS]);
+            let object_name = self.parser.parse_object_name()?;
+            ExtStatement::DropDatabaseObject(DropDatabaseObject {
+                object_name,
+                if_exist,
+                obj_type: DatabaseObjectType::View,
+            })
         } else if self.parser.parse_keyword(Keyword::DATABASE) {
             let if_exist = self.parser.parse_keywords(&[Keyword::IF, Keyword::EXISTS]);
             let object_name = self.parser.parse_identifier()?;
@@ -1561,6 +1750,78 @@ impl<'a> ExtParser<'a> {
         }
     }
 
+    /// Parses `REBUILD INDEX FOR VNODE <vnode_id>`
+    fn parse_rebuild(&mut self) -> Result<ExtStatement> {
+        self.parser.expect_keyword(Keyword::INDEX)?;
+        self.parser.expect_keyword(Keyword::FOR)?;
+        if self.parse_cnos_keyword(CnosKeyWord::VNODE) {
+            let vnode_id = self.parse_number::<VnodeId>()?;
+            Ok(ExtStatement::RebuildIndex(RebuildIndex { vnode_id }))
+        } else {
+            parser_err!("Expected VNODE, after REBUILD INDEX FOR")
+        }
+    }
+
+    /// Parses `BACKUP DATABASE <name> TO '<location>' [CONNECTION = (...)] [INCREMENTAL]`
+    fn parse_backup(&mut self) -> Result<ExtStatement> {
+        self.parser.expect_keyword(Keyword::DATABASE)?;
+        let object_name = self.parser.parse_identifier()?;
+        self.parser.expect_keyword(Keyword::TO)?;
+        let location = self.parse_backup_location()?;
+        let incremental = self.parse_cnos_keyword(CnosKeyWord::INCREMENTAL);
+
+        Ok(ExtStatement::BackupDatabase(BackupDatabase {
+            object_name,
+            location,
+            incremental,
+        }))
+    }
+
+    /// Parses `RESTORE DATABASE <name> FROM '<location>' [CONNECTION = (...)] [AS <new_name>]`
+    fn parse_restore(&mut self) -> Result<ExtStatement> {
+        self.parser.expect_keyword(Keyword::DATABASE)?;
+        let object_name = self.parser.parse_identifier()?;
+        self.parser.expect_keyword(Keyword::FROM)?;
+        let location = self.parse_backup_location()?;
+        let new_name = if self.parser.parse_keyword(Keyword::AS) {
+            Some(self.parser.parse_identifier()?)
+        } else {
+            None
+        };
+
+        Ok(ExtStatement::RestoreDatabase(RestoreDatabase {
+            object_name,
+            location,
+            new_name,
+        }))
+    }
+
+    /// Parses `REBALANCE [DRY RUN]`
+    fn parse_rebalance(&mut self) -> Result<ExtStatement> {
+        let dry_run = if self.parse_cnos_keyword(CnosKeyWord::DRY) {
+            self.expect_cnos_keyword(CnosKeyWord::RUN)?;
+            true
+        } else {
+            false
+        };
+
+        Ok(ExtStatement::Rebalance(ast::Rebalance { dry_run }))
+    }
+
+    fn parse_backup_location(&mut self) -> Result<UriLocation> {
+        let path = self.parser.parse_literal_string()?;
+        let connection_options = if self.parser.parse_keyword(Keyword::CONNECTION) {
+            self.parse_options()?
+        } else {
+            Default::default()
+        };
+
+        Ok(UriLocation {
+            path,
+            connection_options,
+        })
+    }
+
     fn consume_token(&mut self, expected: &Token) -> bool {
         if self.parser.peek_token().token == *expected {
             self.parser.next_token();
@@ -1745,7 +2006,6 @@ impl<'a> ExtParser<'a> {
     }
 }
 
-/// This is a copy of the equivalent implementation in Datafusion.
 fn parse_file_type(s: &str) -> Result<String, ParserError> {
     Ok(s.to_uppercase())
 }
@@ -1782,6 +2042,48 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_placeholders() {
+        let sql = "select * from t where a = ? and b > ? and c < ?";
+        let numbered = match parse_sql(sql) {
+            ExtStatement::SqlStatement(stmt) => stmt.to_string(),
+            _ => panic!("failed"),
+        };
+        assert_eq!(
+            numbered,
+            "SELECT * FROM t WHERE a = $1 AND b > $2 AND c < $3"
+        );
+        // the numbered statement is parsed to the same statement
+        match parse_sql(&numbered) {
+            ExtStatement::SqlStatement(stmt) => assert_eq!(stmt.to_string(), numbered),
+            _ => panic!("failed"),
+        }
+
+        // placeholders in subqueries are numbered in order of appearance too
+        let sql = "select ? from t where a in (select a from t1 where b = ?) and c = ?";
+        match parse_sql(sql) {
+            ExtStatement::SqlStatement(stmt) => assert_eq!(
+                stmt.to_string(),
+                "SELECT $1 FROM t WHERE a IN (SELECT a FROM t1 WHERE b = $2) AND c = $3"
+            ),
+            _ => panic!("failed"),
+        }
+
+        // ? and $n refer to the same parameters, they can't be mixed
+        let sql = "select * from t where a = ? and b > ? and c < $1";
+        assert!(ExtParser::parse_sql(sql).is_err());
+        let sql = "select * from t where a = $1 and b > ?";
+        assert!(ExtParser::parse_sql(sql).is_err());
+
+        let sql = "prepare q as select * from t where a = $1";
+        match parse_sql(sql) {
+            ExtStatement::SqlStatement(stmt) => {
+                assert!(matches!(stmt.deref(), Statement::Prepare { .. }))
+            }
+            _ => panic!("failed"),
+        }
+    }
+
     #[test]
     fn test_drop() {
         let sql = "drop database if exists test_db";
@@ -2212,12 +2514,52 @@ mod tests {
             ExtStatement::CreateDatabase(ref stmt) => {
                 let ans = format!("{:?}", stmt);
                 println!("{ans}");
-                let expectd = "CreateDatabase { name: Ident { value: \"test\", quote_style: None }, if_not_exists: false, options: DatabaseOptions { ttl: Some(\"10d\"), shard_num: Some(5), vnode_duration: Some(\"3d\"), replica: Some(10), precision: Some(\"us\") } }";
+                let expectd = "CreateDatabase { name: Ident { value: \"test\", quote_style: None }, if_not_exists: false, options: DatabaseOptions { ttl: Some(\"10d\"), shard_num: Some(5), vnode_duration: Some(\"3d\"), replica: Some(10), precision: Some(\"us\"), replica_placement: None, cold_after: None } }";
                 assert_eq!(ans, expectd);
             }
             _ => panic!("impossible"),
         }
     }
+
+    #[test]
+    fn test_create_database_with_cold_after() {
+        let sql = "CREATE DATABASE test WITH TTL '365d' COLD_AFTER '30d';";
+        let statements = ExtParser::parse_sql(sql).unwrap();
+        match statements[0] {
+            ExtStatement::CreateDatabase(ref stmt) => {
+                assert_eq!(stmt.options.cold_after, Some("30d".to_string()));
+            }
+            _ => panic!("impossible"),
+        }
+    }
+
+    #[test]
+    fn test_create_database_with_replica_placement() {
+        let sql = "CREATE DATABASE test WITH REPLICA 3 REPLICA_PLACEMENT 'spread:zone';";
+        let statements = ExtParser::parse_sql(sql).unwrap();
+        match statements[0] {
+            ExtStatement::CreateDatabase(ref stmt) => {
+                assert_eq!(stmt.options.replica, Some(3));
+                assert_eq!(
+                    stmt.options.replica_placement,
+                    Some("spread:zone".to_string())
+                );
+            }
+            _ => panic!("impossible"),
+        }
+
+        let sql = "ALTER DATABASE test SET REPLICA_PLACEMENT 'spread:rack';";
+        let statements = ExtParser::parse_sql(sql).unwrap();
+        match statements[0] {
+            ExtStatement::AlterDatabase(ref stmt) => {
+                assert_eq!(
+                    stmt.options.replica_placement,
+                    Some("spread:rack".to_string())
+                );
+            }
+            _ => panic!("impossible"),
+        }
+    }
     #[test]
     #[should_panic]
     fn test_create_table_without_fields() {
@@ -2374,6 +2716,44 @@ mod tests {
         assert_eq!(statement[0], ExtStatement::ShowReplicas);
     }
 
+    #[test]
+    fn test_rebalance_sql() {
+        let statement = ExtParser::parse_sql("rebalance;").unwrap();
+        assert_eq!(
+            statement[0],
+            ExtStatement::Rebalance(ast::Rebalance { dry_run: false })
+        );
+
+        let statement = ExtParser::parse_sql("rebalance dry run;").unwrap();
+        assert_eq!(
+            statement[0],
+            ExtStatement::Rebalance(ast::Rebalance { dry_run: true })
+        );
+
+        let statement = ExtParser::parse_sql("show rebalance;").unwrap();
+        assert_eq!(statement[0], ExtStatement::ShowRebalance);
+
+        let statement = ExtParser::parse_sql("pause rebalance;").unwrap();
+        assert_eq!(statement[0], ExtStatement::PauseRebalance);
+
+        let statement = ExtParser::parse_sql("resume rebalance;").unwrap();
+        assert_eq!(statement[0], ExtStatement::ResumeRebalance);
+
+        assert!(ExtParser::parse_sql("rebalance dry;").is_err());
+    }
+
+    #[test]
+    fn test_decommission_node_sql() {
+        let statement = ExtParser::parse_sql("alter node 3 decommission;").unwrap();
+        assert_eq!(
+            statement[0],
+            ExtStatement::DecommissionNode(ast::DecommissionNode { node_id: 3 })
+        );
+
+        assert!(ExtParser::parse_sql("alter node 3;").is_err());
+        assert!(ExtParser::parse_sql("alter node decommission;").is_err());
+    }
+
     #[test]
     fn test_vnode_sql() {
         let sql1 = "move vnode 1 to node 2;";
@@ -2416,6 +2796,48 @@ mod tests {
                 replication_set_id: 10
             })
         );
+        let sql6 = "rebuild index for vnode 11";
+        let statement = ExtParser::parse_sql(sql6).unwrap();
+        assert_eq!(
+            statement[0],
+            ExtStatement::RebuildIndex(RebuildIndex { vnode_id: 11 })
+        );
+    }
+
+    #[test]
+    fn test_backup_and_restore_database() {
+        let sql = "backup database db1 to 's3://bucket/backup' \
+            connection = (region = 'us-east-1', virtual_hosted_style = true) incremental";
+        let statement = ExtParser::parse_sql(sql).unwrap();
+        match &statement[0] {
+            ExtStatement::BackupDatabase(BackupDatabase {
+                object_name,
+                location,
+                incremental,
+            }) => {
+                assert_eq!(object_name.value, "db1");
+                assert_eq!(location.path, "s3://bucket/backup");
+                assert_eq!(location.connection_options.len(), 2);
+                assert!(*incremental);
+            }
+            _ => panic!("expected BackupDatabase, found: {:?}", statement[0]),
+        }
+
+        let sql = "restore database db1 from '/tmp/backup' as db2";
+        let statement = ExtParser::parse_sql(sql).unwrap();
+        match &statement[0] {
+            ExtStatement::RestoreDatabase(RestoreDatabase {
+                object_name,
+                location,
+                new_name,
+            }) => {
+                assert_eq!(object_name.value, "db1");
+                assert_eq!(location.path, "/tmp/backup");
+                assert!(location.connection_options.is_empty());
+                assert_eq!(new_name.as_ref().unwrap().value, "db2");
+            }
+            _ => panic!("expected RestoreDatabase, found: {:?}", statement[0]),
+        }
     }
 
     #[test]
@@ -2536,6 +2958,99 @@ mod tests {
         assert_eq!(expected, result);
     }
 
+    #[test]
+    fn test_create_view() {
+        let result = parse_sql("create view db1.v1 as select a, b from t1 where a > 1;");
+        match result {
+            ExtStatement::CreateView(CreateView {
+                name,
+                or_replace,
+                query,
+            }) => {
+                assert_eq!(name.to_string(), "db1.v1");
+                assert!(!or_replace);
+                assert_eq!(query.to_string(), "SELECT a, b FROM t1 WHERE a > 1");
+            }
+            _ => panic!("failed"),
+        }
+
+        let result = parse_sql("create or replace view v1 as select * from t1;");
+        match result {
+            ExtStatement::CreateView(CreateView {
+                name, or_replace, ..
+            }) => {
+                assert_eq!(name.to_string(), "v1");
+                assert!(or_replace);
+            }
+            _ => panic!("failed"),
+        }
+
+        assert!(ExtParser::parse_sql("create or replace table t1 (a bigint);").is_err());
+    }
+
+    #[test]
+    fn test_create_materialized_view() {
+        let result = parse_sql(
+            "create materialized view if not exists mv as
+            select time_window(time, '1m'), station, avg(temperature) from air
+            group by time_window(time, '1m'), station;",
+        );
+        match result {
+            ExtStatement::CreateMaterializedView(CreateMaterializedView {
+                name,
+                if_not_exists,
+                query,
+            }) => {
+                assert_eq!(name.to_string(), "mv");
+                assert!(if_not_exists);
+                assert_eq!(
+                    query.to_string(),
+                    "SELECT time_window(time, '1m'), station, avg(temperature) FROM air \
+                    GROUP BY time_window(time, '1m'), station"
+                );
+            }
+            _ => panic!("failed"),
+        }
+    }
+
+    #[test]
+    fn test_drop_and_show_views() {
+        let result = parse_sql("drop view if exists v1;");
+        let expected = ExtStatement::DropDatabaseObject(DropDatabaseObject {
+            object_name: ObjectName(vec![Ident::new("v1")]),
+            if_exist: true,
+            obj_type: DatabaseObjectType::View,
+        });
+        assert_eq!(expected, result);
+
+        let result = parse_sql("show views on db1;");
+        assert_eq!(ExtStatement::ShowViews(Some(Ident::new("db1"))), result);
+
+        let result = parse_sql("show views;");
+        assert_eq!(ExtStatement::ShowViews(None), result);
+    }
+
+    #[test]
+    fn test_show_cardinality() {
+        let result = parse_sql("show cardinality on db1 from test;");
+
+        let expected = ExtStatement::ShowCardinality(ShowCardinality {
+            database_name: Some(Ident::new("db1")),
+            table: Ident::new("test"),
+        });
+
+        assert_eq!(expected, result);
+
+        let result = parse_sql("show cardinality from test;");
+
+        let expected = ExtStatement::ShowCardinality(ShowCardinality {
+            database_name: None,
+            table: Ident::new("test"),
+        });
+
+        assert_eq!(expected, result);
+    }
+
     #[test]
     fn test_create_stream_table() {
         let statement = parse_sql(
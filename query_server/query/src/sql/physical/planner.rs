@@ -25,6 +25,8 @@ use spi::QueryResult;
 use super::optimizer::PhysicalOptimizer;
 use crate::extension::physical::optimizer_rule::add_assert::AddAssertExec;
 use crate::extension::physical::optimizer_rule::add_sort::AddSortExec;
+use crate::extension::physical::optimizer_rule::push_down_aggregate::PushDownPartialAggregate;
+use crate::extension::physical::optimizer_rule::sort_aggregate_input::SortAggregateInput;
 use crate::extension::physical::transform_rule::expand::ExpandPlanner;
 use crate::extension::physical::transform_rule::gapfill::GapFillPlanner;
 use crate::extension::physical::transform_rule::table_writer::TableWriterPlanner;
@@ -119,6 +121,10 @@ impl Default for DefaultPhysicalPlanner {
             // CnosDB
             Arc::new(AddAssertExec::new()),
             Arc::new(AddSortExec::new()),
+            // Aggregations need sorted input are not pushed down, so it runs after AddSortExec.
+            Arc::new(PushDownPartialAggregate::new()),
+            // Sorts the input of the aggregations left after pushing down.
+            Arc::new(SortAggregateInput::new()),
         ];
 
         Self {
@@ -8,15 +8,10 @@ use async_trait::async_trait;
 use datafusion::arrow::datatypes::{DataType, SchemaRef, TimeUnit};
 use datafusion::arrow::error::ArrowError;
 use datafusion::common::parsers::CompressionTypeVariant;
-use datafusion::common::tree_node::TreeNode;
+use datafusion::common::tree_node::{TreeNode, VisitRecursion};
 use datafusion::common::{
     Column, DFField, DFSchema, OwnedTableReference, Result as DFResult, ToDFSchema,
 };
-use datafusion::datasource::file_format::avro::AvroFormat;
-use datafusion::datasource::file_format::csv::CsvFormat;
-use datafusion::datasource::file_format::file_type::FileType;
-use datafusion::datasource::file_format::json::JsonFormat;
-use datafusion::datasource::file_format::parquet::ParquetFormat;
 use datafusion::datasource::file_format::FileFormat;
 use datafusion::datasource::listing::{
     ListingOptions, ListingTable, ListingTableConfig, ListingTableUrl,
@@ -24,17 +19,20 @@ use datafusion::datasource::listing::{
 use datafusion::error::DataFusionError;
 use datafusion::execution::context::SessionState;
 use datafusion::execution::runtime_env::RuntimeEnv;
-use datafusion::logical_expr::expr::{ScalarFunction, Sort};
+use datafusion::logical_expr::expr::{ScalarFunction, ScalarUDF, Sort};
 use datafusion::logical_expr::expr_rewriter::rewrite_preserving_name;
 use datafusion::logical_expr::logical_plan::Analyze;
 use datafusion::logical_expr::utils::expr_to_columns;
 use datafusion::logical_expr::{
-    lit, BinaryExpr, BuiltinScalarFunction, Case, CreateExternalTable as PlanCreateExternalTable,
-    EmptyRelation, Explain, Expr, Extension, LogicalPlan, LogicalPlanBuilder, Operator, PlanType,
-    SubqueryAlias, TableSource, ToStringifiedPlan, Union,
+    approx_distinct, cast, count_distinct, lit, BinaryExpr, BuiltinScalarFunction, Case,
+    CreateExternalTable as PlanCreateExternalTable, EmptyRelation, Explain, Expr, Extension,
+    LogicalPlan, LogicalPlanBuilder, Operator, PlanType, Prepare, SubqueryAlias, TableScan,
+    TableSource, ToStringifiedPlan, Union,
 };
 use datafusion::optimizer::analyzer::type_coercion::TypeCoercionRewriter;
+use datafusion::optimizer::optimizer::Optimizer;
 use datafusion::optimizer::simplify_expressions::ConstEvaluator;
+use datafusion::optimizer::{OptimizerContext, OptimizerRule};
 use datafusion::physical_expr::execution_props::ExecutionProps;
 use datafusion::prelude::col;
 use datafusion::scalar::ScalarValue;
@@ -42,25 +40,28 @@ use datafusion::sql::parser::CreateExternalTable as AstCreateExternalTable;
 use datafusion::sql::planner::{object_name_to_table_reference, PlannerContext, SqlToRel};
 use datafusion::sql::sqlparser::ast::{
     Assignment, DataType as SQLDataType, Expr as SQLExpr, Expr as ASTExpr, Ident, ObjectName,
-    Offset, OrderByExpr, Query, SqlOption, Statement, TableAlias, TableFactor, TableWithJoins,
-    TimezoneInfo,
+    Offset, OrderByExpr, Query, SelectItem, SetExpr, SqlOption, Statement, TableAlias, TableFactor,
+    TableWithJoins, TimezoneInfo,
 };
 use datafusion::sql::sqlparser::parser::ParserError;
 use datafusion::sql::TableReference;
 use lazy_static::__Deref;
 use meta::error::MetaError;
+use models::arrow::ExternalFileType;
 use models::auth::bcrypt_verify;
 use models::auth::privilege::{
     DatabasePrivilege, GlobalPrivilege, Privilege, TenantObjectPrivilege,
 };
 use models::auth::role::{SystemTenantRole, TenantRoleIdentifier};
 use models::auth::user::User;
+use models::codec::Encoding;
 use models::gis::data_type::{Geometry, GeometryType};
 use models::object_reference::{Resolve, ResolvedTable};
 use models::oid::{Identifier, Oid};
 use models::schema::{
-    ColumnType, DatabaseOptions, Duration, Precision, TableColumn, Tenant, TskvTableSchema,
-    TskvTableSchemaRef, Watermark, DEFAULT_CATALOG, TIME_FIELD,
+    is_read_only_table, ColumnType, DatabaseOptions, Duration, MaterializedView, Precision,
+    TableColumn, Tenant, TskvTableSchema, TskvTableSchemaRef, ViewSchema, Watermark,
+    DEFAULT_CATALOG, TIME_FIELD,
 };
 use models::utils::SeqIdGenerator;
 use models::{ColumnId, ValueType};
@@ -70,28 +71,34 @@ use spi::query::ast;
 use spi::query::ast::{
     AlterDatabase as ASTAlterDatabase, AlterTable as ASTAlterTable,
     AlterTableAction as ASTAlterTableAction, AlterTenantOperation, AlterUserOperation,
-    ChecksumGroup as ASTChecksumGroup, ColumnOption, CompactVnode as ASTCompactVnode,
-    CopyIntoTable, CopyTarget, CopyVnode as ASTCopyVnode, CreateDatabase as ASTCreateDatabase,
-    CreateTable as ASTCreateTable, DatabaseOptions as ASTDatabaseOptions,
+    BackupDatabase as ASTBackupDatabase, ChecksumGroup as ASTChecksumGroup, ColumnOption,
+    CompactVnode as ASTCompactVnode, CopyIntoTable, CopyTarget, CopyVnode as ASTCopyVnode,
+    CreateDatabase as ASTCreateDatabase, CreateMaterializedView as ASTCreateMaterializedView,
+    CreateTable as ASTCreateTable, CreateView as ASTCreateView,
+    DatabaseOptions as ASTDatabaseOptions, DecommissionNode as ASTDecommissionNode,
     DescribeDatabase as DescribeDatabaseOptions, DescribeTable as DescribeTableOptions,
-    DropVnode as ASTDropVnode, ExtStatement, MoveVnode as ASTMoveVnode,
-    ReplicaAdd as ASTReplicaAdd, ReplicaDestory as ASTReplicaDestory,
-    ReplicaPromote as ASTReplicaPromote, ReplicaRemove as ASTReplicaRemove,
-    ShowSeries as ASTShowSeries, ShowTagBody, ShowTagValues as ASTShowTagValues, UriLocation, With,
+    DropVnode as ASTDropVnode, ExtStatement, MoveVnode as ASTMoveVnode, Rebalance as ASTRebalance,
+    RebuildIndex as ASTRebuildIndex, ReplicaAdd as ASTReplicaAdd,
+    ReplicaDestory as ASTReplicaDestory, ReplicaPromote as ASTReplicaPromote,
+    ReplicaRemove as ASTReplicaRemove, RestoreDatabase as ASTRestoreDatabase,
+    ShowCardinality as ASTShowCardinality, ShowSeries as ASTShowSeries, ShowTagBody,
+    ShowTagValues as ASTShowTagValues, UriLocation, With,
 };
+use spi::query::config::MaterializedViewRewrite;
 use spi::query::datasource::{self, UriSchema};
 use spi::query::logical_planner::{
     normalize_sql_object_name_to_string, parse_connection_options,
     sql_option_to_alter_tenant_action, sql_options_to_map, sql_options_to_tenant_options,
     sql_options_to_user_options, unset_option_to_alter_tenant_action, AlterDatabase, AlterTable,
     AlterTableAction, AlterTenant, AlterTenantAction, AlterTenantAddUser, AlterTenantSetUser,
-    AlterUser, AlterUserAction, ChecksumGroup, CompactVnode, CopyOptions, CopyOptionsBuilder,
-    CopyVnode, CreateDatabase, CreateRole, CreateStreamTable, CreateTable, CreateTenant,
-    CreateUser, DDLPlan, DMLPlan, DatabaseObjectType, DeleteFromTable, DropDatabaseObject,
-    DropGlobalObject, DropTenantObject, DropVnode, FileFormatOptions, FileFormatOptionsBuilder,
-    GlobalObjectType, GrantRevoke, LogicalPlanner, MoveVnode, Plan, PlanWithPrivileges, QueryPlan,
-    RecoverDatabase, RecoverTenant, ReplicaAdd, ReplicaDestory, ReplicaPromote, ReplicaRemove,
-    SYSPlan, TenantObjectType, TENANT_OPTION_LIMITER,
+    AlterUser, AlterUserAction, BackupDatabase, ChecksumGroup, CompactVnode, CopyOptions,
+    CopyOptionsBuilder, CopyVnode, CreateDatabase, CreateMaterializedView, CreateRole,
+    CreateStreamTable, CreateTable, CreateTenant, CreateUser, CreateView, DDLPlan, DMLPlan,
+    DatabaseObjectType, DecommissionNode, DeleteFromTable, DropDatabaseObject, DropGlobalObject,
+    DropTenantObject, DropVnode, FileFormatOptions, FileFormatOptionsBuilder, GlobalObjectType,
+    GrantRevoke, LogicalPlanner, MoveVnode, Plan, PlanWithPrivileges, QueryPlan, Rebalance,
+    RebuildIndex, RecoverDatabase, RecoverTenant, ReplicaAdd, ReplicaDestory, ReplicaPromote,
+    ReplicaRemove, RestoreDatabase, SYSPlan, TenantObjectType, TENANT_OPTION_LIMITER,
 };
 use spi::query::session::SessionCtx;
 use spi::{
@@ -101,10 +108,19 @@ use trace::span_ext::SpanExt;
 use trace::{debug, warn};
 use url::Url;
 
+use crate::data_source::file_format::{build_file_format, CompressedFileFormat};
 use crate::data_source::source_downcast_adapter;
-use crate::data_source::stream::{get_event_time_column, get_watermark_delay};
+use crate::data_source::stream::tskv::factory::TSKV_STREAM_PROVIDER;
+use crate::data_source::stream::tskv::{STREAM_DB_KEY, STREAM_TABLE_KEY};
+use crate::data_source::stream::{
+    get_event_time_column, get_watermark_delay, EVENT_TIME_COLUMN_OPTION,
+};
 use crate::data_source::table_source::{TableHandle, TableSourceAdapter, TEMP_LOCATION_TABLE_NAME};
+use crate::extension::expr::{TIME_WINDOW, WINDOW_START};
 use crate::extension::logical::logical_plan_builder::LogicalPlanBuilderExt;
+use crate::extension::logical::optimizer_rule::rewrite_materialized_view::{
+    unqualified_name, MaterializedViewCandidate, RewriteMaterializedView,
+};
 use crate::extension::logical::plan_node::update::UpdateNode;
 use crate::metadata::{
     is_system_database, ContextProviderExtension, DatabaseSet, COLUMNS_COLUMN_NAME,
@@ -112,7 +128,7 @@ use crate::metadata::{
     COLUMNS_TABLE_NAME, DATABASES_DATABASE_NAME, DATABASES_PRECISION, DATABASES_REPLICA,
     DATABASES_SHARD, DATABASES_TTL, DATABASES_VNODE_DURATION, INFORMATION_SCHEMA,
     INFORMATION_SCHEMA_COLUMNS, INFORMATION_SCHEMA_DATABASES, INFORMATION_SCHEMA_QUERIES,
-    INFORMATION_SCHEMA_TABLES, TABLES_TABLE_DATABASE, TABLES_TABLE_NAME,
+    INFORMATION_SCHEMA_TABLES, TABLES_TABLE_DATABASE, TABLES_TABLE_NAME, TABLES_TABLE_TYPE,
 };
 
 /// CnosDB SQL query planner
@@ -129,6 +145,29 @@ impl<'a, S: ContextProviderExtension + Send + Sync> LogicalPlanner for SqlPlanne
         session: &SessionCtx,
         auth_enable: bool,
     ) -> QueryResult<Plan> {
+        self.create_logical_plan_with_privileges(statement, session, auth_enable)
+            .await
+            .map(|plan| plan.plan)
+    }
+}
+
+impl<'a, S: ContextProviderExtension + Send + Sync + 'a> SqlPlanner<'a, S> {
+    /// Create a new query planner
+    pub fn new(schema_provider: &'a S) -> Self {
+        SqlPlanner {
+            schema_provider,
+            df_planner: SqlToRel::new(schema_provider),
+        }
+    }
+
+    /// Same as [`LogicalPlanner::create_logical_plan`], but also returns the checked privileges,
+    /// so that they are checked again when the plan is reused by another query.
+    pub(crate) async fn create_logical_plan_with_privileges(
+        &self,
+        statement: ExtStatement,
+        session: &SessionCtx,
+        auth_enable: bool,
+    ) -> QueryResult<PlanWithPrivileges> {
         let PlanWithPrivileges { plan, privileges } = {
             let span = session.get_child_span("statement to logical plan");
             self.statement_to_plan(statement, session, auth_enable)
@@ -140,18 +179,8 @@ impl<'a, S: ContextProviderExtension + Send + Sync> LogicalPlanner for SqlPlanne
         };
 
         let _ = session.get_child_span("check privilege");
-        check_privilege(session.user(), privileges)?;
-        Ok(plan)
-    }
-}
-
-impl<'a, S: ContextProviderExtension + Send + Sync + 'a> SqlPlanner<'a, S> {
-    /// Create a new query planner
-    pub fn new(schema_provider: &'a S) -> Self {
-        SqlPlanner {
-            schema_provider,
-            df_planner: SqlToRel::new(schema_provider),
-        }
+        check_privilege(session.user(), privileges.clone())?;
+        Ok(PlanWithPrivileges { plan, privileges })
     }
 
     /// Generate a logical plan from an  Extent SQL statement
@@ -183,6 +212,10 @@ impl<'a, S: ContextProviderExtension + Send + Sync + 'a> SqlPlanner<'a, S> {
             ExtStatement::CreateTenant(stmt) => self.create_tenant_to_plan(stmt),
             ExtStatement::CreateUser(stmt) => self.create_user_to_plan(stmt),
             ExtStatement::CreateRole(stmt) => self.create_role_to_plan(stmt, session),
+            ExtStatement::CreateView(stmt) => self.create_view_to_plan(stmt, session),
+            ExtStatement::CreateMaterializedView(stmt) => {
+                self.create_materialized_view_to_plan(stmt, session)
+            }
             ExtStatement::DropDatabaseObject(s) => self.drop_database_object_to_plan(s, session),
             ExtStatement::DropTenantObject(s) => self.drop_tenant_object_to_plan(s, session),
             ExtStatement::DropGlobalObject(s) => self.drop_global_object_to_plan(s),
@@ -190,6 +223,7 @@ impl<'a, S: ContextProviderExtension + Send + Sync + 'a> SqlPlanner<'a, S> {
             ExtStatement::DescribeDatabase(stmt) => self.describe_databases_to_plan(stmt, session),
             ExtStatement::ShowDatabases() => self.show_databases_to_plan(session),
             ExtStatement::ShowTables(stmt) => self.show_tables_to_plan(stmt, session),
+            ExtStatement::ShowViews(stmt) => self.show_views_to_plan(stmt, session),
             ExtStatement::AlterDatabase(stmt) => self.database_to_alter(stmt, session),
             ExtStatement::ShowSeries(stmt) => self.show_series_to_plan(*stmt, session),
             ExtStatement::Explain(stmt) => {
@@ -203,6 +237,7 @@ impl<'a, S: ContextProviderExtension + Send + Sync + 'a> SqlPlanner<'a, S> {
                 .await
             }
             ExtStatement::ShowTagValues(stmt) => self.show_tag_values(*stmt, session),
+            ExtStatement::ShowCardinality(stmt) => self.show_cardinality_to_plan(stmt, session),
             ExtStatement::AlterTable(stmt) => self.alter_table_to_plan(stmt, session),
             ExtStatement::AlterTenant(stmt) => self.alter_tenant_to_plan(stmt).await,
             ExtStatement::AlterUser(stmt) => {
@@ -216,6 +251,7 @@ impl<'a, S: ContextProviderExtension + Send + Sync + 'a> SqlPlanner<'a, S> {
             ExtStatement::MoveVnode(stmt) => self.move_vnode_to_plan(stmt),
             ExtStatement::CompactVnode(stmt) => self.compact_vnode_to_plan(stmt),
             ExtStatement::ChecksumGroup(stmt) => self.checksum_group_to_plan(stmt),
+            ExtStatement::RebuildIndex(stmt) => self.rebuild_index_to_plan(stmt),
             ExtStatement::CreateStream(_) => Err(QueryError::NotImplemented {
                 err: "CreateStream Planner.".to_string(),
             }),
@@ -230,11 +266,18 @@ impl<'a, S: ContextProviderExtension + Send + Sync + 'a> SqlPlanner<'a, S> {
             }
             ExtStatement::RecoverTenant(stmt) => self.recovertenant_to_plan(stmt),
             ExtStatement::RecoverDatabase(stmt) => self.recoverdatabase_to_plan(stmt, session),
+            ExtStatement::BackupDatabase(stmt) => self.backup_database_to_plan(stmt, session),
+            ExtStatement::RestoreDatabase(stmt) => self.restore_database_to_plan(stmt, session),
             ExtStatement::ShowReplicas => self.show_replicas_to_plan(),
             ExtStatement::ReplicaDestory(stmt) => self.replica_destory_to_plan(stmt),
             ExtStatement::ReplicaAdd(stmt) => self.replica_add_to_plan(stmt),
             ExtStatement::ReplicaRemove(stmt) => self.replica_remove_to_plan(stmt),
             ExtStatement::ReplicaPromote(stmt) => self.replica_promote_to_plan(stmt),
+            ExtStatement::Rebalance(stmt) => self.rebalance_to_plan(stmt),
+            ExtStatement::ShowRebalance => self.rebalance_cmd_to_plan(DDLPlan::ShowRebalance),
+            ExtStatement::PauseRebalance => self.rebalance_cmd_to_plan(DDLPlan::PauseRebalance),
+            ExtStatement::ResumeRebalance => self.rebalance_cmd_to_plan(DDLPlan::ResumeRebalance),
+            ExtStatement::DecommissionNode(stmt) => self.decommission_node_to_plan(stmt),
         }
     }
 
@@ -246,6 +289,36 @@ impl<'a, S: ContextProviderExtension + Send + Sync + 'a> SqlPlanner<'a, S> {
         match stmt {
             Statement::Query(_) => {
                 let df_plan = self.df_planner.sql_statement_to_plan(stmt)?;
+                let df_plan = self.rewrite_materialized_views(df_plan, session)?;
+                let plan = Plan::Query(QueryPlan { df_plan });
+
+                // privileges
+                let access_databases = self.schema_provider.reset_access_databases();
+                let privileges = databases_privileges(
+                    DatabasePrivilege::Read,
+                    *session.tenant_id(),
+                    access_databases,
+                );
+                Ok(PlanWithPrivileges { plan, privileges })
+            }
+            // only queries can be prepared
+            Statement::Prepare { ref statement, .. }
+                if matches!(statement.as_ref(), Statement::Query(_)) =>
+            {
+                let df_plan = match self.df_planner.sql_statement_to_plan(stmt)? {
+                    LogicalPlan::Prepare(Prepare {
+                        name,
+                        data_types,
+                        input,
+                    }) => LogicalPlan::Prepare(Prepare {
+                        name,
+                        data_types,
+                        input: Arc::new(
+                            self.rewrite_materialized_views(input.as_ref().clone(), session)?,
+                        ),
+                    }),
+                    df_plan => df_plan,
+                };
                 let plan = Plan::Query(QueryPlan { df_plan });
 
                 // privileges
@@ -323,6 +396,60 @@ impl<'a, S: ContextProviderExtension + Send + Sync + 'a> SqlPlanner<'a, S> {
         }
     }
 
+    /// Read the results of aggregates from the materialized views of their source tables,
+    /// unless the session turns it off, the results don't include late data
+    fn rewrite_materialized_views(
+        &self,
+        plan: LogicalPlan,
+        session: &SessionCtx,
+    ) -> QueryResult<LogicalPlan> {
+        let rewrite = session
+            .inner()
+            .config()
+            .get_extension::<MaterializedViewRewrite>()
+            .map(|r| *r)
+            .unwrap_or_default();
+        if rewrite == MaterializedViewRewrite::Off {
+            return Ok(plan);
+        }
+
+        let mut sources = HashSet::new();
+        plan.apply(&mut |plan| {
+            if let LogicalPlan::TableScan(TableScan { source, .. }) = plan {
+                if let Some(adapter) = source.as_any().downcast_ref::<TableSourceAdapter>() {
+                    sources.insert((
+                        adapter.database_name().to_string(),
+                        adapter.table_name().to_string(),
+                    ));
+                }
+            }
+            Ok(VisitRecursion::Continue)
+        })?;
+
+        let mut candidates = vec![];
+        for (database, table) in sources {
+            for view in self.schema_provider.materialized_views(&database, &table)? {
+                let table = self.get_table_source(TableReference::partial(
+                    database.as_str(),
+                    view.table.as_str(),
+                ))?;
+                candidates.push(MaterializedViewCandidate {
+                    database: database.clone(),
+                    view,
+                    table,
+                });
+            }
+        }
+        if candidates.is_empty() {
+            return Ok(plan);
+        }
+
+        let rules: Vec<Arc<dyn OptimizerRule + Send + Sync>> =
+            vec![Arc::new(RewriteMaterializedView::new(candidates))];
+        let optimizer = Optimizer::with_rules(rules);
+        Ok(optimizer.optimize(&plan, &OptimizerContext::new(), |_, _| {})?)
+    }
+
     async fn update_to_plan(
         &self,
         session: &SessionCtx,
@@ -340,6 +467,11 @@ impl<'a, S: ContextProviderExtension + Send + Sync + 'a> SqlPlanner<'a, S> {
         };
 
         let table_ref = normalize_sql_object_name(table_name)?;
+        check_table_writable(
+            &table_ref
+                .clone()
+                .resolve_object(session.tenant(), session.default_database())?,
+        )?;
         let table_owned_reference = table_ref.to_owned_reference();
         let table_source = self.get_table_source(table_ref)?;
 
@@ -469,6 +601,11 @@ impl<'a, S: ContextProviderExtension + Send + Sync + 'a> SqlPlanner<'a, S> {
         );
 
         let table_ref = normalize_sql_object_name(sql_object_name)?;
+        check_table_writable(
+            &table_ref
+                .clone()
+                .resolve_object(session.tenant(), session.default_database())?,
+        )?;
         let columns = sql_column_names
             .into_iter()
             .map(normalize_ident)
@@ -569,6 +706,7 @@ impl<'a, S: ContextProviderExtension + Send + Sync + 'a> SqlPlanner<'a, S> {
         valid_delete(schema.as_ref(), &selection)?;
 
         let table_name = object_name_to_resolved_table(session, table_name)?;
+        check_table_writable(&table_name)?;
         let database = table_name.database().to_string();
         let plan = Plan::DML(DMLPlan::DeleteFromTable(DeleteFromTable {
             table_name,
@@ -600,6 +738,7 @@ impl<'a, S: ContextProviderExtension + Send + Sync + 'a> SqlPlanner<'a, S> {
         let (plan, privilege) = match obj_type {
             DatabaseObjectType::Table => {
                 let table = object_name_to_resolved_table(session, object_name)?;
+                check_table_writable(&table)?;
                 let database_name = table.database().to_string();
                 (
                     DDLPlan::DropDatabaseObject(DropDatabaseObject {
@@ -616,6 +755,24 @@ impl<'a, S: ContextProviderExtension + Send + Sync + 'a> SqlPlanner<'a, S> {
                     ),
                 )
             }
+            DatabaseObjectType::View => {
+                let view = object_name_to_resolved_table(session, object_name)?;
+                let database_name = view.database().to_string();
+                (
+                    DDLPlan::DropDatabaseObject(DropDatabaseObject {
+                        if_exist,
+                        object_name: view,
+                        obj_type: DatabaseObjectType::View,
+                    }),
+                    Privilege::TenantObject(
+                        TenantObjectPrivilege::Database(
+                            DatabasePrivilege::Full,
+                            Some(database_name),
+                        ),
+                        Some(tenant_id),
+                    ),
+                )
+            }
         };
 
         Ok(PlanWithPrivileges {
@@ -945,6 +1102,290 @@ impl<'a, S: ContextProviderExtension + Send + Sync + 'a> SqlPlanner<'a, S> {
         })
     }
 
+    fn create_view_to_plan(
+        &self,
+        statement: ASTCreateView,
+        session: &SessionCtx,
+    ) -> QueryResult<PlanWithPrivileges> {
+        let ASTCreateView {
+            name,
+            or_replace,
+            query,
+        } = statement;
+
+        let resolved_table = object_name_to_resolved_table(session, name)?;
+        let database_name = resolved_table.database().to_string();
+
+        // Plan the query the same way it is planned when the view is queried
+        self.schema_provider.enter_view(&resolved_table)?;
+        let df_plan = self
+            .df_planner
+            .sql_statement_to_plan(Statement::Query(query.clone()));
+        self.schema_provider.exit_view();
+        let df_plan = df_plan?;
+
+        // privileges: read the underlying tables, full on the database of the view
+        let access_databases = self.schema_provider.reset_access_databases();
+        let mut privileges = databases_privileges(
+            DatabasePrivilege::Read,
+            *session.tenant_id(),
+            access_databases,
+        );
+        privileges.push(Privilege::TenantObject(
+            TenantObjectPrivilege::Database(DatabasePrivilege::Full, Some(database_name)),
+            Some(*session.tenant_id()),
+        ));
+
+        let plan = Plan::DDL(DDLPlan::CreateView(CreateView {
+            name: resolved_table,
+            or_replace,
+            definition: query.to_string(),
+            schema: df_plan.schema().as_ref().into(),
+        }));
+
+        Ok(PlanWithPrivileges { plan, privileges })
+    }
+
+    fn create_materialized_view_to_plan(
+        &self,
+        statement: ASTCreateMaterializedView,
+        session: &SessionCtx,
+    ) -> QueryResult<PlanWithPrivileges> {
+        let ASTCreateMaterializedView {
+            name,
+            if_not_exists,
+            query,
+        } = statement;
+
+        let resolved_table = object_name_to_resolved_table(session, name)?;
+        let tenant_name = resolved_table.tenant().to_string();
+        let database_name = resolved_table.database().to_string();
+        let view_name = resolved_table.table().to_string();
+
+        self.schema_provider.enter_view(&resolved_table)?;
+        let df_plan = self
+            .df_planner
+            .sql_statement_to_plan(Statement::Query(query.clone()));
+        self.schema_provider.exit_view();
+        let df_plan = df_plan?;
+
+        // privileges: read the underlying tables, full on the database of the view
+        let access_databases = self.schema_provider.reset_access_databases();
+        let mut privileges = databases_privileges(
+            DatabasePrivilege::Read,
+            *session.tenant_id(),
+            access_databases,
+        );
+        privileges.push(Privilege::TenantObject(
+            TenantObjectPrivilege::Database(DatabasePrivilege::Full, Some(database_name.clone())),
+            Some(*session.tenant_id()),
+        ));
+
+        let unsupported = || {
+            QueryError::Semantic {
+            err: format!(
+                "Materialized view {resolved_table} must aggregate a table of its database, grouped by {TIME_WINDOW}({TIME_FIELD}, interval) and tags"
+            ),
+        }
+        };
+
+        let (projection, aggregate, source) = match &df_plan {
+            LogicalPlan::Projection(projection) => match projection.input.as_ref() {
+                LogicalPlan::Aggregate(aggregate) => match aggregate.input.as_ref() {
+                    LogicalPlan::TableScan(scan) => (projection, aggregate, scan),
+                    _ => return Err(unsupported()),
+                },
+                _ => return Err(unsupported()),
+            },
+            _ => return Err(unsupported()),
+        };
+        let adapter = source_downcast_adapter(&source.source)?;
+        let source_schema = match adapter.table_handle() {
+            TableHandle::Tskv(table) if adapter.database_name() == database_name => {
+                table.table_schema()
+            }
+            _ => return Err(unsupported()),
+        };
+
+        // exactly one time window over the time column, the others are tags
+        let mut window = None;
+        for (i, expr) in aggregate.group_expr.iter().enumerate() {
+            match expr {
+                Expr::ScalarUDF(ScalarUDF { fun, args })
+                    if fun.name == TIME_WINDOW
+                        && args.len() == 2
+                        && matches!(&args[0], Expr::Column(c) if c.name == TIME_FIELD)
+                        && window.is_none() =>
+                {
+                    window = Some(i);
+                }
+                Expr::Column(c)
+                    if source_schema
+                        .column(&c.name)
+                        .map(|c| c.column_type.is_tag())
+                        .unwrap_or(false) => {}
+                _ => return Err(unsupported()),
+            }
+        }
+        let window = window.ok_or_else(unsupported)?;
+
+        // the columns of the backing table storing the items of the projection
+        let id_generator = SeqIdGenerator::default();
+        let unit: TimeUnit = self.get_db_precision(&database_name)?.into();
+        let mut schema = vec![TableColumn::new_time_column(
+            id_generator.next_id() as ColumnId,
+            unit,
+        )];
+        let mut columns = Vec::with_capacity(projection.expr.len());
+        let mut projected_groups = HashSet::new();
+        let mut aggregates = vec![];
+        let group_len = aggregate.group_expr.len();
+        for expr in &projection.expr {
+            let (column, alias) = match expr {
+                Expr::Column(c) => (c, None),
+                Expr::Alias(e, alias) => match e.as_ref() {
+                    Expr::Column(c) => (c, Some(alias)),
+                    _ => return Err(unsupported()),
+                },
+                _ => return Err(unsupported()),
+            };
+            let index = aggregate.schema.index_of_column(column)?;
+            if index < group_len && !projected_groups.insert(index) {
+                return Err(QueryError::SameColumnName {
+                    column: column.name.clone(),
+                });
+            }
+
+            if index == window {
+                columns.push(None);
+            } else if index < group_len {
+                let tag = match &aggregate.group_expr[index] {
+                    Expr::Column(c) => c.name.clone(),
+                    _ => return Err(unsupported()),
+                };
+                schema.push(TableColumn::new_tag_column(
+                    id_generator.next_id() as ColumnId,
+                    tag.clone(),
+                ));
+                columns.push(Some(tag));
+            } else {
+                let aggr_expr = &aggregate.aggr_expr[index - group_len];
+                let name = match alias {
+                    Some(alias) => alias.clone(),
+                    None => unqualified_name(aggr_expr)?,
+                };
+                let value_type = match aggregate.schema.field(index).data_type() {
+                    DataType::Float64 => ValueType::Float,
+                    DataType::Int64 => ValueType::Integer,
+                    DataType::UInt64 => ValueType::Unsigned,
+                    DataType::Boolean => ValueType::Boolean,
+                    DataType::Utf8 => ValueType::String,
+                    other => {
+                        return Err(QueryError::Semantic {
+                            err: format!(
+                            "Materialized view {resolved_table} can't store {name} of type {other}"
+                        ),
+                        })
+                    }
+                };
+                schema.push(TableColumn::new(
+                    id_generator.next_id() as ColumnId,
+                    name.clone(),
+                    ColumnType::Field(value_type),
+                    Encoding::Default,
+                ));
+                aggregates.push((unqualified_name(aggr_expr)?, name.clone()));
+                columns.push(Some(name));
+            }
+        }
+        if (0..group_len).any(|i| !projected_groups.contains(&i)) {
+            return Err(QueryError::Semantic {
+                err: format!(
+                    "Materialized view {resolved_table} must select all group by expressions"
+                ),
+            });
+        }
+        let mut column_names = HashSet::new();
+        for col in schema.iter() {
+            if !column_names.insert(col.name.as_str()) {
+                return Err(QueryError::SameColumnName {
+                    column: col.name.to_string(),
+                });
+            }
+        }
+
+        let table = ResolvedTable::new(
+            tenant_name.clone(),
+            database_name.clone(),
+            format!("__mv_{view_name}"),
+        );
+        let stream_table = ResolvedTable::new(
+            tenant_name.clone(),
+            database_name.clone(),
+            format!("__mv_{view_name}_stream"),
+        );
+        let maintenance_query = materialized_view_query(&query, &table, &stream_table, &columns)?;
+
+        let table_schema = TskvTableSchema::new(
+            tenant_name.clone(),
+            database_name.clone(),
+            table.table().to_string(),
+            schema.clone(),
+        );
+        let view = ViewSchema {
+            tenant: tenant_name,
+            db: database_name.clone(),
+            name: view_name,
+            definition: query.to_string(),
+            schema: table_schema.to_arrow_schema().as_ref().clone(),
+            materialized: Some(MaterializedView {
+                source: adapter.table_name().to_string(),
+                table: table.table().to_string(),
+                stream_table: stream_table.table().to_string(),
+                query: maintenance_query,
+                group_by: aggregate
+                    .group_expr
+                    .iter()
+                    .map(unqualified_name)
+                    .collect::<DFResult<_>>()?,
+                aggregates,
+            }),
+        };
+
+        let extra_options = HashMap::from([
+            (STREAM_DB_KEY.to_string(), database_name),
+            (
+                STREAM_TABLE_KEY.to_string(),
+                adapter.table_name().to_string(),
+            ),
+            (EVENT_TIME_COLUMN_OPTION.to_string(), TIME_FIELD.to_string()),
+        ]);
+        let stream_table = CreateStreamTable {
+            if_not_exists: true,
+            name: stream_table,
+            schema: source_schema.to_arrow_schema().as_ref().clone(),
+            watermark: Watermark {
+                column: TIME_FIELD.to_string(),
+                delay: Default::default(),
+            },
+            stream_type: TSKV_STREAM_PROVIDER.to_string(),
+            extra_options,
+        };
+
+        let plan = Plan::DDL(DDLPlan::CreateMaterializedView(CreateMaterializedView {
+            view,
+            if_not_exists,
+            table: CreateTable {
+                schema,
+                name: table,
+                if_not_exists: true,
+            },
+            stream_table,
+        }));
+
+        Ok(PlanWithPrivileges { plan, privileges })
+    }
+
     fn column_opt_to_table_column(
         &self,
         column_opt: ColumnOption,
@@ -1062,6 +1503,7 @@ impl<'a, S: ContextProviderExtension + Send + Sync + 'a> SqlPlanner<'a, S> {
         let table_name = table_ref
             .clone()
             .resolve_object(session.tenant(), session.default_database())?;
+        check_table_writable(&table_name)?;
         let handle = self.get_table_handle(table_ref)?;
         let table_schema = match handle {
             TableHandle::Tskv(t) => t.table_schema(),
@@ -1271,6 +1713,43 @@ impl<'a, S: ContextProviderExtension + Send + Sync + 'a> SqlPlanner<'a, S> {
         })
     }
 
+    fn show_views_to_plan(
+        &self,
+        database: Option<Ident>,
+        session: &SessionCtx,
+    ) -> QueryResult<PlanWithPrivileges> {
+        let db_name = database
+            .map(normalize_ident)
+            .unwrap_or_else(|| session.default_database().to_string());
+
+        let projections = vec![col(TABLES_TABLE_NAME)];
+        let sorts = vec![col(TABLES_TABLE_NAME).sort(true, true)];
+
+        let table_ref = TableReference::partial(INFORMATION_SCHEMA, INFORMATION_SCHEMA_TABLES);
+        let table_source = self.get_table_source(table_ref.clone())?;
+
+        let df_plan = LogicalPlanBuilder::scan(table_ref, table_source, None)?
+            .filter(
+                col(TABLES_TABLE_DATABASE)
+                    .eq(lit(&db_name))
+                    .and(col(TABLES_TABLE_TYPE).eq(lit("VIEW"))),
+            )?
+            .project(projections)?
+            .sort(sorts)?
+            .build()?;
+
+        let plan = Plan::Query(QueryPlan { df_plan });
+
+        // privileges
+        Ok(PlanWithPrivileges {
+            plan,
+            privileges: vec![Privilege::TenantObject(
+                TenantObjectPrivilege::Database(DatabasePrivilege::Read, Some(db_name)),
+                Some(*session.tenant_id()),
+            )],
+        })
+    }
+
     fn show_tag_body(
         &self,
         session: &SessionCtx,
@@ -1377,6 +1856,38 @@ impl<'a, S: ContextProviderExtension + Send + Sync + 'a> SqlPlanner<'a, S> {
         )
     }
 
+    fn show_cardinality_to_plan(
+        &self,
+        stmt: ASTShowCardinality,
+        session: &SessionCtx,
+    ) -> QueryResult<PlanWithPrivileges> {
+        let ASTShowCardinality {
+            database_name,
+            table,
+        } = stmt;
+
+        let table_name = database_name
+            .map(|e| ObjectName(vec![e, table.clone()]))
+            .unwrap_or_else(|| ObjectName(vec![table]));
+        let table_ref = normalize_sql_object_name(table_name)?;
+
+        let table_schema = self.get_tskv_schema(table_ref.clone())?;
+
+        let (source_plan, _) = self.create_table_relation(table_ref, None, &Default::default())?;
+
+        let df_plan =
+            show_cardinality_projections(&table_schema, LogicalPlanBuilder::from(source_plan))?;
+        let db_name = &table_schema.db;
+
+        Ok(PlanWithPrivileges {
+            plan: Plan::Query(QueryPlan { df_plan }),
+            privileges: vec![Privilege::TenantObject(
+                TenantObjectPrivilege::Database(DatabasePrivilege::Read, Some(db_name.to_string())),
+                Some(*session.tenant_id()),
+            )],
+        })
+    }
+
     fn database_to_plan(
         &self,
         stmt: ASTCreateDatabase,
@@ -1506,7 +2017,14 @@ impl<'a, S: ContextProviderExtension + Send + Sync + 'a> SqlPlanner<'a, S> {
         stmt: ASTAlterDatabase,
         session: &SessionCtx,
     ) -> QueryResult<PlanWithPrivileges> {
-        let ASTAlterDatabase { name, options } = stmt;
+        let ASTAlterDatabase { name, mut options } = stmt;
+        let unset_replica_placement = options
+            .replica_placement
+            .as_deref()
+            .is_some_and(|p| p.eq_ignore_ascii_case("none"));
+        if unset_replica_placement {
+            options.replica_placement = None;
+        }
         let options = self.make_database_option(options)?;
         if options.precision().is_some() {
             return Err(QueryError::Semantic {
@@ -1517,6 +2035,7 @@ impl<'a, S: ContextProviderExtension + Send + Sync + 'a> SqlPlanner<'a, S> {
         let plan = Plan::DDL(DDLPlan::AlterDatabase(AlterDatabase {
             database_name: database_name.clone(),
             database_options: options,
+            unset_replica_placement,
         }));
         // privileges
         let tenant_id = *session.tenant_id();
@@ -1554,6 +2073,16 @@ impl<'a, S: ContextProviderExtension + Send + Sync + 'a> SqlPlanner<'a, S> {
                 }
             })?);
         }
+        if let Some(cold_after) = options.cold_after {
+            plan_options.with_cold_after(self.str_to_duration(&cold_after)?);
+        }
+        if let Some(replica_placement) = options.replica_placement {
+            plan_options.with_replica_placement(replica_placement.parse().map_err(|e| {
+                QueryError::Parser {
+                    source: ParserError::ParserError(e),
+                }
+            })?);
+        }
         Ok(plan_options)
     }
 
@@ -2069,6 +2598,68 @@ impl<'a, S: ContextProviderExtension + Send + Sync + 'a> SqlPlanner<'a, S> {
         })
     }
 
+    fn rebuild_index_to_plan(&self, stmt: ASTRebuildIndex) -> QueryResult<PlanWithPrivileges> {
+        let ASTRebuildIndex { vnode_id } = stmt;
+
+        let plan = Plan::DDL(DDLPlan::RebuildIndex(RebuildIndex { vnode_id }));
+        Ok(PlanWithPrivileges {
+            plan,
+            privileges: vec![Privilege::Global(GlobalPrivilege::System)],
+        })
+    }
+
+    fn backup_database_to_plan(
+        &self,
+        stmt: ASTBackupDatabase,
+        session: &SessionCtx,
+    ) -> QueryResult<PlanWithPrivileges> {
+        let ASTBackupDatabase {
+            object_name,
+            location,
+            incremental,
+        } = stmt;
+
+        let plan = Plan::DDL(DDLPlan::BackupDatabase(BackupDatabase {
+            tenant_name: session.tenant().to_string(),
+            db_name: normalize_ident(object_name),
+            location: location.path,
+            connection_options: location.connection_options,
+            incremental,
+        }));
+        Ok(PlanWithPrivileges {
+            plan,
+            privileges: vec![Privilege::Global(GlobalPrivilege::System)],
+        })
+    }
+
+    fn restore_database_to_plan(
+        &self,
+        stmt: ASTRestoreDatabase,
+        session: &SessionCtx,
+    ) -> QueryResult<PlanWithPrivileges> {
+        let ASTRestoreDatabase {
+            object_name,
+            location,
+            new_name,
+        } = stmt;
+
+        let db_name = normalize_ident(object_name);
+        let new_db_name = new_name
+            .map(normalize_ident)
+            .unwrap_or_else(|| db_name.clone());
+        let plan = Plan::DDL(DDLPlan::RestoreDatabase(RestoreDatabase {
+            tenant_name: session.tenant().to_string(),
+            db_name,
+            new_db_name,
+            location: location.path,
+            connection_options: location.connection_options,
+        }));
+        Ok(PlanWithPrivileges {
+            plan,
+            privileges: vec![Privilege::Global(GlobalPrivilege::System)],
+        })
+    }
+
     fn show_replicas_to_plan(&self) -> QueryResult<PlanWithPrivileges> {
         let plan = Plan::DDL(DDLPlan::ShowReplicas);
         Ok(PlanWithPrivileges {
@@ -2077,6 +2668,32 @@ impl<'a, S: ContextProviderExtension + Send + Sync + 'a> SqlPlanner<'a, S> {
         })
     }
 
+    fn rebalance_to_plan(&self, stmt: ASTRebalance) -> QueryResult<PlanWithPrivileges> {
+        let ASTRebalance { dry_run } = stmt;
+
+        self.rebalance_cmd_to_plan(DDLPlan::Rebalance(Rebalance { dry_run }))
+    }
+
+    fn rebalance_cmd_to_plan(&self, plan: DDLPlan) -> QueryResult<PlanWithPrivileges> {
+        Ok(PlanWithPrivileges {
+            plan: Plan::DDL(plan),
+            privileges: vec![Privilege::Global(GlobalPrivilege::System)],
+        })
+    }
+
+    fn decommission_node_to_plan(
+        &self,
+        stmt: ASTDecommissionNode,
+    ) -> QueryResult<PlanWithPrivileges> {
+        let ASTDecommissionNode { node_id } = stmt;
+
+        let plan = Plan::DDL(DDLPlan::DecommissionNode(DecommissionNode { node_id }));
+        Ok(PlanWithPrivileges {
+            plan,
+            privileges: vec![Privilege::Global(GlobalPrivilege::System)],
+        })
+    }
+
     fn replica_destory_to_plan(&self, stmt: ASTReplicaDestory) -> QueryResult<PlanWithPrivileges> {
         let ASTReplicaDestory { replica_id } = stmt;
 
@@ -2355,6 +2972,12 @@ impl<'a, S: ContextProviderExtension + Send + Sync + 'a> SqlPlanner<'a, S> {
             connection_options,
         } = location;
 
+        if file_format_options.file_type == ExternalFileType::Orc {
+            return Err(QueryError::NotImplemented {
+                err: "writing ORC files is not supported".to_string(),
+            });
+        }
+
         let table_path = ListingTableUrl::parse(path)?;
 
         // 1. Build and register object store
@@ -2595,25 +3218,17 @@ fn build_file_extension_and_format(
         delimiter,
         with_header,
         file_compression_type,
+        compression,
     } = file_format_options;
-    let file_extension = file_type.get_ext_with_compression(file_compression_type.to_owned())?;
-    let file_format: Arc<dyn FileFormat> = match file_type {
-        FileType::CSV => Arc::new(
-            CsvFormat::default()
-                .with_has_header(with_header)
-                .with_delimiter(delimiter as u8)
-                .with_file_compression_type(file_compression_type),
-        ),
-        FileType::PARQUET => Arc::new(ParquetFormat::default()),
-        FileType::AVRO => Arc::new(AvroFormat),
-        FileType::JSON => {
-            Arc::new(JsonFormat::default().with_file_compression_type(file_compression_type))
-        }
-        FileType::ARROW => {
-            return Err(DataFusionError::NotImplemented(
-                "Arrow file format is not supported".to_string(),
-            ))
-        }
+    let (file_extension, file_format) = build_file_format(
+        &file_type,
+        file_compression_type,
+        with_header,
+        delimiter as u8,
+    )?;
+    let file_format: Arc<dyn FileFormat> = match compression {
+        Some(compression) => Arc::new(CompressedFileFormat::new(file_format, compression)),
+        None => file_format,
     };
 
     Ok((file_extension, file_format))
@@ -2778,7 +3393,118 @@ fn show_tag_value_projections(
     Ok(union_distinct)
 }
 
-fn check_privilege(user: &User, privileges: Vec<Privilege<Oid>>) -> QueryResult<()> {
+/// Build the plan of `SHOW CARDINALITY`, the output contains the cardinality of series
+/// (the row whose tag_key is null) and the cardinality of each tag key.
+///
+/// The input only projects tag columns, so it will be rewritten to a tag scan,
+/// `exact_cardinality` counts the distinct values, and `estimated_cardinality` is
+/// estimated by HyperLogLog.
+fn show_cardinality_projections(
+    table_schema: &TskvTableSchema,
+    plan_builder: LogicalPlanBuilder,
+) -> QueryResult<LogicalPlan> {
+    let table_column = "table";
+    let tag_key_column = "tag_key";
+    let exact_column = "exact_cardinality";
+    let estimated_column = "estimated_cardinality";
+
+    let tags = table_schema
+        .columns()
+        .iter()
+        .filter(|column| column.column_type.is_tag())
+        .collect::<Vec<&TableColumn>>();
+
+    if tags.is_empty() {
+        return Ok(LogicalPlan::EmptyRelation(EmptyRelation {
+            produce_one_row: false,
+            schema: Arc::new(DFSchema::new_with_metadata(
+                vec![
+                    DFField::new_unqualified(table_column, DataType::Utf8, false),
+                    DFField::new_unqualified(tag_key_column, DataType::Utf8, true),
+                    DFField::new_unqualified(exact_column, DataType::UInt64, false),
+                    DFField::new_unqualified(estimated_column, DataType::UInt64, false),
+                ],
+                HashMap::new(),
+            )?),
+        }));
+    }
+
+    let tag_columns = tags
+        .iter()
+        .map(|tag| col(Column::new_unqualified(&tag.name)))
+        .collect::<Vec<Expr>>();
+
+    // the series key is the same as the result of SHOW SERIES
+    let tag_concat_expr_iter = tags.iter().map(|tag| {
+        let column_expr = Box::new(Expr::Column(Column::new_unqualified(&tag.name)));
+        let is_null_expr = Box::new(column_expr.clone().is_null());
+        let when_then_expr = vec![(is_null_expr, Box::new(lit(ScalarValue::Null)))];
+        let else_expr = Some(Box::new(Expr::BinaryExpr(BinaryExpr {
+            left: Box::new(lit(format!("{}=", &tag.name))),
+            op: Operator::StringConcat,
+            right: column_expr,
+        })));
+        Expr::Case(Case::new(None, when_then_expr, else_expr))
+    });
+    let concat_ws_args = iter::once(lit(","))
+        .chain(iter::once(lit(&table_schema.name)))
+        .chain(tag_concat_expr_iter)
+        .collect::<Vec<Expr>>();
+    let series_key = Expr::ScalarFunction(ScalarFunction::new(
+        BuiltinScalarFunction::ConcatWithSeparator,
+        concat_ws_args,
+    ));
+
+    let cardinality_plan = |projection: Vec<Expr>, value: Expr, tag_key: ScalarValue| {
+        plan_builder
+            .clone()
+            .project(projection)?
+            .aggregate(
+                Vec::<Expr>::new(),
+                vec![
+                    cast(count_distinct(value.clone()), DataType::UInt64).alias(exact_column),
+                    approx_distinct(value).alias(estimated_column),
+                ],
+            )?
+            .project(vec![
+                lit(&table_schema.name).alias(table_column),
+                lit(tag_key).alias(tag_key_column),
+                col(exact_column),
+                col(estimated_column),
+            ])?
+            .build()
+    };
+
+    let mut plan = LogicalPlanBuilder::from(cardinality_plan(
+        tag_columns,
+        series_key,
+        ScalarValue::Utf8(None),
+    )?);
+    for tag in tags {
+        let tag_column = col(Column::new_unqualified(&tag.name));
+        plan = plan.union(cardinality_plan(
+            vec![tag_column.clone()],
+            tag_column,
+            ScalarValue::Utf8(Some(tag.name.clone())),
+        )?)?;
+    }
+
+    Ok(plan
+        .sort(vec![col(tag_key_column).sort(true, true)])?
+        .build()?)
+}
+
+/// Tables only written by the system can't be modified by statements of any user.
+fn check_table_writable(table: &ResolvedTable) -> QueryResult<()> {
+    if is_read_only_table(table.tenant(), table.database(), table.table()) {
+        return Err(QueryError::ReadOnlyTable {
+            table: table.to_string(),
+        });
+    }
+    Ok(())
+}
+
+pub(crate) fn check_privilege(user: &User, privileges: Vec<Privilege<Oid>>) -> QueryResult<()> {
     let privileges_str = privileges
         .iter()
         .map(|e| format!("{:?}", e))
@@ -2796,6 +3522,86 @@ fn check_privilege(user: &User, privileges: Vec<Privilege<Oid>>) -> QueryResult<
     Ok(())
 }
 
+/// Build the stream query keeping the results of a materialized view up-to-date.
+///
+/// The source of `query` is replaced by `stream_table`, the results are written to `table`.
+/// `columns` are the columns of `table` storing the items of the projection, `None` for the
+/// time window whose start is stored as the time column.
+fn materialized_view_query(
+    query: &Query,
+    table: &ResolvedTable,
+    stream_table: &ResolvedTable,
+    columns: &[Option<String>],
+) -> QueryResult<String> {
+    const WINDOW_ALIAS: &str = "__window";
+    let quote = |s: &str| Ident::with_quote('"', s);
+    let unsupported = || QueryError::Semantic {
+        err: format!("Unsupported materialized view definition: {query}"),
+    };
+
+    let mut query = query.clone();
+    let select = match query.body.as_mut() {
+        SetExpr::Select(select) => select,
+        _ => return Err(unsupported()),
+    };
+
+    // read the new rows of the source, keeping its name as the qualifier of columns
+    match select.from.as_mut_slice() {
+        [TableWithJoins {
+            relation: TableFactor::Table { name, alias, .. },
+            joins,
+        }] if joins.is_empty() => {
+            let qualifier = match alias {
+                Some(alias) => alias.name.clone(),
+                None => name.0.last().cloned().ok_or_else(unsupported)?,
+            };
+            *alias = Some(TableAlias {
+                name: qualifier,
+                columns: vec![],
+            });
+            *name = ObjectName(vec![
+                quote(stream_table.database()),
+                quote(stream_table.table()),
+            ]);
+        }
+        _ => return Err(unsupported()),
+    }
+
+    let mut target_columns = Vec::with_capacity(columns.len());
+    let mut outer_projection = Vec::with_capacity(columns.len());
+    for (i, item) in select.projection.iter_mut().enumerate() {
+        let expr = match item {
+            SelectItem::UnnamedExpr(expr) | SelectItem::ExprWithAlias { expr, .. } => expr.clone(),
+            _ => return Err(unsupported()),
+        };
+        let alias = match columns.get(i) {
+            Some(Some(column)) => {
+                target_columns.push(quote(column).to_string());
+                outer_projection.push(quote(column).to_string());
+                column.as_str()
+            }
+            Some(None) => {
+                target_columns.push(quote(TIME_FIELD).to_string());
+                outer_projection.push(format!("{}.{WINDOW_START}", quote(WINDOW_ALIAS)));
+                WINDOW_ALIAS
+            }
+            _ => return Err(unsupported()),
+        };
+        *item = SelectItem::ExprWithAlias {
+            expr,
+            alias: quote(alias),
+        };
+    }
+
+    Ok(format!(
+        "INSERT INTO {}.{} ({}) SELECT {} FROM ({query}) AS \"__mv\"",
+        quote(table.database()),
+        quote(table.table()),
+        target_columns.join(", "),
+        outer_projection.join(", "),
+    ))
+}
+
 fn databases_privileges(
     db_priv: DatabasePrivilege,
     tenant_id: Oid,
@@ -2989,6 +3795,7 @@ mod tests {
     use meta::error::MetaError;
     use models::auth::user::{User, UserDesc, UserOptions};
     use models::codec::Encoding;
+    use models::meta_data::ReplicaPlacement;
     use models::schema::{ColumnType, Tenant};
     use models::ValueType;
     use spi::query::session::SessionCtxFactory;
@@ -3268,13 +4075,43 @@ mod tests {
         if let Plan::DDL(DDLPlan::CreateDatabase(create)) = plan.plan {
             let ans = format!("{:?}", create);
             println!("{ans}");
-            let expected = r#"CreateDatabase { name: "test", if_not_exists: false, options: DatabaseOptions { ttl: Some(Duration { time_num: 10, unit: Day }), shard_num: Some(5), vnode_duration: Some(Duration { time_num: 3, unit: Day }), replica: Some(10), precision: Some(US), db_is_hidden: false } }"#;
+            let expected = r#"CreateDatabase { name: "test", if_not_exists: false, options: DatabaseOptions { ttl: Some(Duration { time_num: 10, unit: Day }), shard_num: Some(5), vnode_duration: Some(Duration { time_num: 3, unit: Day }), replica: Some(10), precision: Some(US), db_is_hidden: false, replica_placement: None, cold_after: None } }"#;
             assert_eq!(ans, expected);
         } else {
             panic!("expected create table plan")
         }
     }
 
+    #[tokio::test]
+    async fn test_alter_database_replica_placement() {
+        let test = MockContext {};
+        let planner = SqlPlanner::new(&test);
+        for (sql, placement, unset) in [
+            (
+                "ALTER DATABASE test SET REPLICA_PLACEMENT 'spread:zone';",
+                Some("spread:zone".parse::<ReplicaPlacement>().unwrap()),
+                false,
+            ),
+            (
+                "ALTER DATABASE test SET REPLICA_PLACEMENT 'NONE';",
+                None,
+                true,
+            ),
+        ] {
+            let mut statements = ExtParser::parse_sql(sql).unwrap();
+            let plan = planner
+                .statement_to_plan(statements.pop_back().unwrap(), &session(), false)
+                .await
+                .unwrap();
+            if let Plan::DDL(DDLPlan::AlterDatabase(alter)) = plan.plan {
+                assert_eq!(*alter.database_options.replica_placement(), placement);
+                assert_eq!(alter.unset_replica_placement, unset);
+            } else {
+                panic!("expected alter database plan")
+            }
+        }
+    }
+
     #[tokio::test]
     async fn test_create_table_filed_name_same() {
         let sql = "CREATE TABLE air (visibility DOUBLE,temperature DOUBLE,pressure DOUBLE,pressure DOUBLE,TAGS(station));";
@@ -3400,4 +4237,244 @@ mod tests {
             _ => panic!(),
         }
     }
+
+    #[test]
+    fn test_materialized_view_query() {
+        let sql = "SELECT time_window(time, '1m'), station, avg(temperature) AS avg_t \
+                   FROM air GROUP BY time_window(time, '1m'), station";
+        let query = match ExtParser::parse_sql(sql).unwrap().pop_front() {
+            Some(ExtStatement::SqlStatement(stmt)) => match *stmt {
+                Statement::Query(query) => query,
+                _ => panic!("expected query"),
+            },
+            _ => panic!("expected sql statement"),
+        };
+        let table = ResolvedTable::new("cnosdb".into(), "public".into(), "__mv_v".into());
+        let stream_table =
+            ResolvedTable::new("cnosdb".into(), "public".into(), "__mv_v_stream".into());
+        let columns = vec![None, Some("station".to_string()), Some("avg_t".to_string())];
+
+        let sql = materialized_view_query(&query, &table, &stream_table, &columns).unwrap();
+        assert_eq!(
+            sql,
+            "INSERT INTO \"public\".\"__mv_v\" (\"time\", \"station\", \"avg_t\") \
+             SELECT \"__window\".start, \"station\", \"avg_t\" FROM (\
+             SELECT time_window(time, '1m') AS \"__window\", station AS \"station\", \
+             avg(temperature) AS \"avg_t\" FROM \"public\".\"__mv_v_stream\" AS air \
+             GROUP BY time_window(time, '1m'), station) AS \"__mv\""
+        );
+    }
+
+    /// Table `db1.test_tb`, views `public.v1` on it, `public.v2` on `v1`,
+    /// `db1.v4` on the unqualified `test_tb` and `public.v3` on itself.
+    #[derive(Default)]
+    struct MockViewContext {
+        config: datafusion::config::ConfigOptions,
+        access_databases: parking_lot::RwLock<crate::metadata::DatabaseSet>,
+        expanding_views: crate::metadata::ExpandingViews,
+    }
+
+    impl MockViewContext {
+        fn view_definition(name: &ResolvedTable) -> Option<&'static str> {
+            match (name.database(), name.table()) {
+                ("public", "v1") => Some("SELECT field_int FROM db1.test_tb"),
+                ("public", "v2") => Some("SELECT field_int FROM v1 WHERE field_int > 0"),
+                ("public", "v3") => Some("SELECT * FROM v3"),
+                ("db1", "v4") => Some("SELECT field_string FROM test_tb"),
+                _ => None,
+            }
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl ContextProviderExtension for MockViewContext {
+        async fn get_user(&self, _name: &str) -> std::result::Result<UserDesc, MetaError> {
+            todo!()
+        }
+
+        async fn get_tenant(&self, _name: &str) -> std::result::Result<Tenant, MetaError> {
+            todo!()
+        }
+
+        fn reset_access_databases(&self) -> crate::metadata::DatabaseSet {
+            std::mem::take(&mut *self.access_databases.write())
+        }
+
+        fn get_db_precision(&self, _name: &str) -> std::result::Result<Precision, MetaError> {
+            Ok(Precision::NS)
+        }
+
+        fn get_table_source(
+            &self,
+            table_ref: TableReference,
+        ) -> datafusion::common::Result<Arc<TableSourceAdapter>> {
+            let name = table_ref
+                .clone()
+                .resolve_object("cnosdb", &self.expanding_views.default_database("public"))?;
+            self.access_databases
+                .write()
+                .push_table(name.database(), name.table());
+
+            let table: Arc<dyn TableProvider> = match Self::view_definition(&name) {
+                Some(definition) => {
+                    let plan = crate::metadata::plan_view_definition(self, &name, definition)?;
+                    Arc::new(datafusion::datasource::view::ViewTable::try_new(
+                        plan,
+                        Some(definition.to_string()),
+                    )?)
+                }
+                None if name.database() == "db1" && name.table() == "test_tb" => {
+                    Arc::new(TestTable::new(Arc::new(Schema::new(vec![
+                        Field::new("field_int", DataType::Int32, false),
+                        Field::new("field_string", DataType::Utf8, false),
+                    ]))))
+                }
+                None => {
+                    return Err(DataFusionError::Plan(format!("Table not found: {}", name)));
+                }
+            };
+
+            Ok(Arc::new(TableSourceAdapter::try_new(
+                table_ref.to_owned_reference(),
+                name.database(),
+                name.table(),
+                table,
+            )?))
+        }
+
+        fn enter_view(&self, view: &ResolvedTable) -> datafusion::common::Result<()> {
+            self.expanding_views.enter(view)
+        }
+
+        fn exit_view(&self) {
+            self.expanding_views.exit()
+        }
+    }
+
+    impl ContextProvider for MockViewContext {
+        fn get_table_provider(&self, name: TableReference) -> Result<Arc<dyn TableSource>> {
+            Ok(self.get_table_source(name)?)
+        }
+
+        fn get_function_meta(&self, _name: &str) -> Option<Arc<ScalarUDF>> {
+            None
+        }
+
+        fn get_aggregate_meta(&self, _name: &str) -> Option<Arc<AggregateUDF>> {
+            None
+        }
+
+        fn get_variable_type(&self, _: &[String]) -> Option<DataType> {
+            None
+        }
+
+        fn options(&self) -> &datafusion::config::ConfigOptions {
+            &self.config
+        }
+
+        fn get_window_meta(&self, _name: &str) -> Option<Arc<datafusion::logical_expr::WindowUDF>> {
+            None
+        }
+    }
+
+    fn read_privilege(database: &str, session: &SessionCtx) -> Privilege<Oid> {
+        Privilege::TenantObject(
+            TenantObjectPrivilege::Database(DatabasePrivilege::Read, Some(database.to_string())),
+            Some(*session.tenant_id()),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_select_from_view() {
+        let session = session();
+        let test = MockViewContext::default();
+        let planner = SqlPlanner::new(&test);
+
+        // v2 is expanded to v1, and v1 to db1.test_tb
+        let sql = "SELECT field_int FROM v2";
+        let statement = ExtParser::parse_sql(sql).unwrap().pop_front().unwrap();
+        let plan = planner
+            .statement_to_plan(statement, &session, false)
+            .await
+            .unwrap();
+        match &plan.plan {
+            Plan::Query(QueryPlan { df_plan }) => {
+                let fields = df_plan.schema().fields();
+                assert_eq!(fields.len(), 1);
+                assert_eq!(fields[0].name(), "field_int");
+                assert_eq!(fields[0].data_type(), &DataType::Int32);
+            }
+            _ => panic!("expected query plan"),
+        }
+        assert_eq!(plan.privileges.len(), 2);
+        assert!(plan
+            .privileges
+            .contains(&read_privilege("public", &session)));
+        assert!(plan.privileges.contains(&read_privilege("db1", &session)));
+
+        // unqualified tables in the view are resolved against the database of the view
+        let sql = "SELECT field_string FROM db1.v4";
+        let statement = ExtParser::parse_sql(sql).unwrap().pop_front().unwrap();
+        let plan = planner
+            .statement_to_plan(statement, &session, false)
+            .await
+            .unwrap();
+        assert_eq!(plan.privileges, vec![read_privilege("db1", &session)]);
+
+        let sql = "SELECT * FROM v3";
+        let statement = ExtParser::parse_sql(sql).unwrap().pop_front().unwrap();
+        let err = planner
+            .statement_to_plan(statement, &session, false)
+            .await
+            .unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("View cnosdb.public.v3 is defined recursively"));
+    }
+
+    #[tokio::test]
+    async fn test_view_privileges() {
+        let session = session();
+        let test = MockViewContext::default();
+        let planner = SqlPlanner::new(&test);
+        let user = |privileges: Vec<Privilege<Oid>>| {
+            let desc = UserDesc::new(1_u128, "u1".to_string(), UserOptions::default(), false);
+            User::new(desc, privileges.into_iter().collect(), None)
+        };
+
+        // reading a view requires reading the tables it reads
+        let sql = "SELECT field_int FROM v1";
+        let statement = ExtParser::parse_sql(sql).unwrap().pop_front().unwrap();
+        let plan = planner
+            .statement_to_plan(statement, &session, false)
+            .await
+            .unwrap();
+        let only_public = user(vec![read_privilege("public", &session)]);
+        assert!(matches!(
+            check_privilege(&only_public, plan.privileges.clone()),
+            Err(QueryError::InsufficientPrivileges { .. })
+        ));
+        let both = user(vec![
+            read_privilege("public", &session),
+            read_privilege("db1", &session),
+        ]);
+        check_privilege(&both, plan.privileges).unwrap();
+
+        // creating a view requires reading the tables it reads
+        let sql = "CREATE VIEW v5 AS SELECT * FROM v1";
+        let statement = ExtParser::parse_sql(sql).unwrap().pop_front().unwrap();
+        let plan = planner
+            .statement_to_plan(statement, &session, false)
+            .await
+            .unwrap();
+        assert!(plan.privileges.contains(&read_privilege("db1", &session)));
+        assert!(plan.privileges.contains(&Privilege::TenantObject(
+            TenantObjectPrivilege::Database(DatabasePrivilege::Full, Some("public".to_string())),
+            Some(*session.tenant_id()),
+        )));
+        assert!(matches!(
+            check_privilege(&both, plan.privileges),
+            Err(QueryError::InsufficientPrivileges { .. })
+        ));
+    }
 }
@@ -590,6 +590,67 @@ pub enum QueryError {
     Models {
         source: ModelError,
     },
+
+    #[snafu(display("The queue of resource group {} is full", group))]
+    #[error_code(code = 80)]
+    ResourceGroupQueueFull {
+        group: String,
+    },
+
+    #[snafu(display(
+        "The query waited more than {:?} in the queue of resource group {}",
+        timeout,
+        group
+    ))]
+    #[error_code(code = 81)]
+    ResourceGroupQueueTimeout {
+        group: String,
+        timeout: std::time::Duration,
+    },
+
+    #[snafu(display("Prepared statement {} not found", name))]
+    #[error_code(code = 82)]
+    PreparedStatementNotFound {
+        name: String,
+    },
+
+    #[error_code(code = 83)]
+    #[snafu(display("Failed to serialize data to avro bytes, error: {}", reason))]
+    SerializeAvro {
+        reason: String,
+        location: Location,
+        backtrace: Backtrace,
+    },
+
+    #[error_code(code = 84)]
+    #[snafu(display("Failed to serialize data to arrow bytes, error: {}", source))]
+    SerializeArrow {
+        source: ArrowError,
+        location: Location,
+        backtrace: Backtrace,
+    },
+
+    #[snafu(display("Table {} is read-only", table))]
+    #[error_code(code = 85)]
+    ReadOnlyTable {
+        table: String,
+    },
+
+    #[snafu(display("Failed to record the audit event: {}", reason))]
+    #[error_code(code = 86)]
+    AuditLog {
+        reason: String,
+    },
+
+    #[snafu(display("Prepared statements need a session, set the session id of the request"))]
+    #[error_code(code = 87)]
+    SessionRequired,
+
+    #[snafu(display("Too many prepared statements in the session, at most {}", max))]
+    #[error_code(code = 88)]
+    TooManyPreparedStatements {
+        max: usize,
+    },
 }
 
 impl From<DataFusionError> for QueryError {
@@ -2,8 +2,8 @@ use std::fmt;
 
 use datafusion::sql::parser::CreateExternalTable;
 use datafusion::sql::sqlparser::ast::{
-    AnalyzeFormat, DataType, Expr, Ident, ObjectName, Offset, OrderByExpr, SqlOption, Statement,
-    TableFactor, Value,
+    AnalyzeFormat, DataType, Expr, Ident, ObjectName, Offset, OrderByExpr, Query, SqlOption,
+    Statement, TableFactor, Value,
 };
 use datafusion::sql::sqlparser::parser::ParserError;
 use models::codec::Encoding;
@@ -27,6 +27,8 @@ pub enum ExtStatement {
     CreateTenant(CreateTenant),
     CreateUser(CreateUser),
     CreateRole(CreateRole),
+    CreateView(CreateView),
+    CreateMaterializedView(CreateMaterializedView),
 
     CreateStream(CreateStream),
     DropStream(DropStream),
@@ -42,8 +44,10 @@ pub enum ExtStatement {
     DescribeDatabase(DescribeDatabase),
     ShowDatabases(),
     ShowTables(Option<Ident>),
+    ShowViews(Option<Ident>),
     ShowSeries(Box<ShowSeries>),
     ShowTagValues(Box<ShowTagValues>),
+    ShowCardinality(ShowCardinality),
     Explain(Explain),
 
     // system cmd
@@ -59,17 +63,41 @@ pub enum ExtStatement {
     MoveVnode(MoveVnode),
     CompactVnode(CompactVnode),
     ChecksumGroup(ChecksumGroup),
+    RebuildIndex(RebuildIndex),
 
     // recover cmd
     RecoverTenant(RecoverTenant),
     RecoverDatabase(RecoverDatabase),
 
+    // backup cmd
+    BackupDatabase(BackupDatabase),
+    RestoreDatabase(RestoreDatabase),
+
     // replica cmd
     ShowReplicas,
     ReplicaDestory(ReplicaDestory),
     ReplicaAdd(ReplicaAdd),
     ReplicaRemove(ReplicaRemove),
     ReplicaPromote(ReplicaPromote),
+
+    // rebalance cmd
+    Rebalance(Rebalance),
+    ShowRebalance,
+    PauseRebalance,
+    ResumeRebalance,
+
+    // node cmd
+    DecommissionNode(DecommissionNode),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Rebalance {
+    pub dry_run: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DecommissionNode {
+    pub node_id: NodeId,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -105,6 +133,11 @@ pub struct CompactVnode {
     pub vnode_ids: Vec<VnodeId>,
 }
 
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RebuildIndex {
+    pub vnode_id: VnodeId,
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct MoveVnode {
     pub vnode_id: VnodeId,
@@ -252,6 +285,21 @@ pub struct RecoverTenant {
     pub if_exist: bool,
 }
 
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BackupDatabase {
+    pub object_name: Ident,
+    pub location: UriLocation,
+    pub incremental: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RestoreDatabase {
+    pub object_name: Ident,
+    pub location: UriLocation,
+    /// Restore the database with a new name.
+    pub new_name: Option<Ident>,
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct DescribeObject {
     pub object_name: ObjectName,
@@ -320,6 +368,21 @@ pub struct CreateDatabase {
     pub if_not_exists: bool,
     pub options: DatabaseOptions,
 }
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CreateView {
+    pub name: ObjectName,
+    pub or_replace: bool,
+    pub query: Box<Query>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CreateMaterializedView {
+    pub name: ObjectName,
+    pub if_not_exists: bool,
+    pub query: Box<Query>,
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct CreateTable {
     pub name: ObjectName,
@@ -367,6 +430,10 @@ pub struct DatabaseOptions {
     pub replica: Option<u64>,
     // timestamp precision
     pub precision: Option<String>,
+    // e.g. 'spread:zone'
+    pub replica_placement: Option<String>,
+    // buckets older than it are moved to cold nodes
+    pub cold_after: Option<String>,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -421,6 +488,14 @@ pub struct ShowTagValues {
     pub with: With,
 }
 
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ShowCardinality {
+    // on db
+    pub database_name: Option<Ident>,
+    // from
+    pub table: Ident,
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum ObjectType {
@@ -74,11 +74,73 @@ impl FromStr for StreamTriggerInterval {
     }
 }
 
+/// Max staleness of the data read from a replica without confirming the read index with
+/// the raft leader. Reads are linearizable if it is not set.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct StaleReadMaxLag(pub Duration);
+
+impl FromStr for StaleReadMaxLag {
+    type Err = String;
+
+    /// Parse a duration string, e.g. `500ms`, `1s`, `1m+30s`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let duration = duration_str::parse_std(s.trim()).map_err(|err| err.to_string())?;
+        Ok(StaleReadMaxLag(duration))
+    }
+}
+
+/// Whether queries of a session use the result cache, `on` by default.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CacheMode {
+    #[default]
+    On,
+    Off,
+}
+
+impl FromStr for CacheMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "on" | "true" => Ok(Self::On),
+            "off" | "false" => Ok(Self::Off),
+            _ => Err(format!("invalid cache mode '{}', expected on or off", s)),
+        }
+    }
+}
+
+/// Whether aggregates of a session read the results of materialized views, `on` by default.
+/// Materialized views don't include data arriving after its window is emitted by the stream query,
+/// sessions reading such data turn it off.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MaterializedViewRewrite {
+    #[default]
+    On,
+    Off,
+}
+
+impl FromStr for MaterializedViewRewrite {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "on" | "true" => Ok(Self::On),
+            "off" | "false" => Ok(Self::Off),
+            _ => Err(format!(
+                "invalid materialized view rewrite '{}', expected on or off",
+                s
+            )),
+        }
+    }
+}
+
 #[cfg(test)]
 mod test {
     use std::str::FromStr;
 
-    use crate::query::config::StreamTriggerInterval;
+    use crate::query::config::{
+        CacheMode, MaterializedViewRewrite, StaleReadMaxLag, StreamTriggerInterval,
+    };
 
     #[test]
     fn test() {
@@ -142,4 +204,39 @@ mod test {
         let interval = StreamTriggerInterval::from_str("1.5");
         assert!(interval.is_err());
     }
+
+    #[test]
+    fn test_stale_read_max_lag() {
+        let lag = StaleReadMaxLag::from_str("500ms").unwrap();
+        assert_eq!(lag, StaleReadMaxLag(std::time::Duration::from_millis(500)));
+
+        let lag = StaleReadMaxLag::from_str("1m+30s").unwrap();
+        assert_eq!(lag, StaleReadMaxLag(std::time::Duration::from_secs(90)));
+
+        assert!(StaleReadMaxLag::from_str("once").is_err());
+    }
+
+    #[test]
+    fn test_cache_mode() {
+        assert_eq!(CacheMode::from_str("OFF").unwrap(), CacheMode::Off);
+        assert_eq!(CacheMode::from_str("on").unwrap(), CacheMode::On);
+        assert!(CacheMode::from_str("none").is_err());
+    }
+
+    #[test]
+    fn test_materialized_view_rewrite() {
+        assert_eq!(
+            MaterializedViewRewrite::default(),
+            MaterializedViewRewrite::On
+        );
+        assert_eq!(
+            MaterializedViewRewrite::from_str("ON").unwrap(),
+            MaterializedViewRewrite::On
+        );
+        assert_eq!(
+            MaterializedViewRewrite::from_str("false").unwrap(),
+            MaterializedViewRewrite::Off
+        );
+        assert!(MaterializedViewRewrite::from_str("none").is_err());
+    }
 }
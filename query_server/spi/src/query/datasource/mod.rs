@@ -1,14 +1,21 @@
 use std::sync::Arc;
 
+use datafusion::sql::sqlparser::ast::{Ident, SqlOption};
+use datafusion::sql::sqlparser::dialect::GenericDialect;
+use datafusion::sql::sqlparser::parser::Parser;
 use models::oid;
 use object_store::aws::AmazonS3Builder;
 use object_store::azure::MicrosoftAzureBuilder;
 use object_store::gcp::GoogleCloudStorageBuilder;
+use object_store::local::LocalFileSystem;
 use object_store::path::Path;
 use object_store::ObjectStore;
+use snafu::ResultExt;
+use url::Url;
 
-use super::logical_planner::ConnectionOptions;
+use super::logical_planner::{parse_connection_options, ConnectionOptions};
 use super::session::SqlExecInfo;
+use crate::{ObjectStoreSnafu, ParserSnafu, QueryError, QueryResult};
 
 pub mod azure;
 pub mod gcs;
@@ -108,3 +115,103 @@ pub fn build_object_store(
 
     Ok(object_store)
 }
+
+/// Build the object store of a location like `s3://bucket/path`, returns the object store
+/// and the path in it. Location of the local file system must be an absolute path or
+/// a `file://` url, and it is in the local file system of each node.
+pub fn build_object_store_with_path(
+    location: &str,
+    connection_options: Vec<SqlOption>,
+) -> QueryResult<(Arc<dyn ObjectStore>, Path)> {
+    let local_path = match Url::parse(location) {
+        Ok(url) if url.scheme() == "file" => url.to_file_path().ok(),
+        Ok(url) => {
+            let schema = UriSchema::from(url.scheme());
+            let options = parse_connection_options(&schema, url.host_str(), connection_options)?;
+            let object_store = build_object_store(options)
+                .map_err(|e| ObjectStoreSnafu { msg: e.to_string() }.build())?
+                .ok_or_else(|| QueryError::Semantic {
+                    err: format!("Unsupported location [{}]", location),
+                })?;
+            let path = Path::from_url_path(url.path())
+                .map_err(|e| ObjectStoreSnafu { msg: e.to_string() }.build())?;
+            return Ok((object_store, path));
+        }
+        Err(_) => Some(std::path::PathBuf::from(location)),
+    };
+
+    match local_path {
+        Some(local_path) if local_path.is_absolute() => {
+            let path = Path::from_absolute_path(&local_path)
+                .map_err(|e| ObjectStoreSnafu { msg: e.to_string() }.build())?;
+            Ok((Arc::new(LocalFileSystem::new()), path))
+        }
+        _ => Err(QueryError::Semantic {
+            err: format!(
+                "Location [{}] of local file system must be absolute",
+                location
+            ),
+        }),
+    }
+}
+
+/// Encode connection options into pairs of name and sql literal, so that they can be
+/// sent to other nodes.
+pub fn encode_connection_options(options: &[SqlOption]) -> Vec<(String, String)> {
+    options
+        .iter()
+        .map(|SqlOption { name, value }| (name.value.clone(), value.to_string()))
+        .collect()
+}
+
+/// Decode connection options encoded by `encode_connection_options`.
+pub fn decode_connection_options(options: Vec<(String, String)>) -> QueryResult<Vec<SqlOption>> {
+    options
+        .into_iter()
+        .map(|(name, value)| {
+            let value = Parser::new(&GenericDialect {})
+                .try_with_sql(&value)
+                .and_then(|mut parser| parser.parse_value())
+                .context(ParserSnafu)?;
+            Ok(SqlOption {
+                name: Ident::new(name),
+                value,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use datafusion::sql::sqlparser::ast::{Ident, SqlOption, Value};
+
+    use super::{
+        build_object_store_with_path, decode_connection_options, encode_connection_options,
+    };
+
+    #[test]
+    fn test_connection_options_codec() {
+        let options = vec![
+            SqlOption {
+                name: Ident::new("secret_key"),
+                value: Value::SingleQuotedString("it's a key".to_string()),
+            },
+            SqlOption {
+                name: Ident::new("virtual_hosted_style"),
+                value: Value::Boolean(true),
+            },
+        ];
+        let encoded = encode_connection_options(&options);
+        assert_eq!(encoded[0].1, "'it''s a key'");
+        assert_eq!(decode_connection_options(encoded).unwrap(), options);
+    }
+
+    #[test]
+    fn test_build_local_object_store() {
+        let (_, path) = build_object_store_with_path("/tmp/backup/db", vec![]).unwrap();
+        assert_eq!(path.as_ref(), "tmp/backup/db");
+        let (_, path) = build_object_store_with_path("file:///tmp/backup/db", vec![]).unwrap();
+        assert_eq!(path.as_ref(), "tmp/backup/db");
+        assert!(build_object_store_with_path("backup/db", vec![]).is_err());
+    }
+}
@@ -311,6 +311,8 @@ impl QueryStateMachine {
 #[derive(Debug, Clone)]
 pub enum QueryState {
     ACCEPTING,
+    /// Waiting in the queue of its resource group
+    QUEUED,
     RUNNING(RUNNING),
     DONE(DONE),
 }
@@ -319,6 +321,7 @@ impl AsRef<str> for QueryState {
     fn as_ref(&self) -> &str {
         match self {
             QueryState::ACCEPTING => "ACCEPTING",
+            QueryState::QUEUED => "QUEUED",
             QueryState::RUNNING(e) => e.as_ref(),
             QueryState::DONE(e) => e.as_ref(),
         }
@@ -3,22 +3,28 @@ use std::io::Write;
 use std::sync::Arc;
 
 use async_trait::async_trait;
-use config::common::TenantLimiterConfig;
+use config::common::{TenantLimiterConfig, TenantResourceGroupConfig};
 use datafusion::arrow::array::ArrayRef;
 use datafusion::arrow::datatypes::{DataType, Field, Schema, SchemaRef};
+use datafusion::common::tree_node::{Transformed, TreeNode};
+use datafusion::common::DataFusionError;
 use datafusion::datasource::file_format::file_type::{FileCompressionType, FileType};
+use datafusion::logical_expr::expr::{Cast, Placeholder};
 use datafusion::logical_expr::type_coercion::aggregates::{
     DATES, NUMERICS, STRINGS, TIMES, TIMESTAMPS,
 };
+use datafusion::logical_expr::utils::from_plan;
 use datafusion::logical_expr::{
-    expr, expr_fn, CreateExternalTable, LogicalPlan as DFPlan, ReturnTypeFunction, ScalarUDF,
-    Signature, Volatility,
+    expr, expr_fn, CreateExternalTable, LogicalPlan as DFPlan, Prepare, ReturnTypeFunction,
+    ScalarUDF, Signature, Subquery, Volatility,
 };
 use datafusion::physical_plan::functions::make_scalar_function;
 use datafusion::prelude::{col, Expr};
+use datafusion::scalar::ScalarValue;
 use datafusion::sql::sqlparser::ast::{Ident, ObjectName, SqlOption, Value};
 use datafusion::sql::sqlparser::parser::ParserError;
 use lazy_static::lazy_static;
+use models::arrow::ExternalFileType;
 use models::auth::privilege::{DatabasePrivilege, GlobalPrivilege, Privilege};
 use models::auth::role::{SystemTenantRole, TenantRoleIdentifier};
 use models::auth::user::{UserOptions, UserOptionsBuilder};
@@ -26,10 +32,12 @@ use models::meta_data::{NodeId, ReplicationSetId, VnodeId};
 use models::object_reference::ResolvedTable;
 use models::oid::{Identifier, Oid};
 use models::schema::{
-    DatabaseOptions, Duration, TableColumn, Tenant, TenantOptions, TenantOptionsBuilder, Watermark,
+    DatabaseOptions, Duration, TableColumn, Tenant, TenantOptions, TenantOptionsBuilder,
+    ViewSchema, Watermark,
 };
 use snafu::{IntoError, ResultExt};
 use tempfile::NamedTempFile;
+use tskv::index::rebuild::rebuild_index_report_schema;
 
 use super::ast::{parse_bool_value, parse_char_value, parse_string_value, ExtStatement};
 use super::datasource::azure::{AzblobStorageConfig, AzblobStorageConfigBuilder};
@@ -46,6 +54,7 @@ use crate::{
 };
 
 pub const TENANT_OPTION_LIMITER: &str = "_limiter";
+pub const TENANT_OPTION_RESOURCE_GROUP: &str = "_resource_group";
 pub const TENANT_OPTION_COMMENT: &str = "comment";
 pub const TENANT_OPTION_DROP_AFTER: &str = "drop_after";
 
@@ -107,6 +116,85 @@ impl QueryPlan {
     pub fn is_explain(&self) -> bool {
         matches!(self.df_plan, DFPlan::Explain(_) | DFPlan::Analyze(_))
     }
+
+    /// Replace the placeholders `$1`, `$2`, ... of the plan with the values,
+    /// which are cast to the parameter types declared by `PREPARE` or inferred for the placeholders.
+    pub fn with_param_values(&self, values: &[ScalarValue]) -> QueryResult<Self> {
+        let (plan, data_types) = match &self.df_plan {
+            DFPlan::Prepare(Prepare {
+                input, data_types, ..
+            }) => (input.as_ref(), data_types.as_slice()),
+            plan => (plan, [].as_slice()),
+        };
+        if !data_types.is_empty() && data_types.len() != values.len() {
+            return Err(QueryError::InvalidParam {
+                reason: format!(
+                    "expected {} parameters, but {} were provided",
+                    data_types.len(),
+                    values.len()
+                ),
+            });
+        }
+
+        let values = values
+            .iter()
+            .enumerate()
+            .map(|(i, value)| cast_literal(value, data_types.get(i)))
+            .collect::<Vec<_>>();
+        let df_plan = replace_placeholders(plan, &values)?;
+
+        Ok(Self { df_plan })
+    }
+}
+
+fn cast_literal(value: &ScalarValue, data_type: Option<&DataType>) -> Expr {
+    let literal = Expr::Literal(value.clone());
+    match data_type {
+        Some(data_type) if *data_type != value.get_datatype() => {
+            Expr::Cast(Cast::new(Box::new(literal), data_type.clone()))
+        }
+        _ => literal,
+    }
+}
+
+fn replace_placeholders(plan: &DFPlan, values: &[Expr]) -> Result<DFPlan, DataFusionError> {
+    let exprs = plan
+        .expressions()
+        .into_iter()
+        .map(|expr| {
+            expr.transform_up(&|expr| match expr {
+                Expr::Placeholder(Placeholder { id, data_type }) => {
+                    let value = id
+                        .strip_prefix('$')
+                        .and_then(|i| i.parse::<usize>().ok())
+                        .and_then(|i| values.get(i.checked_sub(1)?))
+                        .ok_or_else(|| {
+                            DataFusionError::Plan(format!("No value found for placeholder {}", id))
+                        })?;
+                    let value = match (value, data_type) {
+                        (Expr::Literal(v), data_type) => cast_literal(v, data_type.as_ref()),
+                        (value, _) => value.clone(),
+                    };
+                    Ok(Transformed::Yes(value))
+                }
+                Expr::ScalarSubquery(Subquery {
+                    subquery,
+                    outer_ref_columns,
+                }) => Ok(Transformed::Yes(Expr::ScalarSubquery(Subquery {
+                    subquery: Arc::new(replace_placeholders(&subquery, values)?),
+                    outer_ref_columns,
+                }))),
+                _ => Ok(Transformed::No(expr)),
+            })
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    let inputs = plan
+        .inputs()
+        .into_iter()
+        .map(|input| replace_placeholders(input, values))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    from_plan(plan, &exprs, &inputs)
 }
 
 #[derive(Clone)]
@@ -125,6 +213,10 @@ pub enum DDLPlan {
 
     CreateStreamTable(CreateStreamTable),
 
+    CreateView(CreateView),
+
+    CreateMaterializedView(CreateMaterializedView),
+
     CreateDatabase(CreateDatabase),
 
     CreateTenant(Box<CreateTenant>),
@@ -153,10 +245,16 @@ pub enum DDLPlan {
 
     ChecksumGroup(ChecksumGroup),
 
+    RebuildIndex(RebuildIndex),
+
     RecoverDatabase(RecoverDatabase),
 
     RecoverTenant(RecoverTenant),
 
+    BackupDatabase(BackupDatabase),
+
+    RestoreDatabase(RestoreDatabase),
+
     ShowReplicas,
 
     ReplicaDestory(ReplicaDestory),
@@ -166,6 +264,16 @@ pub enum DDLPlan {
     ReplicaRemove(ReplicaRemove),
 
     ReplicaPromote(ReplicaPromote),
+
+    Rebalance(Rebalance),
+
+    ShowRebalance,
+
+    PauseRebalance,
+
+    ResumeRebalance,
+
+    DecommissionNode(DecommissionNode),
 }
 
 impl DDLPlan {
@@ -175,6 +283,32 @@ impl DDLPlan {
                 Field::new("vnode_id", DataType::UInt32, false),
                 Field::new("check_sum", DataType::Utf8, false),
             ])),
+            DDLPlan::RebuildIndex(_) => rebuild_index_report_schema(),
+            DDLPlan::BackupDatabase(_) => Arc::new(Schema::new(vec![
+                Field::new("vnode_id", DataType::UInt32, false),
+                Field::new("files", DataType::UInt64, false),
+                Field::new("uploaded_files", DataType::UInt64, false),
+                Field::new("uploaded_bytes", DataType::UInt64, false),
+            ])),
+            DDLPlan::RestoreDatabase(_) => Arc::new(Schema::new(vec![
+                Field::new("backup_vnode_id", DataType::UInt32, false),
+                Field::new("vnode_id", DataType::UInt32, false),
+                Field::new("node_id", DataType::UInt64, false),
+            ])),
+            DDLPlan::Rebalance(_) => Arc::new(Schema::new(vec![
+                Field::new("tenant", DataType::Utf8, false),
+                Field::new("replica_id", DataType::UInt32, false),
+                Field::new("vnode_id", DataType::UInt32, false),
+                Field::new("from_node_id", DataType::UInt64, false),
+                Field::new("to_node_id", DataType::UInt64, false),
+            ])),
+            DDLPlan::ShowRebalance => Arc::new(Schema::new(vec![
+                Field::new("time", DataType::Utf8, false),
+                Field::new("status", DataType::Utf8, false),
+                Field::new("moved_vnodes", DataType::UInt64, false),
+                Field::new("total_vnodes", DataType::UInt64, false),
+                Field::new("comment", DataType::Utf8, false),
+            ])),
             _ => Arc::new(Schema::empty()),
         }
     }
@@ -190,6 +324,21 @@ pub struct CompactVnode {
     pub vnode_ids: Vec<VnodeId>,
 }
 
+#[derive(Debug, Clone)]
+pub struct Rebalance {
+    pub dry_run: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct DecommissionNode {
+    pub node_id: NodeId,
+}
+
+#[derive(Debug, Clone)]
+pub struct RebuildIndex {
+    pub vnode_id: VnodeId,
+}
+
 #[derive(Debug, Clone)]
 pub struct MoveVnode {
     pub vnode_id: VnodeId,
@@ -250,6 +399,7 @@ pub struct DropDatabaseObject {
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum DatabaseObjectType {
     Table,
+    View,
 }
 
 #[derive(Debug, Clone)]
@@ -300,6 +450,26 @@ pub struct RecoverTable {
     pub if_exist: bool,
 }
 
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BackupDatabase {
+    pub tenant_name: String,
+    pub db_name: String,
+    pub location: String,
+    pub connection_options: Vec<SqlOption>,
+    pub incremental: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RestoreDatabase {
+    pub tenant_name: String,
+    /// Name of the database in the backup.
+    pub db_name: String,
+    /// Name of the restored database.
+    pub new_db_name: String,
+    pub location: String,
+    pub connection_options: Vec<SqlOption>,
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct CSVOptions {
     /// Whether the CSV file contains a header
@@ -308,6 +478,29 @@ pub struct CSVOptions {
     pub delimiter: char,
 }
 
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CreateView {
+    pub name: ResolvedTable,
+    /// Replace the view if it exists
+    pub or_replace: bool,
+    /// The query of the view
+    pub definition: String,
+    /// The schema of the query result
+    pub schema: Schema,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CreateMaterializedView {
+    /// The view, with the tables storing and maintaining its results
+    pub view: ViewSchema,
+    /// Option to not error if the view already exists
+    pub if_not_exists: bool,
+    /// The tskv table storing the results
+    pub table: CreateTable,
+    /// The stream table of the source table
+    pub stream_table: CreateStreamTable,
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct CreateTable {
     /// The table schema
@@ -385,13 +578,17 @@ pub fn unset_option_to_alter_tenant_action(
             tenant_options_builder.unset_limiter_config();
             Privilege::Global(GlobalPrivilege::System)
         }
+        TENANT_OPTION_RESOURCE_GROUP => {
+            tenant_options_builder.unset_resource_group_config();
+            Privilege::Global(GlobalPrivilege::System)
+        }
         TENANT_OPTION_DROP_AFTER => {
             tenant_options_builder.unset_drop_after();
             Privilege::Global(GlobalPrivilege::Tenant(Some(tenant_id)))
         }
         _ => {
             let source = ParserError::ParserError(format!(
-                "Expected option [{TENANT_OPTION_COMMENT}], [{TENANT_OPTION_LIMITER}], [{TENANT_OPTION_DROP_AFTER}] found [{}]",
+                "Expected option [{TENANT_OPTION_COMMENT}], [{TENANT_OPTION_LIMITER}], [{TENANT_OPTION_RESOURCE_GROUP}], [{TENANT_OPTION_DROP_AFTER}] found [{}]",
                 ident
             ));
             return Err(ParserSnafu.into_error(source));
@@ -428,6 +625,13 @@ pub fn sql_option_to_alter_tenant_action(
             tenant_options_builder.limiter_config(config);
             Privilege::Global(GlobalPrivilege::System)
         }
+        TENANT_OPTION_RESOURCE_GROUP => {
+            let config =
+                serde_json::from_str::<TenantResourceGroupConfig>(parse_string_value(value).context(ParserSnafu)?.as_str())
+                    .map_err(|_| ParserError::ParserError("resource group format error".to_string())).context(ParserSnafu)?;
+            tenant_options_builder.resource_group_config(config);
+            Privilege::Global(GlobalPrivilege::System)
+        }
         TENANT_OPTION_DROP_AFTER => {
             let drop_after_str = parse_string_value(value).context(ParserSnafu)?;
             let drop_after = Duration::new(&drop_after_str).ok_or_else(|| QueryError::Parser {
@@ -439,7 +643,7 @@ pub fn sql_option_to_alter_tenant_action(
         _ => {
             return Err(QueryError::Parser {
                 source: ParserError::ParserError(format!(
-                "Expected option [{TENANT_OPTION_COMMENT}], [{TENANT_OPTION_LIMITER}], [{TENANT_OPTION_DROP_AFTER}] found [{}]",
+                "Expected option [{TENANT_OPTION_COMMENT}], [{TENANT_OPTION_LIMITER}], [{TENANT_OPTION_RESOURCE_GROUP}], [{TENANT_OPTION_DROP_AFTER}] found [{}]",
                 name
             )),
             })
@@ -468,6 +672,12 @@ pub fn sql_options_to_tenant_options(options: Vec<SqlOption>) -> QueryResult<Ten
                 ).context(SerdeJsonSnafu)?;
                 builder.limiter_config(config);
             }
+            TENANT_OPTION_RESOURCE_GROUP => {
+                let config = serde_json::from_str::<TenantResourceGroupConfig>(
+                    parse_string_value(value).context(ParserSnafu)?.as_str(),
+                ).context(SerdeJsonSnafu)?;
+                builder.resource_group_config(config);
+            }
             TENANT_OPTION_DROP_AFTER => {
                 let drop_after_str = parse_string_value(value).context(ParserSnafu)?;
                 let drop_after = Duration::new(&drop_after_str).ok_or_else(|| QueryError::Parser {
@@ -478,7 +688,7 @@ pub fn sql_options_to_tenant_options(options: Vec<SqlOption>) -> QueryResult<Ten
             _ => {
                 return Err(QueryError::Parser {
                     source: ParserError::ParserError(format!(
-                        "Expected option [{TENANT_OPTION_COMMENT}], [{TENANT_OPTION_LIMITER}], [{TENANT_OPTION_DROP_AFTER}] found [{}]",
+                        "Expected option [{TENANT_OPTION_COMMENT}], [{TENANT_OPTION_LIMITER}], [{TENANT_OPTION_RESOURCE_GROUP}], [{TENANT_OPTION_DROP_AFTER}] found [{}]",
                         name
                     )),
                 })
@@ -602,6 +812,8 @@ pub struct AlterTenantSetUser {
 pub struct AlterDatabase {
     pub database_name: String,
     pub database_options: DatabaseOptions,
+    /// Set by `REPLICA_PLACEMENT 'none'`.
+    pub unset_replica_placement: bool,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -707,32 +919,66 @@ impl CopyOptionsBuilder {
     }
 }
 
+/// Compression of the blocks of AVRO and ARROW files,
+/// unlike [`FileCompressionType`] which compresses the whole file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FileBlockCompression {
+    /// AVRO only
+    Deflate,
+    /// AVRO only
+    Snappy,
+    /// ARROW only
+    Lz4,
+    /// ARROW only
+    Zstd,
+}
+
 pub struct FileFormatOptions {
-    pub file_type: FileType,
+    pub file_type: ExternalFileType,
     pub delimiter: char,
     pub with_header: bool,
     pub file_compression_type: FileCompressionType,
+    pub compression: Option<FileBlockCompression>,
 }
 
 #[derive(Debug, Default)]
 pub struct FileFormatOptionsBuilder {
-    file_type: Option<FileType>,
+    file_type: Option<ExternalFileType>,
     delimiter: Option<char>,
     with_header: Option<bool>,
     file_compression_type: Option<FileCompressionType>,
+    compression: Option<FileBlockCompression>,
 }
 
 impl FileFormatOptionsBuilder {
-    fn parse_file_type(s: &str) -> QueryResult<FileType> {
+    fn parse_file_type(s: &str) -> QueryResult<ExternalFileType> {
+        let s = s.to_uppercase();
+        match s.as_str() {
+            "AVRO" => Ok(ExternalFileType::DataFusion(FileType::AVRO)),
+            "ARROW" => Ok(ExternalFileType::DataFusion(FileType::ARROW)),
+            "PARQUET" => Ok(ExternalFileType::DataFusion(FileType::PARQUET)),
+            "CSV" => Ok(ExternalFileType::DataFusion(FileType::CSV)),
+            "JSON" => Ok(ExternalFileType::DataFusion(FileType::JSON)),
+            "ORC" => Ok(ExternalFileType::Orc),
+            _ => Err(QueryError::Semantic {
+                err: format!(
+                    "Unknown FileType: {}, only support AVRO | ARROW | PARQUET | CSV | JSON | ORC",
+                    s
+                ),
+            }),
+        }
+    }
+
+    fn parse_compression(s: &str) -> QueryResult<FileBlockCompression> {
         let s = s.to_uppercase();
         match s.as_str() {
-            "AVRO" => Ok(FileType::AVRO),
-            "PARQUET" => Ok(FileType::PARQUET),
-            "CSV" => Ok(FileType::CSV),
-            "JSON" => Ok(FileType::JSON),
+            "DEFLATE" => Ok(FileBlockCompression::Deflate),
+            "SNAPPY" => Ok(FileBlockCompression::Snappy),
+            "LZ4" => Ok(FileBlockCompression::Lz4),
+            "ZSTD" => Ok(FileBlockCompression::Zstd),
             _ => Err(QueryError::Semantic {
                 err: format!(
-                    "Unknown FileType: {}, only support AVRO | PARQUET | CSV | JSON",
+                    "Unknown compression: {}, only support DEFLATE | SNAPPY for AVRO, LZ4 | ZSTD for ARROW",
                     s
                 ),
             }),
@@ -767,7 +1013,7 @@ impl FileFormatOptionsBuilder {
                 "delimiter" => {
                     // only support csv
                     if let Some(file_type) = &self.file_type {
-                        if *file_type != FileType::CSV {
+                        if *file_type != ExternalFileType::DataFusion(FileType::CSV) {
                             return Err(QueryError::Semantic {
                                 err: "delimiter and with_header fields are specific to CSV"
                                     .to_string(),
@@ -778,7 +1024,7 @@ impl FileFormatOptionsBuilder {
                 }
                 "with_header" => {
                     if let Some(file_type) = &self.file_type {
-                        if *file_type != FileType::CSV {
+                        if *file_type != ExternalFileType::DataFusion(FileType::CSV) {
                             return Err(QueryError::Semantic {
                                 err: "delimiter and with_header fields are specific to CSV"
                                     .to_string(),
@@ -793,6 +1039,11 @@ impl FileFormatOptionsBuilder {
                     )?;
                     self.file_compression_type = Some(file_compression_type);
                 }
+                "compression" => {
+                    let compression =
+                        Self::parse_compression(&parse_string_value(value).context(ParserSnafu)?)?;
+                    self.compression = Some(compression);
+                }
                 option => {
                     return Err(QueryError::Semantic {
                         err: format!("Unsupported option [{}]", option),
@@ -801,18 +1052,44 @@ impl FileFormatOptionsBuilder {
             }
         }
 
+        if let Some(compression) = self.compression {
+            let file_type = self
+                .file_type
+                .clone()
+                .unwrap_or(ExternalFileType::DataFusion(FileType::CSV));
+            let supported = match compression {
+                FileBlockCompression::Deflate | FileBlockCompression::Snappy => {
+                    file_type == ExternalFileType::DataFusion(FileType::AVRO)
+                }
+                FileBlockCompression::Lz4 | FileBlockCompression::Zstd => {
+                    file_type == ExternalFileType::DataFusion(FileType::ARROW)
+                }
+            };
+            if !supported {
+                return Err(QueryError::Semantic {
+                    err: format!(
+                        "compression {:?} is not supported by {} files",
+                        compression, file_type
+                    ),
+                });
+            }
+        }
+
         Ok(self)
     }
 
     /// Construct FileFormatOptions and assign default value
     pub fn build(self) -> FileFormatOptions {
         FileFormatOptions {
-            file_type: self.file_type.unwrap_or(FileType::CSV),
+            file_type: self
+                .file_type
+                .unwrap_or(ExternalFileType::DataFusion(FileType::CSV)),
             delimiter: self.delimiter.unwrap_or(','),
             with_header: self.with_header.unwrap_or(true),
             file_compression_type: self
                 .file_compression_type
                 .unwrap_or(FileCompressionType::UNCOMPRESSED),
+            compression: self.compression,
         }
     }
 }
@@ -15,5 +15,22 @@ pub trait Optimizer {
         &self,
         plan: &LogicalPlan,
         session: &SessionCtx,
+    ) -> QueryResult<Arc<dyn ExecutionPlan>> {
+        let optimized_logical_plan = self.optimize_logical_plan(plan, session)?;
+        self.create_physical_plan(&optimized_logical_plan, session)
+            .await
+    }
+
+    fn optimize_logical_plan(
+        &self,
+        plan: &LogicalPlan,
+        session: &SessionCtx,
+    ) -> QueryResult<LogicalPlan>;
+
+    /// Create the optimized physical plan of an optimized logical plan
+    async fn create_physical_plan(
+        &self,
+        optimized_logical_plan: &LogicalPlan,
+        session: &SessionCtx,
     ) -> QueryResult<Arc<dyn ExecutionPlan>>;
 }
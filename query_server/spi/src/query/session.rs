@@ -1,4 +1,5 @@
 use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
 use std::time::Duration;
 
@@ -6,16 +7,18 @@ use coordinator::Coordinator;
 use datafusion::common::extensions_options;
 use datafusion::config::ConfigExtension;
 use datafusion::execution::context::SessionState;
+use datafusion::execution::disk_manager::{DiskManager, DiskManagerConfig};
 use datafusion::execution::memory_pool::MemoryPool;
 use datafusion::execution::runtime_env::{RuntimeConfig, RuntimeEnv};
 use datafusion::prelude::{SessionConfig, SessionContext};
 use datafusion::variable::VarType;
 use models::auth::user::User;
+use models::consistency_level::ConsistencyLevel;
 use models::oid::Oid;
 use trace::span_ext::SpanExt;
 use trace::{Span, SpanContext};
 
-use super::config::StreamTriggerInterval;
+use super::config::{CacheMode, MaterializedViewRewrite, StaleReadMaxLag, StreamTriggerInterval};
 use super::variable::VarProviderRef;
 use crate::service::protocol::Context;
 use crate::QueryResult;
@@ -23,12 +26,54 @@ use crate::QueryResult;
 extensions_options! {
     pub struct SqlExecInfo {
         pub copyinto_trigger_flush_size: u64, default = 128 * 1024 * 1024 // 128MB
+        /// Sort the input of GROUP BY by the group keys, so that the sorts spill instead of the hash tables
+        pub sort_aggregate_input: bool, default = false
     }
 }
 impl ConfigExtension for SqlExecInfo {
     const PREFIX: &'static str = "sql_exec_info";
 }
 
+/// Disk space used by the data a query spilled to temp files, shared by all partitions of the query.
+///
+/// Only the spills of our own operators are accounted, the temp files of DataFusion sorts are not.
+#[derive(Debug)]
+pub struct QueryTempSpace {
+    limit: u64,
+    used: AtomicU64,
+}
+
+impl QueryTempSpace {
+    pub fn new(limit: u64) -> Self {
+        Self {
+            limit,
+            used: AtomicU64::new(0),
+        }
+    }
+
+    /// Returns false if the query would use more than the limit
+    pub fn try_grow(&self, bytes: u64) -> bool {
+        self.used
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |used| {
+                let new_used = used + bytes;
+                (new_used <= self.limit).then_some(new_used)
+            })
+            .is_ok()
+    }
+
+    pub fn shrink(&self, bytes: u64) {
+        self.used.fetch_sub(bytes, Ordering::Relaxed);
+    }
+
+    pub fn used(&self) -> u64 {
+        self.used.load(Ordering::Relaxed)
+    }
+
+    pub fn limit(&self) -> u64 {
+        self.limit
+    }
+}
+
 #[derive(Clone)]
 pub struct SessionCtx {
     desc: Arc<SessionCtxDesc>,
@@ -69,6 +114,21 @@ impl SessionCtx {
         }
     }
 
+    /// The session for re-planning a query whose GROUP BY ran out of memory,
+    /// see [`SqlExecInfo::sort_aggregate_input`].
+    pub fn with_sort_aggregate_input(&self) -> QueryResult<Self> {
+        let mut inner = self.inner.clone();
+        inner
+            .config_mut()
+            .options_mut()
+            .set("sql_exec_info.sort_aggregate_input", "true")?;
+        Ok(Self {
+            desc: self.desc.clone(),
+            inner,
+            span_ctx: self.span_ctx,
+        })
+    }
+
     pub fn get_span_ctx(&self) -> Option<&SpanContext> {
         self.span_ctx.as_ref()
         // self.inner().config().get_extension::<SpanContext>();
@@ -97,6 +157,7 @@ pub struct SessionCtxFactory {
     sys_var_provider: Option<VarProviderRef>,
     query_dedicated_hidden_dir: PathBuf,
     session_function_register: Option<fn(df_session_ctx: &SessionContext, context: &Context)>,
+    disk_manager: Option<Arc<DiskManager>>,
 }
 
 impl SessionCtxFactory {
@@ -109,9 +170,16 @@ impl SessionCtxFactory {
             sys_var_provider,
             query_dedicated_hidden_dir,
             session_function_register,
+            disk_manager: None,
         }
     }
 
+    /// Operators of the queries spill to temp files of the `disk_manager` when out of memory
+    pub fn with_disk_manager(mut self, disk_manager: Arc<DiskManager>) -> Self {
+        self.disk_manager = Some(disk_manager);
+        self
+    }
+
     pub fn create_session_ctx(
         &self,
         session_id: impl Into<String>,
@@ -159,8 +227,15 @@ impl SessionCtxFactory {
             "sql_exec_info.copyinto_trigger_flush_size",
             coord.get_config().storage.copyinto_trigger_flush_size,
         );
+        config = config.with_extension(Arc::new(QueryTempSpace::new(
+            coord.get_config().query.max_spill_size_per_query,
+        )));
 
-        let rt_config = RuntimeConfig::new().with_memory_pool(memory_pool);
+        let mut rt_config = RuntimeConfig::new().with_memory_pool(memory_pool);
+        if let Some(disk_manager) = &self.disk_manager {
+            rt_config =
+                rt_config.with_disk_manager(DiskManagerConfig::Existing(disk_manager.clone()));
+        }
         let rt = RuntimeEnv::new(rt_config)?;
         let df_session_state =
             SessionState::with_config_rt(config, Arc::new(rt)).with_session_id(session_id.into());
@@ -212,4 +287,49 @@ impl CnosSessionConfig {
         self.inner = self.inner.with_extension(Arc::new(interval));
         self
     }
+
+    /// Allow table and tag scans to read from a replica whose data lags at most `lag`
+    pub fn with_stale_read_max_lag(mut self, lag: StaleReadMaxLag) -> Self {
+        self.inner = self.inner.with_extension(Arc::new(lag));
+        self
+    }
+
+    /// Consistency level of writes in the session
+    pub fn with_consistency_level(mut self, consistency: ConsistencyLevel) -> Self {
+        self.inner = self.inner.with_extension(Arc::new(consistency));
+        self
+    }
+
+    pub fn consistency_level(&self) -> ConsistencyLevel {
+        self.inner
+            .get_extension::<ConsistencyLevel>()
+            .map(|c| *c)
+            .unwrap_or_default()
+    }
+
+    /// Whether queries of the session use the result cache
+    pub fn with_cache_mode(mut self, mode: CacheMode) -> Self {
+        self.inner = self.inner.with_extension(Arc::new(mode));
+        self
+    }
+
+    pub fn cache_mode(&self) -> CacheMode {
+        self.inner
+            .get_extension::<CacheMode>()
+            .map(|c| *c)
+            .unwrap_or_default()
+    }
+
+    /// Whether aggregates of the session may read the results of materialized views
+    pub fn with_materialized_view_rewrite(mut self, rewrite: MaterializedViewRewrite) -> Self {
+        self.inner = self.inner.with_extension(Arc::new(rewrite));
+        self
+    }
+
+    pub fn materialized_view_rewrite(&self) -> MaterializedViewRewrite {
+        self.inner
+            .get_extension::<MaterializedViewRewrite>()
+            .map(|c| *c)
+            .unwrap_or_default()
+    }
 }
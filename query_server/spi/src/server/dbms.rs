@@ -20,7 +20,13 @@ pub type DBMSRef = Arc<dyn DatabaseManagerSystem + Send + Sync>;
 #[async_trait]
 pub trait DatabaseManagerSystem {
     async fn start(&self) -> QueryResult<()>;
-    async fn authenticate(&self, user_info: &UserInfo, tenant_name: &str) -> QueryResult<User>;
+    /// Authenticate the user, `client_addr` is the address of the client if known.
+    async fn authenticate(
+        &self,
+        user_info: &UserInfo,
+        tenant_name: &str,
+        client_addr: Option<&str>,
+    ) -> QueryResult<User>;
     async fn execute(
         &self,
         query: &Query,
@@ -51,7 +57,12 @@ impl DatabaseManagerSystem for DatabaseManagerSystemMock {
     async fn start(&self) -> QueryResult<()> {
         Ok(())
     }
-    async fn authenticate(&self, user_info: &UserInfo, _tenant_name: &str) -> QueryResult<User> {
+    async fn authenticate(
+        &self,
+        user_info: &UserInfo,
+        _tenant_name: &str,
+        _client_addr: Option<&str>,
+    ) -> QueryResult<User> {
         let options = unsafe {
             UserOptionsBuilder::default()
                 .password(user_info.password.clone())
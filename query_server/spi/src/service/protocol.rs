@@ -1,11 +1,15 @@
 use std::fmt::Display;
 
+use datafusion::scalar::ScalarValue;
 use models::auth::user::User;
+use models::consistency_level::ConsistencyLevel;
 use models::oid::uuid_u64;
 use models::schema::{DEFAULT_CATALOG, DEFAULT_DATABASE, DEFAULT_PRECISION};
 use serde::{Deserialize, Serialize};
 
-use crate::query::config::StreamTriggerInterval;
+use crate::query::config::{
+    CacheMode, MaterializedViewRewrite, StaleReadMaxLag, StreamTriggerInterval,
+};
 use crate::query::execution::Output;
 use crate::query::session::CnosSessionConfig;
 
@@ -71,7 +75,10 @@ pub struct Context {
     database: String,
     precision: String,
     chunked: bool,
+    client_addr: Option<String>,
+    session_id: Option<String>,
     session_config: CnosSessionConfig,
+    params: Option<Vec<ScalarValue>>,
 }
 
 impl Context {
@@ -97,6 +104,33 @@ impl Context {
     pub fn chunked(&self) -> bool {
         self.chunked
     }
+
+    /// The address of the client issued the request, if known.
+    pub fn client_addr(&self) -> Option<&str> {
+        self.client_addr.as_deref()
+    }
+
+    /// The id of the client session or connection issued the request, if any.
+    ///
+    /// Statements created by `PREPARE` are only visible in the session that created them.
+    pub fn session_id(&self) -> Option<&str> {
+        self.session_id.as_deref()
+    }
+
+    /// Values of the placeholders `$1`/`?` of a parameterized query,
+    /// [`None`] if the query is not parameterized.
+    pub fn params(&self) -> Option<&[ScalarValue]> {
+        self.params.as_deref()
+    }
+
+    /// Cap the target partitions of the session, e.g. by the cpu share of a resource group.
+    pub fn with_max_target_partitions(mut self, n: usize) -> Self {
+        let target_partitions = self.session_config.to_df_config().target_partitions();
+        if target_partitions > n {
+            self.session_config = self.session_config.with_target_partitions(n.max(1));
+        }
+        self
+    }
 }
 
 pub struct ContextBuilder {
@@ -105,7 +139,10 @@ pub struct ContextBuilder {
     database: String,
     precision: String,
     chunked: bool,
+    client_addr: Option<String>,
+    session_id: Option<String>,
     session_config: CnosSessionConfig,
+    params: Option<Vec<ScalarValue>>,
 }
 
 impl ContextBuilder {
@@ -116,7 +153,10 @@ impl ContextBuilder {
             tenant: DEFAULT_CATALOG.to_string(),
             database: DEFAULT_DATABASE.to_string(),
             chunked: Default::default(),
+            client_addr: None,
+            session_id: None,
             session_config: Default::default(),
+            params: None,
         }
     }
 
@@ -157,6 +197,37 @@ impl ContextBuilder {
         self
     }
 
+    pub fn with_stale_read_max_lag(mut self, lag: Option<StaleReadMaxLag>) -> Self {
+        if let Some(lag) = lag {
+            self.session_config = self.session_config.with_stale_read_max_lag(lag);
+        }
+        self
+    }
+
+    pub fn with_consistency_level(mut self, consistency: Option<ConsistencyLevel>) -> Self {
+        if let Some(consistency) = consistency {
+            self.session_config = self.session_config.with_consistency_level(consistency);
+        }
+        self
+    }
+
+    pub fn with_cache_mode(mut self, mode: Option<CacheMode>) -> Self {
+        if let Some(mode) = mode {
+            self.session_config = self.session_config.with_cache_mode(mode);
+        }
+        self
+    }
+
+    pub fn with_materialized_view_rewrite(
+        mut self,
+        rewrite: Option<MaterializedViewRewrite>,
+    ) -> Self {
+        if let Some(rewrite) = rewrite {
+            self.session_config = self.session_config.with_materialized_view_rewrite(rewrite);
+        }
+        self
+    }
+
     pub fn with_chunked(mut self, chunked: Option<bool>) -> Self {
         if let Some(chunked) = chunked {
             self.chunked = chunked;
@@ -164,6 +235,28 @@ impl ContextBuilder {
         self
     }
 
+    pub fn with_client_addr(mut self, client_addr: Option<String>) -> Self {
+        if let Some(client_addr) = client_addr {
+            self.client_addr = Some(client_addr);
+        }
+        self
+    }
+
+    pub fn with_session_id(mut self, session_id: Option<String>) -> Self {
+        if let Some(session_id) = session_id {
+            self.session_id = Some(session_id);
+        }
+        self
+    }
+
+    /// The plan of a parameterized query is cached, and the values are bound at each execution.
+    pub fn with_params(mut self, params: Option<Vec<ScalarValue>>) -> Self {
+        if let Some(params) = params {
+            self.params = Some(params);
+        }
+        self
+    }
+
     pub fn build(self) -> Context {
         Context {
             user: self.user,
@@ -171,7 +264,10 @@ impl ContextBuilder {
             database: self.database,
             precision: self.precision,
             chunked: self.chunked,
+            client_addr: self.client_addr,
+            session_id: self.session_id,
             session_config: self.session_config,
+            params: self.params,
         }
     }
 }
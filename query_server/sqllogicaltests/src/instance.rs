@@ -183,6 +183,7 @@ pub async fn run_query(
         tenant,
         db,
         target_partitions,
+        materialized_view_rewrite,
         ..
     } = options;
 
@@ -192,6 +193,9 @@ pub async fn run_query(
     client.set_header("TENANT", tenant);
     client.set_header("DB", db);
     client.set_header("target_partitions", &target_partitions.to_string());
+    if let Some(rewrite) = materialized_view_rewrite {
+        client.set_header("materialized_view_rewrite", rewrite);
+    }
 
     // 1. handshake, basic authentication
     let _ = client.handshake(username, password).await?;
@@ -361,6 +365,7 @@ pub struct SqlClientOptions {
     pub timeout: Option<Duration>,
     pub precision: Option<String>,
     pub chunked: Option<bool>,
+    pub materialized_view_rewrite: Option<String>,
 }
 
 impl SqlClientOptions {
@@ -408,6 +413,10 @@ impl SqlClientOptions {
         if let Ok((_, chunked)) = instruction_parse_to::<bool>("CHUNKED")(line) {
             self.chunked = Some(chunked)
         }
+
+        if let Ok((_, rewrite)) = instruction_parse_identity("MATERIALIZED_VIEW_REWRITE")(line) {
+            self.materialized_view_rewrite = Some(rewrite.to_string())
+        }
     }
 }
 #[cfg(test)]
@@ -431,6 +440,7 @@ mod test {
             timeout: None,
             precision: None,
             chunked: None,
+            materialized_view_rewrite: None,
         };
 
         let line = r##"--#DATABASE = _abc_"##;
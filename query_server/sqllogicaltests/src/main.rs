@@ -52,6 +52,7 @@ pub async fn main() -> Result<(), Box<dyn Error>> {
         timeout: None,
         precision: None,
         chunked: None,
+        materialized_view_rewrite: None,
     };
 
     let create_options = CreateOptions {
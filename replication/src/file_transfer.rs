@@ -0,0 +1,307 @@
+//! Ship immutable files, e.g. TSM files of a vnode snapshot, from one node to another.
+//!
+//! A file is sent as `FileChunk`s, each chunk carries its offset in the file and a crc32
+//! checksum of its data. The receiver verifies a chunk before appending it to the local
+//! file, so a partially received file is always a prefix of the source file, and an
+//! interrupted transfer resumes from the length of the local file.
+
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use snafu::ResultExt;
+use tokio::fs::File;
+use tokio::io::{AsyncReadExt, AsyncSeekExt, AsyncWriteExt, SeekFrom};
+use tokio::sync::Mutex;
+use tokio::time::Instant;
+
+use crate::errors::{IOErrSnafu, MsgInvalidSnafu, ReplicationResult, SnapshotErrSnafu};
+
+pub const DEFAULT_CHUNK_SIZE: usize = 4 * 1024 * 1024;
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct FileChunk {
+    pub offset: u64,
+    pub data: Vec<u8>,
+    pub checksum: u32,
+}
+
+impl FileChunk {
+    pub fn new(offset: u64, data: Vec<u8>) -> Self {
+        let checksum = crc32fast::hash(&data);
+        Self {
+            offset,
+            data,
+            checksum,
+        }
+    }
+
+    pub fn encode(&self) -> ReplicationResult<Vec<u8>> {
+        bincode::serialize(self).map_err(|e| MsgInvalidSnafu { msg: e.to_string() }.build())
+    }
+
+    pub fn decode(data: &[u8]) -> ReplicationResult<Self> {
+        bincode::deserialize(data).map_err(|e| MsgInvalidSnafu { msg: e.to_string() }.build())
+    }
+
+    pub fn verify(&self) -> bool {
+        crc32fast::hash(&self.data) == self.checksum
+    }
+}
+
+/// Limits bytes transferred per second, shared by all transfers on a node.
+pub struct RateLimiter {
+    bytes_per_sec: u64,
+    next: Mutex<Instant>,
+}
+
+impl RateLimiter {
+    /// `bytes_per_sec` of 0 means unlimited.
+    pub fn new(bytes_per_sec: u64) -> Self {
+        Self {
+            bytes_per_sec,
+            next: Mutex::new(Instant::now()),
+        }
+    }
+
+    /// Wait until `bytes` are allowed to be transferred.
+    pub async fn acquire(&self, bytes: u64) {
+        if self.bytes_per_sec == 0 {
+            return;
+        }
+
+        let cost = Duration::from_secs_f64(bytes as f64 / self.bytes_per_sec as f64);
+        let start = {
+            let mut next = self.next.lock().await;
+            let start = (*next).max(Instant::now());
+            *next = start + cost;
+            start
+        };
+        tokio::time::sleep_until(start).await;
+    }
+}
+
+/// Read a file as chunks from the offset.
+pub struct FileChunkReader {
+    file: File,
+    offset: u64,
+    chunk_size: usize,
+    limiter: Option<Arc<RateLimiter>>,
+}
+
+impl FileChunkReader {
+    pub async fn open(
+        path: &Path,
+        offset: u64,
+        chunk_size: usize,
+        limiter: Option<Arc<RateLimiter>>,
+    ) -> ReplicationResult<Self> {
+        let mut file = File::open(path).await.context(IOErrSnafu)?;
+        file.seek(SeekFrom::Start(offset))
+            .await
+            .context(IOErrSnafu)?;
+
+        Ok(Self {
+            file,
+            offset,
+            chunk_size: chunk_size.max(1),
+            limiter,
+        })
+    }
+
+    /// Returns the next chunk, or None if the end of file is reached.
+    pub async fn next_chunk(&mut self) -> ReplicationResult<Option<FileChunk>> {
+        let mut data = vec![0; self.chunk_size];
+        let mut len = 0;
+        while len < data.len() {
+            let n = self.file.read(&mut data[len..]).await.context(IOErrSnafu)?;
+            if n == 0 {
+                break;
+            }
+            len += n;
+        }
+        if len == 0 {
+            return Ok(None);
+        }
+        data.truncate(len);
+
+        if let Some(limiter) = &self.limiter {
+            limiter.acquire(len as u64).await;
+        }
+
+        let chunk = FileChunk::new(self.offset, data);
+        self.offset += len as u64;
+
+        Ok(Some(chunk))
+    }
+}
+
+/// Receive a file of known size from chunks. Data received by an interrupted transfer
+/// is kept, the transfer resumes from `offset()`.
+pub struct FileChunkWriter {
+    path: PathBuf,
+    file: File,
+    offset: u64,
+    file_size: u64,
+}
+
+impl FileChunkWriter {
+    pub async fn open(path: &Path, file_size: u64) -> ReplicationResult<Self> {
+        if let Some(dir) = path.parent() {
+            tokio::fs::create_dir_all(dir).await.context(IOErrSnafu)?;
+        }
+
+        let mut file = tokio::fs::OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(path)
+            .await
+            .context(IOErrSnafu)?;
+        let mut offset = file.metadata().await.context(IOErrSnafu)?.len();
+        if offset > file_size {
+            file.set_len(0).await.context(IOErrSnafu)?;
+            offset = 0;
+        }
+        file.seek(SeekFrom::Start(offset))
+            .await
+            .context(IOErrSnafu)?;
+
+        Ok(Self {
+            path: path.to_path_buf(),
+            file,
+            offset,
+            file_size,
+        })
+    }
+
+    pub fn offset(&self) -> u64 {
+        self.offset
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.offset >= self.file_size
+    }
+
+    pub async fn write_chunk(&mut self, chunk: &FileChunk) -> ReplicationResult<()> {
+        if chunk.offset != self.offset {
+            return Err(SnapshotErrSnafu {
+                msg: format!(
+                    "receive chunk of '{}' at offset {}, expected {}",
+                    self.path.display(),
+                    chunk.offset,
+                    self.offset
+                ),
+            }
+            .build());
+        }
+        if !chunk.verify() {
+            return Err(SnapshotErrSnafu {
+                msg: format!(
+                    "checksum of chunk at offset {} of '{}' mismatch",
+                    chunk.offset,
+                    self.path.display()
+                ),
+            }
+            .build());
+        }
+        if self.offset + chunk.data.len() as u64 > self.file_size {
+            return Err(SnapshotErrSnafu {
+                msg: format!(
+                    "receive more data than the size {} of '{}'",
+                    self.file_size,
+                    self.path.display()
+                ),
+            }
+            .build());
+        }
+
+        self.file.write_all(&chunk.data).await.context(IOErrSnafu)?;
+        self.offset += chunk.data.len() as u64;
+
+        Ok(())
+    }
+
+    /// Sync the received file, fails if the file is not completely received.
+    pub async fn finish(mut self) -> ReplicationResult<()> {
+        self.file.flush().await.context(IOErrSnafu)?;
+        self.file.sync_all().await.context(IOErrSnafu)?;
+        if self.offset != self.file_size {
+            return Err(SnapshotErrSnafu {
+                msg: format!(
+                    "size of received file '{}' is {}, expected {}",
+                    self.path.display(),
+                    self.offset,
+                    self.file_size
+                ),
+            }
+            .build());
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+
+    use super::{FileChunkReader, FileChunkWriter, RateLimiter};
+
+    #[tokio::test]
+    async fn test_file_transfer_resume() {
+        let dir = tempfile::tempdir().unwrap();
+        let src_file = dir.path().join("8000/tsm/_000001.tsm");
+        let dst_file = dir.path().join("8001/tsm/_000001.tsm");
+        let chunk_size = 1024 * 1024;
+        let data: Vec<u8> = (0..10 * chunk_size).map(|i| (i * 31 % 251) as u8).collect();
+        std::fs::create_dir_all(src_file.parent().unwrap()).unwrap();
+        std::fs::write(&src_file, &data).unwrap();
+        let limiter = Arc::new(RateLimiter::new(1024 * 1024 * 1024));
+
+        // interrupted after 3 chunks
+        let mut writer = FileChunkWriter::open(&dst_file, data.len() as u64)
+            .await
+            .unwrap();
+        let mut reader = FileChunkReader::open(&src_file, 0, chunk_size, Some(limiter.clone()))
+            .await
+            .unwrap();
+        for _ in 0..3 {
+            let chunk = reader.next_chunk().await.unwrap().unwrap();
+            writer.write_chunk(&chunk).await.unwrap();
+        }
+        drop(writer);
+
+        // resume from the received data
+        let mut writer = FileChunkWriter::open(&dst_file, data.len() as u64)
+            .await
+            .unwrap();
+        assert_eq!(writer.offset(), 3 * chunk_size as u64);
+        let mut reader =
+            FileChunkReader::open(&src_file, writer.offset(), chunk_size, Some(limiter))
+                .await
+                .unwrap();
+
+        // a corrupted chunk is rejected
+        let chunk = reader.next_chunk().await.unwrap().unwrap();
+        let mut corrupted = chunk.clone();
+        corrupted.data[0] ^= 0xff;
+        assert!(writer.write_chunk(&corrupted).await.is_err());
+        assert_eq!(writer.offset(), 3 * chunk_size as u64);
+        writer.write_chunk(&chunk).await.unwrap();
+
+        while let Some(chunk) = reader.next_chunk().await.unwrap() {
+            writer.write_chunk(&chunk).await.unwrap();
+        }
+        assert!(writer.is_finished());
+        writer.finish().await.unwrap();
+
+        // a received file is not downloaded again
+        let writer = FileChunkWriter::open(&dst_file, data.len() as u64)
+            .await
+            .unwrap();
+        assert!(writer.is_finished());
+        assert_eq!(std::fs::read(&dst_file).unwrap(), data);
+    }
+}
@@ -11,6 +11,7 @@ use tokio::sync::RwLock;
 pub mod apply_store;
 pub mod entry_store;
 pub mod errors;
+pub mod file_transfer;
 
 pub mod multi_raft;
 pub mod network_client;
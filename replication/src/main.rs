@@ -1,5 +1,6 @@
 use std::convert::Infallible as StdInfallible;
 use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
 use clap::Parser;
@@ -8,14 +9,19 @@ use openraft::SnapshotPolicy;
 use protos::raft_service::raft_service_server::RaftServiceServer;
 use replication::apply_store::HeedApplyStorage;
 use replication::entry_store::HeedEntryStorage;
-use replication::errors::{MsgInvalidSnafu, ReplicationResult};
+use replication::errors::{IOErrSnafu, MsgInvalidSnafu, ReplicationResult};
+use replication::file_transfer::{FileChunk, FileChunkReader, FileChunkWriter};
 use replication::multi_raft::MultiRaft;
 use replication::network_grpc::RaftCBServer;
 use replication::network_http::{EitherBody, RaftHttpAdmin, SyncSendError};
 use replication::node_store::NodeStorage;
 use replication::raft_node::RaftNode;
 use replication::state_store::StateStorage;
-use replication::{RaftNodeId, RaftNodeInfo, ReplicationConfig};
+use replication::{
+    ApplyContext, ApplyStorage, EngineMetrics, RaftNodeId, RaftNodeInfo, ReplicationConfig,
+    Request, Response,
+};
+use snafu::ResultExt;
 use tokio::sync::RwLock;
 use tower::Service;
 use trace::{debug, info};
@@ -72,41 +78,163 @@ async fn create_raft_node(dir: &str, id_port: RaftNodeId) -> ReplicationResult<R
     let state = Arc::new(state);
     let entry = Arc::new(RwLock::new(entry));
     let engine = Arc::new(RwLock::new(engine));
+    let apply = SnapshotFileStorage::new(engine.clone(), format!("{}/tsm", dir));
+    let apply = Arc::new(RwLock::new(apply));
 
-    let storage = NodeStorage::open(id_port, info.clone(), state, engine.clone(), entry).await?;
+    let storage = NodeStorage::open(id_port, info.clone(), state, apply, entry).await?;
     let storage = Arc::new(storage);
 
-    let config = ReplicationConfig {
-        cluster_name: "raft_test".to_string(),
-        lmdb_max_map_size: 1024 * 1024 * 1024,
-        grpc_enable_gzip: false,
-        heartbeat_interval: 1000,
-        raft_logs_to_keep: 200,
-        send_append_entries_timeout: 3 * 1000,
-        install_snapshot_timeout: 300 * 1000,
-        //snapshot_policy: SnapshotPolicy::Never,
-        snapshot_policy: SnapshotPolicy::LogsSinceLast(200),
-    };
-    let node = RaftNode::new(id_port, info, storage, config).await.unwrap();
+    let node = RaftNode::new(id_port, info, storage.clone(), replication_config())
+        .await
+        .unwrap();
 
     let node = Arc::new(node);
+    let mut multi_raft = MultiRaft::new();
+    multi_raft.add_node(node.clone());
     let raft_admin = RaftHttpAdmin::new(node.clone());
     let node_server = RaftNodeServer {
         node,
         engine,
+        storage,
         http_addr,
+        nodes: Arc::new(RwLock::new(multi_raft)),
         raft_admin: Arc::new(raft_admin),
     };
 
     Ok(node_server)
 }
 
+fn replication_config() -> ReplicationConfig {
+    ReplicationConfig {
+        cluster_name: "raft_test".to_string(),
+        lmdb_max_map_size: 1024 * 1024 * 1024,
+        grpc_enable_gzip: false,
+        heartbeat_interval: 1000,
+        raft_logs_to_keep: 200,
+        send_append_entries_timeout: 3 * 1000,
+        install_snapshot_timeout: 300 * 1000,
+        //snapshot_policy: SnapshotPolicy::Never,
+        snapshot_policy: SnapshotPolicy::LogsSinceLast(200),
+    }
+}
+
+// **************************** snapshot files ******************************************** //
+const SNAPSHOT_FILE_CHUNK_SIZE: usize = 64 * 1024;
+
+#[derive(serde::Serialize, serde::Deserialize, Debug)]
+struct FileSnapshot {
+    /// Directory of the files on the node which built the snapshot.
+    files_dir: PathBuf,
+    /// Names and sizes of the files.
+    files: Vec<(String, u64)>,
+    data: Vec<u8>,
+}
+
+/// Ships the files under `<dir>/tsm` in snapshots, like vnode snapshots ship TSM files.
+///
+/// Nodes of the test run in one process, so the files are read from the directory of
+/// the node which built the snapshot, as chunks encoded and decoded like on the wire.
+struct SnapshotFileStorage {
+    engine: Arc<RwLock<HeedApplyStorage>>,
+    files_dir: PathBuf,
+    snapshot: Option<(Vec<u8>, u64)>,
+}
+
+impl SnapshotFileStorage {
+    fn new(engine: Arc<RwLock<HeedApplyStorage>>, files_dir: impl Into<PathBuf>) -> Self {
+        Self {
+            engine,
+            files_dir: files_dir.into(),
+            snapshot: None,
+        }
+    }
+
+    fn list_files(&self) -> ReplicationResult<Vec<(String, u64)>> {
+        let mut files = vec![];
+        if !self.files_dir.exists() {
+            return Ok(files);
+        }
+        for entry in std::fs::read_dir(&self.files_dir).context(IOErrSnafu)? {
+            let entry = entry.context(IOErrSnafu)?;
+            let size = entry.metadata().context(IOErrSnafu)?.len();
+            files.push((entry.file_name().to_string_lossy().to_string(), size));
+        }
+        files.sort();
+
+        Ok(files)
+    }
+}
+
+/// Download a file from the offset of the local file, a partially received file resumes.
+async fn download_snapshot_file(src: &Path, dst: &Path, file_size: u64) -> ReplicationResult<()> {
+    let mut writer = FileChunkWriter::open(dst, file_size).await?;
+    let mut reader =
+        FileChunkReader::open(src, writer.offset(), SNAPSHOT_FILE_CHUNK_SIZE, None).await?;
+    while let Some(chunk) = reader.next_chunk().await? {
+        let chunk = FileChunk::decode(&chunk.encode()?)?;
+        writer.write_chunk(&chunk).await?;
+    }
+
+    writer.finish().await
+}
+
+#[async_trait::async_trait]
+impl ApplyStorage for SnapshotFileStorage {
+    async fn apply(&mut self, ctx: &ApplyContext, req: &Request) -> ReplicationResult<Response> {
+        self.engine.write().await.apply(ctx, req).await
+    }
+
+    async fn get_snapshot(&mut self) -> ReplicationResult<Option<(Vec<u8>, u64)>> {
+        Ok(self.snapshot.clone())
+    }
+
+    async fn create_snapshot(&mut self, applied_id: u64) -> ReplicationResult<(Vec<u8>, u64)> {
+        let (data, index) = self
+            .engine
+            .write()
+            .await
+            .create_snapshot(applied_id)
+            .await?;
+        let snapshot = FileSnapshot {
+            files_dir: self.files_dir.clone(),
+            files: self.list_files()?,
+            data,
+        };
+        let bytes = serde_json::to_vec(&snapshot)
+            .map_err(|e| MsgInvalidSnafu { msg: e.to_string() }.build())?;
+        self.snapshot = Some((bytes.clone(), index));
+
+        Ok((bytes, index))
+    }
+
+    async fn restore(&mut self, snapshot: &[u8]) -> ReplicationResult<()> {
+        let snapshot: FileSnapshot = serde_json::from_slice(snapshot)
+            .map_err(|e| MsgInvalidSnafu { msg: e.to_string() }.build())?;
+        for (name, size) in snapshot.files.iter() {
+            info!("download snapshot file {} of {} bytes", name, size);
+            download_snapshot_file(
+                &snapshot.files_dir.join(name),
+                &self.files_dir.join(name),
+                *size,
+            )
+            .await?;
+        }
+
+        self.engine.write().await.restore(&snapshot.data).await
+    }
+
+    async fn destory(&mut self) -> ReplicationResult<()> {
+        self.engine.write().await.destory().await
+    }
+
+    async fn metrics(&self) -> ReplicationResult<EngineMetrics> {
+        self.engine.read().await.metrics().await
+    }
+}
+
 // **************************** http and grpc server ************************************** //
 async fn start_server(node_server: RaftNodeServer) -> ReplicationResult<()> {
-    let mut multi_raft = MultiRaft::new();
-    multi_raft.add_node(node_server.node.clone());
-    let nodes = Arc::new(RwLock::new(multi_raft));
-
+    let nodes = node_server.nodes.clone();
     let addr = node_server.http_addr.parse().unwrap();
     hyper::Server::bind(&addr)
         .serve(hyper::service::make_service_fn(move |_| {
@@ -148,6 +276,8 @@ struct RaftNodeServer {
     http_addr: String,
     node: Arc<RaftNode>,
     engine: Arc<RwLock<HeedApplyStorage>>,
+    storage: Arc<NodeStorage>,
+    nodes: Arc<RwLock<MultiRaft>>,
     raft_admin: Arc<RaftHttpAdmin>,
 }
 
@@ -924,4 +1054,267 @@ mod tests {
 
         let _ = std::fs::remove_dir_all(crate::TEST_DATA_DIR);
     }
+
+    #[test]
+    fn test_lagging_follower_install_snapshot() {
+        println!("----- begin test_lagging_follower_install_snapshot -----");
+        let _ = std::fs::remove_dir_all(crate::TEST_DATA_DIR);
+
+        let rt = create_runtime();
+        let dir = format!("{}/test_lagging_follower", crate::TEST_DATA_DIR);
+        let servers = start_servers(rt.clone(), &dir, 8000..=8002);
+
+        // start node-0, node-1, node-2 as cluster
+        let members = btreemap! {
+            servers[0].node.raft_id()=>raft_node_info(servers[0].node.raft_id()),
+            servers[1].node.raft_id()=>raft_node_info(servers[1].node.raft_id()),
+            servers[2].node.raft_id()=>raft_node_info(servers[2].node.raft_id()),
+        };
+        rt.block_on(servers[0].node.raft_init(members)).unwrap();
+        std::thread::sleep(Duration::from_secs(3));
+        let leader = servers[0].node.raft_metrics().current_leader.unwrap();
+        let leader = servers.iter().find(|s| s.node.raft_id() == leader).unwrap();
+        let follower = servers
+            .iter()
+            .find(|s| s.node.raft_id() != leader.node.raft_id())
+            .unwrap();
+
+        #[derive(serde::Serialize, serde::Deserialize, Debug, Clone)]
+        struct RequestCommand {
+            key: String,
+            value: String,
+        }
+        let mut command = RequestCommand {
+            key: "test_key".to_string(),
+            value: "test_val".to_string(),
+        };
+
+        // write data 100 times
+        for i in 0..100 {
+            command.value = format!("v_{}", i);
+            let data = serde_json::to_string(&command).unwrap();
+            rt.block_on(leader.node.raw_raft().client_write(data.into()))
+                .unwrap();
+        }
+        {
+            std::thread::sleep(Duration::from_secs(1));
+            let engine = rt.block_on(follower.server.engine.read());
+            assert_eq!(engine.get(&command.key).unwrap().unwrap(), "v_99");
+        }
+
+        // close the follower, keep its storage
+        let group_id = follower.node.group_id();
+        let closed = rt
+            .block_on(async { follower.server.nodes.write().await.close(group_id).await })
+            .unwrap();
+        assert!(closed.is_some());
+
+        // write data 1000 times, the logs the follower lacks are purged by the leader
+        for i in 100..1100 {
+            command.value = format!("v_{}", i);
+            let data = serde_json::to_string(&command).unwrap();
+            rt.block_on(leader.node.raw_raft().client_write(data.into()))
+                .unwrap();
+        }
+        // the snapshot ships a tsm file of the leader, the follower received a part of it
+        let tsm_file = "tsm/_000001.tsm";
+        let leader_tsm = format!("{}/{}/{}", dir, leader.node.raft_id(), tsm_file);
+        let follower_tsm = format!("{}/{}/{}", dir, follower.node.raft_id(), tsm_file);
+        let tsm_data: Vec<u8> = (0..1024 * 1024).map(|i| (i * 31 % 251) as u8).collect();
+        std::fs::create_dir_all(std::path::Path::new(&leader_tsm).parent().unwrap()).unwrap();
+        std::fs::write(&leader_tsm, &tsm_data).unwrap();
+        std::fs::create_dir_all(std::path::Path::new(&follower_tsm).parent().unwrap()).unwrap();
+        std::fs::write(&follower_tsm, &tsm_data[..100 * 1024]).unwrap();
+
+        let last_applied = leader.node.raft_metrics().last_applied.unwrap();
+        rt.block_on(leader.node.raw_raft().trigger().snapshot())
+            .unwrap();
+        rt.block_on(leader.node.wait_condition(
+            |m| m.snapshot == Some(last_applied),
+            Duration::from_secs(10),
+            "leader build snapshot".to_string(),
+        ))
+        .unwrap();
+        rt.block_on(
+            leader
+                .node
+                .raw_raft()
+                .trigger()
+                .purge_log(last_applied.index),
+        )
+        .unwrap();
+        rt.block_on(leader.node.wait_condition(
+            |m| m.purged.map(|id| id.index) >= Some(200),
+            Duration::from_secs(10),
+            "leader purge logs".to_string(),
+        ))
+        .unwrap();
+        {
+            let engine = rt.block_on(follower.server.engine.read());
+            assert_eq!(engine.get(&command.key).unwrap().unwrap(), "v_99");
+        }
+
+        // reopen the follower, it catches up by installing the snapshot
+        let id = follower.node.raft_id();
+        let node = rt
+            .block_on(RaftNode::new(
+                id,
+                raft_node_info(id),
+                follower.server.storage.clone(),
+                crate::replication_config(),
+            ))
+            .unwrap();
+        let node = Arc::new(node);
+        rt.block_on(follower.server.nodes.write())
+            .add_node(node.clone());
+
+        let metrics = rt
+            .block_on(node.wait_condition(
+                |m| m.last_applied >= Some(last_applied),
+                Duration::from_secs(30),
+                "follower install snapshot".to_string(),
+            ))
+            .unwrap();
+        assert!(metrics.snapshot >= Some(last_applied));
+        {
+            let engine = rt.block_on(follower.server.engine.read());
+            assert_eq!(engine.get(&command.key).unwrap().unwrap(), "v_1099");
+        }
+        assert_eq!(std::fs::read(&follower_tsm).unwrap(), tsm_data);
+
+        let _ = std::fs::remove_dir_all(crate::TEST_DATA_DIR);
+    }
+
+    #[test]
+    fn test_linearizable_read() {
+        println!("----- begin test_linearizable_read -----");
+        let _ = std::fs::remove_dir_all(crate::TEST_DATA_DIR);
+
+        let rt = create_runtime();
+        let dir = format!("{}/test_linearizable_read", crate::TEST_DATA_DIR);
+        let servers = start_servers(rt.clone(), &dir, 8000..=8002);
+
+        // start node-0, node-1, node-2 as cluster
+        let members = btreemap! {
+            servers[0].node.raft_id()=>raft_node_info(servers[0].node.raft_id()),
+            servers[1].node.raft_id()=>raft_node_info(servers[1].node.raft_id()),
+            servers[2].node.raft_id()=>raft_node_info(servers[2].node.raft_id()),
+        };
+        rt.block_on(servers[0].node.raft_init(members)).unwrap();
+        std::thread::sleep(Duration::from_secs(3));
+        let leader = servers[0].node.raft_metrics().current_leader.unwrap();
+        let leader = servers.iter().find(|s| s.node.raft_id() == leader).unwrap();
+        let followers: Vec<_> = servers
+            .iter()
+            .filter(|s| s.node.raft_id() != leader.node.raft_id())
+            .collect();
+
+        #[derive(serde::Serialize, serde::Deserialize, Debug, Clone)]
+        struct RequestCommand {
+            key: String,
+            value: String,
+        }
+        let mut command = RequestCommand {
+            key: "test_key".to_string(),
+            value: "test_val".to_string(),
+        };
+
+        // write data 100 times
+        for i in 0..100 {
+            command.value = format!("v_{}", i);
+            let data = serde_json::to_string(&command).unwrap();
+            rt.block_on(leader.node.raw_raft().client_write(data.into()))
+                .unwrap();
+        }
+
+        // the leader confirms its leadership
+        rt.block_on(leader.node.linearizable_read(None)).unwrap();
+        {
+            let engine = rt.block_on(leader.server.engine.read());
+            assert_eq!(engine.get(&command.key).unwrap().unwrap(), "v_99");
+        }
+
+        // a follower without lag applies the read index got from the leader
+        rt.block_on(followers[0].node.linearizable_read(None))
+            .unwrap();
+        {
+            let engine = rt.block_on(followers[0].server.engine.read());
+            assert_eq!(engine.get(&command.key).unwrap().unwrap(), "v_99");
+        }
+
+        // close follower-0, keep its storage, so it lags behind the leader
+        let group_id = followers[0].node.group_id();
+        let closed = rt
+            .block_on(async {
+                followers[0]
+                    .server
+                    .nodes
+                    .write()
+                    .await
+                    .close(group_id)
+                    .await
+            })
+            .unwrap();
+        assert!(closed.is_some());
+        for i in 100..200 {
+            command.value = format!("v_{}", i);
+            let data = serde_json::to_string(&command).unwrap();
+            rt.block_on(leader.node.raw_raft().client_write(data.into()))
+                .unwrap();
+        }
+        {
+            let engine = rt.block_on(followers[0].server.engine.read());
+            assert_eq!(engine.get(&command.key).unwrap().unwrap(), "v_99");
+        }
+
+        // reopen follower-0, the read waits until the read index is applied
+        let id = followers[0].node.raft_id();
+        let node = rt
+            .block_on(RaftNode::new(
+                id,
+                raft_node_info(id),
+                followers[0].server.storage.clone(),
+                crate::replication_config(),
+            ))
+            .unwrap();
+        let node = Arc::new(node);
+        rt.block_on(followers[0].server.nodes.write())
+            .add_node(node.clone());
+        rt.block_on(node.wait_condition(
+            |m| m.current_leader.is_some(),
+            Duration::from_secs(10),
+            "follower knows the leader".to_string(),
+        ))
+        .unwrap();
+        rt.block_on(node.linearizable_read(None)).unwrap();
+        {
+            let engine = rt.block_on(followers[0].server.engine.read());
+            assert_eq!(engine.get(&command.key).unwrap().unwrap(), "v_199");
+        }
+
+        // follower-1 is checked, then the other nodes are down
+        rt.block_on(followers[1].node.linearizable_read(None))
+            .unwrap();
+        leader.handler.abort();
+        rt.block_on(leader.node.shutdown()).unwrap();
+        followers[0].handler.abort();
+        rt.block_on(node.shutdown()).unwrap();
+
+        // the check is skipped within the max lag, but fails without a quorum
+        rt.block_on(
+            followers[1]
+                .node
+                .linearizable_read(Some(Duration::from_secs(60))),
+        )
+        .unwrap();
+        assert!(rt
+            .block_on(followers[1].node.linearizable_read(None))
+            .is_err());
+        {
+            let engine = rt.block_on(followers[1].server.engine.read());
+            assert_eq!(engine.get(&command.key).unwrap().unwrap(), "v_199");
+        }
+
+        let _ = std::fs::remove_dir_all(crate::TEST_DATA_DIR);
+    }
 }
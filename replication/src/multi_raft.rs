@@ -50,6 +50,20 @@ impl MultiRaft {
         Ok(())
     }
 
+    /// Close and remove the running raft node, returns the closed node.
+    pub async fn close(
+        &mut self,
+        id: ReplicationSetId,
+    ) -> ReplicationResult<Option<Arc<RaftNode>>> {
+        if let Some((node, Status::Running)) = self.raft_nodes.get(&id).cloned() {
+            node.close().await?;
+            self.raft_nodes.remove(&id);
+            return Ok(Some(node));
+        }
+
+        Ok(None)
+    }
+
     pub fn get_node(&self, id: ReplicationSetId) -> ReplicationResult<Option<Arc<RaftNode>>> {
         if let Some((node, status)) = self.raft_nodes.get(&id).cloned() {
             match status {
@@ -2,17 +2,17 @@ use std::collections::HashMap;
 use std::sync::Arc;
 use std::time::Duration;
 
-use openraft::error::{InstallSnapshotError, NetworkError, RemoteError};
+use openraft::error::{CheckIsLeaderError, InstallSnapshotError, NetworkError, RemoteError};
 use openraft::network::{RPCOption, RaftNetwork, RaftNetworkFactory};
 use openraft::raft::*;
-use openraft::MessageSummary;
+use openraft::{LogId, MessageSummary};
 use parking_lot::RwLock;
 use protos::raft_service::*;
 use protos::{raft_service_time_out_client, DEFAULT_GRPC_SERVER_MESSAGE_LEN};
 use tonic::transport::{Channel, Endpoint};
 use trace::debug;
 
-use crate::errors::{GRPCRequestSnafu, ReplicationResult};
+use crate::errors::{GRPCRequestSnafu, MsgInvalidSnafu, RaftInternalErrSnafu, ReplicationResult};
 use crate::{RaftNodeId, RaftNodeInfo, ReplicationConfig, TypeConfig};
 
 // ------------------------------------------------------------------------- //
@@ -54,6 +54,47 @@ impl NetworkConn {
 
         Ok(channel)
     }
+
+    /// Ask the leader for the log id that must be applied before a linearizable read.
+    pub async fn read_index(
+        &self,
+        leader: &RaftNodeInfo,
+    ) -> ReplicationResult<Option<LogId<RaftNodeId>>> {
+        let channel = self.get_conn(&leader.address).await?;
+        let mut client = raft_service_time_out_client(
+            channel,
+            Duration::from_millis(self.config.send_append_entries_timeout),
+            DEFAULT_GRPC_SERVER_MESSAGE_LEN,
+            self.config.grpc_enable_gzip,
+        );
+
+        let cmd = tonic::Request::new(RaftReadIndexReq {
+            group_id: leader.group_id,
+        });
+        let rsp = client
+            .raft_read_index(cmd)
+            .await
+            .map_err(|err| {
+                GRPCRequestSnafu {
+                    msg: format!("Read index from({}) error: {}", leader.address, err),
+                }
+                .build()
+            })?
+            .into_inner();
+
+        let res: Result<
+            Option<LogId<RaftNodeId>>,
+            RaftError<CheckIsLeaderError<RaftNodeId, RaftNodeInfo>>,
+        > = serde_json::from_str(&rsp.data)
+            .map_err(|e| MsgInvalidSnafu { msg: e.to_string() }.build())?;
+
+        res.map_err(|err| {
+            RaftInternalErrSnafu {
+                msg: format!("Read index from({}) failed: {}", leader.address, err),
+            }
+            .build()
+        })
+    }
 }
 
 impl RaftNetworkFactory<TypeConfig> for NetworkConn {
@@ -107,4 +107,20 @@ impl RaftService for RaftCBServer {
 
         Ok(tonic::Response::new(RaftResponse { code: 0, data }))
     }
+
+    async fn raft_read_index(
+        &self,
+        request: tonic::Request<RaftReadIndexReq>,
+    ) -> std::result::Result<tonic::Response<RaftResponse>, tonic::Status> {
+        let inner = request.into_inner();
+
+        debug!("Network callback recv raft_read_index  req: {:?}", inner);
+
+        let node = self.get_node(inner.group_id).await?;
+        let res = node.raw_raft().ensure_linearizable().await;
+        let data =
+            serde_json::to_string(&res).unwrap_or("encode read index rsp failed".to_string());
+
+        Ok(tonic::Response::new(RaftResponse { code: 0, data }))
+    }
 }
@@ -1,9 +1,11 @@
 use std::collections::{BTreeMap, BTreeSet};
 use std::sync::Arc;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
+use openraft::error::ForwardToLeader;
 use openraft::storage::Adaptor;
 use openraft::{OptionalSend, RaftMetrics};
+use parking_lot::Mutex;
 use tracing::info;
 
 use crate::errors::{RaftInternalErrSnafu, ReplicationResult};
@@ -27,6 +29,10 @@ pub struct RaftNode {
     storage: Arc<NodeStorage>,
 
     raft: OpenRaftNode,
+    network: NetworkConn,
+    read_timeout: Duration,
+    /// When the last linearizable read check started.
+    last_read_check: Arc<Mutex<Option<Instant>>>,
 }
 
 impl RaftNode {
@@ -57,7 +63,7 @@ impl RaftNode {
         let (log_store, state_machine) = Adaptor::new(storage.clone());
 
         let network = NetworkConn::new(config.clone());
-        let raft = openraft::Raft::new(id, raft_config, network, log_store, state_machine)
+        let raft = openraft::Raft::new(id, raft_config, network.clone(), log_store, state_machine)
             .await
             .map_err(|err| {
                 RaftInternalErrSnafu {
@@ -71,6 +77,9 @@ impl RaftNode {
             info,
             storage,
             raft,
+            network,
+            read_timeout: Duration::from_millis(config.send_append_entries_timeout),
+            last_read_check: Arc::new(Mutex::new(None)),
         })
     }
 
@@ -149,6 +158,58 @@ impl RaftNode {
         Ok(())
     }
 
+    /// Make sure the state machine of this node has applied all the logs committed
+    /// before the call, so a following read on it is linearizable.
+    ///
+    /// The leader confirms its leadership with a quorum, other nodes get the read index
+    /// from the leader and wait for it to be applied locally. If `max_lag` is set, the
+    /// check is skipped when the last one started within `max_lag`, the read may miss
+    /// the logs committed after that.
+    pub async fn linearizable_read(&self, max_lag: Option<Duration>) -> ReplicationResult<()> {
+        if let (Some(max_lag), Some(last)) = (max_lag, *self.last_read_check.lock()) {
+            if last.elapsed() <= max_lag {
+                return Ok(());
+            }
+        }
+
+        let start = Instant::now();
+        let read_log_id = match self.raft.ensure_linearizable().await {
+            // The read index is already applied on the leader.
+            Ok(_) => None,
+            Err(err) => match err.forward_to_leader() {
+                Some(ForwardToLeader {
+                    leader_node: Some(leader),
+                    ..
+                }) => self.network.read_index(leader).await?,
+                _ => {
+                    return Err(RaftInternalErrSnafu {
+                        msg: format!(
+                            "Get read index of raft group({}) failed: {}",
+                            self.info.group_id, err
+                        ),
+                    }
+                    .build())
+                }
+            },
+        };
+
+        if let Some(log_id) = read_log_id {
+            self.raft
+                .wait(Some(self.read_timeout))
+                .applied_index_at_least(Some(log_id.index), "apply read index")
+                .await
+                .map_err(|err| {
+                    RaftInternalErrSnafu {
+                        msg: format!("Wait read index {} applied failed: {}", log_id, err),
+                    }
+                    .build()
+                })?;
+        }
+
+        *self.last_read_check.lock() = Some(start);
+        Ok(())
+    }
+
     pub async fn shutdown(&self) -> ReplicationResult<()> {
         self.raft.shutdown().await.map_err(|err| {
             RaftInternalErrSnafu {
@@ -162,6 +223,18 @@ impl RaftNode {
         Ok(())
     }
 
+    /// Stop the raft node and keep the storage, the node can be opened again.
+    pub async fn close(&self) -> ReplicationResult<()> {
+        self.raft.shutdown().await.map_err(|err| {
+            RaftInternalErrSnafu {
+                msg: err.to_string(),
+            }
+            .build()
+        })?;
+
+        Ok(())
+    }
+
     pub async fn wait_condition<FUN>(
         &self,
         func: FUN,
@@ -0,0 +1,427 @@
+//! Backup column files of vnodes into an object store, and restore vnodes from it.
+//!
+//! Files of a backed up vnode are stored under the location of the vnode:
+//! - `tsm/_000001.tsm`, `delta/_000002.delta`: column files, they are immutable, so
+//!   they are shared by all backups of the vnode.
+//! - `backups/<backup_id>/summary`: a `VersionEdit` that contains all column files
+//!   of the vnode in the backup.
+//! - `backups/<backup_id>/tsm/_000001.tombstone`: tombstone of a column file, if exists.
+//! - `backups/<backup_id>/index/index.db`: files of the series index, taken after the
+//!   column files, so it contains all series in them.
+//!
+//! A backup doesn't change files of other backups, except that column files which are
+//! already in the location are uploaded again if the backup is not incremental.
+//!
+//! The index is restored with the column files, the index is rebuilt from column files
+//! only if it's not in the backup.
+
+use std::path::Path;
+use std::sync::Arc;
+
+use datafusion::arrow::array::{UInt32Array, UInt64Array};
+use datafusion::arrow::datatypes::{DataType, Field, Schema, SchemaRef};
+use datafusion::arrow::record_batch::RecordBatch;
+use futures::StreamExt;
+use models::meta_data::VnodeId;
+use object_store::path::Path as ObjectPath;
+use object_store::ObjectStore;
+use snafu::ResultExt;
+use tokio::io::AsyncWriteExt;
+
+use crate::error::{ArrowSnafu, CommonSnafu, IOSnafu, ObjectStoreSnafu};
+use crate::kv_option::INDEX_PATH;
+use crate::summary::VersionEdit;
+use crate::{TskvError, TskvResult};
+
+pub const BACKUP_SUMMARY_FILE: &str = "summary";
+pub const BACKUPS_DIR: &str = "backups";
+
+/// Statistics of backing up a vnode.
+#[derive(Debug, Clone, Default)]
+pub struct VnodeBackupReport {
+    pub vnode_id: VnodeId,
+    /// Number of column files, tombstones and index files in the backup.
+    pub files: u64,
+    /// Number of files uploaded, column files already in the location
+    /// are skipped in an incremental backup.
+    pub uploaded_files: u64,
+    pub uploaded_bytes: u64,
+}
+
+impl VnodeBackupReport {
+    pub fn to_record_batch(&self) -> TskvResult<RecordBatch> {
+        RecordBatch::try_new(
+            backup_vnode_report_schema(),
+            vec![
+                Arc::new(UInt32Array::from(vec![self.vnode_id])),
+                Arc::new(UInt64Array::from(vec![self.files])),
+                Arc::new(UInt64Array::from(vec![self.uploaded_files])),
+                Arc::new(UInt64Array::from(vec![self.uploaded_bytes])),
+            ],
+        )
+        .context(ArrowSnafu)
+    }
+}
+
+pub fn backup_vnode_report_schema() -> SchemaRef {
+    Arc::new(Schema::new(vec![
+        Field::new("vnode_id", DataType::UInt32, false),
+        Field::new("files", DataType::UInt64, false),
+        Field::new("uploaded_files", DataType::UInt64, false),
+        Field::new("uploaded_bytes", DataType::UInt64, false),
+    ]))
+}
+
+/// Location of the files of a backup of the vnode, except the column files.
+pub fn backup_location(location: &ObjectPath, backup_id: &str) -> ObjectPath {
+    location.child(BACKUPS_DIR).child(backup_id)
+}
+
+/// Upload column files in the version edit of a vnode into the location, and the
+/// tombstones and files in the index directory into the location of the backup,
+/// the summary is uploaded at last. Files must not be deleted until the backup finished.
+///
+/// Column files are immutable, so in an incremental backup, files that already in
+/// the location with the same size are skipped. Tombstones and index files are always
+/// uploaded.
+pub async fn backup_vnode(
+    store: Arc<dyn ObjectStore>,
+    location: &ObjectPath,
+    backup_id: &str,
+    vnode_dir: &Path,
+    index_dir: &Path,
+    version_edit: &VersionEdit,
+    incremental: bool,
+) -> TskvResult<VnodeBackupReport> {
+    let mut report = VnodeBackupReport {
+        vnode_id: version_edit.tsf_id,
+        ..Default::default()
+    };
+    let backup_location = backup_location(location, backup_id);
+
+    for file in version_edit.add_files.iter() {
+        report.files += 1;
+        let relative_path = file.relative_path();
+        let file_location = object_path(location, &relative_path);
+        let uploaded = incremental
+            && matches!(
+                store.head(&file_location).await,
+                Ok(meta) if meta.size as u64 == file.file_size
+            );
+        if !uploaded {
+            report.uploaded_bytes += upload_file(
+                store.as_ref(),
+                &vnode_dir.join(&relative_path),
+                &file_location,
+            )
+            .await?;
+            report.uploaded_files += 1;
+        }
+
+        let relative_path = file.relative_tombstone_path();
+        let tombstone_path = vnode_dir.join(&relative_path);
+        if tombstone_path.exists() {
+            report.files += 1;
+            report.uploaded_bytes += upload_file(
+                store.as_ref(),
+                &tombstone_path,
+                &object_path(&backup_location, &relative_path),
+            )
+            .await?;
+            report.uploaded_files += 1;
+        }
+    }
+
+    let files = std::fs::read_dir(index_dir).context(IOSnafu)?;
+    for file in files {
+        let file = file.context(IOSnafu)?;
+        let relative_path = Path::new(INDEX_PATH).join(file.file_name());
+        report.files += 1;
+        report.uploaded_bytes += upload_file(
+            store.as_ref(),
+            &file.path(),
+            &object_path(&backup_location, &relative_path),
+        )
+        .await?;
+        report.uploaded_files += 1;
+    }
+
+    let summary = version_edit.encode()?;
+    store
+        .put(
+            &object_path(&backup_location, Path::new(BACKUP_SUMMARY_FILE)),
+            summary.into(),
+        )
+        .await
+        .context(ObjectStoreSnafu)?;
+
+    Ok(report)
+}
+
+/// Copy files in the index directory of a vnode into another directory. The index
+/// must be flushed and not be written during copying, the copy is uploaded then,
+/// so writes to the index are not blocked by uploading.
+pub fn copy_index_files(index_dir: &Path, dest: &Path) -> TskvResult<()> {
+    std::fs::create_dir_all(dest).context(IOSnafu)?;
+    if !index_dir.exists() {
+        return Ok(());
+    }
+    for file in std::fs::read_dir(index_dir).context(IOSnafu)? {
+        let file = file.context(IOSnafu)?;
+        std::fs::copy(file.path(), dest.join(file.file_name())).context(IOSnafu)?;
+    }
+
+    Ok(())
+}
+
+/// Download the summary and files of a backup of the vnode into the directory,
+/// returns the version edit in the summary. Index files are downloaded into
+/// the `index` directory in it.
+pub async fn download_vnode(
+    store: Arc<dyn ObjectStore>,
+    location: &ObjectPath,
+    backup_id: &str,
+    dir: &Path,
+) -> TskvResult<VersionEdit> {
+    let backup_location = backup_location(location, backup_id);
+    let summary = store
+        .get(&object_path(
+            &backup_location,
+            Path::new(BACKUP_SUMMARY_FILE),
+        ))
+        .await
+        .context(ObjectStoreSnafu)?
+        .bytes()
+        .await
+        .context(ObjectStoreSnafu)?;
+    let version_edit = VersionEdit::decode(&summary)?;
+
+    for file in version_edit.add_files.iter() {
+        let relative_path = file.relative_path();
+        let size = download_file(
+            store.as_ref(),
+            &object_path(location, &relative_path),
+            &dir.join(&relative_path),
+        )
+        .await?;
+        if size != file.file_size {
+            return Err(CommonSnafu {
+                reason: format!(
+                    "size of downloaded file '{}' is {}, expected {}",
+                    relative_path.display(),
+                    size,
+                    file.file_size
+                ),
+            }
+            .build());
+        }
+
+        let relative_path = file.relative_tombstone_path();
+        match download_file(
+            store.as_ref(),
+            &object_path(&backup_location, &relative_path),
+            &dir.join(&relative_path),
+        )
+        .await
+        {
+            Ok(_) => {}
+            Err(TskvError::ObjectStore {
+                source: object_store::Error::NotFound { .. },
+                ..
+            }) => {}
+            Err(e) => return Err(e),
+        }
+    }
+
+    let index_location = object_path(&backup_location, Path::new(INDEX_PATH));
+    let index_files: Vec<_> = store
+        .list(Some(&index_location))
+        .await
+        .context(ObjectStoreSnafu)?
+        .collect()
+        .await;
+    for file in index_files {
+        let file = file.context(ObjectStoreSnafu)?;
+        let file_name = match file.location.filename() {
+            Some(name) => name.to_string(),
+            None => continue,
+        };
+        download_file(
+            store.as_ref(),
+            &file.location,
+            &dir.join(INDEX_PATH).join(file_name),
+        )
+        .await?;
+    }
+
+    Ok(version_edit)
+}
+
+fn object_path(location: &ObjectPath, relative_path: &Path) -> ObjectPath {
+    relative_path.iter().fold(location.clone(), |path, part| {
+        path.child(part.to_string_lossy().as_ref())
+    })
+}
+
+async fn upload_file(
+    store: &dyn ObjectStore,
+    path: &Path,
+    location: &ObjectPath,
+) -> TskvResult<u64> {
+    trace::debug!("Upload file '{}' to '{}'", path.display(), location);
+    let mut file = tokio::fs::File::open(path).await.context(IOSnafu)?;
+    let (multipart_id, mut writer) = store
+        .put_multipart(location)
+        .await
+        .context(ObjectStoreSnafu)?;
+    let size = match tokio::io::copy(&mut file, &mut writer).await {
+        Ok(size) => size,
+        Err(e) => {
+            let _ = store.abort_multipart(location, &multipart_id).await;
+            return Err(e).context(IOSnafu);
+        }
+    };
+    writer.shutdown().await.context(IOSnafu)?;
+
+    Ok(size)
+}
+
+async fn download_file(
+    store: &dyn ObjectStore,
+    location: &ObjectPath,
+    path: &Path,
+) -> TskvResult<u64> {
+    trace::debug!("Download file '{}' to '{}'", location, path.display());
+    let mut stream = store
+        .get(location)
+        .await
+        .context(ObjectStoreSnafu)?
+        .into_stream();
+    if let Some(dir) = path.parent() {
+        tokio::fs::create_dir_all(dir).await.context(IOSnafu)?;
+    }
+    let mut file = tokio::fs::File::create(path).await.context(IOSnafu)?;
+    let mut size = 0_u64;
+    while let Some(bytes) = stream.next().await {
+        let bytes = bytes.context(ObjectStoreSnafu)?;
+        file.write_all(&bytes).await.context(IOSnafu)?;
+        size += bytes.len() as u64;
+    }
+    file.sync_all().await.context(IOSnafu)?;
+
+    Ok(size)
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::Path;
+    use std::sync::Arc;
+
+    use object_store::memory::InMemory;
+    use object_store::path::Path as ObjectPath;
+
+    use super::{backup_vnode, download_vnode};
+    use crate::kv_option::{DELTA_PATH, INDEX_PATH, TSM_PATH};
+    use crate::summary::{CompactMeta, VersionEdit};
+    use crate::{file_utils, tsm};
+
+    fn write_file(path: &Path, data: &[u8]) -> u64 {
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, data).unwrap();
+        data.len() as u64
+    }
+
+    #[tokio::test]
+    async fn test_backup_and_download_vnode() {
+        let dir = Path::new("/tmp/test/cnosdb/backup/vnode");
+        let _ = std::fs::remove_dir_all(dir);
+        let vnode_dir = dir.join("1");
+        let index_dir = dir.join("index_copy");
+        let restore_dir = dir.join("restore");
+        write_file(&index_dir.join("index.db"), b"index");
+
+        let mut version_edit = VersionEdit::new_add_vnode(1, "cnosdb.public".to_string(), 10);
+        let tsm_file = file_utils::make_tsm_file(vnode_dir.join(TSM_PATH), 3);
+        let tsm_meta = CompactMeta {
+            file_id: 3,
+            file_size: write_file(&tsm_file, b"tsm file"),
+            tsf_id: 1,
+            level: 1,
+            ..Default::default()
+        };
+        write_file(
+            &tsm_file.with_extension(tsm::TOMBSTONE_FILE_SUFFIX),
+            b"tombstone",
+        );
+        version_edit.add_file(tsm_meta, 0);
+        let delta_file = file_utils::make_delta_file(vnode_dir.join(DELTA_PATH), 5);
+        let delta_meta = CompactMeta {
+            file_id: 5,
+            file_size: write_file(&delta_file, b"delta file"),
+            tsf_id: 1,
+            is_delta: true,
+            ..Default::default()
+        };
+        version_edit.add_file(delta_meta, 0);
+
+        let store = Arc::new(InMemory::new());
+        let location = ObjectPath::from("backup/vnodes/1");
+        let report = backup_vnode(
+            store.clone(),
+            &location,
+            "1",
+            &vnode_dir,
+            &index_dir,
+            &version_edit,
+            true,
+        )
+        .await
+        .unwrap();
+        assert_eq!(report.files, 4);
+        assert_eq!(report.uploaded_files, 4);
+
+        // Column files are skipped in the next incremental backup, tombstones and index are not.
+        let mut next_version_edit = version_edit.clone();
+        next_version_edit.add_files.truncate(1);
+        let report = backup_vnode(
+            store.clone(),
+            &location,
+            "2",
+            &vnode_dir,
+            &index_dir,
+            &next_version_edit,
+            true,
+        )
+        .await
+        .unwrap();
+        assert_eq!(report.files, 3);
+        assert_eq!(report.uploaded_files, 2);
+        assert_eq!(report.uploaded_bytes, 14);
+
+        // The first backup is not changed by the next one.
+        let restored = download_vnode(store.clone(), &location, "1", &restore_dir)
+            .await
+            .unwrap();
+        assert_eq!(restored, version_edit);
+        let tsm_file = file_utils::make_tsm_file(restore_dir.join(TSM_PATH), 3);
+        assert_eq!(std::fs::read(&tsm_file).unwrap(), b"tsm file");
+        assert_eq!(
+            std::fs::read(tsm_file.with_extension(tsm::TOMBSTONE_FILE_SUFFIX)).unwrap(),
+            b"tombstone"
+        );
+        let delta_file = file_utils::make_delta_file(restore_dir.join(DELTA_PATH), 5);
+        assert_eq!(std::fs::read(delta_file).unwrap(), b"delta file");
+        assert_eq!(
+            std::fs::read(restore_dir.join(INDEX_PATH).join("index.db")).unwrap(),
+            b"index"
+        );
+
+        let restore_dir = dir.join("restore_2");
+        let restored = download_vnode(store, &location, "2", &restore_dir)
+            .await
+            .unwrap();
+        assert_eq!(restored, next_version_edit);
+        let delta_file = file_utils::make_delta_file(restore_dir.join(DELTA_PATH), 5);
+        assert!(!delta_file.exists());
+    }
+}
@@ -1,13 +1,15 @@
 use std::borrow::Cow;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::mem::size_of;
 use std::path::Path;
 use std::sync::Arc;
 
+use config::common::TenantObjectLimiterConfig;
 use flatbuffers::{ForwardsUOffset, Vector};
 use memory_pool::MemoryPoolRef;
 use meta::model::MetaRef;
 use metrics::metric_register::MetricsRegister;
+use models::meta_data::TenantSeriesCount;
 use models::predicate::domain::TimeRange;
 use models::schema::{DatabaseSchema, Precision, TskvTableSchema, TskvTableSchemaRef};
 use models::{SeriesId, SeriesKey};
@@ -18,7 +20,8 @@ use tokio::sync::{oneshot, RwLock};
 use trace::error;
 
 use crate::error::{
-    CommonSnafu, IndexErrSnafu, ModelSnafu, SchemaSnafu, TableNotFoundSnafu, TskvResult,
+    CommonSnafu, IndexErrSnafu, ModelSnafu, SchemaSnafu, SeriesLimitExceededSnafu,
+    TableNotFoundSnafu, TskvResult,
 };
 use crate::index::ts_index::TSIndex;
 use crate::index::IndexResult;
@@ -45,6 +48,14 @@ pub struct Database {
     tsf_factory: TsfFactory,
 }
 
+/// The series limits of the tenant, and the series of the tenant in the cluster
+/// if any limit in the cluster is set.
+struct SeriesLimiter {
+    config: TenantObjectLimiterConfig,
+    db_name: Arc<String>,
+    cluster_series: Option<Arc<TenantSeriesCount>>,
+}
+
 #[derive(Debug)]
 pub struct DatabaseFactory {
     meta: MetaRef,
@@ -235,6 +246,12 @@ impl Database {
         strict_write: Option<bool>,
     ) -> TskvResult<HashMap<SeriesId, (SeriesKey, RowGroup)>> {
         let strict_write = strict_write.unwrap_or(self.opt.storage.strict_write);
+        // series limits are not checked when recovering from wal, the data was accepted already
+        let series_limiter = if recover_from_wal {
+            None
+        } else {
+            self.series_limiter().await?
+        };
 
         // (series id, schema id) -> RowGroup
         let mut map = HashMap::new();
@@ -268,6 +285,7 @@ impl Database {
                 num_rows,
                 ts_index.clone(),
                 recover_from_wal,
+                series_limiter.as_ref(),
             )
             .await?;
             // every row produces a sid
@@ -327,6 +345,40 @@ impl Database {
         Ok(())
     }
 
+    async fn series_limiter(&self) -> TskvResult<Option<SeriesLimiter>> {
+        let config = match self
+            .schemas
+            .object_limiter_config()
+            .await
+            .context(SchemaSnafu)?
+        {
+            Some(config) if config.limits_vnode_series() || config.limits_cluster_series() => {
+                config
+            }
+            _ => return Ok(None),
+        };
+        // writes are not refused because meta is not reachable
+        let cluster_series = if config.limits_cluster_series() {
+            match self.schemas.cluster_series_count().await {
+                Ok(count) => Some(count),
+                Err(e) => {
+                    trace::warn!(
+                        "Failed to get series count, series limits in cluster are not checked: {e}"
+                    );
+                    None
+                }
+            }
+        } else {
+            None
+        };
+
+        Ok(Some(SeriesLimiter {
+            config,
+            db_name: self.db_name.clone(),
+            cluster_series,
+        }))
+    }
+
     async fn build_index<'a>(
         fb_schema: &'a FbSchema<'a>,
         columns: &Vector<'a, ForwardsUOffset<Column<'a>>>,
@@ -334,6 +386,7 @@ impl Database {
         row_num: usize,
         ts_index: Arc<RwLock<TSIndex>>,
         recover_from_wal: bool,
+        series_limiter: Option<&SeriesLimiter>,
     ) -> TskvResult<Vec<(u32, SeriesKey)>> {
         let mut res_sids = Vec::with_capacity(row_num);
         let mut series_keys = Vec::with_capacity(row_num);
@@ -373,13 +426,17 @@ impl Database {
         }
         drop(ts_index_r);
 
-        let mut ids = ts_index
-            .write()
-            .await
+        let mut ts_index_w = ts_index.write().await;
+        if let Some(limiter) = series_limiter {
+            Self::check_series_limit(&mut ts_index_w, fb_schema.table, &series_keys, limiter)
+                .await?;
+        }
+        let mut ids = ts_index_w
             .add_series_if_not_exists(series_keys)
             .await
             .context(IndexErrSnafu)?
             .into_iter();
+        drop(ts_index_w);
         for item in res_sids.iter_mut() {
             if item.is_none() {
                 *item = Some(ids.next().context(CommonSnafu {
@@ -392,6 +449,103 @@ impl Database {
         Ok(res_sids)
     }
 
+    /// Check if the new series keys of the table exceed the series limits of the tenant.
+    /// The limits per vnode are checked against the series in the vnode of this index,
+    /// the limits in the cluster against the series reported to meta.
+    async fn check_series_limit(
+        ts_index: &mut TSIndex,
+        table: &str,
+        series_keys: &[SeriesKey],
+        limiter: &SeriesLimiter,
+    ) -> TskvResult<()> {
+        // series keys may be added by other writes since they were checked, and rows may
+        // have the same series key, so count the new series again
+        let mut new_series = HashSet::new();
+        for series_key in series_keys {
+            if ts_index
+                .get_series_id(series_key)
+                .await
+                .context(IndexErrSnafu)?
+                .is_none()
+            {
+                new_series.insert(series_key);
+            }
+        }
+        if new_series.is_empty() {
+            return Ok(());
+        }
+        let new_series_num = new_series.len() as u64;
+        let config = &limiter.config;
+
+        if let Some(max) = config.max_table_series_per_vnode {
+            let count = ts_index
+                .get_table_series_count(table)
+                .await
+                .context(IndexErrSnafu)?;
+            if count + new_series_num > max as u64 {
+                return Err(SeriesLimitExceededSnafu {
+                    reason: format!(
+                        "table '{}' has {} series in vnode, adding {} series exceeds max_table_series_per_vnode {}",
+                        table, count, new_series_num, max
+                    ),
+                }
+                .build());
+            }
+        }
+
+        if let Some(max) = config.max_series_per_vnode {
+            let count = ts_index.get_series_count().await.context(IndexErrSnafu)?;
+            if count + new_series_num > max as u64 {
+                return Err(SeriesLimitExceededSnafu {
+                    reason: format!(
+                        "database has {} series in vnode, adding {} series exceeds max_series_per_vnode {}",
+                        count, new_series_num, max
+                    ),
+                }
+                .build());
+            }
+        }
+
+        if let Some(cluster_series) = limiter.cluster_series.as_ref() {
+            let db = limiter.db_name.as_str();
+            let limits = [
+                (
+                    config.max_series_per_table,
+                    cluster_series.table_series(db, table),
+                    format!("table '{}'", table),
+                    "max_series_per_table",
+                ),
+                (
+                    config.max_series_per_database,
+                    cluster_series.database_series(db),
+                    format!("database '{}'", db),
+                    "max_series_per_database",
+                ),
+                (
+                    config.max_series_per_tenant,
+                    cluster_series.series,
+                    "tenant".to_string(),
+                    "max_series_per_tenant",
+                ),
+            ];
+            for (max, count, object, limit_name) in limits {
+                if let Some(max) = max {
+                    if count + new_series_num > max as u64 {
+                        return Err(SeriesLimitExceededSnafu {
+                            reason: format!(
+                                "{} has {} series in cluster, adding {} series exceeds {} {}",
+                                object, count, new_series_num, limit_name, max
+                            ),
+                        }
+                        .build());
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+
     pub async fn get_series_key(
         &self,
         vnode_id: u32,
@@ -8,6 +8,8 @@ use datafusion::arrow::record_batch::RecordBatch;
 use models::meta_data::VnodeId;
 use models::predicate::domain::ColumnDomains;
 use models::{SeriesId, SeriesKey};
+use object_store::path::Path as ObjectPath;
+use object_store::ObjectStore;
 
 use crate::error::TskvResult;
 use crate::kv_option::StorageOptions;
@@ -110,5 +112,32 @@ impl Engine for MockEngine {
         todo!()
     }
 
+    async fn rebuild_index(&self, vnode_id: VnodeId) -> TskvResult<RecordBatch> {
+        todo!()
+    }
+
+    async fn backup_vnode(
+        &self,
+        vnode_id: VnodeId,
+        store: Arc<dyn ObjectStore>,
+        location: ObjectPath,
+        backup_id: &str,
+        incremental: bool,
+    ) -> TskvResult<RecordBatch> {
+        todo!()
+    }
+
+    async fn restore_vnode(
+        &self,
+        tenant: &str,
+        database: &str,
+        vnode_id: VnodeId,
+        store: Arc<dyn ObjectStore>,
+        location: ObjectPath,
+        backup_id: &str,
+    ) -> TskvResult<()> {
+        todo!()
+    }
+
     async fn close(&self) {}
 }
@@ -405,6 +405,22 @@ pub enum TskvError {
         source: FileSystemError,
     },
 
+    #[error_code(code = 59)]
+    #[snafu(display("Series limit exceeded: {}", reason))]
+    SeriesLimitExceeded {
+        reason: String,
+        location: Location,
+        backtrace: Backtrace,
+    },
+
+    #[error_code(code = 60)]
+    #[snafu(display("Object store error: {}", source))]
+    ObjectStore {
+        source: object_store::Error,
+        location: Location,
+        backtrace: Backtrace,
+    },
+
     #[snafu(display("ModelError: {}", source))]
     #[error_code(code = 89)]
     ModelError {
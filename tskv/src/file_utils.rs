@@ -122,6 +122,36 @@ pub fn get_wal_file_id(file_name: &str) -> TskvResult<u64> {
     })
 }
 
+/// Make a path for hinted handoff file by it's directory and id.
+pub fn make_hinted_handoff_file(dir: impl AsRef<Path>, sequence: u64) -> PathBuf {
+    let p = format!("_{:06}.hh", sequence);
+    dir.as_ref().join(p)
+}
+
+/// Check a hinted handoff file's name.
+pub fn check_hinted_handoff_file_name(file_name: &str) -> bool {
+    HINTEDOFF_FILE_NAME_PATTERN.is_match(file_name)
+}
+
+/// Get id from a hinted handoff file's name.
+pub fn get_hinted_handoff_file_id(file_name: &str) -> TskvResult<u64> {
+    if !check_hinted_handoff_file_name(file_name) {
+        return Err(InvalidFileNameSnafu {
+            file_name: file_name.to_string(),
+            message: "hinted handoff file name does not contain an id".to_string(),
+        }
+        .build());
+    }
+    let file_number = &file_name[1..7];
+    file_number.parse::<u64>().map_err(|_| {
+        InvalidFileNameSnafu {
+            file_name: file_name.to_string(),
+            message: "hinted handoff file name contains an invalid id".to_string(),
+        }
+        .build()
+    })
+}
+
 pub fn make_tsm_file_name(sequence: u64) -> String {
     format!("_{:06}.tsm", sequence)
 }
@@ -216,6 +216,24 @@ impl IndexMemCache {
         self.id_map.get(&id).map(|info| info.key.clone())
     }
 
+    pub fn series(&self) -> Vec<(SeriesId, SeriesKey)> {
+        self.id_map
+            .iter()
+            .map(|(id, info)| (*id, info.key.clone()))
+            .collect()
+    }
+
+    pub fn series_count(&self) -> usize {
+        self.id_map.len()
+    }
+
+    pub fn table_series_count(&self, tab: &str) -> usize {
+        self.id_map
+            .values()
+            .filter(|info| info.key.table() == tab)
+            .count()
+    }
+
     pub fn get_inverted_by_range(
         &self,
         tab: &str,
@@ -236,6 +254,26 @@ impl IndexMemCache {
         bitmap
     }
 
+    /// Returns series ids of the tag values that the filter returns true.
+    pub fn get_inverted_by_filter(
+        &self,
+        tab: &str,
+        tag_key: &str,
+        filter: impl Fn(&[u8]) -> bool,
+    ) -> roaring::RoaringBitmap {
+        let tag_map = self
+            .inverted
+            .get(tab)
+            .and_then(|item| item.get(tag_key.as_bytes()));
+        let mut bitmap = roaring::RoaringBitmap::new();
+        if let Some(bt) = tag_map {
+            for (_, rb) in bt.iter().filter(|(val, _)| filter(val)) {
+                bitmap = bitmap.bitor(rb);
+            }
+        }
+        bitmap
+    }
+
     pub fn get_inverted_by_tags(&self, tab: &str, tags: &[models::Tag]) -> roaring::RoaringBitmap {
         if tags.is_empty() {
             let mut bitmap = roaring::RoaringBitmap::new();
@@ -9,6 +9,9 @@ use snafu::ResultExt;
 
 use super::{IndexResult, IndexStorageSnafu, RoaringBitmapSnafu};
 
+/// The file of the index in the index directory.
+pub const INDEX_DB_FILE: &str = "index.db";
+
 #[derive(Debug)]
 pub struct IndexEngine {
     db: radixdb::RadixTree<store::PagedFileStore>,
@@ -21,7 +24,7 @@ impl IndexEngine {
         let _ = fs::create_dir_all(path);
         trace::debug!("Creating index engine : {:?}", &path);
 
-        let db_path = path.join("index.db");
+        let db_path = path.join(INDEX_DB_FILE);
         let file = fs::OpenOptions::new()
             .create(true)
             .read(true)
@@ -212,6 +215,27 @@ impl IndexEngine {
         Ok(bitmap)
     }
 
+    /// Returns series ids of the keys with the prefix that the filter returns true,
+    /// the filter is called with the key without the prefix.
+    pub fn get_series_id_by_prefix_filter(
+        &self,
+        prefix: &[u8],
+        filter: impl Fn(&[u8]) -> bool,
+    ) -> IndexResult<roaring::RoaringBitmap> {
+        let mut bitmap = roaring::RoaringBitmap::new();
+        for item in self.prefix(prefix)? {
+            let item = item.map_err(|e| IndexStorageSnafu { msg: e.to_string() }.build())?;
+            if let Some(suffix) = item.0.as_ref().strip_prefix(prefix) {
+                if filter(suffix) {
+                    let rb = self.load_rb(&item.1)?;
+                    bitmap = bitmap.bitor(rb);
+                }
+            }
+        }
+
+        Ok(bitmap)
+    }
+
     pub fn get_series_id_by_tags(
         &self,
         tab: &str,
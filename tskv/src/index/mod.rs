@@ -2,6 +2,7 @@ mod engine;
 mod errors;
 
 pub mod cache;
+pub mod rebuild;
 pub mod ts_index;
 pub use engine::*;
 pub use errors::*;
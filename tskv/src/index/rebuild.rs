@@ -0,0 +1,695 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt::Display;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use datafusion::arrow::array::{UInt32Array, UInt64Array};
+use datafusion::arrow::datatypes::{DataType, Field, Schema, SchemaRef};
+use datafusion::arrow::record_batch::RecordBatch;
+use models::meta_data::VnodeId;
+use models::schema::TskvTableSchemaRef;
+use models::{SeriesId, SeriesKey};
+use openraft::EntryPayload;
+use protos::kv_service::{raft_write_command, RaftWriteCommand};
+use protos::models_helper::parse_prost_bytes;
+use snafu::{IntoError, OptionExt, ResultExt};
+use tokio::sync::RwLock;
+
+use super::engine::INDEX_DB_FILE;
+use super::ts_index::TSIndex;
+use crate::database::FbSchema;
+use crate::error::{
+    ArrowSnafu, CommonSnafu, DecodeSnafu, IOSnafu, IndexErrSnafu, InvalidFlatbufferSnafu,
+    InvalidPointTableSnafu,
+};
+use crate::file_system::async_filesystem::LocalFileSystem;
+use crate::file_system::FileSystem;
+use crate::kv_option::{DELTA_PATH, INDEX_PATH, TSM_PATH};
+use crate::tseries_family::{TseriesFamily, Version};
+use crate::tsm::reader::TsmReader;
+use crate::wal::WalReader;
+use crate::{file_utils, TskvError, TskvResult};
+
+/// A series in column files or caches that the index does not match.
+#[derive(Debug, Clone)]
+pub enum SeriesMismatch {
+    /// The series is not in the index.
+    Missing { id: SeriesId, key: SeriesKey },
+    /// The series id is mapped to another series key in the index.
+    KeyMismatch {
+        id: SeriesId,
+        key: SeriesKey,
+        index_key: SeriesKey,
+    },
+    /// The series key is mapped to another series id in the index.
+    IdMismatch {
+        id: SeriesId,
+        key: SeriesKey,
+        index_id: SeriesId,
+    },
+    /// The series is only in the WAL, and it's not in the index.
+    MissingInWal { key: SeriesKey },
+}
+
+impl Display for SeriesMismatch {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SeriesMismatch::Missing { id, key } => {
+                write!(f, "missing series id: {id}, key: {key}")
+            }
+            SeriesMismatch::KeyMismatch { id, key, index_key } => {
+                write!(
+                    f,
+                    "mismatched series id: {id}, key: {key}, key in index: {index_key}"
+                )
+            }
+            SeriesMismatch::IdMismatch { id, key, index_id } => {
+                write!(
+                    f,
+                    "mismatched series id: {id}, key: {key}, id in index: {index_id}"
+                )
+            }
+            SeriesMismatch::MissingInWal { key } => {
+                write!(f, "missing series in WAL, key: {key}")
+            }
+        }
+    }
+}
+
+/// The result of comparing the series of a vnode with it's index.
+#[derive(Debug, Clone, Default)]
+pub struct IndexRebuildReport {
+    pub vnode_id: VnodeId,
+    /// Number of series in column files and caches.
+    pub series_in_data: u64,
+    /// Number of series in the index before rebuilding.
+    pub series_in_index: u64,
+    /// Number of series that the index matches.
+    pub matched_series: u64,
+    pub mismatches: Vec<SeriesMismatch>,
+}
+
+impl IndexRebuildReport {
+    /// Number of series in the index but not in column files and caches.
+    pub fn orphan_series(&self) -> u64 {
+        self.series_in_index.saturating_sub(self.matched_series)
+    }
+
+    pub fn missing_series(&self) -> u64 {
+        self.mismatches
+            .iter()
+            .filter(|m| {
+                matches!(
+                    m,
+                    SeriesMismatch::Missing { .. } | SeriesMismatch::MissingInWal { .. }
+                )
+            })
+            .count() as u64
+    }
+
+    pub fn mismatched_series(&self) -> u64 {
+        self.mismatches.len() as u64 - self.missing_series()
+    }
+
+    pub fn to_record_batch(&self) -> TskvResult<RecordBatch> {
+        RecordBatch::try_new(
+            rebuild_index_report_schema(),
+            vec![
+                Arc::new(UInt32Array::from(vec![self.vnode_id])),
+                Arc::new(UInt64Array::from(vec![self.series_in_data])),
+                Arc::new(UInt64Array::from(vec![self.series_in_index])),
+                Arc::new(UInt64Array::from(vec![self.missing_series()])),
+                Arc::new(UInt64Array::from(vec![self.mismatched_series()])),
+                Arc::new(UInt64Array::from(vec![self.orphan_series()])),
+            ],
+        )
+        .context(ArrowSnafu)
+    }
+}
+
+impl Display for IndexRebuildReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "vnode: {}, series in data: {}, series in index: {}, missing: {}, mismatched: {}, orphan: {}",
+            self.vnode_id,
+            self.series_in_data,
+            self.series_in_index,
+            self.missing_series(),
+            self.mismatched_series(),
+            self.orphan_series(),
+        )
+    }
+}
+
+pub fn rebuild_index_report_schema() -> SchemaRef {
+    Arc::new(Schema::new(vec![
+        Field::new("vnode_id", DataType::UInt32, false),
+        Field::new("series_in_data", DataType::UInt64, false),
+        Field::new("series_in_index", DataType::UInt64, false),
+        Field::new("missing_series", DataType::UInt64, false),
+        Field::new("mismatched_series", DataType::UInt64, false),
+        Field::new("orphan_series", DataType::UInt64, false),
+    ]))
+}
+
+/// Compare the series found in column files and caches with the index.
+///
+/// Series that can not be read from the index are reported as missing.
+pub async fn verify_index(
+    index: &mut TSIndex,
+    vnode_id: VnodeId,
+    series: &BTreeMap<SeriesId, SeriesKey>,
+) -> IndexRebuildReport {
+    let mut report = IndexRebuildReport {
+        vnode_id,
+        series_in_data: series.len() as u64,
+        ..Default::default()
+    };
+    report.series_in_index = match index.get_series_count().await {
+        Ok(count) => count,
+        Err(e) => {
+            trace::warn!("Failed to count series of vnode {vnode_id} in index: {e}");
+            0
+        }
+    };
+
+    for (id, key) in series {
+        let mismatch = match index.get_series_key(*id).await {
+            Ok(Some(index_key)) if &index_key == key => {
+                report.matched_series += 1;
+                continue;
+            }
+            Ok(Some(index_key)) => SeriesMismatch::KeyMismatch {
+                id: *id,
+                key: key.clone(),
+                index_key,
+            },
+            Ok(None) => match index.get_series_id(key).await {
+                Ok(Some(index_id)) => SeriesMismatch::IdMismatch {
+                    id: *id,
+                    key: key.clone(),
+                    index_id,
+                },
+                _ => SeriesMismatch::Missing {
+                    id: *id,
+                    key: key.clone(),
+                },
+            },
+            Err(e) => {
+                trace::warn!("Failed to read series {id} of vnode {vnode_id} in index: {e}");
+                SeriesMismatch::Missing {
+                    id: *id,
+                    key: key.clone(),
+                }
+            }
+        };
+        trace::warn!("Index of vnode {vnode_id} has {mismatch}");
+        report.mismatches.push(mismatch);
+    }
+
+    report
+}
+
+/// Rebuild the index of a vnode from its caches and column files,
+/// the index is replaced in place and returns the differences with the old one.
+///
+/// Writes of the vnode are blocked until the index is rebuilt.
+pub async fn rebuild_vnode_index(
+    ts_family: Arc<RwLock<TseriesFamily>>,
+    ts_index: Arc<RwLock<TSIndex>>,
+    index_dir: impl AsRef<Path>,
+) -> TskvResult<IndexRebuildReport> {
+    let mut ts_index_w = ts_index.write().await;
+
+    // Column files of the version are not deleted by compactions while it's held,
+    // series flushed after the caches are taken are in the caches.
+    let (vnode_id, mut series, version) = {
+        let ts_family_r = ts_family.read().await;
+        let (series, version) = ts_family_r.series_for_rebuild();
+        (ts_family_r.tf_id(), series, version)
+    };
+    series_in_version(&version, &mut series).await?;
+    drop(version);
+    let report = verify_index(&mut ts_index_w, vnode_id, &series).await;
+
+    // Series created recently may have no data in caches yet.
+    for (id, key) in ts_index_w.cached_series() {
+        series.entry(id).or_insert(key);
+    }
+
+    // Do not reuse series ids of the old index.
+    let index_dir = index_dir.as_ref();
+    build_index(index_dir, &series, &[], ts_index_w.last_series_id()).await?;
+    let new_index = TSIndex::new(index_dir).await.context(IndexErrSnafu)?;
+    let mut new_index_w = new_index.write().await;
+
+    std::mem::swap(&mut *ts_index_w, &mut *new_index_w);
+    trace::info!("Rebuilt index of vnode {vnode_id}: {report}");
+
+    Ok(report)
+}
+
+/// Rebuild the index of a vnode from column files in the vnode directory and
+/// the WAL of the vnode, the vnode must not be opened by a running server.
+///
+/// Series in the WAL are built with the table schemas in column files, series of tables
+/// without column files are not found. If `verify_only` is true, just compare column
+/// files and the WAL with the index.
+pub async fn rebuild_vnode_index_offline(
+    vnode_dir: impl AsRef<Path>,
+    wal_dir: impl AsRef<Path>,
+    vnode_id: VnodeId,
+    verify_only: bool,
+) -> TskvResult<IndexRebuildReport> {
+    let vnode_dir = vnode_dir.as_ref();
+    let mut series = BTreeMap::new();
+    let mut schemas: HashMap<String, TskvTableSchemaRef> = HashMap::new();
+    for dir in [TSM_PATH, DELTA_PATH] {
+        let dir = vnode_dir.join(dir);
+        if !dir.exists() {
+            continue;
+        }
+        for entry in std::fs::read_dir(&dir).context(IOSnafu)? {
+            let path = entry.context(IOSnafu)?.path();
+            match path.extension().and_then(|e| e.to_str()) {
+                Some("tsm") | Some("delta") => {}
+                _ => continue,
+            }
+            let reader = TsmReader::open(&path).await?;
+            for chunk in reader.chunk().values() {
+                series
+                    .entry(chunk.series_id())
+                    .or_insert_with(|| chunk.series_key().clone());
+                if let Some(schema) = reader.table_schema(chunk.table_name()) {
+                    match schemas.get(chunk.table_name()) {
+                        Some(latest) if latest.schema_version >= schema.schema_version => {}
+                        _ => {
+                            schemas.insert(chunk.table_name().to_string(), schema);
+                        }
+                    }
+                }
+            }
+        }
+    }
+    let in_files: HashSet<&SeriesKey> = series.values().collect();
+    let wal_series = series_in_wal(wal_dir.as_ref(), &schemas)
+        .await?
+        .into_iter()
+        .filter(|key| !in_files.contains(key))
+        .collect::<Vec<_>>();
+
+    let index_dir = vnode_dir.join(INDEX_PATH);
+    let (report, wal_series, last_series_id) = match TSIndex::new(&index_dir).await {
+        Ok(index) => {
+            let mut index_w = index.write().await;
+            let mut report = verify_index(&mut index_w, vnode_id, &series).await;
+            // Series of the WAL keep their ids in the old index.
+            let mut with_ids = Vec::with_capacity(wal_series.len());
+            for key in wal_series {
+                let id = match index_w.get_series_id(&key).await {
+                    Ok(Some(id)) if !series.contains_key(&id) => {
+                        report.matched_series += 1;
+                        Some(id)
+                    }
+                    _ => {
+                        trace::warn!("Index of vnode {vnode_id} has missing series in WAL: {key}");
+                        report
+                            .mismatches
+                            .push(SeriesMismatch::MissingInWal { key: key.clone() });
+                        None
+                    }
+                };
+                report.series_in_data += 1;
+                with_ids.push((id, key));
+            }
+            (report, with_ids, index_w.last_series_id())
+        }
+        Err(e) => {
+            trace::warn!("Failed to open index '{}': {e}", index_dir.display());
+            let mut mismatches = series
+                .iter()
+                .map(|(id, key)| SeriesMismatch::Missing {
+                    id: *id,
+                    key: key.clone(),
+                })
+                .collect::<Vec<_>>();
+            mismatches.extend(
+                wal_series
+                    .iter()
+                    .map(|key| SeriesMismatch::MissingInWal { key: key.clone() }),
+            );
+            let report = IndexRebuildReport {
+                vnode_id,
+                series_in_data: (series.len() + wal_series.len()) as u64,
+                mismatches,
+                ..Default::default()
+            };
+            (
+                report,
+                wal_series.into_iter().map(|key| (None, key)).collect(),
+                0,
+            )
+        }
+    };
+    if verify_only {
+        return Ok(report);
+    }
+
+    build_index(&index_dir, &series, &wal_series, last_series_id).await?;
+
+    Ok(report)
+}
+
+/// Add the series in column files of the version.
+pub async fn series_in_version(
+    version: &Version,
+    series: &mut BTreeMap<SeriesId, SeriesKey>,
+) -> TskvResult<()> {
+    for level in version.levels_info().iter() {
+        for file in level.files.iter() {
+            let reader = version.get_tsm_reader(file.file_path()).await?;
+            for chunk in reader.chunk().values() {
+                series
+                    .entry(chunk.series_id())
+                    .or_insert_with(|| chunk.series_key().clone());
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Returns the series written to the WAL files in the directory, in the order they are written.
+/// Series of dropped tables are removed, series renamed by updating tags are kept.
+async fn series_in_wal(
+    wal_dir: &Path,
+    schemas: &HashMap<String, TskvTableSchemaRef>,
+) -> TskvResult<Vec<SeriesKey>> {
+    let mut wal_ids = LocalFileSystem::list_file_names(wal_dir)
+        .iter()
+        .filter_map(|name| file_utils::get_wal_file_id(name).ok())
+        .collect::<Vec<_>>();
+    wal_ids.sort_unstable();
+
+    let mut series = vec![];
+    let mut added = HashSet::new();
+    for wal_id in wal_ids {
+        let path = file_utils::make_wal_file(wal_dir, wal_id);
+        let mut reader = WalReader::open(&path).await?;
+        loop {
+            let entry = match reader.next_wal_entry().await {
+                Ok(Some(entry)) => entry,
+                Ok(None) => break,
+                Err(TskvError::WalTruncated { .. }) => {
+                    trace::warn!("WAL file '{}' is truncated", path.display());
+                    break;
+                }
+                Err(e) => return Err(e),
+            };
+            let data = match entry.block.map(|entry| entry.payload) {
+                Some(EntryPayload::Normal(data)) => data,
+                _ => continue,
+            };
+            let request = parse_prost_bytes::<RaftWriteCommand>(&data)
+                .map_err(|e| DecodeSnafu.into_error(Box::new(e)))?;
+            match request.command {
+                Some(raft_write_command::Command::WriteData(cmd)) => {
+                    for key in series_in_points(&cmd.data, schemas)? {
+                        if added.insert(key.clone()) {
+                            series.push(key);
+                        }
+                    }
+                }
+                Some(raft_write_command::Command::DropTable(cmd)) => {
+                    series.retain(|key| *key.table() != cmd.table);
+                    added.retain(|key| *key.table() != cmd.table);
+                }
+                _ => {}
+            }
+        }
+    }
+
+    Ok(series)
+}
+
+fn series_in_points(
+    points: &[u8],
+    schemas: &HashMap<String, TskvTableSchemaRef>,
+) -> TskvResult<Vec<SeriesKey>> {
+    let fb_points =
+        flatbuffers::root::<protos::models::Points>(points).context(InvalidFlatbufferSnafu)?;
+    let tables = fb_points.tables().context(InvalidPointTableSnafu)?;
+
+    let mut series = vec![];
+    for table in tables {
+        let table_name = table.tab_ext()?;
+        let schema = match schemas.get(table_name) {
+            Some(schema) => schema,
+            None => {
+                trace::warn!("Schema of table {table_name} is not in column files, skip it in WAL");
+                continue;
+            }
+        };
+        let columns = table.columns().context(CommonSnafu {
+            reason: "table missing columns".to_string(),
+        })?;
+        let fb_schema = FbSchema::from_fb_column(table_name, columns)?;
+        for row in 0..table.num_rows() as usize {
+            match SeriesKey::build_series_key(
+                fb_schema.table,
+                &columns,
+                schema,
+                &fb_schema.tag_indexes,
+                row,
+            ) {
+                Ok(key) => series.push(key),
+                Err(e) => {
+                    trace::warn!("Failed to build series of table {table_name} in WAL: {e}");
+                    break;
+                }
+            }
+        }
+    }
+
+    Ok(series)
+}
+
+/// Build an index of the series in a temporary directory, then move it into `index_dir`
+/// by a rename, so the old index is kept if the rebuilding fails. Series without ids are
+/// given new ids, which are allocated after `last_series_id`.
+pub async fn build_index(
+    index_dir: &Path,
+    series: &BTreeMap<SeriesId, SeriesKey>,
+    new_series: &[(Option<SeriesId>, SeriesKey)],
+    last_series_id: SeriesId,
+) -> TskvResult<()> {
+    let mut tmp_dir = index_dir.as_os_str().to_owned();
+    tmp_dir.push(".rebuild");
+    let tmp_dir = PathBuf::from(tmp_dir);
+    let _ = std::fs::remove_dir_all(&tmp_dir);
+    {
+        let index = TSIndex::new(&tmp_dir).await.context(IndexErrSnafu)?;
+        let mut index_w = index.write().await;
+        let with_ids = new_series
+            .iter()
+            .filter_map(|(id, key)| id.map(|id| (id, key)));
+        for (id, key) in series.iter().map(|(id, key)| (*id, key)).chain(with_ids) {
+            index_w
+                .add_series_for_rebuild(id, key)
+                .await
+                .context(IndexErrSnafu)?;
+        }
+        index_w.reserve_series_id(last_series_id);
+        let without_ids = new_series
+            .iter()
+            .filter(|(id, _)| id.is_none())
+            .map(|(_, key)| key.clone())
+            .collect::<Vec<_>>();
+        index_w
+            .add_series_if_not_exists(without_ids)
+            .await
+            .context(IndexErrSnafu)?;
+        index_w.flush().await.context(IndexErrSnafu)?;
+    }
+
+    std::fs::create_dir_all(index_dir).context(IOSnafu)?;
+    std::fs::rename(tmp_dir.join(INDEX_DB_FILE), index_dir.join(INDEX_DB_FILE)).context(IOSnafu)?;
+    let _ = std::fs::remove_dir_all(&tmp_dir);
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::{BTreeMap, HashMap};
+    use std::path::Path;
+    use std::sync::Arc;
+
+    use arrow_schema::TimeUnit;
+    use models::codec::Encoding;
+    use models::schema::{ColumnType, TableColumn, TskvTableSchema};
+    use models::{SeriesKey, Tag, ValueType};
+    use protos::models::FieldType;
+    use protos::models_helper::test::create_points;
+
+    use super::{build_index, series_in_points, verify_index, SeriesMismatch};
+    use crate::index::ts_index::TSIndex;
+
+    fn series_key(table: &str, host: &str) -> SeriesKey {
+        SeriesKey {
+            tags: vec![Tag::new(b"host".to_vec(), host.as_bytes().to_vec())],
+            table: table.to_string(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_verify_index() {
+        let dir = "/tmp/test/cnosdb/ts_index/verify";
+        let _ = std::fs::remove_dir_all(dir);
+
+        let ts_index = TSIndex::new(dir).await.unwrap();
+        let mut ts_index = ts_index.write().await;
+        let sids = ts_index
+            .add_series_if_not_exists(vec![
+                series_key("table", "h1"),
+                series_key("table", "h2"),
+                series_key("table", "h3"),
+            ])
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|(sid, _)| sid)
+            .collect::<Vec<_>>();
+
+        let mut series = BTreeMap::new();
+        // Matched series.
+        series.insert(sids[0], series_key("table", "h1"));
+        // Series id is mapped to another series key.
+        series.insert(sids[1], series_key("table", "h4"));
+        // Series is not in the index.
+        series.insert(100, series_key("table", "h5"));
+
+        let report = verify_index(&mut ts_index, 1, &series).await;
+        assert_eq!(report.series_in_data, 3);
+        assert_eq!(report.series_in_index, 3);
+        assert_eq!(report.matched_series, 1);
+        assert_eq!(report.missing_series(), 1);
+        assert_eq!(report.mismatched_series(), 1);
+        assert_eq!(report.orphan_series(), 2);
+        assert!(matches!(
+            report.mismatches[0],
+            SeriesMismatch::KeyMismatch { id, .. } if id == sids[1]
+        ));
+        assert!(matches!(
+            report.mismatches[1],
+            SeriesMismatch::Missing { id: 100, .. }
+        ));
+    }
+
+    #[test]
+    fn test_series_in_points() {
+        let mut fbb = flatbuffers::FlatBufferBuilder::new();
+        let points = create_points(
+            &mut fbb,
+            "db0",
+            "table",
+            HashMap::from([("ta", vec!["a", "a", "c"]), ("tb", vec!["b", "b", "b"])]),
+            HashMap::from([("fa", vec![&1.0_f64.to_be_bytes()[..]; 3])]),
+            HashMap::from([("fa", FieldType::Float)]),
+            &[1, 2, 3],
+            3,
+        );
+        fbb.finish(points, None);
+        let points = fbb.finished_data();
+
+        let schema = TskvTableSchema::new(
+            "cnosdb".to_string(),
+            "db0".to_string(),
+            "table".to_string(),
+            vec![
+                TableColumn::new_time_column(0, TimeUnit::Nanosecond),
+                TableColumn::new_tag_column(1, "ta".to_string()),
+                TableColumn::new_tag_column(2, "tb".to_string()),
+                TableColumn::new(
+                    3,
+                    "fa".to_string(),
+                    ColumnType::Field(ValueType::Float),
+                    Encoding::default(),
+                ),
+            ],
+        );
+        let schemas = HashMap::from([("table".to_string(), Arc::new(schema))]);
+        let series = series_in_points(points, &schemas).unwrap();
+        let series_key = |ta: &str| SeriesKey {
+            tags: vec![
+                Tag::new_with_column_id(1, ta.as_bytes().to_vec()),
+                Tag::new_with_column_id(2, b"b".to_vec()),
+            ],
+            table: "table".to_string(),
+        };
+        assert_eq!(
+            series,
+            vec![series_key("a"), series_key("a"), series_key("c")]
+        );
+
+        // Tables without a schema are skipped.
+        let series = series_in_points(points, &HashMap::new()).unwrap();
+        assert!(series.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_build_index() {
+        let dir = Path::new("/tmp/test/cnosdb/ts_index/build");
+        let _ = std::fs::remove_dir_all(dir);
+
+        let series = BTreeMap::from([
+            (1, series_key("table", "h1")),
+            (5, series_key("table", "h2")),
+        ]);
+        let new_series = vec![
+            (Some(7), series_key("table", "h3")),
+            (None, series_key("table", "h4")),
+        ];
+        build_index(dir, &series, &new_series, 10).await.unwrap();
+        assert!(!Path::new("/tmp/test/cnosdb/ts_index/build.rebuild").exists());
+        {
+            let ts_index = TSIndex::new(dir).await.unwrap();
+            let ts_index = ts_index.read().await;
+            for (id, host) in [(1, "h1"), (5, "h2"), (7, "h3")] {
+                assert_eq!(
+                    ts_index
+                        .get_series_id(&series_key("table", host))
+                        .await
+                        .unwrap(),
+                    Some(id)
+                );
+            }
+            let id = ts_index
+                .get_series_id(&series_key("table", "h4"))
+                .await
+                .unwrap()
+                .unwrap();
+            assert!(id > 10);
+        }
+
+        // The old index is replaced.
+        let series = BTreeMap::from([(5, series_key("table", "h2"))]);
+        build_index(dir, &series, &[], 10).await.unwrap();
+        let ts_index = TSIndex::new(dir).await.unwrap();
+        let ts_index = ts_index.read().await;
+        assert_eq!(
+            ts_index
+                .get_series_id(&series_key("table", "h1"))
+                .await
+                .unwrap(),
+            None
+        );
+        assert_eq!(
+            ts_index
+                .get_series_id(&series_key("table", "h2"))
+                .await
+                .unwrap(),
+            Some(5)
+        );
+    }
+}
@@ -1,4 +1,4 @@
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::ops::{BitAnd, BitOr, Bound, RangeBounds};
 use std::path::Path;
 use std::sync::atomic::{AtomicU32, Ordering};
@@ -6,14 +6,14 @@ use std::sync::Arc;
 
 use datafusion::arrow::datatypes::DataType;
 use datafusion::scalar::ScalarValue;
-use models::predicate::domain::{utf8_from, ColumnDomains, Domain, Range};
+use models::predicate::domain::{utf8_from, ColumnDomains, Domain, Pattern, Range};
 use models::schema::TskvTableSchema;
 use models::{tag, SeriesId, SeriesKey, Tag, TagKey, TagValue};
 use snafu::{OptionExt, ResultExt};
 use tokio::sync::RwLock;
 
 use super::cache::IndexCache;
-use super::{DecodeSeriesKeySnafu, IndexEngine, IndexResult};
+use super::{DecodeSeriesKeySnafu, IndexEngine, IndexResult, IndexStorageSnafu};
 use crate::error::{ColumnNotFoundSnafu, IndexErrSnafu};
 use crate::index::SeriesAlreadyExistsSnafu;
 use crate::{byte_utils, TskvError, UpdateSetValue};
@@ -85,6 +85,11 @@ pub struct TSIndex {
 
     storage: IndexEngine,
     cache: IndexCache,
+
+    // table -> series count, loaded lazily and kept up to date on writes
+    table_series_count: HashMap<String, u64>,
+    // series count of the whole index, loaded lazily
+    series_count: Option<u64>,
 }
 
 impl TSIndex {
@@ -102,6 +107,8 @@ impl TSIndex {
             incr_id: AtomicU32::new(incr_id),
             write_count: AtomicU32::new(0),
             cache: IndexCache::new(1_000_000),
+            table_series_count: HashMap::new(),
+            series_count: None,
         };
 
         trace::info!(
@@ -119,7 +126,7 @@ impl TSIndex {
         key: &SeriesKey,
     ) -> IndexResult<()> {
         let key_buf = encode_series_key(key.table(), key.tags());
-        if self.storage.exist(&key_buf)? {
+        if self.cache.get_series_id_by_key(key).is_some() || self.storage.exist(&key_buf)? {
             return Ok(());
         }
 
@@ -128,9 +135,25 @@ impl TSIndex {
         }
 
         self.cache.write(id, key.clone());
+        self.incr_series_count(key.table());
         Ok(())
     }
 
+    /// Returns the last allocated series id.
+    pub fn last_series_id(&self) -> SeriesId {
+        self.incr_id.load(Ordering::Relaxed)
+    }
+
+    /// Make sure that new series ids are allocated after the given series id.
+    pub fn reserve_series_id(&mut self, id: SeriesId) {
+        self.incr_id.fetch_max(id, Ordering::Relaxed);
+    }
+
+    /// Returns series that are not flushed to storage.
+    pub fn cached_series(&self) -> Vec<(SeriesId, SeriesKey)> {
+        self.cache.write_cache.series()
+    }
+
     pub async fn add_series_if_not_exists(
         &mut self,
         series_keys: Vec<SeriesKey>,
@@ -153,6 +176,7 @@ impl TSIndex {
 
             // write index memcache
             trace::debug!("Index add new series id:{}, key: {}", id, series_key);
+            self.incr_series_count(series_key.table());
             self.cache.write(id, series_key);
 
             let _ = self.check_to_flush(false).await;
@@ -227,6 +251,7 @@ impl TSIndex {
         let series_key = self.get_series_key(sid).await?;
         let _ = self.storage.delete(&encode_series_id_key(sid));
         if let Some(series_key) = series_key {
+            self.decr_series_count(series_key.table());
             self.cache.del(sid, &series_key);
             let key_buf = encode_series_key(series_key.table(), series_key.tags());
             let _ = self.storage.delete(&key_buf);
@@ -269,6 +294,7 @@ impl TSIndex {
             self.del_series_info(*sid).await?;
             self.add_tombstone_series(*sid, old_series).await?;
 
+            self.incr_series_count(new_series.table());
             self.cache.write(*sid, new_series.clone());
 
             let _ = self.check_to_flush(false).await;
@@ -277,6 +303,57 @@ impl TSIndex {
         Ok(())
     }
 
+    /// Returns the number of series of the table in this index.
+    pub async fn get_table_series_count(&mut self, tab: &str) -> IndexResult<u64> {
+        if let Some(count) = self.table_series_count.get(tab) {
+            return Ok(*count);
+        }
+
+        let mut count = self.cache.write_cache.table_series_count(tab) as u64;
+        let prefix = encode_series_key(tab, &[]);
+        for item in self.storage.prefix(&prefix)? {
+            item.map_err(|e| IndexStorageSnafu { msg: e.to_string() }.build())?;
+            count += 1;
+        }
+        self.table_series_count.insert(tab.to_string(), count);
+
+        Ok(count)
+    }
+
+    /// Returns the number of series of all tables in this index.
+    pub async fn get_series_count(&mut self) -> IndexResult<u64> {
+        if let Some(count) = self.series_count {
+            return Ok(count);
+        }
+
+        let mut count = self.cache.write_cache.series_count() as u64;
+        for item in self.storage.prefix(SERIES_ID_PREFIX.as_bytes())? {
+            item.map_err(|e| IndexStorageSnafu { msg: e.to_string() }.build())?;
+            count += 1;
+        }
+        self.series_count = Some(count);
+
+        Ok(count)
+    }
+
+    fn incr_series_count(&mut self, tab: &str) {
+        if let Some(count) = self.table_series_count.get_mut(tab) {
+            *count += 1;
+        }
+        if let Some(count) = self.series_count.as_mut() {
+            *count += 1;
+        }
+    }
+
+    fn decr_series_count(&mut self, tab: &str) {
+        if let Some(count) = self.table_series_count.get_mut(tab) {
+            *count = count.saturating_sub(1);
+        }
+        if let Some(count) = self.series_count.as_mut() {
+            *count = count.saturating_sub(1);
+        }
+    }
+
     async fn check_to_flush(&mut self, force: bool) -> IndexResult<()> {
         let count = self.write_count.fetch_add(1, Ordering::Relaxed);
         if !force && count < 10000 {
@@ -475,10 +552,73 @@ impl TSIndex {
                     }
                 } else {
                     // Does not contain a given value, that is, a value other than a given value
-                    // TODO will not deal with this situation for the time being
-                    bitmap = self.get_series_id_bitmap(tab, &[]).await?;
+                    let values = val
+                        .entries()
+                        .into_iter()
+                        .map(|entry| scalar_value_to_tag_value(entry.value()))
+                        .collect::<HashSet<_>>();
+                    let filter = |v: &[u8]| !values.contains(v);
+
+                    let cache_rb = self
+                        .cache
+                        .write_cache
+                        .get_inverted_by_filter(tab, tag_key, filter);
+                    bitmap = bitmap.bitor(cache_rb);
+
+                    let prefix = encode_inverted_index_key(tab, tag_key.as_bytes(), &[]);
+                    let engine_rb = self
+                        .storage
+                        .get_series_id_by_prefix_filter(&prefix, filter)?;
+                    bitmap = bitmap.bitor(engine_rb);
                 }
             }
+            Domain::Pattern(pattern_set) => {
+                // Every pattern must be matched, intersect the series of each pattern
+                let mut pattern_bitmaps = Vec::with_capacity(pattern_set.entries().len());
+                for entry in pattern_set.entries() {
+                    let regex = match pattern_to_regex(&entry.pattern) {
+                        Ok(regex) => regex,
+                        Err(e) => {
+                            // Let the query engine report the invalid pattern
+                            trace::debug!("Invalid pattern {}: {}", entry.pattern, e);
+                            return self.get_series_id_bitmap(tab, &[]).await;
+                        }
+                    };
+                    let filter = |v: &[u8]| regex.is_match(v) != entry.negated;
+
+                    let mut pattern_bitmap = self
+                        .cache
+                        .write_cache
+                        .get_inverted_by_filter(tab, tag_key, filter);
+
+                    // Values not match the pattern can not be skipped by prefix if negated
+                    let literal_prefix = if entry.negated {
+                        String::new()
+                    } else {
+                        entry.pattern.literal_prefix()
+                    };
+                    let scan_prefix = encode_inverted_index_key(
+                        tab,
+                        tag_key.as_bytes(),
+                        literal_prefix.as_bytes(),
+                    );
+                    let engine_rb =
+                        self.storage
+                            .get_series_id_by_prefix_filter(&scan_prefix, |suffix| {
+                                let mut value = literal_prefix.as_bytes().to_vec();
+                                value.extend_from_slice(suffix);
+                                filter(&value)
+                            })?;
+                    pattern_bitmap = pattern_bitmap.bitor(engine_rb);
+
+                    pattern_bitmaps.push(pattern_bitmap);
+                }
+
+                bitmap = pattern_bitmaps
+                    .into_iter()
+                    .reduce(|p, c| p.bitand(c))
+                    .unwrap_or_default();
+            }
             Domain::None => {
                 // Normally, it will not go here unless no judgment is made at the ColumnDomains level
                 // If you go here, you will directly return an empty series, because the tag condition in the map is' and '
@@ -507,6 +647,53 @@ impl std::fmt::Debug for TSIndex {
     }
 }
 
+/// Prints the series and tombstones in the index directory,
+/// the index must not be opened by a running server.
+pub async fn print_index_statistics(path: impl AsRef<Path>) -> IndexResult<()> {
+    let storage = IndexEngine::new(path)?;
+    let incr_id = match storage.get(AUTO_INCR_ID_KEY.as_bytes())? {
+        Some(data) => byte_utils::decode_be_u32(&data),
+        None => 0,
+    };
+    println!("============================================================");
+    println!("Auto increment series id: {}", incr_id);
+    println!("============================================================");
+
+    let mut table_series_count: BTreeMap<String, u64> = BTreeMap::new();
+    for item in storage.prefix(SERIES_ID_PREFIX.as_bytes())? {
+        let item = item.map_err(|e| IndexStorageSnafu { msg: e.to_string() }.build())?;
+        let key = item.0.as_ref();
+        if key.len() != SERIES_ID_PREFIX.len() + 4 {
+            continue;
+        }
+        let sid = byte_utils::decode_be_u32(&key[SERIES_ID_PREFIX.len()..]);
+        let data = storage.load(&item.1)?;
+        match SeriesKey::decode(&data) {
+            Ok(series_key) => {
+                println!("Series id: {}, key: {}", sid, series_key);
+                *table_series_count
+                    .entry(series_key.table().clone())
+                    .or_default() += 1;
+            }
+            Err(e) => println!("Series id: {}, invalid key: {}", sid, e),
+        }
+    }
+    println!("============================================================");
+    for (table, count) in table_series_count {
+        println!("Table: {}, series: {}", table, count);
+    }
+
+    let mut tombstone_count = 0_u64;
+    for item in storage.prefix(TOMBSTONE_PREFIX.as_bytes())? {
+        item.map_err(|e| IndexStorageSnafu { msg: e.to_string() }.build())?;
+        tombstone_count += 1;
+    }
+    println!("Tombstone series: {}", tombstone_count);
+    println!("============================================================");
+
+    Ok(())
+}
+
 pub fn filter_range_to_index_key_range(
     tab: &str,
     tag_key: &str,
@@ -541,6 +728,33 @@ pub fn filter_range_to_index_key_range(
     )
 }
 
+/// Convert the pattern to a regular expression matches tag values.
+pub fn pattern_to_regex(pattern: &Pattern) -> Result<regex::bytes::Regex, regex::Error> {
+    match pattern {
+        Pattern::Like(like) => {
+            let mut regex = String::with_capacity(like.len() + 8);
+            regex.push_str("(?s)^");
+            let mut chars = like.chars();
+            while let Some(c) = chars.next() {
+                match c {
+                    '%' => regex.push_str(".*"),
+                    '_' => regex.push('.'),
+                    '\\' => {
+                        // The escaped character matches itself
+                        if let Some(c) = chars.next() {
+                            regex.push_str(&regex::escape(&c.to_string()));
+                        }
+                    }
+                    c => regex.push_str(&regex::escape(&c.to_string())),
+                }
+            }
+            regex.push('$');
+            regex::bytes::Regex::new(&regex)
+        }
+        Pattern::Regex(regex) => regex::bytes::Regex::new(regex),
+    }
+}
+
 pub fn filter_range_to_value_range(range: &Range) -> impl RangeBounds<Vec<u8>> {
     let start_bound = range.start_bound();
     let end_bound = range.end_bound();
@@ -644,7 +858,9 @@ mod test {
     use models::schema::ExternalTableSchema;
     use models::{SeriesId, SeriesKey, Tag};
 
-    use super::TSIndex;
+    use models::predicate::domain::{Domain, Pattern};
+
+    use super::{pattern_to_regex, TSIndex};
     use crate::UpdateSetValue;
 
     /// ( sid, database, table, [(tag_key, tag_value)] )
@@ -828,6 +1044,124 @@ mod test {
         }
     }
 
+    #[tokio::test]
+    async fn test_series_count() {
+        let dir = "/tmp/test/cnosdb/ts_index/series_count";
+        let _ = std::fs::remove_dir_all(dir);
+
+        #[rustfmt::skip]
+        let series_keys_desc: Vec<SeriesKeyDesc> = vec![
+            (0, "db_test", "table_a", vec![("host", "h1")]),
+            (0, "db_test", "table_a", vec![("host", "h2")]),
+            (0, "db_test", "table_a", vec![("host", "h2")]),
+            (0, "db_test", "table_b", vec![("host", "h1")]),
+        ];
+        let series_keys = build_series_keys(&series_keys_desc);
+
+        {
+            let ts_index = TSIndex::new(dir).await.unwrap();
+            let mut ts_index = ts_index.write().await;
+            let sids = ts_index
+                .add_series_if_not_exists(series_keys[..2].to_vec())
+                .await
+                .unwrap();
+            assert_eq!(ts_index.get_table_series_count("table_a").await.unwrap(), 2);
+            assert_eq!(ts_index.get_table_series_count("table_b").await.unwrap(), 0);
+            assert_eq!(ts_index.get_series_count().await.unwrap(), 2);
+
+            // Counters are kept up to date after loaded.
+            ts_index
+                .add_series_if_not_exists(series_keys[2..].to_vec())
+                .await
+                .unwrap();
+            assert_eq!(ts_index.get_table_series_count("table_a").await.unwrap(), 2);
+            assert_eq!(ts_index.get_table_series_count("table_b").await.unwrap(), 1);
+            assert_eq!(ts_index.get_series_count().await.unwrap(), 3);
+
+            ts_index.del_series_info(sids[0].0).await.unwrap();
+            assert_eq!(ts_index.get_table_series_count("table_a").await.unwrap(), 1);
+            assert_eq!(ts_index.get_series_count().await.unwrap(), 2);
+
+            ts_index.flush().await.unwrap();
+        }
+
+        // Test re-open, counters are loaded from storage.
+        let ts_index = TSIndex::new(dir).await.unwrap();
+        let mut ts_index = ts_index.write().await;
+        assert_eq!(ts_index.get_table_series_count("table_a").await.unwrap(), 1);
+        assert_eq!(ts_index.get_table_series_count("table_b").await.unwrap(), 1);
+        assert_eq!(ts_index.get_series_count().await.unwrap(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_get_series_ids_by_pattern() {
+        let dir = "/tmp/test/cnosdb/ts_index/pattern";
+        let _ = std::fs::remove_dir_all(dir);
+
+        #[rustfmt::skip]
+        let series_keys_desc: Vec<SeriesKeyDesc> = vec![
+            (0, "db_test", "table", vec![("host", "server_a")]),
+            (0, "db_test", "table", vec![("host", "server_b")]),
+            (0, "db_test", "table", vec![("host", "client_a")]),
+            (0, "db_test", "table", vec![("host", "server_c")]),
+        ];
+        let series_keys = build_series_keys(&series_keys_desc);
+
+        let ts_index = TSIndex::new(dir).await.unwrap();
+        let mut ts_index = ts_index.write().await;
+        // The first 2 series are flushed to storage, the others are kept in cache.
+        let mut sids = ts_index
+            .add_series_if_not_exists(series_keys[..2].to_vec())
+            .await
+            .unwrap();
+        ts_index.flush().await.unwrap();
+        sids.extend(
+            ts_index
+                .add_series_if_not_exists(series_keys[2..].to_vec())
+                .await
+                .unwrap(),
+        );
+        let sids = sids.into_iter().map(|(sid, _)| sid).collect::<Vec<_>>();
+
+        let get_sids = |pattern: Pattern, negated: bool| {
+            let domain = Domain::of_pattern(pattern, negated);
+            let ts_index = &ts_index;
+            async move {
+                ts_index
+                    .get_series_ids_by_domain("table", "host", &domain)
+                    .await
+                    .unwrap()
+                    .into_iter()
+                    .collect::<Vec<_>>()
+            }
+        };
+
+        let like = |p: &str| Pattern::Like(p.to_string());
+        let regex = |p: &str| Pattern::Regex(p.to_string());
+
+        assert_eq!(
+            get_sids(like("server%"), false).await,
+            vec![sids[0], sids[1], sids[3]]
+        );
+        assert_eq!(get_sids(like("%_a"), false).await, vec![sids[0], sids[2]]);
+        assert_eq!(get_sids(like("server%"), true).await, vec![sids[2]]);
+        assert_eq!(
+            get_sids(regex("^server_[ab]$"), false).await,
+            vec![sids[0], sids[1]]
+        );
+        assert_eq!(get_sids(regex("a$"), true).await, vec![sids[1], sids[3]]);
+        // Invalid regular expression matches all series.
+        assert_eq!(get_sids(regex("("), false).await, sids);
+    }
+
+    #[test]
+    fn test_pattern_to_regex() {
+        let regex = pattern_to_regex(&Pattern::Like("a.b%c_\\%".to_string())).unwrap();
+        assert!(regex.is_match(b"a.bxxc1%"));
+        assert!(!regex.is_match(b"axbxxc1%"));
+        assert!(!regex.is_match(b"a.bxxc1x"));
+    }
+
     #[test]
     fn test_serde() {
         let schema = Schema::new(vec![
@@ -6,11 +6,13 @@ use datafusion::arrow::record_batch::RecordBatch;
 use memory_pool::{MemoryPool, MemoryPoolRef};
 use meta::model::MetaRef;
 use metrics::metric_register::MetricsRegister;
-use models::meta_data::VnodeId;
+use models::meta_data::{VnodeId, VnodeSeriesCount};
 use models::predicate::domain::ColumnDomains;
 use models::schema::{make_owner, split_owner, DatabaseSchema};
 use models::{SeriesId, SeriesKey};
-use snafu::ResultExt;
+use object_store::path::Path as ObjectPath;
+use object_store::ObjectStore;
+use snafu::{OptionExt, ResultExt};
 use tokio::runtime::Runtime;
 use tokio::sync::broadcast::{self, Sender as BroadcastSender};
 use tokio::sync::mpsc::{self, Receiver, Sender};
@@ -20,15 +22,16 @@ use trace::{debug, error, info, warn};
 use crate::compaction::job::CompactJob;
 use crate::compaction::{self, check, LevelCompactionPicker, Picker};
 use crate::database::Database;
-use crate::error::{IndexErrSnafu, TskvResult};
+use crate::error::{IndexErrSnafu, SchemaSnafu, TskvResult, VnodeNotFoundSnafu};
 use crate::file_system::async_filesystem::LocalFileSystem;
 use crate::file_system::FileSystem;
+use crate::index::rebuild;
 use crate::kv_option::{Options, StorageOptions};
 use crate::summary::{Summary, SummaryTask};
 use crate::tseries_family::{SuperVersion, TseriesFamily};
 use crate::version_set::VersionSet;
 use crate::vnode_store::VnodeStorage;
-use crate::{file_utils, Engine, TsKvContext, TseriesFamilyId};
+use crate::{backup, file_utils, Engine, TsKvContext, TseriesFamilyId, VnodeSnapshot};
 
 // TODO: A small summay channel capacity can cause a block
 pub const COMPACT_REQ_CHANNEL_CAP: usize = 1024;
@@ -90,6 +93,7 @@ impl TsKv {
 
         core.run_summary_job(summary, summary_task_receiver);
         core.run_flush_cold_vnode_job();
+        core.run_report_series_count_job();
         core.compact_job
             .start_merge_compact_task_job(compact_task_receiver)
             .await;
@@ -192,6 +196,66 @@ impl TsKv {
         });
     }
 
+    /// Report the series of the vnodes to meta, for the series limits in the cluster.
+    fn run_report_series_count_job(&self) {
+        let tskv_ctx = self.ctx.clone();
+        let meta = self._meta_manager.clone();
+        self.runtime.spawn(async move {
+            let mut report_interval = tokio::time::interval(meta.report_time_interval());
+            loop {
+                report_interval.tick().await;
+
+                let dbs = tskv_ctx.version_set.read().await.get_all_db().clone();
+                let mut counts = vec![];
+                for (owner, db) in dbs {
+                    match Self::vnode_series_counts(&db).await {
+                        Ok(db_counts) => counts.extend(db_counts),
+                        Err(e) => error!("Failed to count series of database {owner}: {e}"),
+                    }
+                }
+                if counts.is_empty() {
+                    continue;
+                }
+                if let Err(e) = meta.report_series_count(counts).await {
+                    error!("Failed to report series count: {e}");
+                }
+            }
+        });
+    }
+
+    async fn vnode_series_counts(db: &RwLock<Database>) -> TskvResult<Vec<VnodeSeriesCount>> {
+        let (schemas, ts_indexes) = {
+            let db = db.read().await;
+            (db.get_schemas(), db.ts_indexes())
+        };
+        let tables = schemas.list_tables().await.context(SchemaSnafu)?;
+
+        let mut counts = Vec::with_capacity(ts_indexes.len());
+        for (vnode_id, ts_index) in ts_indexes {
+            let mut ts_index = ts_index.write().await;
+            let series = ts_index.get_series_count().await.context(IndexErrSnafu)?;
+            let mut table_series = HashMap::new();
+            for table in tables.iter() {
+                let count = ts_index
+                    .get_table_series_count(table)
+                    .await
+                    .context(IndexErrSnafu)?;
+                if count > 0 {
+                    table_series.insert(table.clone(), count);
+                }
+            }
+            counts.push(VnodeSeriesCount {
+                vnode_id,
+                tenant: schemas.tenant_name().to_string(),
+                db_name: schemas.database_name().to_string(),
+                series,
+                tables: table_series,
+            });
+        }
+
+        Ok(counts)
+    }
+
     pub async fn get_db(&self, tenant: &str, database: &str) -> Option<Arc<RwLock<Database>>> {
         self.ctx.version_set.read().await.get_db(tenant, database)
     }
@@ -462,6 +526,139 @@ impl Engine for TsKv {
         Ok(RecordBatch::new_empty(check::vnode_table_checksum_schema()))
     }
 
+    async fn rebuild_index(&self, vnode_id: VnodeId) -> TskvResult<RecordBatch> {
+        // Don't hold the version set while rebuilding.
+        let databases = self
+            .ctx
+            .version_set
+            .read()
+            .await
+            .get_all_db()
+            .values()
+            .cloned()
+            .collect::<Vec<_>>();
+        for database in databases {
+            let db = database.read().await;
+            if let (Some(ts_family), Some(ts_index)) = (
+                db.ts_families().get(&vnode_id).cloned(),
+                db.get_ts_index(vnode_id),
+            ) {
+                drop(db);
+
+                let owner = ts_family.read().await.tenant_database();
+                let index_dir = self.ctx.options.storage.index_dir(&owner, vnode_id);
+                let report = rebuild::rebuild_vnode_index(ts_family, ts_index, index_dir).await?;
+
+                return report.to_record_batch();
+            }
+        }
+
+        Err(VnodeNotFoundSnafu { vnode_id }.build())
+    }
+
+    async fn backup_vnode(
+        &self,
+        vnode_id: VnodeId,
+        store: Arc<dyn ObjectStore>,
+        location: ObjectPath,
+        backup_id: &str,
+        incremental: bool,
+    ) -> TskvResult<RecordBatch> {
+        let vnode_opt = self.vnodes.read().await.get(&vnode_id).cloned();
+        let mut vnode = vnode_opt.context(VnodeNotFoundSnafu { vnode_id })?;
+        vnode.flush(true, true, false).await?;
+
+        // The snapshot holds the current version, column files in it
+        // will not be deleted by compactions until the backup finished.
+        let snapshot = vnode.create_snapshot().await?;
+        let owner = vnode.ts_family().read().await.tenant_database();
+        let vnode_dir = self.ctx.options.storage.ts_family_dir(&owner, vnode_id);
+
+        // Copy the index after the snapshot, so that it contains all series in column files.
+        let index_dir = self.ctx.options.storage.path().join(format!(
+            "backup_index_{}_{}",
+            vnode_id,
+            chrono::Local::now().format("%Y%m%d_%H%M%S_%3f")
+        ));
+        {
+            let mut ts_index = vnode.ts_index.write().await;
+            ts_index.flush().await.context(IndexErrSnafu)?;
+            let copied = backup::copy_index_files(
+                &self.ctx.options.storage.index_dir(&owner, vnode_id),
+                &index_dir,
+            );
+            if let Err(e) = copied {
+                let _ = std::fs::remove_dir_all(&index_dir);
+                return Err(e);
+            }
+        }
+
+        let result = backup::backup_vnode(
+            store,
+            &location,
+            backup_id,
+            &vnode_dir,
+            &index_dir,
+            &snapshot.version_edit,
+            incremental,
+        )
+        .await;
+        let _ = std::fs::remove_dir_all(&index_dir);
+        let report = result?;
+        info!("Backed up vnode {vnode_id} to '{location}' as {backup_id}: {report:?}");
+
+        report.to_record_batch()
+    }
+
+    async fn restore_vnode(
+        &self,
+        tenant: &str,
+        database: &str,
+        vnode_id: VnodeId,
+        store: Arc<dyn ObjectStore>,
+        location: ObjectPath,
+        backup_id: &str,
+    ) -> TskvResult<()> {
+        let create_time = chrono::Local::now().format("%Y%m%d_%H%M%S_%3f").to_string();
+        let restore_dir = self
+            .ctx
+            .options
+            .storage
+            .path()
+            .join(format!("restore_{}_{}", vnode_id, create_time));
+        let mut version_edit =
+            match backup::download_vnode(store, &location, backup_id, &restore_dir).await {
+                Ok(ve) => ve,
+                Err(e) => {
+                    let _ = std::fs::remove_dir_all(&restore_dir);
+                    return Err(e);
+                }
+            };
+
+        // The restored vnode starts with a new raft log, so the sequence
+        // of the backed up vnode is discarded.
+        version_edit.seq_no = 0;
+        version_edit.tsf_name = make_owner(tenant, database);
+        let snapshot = VnodeSnapshot {
+            node_id: self.ctx.options.storage.node_id,
+            vnode_id,
+            last_seq_no: 0,
+            create_time,
+            version_edit,
+            version: None,
+            active_time: 0,
+        };
+
+        let mut vnode = self.open_tsfamily(tenant, database, vnode_id).await?;
+        let result = vnode.apply_snapshot(snapshot, &restore_dir).await;
+        let _ = std::fs::remove_dir_all(&restore_dir);
+        result?;
+        self.vnodes.write().await.insert(vnode_id, vnode);
+        info!("Restored vnode {vnode_id} from backup {backup_id} in '{location}'");
+
+        Ok(())
+    }
+
     async fn close(&self) {
         let (tx, mut rx) = mpsc::channel(1);
         if let Err(e) = self.close_sender.send(tx) {
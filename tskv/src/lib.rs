@@ -11,6 +11,8 @@ use datafusion::arrow::record_batch::RecordBatch;
 use models::meta_data::{NodeId, VnodeId};
 use models::predicate::domain::ColumnDomains;
 use models::{SeriesId, SeriesKey};
+use object_store::path::Path as ObjectPath;
+use object_store::ObjectStore;
 use serde::{Deserialize, Serialize};
 use summary::SummaryTask;
 use tokio::runtime::Runtime;
@@ -26,10 +28,10 @@ use crate::kv_option::StorageOptions;
 pub use crate::kvcore::TsKv;
 pub use crate::summary::{print_summary_statistics, Summary, VersionEdit};
 use crate::tseries_family::SuperVersion;
-// todo: add a method for print tsm statistics
-// pub use crate::tsm::print_tsm_statistics;
+pub use crate::tsm::print_tsm_statistics;
 pub use crate::wal::print_wal_statistics;
 
+pub mod backup;
 pub mod byte_utils;
 mod compaction;
 mod compute;
@@ -45,7 +47,7 @@ mod kvcore;
 mod memcache;
 // TODO supposedly private
 pub mod reader;
-mod record_file;
+pub mod record_file;
 mod schema;
 mod summary;
 mod tseries_family;
@@ -135,6 +137,32 @@ pub trait Engine: Send + Sync + Debug {
     /// Get a compressed hash_tree(ID and checksum of each vnode) of engine.
     async fn get_vnode_hash_tree(&self, vnode_id: VnodeId) -> TskvResult<RecordBatch>;
 
+    /// Rebuild the index of the storage unit from its caches and column files,
+    /// returns the differences with the old index.
+    async fn rebuild_index(&self, vnode_id: VnodeId) -> TskvResult<RecordBatch>;
+
+    /// Flush caches of the storage unit, then upload its column files and the summary
+    /// of the backup into the location of the object store, returns statistics of the backup.
+    async fn backup_vnode(
+        &self,
+        vnode_id: VnodeId,
+        store: Arc<dyn ObjectStore>,
+        location: ObjectPath,
+        backup_id: &str,
+        incremental: bool,
+    ) -> TskvResult<RecordBatch>;
+
+    /// Replace the storage unit with the backup of it in the location of the object store.
+    async fn restore_vnode(
+        &self,
+        tenant: &str,
+        database: &str,
+        vnode_id: VnodeId,
+        store: Arc<dyn ObjectStore>,
+        location: ObjectPath,
+        backup_id: &str,
+    ) -> TskvResult<()>;
+
     /// Close all background jobs of engine.
     async fn close(&self);
 }
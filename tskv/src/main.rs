@@ -1,17 +1,47 @@
 use std::env;
+use std::path::PathBuf;
+
+use arrow::util::pretty::pretty_format_batches;
+use snafu::ResultExt;
+use tskv::error::ArrowSnafu;
 
 const ARG_PRINT: &str = "print"; // To print something
 const ARG_TSM: &str = "--tsm"; // To print a .tsm file
 const ARG_TOMBSTONE: &str = "--tombstone"; // To print a .tsm file with tombsotne
 const ARG_SUMMARY: &str = "--summary"; // To print a summary file
 const ARG_WAL: &str = "--wal"; // To print a wal file
+const ARG_INDEX: &str = "--index"; // To print an index directory
+const ARG_PAGES: &str = "--pages"; // To print pages of a .tsm file
+const ARG_ENCODING: &str = "--encoding"; // To print encoding and compression ratio of a .tsm file
+const ARG_VERIFY_CRC: &str = "--verify-crc"; // To verify crc of all pages in a .tsm file
+const ARG_DUMP: &str = "--dump"; // To decode pages of a .tsm file into csv or arrow files
+const ARG_REBUILD_INDEX: &str = "rebuild-index"; // To rebuild index of a vnode
+const ARG_VNODE_DIR: &str = "--vnode-dir"; // Directory of the vnode
+const ARG_WAL_DIR: &str = "--wal-dir"; // WAL directory of the vnode
+const ARG_VERIFY: &str = "--verify"; // Only verify index, do not rebuild
 
 /// # Example
-/// tskv print [--tsm <tsm_path>] [--tombstone]
+/// tskv print [--tsm <tsm_path>] [--tombstone] [--pages] [--encoding] [--verify-crc]
+///     [--dump <csv|arrow> <output_dir>]
 /// tskv print [--summary <summary_path>]
 /// tskv print [--wal <wal_path>]
+/// tskv print [--index <index_dir>]
 /// - --tsm <tsm_path> print statistics for .tsm file at <tsm_path> .
 /// - --tombstone also print tombstone for every field_id in .tsm file.
+/// - --pages also print meta of every page in .tsm file.
+/// - --encoding print encoding and compression ratio of every column in .tsm file.
+/// - --verify-crc check crc of every page in .tsm file, exits with 1 if any page is corrupted.
+/// - --dump <csv|arrow> <output_dir> decode pages in .tsm file to <output_dir>/<table>.<csv|arrow> .
+/// - --summary <summary_path> print version edits in summary file at <summary_path> .
+/// - --wal <wal_path> print entries in wal file at <wal_path> .
+/// - --index <index_dir> print series in index at <index_dir>,
+///   the index must not be opened by a running server.
+///
+/// tskv rebuild-index --vnode-dir <vnode_dir> --wal-dir <wal_dir> [--verify]
+/// - --vnode-dir <vnode_dir> rebuild index of the vnode at <vnode_dir> from it's column files,
+///   the vnode must not be opened by a running server.
+/// - --wal-dir <wal_dir> series in the WAL of the vnode at <wal_dir> are added to the index too.
+/// - --verify only print mismatches between column files, WAL and the index, do not rebuild.
 #[tokio::main]
 async fn main() {
     let mut args = env::args().peekable();
@@ -19,6 +49,10 @@ async fn main() {
     let mut show_tsm = false;
     let mut tsm_path: Option<String> = None;
     let mut show_tombstone = false;
+    let mut show_pages = false;
+    let mut show_encoding = false;
+    let mut verify_crc = false;
+    let mut dump: Option<(String, String)> = None;
 
     let mut show_summary = false;
     let mut summary_path: Option<String> = None;
@@ -26,6 +60,17 @@ async fn main() {
     let mut show_wal = false;
     let mut wal_path: Option<String> = None;
 
+    let mut show_index = false;
+    let mut index_path: Option<String> = None;
+
+    let mut rebuild_index = false;
+    let mut vnode_dir: Option<String> = None;
+    let mut wal_dir: Option<String> = None;
+    let mut verify_only = false;
+
+    // Exit with a non-zero code if any file is corrupted or failed to be read.
+    let mut failed = false;
+
     while let Some(arg) = args.peek() {
         // --print [--tsm <path>]
         if arg.as_str() == ARG_PRINT {
@@ -41,6 +86,21 @@ async fn main() {
                     ARG_TOMBSTONE => {
                         show_tombstone = true;
                     }
+                    ARG_PAGES => {
+                        show_pages = true;
+                    }
+                    ARG_ENCODING => {
+                        show_encoding = true;
+                    }
+                    ARG_VERIFY_CRC => {
+                        verify_crc = true;
+                    }
+                    ARG_DUMP => {
+                        dump = args.next().zip(args.next());
+                        if dump.is_none() {
+                            println!("Invalid arguments: --dump <csv|arrow> <output_dir>");
+                        }
+                    }
                     ARG_SUMMARY => {
                         show_summary = true;
                         summary_path = args.next();
@@ -55,6 +115,36 @@ async fn main() {
                             println!("Invalid arguments: --wal <wal_path>")
                         }
                     }
+                    ARG_INDEX => {
+                        show_index = true;
+                        index_path = args.next();
+                        if index_path.is_none() {
+                            println!("Invalid arguments: --index <index_dir>")
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        } else if arg.as_str() == ARG_REBUILD_INDEX {
+            // rebuild-index --vnode-dir <path> --wal-dir <path> [--verify]
+            rebuild_index = true;
+            while let Some(rebuild_arg) = args.next() {
+                match rebuild_arg.as_str() {
+                    ARG_VNODE_DIR => {
+                        vnode_dir = args.next();
+                        if vnode_dir.is_none() {
+                            println!("Invalid arguments: --vnode-dir <vnode_dir>");
+                        }
+                    }
+                    ARG_WAL_DIR => {
+                        wal_dir = args.next();
+                        if wal_dir.is_none() {
+                            println!("Invalid arguments: --wal-dir <wal_dir>");
+                        }
+                    }
+                    ARG_VERIFY => {
+                        verify_only = true;
+                    }
                     _ => {}
                 }
             }
@@ -65,7 +155,36 @@ async fn main() {
     if show_tsm {
         if let Some(p) = tsm_path {
             println!("TSM Path: {}, ShowTombstone: {}", p, show_tombstone);
-            // tskv::print_tsm_statistics(p, show_tombstone).await;
+            if let Err(e) = tskv::print_tsm_statistics(&p, show_pages, show_tombstone).await {
+                println!("Failed to print tsm file: {}", e);
+                failed = true;
+            }
+            if show_encoding {
+                if let Err(e) = tskv::tsm::inspect::print_tsm_encoding(&p).await {
+                    println!("Failed to print encoding of tsm file: {}", e);
+                    failed = true;
+                }
+            }
+            if verify_crc {
+                match tskv::tsm::inspect::verify_tsm_crc(&p).await {
+                    Ok(0) => {}
+                    Ok(_) => failed = true,
+                    Err(e) => {
+                        println!("Failed to verify tsm file: {}", e);
+                        failed = true;
+                    }
+                }
+            }
+            if let Some((format, output_dir)) = dump {
+                let result = match format.parse::<tskv::tsm::inspect::TsmDumpFormat>() {
+                    Ok(format) => tskv::tsm::inspect::dump_tsm(&p, format, &output_dir).await,
+                    Err(e) => Err(e),
+                };
+                if let Err(e) = result {
+                    println!("Failed to dump tsm file: {}", e);
+                    failed = true;
+                }
+            }
         }
     }
 
@@ -82,4 +201,66 @@ async fn main() {
             tskv::print_wal_statistics(p).await;
         }
     }
+
+    if show_index {
+        if let Some(p) = index_path {
+            println!("Index Path: {}", p);
+            if let Err(e) = tskv::index::ts_index::print_index_statistics(p).await {
+                println!("Failed to print index: {}", e);
+                failed = true;
+            }
+        }
+    }
+
+    if rebuild_index {
+        if let (Some(p), Some(wal_dir)) = (vnode_dir, wal_dir) {
+            let path = PathBuf::from(p);
+            // The directory name of a vnode is it's id.
+            let vnode_id = path
+                .file_name()
+                .and_then(|n| n.to_str())
+                .and_then(|n| n.parse::<u32>().ok())
+                .unwrap_or_default();
+            println!(
+                "Vnode Path: {}, WAL Path: {}, VnodeId: {}, VerifyOnly: {}",
+                path.display(),
+                wal_dir,
+                vnode_id,
+                verify_only
+            );
+            match tskv::index::rebuild::rebuild_vnode_index_offline(
+                &path,
+                &wal_dir,
+                vnode_id,
+                verify_only,
+            )
+            .await
+            {
+                Ok(report) => {
+                    for mismatch in report.mismatches.iter() {
+                        println!("{}", mismatch);
+                    }
+                    match report
+                        .to_record_batch()
+                        .and_then(|batch| pretty_format_batches(&[batch]).context(ArrowSnafu))
+                    {
+                        Ok(table) => println!("{}", table),
+                        Err(e) => println!("Failed to print report: {}", e),
+                    }
+                }
+                Err(e) => {
+                    println!("Failed to rebuild index: {}", e);
+                    failed = true;
+                }
+            }
+        } else {
+            println!(
+                "Invalid arguments: rebuild-index --vnode-dir <vnode_dir> --wal-dir <wal_dir>"
+            );
+            failed = true;
+        }
+    }
+    if failed {
+        std::process::exit(1);
+    }
 }
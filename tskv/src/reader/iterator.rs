@@ -1,6 +1,7 @@
 use std::iter;
 use std::ops::Not;
 use std::sync::Arc;
+use std::time::Duration;
 
 use arrow::datatypes::{Schema, SchemaRef};
 use datafusion::arrow::array::{
@@ -42,6 +43,7 @@ use crate::reader::column_group::ColumnGroupReader;
 use crate::reader::filter::DataFilter;
 use crate::reader::function_register::NoRegistry;
 use crate::reader::paralle_merge::ParallelMergeAdapter;
+use crate::reader::pushdown::PushedPlan;
 use crate::reader::schema_alignmenter::SchemaAlignmenter;
 use crate::reader::trace::TraceCollectorBatcherReaderProxy;
 use crate::reader::utils::group_overlapping_segments;
@@ -822,6 +824,11 @@ pub struct QueryOption {
     pub df_schema: SchemaRef,
     pub table_schema: TskvTableSchemaRef,
     pub aggregates: Option<Vec<TableColumn>>, // TODO: Use PushedAggregateFunction
+    /// Plan executed on the scanned record batches, its output schema is
+    /// different from the `df_schema` of the scan.
+    pub pushed_plan: Option<PushedPlan>,
+    /// Max staleness of the data read from the replica, reads are linearizable if it's None.
+    pub stale_read_max_lag: Option<Duration>,
 }
 
 impl QueryOption {
@@ -839,9 +846,21 @@ impl QueryOption {
             aggregates,
             df_schema,
             table_schema,
+            pushed_plan: None,
+            stale_read_max_lag: None,
         }
     }
 
+    pub fn with_pushed_plan(mut self, pushed_plan: Option<PushedPlan>) -> Self {
+        self.pushed_plan = pushed_plan;
+        self
+    }
+
+    pub fn with_stale_read_max_lag(mut self, stale_read_max_lag: Option<Duration>) -> Self {
+        self.stale_read_max_lag = stale_read_max_lag;
+        self
+    }
+
     pub fn tenant_name(&self) -> &str {
         &self.table_schema.tenant
     }
@@ -854,6 +873,7 @@ impl QueryOption {
             vnode_ids,
             limit: self.split.limit(),
             batch_size: self.batch_size,
+            stale_read_max_lag: self.stale_read_max_lag,
         };
         let expr = QueryExpr {
             split: self.split.clone(),
@@ -865,10 +885,17 @@ impl QueryOption {
         let expr_bytes = QueryExpr::encode(&expr)?;
         let aggs_bytes = domain::encode_agg(&self.aggregates)?;
 
+        let plan_bytes = self
+            .pushed_plan
+            .as_ref()
+            .map(|p| p.bytes().to_vec())
+            .unwrap_or_default();
+
         Ok(QueryRecordBatchRequest {
             args: args_bytes,
             expr: expr_bytes,
             aggs: aggs_bytes,
+            plan: plan_bytes,
         })
     }
 }
@@ -45,6 +45,7 @@ mod trace;
 mod utils;
 mod visitor;
 
+pub mod pushdown;
 pub mod query_executor;
 pub mod serialize;
 pub mod sort_merge;
@@ -52,6 +53,8 @@ pub mod table_scan;
 pub mod tag_scan;
 pub mod test_util;
 
+pub(crate) use page::page_to_arrow_array;
+
 pub type PredicateRef = Arc<Predicate>;
 
 #[derive(Debug)]
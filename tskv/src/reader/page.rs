@@ -136,6 +136,15 @@ async fn page_to_arrow_array_with_tomb(
     data_buf_to_arrow_array(data_buffer, meta, null_bitset)
 }
 
+/// Decodes a page to an arrow array, tombstones are not applied.
+pub(crate) fn page_to_arrow_array(page: &Page) -> TskvResult<ArrayRef> {
+    data_buf_to_arrow_array(
+        page.data_buffer(),
+        page.meta(),
+        NullBitset::Ref(page.null_bitset()),
+    )
+}
+
 fn data_buf_to_arrow_array(
     data_buffer: &[u8],
     meta: &PageMeta,
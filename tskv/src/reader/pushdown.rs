@@ -0,0 +1,404 @@
+//! Physical plans pushed down to vnode scans.
+//!
+//! A query node may push the partial aggregation of a table scan, with projections and
+//! filters under it, down to the nodes where vnodes are located, so only the intermediate
+//! aggregate states are sent back instead of raw rows.
+//!
+//! Limits, with projections and filters under them, are pushed down the same way, so
+//! at most `fetch` rows are sent back for each vnode.
+//!
+//! The leaf of a pushed plan is a [`ScanInputExec`], which is bound to the record batches
+//! scanned from vnodes before the plan is executed.
+//!
+//! A node that can't decode the pushed plan, e.g. it doesn't know a function used by the
+//! plan, scans vnodes without it and sets [`PLAN_NOT_PUSHED_HEADER`] in the response
+//! metadata, the query node then executes the plan on the raw rows itself.
+
+use std::any::Any;
+use std::fmt::{self, Debug, Display, Formatter};
+use std::sync::Arc;
+
+use arrow_schema::Schema;
+use bytes::Bytes;
+use datafusion::arrow::datatypes::SchemaRef;
+use datafusion::arrow::record_batch::RecordBatch;
+use datafusion::common::tree_node::{Transformed, TreeNode};
+use datafusion::error::{DataFusionError, Result as DFResult};
+use datafusion::execution::context::TaskContext;
+use datafusion::execution::runtime_env::{RuntimeConfig, RuntimeEnv};
+use datafusion::execution::FunctionRegistry;
+use datafusion::physical_expr::PhysicalSortExpr;
+use datafusion::physical_plan::stream::RecordBatchStreamAdapter;
+use datafusion::physical_plan::{
+    DisplayFormatType, ExecutionPlan, Partitioning, SendableRecordBatchStream, Statistics,
+};
+use datafusion::prelude::{SessionConfig, SessionContext};
+use datafusion_proto::bytes::{
+    physical_plan_from_bytes_with_extension_codec, physical_plan_to_bytes_with_extension_codec,
+};
+use datafusion_proto::physical_plan::PhysicalExtensionCodec;
+use futures::{Stream, TryStreamExt};
+use memory_pool::MemoryPoolRef;
+use parking_lot::Mutex;
+
+use crate::reader::SendableTskvRecordBatchStream;
+use crate::{TskvError, TskvResult};
+
+/// Set in the metadata of a query response if the pushed plan wasn't executed by the node.
+pub const PLAN_NOT_PUSHED_HEADER: &str = "x-cnosdb-plan-not-pushed";
+
+/// Placeholder of the record batches scanned from vnodes in a pushed plan.
+pub struct ScanInputExec {
+    schema: SchemaRef,
+    input: Arc<Mutex<Option<SendableRecordBatchStream>>>,
+}
+
+impl ScanInputExec {
+    pub fn new(schema: SchemaRef) -> Self {
+        Self {
+            schema,
+            input: Arc::new(Mutex::new(None)),
+        }
+    }
+}
+
+impl Debug for ScanInputExec {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ScanInputExec")
+            .field("schema", &self.schema)
+            .finish()
+    }
+}
+
+impl ExecutionPlan for ScanInputExec {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn schema(&self) -> SchemaRef {
+        self.schema.clone()
+    }
+
+    fn output_partitioning(&self) -> Partitioning {
+        Partitioning::UnknownPartitioning(1)
+    }
+
+    fn output_ordering(&self) -> Option<&[PhysicalSortExpr]> {
+        None
+    }
+
+    fn children(&self) -> Vec<Arc<dyn ExecutionPlan>> {
+        vec![]
+    }
+
+    fn with_new_children(
+        self: Arc<Self>,
+        _: Vec<Arc<dyn ExecutionPlan>>,
+    ) -> DFResult<Arc<dyn ExecutionPlan>> {
+        Ok(self)
+    }
+
+    fn execute(
+        &self,
+        _partition: usize,
+        _context: Arc<TaskContext>,
+    ) -> DFResult<SendableRecordBatchStream> {
+        self.input.lock().take().ok_or_else(|| {
+            DataFusionError::Internal(
+                "Input of the pushed plan is not bound or already consumed".to_string(),
+            )
+        })
+    }
+
+    fn fmt_as(&self, t: DisplayFormatType, f: &mut Formatter) -> fmt::Result {
+        match t {
+            DisplayFormatType::Default | DisplayFormatType::Verbose => {
+                write!(f, "ScanInputExec")
+            }
+        }
+    }
+
+    fn statistics(&self) -> Statistics {
+        Statistics::default()
+    }
+}
+
+/// Only [`ScanInputExec`] is encoded by the codec, as its schema.
+#[derive(Debug)]
+struct PushedPlanCodec;
+
+impl PhysicalExtensionCodec for PushedPlanCodec {
+    fn try_decode(
+        &self,
+        buf: &[u8],
+        _inputs: &[Arc<dyn ExecutionPlan>],
+        _registry: &dyn FunctionRegistry,
+    ) -> DFResult<Arc<dyn ExecutionPlan>> {
+        let schema: Schema =
+            bincode::deserialize(buf).map_err(|e| DataFusionError::External(Box::new(e)))?;
+        Ok(Arc::new(ScanInputExec::new(Arc::new(schema))))
+    }
+
+    fn try_encode(&self, node: Arc<dyn ExecutionPlan>, buf: &mut Vec<u8>) -> DFResult<()> {
+        match node.as_any().downcast_ref::<ScanInputExec>() {
+            Some(exec) => {
+                let data = bincode::serialize(exec.schema.as_ref())
+                    .map_err(|e| DataFusionError::External(Box::new(e)))?;
+                buf.extend(data);
+                Ok(())
+            }
+            None => Err(DataFusionError::NotImplemented(format!(
+                "Can not push down plan: {node:?}"
+            ))),
+        }
+    }
+}
+
+/// A plan executed on the record batches scanned from vnodes, it's encoded by
+/// datafusion-proto to be sent to the node where vnodes are located.
+#[derive(Debug, Clone)]
+pub struct PushedPlan {
+    plan: Arc<dyn ExecutionPlan>,
+    bytes: Bytes,
+}
+
+impl PushedPlan {
+    /// Returns an error if the plan can't be encoded, e.g. it contains a plan
+    /// or an expression that datafusion-proto doesn't support.
+    pub fn try_new(plan: Arc<dyn ExecutionPlan>) -> TskvResult<Self> {
+        let bytes = physical_plan_to_bytes_with_extension_codec(plan.clone(), &PushedPlanCodec)?;
+        Ok(Self { plan, bytes })
+    }
+
+    /// Functions used by the plan must be registered in the context.
+    pub fn decode(bytes: &[u8], ctx: &SessionContext) -> TskvResult<Self> {
+        let plan = physical_plan_from_bytes_with_extension_codec(bytes, ctx, &PushedPlanCodec)?;
+        Ok(Self {
+            plan,
+            bytes: Bytes::copy_from_slice(bytes),
+        })
+    }
+
+    pub fn schema(&self) -> SchemaRef {
+        self.plan.schema()
+    }
+
+    pub fn bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    /// Execute the plan on the scanned record batches, which are in `input_schema`.
+    ///
+    /// Memory used by the plan, e.g. the hash table of an aggregation, is reserved
+    /// from `memory_pool`, which should be the memory pool of the node.
+    pub fn execute<S, E>(
+        &self,
+        input: S,
+        input_schema: SchemaRef,
+        batch_size: usize,
+        memory_pool: MemoryPoolRef,
+    ) -> TskvResult<SendableTskvRecordBatchStream>
+    where
+        S: Stream<Item = Result<RecordBatch, E>> + Send + 'static,
+        E: std::error::Error + Send + Sync + 'static,
+    {
+        let input: SendableRecordBatchStream = Box::pin(RecordBatchStreamAdapter::new(
+            input_schema,
+            input.map_err(|e| DataFusionError::External(Box::new(e))),
+        ));
+        let input = Arc::new(Mutex::new(Some(input)));
+        let plan = self.plan.clone().transform_up(&|plan| {
+            if let Some(exec) = plan.as_any().downcast_ref::<ScanInputExec>() {
+                let exec = ScanInputExec {
+                    schema: exec.schema.clone(),
+                    input: input.clone(),
+                };
+                return Ok(Transformed::Yes(Arc::new(exec)));
+            }
+            Ok(Transformed::No(plan))
+        })?;
+
+        let runtime = RuntimeEnv::new(RuntimeConfig::new().with_memory_pool(memory_pool))?;
+        let ctx = SessionContext::with_config_rt(
+            SessionConfig::new().with_batch_size(batch_size),
+            Arc::new(runtime),
+        );
+        let stream = plan.execute(0, ctx.task_ctx())?;
+        Ok(Box::pin(stream.map_err(TskvError::from)))
+    }
+}
+
+impl Display for PushedPlan {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let mut plan = Some(self.plan.clone());
+        while let Some(p) = plan {
+            p.fmt_as(DisplayFormatType::Default, f)?;
+            plan = p.children().first().cloned();
+            if plan.is_some() {
+                write!(f, " -> ")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+
+    use datafusion::arrow::array::{Int64Array, StringArray};
+    use datafusion::arrow::datatypes::{DataType, Field, Schema};
+    use datafusion::arrow::record_batch::RecordBatch;
+    use datafusion::logical_expr::Operator;
+    use datafusion::physical_expr::expressions::{col, lit, BinaryExpr, Count};
+    use datafusion::physical_plan::aggregates::{AggregateExec, AggregateMode, PhysicalGroupBy};
+    use datafusion::physical_plan::filter::FilterExec;
+    use datafusion::physical_plan::limit::LocalLimitExec;
+    use datafusion::physical_plan::ExecutionPlan;
+    use datafusion::prelude::SessionContext;
+    use futures::TryStreamExt;
+    use memory_pool::GreedyMemoryPool;
+
+    use super::{PushedPlan, ScanInputExec};
+    use crate::reader::SendableTskvRecordBatchStream;
+
+    #[tokio::test]
+    async fn test_pushed_partial_aggregate() {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("tag", DataType::Utf8, true),
+            Field::new("value", DataType::Int64, true),
+        ]));
+        let scan: Arc<dyn ExecutionPlan> = Arc::new(ScanInputExec::new(schema.clone()));
+        let predicate = Arc::new(BinaryExpr::new(
+            col("value", &schema).unwrap(),
+            Operator::Gt,
+            lit(1_i64),
+        ));
+        let filter = Arc::new(FilterExec::try_new(predicate, scan).unwrap());
+        let aggregate = Arc::new(
+            AggregateExec::try_new(
+                AggregateMode::Partial,
+                PhysicalGroupBy::new_single(vec![(
+                    col("tag", &schema).unwrap(),
+                    "tag".to_string(),
+                )]),
+                vec![Arc::new(Count::new(
+                    col("value", &schema).unwrap(),
+                    "COUNT(value)",
+                    DataType::Int64,
+                ))],
+                vec![None],
+                vec![None],
+                filter,
+                schema.clone(),
+            )
+            .unwrap(),
+        );
+
+        let pushed_plan = PushedPlan::try_new(aggregate).unwrap();
+        let pushed_plan = PushedPlan::decode(pushed_plan.bytes(), &SessionContext::new()).unwrap();
+        assert_eq!(
+            pushed_plan.to_string(),
+            "AggregateExec: mode=Partial, gby=[tag@0 as tag], aggr=[COUNT(value)] \
+            -> FilterExec: value@1 > 1 -> ScanInputExec"
+        );
+
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![
+                Arc::new(StringArray::from(vec!["a", "a", "a"])),
+                Arc::new(Int64Array::from(vec![1, 2, 3])),
+            ],
+        )
+        .unwrap();
+        let input: SendableTskvRecordBatchStream = Box::pin(futures::stream::iter(vec![Ok(batch)]));
+        let batches = pushed_plan
+            .execute(
+                input,
+                schema.clone(),
+                1024,
+                Arc::new(GreedyMemoryPool::default()),
+            )
+            .unwrap()
+            .try_collect::<Vec<_>>()
+            .await
+            .unwrap();
+        assert_eq!(batches.len(), 1);
+        assert_eq!(batches[0].schema(), pushed_plan.schema());
+        let counts = batches[0]
+            .column(1)
+            .as_any()
+            .downcast_ref::<Int64Array>()
+            .unwrap();
+        assert_eq!(counts.value(0), 2);
+
+        // The aggregation reserves memory from the memory pool of the node.
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![
+                Arc::new(StringArray::from(vec!["a", "b", "c"])),
+                Arc::new(Int64Array::from(vec![2, 3, 4])),
+            ],
+        )
+        .unwrap();
+        let input: SendableTskvRecordBatchStream = Box::pin(futures::stream::iter(vec![Ok(batch)]));
+        let result = pushed_plan
+            .execute(input, schema, 1024, Arc::new(GreedyMemoryPool::new(1)))
+            .unwrap()
+            .try_collect::<Vec<_>>()
+            .await;
+        assert!(result.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_pushed_limit() {
+        let schema = Arc::new(Schema::new(vec![Field::new(
+            "value",
+            DataType::Int64,
+            true,
+        )]));
+        let scan: Arc<dyn ExecutionPlan> = Arc::new(ScanInputExec::new(schema.clone()));
+        let predicate = Arc::new(BinaryExpr::new(
+            col("value", &schema).unwrap(),
+            Operator::Gt,
+            lit(1_i64),
+        ));
+        let filter = Arc::new(FilterExec::try_new(predicate, scan).unwrap());
+        let limit = Arc::new(LocalLimitExec::new(filter, 2));
+
+        let pushed_plan = PushedPlan::try_new(limit).unwrap();
+        let pushed_plan = PushedPlan::decode(pushed_plan.bytes(), &SessionContext::new()).unwrap();
+        assert_eq!(
+            pushed_plan.to_string(),
+            "LocalLimitExec: fetch=2 -> FilterExec: value@0 > 1 -> ScanInputExec"
+        );
+
+        let batches = (0..3)
+            .map(|i| {
+                RecordBatch::try_new(
+                    schema.clone(),
+                    vec![Arc::new(Int64Array::from(vec![i, i + 1, i + 2]))],
+                )
+            })
+            .collect::<Vec<_>>();
+        let input = futures::stream::iter(batches);
+        let batches = pushed_plan
+            .execute(input, schema, 1024, Arc::new(GreedyMemoryPool::default()))
+            .unwrap()
+            .try_collect::<Vec<_>>()
+            .await
+            .unwrap();
+        let values = batches
+            .iter()
+            .flat_map(|b| {
+                b.column(0)
+                    .as_any()
+                    .downcast_ref::<Int64Array>()
+                    .unwrap()
+                    .values()
+                    .to_vec()
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(values, vec![2, 2]);
+    }
+}
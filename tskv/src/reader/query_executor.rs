@@ -1,6 +1,7 @@
 use std::sync::Arc;
 
 use datafusion::arrow::record_batch::RecordBatch;
+use memory_pool::MemoryPoolRef;
 use meta::model::MetaRef;
 use models::arrow::stream::{BoxStream, ParallelMergeStream};
 use models::meta_data::VnodeInfo;
@@ -36,9 +37,11 @@ impl QueryExecutor {
         }
     }
 
+    /// The pushed plan in the query option, if any, reserves memory from `memory_pool`.
     pub fn local_node_executor(
         &self,
         vnodes: Vec<VnodeInfo>,
+        memory_pool: MemoryPoolRef,
         span_context: Option<&SpanContext>,
     ) -> TskvResult<SendableTskvRecordBatchStream> {
         let mut streams: Vec<BoxStream<TskvResult<RecordBatch>>> = Vec::with_capacity(vnodes.len());
@@ -60,7 +63,15 @@ impl QueryExecutor {
 
         let parallel_merge_stream = ParallelMergeStream::new(Some(self.runtime.clone()), streams);
 
-        Ok(Box::pin(parallel_merge_stream))
+        match self.option.pushed_plan {
+            Some(ref plan) => plan.execute(
+                Box::pin(parallel_merge_stream),
+                self.option.df_schema.clone(),
+                self.option.batch_size,
+                memory_pool,
+            ),
+            None => Ok(Box::pin(parallel_merge_stream)),
+        }
     }
 
     pub fn local_node_tag_scan(
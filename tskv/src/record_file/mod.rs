@@ -84,6 +84,7 @@ pub enum RecordDataType {
     Tombstone = 4,
     Wal = 8,
     IndexLog = 16,
+    HintedHandoff = 32,
 }
 
 impl Display for RecordDataType {
@@ -93,6 +94,7 @@ impl Display for RecordDataType {
             RecordDataType::Tombstone => write!(f, "tombstone"),
             RecordDataType::Wal => write!(f, "WAL"),
             RecordDataType::IndexLog => write!(f, "indexlog"),
+            RecordDataType::HintedHandoff => write!(f, "hinted handoff"),
         }
     }
 }
@@ -1,9 +1,12 @@
 use std::borrow::Cow;
+use std::sync::Arc;
 
 use async_recursion::async_recursion;
+use config::common::TenantObjectLimiterConfig;
 use meta::error::{MetaError, TenantNotFoundSnafu};
 use meta::model::{MetaClientRef, MetaRef};
 use models::codec::Encoding;
+use models::meta_data::TenantSeriesCount;
 use models::schema::{
     ColumnType, DatabaseSchema, TableColumn, TableSchema, TskvTableSchema, TskvTableSchemaRef,
 };
@@ -227,6 +230,29 @@ impl DBschemas {
         Ok(())
     }
 
+    pub async fn object_limiter_config(&self) -> SchemaResult<Option<TenantObjectLimiterConfig>> {
+        let config = self
+            .tenant_meta()
+            .await?
+            .tenant()
+            .options()
+            .object_config()
+            .copied();
+
+        Ok(config)
+    }
+
+    /// Series of the tenant in the cluster, refreshed from meta once in a report interval.
+    pub async fn cluster_series_count(&self) -> SchemaResult<Arc<TenantSeriesCount>> {
+        let count = self
+            .tenant_meta()
+            .await?
+            .series_count(self.meta.report_time_interval())
+            .await?;
+
+        Ok(count)
+    }
+
     pub async fn db_schema(&self) -> SchemaResult<DatabaseSchema> {
         let db_schema = self
             .tenant_meta()
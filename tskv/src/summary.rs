@@ -20,10 +20,12 @@ use utils::BloomFilter;
 use crate::context::GlobalContext;
 use crate::error::{IOSnafu, RecordFileDecodeSnafu, RecordFileEncodeSnafu, TskvError, TskvResult};
 use crate::file_system::async_filesystem::LocalFileSystem;
+use crate::file_system::FileSystem;
 use crate::kv_option::{Options, StorageOptions, DELTA_PATH, TSM_PATH};
 use crate::memcache::MemCache;
 use crate::record_file::{Reader, RecordDataType, RecordDataVersion, Writer};
 use crate::tseries_family::{ColumnFile, LevelInfo, TseriesFamily, Version};
+use crate::tsm::TOMBSTONE_FILE_SUFFIX;
 use crate::version_set::VersionSet;
 use crate::{byte_utils, file_utils, ColumnFileId, LevelId, TseriesFamilyId};
 
@@ -118,8 +120,24 @@ impl CompactMeta {
         trace::info!("rename file from {:?} to {:?}", &old_name, &new_name);
         file_utils::rename(&old_name, &new_name).await?;
 
+        let old_tombstone = old_name.with_extension(TOMBSTONE_FILE_SUFFIX);
+        if LocalFileSystem::try_exists(&old_tombstone) {
+            let new_tombstone = new_name.with_extension(TOMBSTONE_FILE_SUFFIX);
+            trace::info!(
+                "rename file from {:?} to {:?}",
+                &old_tombstone,
+                &new_tombstone
+            );
+            file_utils::rename(&old_tombstone, &new_tombstone).await?;
+        }
+
         Ok(new_name)
     }
+
+    /// Path of the tombstone file relative to the vnode directory.
+    pub fn relative_tombstone_path(&self) -> PathBuf {
+        self.relative_path().with_extension(TOMBSTONE_FILE_SUFFIX)
+    }
 }
 
 pub struct CompactMetaBuilder {
@@ -23,6 +23,7 @@ use crate::error::{CommonSnafu, IndexErrSnafu, TskvResult};
 use crate::file_system::async_filesystem::LocalFileSystem;
 use crate::file_system::FileSystem;
 use crate::file_utils::{make_delta_file, make_tsm_file};
+use crate::index::rebuild;
 use crate::index::ts_index::TSIndex;
 use crate::kv_option::{CacheOptions, StorageOptions};
 use crate::memcache::{MemCache, MemCacheStatistics, RowGroup};
@@ -677,6 +678,15 @@ impl SuperVersion {
         }
     }
 
+    /// The max seq_no of writes in the caches and column files, increases on every write.
+    pub fn max_seq_no(&self) -> u64 {
+        let mut seq_no = self.version.last_seq();
+        for cache in self.caches.immut_cache.iter() {
+            seq_no = seq_no.max(cache.read().seq_no());
+        }
+        seq_no.max(self.caches.mut_cache.read().seq_no())
+    }
+
     pub fn column_files(&self, time_ranges: &TimeRanges) -> Vec<Arc<ColumnFile>> {
         let mut files = Vec::new();
 
@@ -1112,15 +1122,10 @@ impl TseriesFamily {
         Ok(file_metas)
     }
 
-    pub async fn rebuild_index(&self) -> TskvResult<Arc<tokio::sync::RwLock<TSIndex>>> {
-        let path = self
-            .storage_opt
-            .index_dir(self.tenant_database.as_str(), self.tf_id);
-        let _ = std::fs::remove_dir_all(path.clone());
-
-        let index = TSIndex::new(path).await.context(IndexErrSnafu)?;
-        let index_clone = index.clone();
-        let mut index_w = index_clone.write().await;
+    /// Returns all series in caches and column files of the vnode.
+    /// Returns the series in caches, and the version whose column files have the other series.
+    pub fn series_for_rebuild(&self) -> (BTreeMap<SeriesId, SeriesKey>, Arc<Version>) {
+        let mut series = BTreeMap::new();
 
         // cache index
         let mut series_data = self.mut_cache.read().read_series_data();
@@ -1128,29 +1133,24 @@ impl TseriesFamily {
             series_data.extend(imut_cache.read().read_series_data());
         }
         for (sid, data) in series_data {
-            let series_key = data.read().series_key.clone();
-            index_w
-                .add_series_for_rebuild(sid, &series_key)
-                .await
-                .context(IndexErrSnafu)?;
+            series
+                .entry(sid)
+                .or_insert_with(|| data.read().series_key.clone());
         }
 
-        // tsm index
-        for level in self.version().levels_info.iter() {
-            for file in level.files.iter() {
-                let reader = self.version().get_tsm_reader(file.file_path()).await?;
-                for chunk in reader.chunk().values() {
-                    index_w
-                        .add_series_for_rebuild(chunk.series_id(), chunk.series_key())
-                        .await
-                        .context(IndexErrSnafu)?;
-                }
-            }
-        }
+        (series, self.version())
+    }
+
+    pub async fn rebuild_index(&self) -> TskvResult<Arc<tokio::sync::RwLock<TSIndex>>> {
+        let path = self
+            .storage_opt
+            .index_dir(self.tenant_database.as_str(), self.tf_id);
 
-        index_w.flush().await.context(IndexErrSnafu)?;
+        let (mut series, version) = self.series_for_rebuild();
+        rebuild::series_in_version(&version, &mut series).await?;
+        rebuild::build_index(&path, &series, &[], 0).await?;
 
-        Ok(index)
+        TSIndex::new(path).await.context(IndexErrSnafu)
     }
 
     pub fn tf_id(&self) -> TseriesFamilyId {
@@ -0,0 +1,590 @@
+//! Offline inspection of tsm files, used by the `tskv` command line tool.
+
+use std::collections::{BTreeMap, HashMap};
+use std::fs::File;
+use std::path::Path;
+use std::str::FromStr;
+use std::sync::Arc;
+
+use arrow::array::{new_null_array, Array, ArrayRef, AsArray, StringArray};
+use arrow::csv::Writer as CsvWriter;
+use arrow::datatypes::{DataType, Field, Schema, SchemaRef};
+use arrow::ipc::writer::FileWriter;
+use arrow::record_batch::RecordBatch;
+use models::codec::Encoding;
+use models::schema::{PhysicalCType, TableColumn};
+use models::{ColumnId, PhysicalDType, SeriesKey};
+use snafu::ResultExt;
+
+use crate::error::{ArrowSnafu, CommonSnafu, IOSnafu};
+use crate::reader::page_to_arrow_array;
+use crate::tsm::codec::get_encoding;
+use crate::tsm::column_group::ColumnGroup;
+use crate::tsm::page::Page;
+use crate::tsm::reader::TsmReader;
+use crate::{TskvError, TskvResult};
+
+/// Output format of decoded tsm pages.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TsmDumpFormat {
+    Csv,
+    Arrow,
+}
+
+impl TsmDumpFormat {
+    pub fn file_extension(&self) -> &'static str {
+        match self {
+            TsmDumpFormat::Csv => "csv",
+            TsmDumpFormat::Arrow => "arrow",
+        }
+    }
+}
+
+impl FromStr for TsmDumpFormat {
+    type Err = TskvError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "csv" => Ok(TsmDumpFormat::Csv),
+            "arrow" => Ok(TsmDumpFormat::Arrow),
+            _ => Err(CommonSnafu {
+                reason: format!("unsupported dump format '{s}', expected csv or arrow"),
+            }
+            .build()),
+        }
+    }
+}
+
+/// Prints footer, chunk groups, chunks and column groups of a tsm file,
+/// pages and tombstones are also printed if `show_pages` or `show_tombstone` is true.
+pub async fn print_tsm_statistics(
+    path: impl AsRef<Path>,
+    show_pages: bool,
+    show_tombstone: bool,
+) -> TskvResult<()> {
+    let reader = TsmReader::open(&path).await?;
+    let footer = reader.footer();
+    println!("============================================================");
+    println!(
+        "File id: {}, version: {:?}, time range: {}",
+        reader.file_id(),
+        footer.version(),
+        footer.time_range()
+    );
+    println!(
+        "Chunk group meta offset: {}, size: {}",
+        footer.table().chunk_group_offset(),
+        footer.table().chunk_group_size()
+    );
+    println!(
+        "Chunk offset: {}, size: {}",
+        footer.series().chunk_offset(),
+        footer.series().chunk_size()
+    );
+    println!("============================================================");
+    for (table, spec) in reader.chunk_group_meta().tables() {
+        println!(
+            "Table: {}, chunk group offset: {}, size: {}, series: {}, time range: {}",
+            table,
+            spec.chunk_group_offset(),
+            spec.chunk_group_size(),
+            spec.count(),
+            spec.time_range()
+        );
+    }
+    for (series_id, chunk) in reader.chunk() {
+        println!("------------------------------------------------------------");
+        println!(
+            "Series id: {}, key: {}, time range: {}",
+            series_id,
+            chunk.series_key(),
+            chunk.time_range()
+        );
+        for (column_group_id, column_group) in chunk.column_group() {
+            println!(
+                "  Column group: {}, offset: {}, size: {}, rows: {}, time range: {}",
+                column_group_id,
+                column_group.pages_offset(),
+                column_group.size(),
+                column_group.row_len(),
+                column_group.time_range()
+            );
+            if !show_pages {
+                continue;
+            }
+            for page in column_group.pages() {
+                let meta = page.meta();
+                println!(
+                    "    Page column: {} ({}), type: {}, values: {}, offset: {}, size: {}",
+                    meta.column.name,
+                    meta.column.id,
+                    meta.column.column_type,
+                    meta.num_values,
+                    page.offset(),
+                    page.size()
+                );
+            }
+        }
+    }
+
+    if show_tombstone {
+        println!("============================================================");
+        let tombstones = reader.tombstone().tombstones();
+        if tombstones.is_empty() {
+            println!("Tombstone: None.");
+        }
+        for tombstone in tombstones {
+            println!(
+                "Tombstone series id: {}, column id: {}, time range: {}",
+                tombstone.series_id, tombstone.column_id, tombstone.time_range
+            );
+        }
+    }
+    println!("============================================================");
+
+    Ok(())
+}
+
+/// Reads every page of a tsm file and checks it's crc,
+/// returns the number of pages that failed the check.
+pub async fn verify_tsm_crc(path: impl AsRef<Path>) -> TskvResult<usize> {
+    let reader = TsmReader::open(&path).await?;
+    let (mut page_count, mut corrupted_count) = (0_usize, 0_usize);
+    for (series_id, chunk) in reader.chunk() {
+        for (column_group_id, column_group) in chunk.column_group() {
+            for page in column_group.pages() {
+                page_count += 1;
+                match reader.read_page(page).await {
+                    Ok(_) => {}
+                    Err(TskvError::TSMPageFileHashCheckFailed {
+                        crc,
+                        crc_calculated,
+                        ..
+                    }) => {
+                        corrupted_count += 1;
+                        println!(
+                            "Crc check failed, series id: {}, column group: {}, column: {}, offset: {}, crc: {}, calculated: {}",
+                            series_id,
+                            column_group_id,
+                            page.meta().column.name,
+                            page.offset(),
+                            crc,
+                            crc_calculated
+                        );
+                    }
+                    Err(e) => return Err(e),
+                }
+            }
+        }
+    }
+    println!("Checked pages: {page_count}, corrupted pages: {corrupted_count}");
+
+    Ok(corrupted_count)
+}
+
+#[derive(Default)]
+struct ColumnEncodingStatistics {
+    column_type: String,
+    pages: usize,
+    values: u64,
+    encoded_size: u64,
+    plain_size: u64,
+}
+
+/// Prints the encoding and compression ratio of each column in a tsm file.
+///
+/// The plain size is the size of non-null values without encoding,
+/// strings are counted by their length and booleans by one byte.
+pub async fn print_tsm_encoding(path: impl AsRef<Path>) -> TskvResult<()> {
+    let reader = TsmReader::open(&path).await?;
+    let statistics = column_encoding_statistics(&reader).await?;
+
+    println!("============================================================");
+    for ((table, column, encoding), stat) in statistics {
+        let ratio = if stat.encoded_size == 0 {
+            0_f64
+        } else {
+            stat.plain_size as f64 / stat.encoded_size as f64
+        };
+        println!(
+            "Table: {}, column: {}, type: {}, encoding: {}, pages: {}, values: {}, encoded size: {} B, plain size: {} B, ratio: {:.2}",
+            table,
+            column,
+            stat.column_type,
+            encoding.as_str(),
+            stat.pages,
+            stat.values,
+            stat.encoded_size,
+            stat.plain_size,
+            ratio
+        );
+    }
+    println!("============================================================");
+
+    Ok(())
+}
+
+/// (table, column name, encoding) -> statistics
+type ColumnEncodingStatisticsMap = BTreeMap<(String, String, Encoding), ColumnEncodingStatistics>;
+
+async fn column_encoding_statistics(reader: &TsmReader) -> TskvResult<ColumnEncodingStatisticsMap> {
+    let mut statistics = ColumnEncodingStatisticsMap::new();
+    for chunk in reader.chunk().values() {
+        for column_group in chunk.column_group().values() {
+            for page_spec in column_group.pages() {
+                let page = reader.read_page(page_spec).await?;
+                let column = &page.meta().column;
+                let encoding = get_encoding(page.data_buffer());
+                let stat = statistics
+                    .entry((
+                        chunk.table_name().to_string(),
+                        column.name.clone(),
+                        encoding,
+                    ))
+                    .or_default();
+                stat.column_type = column.column_type.to_string();
+                stat.pages += 1;
+                stat.values += page.meta().num_values as u64;
+                stat.encoded_size += page.data_buffer().len() as u64;
+                stat.plain_size += page_plain_size(&page)?;
+            }
+        }
+    }
+
+    Ok(statistics)
+}
+
+fn page_plain_size(page: &Page) -> TskvResult<u64> {
+    let array = page_to_arrow_array(page)?;
+    let values = (array.len() - array.null_count()) as u64;
+    let size = match array.data_type() {
+        DataType::Utf8 => array.as_string::<i32>().values().len() as u64,
+        DataType::Boolean => values,
+        _ => values * 8,
+    };
+    Ok(size)
+}
+
+/// Decodes all pages of a tsm file and writes them into `<output_dir>/<table>.<format>`,
+/// tag values are taken from series keys. Tombstones are not applied.
+pub async fn dump_tsm(
+    path: impl AsRef<Path>,
+    format: TsmDumpFormat,
+    output_dir: impl AsRef<Path>,
+) -> TskvResult<()> {
+    let reader = TsmReader::open(&path).await?;
+    let output_dir = output_dir.as_ref();
+    std::fs::create_dir_all(output_dir).context(IOSnafu)?;
+
+    for (table, chunk_group) in reader.chunk_group() {
+        let table_schema = reader.table_schema(table).ok_or_else(|| {
+            CommonSnafu {
+                reason: format!("table schema for table : {} not found", table),
+            }
+            .build()
+        })?;
+        let columns = table_schema.columns().to_vec();
+        let schema = dump_schema(&columns);
+
+        let output_path = output_dir.join(format!("{}.{}", table, format.file_extension()));
+        let file = File::create(&output_path).context(IOSnafu)?;
+        let mut writer = match format {
+            TsmDumpFormat::Csv => DumpWriter::Csv(CsvWriter::new(file)),
+            TsmDumpFormat::Arrow => {
+                DumpWriter::Arrow(FileWriter::try_new(file, &schema).context(ArrowSnafu)?)
+            }
+        };
+
+        let mut rows = 0_usize;
+        for chunk_spec in chunk_group.chunks() {
+            let chunk = match reader.chunk().get(&chunk_spec.series_id()) {
+                Some(chunk) => chunk,
+                None => continue,
+            };
+            for column_group in chunk.column_group().values() {
+                let batch = column_group_to_record_batch(
+                    &reader,
+                    column_group,
+                    chunk.series_key(),
+                    &columns,
+                    schema.clone(),
+                )
+                .await?;
+                rows += batch.num_rows();
+                writer.write(&batch)?;
+            }
+        }
+        writer.finish()?;
+        println!(
+            "Table: {}, rows: {}, output: {}",
+            table,
+            rows,
+            output_path.display()
+        );
+    }
+
+    Ok(())
+}
+
+fn dump_schema(columns: &[TableColumn]) -> SchemaRef {
+    let fields = columns
+        .iter()
+        .map(|c| {
+            Field::new(
+                &c.name,
+                physical_data_type(&c.column_type.to_physical_type()),
+                true,
+            )
+        })
+        .collect::<Vec<_>>();
+    Arc::new(Schema::new(fields))
+}
+
+fn physical_data_type(column_type: &PhysicalCType) -> DataType {
+    match column_type {
+        PhysicalCType::Tag | PhysicalCType::Field(PhysicalDType::String) => DataType::Utf8,
+        PhysicalCType::Time(_) | PhysicalCType::Field(PhysicalDType::Integer) => DataType::Int64,
+        PhysicalCType::Field(PhysicalDType::Float) => DataType::Float64,
+        PhysicalCType::Field(PhysicalDType::Unsigned) => DataType::UInt64,
+        PhysicalCType::Field(PhysicalDType::Boolean) => DataType::Boolean,
+        PhysicalCType::Field(PhysicalDType::Unknown) => DataType::Null,
+    }
+}
+
+async fn column_group_to_record_batch(
+    reader: &TsmReader,
+    column_group: &ColumnGroup,
+    series_key: &SeriesKey,
+    columns: &[TableColumn],
+    schema: SchemaRef,
+) -> TskvResult<RecordBatch> {
+    let num_rows = column_group.row_len();
+    let mut arrays: HashMap<ColumnId, ArrayRef> = HashMap::new();
+    for page_spec in column_group.pages() {
+        let page = reader.read_page(page_spec).await?;
+        arrays.insert(page.meta().column.id, page_to_arrow_array(&page)?);
+    }
+
+    let mut columns_arrays = Vec::with_capacity(columns.len());
+    for (column, field) in columns.iter().zip(schema.fields().iter()) {
+        let array = if column.column_type.is_tag() {
+            let value = series_key
+                .tag_string_val(&column.id.to_string())
+                .map_err(|e| {
+                    CommonSnafu {
+                        reason: e.to_string(),
+                    }
+                    .build()
+                })?;
+            Arc::new(StringArray::from(vec![value; num_rows])) as ArrayRef
+        } else {
+            match arrays.remove(&column.id) {
+                Some(array) => array,
+                None => new_null_array(field.data_type(), num_rows),
+            }
+        };
+        columns_arrays.push(array);
+    }
+
+    RecordBatch::try_new(schema, columns_arrays).context(ArrowSnafu)
+}
+
+enum DumpWriter {
+    Csv(CsvWriter<File>),
+    Arrow(FileWriter<File>),
+}
+
+impl DumpWriter {
+    fn write(&mut self, batch: &RecordBatch) -> TskvResult<()> {
+        match self {
+            DumpWriter::Csv(w) => w.write(batch).context(ArrowSnafu),
+            DumpWriter::Arrow(w) => w.write(batch).context(ArrowSnafu),
+        }
+    }
+
+    fn finish(self) -> TskvResult<()> {
+        match self {
+            DumpWriter::Csv(_) => Ok(()),
+            DumpWriter::Arrow(mut w) => w.finish().context(ArrowSnafu),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::{Path, PathBuf};
+    use std::str::FromStr;
+    use std::sync::Arc;
+
+    use arrow::datatypes::TimeUnit;
+    use models::codec::Encoding;
+    use models::field_value::FieldVal;
+    use models::schema::{ColumnType, TableColumn, TskvTableSchema};
+    use models::{SeriesKey, Tag, ValueType};
+
+    use super::{column_encoding_statistics, dump_tsm, verify_tsm_crc, TsmDumpFormat};
+    use crate::tsm::data_block::{DataBlock, MutableColumn};
+    use crate::tsm::reader::TsmReader;
+    use crate::tsm::writer::TsmWriter;
+    use crate::TskvError;
+
+    fn test_schema() -> Arc<TskvTableSchema> {
+        Arc::new(TskvTableSchema::new(
+            "cnosdb".to_string(),
+            "public".to_string(),
+            "test0".to_string(),
+            vec![
+                TableColumn::new(
+                    0,
+                    "time".to_string(),
+                    ColumnType::Time(TimeUnit::Nanosecond),
+                    Encoding::default(),
+                ),
+                TableColumn::new(1, "host".to_string(), ColumnType::Tag, Encoding::default()),
+                TableColumn::new(
+                    2,
+                    "f1".to_string(),
+                    ColumnType::Field(ValueType::Integer),
+                    Encoding::default(),
+                ),
+            ],
+        ))
+    }
+
+    fn i64_column(column: TableColumn, data: Vec<i64>) -> MutableColumn {
+        let mut col = MutableColumn::empty(column).unwrap();
+        for datum in data {
+            col.push(Some(FieldVal::Integer(datum))).unwrap()
+        }
+        col
+    }
+
+    /// Writes series `host=a` of table `test0` with rows (1, 10), (2, 20), (3, 30).
+    async fn write_test_tsm(dir: &Path) -> PathBuf {
+        let schema = test_schema();
+        let data = DataBlock::new(
+            schema.clone(),
+            i64_column(schema.column("time").unwrap().clone(), vec![1, 2, 3]),
+            vec![i64_column(
+                schema.column("f1").unwrap().clone(),
+                vec![10, 20, 30],
+            )],
+        );
+        let series_key = SeriesKey {
+            tags: vec![Tag::new_with_column_id(1, b"a".to_vec())],
+            table: "test0".to_string(),
+        };
+
+        let mut tsm_writer = TsmWriter::open(&dir, 1, 0, false).await.unwrap();
+        tsm_writer
+            .write_pages(
+                schema,
+                1,
+                series_key,
+                data.block_to_page().unwrap(),
+                data.time_range().unwrap(),
+            )
+            .await
+            .unwrap();
+        tsm_writer.finish().await.unwrap();
+        tsm_writer.path().to_path_buf()
+    }
+
+    /// Flips the last byte of the page of `column`, so the crc of the page mismatches.
+    async fn corrupt_page(path: &Path, column: &str) {
+        let reader = TsmReader::open(path).await.unwrap();
+        let mut offset = None;
+        for chunk in reader.chunk().values() {
+            for column_group in chunk.column_group().values() {
+                for page in column_group.pages() {
+                    if page.meta().column.name == column {
+                        offset = Some((page.offset() + page.size() - 1) as usize);
+                    }
+                }
+            }
+        }
+        let offset = offset.unwrap();
+        drop(reader);
+
+        let mut bytes = std::fs::read(path).unwrap();
+        bytes[offset] = !bytes[offset];
+        std::fs::write(path, bytes).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_verify_tsm_crc() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = write_test_tsm(dir.path()).await;
+        assert_eq!(verify_tsm_crc(&path).await.unwrap(), 0);
+
+        corrupt_page(&path, "f1").await;
+        assert_eq!(verify_tsm_crc(&path).await.unwrap(), 1);
+
+        // Dumping a corrupted file fails with the crc error.
+        let output_dir = dir.path().join("dump");
+        match dump_tsm(&path, TsmDumpFormat::Csv, &output_dir).await {
+            Err(TskvError::TSMPageFileHashCheckFailed { .. }) => {}
+            other => panic!("expected crc check failure, got {other:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_dump_tsm() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = write_test_tsm(dir.path()).await;
+
+        let output_dir = dir.path().join("dump");
+        dump_tsm(&path, TsmDumpFormat::Csv, &output_dir)
+            .await
+            .unwrap();
+        let csv = std::fs::read_to_string(output_dir.join("test0.csv")).unwrap();
+        assert_eq!(csv, "time,host,f1\n1,a,10\n2,a,20\n3,a,30\n");
+
+        dump_tsm(&path, TsmDumpFormat::Arrow, &output_dir)
+            .await
+            .unwrap();
+        let file = std::fs::File::open(output_dir.join("test0.arrow")).unwrap();
+        let reader = arrow::ipc::reader::FileReader::try_new(file, None).unwrap();
+        let batches = reader.collect::<Result<Vec<_>, _>>().unwrap();
+        assert_eq!(batches.len(), 1);
+        assert_eq!(batches[0].num_rows(), 3);
+        assert_eq!(batches[0].schema().field(1).name(), "host");
+    }
+
+    #[tokio::test]
+    async fn test_column_encoding_statistics() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = write_test_tsm(dir.path()).await;
+        super::print_tsm_encoding(&path).await.unwrap();
+
+        let reader = TsmReader::open(&path).await.unwrap();
+        let statistics = column_encoding_statistics(&reader).await.unwrap();
+        assert_eq!(statistics.len(), 2);
+        for ((table, column, _), stat) in statistics.iter() {
+            assert_eq!(table, "test0");
+            assert!(column == "time" || column == "f1");
+            assert_eq!(
+                stat.column_type,
+                if column == "time" {
+                    "TimestampNanosecond"
+                } else {
+                    "I64"
+                }
+            );
+            assert_eq!(stat.pages, 1);
+            assert_eq!(stat.values, 3);
+            assert_eq!(stat.plain_size, 24);
+            assert!(stat.encoded_size > 0);
+        }
+    }
+
+    #[test]
+    fn test_dump_format_from_str() {
+        assert_eq!(TsmDumpFormat::from_str("csv").unwrap(), TsmDumpFormat::Csv);
+        assert_eq!(
+            TsmDumpFormat::from_str("ARROW").unwrap(),
+            TsmDumpFormat::Arrow
+        );
+        assert!(TsmDumpFormat::from_str("parquet").is_err());
+    }
+}
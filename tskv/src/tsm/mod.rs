@@ -4,6 +4,7 @@ pub mod codec;
 pub mod column_group;
 pub mod data_block;
 pub mod footer;
+pub mod inspect;
 pub(crate) mod page;
 pub mod reader;
 pub mod statistics;
@@ -14,6 +15,7 @@ pub mod writer;
 // MAX_BLOCK_VALUES is the maximum number of values a Tsm block can store.
 use std::collections::BTreeMap;
 
+pub use inspect::print_tsm_statistics;
 use models::{SeriesId, SeriesKey};
 pub use tombstone::{Tombstone, TsmTombstone, TOMBSTONE_FILE_SUFFIX};
 
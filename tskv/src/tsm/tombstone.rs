@@ -131,6 +131,23 @@ impl TsmTombstone {
         self.tombstones.is_empty()
     }
 
+    /// Returns all tombstones, ordered by series id and column id.
+    pub fn tombstones(&self) -> Vec<Tombstone> {
+        let mut tombstones = self
+            .tombstones
+            .iter()
+            .flat_map(|((series_id, column_id), time_ranges)| {
+                time_ranges.iter().map(|time_range| Tombstone {
+                    series_id: *series_id,
+                    column_id: *column_id,
+                    time_range: *time_range,
+                })
+            })
+            .collect::<Vec<_>>();
+        tombstones.sort_by_key(|t| (t.series_id, t.column_id, t.time_range.min_ts));
+        tombstones
+    }
+
     pub async fn add_range(
         &mut self,
         columns: &[(SeriesId, ColumnId)],
@@ -17,8 +17,9 @@ use trace::{debug, error, info, Span, SpanContext};
 use crate::compaction::job::FlushJob;
 use crate::compaction::FlushReq;
 use crate::database::Database;
-use crate::error::{IndexErrSnafu, InvalidParamSnafu, InvalidPointTableSnafu, TskvResult};
+use crate::error::{IOSnafu, IndexErrSnafu, InvalidParamSnafu, InvalidPointTableSnafu, TskvResult};
 use crate::index::ts_index::TSIndex;
+use crate::kv_option::INDEX_PATH;
 use crate::schema::error::{FieldNotFoundSnafu, TableNotFoundSnafu};
 use crate::tseries_family::TseriesFamily;
 use crate::{TsKvContext, VnodeSnapshot};
@@ -181,7 +182,16 @@ impl VnodeStorage {
             .add_tsfamily(version_edit, shapshot_dir, self.ctx.clone())
             .await?;
 
-        let ts_index = db_wlock.rebuild_tsfamily_index(ts_family.clone()).await?;
+        // Snapshots of backups contain the index, otherwise rebuild it from column files.
+        let index_dir = shapshot_dir.join(INDEX_PATH);
+        let ts_index = if index_dir.exists() {
+            std::fs::create_dir_all(&vnode_dir).context(IOSnafu)?;
+            std::fs::rename(&index_dir, storage_opt.index_dir(&owner, vnode_id))
+                .context(IOSnafu)?;
+            db_wlock.get_ts_index_or_add(vnode_id).await?
+        } else {
+            db_wlock.rebuild_tsfamily_index(ts_family.clone()).await?
+        };
 
         self.ts_index = ts_index;
         self.ts_family = ts_family;
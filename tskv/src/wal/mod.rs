@@ -48,14 +48,13 @@ use models::codec::Encoding;
 use models::meta_data::VnodeId;
 use snafu::{IntoError, OptionExt, ResultExt};
 
-use self::reader::WalReader;
 use self::writer::WalWriter;
 use crate::error::{CommonSnafu, DecodeSnafu, EncodeSnafu};
 use crate::file_system::async_filesystem::LocalFileSystem;
 use crate::file_system::FileSystem;
 use crate::kv_option::WalOptions;
 use crate::tsm::codec::{get_str_codec, StringCodec};
-pub use crate::wal::reader::print_wal_statistics;
+pub use crate::wal::reader::{print_wal_statistics, WalReader};
 use crate::{error, file_utils, TskvResult};
 
 /// 9 = type(1) + sequence(8)
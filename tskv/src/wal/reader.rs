@@ -1,6 +1,8 @@
 use std::path::{Path, PathBuf};
 
 use openraft::EntryPayload;
+use protos::kv_service::{raft_write_command, RaftWriteCommand};
+use protos::models_helper::parse_prost_bytes;
 
 use super::{wal_store, WalType, WAL_FOOTER_MAGIC_NUMBER, WAL_HEADER_LEN};
 use crate::byte_utils::{decode_be_u32, decode_be_u64};
@@ -191,8 +193,9 @@ pub async fn print_wal_statistics(path: impl AsRef<Path>) {
                         EntryPayload::Blank => {
                             println!("Raft log: empty");
                         }
-                        EntryPayload::Normal(_data) => {
-                            println!("Raft log: normal");
+                        EntryPayload::Normal(data) => {
+                            println!("Raft log: normal, index: {}", entry.log_id.index);
+                            print_raft_write_command(&data);
                         }
                         EntryPayload::Membership(_data) => {
                             println!("Raft log: membership");
@@ -224,3 +227,57 @@ pub async fn print_wal_statistics(path: impl AsRef<Path>) {
         }
     }
 }
+
+fn print_raft_write_command(data: &[u8]) {
+    let request = match parse_prost_bytes::<RaftWriteCommand>(data) {
+        Ok(r) => r,
+        Err(e) => {
+            println!("  Invalid write command: {}", e);
+            return;
+        }
+    };
+    println!(
+        "  Tenant: {}, database: {}, replica: {}",
+        request.tenant, request.db_name, request.replica_id
+    );
+    match request.command {
+        Some(raft_write_command::Command::WriteData(cmd)) => {
+            println!("  Write data, precision: {}", cmd.precision);
+            let points = match flatbuffers::root::<protos::models::Points>(&cmd.data) {
+                Ok(p) => p,
+                Err(e) => {
+                    println!("  Invalid points: {}", e);
+                    return;
+                }
+            };
+            for table in points.tables().into_iter().flatten() {
+                println!(
+                    "    Table: {}, rows: {}",
+                    table.tab().unwrap_or_default(),
+                    table.num_rows()
+                );
+            }
+        }
+        Some(raft_write_command::Command::DropTable(cmd)) => {
+            println!("  Drop table: {}.{}", cmd.db, cmd.table);
+        }
+        Some(raft_write_command::Command::DropColumn(cmd)) => {
+            println!("  Drop column: {}.{}.{}", cmd.db, cmd.table, cmd.column);
+        }
+        Some(raft_write_command::Command::DeleteFromTable(cmd)) => {
+            println!(
+                "  Delete from table: {}.{}, vnode: {}",
+                cmd.database, cmd.table, cmd.vnode_id
+            );
+        }
+        Some(raft_write_command::Command::UpdateTags(cmd)) => {
+            println!(
+                "  Update tags: {}, series: {}, dry run: {}",
+                cmd.db,
+                cmd.matched_series.len(),
+                cmd.dry_run
+            );
+        }
+        None => println!("  Empty write command"),
+    }
+}